@@ -0,0 +1,269 @@
+//! Module with the [`AccessLog`] middleware.
+//!
+//! [`AccessLog`] records the method, path, version, response status, number of
+//! bytes in the response body, duration and peer address of each request. The
+//! output depends on the request type passed to the middleware:
+//!  * `(SocketAddr, Request<B>)`: writes a line in the [Common Log Format]
+//!    using the [`log`] crate (at the info level).
+//!  * `(&mut T, SocketAddr, Request<B>)`, where `T` implements [`Trace`], e.g.
+//!    [`actor::Context`]: adds a trace event using [`Trace::finish_trace`], see
+//!    the [`heph_rt::trace`] module.
+//!
+//! [Common Log Format]: https://en.wikipedia.org/wiki/Common_Log_Format
+//! [`log`]: log
+//! [`actor::Context`]: heph::actor::Context
+//!
+//! # Examples
+//!
+//! ```
+//! # #![allow(dead_code)]
+//! use std::net::SocketAddr;
+//!
+//! use heph_http::access_log::AccessLog;
+//! use heph_http::body::OneshotBody;
+//! use heph_http::handler::Handler;
+//! use heph_http::{server, Request, Response};
+//!
+//! async fn index<B>(_: Request<B>) -> Response<OneshotBody<&'static str>> {
+//!     Response::ok().with_body(OneshotBody::new("Hello world"))
+//! }
+//!
+//! async fn handle(peer_addr: SocketAddr, request: Request<server::Body<'_>>) -> Response<OneshotBody<&'static str>> {
+//!     // NOTE: in practice the middleware should be created once and reused.
+//!     let handler = AccessLog::new(index);
+//!     // Logs something like:
+//!     // 127.0.0.1 - - [10/Oct/2000:13:55:36 +0000] "GET / HTTP/1.1" 200 11 153
+//!     handler.handle((peer_addr, request)).await
+//! }
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant, SystemTime};
+
+use heph_rt::trace::{AttributeValue, Trace};
+use log::info;
+
+use crate::body::{Body, BodyLength};
+use crate::handler::{Handler, Middleware};
+use crate::{Method, Request, Response, Version};
+
+/// [`Middleware`] that records an access log entry for each request.
+///
+/// See the [module documentation] for the supported output and an example.
+///
+/// [module documentation]: crate::access_log
+#[derive(Debug)]
+pub struct AccessLog<H> {
+    handler: H,
+}
+
+impl<H> AccessLog<H> {
+    /// Create new access log middleware wrapping `handler`.
+    ///
+    /// Same as [`Middleware::wrap`], but doesn't require the request type to
+    /// be known.
+    pub const fn new(handler: H) -> AccessLog<H> {
+        AccessLog { handler }
+    }
+}
+
+/// Writes a line in the Common Log Format, with the duration of the request
+/// (in microseconds) appended.
+impl<H, B, RB> Handler<(SocketAddr, Request<B>)> for AccessLog<H>
+where
+    H: Handler<(Request<B>,), Response = Response<RB>>,
+    RB: Body,
+{
+    type Response = Response<RB>;
+    type Future = impl Future<Output = Self::Response>;
+
+    fn handle(&self, (peer_addr, request): (SocketAddr, Request<B>)) -> Self::Future {
+        let entry = Entry::start(peer_addr, &request);
+        let future = self.handler.handle((request,));
+        async move {
+            let response = future.await;
+            let entry = entry.finish(&response);
+            info!("{entry}");
+            response
+        }
+    }
+}
+
+/// Adds a trace event with the request's details as attributes.
+impl<'t, T, H, B, RB> Handler<(&'t mut T, SocketAddr, Request<B>)> for AccessLog<H>
+where
+    T: Trace,
+    H: Handler<(Request<B>,), Response = Response<RB>>,
+    RB: Body,
+{
+    type Response = Response<RB>;
+    type Future = impl Future<Output = Self::Response>;
+
+    fn handle(
+        &self,
+        (tracer, peer_addr, request): (&'t mut T, SocketAddr, Request<B>),
+    ) -> Self::Future {
+        let timing = tracer.start_trace();
+        let entry = Entry::start(peer_addr, &request);
+        let future = self.handler.handle((request,));
+        async move {
+            let response = future.await;
+            let entry = entry.finish(&response);
+            // NOTE: the duration is already part of the trace event.
+            let attributes: &[(&str, &dyn AttributeValue)] = &[
+                ("method", &entry.method.to_string()),
+                ("path", &entry.path),
+                ("version", &entry.version.to_string()),
+                ("status", &entry.status),
+                // Zero if the body length is unknown (chunked).
+                ("bytes", &entry.bytes.unwrap_or(0)),
+                ("peer_address", &entry.peer_addr.to_string()),
+            ];
+            tracer.finish_trace(timing, "HTTP request", attributes);
+            response
+        }
+    }
+}
+
+impl<H, Req> Middleware<H, Req> for AccessLog<H>
+where
+    AccessLog<H>: Handler<Req>,
+{
+    fn wrap(handler: H) -> Self {
+        AccessLog::new(handler)
+    }
+}
+
+/// Access log entry for a single request.
+struct Entry {
+    peer_addr: SocketAddr,
+    time: SystemTime,
+    start: Instant,
+    method: Method,
+    path: String,
+    version: Version,
+    status: u16,
+    /// `None` if the body length is unknown.
+    bytes: Option<usize>,
+    duration: Duration,
+}
+
+impl Entry {
+    /// Start an entry for `request`.
+    fn start<B>(peer_addr: SocketAddr, request: &Request<B>) -> Entry {
+        Entry {
+            peer_addr,
+            time: SystemTime::now(),
+            start: Instant::now(),
+            method: request.method(),
+            path: request.path().to_owned(),
+            version: request.version(),
+            status: 0,
+            bytes: None,
+            duration: Duration::ZERO,
+        }
+    }
+
+    /// Finish the entry with the `response`.
+    fn finish<B: Body>(mut self, response: &Response<B>) -> Entry {
+        self.status = response.status().0;
+        self.bytes = match response.body().length() {
+            BodyLength::Known(length) => Some(length),
+            BodyLength::Chunked => None,
+        };
+        self.duration = self.start.elapsed();
+        self
+    }
+}
+
+/// Formats the entry in the Common Log Format, with the duration appended.
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} - - [{}] \"{} {} {}\" {} ",
+            self.peer_addr.ip(),
+            ClfDate(self.time),
+            self.method,
+            self.path,
+            self.version,
+            self.status,
+        )?;
+        match self.bytes {
+            Some(bytes) => write!(f, "{bytes}")?,
+            None => f.write_str("-")?,
+        }
+        write!(f, " {}", self.duration.as_micros())
+    }
+}
+
+/// Formats a time as used in the Common Log Format, e.g.
+/// `10/Oct/2000:13:55:36 +0000`. Always uses UTC.
+struct ClfDate(SystemTime);
+
+impl fmt::Display for ClfDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const MONTHS: [&str; 12] = [
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ];
+        let secs = self
+            .0
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let (year, month, day) = civil_from_days(secs / 86400);
+        let secs_of_day = secs % 86400;
+        write!(
+            f,
+            "{day:02}/{}/{year}:{:02}:{:02}:{:02} +0000",
+            MONTHS[(month - 1) as usize],
+            secs_of_day / 3600,
+            (secs_of_day / 60) % 60,
+            secs_of_day % 60,
+        )
+    }
+}
+
+/// Returns the year, month (1-12) and day of the month (1-31) for `days` since
+/// the Unix epoch.
+///
+/// Based on the `civil_from_days` algorithm by Howard Hinnant.
+const fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    // Shift the epoch to 1 March 0000.
+    let days = days + 719_468;
+    let era = days / 146_097;
+    // Day of the era (400 year period).
+    let doe = days % 146_097;
+    // Year of the era.
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    // Day of the year, starting at 1 March.
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    // Month, starting at March (0).
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = era * 400 + yoe + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::ClfDate;
+
+    #[test]
+    fn clf_date() {
+        let tests = [
+            (0, "01/Jan/1970:00:00:00 +0000"),
+            (971_186_136, "10/Oct/2000:13:55:36 +0000"),
+            (951_782_400, "29/Feb/2000:00:00:00 +0000"),
+            (1_704_067_199, "31/Dec/2023:23:59:59 +0000"),
+        ];
+        for (secs, expected) in tests {
+            let time = SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+            assert_eq!(ClfDate(time).to_string(), expected, "secs: {secs}");
+        }
+    }
+}
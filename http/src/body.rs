@@ -1,18 +1,68 @@
 //! Module with HTTP body related types.
 //!
 //! See the [`Body`] trait.
+//!
+//! # Bodies produced by another actor
+//!
+//! Large response bodies can be produced by another actor, separating the
+//! producing of the body from writing it to the connection. This is done using
+//! [`BodyReceiver::from_actor`], which sends a [`BodyMessage`] to the producing
+//! actor. The actor sends the body in chunks using [`BodySender::send`], which
+//! waits if the connection is slow, i.e. it provides back-pressure.
+//!
+//! ```
+//! # #![feature(never_type)]
+//! use heph::actor;
+//! use heph::actor_ref::{ActorRef, SendError};
+//! use heph_http::body::{BodyMessage, BodyReceiver, ChunkedBody};
+//! use heph_http::Response;
+//! use heph_rt::ThreadLocal;
+//!
+//! /// Actor that produces the body, in this case a lot of numbers.
+//! async fn producer(mut ctx: actor::Context<BodyMessage<usize, String>, ThreadLocal>) {
+//!     while let Ok(msg) = ctx.receive_next().await {
+//!         for n in 0..msg.request {
+//!             // Stop if the connection is closed.
+//!             if msg.body.send(format!("{n}\n")).await.is_err() {
+//!                 break;
+//!             }
+//!         }
+//!         // Dropping the `BodySender` ends the body.
+//!     }
+//! }
+//!
+//! /// Create a response with a body produced by `producer`.
+//! async fn response(
+//!     producer: &ActorRef<BodyMessage<usize, String>>,
+//! ) -> Result<Response<ChunkedBody<BodyReceiver<String>>>, SendError> {
+//!     let body = BodyReceiver::from_actor(producer, 1_000_000).await?;
+//!     Ok(Response::ok().with_body(ChunkedBody::new(body)))
+//! }
+//! # _ = (producer, response);
+//! ```
 
 use std::async_iter::AsyncIterator;
 use std::future::Future;
-use std::io;
-use std::pin::pin;
+use std::io::{self, Write};
+use std::pin::{pin, Pin};
+use std::task::{self, Poll};
 
+use heph::actor_ref::{ActorRef, SendError};
+use heph_rt::fs::File;
 use heph_rt::io::Buf;
 use heph_rt::net::TcpStream;
 use heph_rt::util::next;
 
+use crate::head::header::Headers;
+
 /// Last chunk of a body in a chunked response.
-const LAST_CHUNK: &[u8] = b"0\r\n\r\n";
+pub(crate) const LAST_CHUNK: &[u8] = b"0\r\n\r\n";
+
+/// End of the data of a chunk.
+const CRLF: &[u8] = b"\r\n";
+
+/// Maximum size of the buffer used to read a file in [`FileBody`].
+const FILE_BUF_SIZE: usize = 64 * 1024;
 
 /// Trait that defines a HTTP body.
 ///
@@ -24,6 +74,7 @@ const LAST_CHUNK: &[u8] = b"0\r\n\r\n";
 /// * [`StreamingBody`]: body that is streaming, with a known length.
 /// * [`ChunkedBody`]: body that is streaming, with a *un*known length. This
 ///   uses HTTP chunked encoding to transfer the body.
+/// * [`FileBody`]: body send from a (part of a) file.
 pub trait Body: PrivateBody {
     /// Length of the body, or the body will be chunked.
     fn length(&self) -> BodyLength;
@@ -185,9 +236,15 @@ where
 }
 
 /// Streaming body with an unknown length. Send in multiple chunks.
+///
+/// Optionally a trailer section can be send after the body, see
+/// [`ChunkedBody::with_trailers`].
 #[derive(Debug)]
 pub struct ChunkedBody<S> {
     body: S,
+    /// Boxed as trailers are rarely used, keeping the body small for the
+    /// common case.
+    trailers: Option<Box<Headers>>,
 }
 
 impl<S, B> ChunkedBody<S>
@@ -200,7 +257,19 @@ where
     /// If the total length of `stream` is known prefer to use
     /// [`StreamingBody`].
     pub const fn new(stream: S) -> ChunkedBody<S> {
-        ChunkedBody { body: stream }
+        ChunkedBody {
+            body: stream,
+            trailers: None,
+        }
+    }
+
+    /// Send `trailers` after the body.
+    ///
+    /// Note that the receiver is allowed to ignore the trailer section, so
+    /// these shouldn't contain anything required to process the message.
+    pub fn with_trailers(mut self, trailers: Headers) -> ChunkedBody<S> {
+        self.trailers = (!trailers.is_empty()).then(|| Box::new(trailers));
+        self
     }
 }
 
@@ -228,11 +297,186 @@ where
     ) -> Self::WriteFuture<'stream> {
         async move {
             let mut body = pin!(self.body);
-            let http_head = stream.send_all(http_head).await?;
+            let mut http_head = stream.send_all(http_head).await?;
+            // NOTE: reusing the buffer of the HTTP head for the chunk heads.
             while let Some(chunk) = next(&mut body).await {
+                // An empty chunk would signal the end of the body.
+                if chunk.len() == 0 {
+                    continue;
+                }
+                http_head.clear();
+                write!(http_head, "{:x}\r\n", chunk.len()).unwrap();
+                http_head = stream.send_all(http_head).await?;
                 _ = stream.send_all(chunk).await?;
+                _ = stream.send_all(CRLF).await?;
+            }
+
+            if let Some(trailers) = self.trailers {
+                // Last chunk followed by the trailer section, RFC 9112 section
+                // 7.1.2.
+                http_head.clear();
+                http_head.extend_from_slice(b"0\r\n");
+                for trailer in &*trailers {
+                    http_head.extend_from_slice(trailer.name().as_ref().as_bytes());
+                    http_head.extend_from_slice(b": ");
+                    http_head.extend_from_slice(trailer.value());
+                    http_head.extend_from_slice(b"\r\n");
+                }
+                http_head.extend_from_slice(b"\r\n");
+                http_head = stream.send_all(http_head).await?;
+            } else {
+                _ = stream.send_all(LAST_CHUNK).await?;
+            }
+            Ok(http_head)
+        }
+    }
+}
+
+/// Message send to the actor producing a body, see
+/// [`BodyReceiver::from_actor`].
+///
+/// It holds both the request (`Req`) and the way to send the body,
+/// [`BodySender`].
+#[derive(Debug)]
+pub struct BodyMessage<Req, B> {
+    /// The request object, describing what body to produce.
+    pub request: Req,
+    /// Used to send the body.
+    pub body: BodySender<B>,
+}
+
+/// Sending side of a body produced by an actor, see
+/// [`BodyReceiver::from_actor`].
+///
+/// The body ends once the sender is dropped.
+#[derive(Debug)]
+pub struct BodySender<B> {
+    sender: heph_inbox::Sender<B>,
+}
+
+impl<B> BodySender<B> {
+    /// Send a `chunk` of the body.
+    ///
+    /// If the chunks aren't written to the connection fast enough this waits
+    /// until there is space for more chunks. Returns an error if the body is
+    /// no longer being written, e.g. when the connection is closed.
+    pub async fn send(&self, chunk: B) -> Result<(), SendError> {
+        self.sender.send(chunk).await.map_err(|_| SendError)
+    }
+
+    /// Attempt to send a `chunk` of the body, without waiting.
+    ///
+    /// Returns an error if there is no space for more chunks or if the body is
+    /// no longer being written.
+    pub fn try_send(&self, chunk: B) -> Result<(), SendError> {
+        self.sender.try_send(chunk).map_err(|_| SendError)
+    }
+
+    /// Returns `true` if the body is still being written.
+    pub fn is_connected(&self) -> bool {
+        self.sender.is_connected()
+    }
+}
+
+/// Receiving side of a body produced by an actor.
+///
+/// This is an [`AsyncIterator`] of the chunks of the body, to be used in
+/// [`ChunkedBody`] or [`StreamingBody`]. See the [module documentation] for an
+/// example.
+///
+/// [module documentation]: crate::body#bodies-produced-by-another-actor
+#[derive(Debug)]
+pub struct BodyReceiver<B> {
+    receiver: heph_inbox::Receiver<B>,
+}
+
+impl<B> BodyReceiver<B> {
+    /// Request the body from the actor behind `actor_ref`.
+    ///
+    /// This sends a [`BodyMessage`] with `request` to the actor. The actor
+    /// sends the body using the [`BodySender`] in the message.
+    pub async fn from_actor<M, Req>(
+        actor_ref: &ActorRef<M>,
+        request: Req,
+    ) -> Result<BodyReceiver<B>, SendError>
+    where
+        M: From<BodyMessage<Req, B>>,
+    {
+        let (sender, receiver) = heph_inbox::new_small();
+        let body = BodySender { sender };
+        actor_ref.send(BodyMessage { request, body }).await?;
+        Ok(BodyReceiver { receiver })
+    }
+}
+
+impl<B> AsyncIterator for BodyReceiver<B> {
+    type Item = B;
+
+    fn poll_next(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().receiver.recv()).poll(ctx)
+    }
+}
+
+/// Body send from a file, with a known length. Send in a single payload (i.e.
+/// not chunked).
+///
+/// # Notes
+///
+/// Ideally this would use `sendfile(2)`, but io_uring doesn't support it (yet).
+/// So instead the file is read into a buffer (in parts), which is then send.
+#[derive(Debug)]
+pub struct FileBody {
+    file: File,
+    offset: u64,
+    length: usize,
+}
+
+impl FileBody {
+    /// Use `length` bytes, starting at `offset`, of `file` as HTTP body.
+    ///
+    /// The caller must ensure `file` contains at least `offset + length` bytes,
+    /// otherwise sending the body will fail with an
+    /// [`io::ErrorKind::UnexpectedEof`] error.
+    pub const fn new(file: File, offset: u64, length: usize) -> FileBody {
+        FileBody {
+            file,
+            offset,
+            length,
+        }
+    }
+}
+
+impl Body for FileBody {
+    fn length(&self) -> BodyLength {
+        BodyLength::Known(self.length)
+    }
+}
+
+impl PrivateBody for FileBody {
+    type WriteFuture<'stream> = impl Future<Output = io::Result<Vec<u8>>> + 'stream;
+
+    fn write_message<'stream>(
+        self,
+        stream: &'stream mut TcpStream,
+        http_head: Vec<u8>,
+    ) -> Self::WriteFuture<'stream> {
+        async move {
+            let http_head = stream.send_all(http_head).await?;
+            let mut offset = self.offset;
+            let mut left = self.length;
+            let mut buf = Vec::with_capacity(left.min(FILE_BUF_SIZE));
+            while left != 0 {
+                buf.clear();
+                buf = self.file.read_at(buf, offset).await?;
+                if buf.is_empty() {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                // Don't send more than requested.
+                buf.truncate(left);
+                offset += buf.len() as u64;
+                left -= buf.len();
+                buf = stream.send_all(buf).await?;
             }
-            _ = stream.send_all(LAST_CHUNK).await?;
             Ok(http_head)
         }
     }
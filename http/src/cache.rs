@@ -0,0 +1,454 @@
+//! Module with the [`ResponseCache`], an in-memory response cache.
+//!
+//! The cache stores the responses to `GET` and `HEAD` requests in memory,
+//! keyed by the method, path and the values of a configurable subset of the
+//! request headers (e.g. `Accept-Encoding`). Cached responses are served
+//! without invoking the handler, which makes a big difference for read-heavy
+//! endpoints.
+//!
+//! Caching is configured per route using [`CacheConfig`], which sets the time
+//! to live (TTL) of the responses. The total size of the cache is limited, once
+//! the limit is reached the least recently used responses are evicted.
+//!
+//! Only successful (`200 OK`) responses are cached. Responses that set a
+//! cookie or have a `Cache-Control` header containing `no-store`, `no-cache`
+//! or `private` are never cached. Requests with a `Cache-Control` header
+//! containing `no-store` or `no-cache` bypass the cache.
+//!
+//! Responses served from the cache have an `Age` header added, the number of
+//! seconds since the response was cached.
+//!
+//! The cache can be used with [`HttpActor::with_cache`], in which case cache
+//! hits are responded to without spawning the request actor, or directly using
+//! [`ResponseCache::get`] and [`ResponseCache::insert`].
+//!
+//! [`HttpActor::with_cache`]: crate::request_actor::HttpActor::with_cache
+//!
+//! # Notes
+//!
+//! The cache is shared between all its clones, i.e. all connections when used
+//! with [`HttpActor`]. It's protected by a lock, so it can be shared between
+//! thread-local and thread-safe actors.
+//!
+//! [`HttpActor`]: crate::request_actor::HttpActor
+//!
+//! # Examples
+//!
+//! ```
+//! # #![feature(never_type)]
+//! use std::time::Duration;
+//!
+//! use heph::actor::actor_fn;
+//! use heph::supervisor::NoSupervisor;
+//! use heph_http::cache::{CacheConfig, ResponseCache};
+//! use heph_http::request_actor::HttpActor;
+//! use heph_http::HeaderName;
+//! use heph_rt::spawn::ActorOptions;
+//! # use heph::actor;
+//! # use heph_http::request_actor::Responder;
+//! # use heph_http::Request;
+//! # use heph_rt::ThreadLocal;
+//! # async fn request_actor(_: actor::Context<!, ThreadLocal>, _: (Request<Vec<u8>>, Responder)) {}
+//!
+//! // Cache at most 16 MB of responses.
+//! let cache = ResponseCache::new(16 * 1024 * 1024)
+//!     // The list of pets changes often.
+//!     .with_route("/pets", CacheConfig::new(Duration::from_secs(5)))
+//!     // Other pages hardly ever change, but are compressed based on the
+//!     // `Accept-Encoding` request header.
+//!     .with_default(
+//!         CacheConfig::new(Duration::from_secs(60 * 60))
+//!             .with_vary_header(HeaderName::ACCEPT_ENCODING),
+//!     );
+//!
+//! let http_actor = HttpActor::new(NoSupervisor, actor_fn(request_actor), ActorOptions::default())
+//!     .with_cache(cache);
+//! # _ = http_actor;
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::body::{Body, BodyLength, OneshotBody};
+use crate::request_actor::ActorResponse;
+use crate::{Header, HeaderName, Headers, Method, Request, Response, StatusCode, Version};
+
+/// In-memory response cache.
+///
+/// See the [module documentation] for more information and an example.
+///
+/// [module documentation]: crate::cache
+#[derive(Clone)]
+pub struct ResponseCache {
+    shared: Arc<Shared>,
+}
+
+/// Data shared between all clones of a [`ResponseCache`].
+struct Shared {
+    routes: Vec<(String, CacheConfig)>,
+    default: Option<CacheConfig>,
+    max_bytes: usize,
+    entries: Mutex<Entries>,
+}
+
+impl ResponseCache {
+    /// Create a new cache storing at most `max_bytes` of responses.
+    ///
+    /// By default no routes are configured, meaning that nothing is cached.
+    /// Use [`ResponseCache::with_route`] and [`ResponseCache::with_default`]
+    /// to configure it.
+    pub fn new(max_bytes: usize) -> ResponseCache {
+        ResponseCache {
+            shared: Arc::new(Shared {
+                routes: Vec::new(),
+                default: None,
+                max_bytes,
+                entries: Mutex::new(Entries::new()),
+            }),
+        }
+    }
+
+    /// Cache the responses of requests to `path` using `config`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cache was cloned.
+    pub fn with_route(mut self, path: &str, config: CacheConfig) -> Self {
+        self.shared_mut().routes.push((path.to_owned(), config));
+        self
+    }
+
+    /// Cache the responses of requests to paths not registered using
+    /// [`ResponseCache::with_route`] using `config`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cache was cloned.
+    pub fn with_default(mut self, config: CacheConfig) -> Self {
+        self.shared_mut().default = Some(config);
+        self
+    }
+
+    fn shared_mut(&mut self) -> &mut Shared {
+        Arc::get_mut(&mut self.shared).expect("can't configure a `ResponseCache` after cloning it")
+    }
+
+    /// Returns the cached response to `request`, if any.
+    pub fn get<B>(&self, request: &Request<B>) -> Option<ActorResponse> {
+        match self.lookup(request) {
+            Lookup::Hit(response) => Some(response),
+            Lookup::Miss(_) | Lookup::Uncached => None,
+        }
+    }
+
+    /// Cache `response` as response to `request`.
+    ///
+    /// This does nothing if the `request` is not cached (see
+    /// [`ResponseCache::with_route`]) or the `response` is not cacheable.
+    pub fn insert<B>(&self, request: &Request<B>, response: &ActorResponse) {
+        if let Some(key) = self.key(request) {
+            self.store(key, response);
+        }
+    }
+
+    /// Returns the number of bytes used by the cached responses.
+    pub fn size(&self) -> usize {
+        self.shared.entries.lock().unwrap().size
+    }
+
+    /// Remove all cached responses.
+    pub fn clear(&self) {
+        self.shared.entries.lock().unwrap().clear();
+    }
+
+    /// Lookup the response to `request`.
+    pub(crate) fn lookup<B>(&self, request: &Request<B>) -> Lookup {
+        let Some(key) = self.key(request) else {
+            return Lookup::Uncached;
+        };
+        let now = Instant::now();
+        let mut entries = self.shared.entries.lock().unwrap();
+        match entries.get(&key.key, now) {
+            Some(entry) => Lookup::Hit(entry.response(now)),
+            None => Lookup::Miss(key),
+        }
+    }
+
+    /// Store `response` under `key`, if it's cacheable.
+    pub(crate) fn store(&self, key: CacheKey, response: &ActorResponse) {
+        if !is_cacheable_response(response) {
+            return;
+        }
+        let entry = Entry::new(&key, response);
+        if entry.size > self.shared.max_bytes {
+            return;
+        }
+        let mut entries = self.shared.entries.lock().unwrap();
+        entries.insert(key.key, entry, self.shared.max_bytes);
+    }
+
+    /// Returns the cache key for `request`, `None` if the request shouldn't
+    /// be cached.
+    fn key<B>(&self, request: &Request<B>) -> Option<CacheKey> {
+        let head = match request.method() {
+            Method::Get => false,
+            Method::Head => true,
+            _ => return None,
+        };
+        if has_cache_directive(request.headers(), &["no-store", "no-cache"]) {
+            return None;
+        }
+        let path = request.path();
+        let config = match self.shared.routes.iter().find(|(p, _)| p == path) {
+            Some((_, config)) => config,
+            None => self.shared.default.as_ref()?,
+        };
+        let vary = config
+            .vary
+            .iter()
+            .map(|name| request.headers().get_bytes(name).map(<[u8]>::to_vec))
+            .collect();
+        Some(CacheKey {
+            key: Key {
+                head,
+                path: path.to_owned(),
+                vary,
+            },
+            ttl: config.ttl,
+        })
+    }
+}
+
+impl fmt::Debug for ResponseCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries = self.shared.entries.lock().unwrap();
+        f.debug_struct("ResponseCache")
+            .field("routes", &self.shared.routes)
+            .field("default", &self.shared.default)
+            .field("max_bytes", &self.shared.max_bytes)
+            .field("size", &entries.size)
+            .field("entries", &entries.map.len())
+            .finish()
+    }
+}
+
+/// Caching configuration for a route, see [`ResponseCache::with_route`].
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct CacheConfig {
+    /// Time to live of the cached responses.
+    ttl: Duration,
+    /// Request headers that are part of the cache key.
+    vary: Vec<HeaderName<'static>>,
+}
+
+impl CacheConfig {
+    /// Create a new configuration caching responses for `ttl`.
+    pub const fn new(ttl: Duration) -> CacheConfig {
+        CacheConfig {
+            ttl,
+            vary: Vec::new(),
+        }
+    }
+
+    /// Add the request header `name` to the cache key.
+    ///
+    /// This should include all request headers that influence the response,
+    /// e.g. `Accept-Encoding` if the response is compressed based on it.
+    pub fn with_vary_header(mut self, name: HeaderName<'static>) -> Self {
+        self.vary.push(name);
+        self
+    }
+
+    /// Returns the time to live of the cached responses.
+    pub const fn ttl(&self) -> Duration {
+        self.ttl
+    }
+}
+
+/// Result of [`ResponseCache::lookup`].
+pub(crate) enum Lookup {
+    /// Response is cached.
+    Hit(ActorResponse),
+    /// Response is not cached, but can be using [`ResponseCache::store`].
+    Miss(CacheKey),
+    /// Request should not be cached.
+    Uncached,
+}
+
+/// Key for a cached response, with the time to live of the response.
+pub(crate) struct CacheKey {
+    key: Key,
+    ttl: Duration,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Key {
+    /// `true` for `HEAD` requests, `false` for `GET` requests.
+    head: bool,
+    path: String,
+    /// Values of [`CacheConfig::vary`] headers.
+    vary: Vec<Option<Vec<u8>>>,
+}
+
+/// Cached responses.
+struct Entries {
+    map: HashMap<Key, Entry>,
+    /// Total size of all entries in `map`.
+    size: usize,
+    /// Counter used to determine the least recently used entry.
+    clock: u64,
+}
+
+impl Entries {
+    fn new() -> Entries {
+        Entries {
+            map: HashMap::new(),
+            size: 0,
+            clock: 0,
+        }
+    }
+
+    /// Returns the entry for `key`, if it's not expired.
+    fn get(&mut self, key: &Key, now: Instant) -> Option<&Entry> {
+        if self.map.get(key)?.expires <= now {
+            self.remove(key);
+            return None;
+        }
+        self.clock += 1;
+        let entry = self.map.get_mut(key)?;
+        entry.last_used = self.clock;
+        Some(entry)
+    }
+
+    /// Insert `entry`, evicting entries to keep the size below `max_bytes`.
+    fn insert(&mut self, key: Key, mut entry: Entry, max_bytes: usize) {
+        self.remove(&key);
+        if self.size + entry.size > max_bytes {
+            let now = Instant::now();
+            self.evict_expired(now);
+            while self.size + entry.size > max_bytes {
+                self.evict_least_recently_used();
+            }
+        }
+        self.clock += 1;
+        entry.last_used = self.clock;
+        self.size += entry.size;
+        _ = self.map.insert(key, entry);
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(entry) = self.map.remove(key) {
+            self.size -= entry.size;
+        }
+    }
+
+    /// Remove all expired entries.
+    fn evict_expired(&mut self, now: Instant) {
+        let mut size = self.size;
+        self.map.retain(|_, entry| {
+            let expired = entry.expires <= now;
+            if expired {
+                size -= entry.size;
+            }
+            !expired
+        });
+        self.size = size;
+    }
+
+    /// Remove the least recently used entry.
+    fn evict_least_recently_used(&mut self) {
+        let key = self
+            .map
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = key {
+            self.remove(&key);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.map.clear();
+        self.size = 0;
+    }
+}
+
+/// Cached response.
+struct Entry {
+    version: Version,
+    headers: Headers,
+    body: OneshotBody<Vec<u8>>,
+    /// Time the response was cached.
+    created: Instant,
+    /// Time after which the response is no longer served.
+    expires: Instant,
+    /// Value of [`Entries::clock`] when the response was last used.
+    last_used: u64,
+    /// Approximate size of the cached response.
+    size: usize,
+}
+
+impl Entry {
+    fn new(key: &CacheKey, response: &ActorResponse) -> Entry {
+        let now = Instant::now();
+        let headers = response.headers().clone();
+        let body = response.body().clone();
+        let headers_size: usize = headers
+            .iter()
+            .map(|header| header.name().as_ref().len() + header.value().len())
+            .sum();
+        let vary_size: usize = key.key.vary.iter().flatten().map(Vec::len).sum();
+        let body_size = match body.length() {
+            BodyLength::Known(length) => length,
+            BodyLength::Chunked => 0,
+        };
+        let size = body_size + headers_size + key.key.path.len() + vary_size;
+        Entry {
+            version: response.version(),
+            headers,
+            body,
+            created: now,
+            expires: now + key.ttl,
+            last_used: 0,
+            size,
+        }
+    }
+
+    /// Create a response from the cached entry.
+    fn response(&self, now: Instant) -> ActorResponse {
+        let mut headers = self.headers.clone();
+        let age = now.duration_since(self.created).as_secs();
+        let mut itoa_buf = itoa::Buffer::new();
+        headers.append(Header::new(
+            HeaderName::AGE,
+            itoa_buf.format(age).as_bytes(),
+        ));
+        Response::new(self.version, StatusCode::OK, headers, self.body.clone())
+    }
+}
+
+/// Returns `true` if `response` can be cached.
+fn is_cacheable_response(response: &ActorResponse) -> bool {
+    response.status() == StatusCode::OK
+        && response.headers().get(&HeaderName::SET_COOKIE).is_none()
+        && !has_cache_directive(response.headers(), &["no-store", "no-cache", "private"])
+}
+
+/// Returns `true` if the `Cache-Control` header in `headers` contains any of
+/// `directives`.
+fn has_cache_directive(headers: &Headers, directives: &[&str]) -> bool {
+    headers
+        .get_all(&HeaderName::CACHE_CONTROL)
+        .flat_map(|header| header.value().split(|b| *b == b','))
+        .map(<[u8]>::trim_ascii)
+        .any(|directive| {
+            // Ignore any arguments, e.g. `no-cache="Set-Cookie"`.
+            let name = directive.split(|b| *b == b'=').next().unwrap_or(directive);
+            directives
+                .iter()
+                .any(|d| name.eq_ignore_ascii_case(d.as_bytes()))
+        })
+}
@@ -12,8 +12,8 @@ use heph_rt::Access;
 use crate::body::{BodyLength, EmptyBody};
 use crate::head::header::{FromHeaderValue, HeaderName, Headers};
 use crate::{
-    map_version_byte, trim_ws, Method, Response, StatusCode, BUF_SIZE, INIT_HEAD_SIZE, MAX_HEADERS,
-    MAX_HEAD_SIZE, MIN_READ_SIZE,
+    map_version_byte, parse_chunk, trim_ws, Chunk, ChunkError, Method, Response, StatusCode,
+    BUF_SIZE, INIT_HEAD_SIZE, MAX_HEADERS, MAX_HEAD_SIZE, MIN_READ_SIZE,
 };
 
 /// HTTP/1.1 client.
@@ -232,6 +232,15 @@ impl Client {
                     let status = StatusCode(response.code.unwrap());
                     // NOTE: don't care about the reason.
 
+                    // RFC 9110 section 15.2:
+                    // > A client MUST be able to parse one or more 1xx
+                    // > responses received prior to a final response, even if
+                    // > the client does not expect one. A user agent MAY ignore
+                    // > unexpected 1xx responses.
+                    if status.is_informational() && status != StatusCode::SWITCHING_PROTOCOLS {
+                        continue;
+                    }
+
                     // RFC 7230 section 3.3.3 Message Body Length.
                     let mut body_length: Option<ResponseBodyLength> = None;
                     let headers =
@@ -337,25 +346,11 @@ impl Client {
                             BodyKind::Known { left: 0 }
                         }
                         Some(ResponseBodyLength::Known(left)) => BodyKind::Known { left },
-                        Some(ResponseBodyLength::Chunked) => {
-                            #[allow(clippy::cast_possible_truncation)] // For truncate below.
-                            match httparse::parse_chunk_size(&self.buf[self.parsed_bytes..]) {
-                                Ok(httparse::Status::Complete((idx, chunk_size))) => {
-                                    self.parsed_bytes += idx;
-                                    BodyKind::Chunked {
-                                        // FIXME: add check here. It's fine on
-                                        // 64 bit (only currently supported).
-                                        left_in_chunk: chunk_size as usize,
-                                        read_complete: chunk_size == 0,
-                                    }
-                                }
-                                Ok(httparse::Status::Partial) => BodyKind::Chunked {
-                                    left_in_chunk: 0,
-                                    read_complete: false,
-                                },
-                                Err(_) => return Err(ResponseError::InvalidChunkSize),
-                            }
-                        }
+                        Some(ResponseBodyLength::Chunked) => BodyKind::Chunked {
+                            left_in_chunk: 0,
+                            read_complete: false,
+                            after_data: false,
+                        },
                         // RFC 7230 section 3.3.3 point 1:
                         // > Any response to a HEAD request and any response
                         // > with a 1xx (Informational), 204 (No Content), or
@@ -380,7 +375,26 @@ impl Client {
                             read_complete: false,
                         },
                     };
-                    let body = Body { client: self, kind };
+                    let mut body = Body {
+                        client: self,
+                        kind,
+                        trailers: Headers::EMPTY,
+                    };
+                    if let BodyKind::Chunked {
+                        left_in_chunk,
+                        read_complete,
+                        after_data,
+                    } = &mut body.kind
+                    {
+                        // Try to parse the first chunk using the bytes already
+                        // read, so that `Body::is_empty` is accurate.
+                        _ = body.client.parse_chunk(
+                            left_in_chunk,
+                            read_complete,
+                            after_data,
+                            &mut body.trailers,
+                        )?;
+                    }
                     return Ok(Some(Response::new(version, status, headers, body)));
                 }
                 Ok(httparse::Status::Partial) => {
@@ -403,27 +417,47 @@ impl Client {
         // Fields of `BodyKind::Chunked`:
         left_in_chunk: &mut usize,
         read_complete: &mut bool,
+        after_data: &mut bool,
+        // Field of `Body`:
+        trailers: &mut Headers,
     ) -> Result<(), ResponseError> {
-        loop {
-            match httparse::parse_chunk_size(&self.buf[self.parsed_bytes..]) {
-                #[allow(clippy::cast_possible_truncation)] // For truncate below.
-                Ok(httparse::Status::Complete((idx, chunk_size))) => {
-                    self.parsed_bytes += idx;
-                    if chunk_size == 0 {
+        while !self.parse_chunk(left_in_chunk, read_complete, after_data, trailers)? {
+            if self.recv().await? {
+                return Err(ResponseError::IncompleteResponse);
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse the next chunk from the buffer, returns `false` if more bytes are
+    /// needed.
+    fn parse_chunk(
+        &mut self,
+        // Fields of `BodyKind::Chunked`:
+        left_in_chunk: &mut usize,
+        read_complete: &mut bool,
+        after_data: &mut bool,
+        // Field of `Body`:
+        trailers: &mut Headers,
+    ) -> Result<bool, ResponseError> {
+        match parse_chunk(&self.buf[self.parsed_bytes..], *after_data) {
+            Ok(Some((idx, chunk))) => {
+                self.parsed_bytes += idx;
+                match chunk {
+                    Chunk::Data { size } => {
+                        *left_in_chunk = size;
+                        *after_data = true;
+                    }
+                    Chunk::Last { trailers: t } => {
                         *read_complete = true;
+                        *trailers = t;
                     }
-                    // FIXME: add check here. It's fine on 64 bit (only currently
-                    // supported).
-                    *left_in_chunk = chunk_size as usize;
-                    return Ok(());
                 }
-                Ok(httparse::Status::Partial) => {} // Read some more data below.
-                Err(_) => return Err(ResponseError::InvalidChunkSize),
-            }
-
-            if self.recv().await? {
-                return Err(ResponseError::IncompleteResponse);
+                Ok(true)
             }
+            Ok(None) => Ok(false),
+            Err(ChunkError::InvalidSize) => Err(ResponseError::InvalidChunkSize),
+            Err(ChunkError::Trailer(err)) => Err(ResponseError::from_httparse(err)),
         }
     }
 
@@ -467,6 +501,8 @@ enum ResponseBodyLength {
 pub struct Body<'c> {
     client: &'c mut Client,
     kind: BodyKind,
+    /// Trailer section of a chunked body.
+    trailers: Headers,
 }
 
 #[derive(Debug)]
@@ -482,6 +518,9 @@ enum BodyKind {
         left_in_chunk: usize,
         /// Read all chunks.
         read_complete: bool,
+        /// Read the data of a chunk, meaning the next chunk starts with the
+        /// CRLF ending that data.
+        after_data: bool,
     },
     /// Body length is not known, read the body until the server closes the
     /// connection.
@@ -515,6 +554,7 @@ impl<'c> Body<'c> {
             BodyKind::Chunked {
                 left_in_chunk,
                 read_complete,
+                ..
             } => read_complete && left_in_chunk == 0,
             BodyKind::Unknown { read_complete } => read_complete,
         }
@@ -539,6 +579,15 @@ impl<'c> Body<'c> {
         matches!(self.kind, BodyKind::Chunked { .. })
     }
 
+    /// Returns the trailer section of a chunked body.
+    ///
+    /// The trailers are only available once the entire body is read, i.e. when
+    /// [`Body::is_empty`] returns `true`. Before that, or if the body is not
+    /// chunked, this returns no headers.
+    pub const fn trailers(&self) -> &Headers {
+        &self.trailers
+    }
+
     /*
     TODO: RFC 7230 section 3.3.3 point 5:
        [..] If the sender closes the connection or the recipient times out
@@ -574,9 +623,17 @@ impl<'c> Body<'c> {
                 BodyKind::Chunked {
                     left_in_chunk,
                     read_complete,
+                    after_data,
                 } => {
                     if *left_in_chunk == 0 {
-                        self.client.read_chunk(left_in_chunk, read_complete).await?;
+                        self.client
+                            .read_chunk(
+                                left_in_chunk,
+                                read_complete,
+                                after_data,
+                                &mut self.trailers,
+                            )
+                            .await?;
                         // Read from the client's buffer again.
                         continue;
                     }
@@ -590,7 +647,7 @@ impl<'c> Body<'c> {
             let len_before = buf.spare_capacity();
             let limited_buf = self.client.stream.recv(buf.limit(limit)).await?;
             let buf = limited_buf.into_inner();
-            self.processed(buf.spare_capacity() - len_before);
+            self.received(len_before - buf.spare_capacity());
             return Ok(buf);
         }
     }
@@ -627,9 +684,17 @@ impl<'c> Body<'c> {
                 BodyKind::Chunked {
                     left_in_chunk,
                     read_complete,
+                    after_data,
                 } => {
                     if *left_in_chunk == 0 {
-                        self.client.read_chunk(left_in_chunk, read_complete).await?;
+                        self.client
+                            .read_chunk(
+                                left_in_chunk,
+                                read_complete,
+                                after_data,
+                                &mut self.trailers,
+                            )
+                            .await?;
                         // Read from the client's buffer again.
                         continue;
                     }
@@ -643,7 +708,7 @@ impl<'c> Body<'c> {
             let len_before = bufs.total_spare_capacity();
             let limited_bufs = self.client.stream.recv_vectored(bufs.limit(limit)).await?;
             let bufs = limited_bufs.into_inner();
-            self.processed(bufs.total_spare_capacity() - len_before);
+            self.received(len_before - bufs.total_spare_capacity());
             return Ok(bufs);
         }
     }
@@ -664,15 +729,21 @@ impl<'c> Body<'c> {
         }
     }
 
-    /// Mark `n` bytes are processed.
+    /// Mark `n` bytes in the buffer as processed.
     fn processed(&mut self, n: usize) {
+        self.received(n);
+        self.client.parsed_bytes += n;
+    }
+
+    /// Mark `n` bytes as received directly from the stream, i.e. without
+    /// going through the buffer.
+    fn received(&mut self, n: usize) {
         // TODO: should this be `unsafe`? We don't do underflow checks...
         match &mut self.kind {
             BodyKind::Known { left } => *left -= n,
             BodyKind::Chunked { left_in_chunk, .. } => *left_in_chunk -= n,
             BodyKind::Unknown { .. } => {}
         }
-        self.client.parsed_bytes += n;
     }
 }
 
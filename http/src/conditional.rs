@@ -0,0 +1,190 @@
+//! Module with helpers for conditional requests.
+//!
+//! Conditional requests allow clients to revalidate their cached copy of a
+//! resource, see RFC 9110 section 13. The server compares the validators of
+//! the current representation, the "ETag" and "Last-Modified" headers, with
+//! the "If-None-Match" and "If-Modified-Since" headers of the request and, if
+//! the cached copy is still valid, responds with a 304 Not Modified response
+//! without a body.
+//!
+//! [`Validators`] holds the validators of a representation and implements the
+//! evaluation.
+//!
+//! # Examples
+//!
+//! ```
+//! # #![allow(dead_code)]
+//! use std::time::SystemTime;
+//!
+//! use heph_http::body::OneshotBody;
+//! use heph_http::conditional::Validators;
+//! use heph_http::{Request, Response};
+//!
+//! async fn handler<B>(request: Request<B>) -> Response<OneshotBody<&'static str>> {
+//!     const BODY: &str = "Hello world";
+//!     let validators = Validators::new()
+//!         .with_etag("\"v1\"")
+//!         .with_last_modified(SystemTime::UNIX_EPOCH);
+//!
+//!     // Respond with 304 Not Modified if the client's copy is still valid.
+//!     if let Some(response) = validators.not_modified(&request) {
+//!         return response.with_body(OneshotBody::new(""));
+//!     }
+//!
+//!     let mut response = Response::ok().with_body(OneshotBody::new(BODY));
+//!     validators.append_headers(response.headers_mut());
+//!     response
+//! }
+//! ```
+
+use std::time::SystemTime;
+
+use httpdate::fmt_http_date;
+
+use crate::body::EmptyBody;
+use crate::{Header, HeaderName, Headers, Method, Request, Response};
+
+/// Validators of a representation, used to evaluate conditional requests.
+///
+/// See the [module documentation] for an example.
+///
+/// [module documentation]: crate::conditional
+#[derive(Clone, Debug, Default)]
+pub struct Validators {
+    /// Value for the "ETag" header.
+    etag: Option<String>,
+    /// Value for the "Last-Modified" header.
+    last_modified: Option<SystemTime>,
+}
+
+impl Validators {
+    /// Create a new set of validators, without any validators.
+    pub const fn new() -> Validators {
+        Validators {
+            etag: None,
+            last_modified: None,
+        }
+    }
+
+    /// Set the entity-tag of the representation.
+    ///
+    /// The `etag` must be formatted as it should be send in the "ETag" header,
+    /// i.e. including the quotes and the optional `W/` prefix for weak
+    /// entity-tags.
+    pub fn with_etag<E: Into<String>>(mut self, etag: E) -> Validators {
+        self.etag = Some(etag.into());
+        self
+    }
+
+    /// Set the time the representation was last modified.
+    pub const fn with_last_modified(mut self, last_modified: SystemTime) -> Validators {
+        self.last_modified = Some(last_modified);
+        self
+    }
+
+    /// Returns the entity-tag, if any.
+    pub fn etag(&self) -> Option<&str> {
+        self.etag.as_deref()
+    }
+
+    /// Returns the time the representation was last modified, if any.
+    pub const fn last_modified(&self) -> Option<SystemTime> {
+        self.last_modified
+    }
+
+    /// Returns `true` if `request` is a conditional request for which the
+    /// client's cached copy is still valid, i.e. if we can respond with a 304
+    /// Not Modified response.
+    ///
+    /// This is only the case for GET and HEAD requests. The "If-None-Match"
+    /// header takes precedence over the "If-Modified-Since" header (RFC 9110
+    /// section 13.2.2).
+    pub fn is_not_modified<B>(&self, request: &Request<B>) -> bool {
+        if !matches!(request.method(), Method::Get | Method::Head) {
+            return false;
+        }
+
+        if let Some(if_none_match) = request.headers().get_bytes(&HeaderName::IF_NONE_MATCH) {
+            return self
+                .etag
+                .as_deref()
+                .is_some_and(|etag| etag_matches(if_none_match, etag));
+        }
+
+        let Some(last_modified) = self.last_modified else {
+            return false;
+        };
+        match request.header::<SystemTime>(&HeaderName::IF_MODIFIED_SINCE) {
+            // HTTP dates only have a precision of seconds.
+            Ok(Some(since)) => last_modified
+                .duration_since(since)
+                .map_or(true, |diff| diff.as_secs() == 0),
+            Ok(None) | Err(_) => false,
+        }
+    }
+
+    /// Returns a 304 Not Modified response if `request` is a conditional
+    /// request for which the client's cached copy is still valid, see
+    /// [`Validators::is_not_modified`].
+    ///
+    /// The returned response includes the "ETag" and "Last-Modified" headers,
+    /// as required by RFC 9110 section 15.4.5.
+    pub fn not_modified<B>(&self, request: &Request<B>) -> Option<Response<EmptyBody>> {
+        if !self.is_not_modified(request) {
+            return None;
+        }
+        let mut response = Response::not_modified();
+        self.append_headers(response.headers_mut());
+        Some(response)
+    }
+
+    /// Append the "ETag" and "Last-Modified" headers to `headers`, if set.
+    pub fn append_headers(&self, headers: &mut Headers) {
+        if let Some(etag) = &self.etag {
+            headers.append(Header::new(HeaderName::ETAG, etag.as_bytes()));
+        }
+        if let Some(last_modified) = self.last_modified {
+            let last_modified = fmt_http_date(last_modified);
+            headers.append(Header::new(
+                HeaderName::LAST_MODIFIED,
+                last_modified.as_bytes(),
+            ));
+        }
+    }
+}
+
+/// Returns `true` if `header` (the value of an "If-None-Match" header) matches
+/// `etag`, using the weak comparison.
+pub(crate) fn etag_matches(header: &[u8], etag: &str) -> bool {
+    let header = crate::trim_ws(header);
+    if header == b"*" {
+        return true;
+    }
+    let etag = etag.strip_prefix("W/").unwrap_or(etag);
+    header.split(|b| *b == b',').any(|tag| {
+        let tag = crate::trim_ws(tag);
+        let tag = tag.strip_prefix(b"W/").unwrap_or(tag);
+        tag == etag.as_bytes()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::etag_matches;
+
+    #[test]
+    fn matching_etags() {
+        let etag = "\"a-b\"";
+        assert!(etag_matches(b"*", etag));
+        assert!(etag_matches(b"\"a-b\"", etag));
+        assert!(etag_matches(b"W/\"a-b\"", etag));
+        assert!(etag_matches(b"\"c-d\", \"a-b\"", etag));
+        assert!(!etag_matches(b"\"c-d\"", etag));
+        assert!(!etag_matches(b"a-b", etag));
+
+        let weak_etag = "W/\"a-b\"";
+        assert!(etag_matches(b"\"a-b\"", weak_etag));
+        assert!(etag_matches(b"W/\"a-b\"", weak_etag));
+        assert!(!etag_matches(b"W/\"c-d\"", weak_etag));
+    }
+}
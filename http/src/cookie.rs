@@ -0,0 +1,569 @@
+//! Module with types to support HTTP cookies.
+//!
+//! Cookies are defined in [RFC 6265]. The server sets cookies using the
+//! `Set-Cookie` header in the response, which is represented by [`Cookie`].
+//! The client sends the cookies back using the `Cookie` header in future
+//! requests, which can be parsed using [`Cookies`].
+//!
+//! For clients the [`CookieJar`] can be used to store the cookies set by the
+//! server and create the `Cookie` header for the next request.
+//!
+//! [RFC 6265]: https://datatracker.ietf.org/doc/html/rfc6265
+//!
+//! # Examples
+//!
+//! Reading and setting cookies in a handler.
+//!
+//! ```
+//! # #![allow(dead_code)]
+//! use std::time::Duration;
+//!
+//! use heph_http::body::OneshotBody;
+//! use heph_http::cookie::{Cookie, Cookies, SameSite};
+//! use heph_http::{Header, HeaderName, Request, Response};
+//!
+//! async fn handler<B>(request: Request<B>) -> Response<OneshotBody<&'static str>> {
+//!     let session = request
+//!         .headers()
+//!         .get_all(&HeaderName::COOKIE)
+//!         .filter_map(|header| header.parse::<Cookies<'_>>().ok())
+//!         .flatten()
+//!         .find_map(|(name, value)| (name == "session").then_some(value));
+//!     if session.is_some() {
+//!         return Response::ok().with_body(OneshotBody::new("Welcome back"));
+//!     }
+//!
+//!     let cookie = Cookie::new("session", "abc123")
+//!         .with_max_age(Duration::from_secs(60 * 60))
+//!         .with_path("/")
+//!         .with_secure(true)
+//!         .with_http_only(true)
+//!         .with_same_site(SameSite::Lax);
+//!     let mut response = Response::ok().with_body(OneshotBody::new("Hello"));
+//!     let set_cookie = cookie.to_string();
+//!     response
+//!         .headers_mut()
+//!         .append(Header::new(HeaderName::SET_COOKIE, set_cookie.as_bytes()));
+//!     response
+//! }
+//! ```
+
+use std::error::Error;
+use std::time::{Duration, SystemTime};
+use std::{fmt, str};
+
+use httpdate::{fmt_http_date, parse_http_date};
+
+use crate::head::header::FromHeaderValue;
+use crate::{is_token, HeaderName, Headers};
+
+/// HTTP cookie, as set using the `Set-Cookie` header.
+///
+/// The [`fmt::Display`] implementation formats the cookie as the value of a
+/// `Set-Cookie` header and the [`FromHeaderValue`] implementation parses it.
+///
+/// RFC 6265 section 4.1.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    expires: Option<SystemTime>,
+    max_age: Option<Duration>,
+    domain: Option<String>,
+    path: Option<String>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+}
+
+impl Cookie {
+    /// Create a new cookie with `name` and `value`, without any attributes.
+    ///
+    /// # Notes
+    ///
+    /// `name` MUST be a token, i.e. not contain any separators such as `=` or
+    /// `;`, and `value` MUST NOT contain whitespace, `"`, `,`, `;` or `\`.
+    pub fn new<N, V>(name: N, value: V) -> Cookie
+    where
+        N: Into<String>,
+        V: Into<String>,
+    {
+        let name = name.into();
+        let value = value.into();
+        debug_assert!(is_token(&name), "invalid cookie name");
+        debug_assert!(is_cookie_value(&value), "invalid cookie value");
+        Cookie {
+            name,
+            value,
+            expires: None,
+            max_age: None,
+            domain: None,
+            path: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+        }
+    }
+
+    /// Returns the name of the cookie.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the value of the cookie.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Returns the `Expires` attribute, if set.
+    pub const fn expires(&self) -> Option<SystemTime> {
+        self.expires
+    }
+
+    /// Set the `Expires` attribute.
+    pub const fn with_expires(mut self, expires: SystemTime) -> Self {
+        self.expires = Some(expires);
+        self
+    }
+
+    /// Returns the `Max-Age` attribute, if set.
+    ///
+    /// A non-positive `Max-Age` is returned as [`Duration::ZERO`].
+    pub const fn max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+
+    /// Set the `Max-Age` attribute.
+    ///
+    /// A `max_age` of zero instructs the client to remove the cookie.
+    pub const fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Returns the `Domain` attribute, if set.
+    pub fn domain(&self) -> Option<&str> {
+        self.domain.as_deref()
+    }
+
+    /// Set the `Domain` attribute.
+    pub fn with_domain<D>(mut self, domain: D) -> Self
+    where
+        D: Into<String>,
+    {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    /// Returns the `Path` attribute, if set.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Set the `Path` attribute.
+    pub fn with_path<P>(mut self, path: P) -> Self
+    where
+        P: Into<String>,
+    {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Returns `true` if the `Secure` attribute is set.
+    pub const fn secure(&self) -> bool {
+        self.secure
+    }
+
+    /// Set the `Secure` attribute, limiting the cookie to secure connections.
+    pub const fn with_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Returns `true` if the `HttpOnly` attribute is set.
+    pub const fn http_only(&self) -> bool {
+        self.http_only
+    }
+
+    /// Set the `HttpOnly` attribute, hiding the cookie from scripts in
+    /// browsers.
+    pub const fn with_http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self
+    }
+
+    /// Returns the `SameSite` attribute, if set.
+    pub const fn same_site(&self) -> Option<SameSite> {
+        self.same_site
+    }
+
+    /// Set the `SameSite` attribute.
+    pub const fn with_same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+
+    /// Returns the time at which the cookie expires, if any, when it was
+    /// received at `received`.
+    ///
+    /// `Max-Age` takes precedence over `Expires`, following RFC 6265 section
+    /// 5.3.
+    fn expiry(&self, received: SystemTime) -> Option<SystemTime> {
+        match self.max_age {
+            Some(max_age) => received.checked_add(max_age),
+            None => self.expires,
+        }
+    }
+}
+
+impl fmt::Display for Cookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+        if let Some(expires) = self.expires {
+            write!(f, "; Expires={}", fmt_http_date(expires))?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age.as_secs())?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={domain}")?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "; Path={path}")?;
+        }
+        if self.secure {
+            f.write_str("; Secure")?;
+        }
+        if self.http_only {
+            f.write_str("; HttpOnly")?;
+        }
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={same_site}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the value of a `Set-Cookie` header following RFC 6265 section 5.2.
+///
+/// Unknown attributes and attributes with an invalid value are ignored.
+impl FromHeaderValue<'_> for Cookie {
+    type Err = ParseCookieError;
+
+    fn from_bytes(value: &[u8]) -> Result<Self, Self::Err> {
+        let value = str::from_utf8(value).map_err(|_| ParseCookieError::InvalidUtf8)?;
+        let mut parts = value.split(';');
+        // NOTE: `split` always returns at least one item.
+        let (name, value) = parse_pair(parts.next().unwrap_or(""))?;
+        let mut cookie = Cookie {
+            name: name.to_owned(),
+            value: value.to_owned(),
+            expires: None,
+            max_age: None,
+            domain: None,
+            path: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+        };
+
+        for attribute in parts {
+            let (name, value) = match attribute.split_once('=') {
+                Some((name, value)) => (name.trim(), value.trim()),
+                None => (attribute.trim(), ""),
+            };
+            if name.eq_ignore_ascii_case("expires") {
+                if let Ok(expires) = parse_http_date(value) {
+                    cookie.expires = Some(expires);
+                }
+            } else if name.eq_ignore_ascii_case("max-age") {
+                if let Some(max_age) = parse_max_age(value) {
+                    cookie.max_age = Some(max_age);
+                }
+            } else if name.eq_ignore_ascii_case("domain") {
+                // A leading dot is ignored.
+                let domain = value.strip_prefix('.').unwrap_or(value);
+                if !domain.is_empty() {
+                    cookie.domain = Some(domain.to_ascii_lowercase());
+                }
+            } else if name.eq_ignore_ascii_case("path") {
+                // Paths not starting with a slash use the default path.
+                if value.starts_with('/') {
+                    cookie.path = Some(value.to_owned());
+                }
+            } else if name.eq_ignore_ascii_case("secure") {
+                cookie.secure = true;
+            } else if name.eq_ignore_ascii_case("httponly") {
+                cookie.http_only = true;
+            } else if name.eq_ignore_ascii_case("samesite") {
+                if let Some(same_site) = SameSite::parse(value) {
+                    cookie.same_site = Some(same_site);
+                }
+            }
+        }
+        Ok(cookie)
+    }
+}
+
+/// Parse the name-value pair of a cookie.
+fn parse_pair(pair: &str) -> Result<(&str, &str), ParseCookieError> {
+    let (name, value) = pair.split_once('=').ok_or(ParseCookieError::MissingValue)?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ParseCookieError::EmptyName);
+    }
+    let value = value.trim();
+    // The value may be surrounded by double quotes, which are not part of the
+    // value.
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value);
+    Ok((name, value))
+}
+
+/// Parse the value of the `Max-Age` attribute, RFC 6265 section 5.2.2.
+fn parse_max_age(value: &str) -> Option<Duration> {
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, value),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    if negative {
+        return Some(Duration::ZERO);
+    }
+    // Values too large are capped, as allowed by RFC 6265 section 4.1.2.2.
+    Some(Duration::from_secs(digits.parse().unwrap_or(u64::MAX)))
+}
+
+/// Value of the `SameSite` attribute of a [`Cookie`].
+///
+/// See the [draft RFC] that defines it for more information.
+///
+/// [draft RFC]: https://datatracker.ietf.org/doc/html/draft-ietf-httpbis-rfc6265bis
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SameSite {
+    /// Only send the cookie in a first-party context.
+    Strict,
+    /// Also send the cookie when navigating to the site from another site.
+    Lax,
+    /// Send the cookie in all contexts, requires the `Secure` attribute.
+    None,
+}
+
+impl SameSite {
+    /// Parse the value of the attribute, ignoring case.
+    fn parse(value: &str) -> Option<SameSite> {
+        if value.eq_ignore_ascii_case("strict") {
+            Some(SameSite::Strict)
+        } else if value.eq_ignore_ascii_case("lax") {
+            Some(SameSite::Lax)
+        } else if value.eq_ignore_ascii_case("none") {
+            Some(SameSite::None)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for SameSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        })
+    }
+}
+
+/// Error returned by the [`FromHeaderValue`] implementation for [`Cookie`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ParseCookieError {
+    /// Header value is not valid UTF-8.
+    InvalidUtf8,
+    /// Missing the `=` separating the name and value.
+    MissingValue,
+    /// Name of the cookie is empty.
+    EmptyName,
+}
+
+impl fmt::Display for ParseCookieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ParseCookieError::InvalidUtf8 => "invalid UTF-8 in cookie",
+            ParseCookieError::MissingValue => "missing cookie value",
+            ParseCookieError::EmptyName => "empty cookie name",
+        })
+    }
+}
+
+impl Error for ParseCookieError {}
+
+/// Iterator over the name-value pairs in a `Cookie` header.
+///
+/// Pairs that are invalid, e.g. missing the `=` separator, are skipped.
+///
+/// RFC 6265 section 4.2.
+#[derive(Clone, Debug)]
+pub struct Cookies<'a> {
+    parts: str::Split<'a, char>,
+}
+
+impl<'a> Cookies<'a> {
+    /// Parse the cookie pairs in `value`, the value of a `Cookie` header.
+    pub fn new(value: &'a str) -> Cookies<'a> {
+        Cookies {
+            parts: value.split(';'),
+        }
+    }
+}
+
+impl<'a> Iterator for Cookies<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.parts.by_ref().find_map(|pair| parse_pair(pair).ok())
+    }
+}
+
+impl<'a> FromHeaderValue<'a> for Cookies<'a> {
+    type Err = str::Utf8Error;
+
+    fn from_bytes(value: &'a [u8]) -> Result<Self, Self::Err> {
+        str::from_utf8(value).map(Cookies::new)
+    }
+}
+
+/// Collection of cookies, for use by a client.
+///
+/// Use [`CookieJar::store`] to store the cookies set by the server in a
+/// response and [`CookieJar::to_header_value`] to create the value of the
+/// `Cookie` header for the next request.
+///
+/// # Notes
+///
+/// The jar doesn't match cookies based on the `Domain`, `Path` or `Secure`
+/// attributes, it assumes all cookies are for a single origin. Use a jar per
+/// origin if this is not the case.
+#[derive(Clone, Debug, Default)]
+pub struct CookieJar {
+    entries: Vec<Entry>,
+}
+
+/// Cookie in a [`CookieJar`].
+#[derive(Clone, Debug)]
+struct Entry {
+    cookie: Cookie,
+    /// Time at which the cookie expires, or `None` for a session cookie.
+    expiry: Option<SystemTime>,
+}
+
+impl Entry {
+    /// Returns `true` if the cookie is expired at time `now`.
+    fn is_expired(&self, now: SystemTime) -> bool {
+        self.expiry.is_some_and(|expiry| expiry <= now)
+    }
+}
+
+impl CookieJar {
+    /// Create an empty cookie jar.
+    pub const fn new() -> CookieJar {
+        CookieJar {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Add `cookie`, replacing any existing cookie with the same name.
+    ///
+    /// If the cookie is already expired, e.g. has a `Max-Age` of zero, the
+    /// existing cookie is removed and `cookie` is not added.
+    pub fn add(&mut self, cookie: Cookie) {
+        let now = SystemTime::now();
+        self.entries.retain(|e| e.cookie.name != cookie.name);
+        let entry = Entry {
+            expiry: cookie.expiry(now),
+            cookie,
+        };
+        if !entry.is_expired(now) {
+            self.entries.push(entry);
+        }
+    }
+
+    /// Store all cookies set in the `Set-Cookie` headers in `headers`, e.g.
+    /// the headers of a response.
+    ///
+    /// Invalid `Set-Cookie` headers are ignored.
+    pub fn store(&mut self, headers: &Headers) {
+        for header in headers.get_all(&HeaderName::SET_COOKIE) {
+            if let Ok(cookie) = header.parse::<Cookie>() {
+                self.add(cookie);
+            }
+        }
+    }
+
+    /// Returns the cookie with `name`, if any and not expired.
+    pub fn get(&self, name: &str) -> Option<&Cookie> {
+        let now = SystemTime::now();
+        self.entries
+            .iter()
+            .find(|e| e.cookie.name == name && !e.is_expired(now))
+            .map(|e| &e.cookie)
+    }
+
+    /// Remove the cookie with `name`, returning it if present.
+    pub fn remove(&mut self, name: &str) -> Option<Cookie> {
+        let index = self.entries.iter().position(|e| e.cookie.name == name)?;
+        Some(self.entries.remove(index).cookie)
+    }
+
+    /// Remove all expired cookies.
+    pub fn remove_expired(&mut self) {
+        let now = SystemTime::now();
+        self.entries.retain(|e| !e.is_expired(now));
+    }
+
+    /// Returns the number of cookies in the jar, including expired cookies
+    /// that haven't been removed yet.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the jar contains no cookies.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns an iterator over all cookies that are not expired.
+    pub fn iter(&self) -> impl Iterator<Item = &Cookie> {
+        let now = SystemTime::now();
+        self.entries
+            .iter()
+            .filter(move |e| !e.is_expired(now))
+            .map(|e| &e.cookie)
+    }
+
+    /// Returns the value for the `Cookie` header containing all cookies that
+    /// are not expired, or `None` if there are no such cookies.
+    pub fn to_header_value(&self) -> Option<String> {
+        let mut value = String::new();
+        for cookie in self.iter() {
+            if !value.is_empty() {
+                value.push_str("; ");
+            }
+            value.push_str(&cookie.name);
+            value.push('=');
+            value.push_str(&cookie.value);
+        }
+        (!value.is_empty()).then_some(value)
+    }
+}
+
+/// Returns `true` if `value` only contains `cookie-octet`s, RFC 6265 section
+/// 4.1.1.
+fn is_cookie_value(value: &str) -> bool {
+    value
+        .bytes()
+        .all(|b| b.is_ascii_graphic() && !matches!(b, b'"' | b',' | b';' | b'\\'))
+}
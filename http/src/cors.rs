@@ -0,0 +1,406 @@
+//! Module with the [`Cors`] middleware.
+//!
+//! [Cross-Origin Resource Sharing] (CORS) allows browsers to make requests to
+//! a different origin (domain, scheme or port) than the one that served the web
+//! page. The [`Cors`] middleware adds the required headers to responses and
+//! answers preflight requests, i.e. `OPTIONS` requests with an
+//! `Access-Control-Request-Method` header, without calling the wrapped
+//! handler.
+//!
+//! CORS is configured per route using [`CorsConfig`]. The methods of the route
+//! are registered along side the configuration and are used to answer the
+//! preflight requests, these should match the methods used in the router, e.g.
+//! the [`route!`] macro.
+//!
+//! [Cross-Origin Resource Sharing]: https://fetch.spec.whatwg.org/#http-cors-protocol
+//! [`route!`]: crate::route
+//!
+//! # Examples
+//!
+//! ```
+//! # #![allow(dead_code)]
+//! use std::time::Duration;
+//!
+//! use heph_http::body::OneshotBody;
+//! use heph_http::cors::{Cors, CorsBody, CorsConfig};
+//! use heph_http::handler::Handler;
+//! use heph_http::{route, HeaderName, Method, Request, Response};
+//!
+//! async fn router<B>(request: Request<B>) -> Response<OneshotBody<&'static str>> {
+//!     route!(match request {
+//!         GET | HEAD "/pets" => list_pets,
+//!         POST       "/pets" => create_pet,
+//!         _ => not_found,
+//!     })
+//! }
+//! # async fn list_pets<B>(_: Request<B>) -> Response<OneshotBody<&'static str>> { todo!() }
+//! # async fn create_pet<B>(_: Request<B>) -> Response<OneshotBody<&'static str>> { todo!() }
+//! # async fn not_found<B>(_: Request<B>) -> Response<OneshotBody<&'static str>> { todo!() }
+//!
+//! async fn handle<B>(request: Request<B>) -> Response<CorsBody<OneshotBody<&'static str>>> {
+//!     // NOTE: in practice the middleware should be created once and reused.
+//!     let config = CorsConfig::new()
+//!         .with_allowed_origin("https://example.com")
+//!         .with_allowed_header(HeaderName::CONTENT_TYPE)
+//!         .with_max_age(Duration::from_secs(60 * 60));
+//!     let handler = Cors::new(router)
+//!         .with_route("/pets", &[Method::Get, Method::Head, Method::Post], config);
+//!     handler.handle((request,)).await
+//! }
+//! ```
+
+use std::future::Future;
+use std::io;
+use std::time::Duration;
+
+use heph_rt::net::TcpStream;
+
+use crate::body::{BodyLength, EmptyBody, PrivateBody};
+use crate::handler::{Handler, Middleware};
+use crate::{Body, Header, HeaderName, Headers, Method, Request, Response, StatusCode};
+
+/// [`Middleware`] that implements Cross-Origin Resource Sharing (CORS).
+///
+/// See the [module documentation] for more information and an example.
+///
+/// [module documentation]: crate::cors
+#[derive(Debug)]
+pub struct Cors<H> {
+    handler: H,
+    routes: Vec<CorsRoute>,
+    default: Option<CorsConfig>,
+}
+
+/// Route registered with [`Cors::with_route`].
+#[derive(Debug)]
+struct CorsRoute {
+    path: String,
+    methods: Vec<Method>,
+    config: CorsConfig,
+}
+
+impl<H> Cors<H> {
+    /// Create new CORS middleware wrapping `handler`.
+    ///
+    /// By default no routes are configured, meaning that no CORS headers are
+    /// added and all requests are passed to `handler` as is. Use
+    /// [`Cors::with_route`] and [`Cors::with_default`] to configure it.
+    pub const fn new(handler: H) -> Cors<H> {
+        Cors {
+            handler,
+            routes: Vec::new(),
+            default: None,
+        }
+    }
+
+    /// Enable CORS for requests to `path`, which is routed for `methods`.
+    ///
+    /// `methods` are used in answering preflight requests, limited by
+    /// [`CorsConfig::with_allowed_methods`] if set.
+    pub fn with_route(mut self, path: &str, methods: &[Method], config: CorsConfig) -> Self {
+        self.routes.push(CorsRoute {
+            path: path.to_owned(),
+            methods: methods.to_vec(),
+            config,
+        });
+        self
+    }
+
+    /// Enable CORS for all requests to paths not registered using
+    /// [`Cors::with_route`].
+    ///
+    /// Because the methods of these routes are unknown preflight requests are
+    /// answered using the methods set in [`CorsConfig::with_allowed_methods`],
+    /// or all safe methods (`GET` and `HEAD`) if not set.
+    pub fn with_default(mut self, config: CorsConfig) -> Self {
+        self.default = Some(config);
+        self
+    }
+
+    /// Returns the methods and configuration for `path`, if any.
+    fn route(&self, path: &str) -> Option<(&[Method], &CorsConfig)> {
+        /// Methods used for routes with unknown methods.
+        const SAFE_METHODS: &[Method] = &[Method::Get, Method::Head];
+        match self.routes.iter().find(|route| route.path == path) {
+            Some(route) => Some((&route.methods, &route.config)),
+            None => self.default.as_ref().map(|config| {
+                let methods = config.methods.as_deref().unwrap_or(SAFE_METHODS);
+                (methods, config)
+            }),
+        }
+    }
+}
+
+impl<H, B, RB> Handler<(Request<B>,)> for Cors<H>
+where
+    H: Handler<(Request<B>,), Response = Response<RB>>,
+    RB: Body + 'static,
+{
+    type Response = Response<CorsBody<RB>>;
+    type Future = impl Future<Output = Self::Response>;
+
+    fn handle(&self, (request,): (Request<B>,)) -> Self::Future {
+        let origin = request.headers().get_bytes(&HeaderName::ORIGIN);
+        let route = origin.and_then(|_| self.route(request.path()));
+        let preflight = route.is_some()
+            && matches!(request.method(), Method::Options)
+            && request
+                .headers()
+                .get(&HeaderName::ACCESS_CONTROL_REQUEST_METHOD)
+                .is_some();
+
+        // Determine the CORS headers before passing the request to the handler.
+        let mut headers = Headers::EMPTY;
+        if let (Some(origin), Some((methods, config))) = (origin, route) {
+            if preflight {
+                config.preflight_headers(&mut headers, origin, methods);
+            } else {
+                config.response_headers(&mut headers, origin);
+            }
+        }
+
+        let future = if preflight {
+            None
+        } else {
+            Some(self.handler.handle((request,)))
+        };
+        async move {
+            let Some(future) = future else {
+                let mut response = Response::build_new(StatusCode::NO_CONTENT);
+                *response.headers_mut() = headers;
+                return response.with_body(CorsBody::Empty);
+            };
+            let mut response = future.await;
+            response.headers_mut().append_all(&headers);
+            response.map_body(CorsBody::Body)
+        }
+    }
+}
+
+impl<H, Req> Middleware<H, Req> for Cors<H>
+where
+    Cors<H>: Handler<Req>,
+{
+    fn wrap(handler: H) -> Self {
+        Cors::new(handler)
+    }
+}
+
+/// CORS configuration for a route, see [`Cors::with_route`].
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct CorsConfig {
+    /// Allowed origins, empty means any origin is allowed.
+    origins: Vec<String>,
+    /// Allowed methods, `None` means all methods of the route are allowed.
+    methods: Option<Vec<Method>>,
+    /// Allowed request headers.
+    headers: Vec<HeaderName<'static>>,
+    /// Response headers exposed to the browser.
+    expose_headers: Vec<HeaderName<'static>>,
+    /// Maximum duration to cache the preflight response.
+    max_age: Option<Duration>,
+    /// Whether or not to allow credentials, e.g. cookies.
+    credentials: bool,
+}
+
+impl CorsConfig {
+    /// Create a new configuration that allows any origin to use all methods
+    /// of the route, without credentials.
+    pub const fn new() -> CorsConfig {
+        CorsConfig {
+            origins: Vec::new(),
+            methods: None,
+            headers: Vec::new(),
+            expose_headers: Vec::new(),
+            max_age: None,
+            credentials: false,
+        }
+    }
+
+    /// Only allow requests from `origin`, e.g. `https://example.com`.
+    ///
+    /// Can be called multiple times to allow multiple origins.
+    pub fn with_allowed_origin(mut self, origin: &str) -> Self {
+        self.origins.push(origin.to_owned());
+        self
+    }
+
+    /// Only allow `methods`, instead of all methods of the route.
+    pub fn with_allowed_methods(mut self, methods: &[Method]) -> Self {
+        self.methods = Some(methods.to_vec());
+        self
+    }
+
+    /// Allow the request header `name`.
+    pub fn with_allowed_header(mut self, name: HeaderName<'static>) -> Self {
+        self.headers.push(name);
+        self
+    }
+
+    /// Expose the response header `name` to the browser.
+    pub fn with_exposed_header(mut self, name: HeaderName<'static>) -> Self {
+        self.expose_headers.push(name);
+        self
+    }
+
+    /// Allow browsers to cache the preflight response for `max_age`.
+    pub const fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Allow credentials, e.g. cookies, to be send with the requests.
+    ///
+    /// # Notes
+    ///
+    /// When credentials are allowed the origin of the request is returned,
+    /// rather than the `*` wildcard, as browsers don't accept the wildcard in
+    /// combination with credentials.
+    pub const fn with_credentials(mut self, credentials: bool) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    /// Returns `true` if `origin` is allowed.
+    fn allows_origin(&self, origin: &[u8]) -> bool {
+        self.origins.is_empty()
+            || (self.origins.iter()).any(|o| o.as_bytes().eq_ignore_ascii_case(origin))
+    }
+
+    /// Add the headers for a preflight request to `headers`.
+    fn preflight_headers(&self, headers: &mut Headers, origin: &[u8], methods: &[Method]) {
+        if !self.add_origin(headers, origin) {
+            return;
+        }
+
+        let allowed = methods
+            .iter()
+            .filter(|m| (self.methods.as_ref()).is_none_or(|allowed| allowed.contains(m)));
+        let methods = join(allowed.map(Method::as_str));
+        headers.append(Header::new(
+            HeaderName::ACCESS_CONTROL_ALLOW_METHODS,
+            methods.as_bytes(),
+        ));
+        if !self.headers.is_empty() {
+            let value = join(self.headers.iter().map(AsRef::as_ref));
+            headers.append(Header::new(
+                HeaderName::ACCESS_CONTROL_ALLOW_HEADERS,
+                value.as_bytes(),
+            ));
+        }
+        if let Some(max_age) = self.max_age {
+            let value = max_age.as_secs().to_string();
+            headers.append(Header::new(
+                HeaderName::ACCESS_CONTROL_MAX_AGE,
+                value.as_bytes(),
+            ));
+        }
+    }
+
+    /// Add the headers for a non-preflight request to `headers`.
+    fn response_headers(&self, headers: &mut Headers, origin: &[u8]) {
+        if !self.add_origin(headers, origin) {
+            return;
+        }
+
+        if !self.expose_headers.is_empty() {
+            let value = join(self.expose_headers.iter().map(AsRef::as_ref));
+            headers.append(Header::new(
+                HeaderName::ACCESS_CONTROL_EXPOSE_HEADERS,
+                value.as_bytes(),
+            ));
+        }
+    }
+
+    /// Add the `Access-Control-Allow-Origin` and related headers to `headers`.
+    ///
+    /// Returns `false` if `origin` is not allowed, in which case no headers
+    /// are added.
+    fn add_origin(&self, headers: &mut Headers, origin: &[u8]) -> bool {
+        if !self.allows_origin(origin) {
+            return false;
+        }
+
+        if self.origins.is_empty() && !self.credentials {
+            headers.append(Header::new(HeaderName::ACCESS_CONTROL_ALLOW_ORIGIN, b"*"));
+        } else {
+            headers.append(Header::new(HeaderName::ACCESS_CONTROL_ALLOW_ORIGIN, origin));
+            // The response depends on the origin of the request.
+            headers.append(Header::new(HeaderName::VARY, b"Origin"));
+        }
+        if self.credentials {
+            headers.append(Header::new(
+                HeaderName::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                b"true",
+            ));
+        }
+        true
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> CorsConfig {
+        CorsConfig::new()
+    }
+}
+
+/// Join `values` with `, `.
+fn join<'a, I>(values: I) -> String
+where
+    I: Iterator<Item = &'a str>,
+{
+    let mut output = String::new();
+    for value in values {
+        if !output.is_empty() {
+            output.push_str(", ");
+        }
+        output.push_str(value);
+    }
+    output
+}
+
+/// Body returned by [`Cors`].
+#[derive(Debug)]
+pub enum CorsBody<B> {
+    /// Empty body, used in response to preflight requests.
+    Empty,
+    /// Body returned by the wrapped handler.
+    Body(B),
+}
+
+impl<B> CorsBody<B> {
+    /// Returns the body returned by the wrapped handler, if any.
+    pub fn into_inner(self) -> Option<B> {
+        match self {
+            CorsBody::Empty => None,
+            CorsBody::Body(body) => Some(body),
+        }
+    }
+}
+
+impl<B: Body + 'static> Body for CorsBody<B> {
+    fn length(&self) -> BodyLength {
+        match self {
+            CorsBody::Empty => EmptyBody.length(),
+            CorsBody::Body(body) => body.length(),
+        }
+    }
+}
+
+impl<B: Body + 'static> PrivateBody for CorsBody<B> {
+    type WriteFuture<'stream> = impl Future<Output = io::Result<Vec<u8>>> + 'stream;
+
+    fn write_message<'stream>(
+        self,
+        stream: &'stream mut TcpStream,
+        http_head: Vec<u8>,
+    ) -> Self::WriteFuture<'stream> {
+        async move {
+            match self {
+                CorsBody::Empty => EmptyBody.write_message(stream, http_head).await,
+                CorsBody::Body(body) => body.write_message(stream, http_head).await,
+            }
+        }
+    }
+}
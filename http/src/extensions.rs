@@ -0,0 +1,169 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Type map of extensions.
+///
+/// This can be used to attach values to a [`Request`], [`Response`] or
+/// [`Connection`], for example an authenticated user or a request id, which
+/// can be retrieved by later handlers in a type-safe way. At most one value
+/// per type can be stored.
+///
+/// [`Request`]: crate::Request
+/// [`Response`]: crate::Response
+/// [`Connection`]: crate::server::Connection
+///
+/// # Examples
+///
+/// ```
+/// use heph_http::Extensions;
+///
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct RequestId(u64);
+///
+/// let mut extensions = Extensions::new();
+/// assert!(extensions.insert(RequestId(1)).is_none());
+/// assert_eq!(extensions.get::<RequestId>(), Some(&RequestId(1)));
+/// // Only a single value per type can be stored.
+/// assert_eq!(extensions.insert(RequestId(2)), Some(RequestId(1)));
+/// assert_eq!(extensions.remove::<RequestId>(), Some(RequestId(2)));
+/// assert!(extensions.is_empty());
+/// ```
+#[derive(Default)]
+pub struct Extensions {
+    /// Lazily allocated as most requests don't use extensions, boxed to keep
+    /// the size of the request and response heads small.
+    #[allow(clippy::box_collection)]
+    map: Option<Box<HashMap<TypeId, Box<dyn Extension>>>>,
+}
+
+impl Extensions {
+    /// Create an empty set of extensions.
+    pub const fn new() -> Extensions {
+        Extensions { map: None }
+    }
+
+    /// Insert `value`, returning the previous value of the same type (if any).
+    pub fn insert<T>(&mut self, value: T) -> Option<T>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        self.map
+            .get_or_insert_with(Box::default)
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .map(|old| *downcast(old))
+    }
+
+    /// Returns a reference to the value of type `T`, if any.
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.map
+            .as_ref()?
+            .get(&TypeId::of::<T>())
+            .and_then(|value| (**value).as_any().downcast_ref())
+    }
+
+    /// Returns a mutable reference to the value of type `T`, if any.
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.map
+            .as_mut()?
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| (**value).as_any_mut().downcast_mut())
+    }
+
+    /// Returns `true` if a value of type `T` is present.
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.map
+            .as_ref()
+            .is_some_and(|map| map.contains_key(&TypeId::of::<T>()))
+    }
+
+    /// Remove the value of type `T`, returning it (if any).
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        self.map
+            .as_mut()?
+            .remove(&TypeId::of::<T>())
+            .map(|value| *downcast(value))
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.map.as_ref().map_or(0, |map| map.len())
+    }
+
+    /// Returns `true` if there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all values.
+    pub fn clear(&mut self) {
+        if let Some(map) = self.map.as_mut() {
+            map.clear();
+        }
+    }
+}
+
+impl Clone for Extensions {
+    fn clone(&self) -> Extensions {
+        let map = self.map.as_ref().map(|map| {
+            let map = map
+                .iter()
+                .map(|(type_id, value)| (*type_id, (**value).clone_box()))
+                .collect();
+            Box::new(map)
+        });
+        Extensions { map }
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Value stored in [`Extensions`].
+///
+/// # Notes
+///
+/// As this is implemented for all `Clone` types, including references to
+/// `Box<dyn Extension>`, always call the methods on `dyn Extension` directly.
+trait Extension: Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+    fn clone_box(&self) -> Box<dyn Extension>;
+}
+
+impl<T> Extension for T
+where
+    T: Clone + Send + Sync + 'static,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn Extension> {
+        Box::new(self.clone())
+    }
+}
+
+/// Downcast `value` to `T`.
+///
+/// # Panics
+///
+/// Panics if `value` is not of type `T`, which can't happen as the values are
+/// keyed by their type id.
+fn downcast<T: 'static>(value: Box<dyn Extension>) -> Box<T> {
+    value.into_any().downcast().unwrap()
+}
@@ -103,6 +103,15 @@ impl Headers {
         self._append(header.name, header.value);
     }
 
+    /// Append all headers in `other`.
+    pub(crate) fn append_all(&mut self, other: &Headers) {
+        self.values.reserve(other.values.len());
+        self.parts.reserve(other.parts.len());
+        for part in &other.parts {
+            self._append(part.name.clone(), &other.values[part.start..part.end]);
+        }
+    }
+
     fn _append(&mut self, name: HeaderName<'static>, value: &[u8]) {
         let start = self.values.len();
         self.values.extend_from_slice(value);
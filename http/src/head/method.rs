@@ -1,12 +1,15 @@
 //! Method related types.
 
 use std::fmt;
-use std::str::FromStr;
+use std::str::{self, FromStr};
 
-use crate::cmp_lower_case;
+use crate::{cmp_lower_case, is_token};
 
 /// HTTP method.
 ///
+/// Methods not defined in RFC 9110 (or RFC 5789 for PATCH), e.g. the WebDAV
+/// methods, are represented by [`Method::Extension`].
+///
 /// RFC 9110 section 9.3
 #[non_exhaustive]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -47,22 +50,46 @@ pub enum Method {
     ///
     /// RFC 5789.
     Patch,
+    /// Extension method, e.g. the WebDAV PROPFIND method.
+    ///
+    /// RFC 9110 section 16.1.
+    Extension(ExtensionMethod),
 }
 
 impl Method {
     /// Returns `true` if the method is safe.
     ///
     /// RFC 9110 section 9.2.1.
+    ///
+    /// For extension methods see [`ExtensionMethod::is_safe`].
     #[rustfmt::skip]
     pub const fn is_safe(self) -> bool {
-        matches!(self, Method::Get | Method::Head | Method::Options | Method::Trace)
+        match self {
+            Method::Extension(method) => method.is_safe(),
+            _ => matches!(self, Method::Get | Method::Head | Method::Options | Method::Trace),
+        }
     }
 
     /// Returns `true` if the method is idempotent.
     ///
     /// RFC 9110 section 9.2.2.
+    ///
+    /// For extension methods see [`ExtensionMethod::is_idempotent`].
     pub const fn is_idempotent(self) -> bool {
-        matches!(self, Method::Put | Method::Delete) || self.is_safe()
+        match self {
+            Method::Extension(method) => method.is_idempotent(),
+            _ => matches!(self, Method::Put | Method::Delete) || self.is_safe(),
+        }
+    }
+
+    /// Returns `true` if the method is HEAD.
+    ///
+    /// A response to a HEAD request is identical to a response to a GET
+    /// request, but without the body.
+    ///
+    /// RFC 9110 section 9.3.2.
+    pub const fn is_head(self) -> bool {
+        matches!(self, Method::Head)
     }
 
     /// Returns `false` if a response to this method MUST NOT include a body.
@@ -71,11 +98,11 @@ impl Method {
     ///
     /// RFC 9110 section 6.4.1.
     pub const fn expects_body(self) -> bool {
-        !matches!(self, Method::Head)
+        !self.is_head()
     }
 
     /// Returns the method as string.
-    pub const fn as_str(self) -> &'static str {
+    pub fn as_str(&self) -> &str {
         match self {
             Method::Options => "OPTIONS",
             Method::Get => "GET",
@@ -86,6 +113,7 @@ impl Method {
             Method::Trace => "TRACE",
             Method::Connect => "CONNECT",
             Method::Patch => "PATCH",
+            Method::Extension(method) => method.as_str(),
         }
     }
 }
@@ -146,12 +174,15 @@ impl FromStr for Method {
             }
             _ => {}
         }
-        Err(UnknownMethod)
+        method.parse().map(Method::Extension)
     }
 }
 
 impl PartialEq<str> for Method {
     fn eq(&self, other: &str) -> bool {
+        if let Method::Extension(method) = self {
+            return method.as_str().eq_ignore_ascii_case(other);
+        }
         match other.len() {
             3 => {
                 if let Method::Get = self {
@@ -200,3 +231,147 @@ impl PartialEq<str> for Method {
         }
     }
 }
+
+/// Maximum length of an [`ExtensionMethod`].
+pub const MAX_EXTENSION_METHOD_LEN: usize = 22;
+
+/// Extension HTTP method, i.e. a method not defined in RFC 9110, see
+/// [`Method::Extension`].
+///
+/// The method must be a token, i.e. only contain letters, digits and the
+/// characters ``!#$%&'*+-.^_`|~``, of at most [`MAX_EXTENSION_METHOD_LEN`]
+/// bytes. Unlike the methods defined in RFC 9110, which are parsed ignoring
+/// case, the case of an extension method is kept as is.
+///
+/// RFC 9110 section 16.1.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct ExtensionMethod {
+    len: u8,
+    buf: [u8; MAX_EXTENSION_METHOD_LEN],
+}
+
+/// Extension methods in the [IANA HTTP Method Registry], with whether or not
+/// they're safe and idempotent.
+///
+/// Names must be in lower case.
+///
+/// [IANA HTTP Method Registry]: https://www.iana.org/assignments/http-methods/http-methods.xhtml
+#[rustfmt::skip]
+const REGISTERED_METHODS: [(&str, bool, bool); 31] = [
+    ("acl", false, true),
+    ("baseline-control", false, true),
+    ("bind", false, true),
+    ("checkin", false, true),
+    ("checkout", false, true),
+    ("copy", false, true),
+    ("label", false, true),
+    ("link", false, true),
+    ("lock", false, false),
+    ("merge", false, true),
+    ("mkactivity", false, true),
+    ("mkcalendar", false, true),
+    ("mkcol", false, true),
+    ("mkredirectref", false, true),
+    ("mkworkspace", false, true),
+    ("move", false, true),
+    ("orderpatch", false, true),
+    ("pri", true, true),
+    ("propfind", true, true),
+    ("proppatch", false, true),
+    ("query", true, true),
+    ("rebind", false, true),
+    ("report", true, true),
+    ("search", true, true),
+    ("unbind", false, true),
+    ("uncheckout", false, true),
+    ("unlink", false, true),
+    ("unlock", false, true),
+    ("update", false, true),
+    ("updateredirectref", false, true),
+    ("version-control", false, true),
+];
+
+impl ExtensionMethod {
+    /// Returns `true` if the method is safe.
+    ///
+    /// This is only `true` for safe methods in the [IANA HTTP Method
+    /// Registry], e.g. PROPFIND, unknown methods are assumed to be unsafe.
+    ///
+    /// [IANA HTTP Method Registry]: https://www.iana.org/assignments/http-methods/http-methods.xhtml
+    pub const fn is_safe(self) -> bool {
+        self.properties().0
+    }
+
+    /// Returns `true` if the method is idempotent.
+    ///
+    /// This is only `true` for idempotent methods in the [IANA HTTP Method
+    /// Registry], e.g. MKCOL, unknown methods are assumed to be not idempotent.
+    ///
+    /// [IANA HTTP Method Registry]: https://www.iana.org/assignments/http-methods/http-methods.xhtml
+    pub const fn is_idempotent(self) -> bool {
+        self.properties().1
+    }
+
+    /// Returns the method as string.
+    pub fn as_str(&self) -> &str {
+        // SAFETY: only created from tokens, which are ASCII.
+        unsafe { str::from_utf8_unchecked(&self.buf[..self.len as usize]) }
+    }
+
+    /// Returns whether or not the method is safe and idempotent, based on
+    /// [`REGISTERED_METHODS`].
+    const fn properties(self) -> (bool, bool) {
+        let mut i = 0;
+        while i < REGISTERED_METHODS.len() {
+            let (name, safe, idempotent) = REGISTERED_METHODS[i];
+            if self.eq_lower_case(name) {
+                return (safe, idempotent);
+            }
+            i += 1;
+        }
+        (false, false)
+    }
+
+    /// Compare the method to `lower_case`, ignoring the case of the method.
+    const fn eq_lower_case(&self, lower_case: &str) -> bool {
+        let lower_case = lower_case.as_bytes();
+        if lower_case.len() != self.len as usize {
+            return false;
+        }
+        let mut i = 0;
+        while i < lower_case.len() {
+            if self.buf[i].to_ascii_lowercase() != lower_case[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+}
+
+impl FromStr for ExtensionMethod {
+    type Err = UnknownMethod;
+
+    fn from_str(method: &str) -> Result<Self, Self::Err> {
+        if method.len() > MAX_EXTENSION_METHOD_LEN || !is_token(method) {
+            return Err(UnknownMethod);
+        }
+        let mut buf = [0; MAX_EXTENSION_METHOD_LEN];
+        buf[..method.len()].copy_from_slice(method.as_bytes());
+        #[allow(clippy::cast_possible_truncation)] // Checked above.
+        let len = method.len() as u8;
+        Ok(ExtensionMethod { len, buf })
+    }
+}
+
+impl fmt::Debug for ExtensionMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Display for ExtensionMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -13,7 +13,7 @@ pub use status_code::StatusCode;
 #[doc(no_inline)]
 pub use version::Version;
 
-use crate::{Request, Response};
+use crate::{Extensions, Request, Response, Uri};
 use header::FromHeaderValue;
 
 /// Head of a [`Request`].
@@ -23,6 +23,7 @@ pub struct RequestHead {
     pub(crate) path: String,
     version: Version,
     pub(crate) headers: Headers,
+    extensions: Extensions,
 }
 
 impl RequestHead {
@@ -38,6 +39,7 @@ impl RequestHead {
             path,
             version,
             headers,
+            extensions: Extensions::new(),
         }
     }
 
@@ -56,6 +58,12 @@ impl RequestHead {
         &self.path
     }
 
+    /// Returns the path of this request as [`Uri`], which can be used to get
+    /// the decoded path and query parameters.
+    pub fn uri(&self) -> Uri<'_> {
+        Uri::parse(&self.path)
+    }
+
     /// Returns the HTTP version of this request.
     ///
     /// # Notes
@@ -83,6 +91,16 @@ impl RequestHead {
         &mut self.headers
     }
 
+    /// Returns the extensions.
+    pub const fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Returns mutable access to the extensions.
+    pub const fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
     /// Get the header’s value with `name`, if any.
     ///
     /// See [`Headers::get_value`] for more information.
@@ -135,6 +153,7 @@ pub struct ResponseHead {
     version: Version,
     status: StatusCode,
     pub(crate) headers: Headers,
+    extensions: Extensions,
 }
 
 impl ResponseHead {
@@ -144,6 +163,7 @@ impl ResponseHead {
             version,
             status,
             headers,
+            extensions: Extensions::new(),
         }
     }
 
@@ -177,6 +197,16 @@ impl ResponseHead {
         &mut self.headers
     }
 
+    /// Returns the extensions.
+    pub const fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Returns mutable access to the extensions.
+    pub const fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
     /// Get the header’s value with `name`, if any.
     ///
     /// See [`Headers::get_value`] for more information.
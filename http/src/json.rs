@@ -0,0 +1,254 @@
+//! Module with the [`Json`] type.
+//!
+//! [`Json`] can be used to deserialise request bodies and serialise response
+//! bodies using JSON.
+//!
+//! # Examples
+//!
+//! Using `Json` in handlers used with the [`route!`] macro.
+//!
+//! ```
+//! # #![allow(dead_code)]
+//! use std::collections::HashMap;
+//!
+//! use heph_http::body::OneshotBody;
+//! use heph_http::json::{Json, DEFAULT_MAX_SIZE};
+//! use heph_http::{route, server, Request, Response};
+//!
+//! async fn handle(request: Request<server::Body<'_>>) -> Response<OneshotBody<Vec<u8>>> {
+//!     route!(match request {
+//!         POST "/sum" => sum,
+//!         _ => not_found,
+//!     })
+//! }
+//!
+//! /// Returns the sum of the values in the request, e.g. `{"a": 1, "b": 2}`.
+//! async fn sum(mut request: Request<server::Body<'_>>) -> Response<OneshotBody<Vec<u8>>> {
+//!     let result = Json::<HashMap<String, u64>>::from_request(&mut request, DEFAULT_MAX_SIZE)
+//!         .await
+//!         .map(|Json(values)| Json(values.values().sum::<u64>()));
+//!     // On error this will respond with the proper status code, see
+//!     // `JsonError::proper_status_code`.
+//!     Response::from(result)
+//! }
+//!
+//! async fn not_found(_: Request<server::Body<'_>>) -> Response<OneshotBody<Vec<u8>>> {
+//!     Response::not_found().with_body(OneshotBody::new(Vec::new()))
+//! }
+//! ```
+//!
+//! [`route!`]: crate::route
+
+use std::{fmt, io};
+
+use log::warn;
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+
+use crate::body::{BodyLength, OneshotBody};
+use crate::head::header::{Header, HeaderName};
+use crate::server::Body;
+use crate::{Request, Response, StatusCode, MIN_READ_SIZE};
+
+/// Default maximum size of a request body, see [`Json::from_request`].
+pub const DEFAULT_MAX_SIZE: usize = 1024 * 1024;
+
+/// JSON request or response body.
+///
+/// Use [`Json::from_request`] to deserialise a request body. For responses
+/// `Json` can be converted into a [`Response`] using [`Json::into_response`]
+/// or the [`From`] implementation.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Json<T>(pub T);
+
+impl<T> Json<T> {
+    /// Returns the inner value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Json<T>
+where
+    T: DeserializeOwned,
+{
+    /// Read the body of `request` and deserialise it.
+    ///
+    /// This returns an error if the "Content-Type" header is not a JSON type,
+    /// i.e. `application/json` or a type with a `+json` suffix, or if the body
+    /// is larger than `max_size` bytes.
+    pub async fn from_request(
+        request: &mut Request<Body<'_>>,
+        max_size: usize,
+    ) -> Result<Json<T>, JsonError> {
+        let content_type = request
+            .header::<&str>(&HeaderName::CONTENT_TYPE)
+            .ok()
+            .flatten();
+        if !content_type.is_some_and(is_json) {
+            return Err(JsonError::InvalidContentType);
+        }
+
+        let buf = read_body(request.body_mut(), max_size).await?;
+        serde_json::from_slice(&buf)
+            .map(Json)
+            .map_err(JsonError::Deserialise)
+    }
+}
+
+impl<T> Json<T>
+where
+    T: Serialize,
+{
+    /// Serialise the value into a `200 OK` response, setting the
+    /// "Content-Type" header to `application/json`.
+    pub fn into_response(self) -> Result<Response<OneshotBody<Vec<u8>>>, JsonError> {
+        let body = serde_json::to_vec(&self.0).map_err(JsonError::Serialise)?;
+        Ok(json_response(StatusCode::OK, body))
+    }
+}
+
+/// Returns a `500 Internal Server Error` response if the value can't be
+/// serialised.
+impl<T> From<Json<T>> for Response<OneshotBody<Vec<u8>>>
+where
+    T: Serialize,
+{
+    fn from(json: Json<T>) -> Response<OneshotBody<Vec<u8>>> {
+        json.into_response().unwrap_or_else(|err| {
+            warn!("failed to serialise JSON response: {err}");
+            Response::from(err)
+        })
+    }
+}
+
+/// Read at most `max_size` bytes from `body`.
+async fn read_body(body: &mut Body<'_>, max_size: usize) -> Result<Vec<u8>, JsonError> {
+    let mut buf = match body.len() {
+        BodyLength::Known(length) if length > max_size => return Err(JsonError::BodyTooLarge),
+        BodyLength::Known(length) => Vec::with_capacity(length),
+        BodyLength::Chunked => Vec::with_capacity(MIN_READ_SIZE),
+    };
+    while !body.is_empty() {
+        if buf.len() == buf.capacity() {
+            buf.reserve(MIN_READ_SIZE);
+        }
+        let len = buf.len();
+        buf = body.recv(buf).await?;
+        if buf.len() > max_size {
+            return Err(JsonError::BodyTooLarge);
+        } else if buf.len() == len && !body.is_empty() {
+            return Err(JsonError::IncompleteBody);
+        }
+    }
+    Ok(buf)
+}
+
+/// Returns `true` if `content_type` is a JSON type.
+fn is_json(content_type: &str) -> bool {
+    let mime = content_type
+        .split_once(';')
+        .map_or(content_type, |(mime, _)| mime)
+        .trim();
+    let Some((kind, subtype)) = mime.split_once('/') else {
+        return false;
+    };
+    kind.eq_ignore_ascii_case("application")
+        && (subtype.eq_ignore_ascii_case("json")
+            || subtype.len() > 5 && subtype[subtype.len() - 5..].eq_ignore_ascii_case("+json"))
+}
+
+/// Create a response with `status` and a JSON `body`.
+fn json_response(status: StatusCode, body: Vec<u8>) -> Response<OneshotBody<Vec<u8>>> {
+    let mut response = Response::ok().with_body(OneshotBody::new(body));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .append(Header::new(HeaderName::CONTENT_TYPE, b"application/json"));
+    response
+}
+
+/// Error reading or writing a [`Json`] body.
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum JsonError {
+    /// The "Content-Type" header is missing or not a JSON type.
+    InvalidContentType,
+    /// Request body is too large.
+    BodyTooLarge,
+    /// Request body ended before all bytes were read.
+    IncompleteBody,
+    /// Request body is invalid JSON, or doesn't match the expected type.
+    Deserialise(serde_json::Error),
+    /// Failed to serialise the response body.
+    Serialise(serde_json::Error),
+    /// I/O error.
+    Io(io::Error),
+}
+
+impl JsonError {
+    /// Returns the proper status code for a given error.
+    pub fn proper_status_code(&self) -> StatusCode {
+        use JsonError::*;
+        match self {
+            InvalidContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            BodyTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            // Valid JSON, but not the expected type.
+            Deserialise(err) if err.is_data() => StatusCode::UNPROCESSABLE_ENTITY,
+            IncompleteBody | Deserialise(_) | Io(_) => StatusCode::BAD_REQUEST,
+            Serialise(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Returns a description of the error.
+    pub const fn as_str(&self) -> &'static str {
+        use JsonError::*;
+        match self {
+            InvalidContentType => "invalid JSON Content-Type header",
+            BodyTooLarge => "JSON body too large",
+            IncompleteBody => "incomplete JSON body",
+            Deserialise(_) => "invalid JSON body",
+            Serialise(_) => "failed to serialise JSON body",
+            Io(_) => "I/O error",
+        }
+    }
+}
+
+/// Responds with the [proper status code] and the [description] of the error,
+/// e.g. `{"error":"JSON body too large"}`.
+///
+/// [proper status code]: JsonError::proper_status_code
+/// [description]: JsonError::as_str
+impl From<JsonError> for Response<OneshotBody<Vec<u8>>> {
+    fn from(err: JsonError) -> Response<OneshotBody<Vec<u8>>> {
+        let body = format!("{{\"error\":\"{}\"}}", err.as_str());
+        json_response(err.proper_status_code(), body.into_bytes())
+    }
+}
+
+impl From<io::Error> for JsonError {
+    fn from(err: io::Error) -> JsonError {
+        JsonError::Io(err)
+    }
+}
+
+impl From<JsonError> for io::Error {
+    fn from(err: JsonError) -> io::Error {
+        match err {
+            JsonError::Io(err) => err,
+            err => io::Error::new(io::ErrorKind::InvalidData, err.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::Deserialise(err) | JsonError::Serialise(err) => {
+                write!(f, "{}: {err}", self.as_str())
+            }
+            JsonError::Io(err) => err.fmt(f),
+            err => err.as_str().fmt(f),
+        }
+    }
+}
@@ -6,7 +6,8 @@
     extract_if,
     impl_trait_in_assoc_type,
     maybe_uninit_uninit_array,
-    maybe_uninit_write_slice
+    maybe_uninit_write_slice,
+    never_type
 )]
 #![warn(
     anonymous_parameters,
@@ -22,21 +23,35 @@
     variant_size_differences
 )]
 
+pub mod access_log;
 pub mod body;
+pub mod cache;
 pub mod client;
+pub mod conditional;
+pub mod cookie;
+pub mod cors;
+mod extensions;
 pub mod handler;
 pub mod head;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod multipart;
 mod request;
+pub mod request_actor;
 mod response;
 mod route;
 pub mod server;
+pub mod sse;
+pub mod static_files;
 mod str;
 pub mod transform;
+pub mod uri;
 
 #[doc(no_inline)]
 pub use body::Body;
 #[doc(no_inline)]
 pub use client::Client;
+pub use extensions::Extensions;
 #[doc(no_inline)]
 pub use head::header::{Header, HeaderName, Headers};
 #[doc(no_inline)]
@@ -49,6 +64,8 @@ pub use request::Request;
 pub use response::Response;
 #[doc(no_inline)]
 pub use server::Connection;
+#[doc(no_inline)]
+pub use uri::Uri;
 
 /// Maximum size of the HTTP head (the start line and the headers).
 ///
@@ -146,9 +163,83 @@ const fn is_lower_case(value: &str) -> bool {
     true
 }
 
+/// Returns `true` if `value` is a non-empty token, RFC 9110 section 5.6.2.
+fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+/// Head of a chunk in a body using chunked transfer encoding, see
+/// [`parse_chunk`].
+#[derive(Debug)]
+enum Chunk {
+    /// Chunk with `size` bytes of data following the head.
+    Data { size: usize },
+    /// Last chunk, including the trailer section.
+    Last { trailers: Headers },
+}
+
+/// Error returned by [`parse_chunk`].
+#[derive(Debug)]
+enum ChunkError {
+    /// Chunk size is invalid.
+    InvalidSize,
+    /// Error parsing the trailer section.
+    Trailer(httparse::Error),
+}
+
+/// Parse the head of the next chunk from `buf`, RFC 9112 section 7.1.
+///
+/// If `after_data` is `true` the data of the previous chunk was read, in which
+/// case `buf` must start with the CRLF ending that data. For the last chunk
+/// this also parses the trailer section.
+///
+/// Returns the number of bytes parsed and the chunk, or `None` if `buf` doesn't
+/// contain the complete chunk head (and trailer section) yet.
+fn parse_chunk(buf: &[u8], after_data: bool) -> Result<Option<(usize, Chunk)>, ChunkError> {
+    let mut parsed = 0;
+    if after_data {
+        match buf {
+            [b'\r', b'\n', ..] => parsed = 2,
+            [] | [b'\r'] => return Ok(None),
+            _ => return Err(ChunkError::InvalidSize),
+        }
+    }
+
+    let chunk_size = match httparse::parse_chunk_size(&buf[parsed..]) {
+        Ok(httparse::Status::Complete((idx, chunk_size))) => {
+            parsed += idx;
+            chunk_size
+        }
+        Ok(httparse::Status::Partial) => return Ok(None),
+        Err(_) => return Err(ChunkError::InvalidSize),
+    };
+    if chunk_size != 0 {
+        // FIXME: add check here. It's fine on 64 bit (only currently
+        // supported).
+        #[allow(clippy::cast_possible_truncation)]
+        let size = chunk_size as usize;
+        return Ok(Some((parsed, Chunk::Data { size })));
+    }
+
+    let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+    match httparse::parse_headers(&buf[parsed..], &mut headers) {
+        Ok(httparse::Status::Complete((idx, headers))) => {
+            let Ok(trailers) = Headers::from_httparse_headers(headers, |_, _| Ok::<_, !>(()));
+            Ok(Some((parsed + idx, Chunk::Last { trailers })))
+        }
+        Ok(httparse::Status::Partial) => Ok(None),
+        Err(err) => Err(ChunkError::Trailer(err)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{cmp_lower_case, is_lower_case, trim_ws};
+    use super::{
+        cmp_lower_case, is_lower_case, parse_chunk, trim_ws, Chunk, ChunkError, HeaderName,
+    };
 
     #[test]
     fn test_trim_ws() {
@@ -202,4 +293,64 @@ mod tests {
             assert_eq!(got, *expected, "input: '{lower_case}', '{right}'");
         }
     }
+
+    #[test]
+    fn test_parse_chunk() {
+        assert!(parse_chunk(b"", false).unwrap().is_none());
+        assert!(parse_chunk(b"5", false).unwrap().is_none());
+        assert!(parse_chunk(b"\r", true).unwrap().is_none());
+
+        let tests: &[(&[u8], bool, usize, usize)] = &[
+            (b"5\r\n", false, 3, 5),
+            (b"a;ext=1\r\nHello", false, 9, 10),
+            (b"\r\n5\r\n", true, 5, 5),
+        ];
+        for (input, after_data, expected_n, expected_size) in tests {
+            let (n, chunk) = parse_chunk(input, *after_data).unwrap().unwrap();
+            assert_eq!(n, *expected_n, "input: {input:?}");
+            assert!(
+                matches!(chunk, Chunk::Data { size } if size == *expected_size),
+                "input: {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_last_chunk() {
+        assert!(parse_chunk(b"0\r\n", false).unwrap().is_none());
+        assert!(parse_chunk(b"0\r\nX-Checksum: 1", false).unwrap().is_none());
+
+        let (n, chunk) = parse_chunk(b"\r\n0\r\n\r\nGET", true).unwrap().unwrap();
+        assert_eq!(n, 7);
+        assert!(matches!(chunk, Chunk::Last { trailers } if trailers.is_empty()));
+
+        let input = b"0\r\nX-Checksum: 123\r\n\r\n";
+        let (n, chunk) = parse_chunk(input, false).unwrap().unwrap();
+        assert_eq!(n, input.len());
+        let Chunk::Last { trailers } = chunk else {
+            panic!("expected last chunk");
+        };
+        assert_eq!(trailers.len(), 1);
+        assert_eq!(
+            trailers.get_bytes(&HeaderName::from_lowercase("x-checksum")),
+            Some(&b"123"[..])
+        );
+    }
+
+    #[test]
+    fn test_parse_chunk_errors() {
+        assert!(matches!(
+            parse_chunk(b"Z\r\n", false),
+            Err(ChunkError::InvalidSize)
+        ));
+        // Missing CRLF after the chunk data.
+        assert!(matches!(
+            parse_chunk(b"0\r\n\r\n", true),
+            Err(ChunkError::InvalidSize)
+        ));
+        assert!(matches!(
+            parse_chunk(b"0\r\nX-Checksum\r\n\r\n", false),
+            Err(ChunkError::Trailer(_))
+        ));
+    }
 }
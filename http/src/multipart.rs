@@ -0,0 +1,705 @@
+//! Module with a `multipart/form-data` body parser.
+//!
+//! See [`Multipart`] and RFC 7578 (and RFC 2046 section 5.1) for more
+//! information.
+//!
+//! # Examples
+//!
+//! Reading a file upload without buffering the entire request body.
+//!
+//! ```
+//! # #![allow(dead_code)]
+//! use heph_http::multipart::{Multipart, MultipartError};
+//! use heph_http::{server, Request};
+//!
+//! async fn upload(mut request: Request<server::Body<'_>>) -> Result<usize, MultipartError> {
+//!     let mut multipart = Multipart::from_request(&mut request)?
+//!         .with_max_parts(10)
+//!         .with_max_part_size(10 * 1024 * 1024);
+//!     let mut uploaded = 0;
+//!     while let Some(mut part) = multipart.next_part().await? {
+//!         if part.name() != Some("file") {
+//!             // Unread parts are skipped.
+//!             continue;
+//!         }
+//!         let mut buf = Vec::with_capacity(4096);
+//!         loop {
+//!             buf.clear();
+//!             buf = part.recv(buf).await?;
+//!             if buf.is_empty() {
+//!                 // Read the entire part.
+//!                 break;
+//!             }
+//!             // Process the bytes, e.g. write them to a file.
+//!             uploaded += buf.len();
+//!         }
+//!     }
+//!     Ok(uploaded)
+//! }
+//! ```
+
+use std::{fmt, io, str};
+
+use heph_rt::io::BufMut;
+
+use crate::head::header::{HeaderName, Headers};
+use crate::server::Body;
+use crate::{Request, StatusCode, MAX_HEADERS, MIN_READ_SIZE};
+
+/// Default maximum number of parts, see [`Multipart::with_max_parts`].
+pub const DEFAULT_MAX_PARTS: usize = 128;
+
+/// Default maximum size of the head of a part, see
+/// [`Multipart::with_max_head_size`].
+pub const DEFAULT_MAX_HEAD_SIZE: usize = 8 * 1024;
+
+/// Default maximum size of the body of a single part, see
+/// [`Multipart::with_max_part_size`].
+pub const DEFAULT_MAX_PART_SIZE: usize = 16 * 1024 * 1024;
+
+/// Maximum length of a boundary, per RFC 2046 section 5.1.1.
+const MAX_BOUNDARY_LEN: usize = 70;
+
+/// Maximum number of bytes of (transport) padding allowed after the boundary.
+const MAX_PADDING: usize = 64;
+
+/// Streaming `multipart/form-data` parser.
+///
+/// Reads parts from a request [`Body`], see [`Multipart::next_part`]. The body
+/// of each part is streamed, see [`Part::recv`], so the (entire) request body
+/// doesn't have to be buffered.
+///
+/// See the [module documentation] for an example.
+///
+/// [module documentation]: crate::multipart
+#[derive(Debug)]
+pub struct Multipart<'b, 'a> {
+    body: &'b mut Body<'a>,
+    parser: Parser,
+    /// Read all bytes from `body`.
+    eof: bool,
+}
+
+impl<'b, 'a> Multipart<'b, 'a> {
+    /// Create a new multipart parser reading from `body`, using `boundary` to
+    /// separate the parts.
+    ///
+    /// Also see [`Multipart::from_request`] which determines the boundary
+    /// using the "Content-Type" header.
+    pub fn new(body: &'b mut Body<'a>, boundary: &str) -> Multipart<'b, 'a> {
+        Multipart {
+            body,
+            parser: Parser::new(boundary.as_bytes()),
+            eof: false,
+        }
+    }
+
+    /// Create a new multipart parser for `request`.
+    ///
+    /// This returns an error if the "Content-Type" header is not a multipart
+    /// type or is missing the boundary parameter.
+    pub fn from_request(
+        request: &'b mut Request<Body<'a>>,
+    ) -> Result<Multipart<'b, 'a>, MultipartError> {
+        let boundary = request
+            .header::<&str>(&HeaderName::CONTENT_TYPE)
+            .ok()
+            .flatten()
+            .and_then(boundary)
+            .ok_or(MultipartError::InvalidContentType)?
+            .to_owned();
+        Ok(Multipart::new(request.body_mut(), &boundary))
+    }
+
+    /// Set the maximum number of parts, defaults to [`DEFAULT_MAX_PARTS`].
+    pub const fn with_max_parts(mut self, max_parts: usize) -> Multipart<'b, 'a> {
+        self.parser.max_parts = max_parts;
+        self
+    }
+
+    /// Set the maximum size of the head (the headers) of a single part,
+    /// defaults to [`DEFAULT_MAX_HEAD_SIZE`].
+    pub const fn with_max_head_size(mut self, max_head_size: usize) -> Multipart<'b, 'a> {
+        self.parser.max_head_size = max_head_size;
+        self
+    }
+
+    /// Set the maximum size of the body of a single part, defaults to
+    /// [`DEFAULT_MAX_PART_SIZE`].
+    pub const fn with_max_part_size(mut self, max_part_size: usize) -> Multipart<'b, 'a> {
+        self.parser.max_part_size = max_part_size;
+        self
+    }
+
+    /// Returns the next part, or `None` if all parts have been read.
+    ///
+    /// If the body of the previous part wasn't (completely) read the remainder
+    /// of it is skipped.
+    pub async fn next_part<'m>(&'m mut self) -> Result<Option<Part<'m, 'b, 'a>>, MultipartError> {
+        loop {
+            match self.parser.parse_head(self.eof)? {
+                Some(Some(headers)) => {
+                    return Ok(Some(Part {
+                        multipart: self,
+                        headers,
+                    }))
+                }
+                Some(None) => return Ok(None),
+                None => self.fill().await?,
+            }
+        }
+    }
+
+    /// Read more bytes from the body.
+    async fn fill(&mut self) -> Result<(), MultipartError> {
+        let mut buf = self.parser.take_buf();
+        buf.reserve(MIN_READ_SIZE);
+        let len_before = buf.len();
+        let buf = self.body.recv(buf).await?;
+        self.eof = buf.len() == len_before;
+        self.parser.buf = buf;
+        Ok(())
+    }
+}
+
+/// Single part of a multipart body.
+///
+/// See [`Multipart::next_part`].
+#[derive(Debug)]
+pub struct Part<'m, 'b, 'a> {
+    multipart: &'m mut Multipart<'b, 'a>,
+    headers: Headers,
+}
+
+impl<'m, 'b, 'a> Part<'m, 'b, 'a> {
+    /// Returns the headers of the part.
+    pub const fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Returns the name of the form field, from the `name` parameter of the
+    /// "Content-Disposition" header.
+    pub fn name(&self) -> Option<&str> {
+        self.disposition_param("name")
+    }
+
+    /// Returns the original file name, from the `filename` parameter of the
+    /// "Content-Disposition" header.
+    pub fn filename(&self) -> Option<&str> {
+        self.disposition_param("filename")
+    }
+
+    /// Returns the value of the "Content-Type" header, if any.
+    pub fn content_type(&self) -> Option<&str> {
+        self.header_str(&HeaderName::CONTENT_TYPE)
+    }
+
+    fn disposition_param(&self, name: &str) -> Option<&str> {
+        self.header_str(&HeaderName::CONTENT_DISPOSITION)
+            .and_then(|value| param(value, name))
+    }
+
+    fn header_str(&self, name: &HeaderName<'_>) -> Option<&str> {
+        self.headers
+            .get_bytes(name)
+            .and_then(|value| str::from_utf8(value).ok())
+    }
+
+    /// Receive bytes from the body of the part, writing them into `buf`.
+    ///
+    /// If no bytes are written into `buf` (and it has spare capacity) the
+    /// entire part has been read.
+    pub async fn recv<B: BufMut>(&mut self, mut buf: B) -> Result<B, MultipartError> {
+        loop {
+            if let Some(bytes) = self.multipart.parser.parse_body(self.multipart.eof)? {
+                let n = buf.extend_from_slice(bytes);
+                self.multipart.parser.consume_body(n)?;
+                return Ok(buf);
+            }
+            self.multipart.fill().await?;
+        }
+    }
+}
+
+/// Error parsing a multipart body.
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum MultipartError {
+    /// The "Content-Type" header is missing, not a multipart type or doesn't
+    /// contain a (valid) boundary.
+    InvalidContentType,
+    /// Invalid bytes after the boundary.
+    InvalidBoundary,
+    /// Invalid head (headers) of a part.
+    InvalidHead,
+    /// Head of a part is too large, see [`Multipart::with_max_head_size`].
+    HeadTooLarge,
+    /// Body of a part is too large, see [`Multipart::with_max_part_size`].
+    PartTooLarge,
+    /// Too many parts, see [`Multipart::with_max_parts`].
+    TooManyParts,
+    /// Body ended before the final boundary.
+    IncompleteBody,
+    /// I/O error.
+    Io(io::Error),
+}
+
+impl MultipartError {
+    /// Returns the proper status code for a given error.
+    pub const fn proper_status_code(&self) -> StatusCode {
+        use MultipartError::*;
+        match self {
+            InvalidContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            InvalidBoundary | InvalidHead | IncompleteBody | Io(_) => StatusCode::BAD_REQUEST,
+            HeadTooLarge | PartTooLarge | TooManyParts => StatusCode::PAYLOAD_TOO_LARGE,
+        }
+    }
+
+    /// Returns a description of the error.
+    pub const fn as_str(&self) -> &'static str {
+        use MultipartError::*;
+        match self {
+            InvalidContentType => "invalid multipart Content-Type header",
+            InvalidBoundary => "invalid multipart boundary",
+            InvalidHead => "invalid multipart part head",
+            HeadTooLarge => "multipart part head too large",
+            PartTooLarge => "multipart part too large",
+            TooManyParts => "too many multipart parts",
+            IncompleteBody => "incomplete multipart body",
+            Io(_) => "I/O error",
+        }
+    }
+}
+
+impl From<io::Error> for MultipartError {
+    fn from(err: io::Error) -> MultipartError {
+        MultipartError::Io(err)
+    }
+}
+
+impl From<MultipartError> for io::Error {
+    fn from(err: MultipartError) -> io::Error {
+        match err {
+            MultipartError::Io(err) => err,
+            err => io::Error::new(io::ErrorKind::InvalidData, err.as_str()),
+        }
+    }
+}
+
+impl fmt::Display for MultipartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MultipartError::Io(err) => err.fmt(f),
+            err => err.as_str().fmt(f),
+        }
+    }
+}
+
+/// Returns the boundary parameter of the `content_type` if it's a multipart
+/// type.
+fn boundary(content_type: &str) -> Option<&str> {
+    let (mime_type, _) = content_type.split_once(';')?;
+    let (type_, _) = mime_type.split_once('/')?;
+    if !type_.trim().eq_ignore_ascii_case("multipart") {
+        return None;
+    }
+    param(content_type, "boundary")
+        .filter(|boundary| !boundary.is_empty() && boundary.len() <= MAX_BOUNDARY_LEN)
+}
+
+/// Returns the value of the parameter `name` in a header `value`, e.g. `name`
+/// in `form-data; name="field"`. Quotes are removed.
+fn param<'v>(value: &'v str, name: &str) -> Option<&'v str> {
+    value.split(';').skip(1).find_map(|param| {
+        let (param_name, value) = param.split_once('=')?;
+        if !param_name.trim().eq_ignore_ascii_case(name) {
+            return None;
+        }
+        let value = value.trim();
+        match value.strip_prefix('"') {
+            Some(value) => value.strip_suffix('"'),
+            None => Some(value),
+        }
+    })
+}
+
+/// State of the [`Parser`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum State {
+    /// Looking for the next delimiter, skipping all bytes before it. This is
+    /// also used to skip the (unread) body of a part.
+    Delimiter,
+    /// After the delimiter, either the end of the body or the head of a part
+    /// follows.
+    AfterDelimiter,
+    /// Parsing the head of a part.
+    Head,
+    /// Reading the body of a part.
+    Body,
+    /// Read the final delimiter.
+    Done,
+}
+
+/// Multipart parser, independent of the I/O.
+#[derive(Debug)]
+struct Parser {
+    /// The delimiter: `\r\n--$boundary`.
+    delimiter: Vec<u8>,
+    /// Buffered bytes.
+    buf: Vec<u8>,
+    /// Number of processed bytes in `buf`.
+    processed: usize,
+    state: State,
+    /// Number of parts read.
+    parts: usize,
+    /// Number of bytes read of the body of the current part.
+    part_size: usize,
+    max_parts: usize,
+    max_head_size: usize,
+    max_part_size: usize,
+}
+
+impl Parser {
+    fn new(boundary: &[u8]) -> Parser {
+        let mut delimiter = Vec::with_capacity(4 + boundary.len());
+        delimiter.extend_from_slice(b"\r\n--");
+        delimiter.extend_from_slice(boundary);
+        // The first boundary doesn't need to be preceded by a CRLF, but by
+        // adding it to the buffer we can use the same delimiter for all parts.
+        let mut buf = Vec::with_capacity(MIN_READ_SIZE);
+        buf.extend_from_slice(b"\r\n");
+        Parser {
+            delimiter,
+            buf,
+            processed: 0,
+            state: State::Delimiter,
+            parts: 0,
+            part_size: 0,
+            max_parts: DEFAULT_MAX_PARTS,
+            max_head_size: DEFAULT_MAX_HEAD_SIZE,
+            max_part_size: DEFAULT_MAX_PART_SIZE,
+        }
+    }
+
+    /// Returns the buffer to read more bytes into, removing all processed
+    /// bytes.
+    fn take_buf(&mut self) -> Vec<u8> {
+        drop(self.buf.drain(..self.processed));
+        self.processed = 0;
+        std::mem::take(&mut self.buf)
+    }
+
+    /// Returns the unprocessed bytes.
+    fn bytes(&self) -> &[u8] {
+        &self.buf[self.processed..]
+    }
+
+    /// Parse the head of the next part, skipping the body of the current part.
+    ///
+    /// Returns:
+    ///  * `Some(Some(headers))` if the head of the next part is parsed,
+    ///  * `Some(None)` if all parts are read, and
+    ///  * `None` if more bytes are needed.
+    ///
+    /// `eof` indicates that no more bytes can be read.
+    fn parse_head(&mut self, eof: bool) -> Result<Option<Option<Headers>>, MultipartError> {
+        loop {
+            match self.state {
+                State::Delimiter | State::Body => {
+                    if let Some(idx) = find(self.bytes(), &self.delimiter) {
+                        self.processed += idx + self.delimiter.len();
+                        self.state = State::AfterDelimiter;
+                        continue;
+                    }
+                    // Skip all bytes that can't be part of the delimiter.
+                    let len = self.bytes().len();
+                    self.processed += len.saturating_sub(self.delimiter.len() - 1);
+                    return need_more(eof);
+                }
+                State::AfterDelimiter => {
+                    let bytes = self.bytes();
+                    if bytes.starts_with(b"--") {
+                        // Final delimiter, we ignore the epilogue.
+                        self.state = State::Done;
+                        continue;
+                    }
+                    let Some(idx) = find(bytes, b"\r\n") else {
+                        if bytes.len() > MAX_PADDING {
+                            return Err(MultipartError::InvalidBoundary);
+                        }
+                        return need_more(eof);
+                    };
+                    // Allow for (transport) padding after the boundary.
+                    if !bytes[..idx].iter().all(|b| matches!(b, b' ' | b'\t')) {
+                        return Err(MultipartError::InvalidBoundary);
+                    }
+                    self.processed += idx + 2;
+                    self.parts += 1;
+                    if self.parts > self.max_parts {
+                        return Err(MultipartError::TooManyParts);
+                    }
+                    self.state = State::Head;
+                }
+                State::Head => {
+                    let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+                    let bytes = self.bytes();
+                    match httparse::parse_headers(bytes, &mut headers) {
+                        Ok(httparse::Status::Complete((head_length, headers))) => {
+                            if head_length > self.max_head_size {
+                                return Err(MultipartError::HeadTooLarge);
+                            }
+                            let headers = Headers::from_httparse_headers(headers, |_, _| {
+                                Ok::<_, MultipartError>(())
+                            })?;
+                            self.processed += head_length;
+                            self.state = State::Body;
+                            self.part_size = 0;
+                            return Ok(Some(Some(headers)));
+                        }
+                        Ok(httparse::Status::Partial) if bytes.len() > self.max_head_size => {
+                            return Err(MultipartError::HeadTooLarge);
+                        }
+                        Ok(httparse::Status::Partial) => return need_more(eof),
+                        Err(httparse::Error::TooManyHeaders) => {
+                            return Err(MultipartError::HeadTooLarge)
+                        }
+                        Err(_) => return Err(MultipartError::InvalidHead),
+                    }
+                }
+                State::Done => return Ok(Some(None)),
+            }
+        }
+    }
+
+    /// Returns the next bytes of the body of the current part.
+    ///
+    /// Returns an empty slice if the entire part is read, or `None` if more
+    /// bytes are needed. Call [`Parser::consume_body`] to mark the bytes as
+    /// processed.
+    fn parse_body(&mut self, eof: bool) -> Result<Option<&[u8]>, MultipartError> {
+        if self.state != State::Body {
+            // Part is read and the parser has moved on.
+            return Ok(Some(&[]));
+        }
+        let bytes = self.bytes();
+        if let Some(idx) = find(bytes, &self.delimiter) {
+            return Ok(Some(&bytes[..idx]));
+        }
+        // Can't return bytes that might be part of the delimiter.
+        let len = bytes.len().saturating_sub(self.delimiter.len() - 1);
+        if len == 0 {
+            return need_more(eof);
+        }
+        Ok(Some(&bytes[..len]))
+    }
+
+    /// Mark `n` bytes of the body of the current part as processed.
+    fn consume_body(&mut self, n: usize) -> Result<(), MultipartError> {
+        self.processed += n;
+        self.part_size += n;
+        if self.part_size > self.max_part_size {
+            return Err(MultipartError::PartTooLarge);
+        }
+        Ok(())
+    }
+}
+
+/// Returns `Ok(None)` (need more bytes) or an error if `eof` is true.
+const fn need_more<T>(eof: bool) -> Result<Option<T>, MultipartError> {
+    if eof {
+        Err(MultipartError::IncompleteBody)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Returns the index of `needle` in `haystack`, if any.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{boundary, param, MultipartError, Parser};
+    use crate::HeaderName;
+
+    const BODY: &[u8] = b"preamble\r\n\
+        --XyZ\r\n\
+        Content-Disposition: form-data; name=\"field\"\r\n\
+        \r\n\
+        value\r\n\
+        --XyZ  \r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+        Content-Type: text/plain\r\n\
+        \r\n\
+        line1\r\nline2\r\n--XyZ\r\n\r\n\r\n\r\n\
+        --XyZ--\r\n\
+        epilogue";
+
+    /// Content-Disposition header and body of all parts.
+    type Parts = Vec<(Option<String>, Vec<u8>)>;
+
+    /// Parse `body` feeding it to the parser in chunks of `chunk_size`,
+    /// returning the headers and body of all parts.
+    fn parse(
+        body: &[u8],
+        chunk_size: usize,
+        max_part_size: usize,
+    ) -> Result<Parts, MultipartError> {
+        let mut parser = Parser::new(b"XyZ");
+        parser.max_part_size = max_part_size;
+        let mut chunks = body.chunks(chunk_size);
+        let mut feed = |parser: &mut Parser| -> bool {
+            let mut buf = parser.take_buf();
+            let eof = match chunks.next() {
+                Some(chunk) => {
+                    buf.extend_from_slice(chunk);
+                    false
+                }
+                None => true,
+            };
+            parser.buf = buf;
+            eof
+        };
+
+        let mut eof = false;
+        let mut parts = Vec::new();
+        loop {
+            let headers = match parser.parse_head(eof)? {
+                Some(Some(headers)) => headers,
+                Some(None) => return Ok(parts),
+                None => {
+                    eof = feed(&mut parser);
+                    continue;
+                }
+            };
+            let name = headers
+                .get_bytes(&HeaderName::CONTENT_DISPOSITION)
+                .map(|v| String::from_utf8(v.to_vec()).unwrap());
+            let mut body = Vec::new();
+            loop {
+                match parser.parse_body(eof)? {
+                    Some([]) => break,
+                    Some(bytes) => {
+                        let n = bytes.len();
+                        body.extend_from_slice(bytes);
+                        parser.consume_body(n)?;
+                    }
+                    None => eof = feed(&mut parser),
+                }
+            }
+            parts.push((name, body));
+        }
+    }
+
+    #[test]
+    fn parsing() {
+        for chunk_size in [1, 2, 3, 7, 16, 1024] {
+            let parts = parse(BODY, chunk_size, usize::MAX).unwrap();
+            assert_eq!(parts.len(), 3, "chunk_size: {chunk_size}");
+            assert_eq!(parts[0].0.as_deref(), Some("form-data; name=\"field\""));
+            assert_eq!(parts[0].1, b"value");
+            assert_eq!(
+                parts[1].0.as_deref(),
+                Some("form-data; name=\"file\"; filename=\"a.txt\"")
+            );
+            assert_eq!(parts[1].1, b"line1\r\nline2");
+            assert_eq!(parts[2].0, None);
+            assert_eq!(parts[2].1, b"\r\n");
+        }
+    }
+
+    #[test]
+    fn boundary_at_start() {
+        let body = b"--XyZ\r\n\r\nvalue\r\n--XyZ--";
+        let parts = parse(body, 4, usize::MAX).unwrap();
+        assert_eq!(parts, vec![(None, b"value".to_vec())]);
+    }
+
+    #[test]
+    fn no_parts() {
+        let parts = parse(b"--XyZ--\r\n", 4, usize::MAX).unwrap();
+        assert!(parts.is_empty());
+    }
+
+    #[test]
+    fn incomplete_body() {
+        let tests: &[&[u8]] = &[
+            b"",
+            b"--XyZ",
+            b"--XyZ\r\n",
+            b"--XyZ\r\nContent-Type: text/plain\r\n",
+            b"--XyZ\r\n\r\nvalue",
+            b"--XyZ\r\n\r\nvalue\r\n--XyZ",
+        ];
+        for body in tests {
+            let err = parse(body, 3, usize::MAX).unwrap_err();
+            assert!(
+                matches!(err, MultipartError::IncompleteBody),
+                "body: {:?}, error: {err}",
+                std::str::from_utf8(body)
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_boundary() {
+        let err = parse(b"--XyZ garbage\r\n\r\nvalue\r\n--XyZ--", 3, usize::MAX).unwrap_err();
+        assert!(matches!(err, MultipartError::InvalidBoundary), "{err}");
+    }
+
+    #[test]
+    fn part_too_large() {
+        let err = parse(BODY, 3, 10).unwrap_err();
+        assert!(matches!(err, MultipartError::PartTooLarge), "{err}");
+        // Exactly the limit is fine.
+        assert!(parse(BODY, 3, 12).is_ok());
+    }
+
+    #[test]
+    fn too_many_parts() {
+        let mut parser = Parser::new(b"XyZ");
+        parser.max_parts = 1;
+        parser.buf.extend_from_slice(BODY);
+        assert!(parser.parse_head(true).unwrap().is_some());
+        let err = parser.parse_head(true).unwrap_err();
+        assert!(matches!(err, MultipartError::TooManyParts), "{err}");
+    }
+
+    #[test]
+    fn head_too_large() {
+        let mut parser = Parser::new(b"XyZ");
+        parser.max_head_size = 16;
+        parser.buf.extend_from_slice(BODY);
+        let err = parser.parse_head(true).unwrap_err();
+        assert!(matches!(err, MultipartError::HeadTooLarge), "{err}");
+    }
+
+    #[test]
+    fn parsing_boundary() {
+        let tests = [
+            ("multipart/form-data; boundary=XyZ", Some("XyZ")),
+            ("multipart/form-data; boundary=\"Xy Z\"", Some("Xy Z")),
+            ("Multipart/Mixed;charset=utf-8; BOUNDARY=abc", Some("abc")),
+            ("multipart/form-data", None),
+            ("multipart/form-data; boundary=", None),
+            ("text/plain; boundary=XyZ", None),
+        ];
+        for (content_type, expected) in tests {
+            assert_eq!(boundary(content_type), expected, "{content_type}");
+        }
+        let too_long = format!("multipart/form-data; boundary={}", "a".repeat(71));
+        assert_eq!(boundary(&too_long), None);
+    }
+
+    #[test]
+    fn parsing_param() {
+        let value = "form-data; name=\"field\"; filename=a.txt";
+        assert_eq!(param(value, "name"), Some("field"));
+        assert_eq!(param(value, "filename"), Some("a.txt"));
+        assert_eq!(param(value, "form-data"), None);
+        assert_eq!(param(value, "other"), None);
+    }
+}
@@ -0,0 +1,309 @@
+//! Module with [`HttpActor`], handling each request in its own actor.
+//!
+//! The actor started by [`server::setup`] handles all requests on a
+//! connection, which means that the actor machinery (supervision, priorities
+//! and other [`ActorOptions`]) only applies per connection. [`HttpActor`]
+//! changes this by spawning a new actor for each request, using a regular
+//! [`NewActor`] implementation. The request actor receives a [`Request`], with
+//! the body read into memory, and a [`Responder`], used to send the response
+//! back to the connection.
+//!
+//! If the request actor fails, or otherwise drops the [`Responder`] without
+//! responding, the client receives a `500 Internal Server Error` response.
+//!
+//! Responses can be cached using [`HttpActor::with_cache`], requests for
+//! cached responses are responded to without spawning a request actor.
+//!
+//! [`server::setup`]: crate::server::setup
+//!
+//! # Examples
+//!
+//! ```
+//! #![feature(never_type)]
+//!
+//! use std::io;
+//!
+//! use heph::actor::{self, actor_fn};
+//! use heph::supervisor::{NoSupervisor, SupervisorStrategy};
+//! use heph_http::body::OneshotBody;
+//! use heph_http::request_actor::{HttpActor, Responder};
+//! use heph_http::{server, Request, Response};
+//! use heph_rt::net::TcpStream;
+//! use heph_rt::spawn::ActorOptions;
+//! use heph_rt::ThreadLocal;
+//!
+//! # fn main() -> Result<(), heph_rt::Error> {
+//! // Actor that handles a single request.
+//! async fn hello_actor(
+//!     _: actor::Context<!, ThreadLocal>,
+//!     (request, responder): (Request<Vec<u8>>, Responder),
+//! ) {
+//!     let body = format!("Hello {}", request.path());
+//!     let response = Response::ok().with_body(OneshotBody::new(body.into_bytes()));
+//!     // Error means the connection was closed, nothing we can do about that.
+//!     _ = responder.respond(response);
+//! }
+//!
+//! let http_actor = HttpActor::new(NoSupervisor, actor_fn(hello_actor), ActorOptions::default());
+//! let address = "127.0.0.1:7890".parse().unwrap();
+//! let server = server::setup(address, conn_supervisor, http_actor, ActorOptions::default())
+//!     .map_err(heph_rt::Error::setup)?;
+//! # _ = server;
+//!
+//! fn conn_supervisor(err: io::Error) -> SupervisorStrategy<TcpStream> {
+//!     log::error!("error handling connection: {err}");
+//!     SupervisorStrategy::Stop
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::io;
+
+use heph::actor::{self, NewActor};
+use heph::supervisor::Supervisor;
+use heph_inbox::oneshot::{new_oneshot, Sender};
+use heph_rt::spawn::{ActorOptions, Spawn};
+use log::warn;
+
+use crate::body::{BodyLength, OneshotBody};
+use crate::cache::{Lookup, ResponseCache};
+use crate::server::{Body, Connection};
+use crate::{Header, HeaderName, Request, Response, StatusCode, MIN_READ_SIZE};
+
+/// Default maximum size of a request body, see
+/// [`HttpActor::with_max_body_size`].
+pub const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Response send using a [`Responder`].
+pub type ActorResponse = Response<OneshotBody<Vec<u8>>>;
+
+/// [`NewActor`] implementation that handles each request in its own actor.
+///
+/// This should be passed to [`server::setup`] as the actor handling the
+/// connections. For each request on a connection it spawns a new actor using
+/// the `NA` [`NewActor`] implementation, supervised by `S` and spawned with the
+/// provided [`ActorOptions`].
+///
+/// See the [module documentation] for more information and an example.
+///
+/// [module documentation]: crate::request_actor
+#[derive(Debug)]
+pub struct HttpActor<S, NA> {
+    supervisor: S,
+    new_actor: NA,
+    options: ActorOptions,
+    max_body_size: usize,
+    cache: Option<ResponseCache>,
+}
+
+impl<S, NA> HttpActor<S, NA> {
+    /// Create a new `HttpActor` spawning a new actor for each request using
+    /// `new_actor`, with `supervisor` and `options`.
+    pub const fn new(supervisor: S, new_actor: NA, options: ActorOptions) -> HttpActor<S, NA> {
+        HttpActor {
+            supervisor,
+            new_actor,
+            options,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            cache: None,
+        }
+    }
+
+    /// Set the maximum size of a request body, defaults to
+    /// [`DEFAULT_MAX_BODY_SIZE`].
+    ///
+    /// As the entire body is read into memory before the request actor is
+    /// spawned larger requests are responded to with `413 Payload Too Large`.
+    pub const fn with_max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Cache the responses using `cache`.
+    ///
+    /// Requests for which a response is cached are responded to directly,
+    /// without spawning a request actor. See the [`cache`] module for more
+    /// information.
+    ///
+    /// [`cache`]: crate::cache
+    pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+}
+
+impl<S, NA> NewActor for HttpActor<S, NA>
+where
+    S: Supervisor<NA> + Clone + 'static,
+    NA: NewActor<Argument = (Request<Vec<u8>>, Responder)> + Clone + 'static,
+    NA::Error: fmt::Display,
+    NA::RuntimeAccess: Spawn<S, NA, NA::RuntimeAccess>,
+{
+    type Message = !;
+    type Argument = Connection;
+    type Actor = impl Future<Output = io::Result<()>>;
+    type Error = !;
+    type RuntimeAccess = NA::RuntimeAccess;
+
+    fn new(
+        &mut self,
+        ctx: actor::Context<Self::Message, Self::RuntimeAccess>,
+        connection: Self::Argument,
+    ) -> Result<Self::Actor, Self::Error> {
+        Ok(connection_actor(
+            ctx,
+            connection,
+            self.supervisor.clone(),
+            self.new_actor.clone(),
+            self.options.clone(),
+            self.max_body_size,
+            self.cache.clone(),
+        ))
+    }
+
+    fn name() -> &'static str {
+        NA::name()
+    }
+}
+
+impl<S: Clone, NA: Clone> Clone for HttpActor<S, NA> {
+    fn clone(&self) -> HttpActor<S, NA> {
+        HttpActor {
+            supervisor: self.supervisor.clone(),
+            new_actor: self.new_actor.clone(),
+            options: self.options.clone(),
+            max_body_size: self.max_body_size,
+            cache: self.cache.clone(),
+        }
+    }
+}
+
+/// Actor handling a single connection, spawning an actor per request.
+async fn connection_actor<S, NA>(
+    mut ctx: actor::Context<!, NA::RuntimeAccess>,
+    mut connection: Connection,
+    supervisor: S,
+    new_actor: NA,
+    options: ActorOptions,
+    max_body_size: usize,
+    cache: Option<ResponseCache>,
+) -> io::Result<()>
+where
+    S: Supervisor<NA> + Clone + 'static,
+    NA: NewActor<Argument = (Request<Vec<u8>>, Responder)> + Clone + 'static,
+    NA::Error: fmt::Display,
+    NA::RuntimeAccess: Spawn<S, NA, NA::RuntimeAccess>,
+{
+    connection.set_max_body_size(max_body_size);
+    loop {
+        // NOTE: the request body borrows the connection, so we read it
+        // completely before responding to anything.
+        let request = match connection.next_request().await {
+            Ok(Some(request)) => {
+                let (head, mut body) = request.split();
+                read_body(&mut body, max_body_size)
+                    .await
+                    .map(|body| Ok((head, body)))
+            }
+            // No more requests.
+            Ok(None) => return Ok(()),
+            Err(err) => Ok(Err(err)),
+        }?;
+        let (head, body) = match request {
+            Ok((head, Some(body))) => (head, body),
+            Ok((_, None)) => {
+                // Can't continue reading from the connection as we don't know
+                // where the next request starts.
+                let mut response = Response::build_new(StatusCode::PAYLOAD_TOO_LARGE);
+                response
+                    .headers_mut()
+                    .append(Header::new(HeaderName::CONNECTION, b"close"));
+                return connection.respond_with(response).await;
+            }
+            Err(err) => {
+                connection.respond_with(err.response()).await?;
+                if err.should_close() {
+                    return Ok(());
+                }
+                continue;
+            }
+        };
+        let request = Request::from_head(head, body);
+
+        let cache_key = match cache.as_ref().map(|cache| cache.lookup(&request)) {
+            Some(Lookup::Hit(response)) => {
+                connection.respond_with(response).await?;
+                continue;
+            }
+            Some(Lookup::Miss(key)) => Some(key),
+            Some(Lookup::Uncached) | None => None,
+        };
+
+        let (sender, receiver) = new_oneshot();
+        let arg = (request, Responder { sender });
+        let result = ctx.try_spawn(supervisor.clone(), new_actor.clone(), arg, options.clone());
+        let response = match result {
+            Ok(_) => receiver.recv_once().await,
+            Err(err) => {
+                warn!("failed to spawn actor to handle HTTP request: {err}");
+                None
+            }
+        };
+        // Responder dropped without responding, e.g. because the actor failed.
+        let response = response
+            .unwrap_or_else(|| Response::server_error().with_body(OneshotBody::new(Vec::new())));
+        if let (Some(cache), Some(key)) = (&cache, cache_key) {
+            cache.store(key, &response);
+        }
+        connection.respond_with(response).await?;
+    }
+}
+
+/// Read the entire `body`, returning `None` if it's larger than `max_size`.
+async fn read_body(body: &mut Body<'_>, max_size: usize) -> io::Result<Option<Vec<u8>>> {
+    let mut buf = match body.len() {
+        BodyLength::Known(length) if length > max_size => return Ok(None),
+        BodyLength::Known(length) => Vec::with_capacity(length),
+        BodyLength::Chunked => Vec::with_capacity(MIN_READ_SIZE),
+    };
+    while !body.is_empty() {
+        if buf.len() == buf.capacity() {
+            buf.reserve(MIN_READ_SIZE);
+        }
+        let len = buf.len();
+        buf = body.recv(buf).await?;
+        if buf.len() > max_size {
+            return Ok(None);
+        } else if buf.len() == len && !body.is_empty() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+    }
+    Ok(Some(buf))
+}
+
+/// Send the response to a request handled by an [`HttpActor`].
+///
+/// If the `Responder` is dropped without responding the client receives a
+/// `500 Internal Server Error` response.
+#[derive(Debug)]
+pub struct Responder {
+    sender: Sender<ActorResponse>,
+}
+
+impl Responder {
+    /// Send `response` to the client.
+    ///
+    /// Returns an error, containing the `response`, if the connection is
+    /// closed.
+    pub fn respond(self, response: ActorResponse) -> Result<(), ActorResponse> {
+        self.sender.try_send(response)
+    }
+
+    /// Returns `true` if the connection is still open.
+    pub fn is_connected(&self) -> bool {
+        self.sender.is_connected()
+    }
+}
@@ -21,6 +21,13 @@
 //! Graceful shutdown is done by sending it a [`Terminate`] message. The HTTP
 //! server can also handle (shutdown) process signals, see below for an example.
 //!
+//! Once the server stops accepting new connections it starts draining the
+//! existing connections. Responses send after this point will include the
+//! "Connection: close" header and idle connections are closed, i.e.
+//! [`Connection::next_request`] returns `Ok(None)`. Connections still open after
+//! the grace period (see [`Setup::with_grace_period`]) are closed, returning an
+//! error when reading from or writing to them.
+//!
 //! [`Terminate`]: heph::messages::Terminate
 //!
 //! # Examples
@@ -142,24 +149,35 @@
 //! }
 //! ```
 
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::fmt;
+use std::future::{poll_fn, Future};
 use std::io::{self, Write};
 use std::mem::{take, MaybeUninit};
 use std::net::SocketAddr;
-use std::time::SystemTime;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{self, Poll, Waker};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use heph::{actor, NewActor, Supervisor};
 use heph_rt::io::{BufMut, BufMutSlice};
+use heph_rt::net::tcp::server::{TrackedNewActor, TrackedSupervisor};
 use heph_rt::net::{tcp, TcpStream};
-use heph_rt::spawn::ActorOptions;
-use heph_rt::timer::DeadlinePassed;
+use heph_rt::spawn::{ActorOptions, Spawn};
+use heph_rt::timer::{DeadlinePassed, Timer};
+use heph_rt::util::either;
+use heph_rt::Access;
 use httpdate::HttpDate;
+use log::debug;
 
 use crate::body::{BodyLength, EmptyBody};
 use crate::head::header::{FromHeaderValue, Header, HeaderName, Headers};
 use crate::{
-    map_version_byte, trim_ws, Method, Request, Response, StatusCode, Version, BUF_SIZE,
-    INIT_HEAD_SIZE, MAX_HEADERS, MAX_HEAD_SIZE, MIN_READ_SIZE,
+    map_version_byte, parse_chunk, trim_ws, Chunk, ChunkError, Extensions, Method, Request,
+    Response, StatusCode, Version, BUF_SIZE, INIT_HEAD_SIZE, MAX_HEADERS, MAX_HEAD_SIZE,
+    MIN_READ_SIZE,
 };
 
 /// Create a new [server setup].
@@ -184,22 +202,156 @@ where
     S: Supervisor<HttpNewActor<NA>> + Clone + 'static,
     NA: NewActor<Argument = Connection> + Clone + 'static,
 {
-    let new_actor = HttpNewActor { new_actor };
-    tcp::server::setup(address, supervisor, new_actor, options)
+    let shutdown = Arc::new(Shutdown::new());
+    let new_actor = HttpNewActor {
+        new_actor,
+        shutdown: shutdown.clone(),
+    };
+    tcp::server::setup(address, supervisor, new_actor, options).map(|inner| Setup {
+        inner: inner.with_rejection_response(SERVICE_UNAVAILABLE_RESPONSE),
+        shutdown,
+        grace_period: DEFAULT_GRACE_PERIOD,
+    })
 }
 
+/// Default grace period, see [`Setup::with_grace_period`].
+pub const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
 /// A intermediate structure that implements [`NewActor`], creating an actor
 /// that spawn a new actor for each incoming HTTP connection.
 ///
 /// See [`setup`] to create this and the [module documentation] for examples.
 ///
 /// [module documentation]: crate::server
-pub type Setup<S, NA> = tcp::server::Setup<S, HttpNewActor<NA>>;
+#[derive(Debug)]
+pub struct Setup<S, NA> {
+    inner: tcp::server::Setup<S, HttpNewActor<NA>>,
+    /// Shared with all connections.
+    shutdown: Arc<Shutdown>,
+    grace_period: Duration,
+}
+
+impl<S, NA> Setup<S, NA> {
+    /// Returns the address the server is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.inner.local_addr()
+    }
+
+    /// Set the grace period used when shutting down, defaults to
+    /// [`DEFAULT_GRACE_PERIOD`].
+    ///
+    /// After the server stopped accepting new connections it waits for at
+    /// most `grace_period` for the existing connections to complete their
+    /// in-flight requests, after which the connections are closed.
+    pub const fn with_grace_period(mut self, grace_period: Duration) -> Setup<S, NA> {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// Set the maximum number of concurrent connections, defaults to no limit.
+    ///
+    /// Connections accepted while over the limit receive a 503 Service
+    /// Unavailable response and are closed. See
+    /// [`tcp::server::Setup::with_max_connections`].
+    pub fn with_max_connections(mut self, max: usize) -> Setup<S, NA> {
+        self.inner = self.inner.with_max_connections(max);
+        self
+    }
+
+    /// Set the maximum number of connections accepted per second, defaults to
+    /// no limit.
+    ///
+    /// Connections accepted while over the limit receive a 503 Service
+    /// Unavailable response and are closed. See
+    /// [`tcp::server::Setup::with_max_accept_rate`].
+    pub fn with_max_accept_rate(mut self, max: u32) -> Setup<S, NA> {
+        self.inner = self.inner.with_max_accept_rate(max);
+        self
+    }
+
+    /// Returns the number of connections currently open.
+    pub fn active_connections(&self) -> usize {
+        self.inner.active_connections()
+    }
+
+    /// Returns the number of connections rejected because one of the limits
+    /// was hit.
+    pub fn rejected_connections(&self) -> u64 {
+        self.inner.rejected_connections()
+    }
+}
+
+impl<S, NA> NewActor for Setup<S, NA>
+where
+    S: Supervisor<HttpNewActor<NA>> + Clone + 'static,
+    NA: NewActor<Argument = Connection> + Clone + 'static,
+    NA::RuntimeAccess: Access
+        + Clone
+        + Spawn<TrackedSupervisor<S>, TrackedNewActor<HttpNewActor<NA>>, NA::RuntimeAccess>,
+{
+    type Message = Message;
+    type Argument = ();
+    type Actor = impl Future<Output = Result<(), Error<NA::Error>>>;
+    type Error = !;
+    type RuntimeAccess = NA::RuntimeAccess;
+
+    fn new(
+        &mut self,
+        ctx: actor::Context<Self::Message, Self::RuntimeAccess>,
+        (): Self::Argument,
+    ) -> Result<Self::Actor, Self::Error> {
+        let rt = ctx.runtime_ref().clone();
+        let server = self.inner.new(ctx, ())?;
+        Ok(http_server(
+            server,
+            rt,
+            self.shutdown.clone(),
+            self.grace_period,
+        ))
+    }
+}
+
+impl<S, NA> Clone for Setup<S, NA> {
+    fn clone(&self) -> Setup<S, NA> {
+        Setup {
+            inner: self.inner.clone(),
+            shutdown: self.shutdown.clone(),
+            grace_period: self.grace_period,
+        }
+    }
+}
+
+/// Runs the TCP `server`, draining all connections once it stops.
+async fn http_server<Fut, E, RT>(
+    server: Fut,
+    rt: RT,
+    shutdown: Arc<Shutdown>,
+    grace_period: Duration,
+) -> Result<(), Error<E>>
+where
+    Fut: Future<Output = Result<(), Error<E>>>,
+    RT: Access,
+{
+    // NOTE: on error the supervisor can restart the server, so we only start
+    // draining the connections if the server stopped without error.
+    server.await?;
+
+    debug!(grace_period:? = grace_period; "HTTP server stopped, draining connections");
+    shutdown.set_state(DRAINING);
+    let drained = poll_fn(|ctx| shutdown.poll_drained(ctx));
+    let grace_period = Timer::after(rt, grace_period);
+    if either(drained, grace_period).await.is_err() {
+        debug!("HTTP server grace period passed, closing connections");
+        shutdown.set_state(CLOSED);
+    }
+    Ok(())
+}
 
 /// Maps `NA` to accept `TcpStream` as argument, creating a [`Connection`].
 #[derive(Debug, Clone)]
 pub struct HttpNewActor<NA> {
     new_actor: NA,
+    shutdown: Arc<Shutdown>,
 }
 
 impl<NA> NewActor for HttpNewActor<NA>
@@ -217,7 +369,7 @@ where
         ctx: actor::Context<Self::Message, Self::RuntimeAccess>,
         stream: Self::Argument,
     ) -> Result<Self::Actor, Self::Error> {
-        let conn = Connection::new(stream);
+        let conn = Connection::new(stream, self.shutdown.clone());
         self.new_actor.new(ctx, conn)
     }
 
@@ -249,17 +401,38 @@ pub struct Connection {
     last_version: Option<Version>,
     /// The HTTP method of the last request.
     last_method: Option<Method>,
+    /// Shutdown state of the server.
+    shutdown: Arc<Shutdown>,
+    /// Id used in `shutdown`.
+    shutdown_id: usize,
+    /// Waker registered with `shutdown`, used to only (re)register the waker
+    /// if it changed.
+    shutdown_waker: Option<Waker>,
+    /// Close the connection after the current request, sending the
+    /// "Connection: close" header.
+    closing: bool,
+    /// Maximum size of a request body, see [`Connection::set_max_body_size`].
+    max_body_size: usize,
+    /// Extensions for the entire connection.
+    extensions: Extensions,
 }
 
 impl Connection {
     /// Create a new `Connection`.
-    fn new(stream: TcpStream) -> Connection {
+    fn new(stream: TcpStream, shutdown: Arc<Shutdown>) -> Connection {
+        let shutdown_id = shutdown.register();
         Connection {
             stream,
             buf: Vec::with_capacity(BUF_SIZE),
             parsed_bytes: 0,
             last_version: None,
             last_method: None,
+            shutdown,
+            shutdown_id,
+            shutdown_waker: None,
+            closing: false,
+            max_body_size: usize::MAX,
+            extensions: Extensions::new(),
         }
     }
 
@@ -275,12 +448,22 @@ impl Connection {
     /// Also see the [`Connection::last_request_version`] and
     /// [`Connection::last_request_method`] functions to properly respond to
     /// request errors.
+    ///
+    /// If the server is shutting down this returns `Ok(None)` once there are no
+    /// more (partial) requests to read, see the [module documentation].
+    ///
+    /// [module documentation]: crate::server#graceful-shutdown
     #[allow(clippy::too_many_lines)] // TODO.
     pub async fn next_request<'a>(&'a mut self) -> Result<Option<Request<Body<'a>>>, RequestError> {
         // NOTE: not resetting the version as that doesn't change between
         // requests.
         self.last_method = None;
 
+        if self.closing {
+            // Send "Connection: close" in the last response.
+            return Ok(None);
+        }
+
         let mut too_short = 0;
         loop {
             // In case of pipelined requests it could be that while reading a
@@ -293,7 +476,17 @@ impl Connection {
                 // while we have less than `too_short` bytes we try to receive
                 // some more bytes.
 
-                if self.recv().await? {
+                let done = if self.parsed_bytes >= self.buf.len() {
+                    // Waiting on a new request, stop if the server is shutting
+                    // down.
+                    match self.recv_until(DRAINING).await? {
+                        Some(done) => done,
+                        None => return Ok(None),
+                    }
+                } else {
+                    self.recv().await?
+                };
+                if done {
                     return if self.buf.is_empty() {
                         // Read the entire stream, so we're done.
                         Ok(None)
@@ -326,6 +519,7 @@ impl Connection {
 
                     // RFC 7230 section 3.3.3 Message Body Length.
                     let mut body_length: Option<BodyLength> = None;
+                    let mut expect_continue = false;
                     let headers =
                         Headers::from_httparse_headers(request.headers, |name, value| {
                             if *name == HeaderName::CONTENT_LENGTH {
@@ -407,38 +601,79 @@ impl Connection {
                                         _ => return Err(RequestError::UnsupportedTransferEncoding),
                                     }
                                 }
+                            } else if *name == HeaderName::EXPECT {
+                                // RFC 9110 section 10.1.1:
+                                // > A server that receives a 100-continue
+                                // > expectation in an HTTP/1.0 request MUST
+                                // > ignore that expectation.
+                                if matches!(version, Version::Http10) {
+                                    return Ok(());
+                                }
+                                if trim_ws(value).eq_ignore_ascii_case(b"100-continue") {
+                                    expect_continue = true;
+                                } else {
+                                    // > A server that receives an Expect field
+                                    // > value containing a member other than
+                                    // > 100-continue MAY respond with a 417
+                                    // > (Expectation Failed) status code to
+                                    // > indicate that the unexpected
+                                    // > expectation cannot be met.
+                                    return Err(RequestError::ExpectationFailed);
+                                }
                             }
                             Ok(())
                         })?;
 
+                    // Reject the body based on the declared length, before the
+                    // client sends it (if it expects a 100 Continue response).
+                    if let Some(BodyLength::Known(length)) = body_length {
+                        if length > self.max_body_size {
+                            return Err(RequestError::BodyTooLarge);
+                        }
+                    }
+
                     let kind = match body_length {
                         Some(BodyLength::Known(left)) => BodyKind::Oneshot { left },
-                        Some(BodyLength::Chunked) => {
-                            #[allow(clippy::cast_possible_truncation)] // For truncate below.
-                            match httparse::parse_chunk_size(&self.buf[self.parsed_bytes..]) {
-                                Ok(httparse::Status::Complete((idx, chunk_size))) => {
-                                    self.parsed_bytes += idx;
-                                    BodyKind::Chunked {
-                                        // FIXME: add check here. It's fine on
-                                        // 64 bit (only currently supported).
-                                        left_in_chunk: chunk_size as usize,
-                                        read_complete: chunk_size == 0,
-                                    }
-                                }
-                                Ok(httparse::Status::Partial) => BodyKind::Chunked {
-                                    left_in_chunk: 0,
-                                    read_complete: false,
-                                },
-                                Err(_) => return Err(RequestError::InvalidChunkSize),
-                            }
-                        }
+                        Some(BodyLength::Chunked) => BodyKind::Chunked {
+                            left_in_chunk: 0,
+                            read_complete: false,
+                            after_data: false,
+                        },
                         // RFC 7230 section 3.3.3 point 6:
                         // > If this is a request message and none of the above
                         // > are true, then the message body length is zero (no
                         // > message body is present).
                         None => BodyKind::Oneshot { left: 0 },
                     };
-                    let body = Body { conn: self, kind };
+                    let mut body = Body {
+                        conn: self,
+                        kind,
+                        trailers: Headers::EMPTY,
+                        expect_continue,
+                    };
+                    if let BodyKind::Chunked {
+                        left_in_chunk,
+                        read_complete,
+                        after_data,
+                    } = &mut body.kind
+                    {
+                        // Try to parse the first chunk using the bytes already
+                        // read, so that `Body::is_empty` is accurate.
+                        _ = body.conn.parse_chunk(
+                            left_in_chunk,
+                            read_complete,
+                            after_data,
+                            &mut body.trailers,
+                        )?;
+                    }
+                    if body.is_empty() {
+                        // RFC 9110 section 10.1.1:
+                        // > A server MAY omit sending a 100 (Continue)
+                        // > response if it has already received some or all of
+                        // > the content for the corresponding request, or if
+                        // > the framing indicates that there is no content.
+                        body.expect_continue = false;
+                    }
                     return Ok(Some(Request::new(method, path, version, headers, body)));
                 }
                 Ok(httparse::Status::Partial) => {
@@ -576,10 +811,15 @@ impl Connection {
     /// # Notes
     ///
     /// This automatically sets the "Content-Length" or "Transfer-Encoding",
-    /// "Connection" and "Date" headers if not provided in `headers`.
+    /// "Connection" and "Date" headers if not provided in `headers`. If the
+    /// server is shutting down the "Connection: close" header is set, unless
+    /// the "Connection" header is provided in `headers`.
     ///
     /// If `request_method.`[`expects_body()`] or `status.`[`includes_body()`]
-    /// returns `false` this will not write the body to the connection.
+    /// returns `false` this will not write the body to the connection. For HEAD
+    /// requests the "Content-Length" header is still set to the length of the
+    /// `body`, as it would be for a GET request, so handlers don't have to
+    /// special case HEAD requests.
     ///
     /// [`expects_body()`]: Method::expects_body
     /// [`includes_body()`]: StatusCode::includes_body
@@ -597,6 +837,11 @@ impl Connection {
     {
         let mut itoa_buf = itoa::Buffer::new();
 
+        let state = self.shutdown.state();
+        if state == CLOSED {
+            return Err(shutdown_error());
+        }
+
         // Clear bytes from the previous request, keeping the bytes of any
         // unprocessed request(s).
         self.clear_buffer();
@@ -610,12 +855,17 @@ impl Connection {
         };
 
         // Format the status-line (RFC 7230 section 3.1.2).
-        http_head.extend_from_slice(version.as_str().as_bytes());
-        http_head.push(b' ');
-        http_head.extend_from_slice(itoa_buf.format(status.0).as_bytes());
-        // NOTE: we're not sending a reason-phrase, but the space is required
-        // before \r\n.
-        http_head.extend_from_slice(b" \r\n");
+        if let (Version::Http11, StatusCode::OK) = (version, status) {
+            // Fast path for the most common status-line.
+            http_head.extend_from_slice(OK_STATUS_LINE);
+        } else {
+            http_head.extend_from_slice(version.as_str().as_bytes());
+            http_head.push(b' ');
+            http_head.extend_from_slice(itoa_buf.format(status.0).as_bytes());
+            // NOTE: we're not sending a reason-phrase, but the space is
+            // required before \r\n.
+            http_head.extend_from_slice(b" \r\n");
+        }
 
         // Format the headers (RFC 7230 section 3.2).
         let mut set_connection_header = false;
@@ -645,7 +895,12 @@ impl Connection {
         }
 
         // Provide the "Connection" header if the user didn't.
-        if !set_connection_header && matches!(version, Version::Http10) {
+        if !set_connection_header && (state == DRAINING || self.closing) {
+            // Server is shutting down, or we can't read the next request, so
+            // close the connection after this response.
+            http_head.extend_from_slice(b"Connection: close\r\n");
+            self.closing = true;
+        } else if !set_connection_header && matches!(version, Version::Http10) {
             // Per RFC 7230 section 6.3, HTTP/1.0 needs the "Connection:
             // keep-alive" header to persistent the connection. Connections
             // using HTTP/1.1 persistent by default.
@@ -654,19 +909,20 @@ impl Connection {
 
         // Provide the "Date" header if the user didn't.
         if !set_date_header {
-            let now = HttpDate::from(SystemTime::now());
-            write!(&mut http_head, "Date: {now}\r\n").unwrap();
+            extend_date_header(&mut http_head);
         }
 
         // Provide the "Conent-Length" or "Transfer-Encoding" header if the user
         // didn't.
-        let mut send_body = true;
+        let send_body = request_method.expects_body() && status.includes_body();
         if !set_content_length_header && !set_transfer_encoding_header {
             match body.length() {
-                _ if !request_method.expects_body() || !status.includes_body() => {
-                    send_body = false;
+                _ if !status.includes_body() => {
                     extend_content_length_header(&mut http_head, &mut itoa_buf, 0);
                 }
+                // NOTE: for HEAD requests we send the headers as we would for
+                // a GET request, but without sending the body (RFC 9110
+                // section 9.3.2).
                 BodyLength::Known(length) => {
                     extend_content_length_header(&mut http_head, &mut itoa_buf, length);
                 }
@@ -680,10 +936,25 @@ impl Connection {
         http_head.extend_from_slice(b"\r\n");
 
         // Write the response to the stream.
+        let shutdown = &*self.shutdown;
+        let id = self.shutdown_id;
+        let stream = &mut self.stream;
+        let shutdown_waker = &mut self.shutdown_waker;
         let mut http_head = if send_body {
-            body.write_message(&mut self.stream, http_head).await?
+            shutdown
+                .until(
+                    id,
+                    shutdown_waker,
+                    CLOSED,
+                    body.write_message(stream, http_head),
+                )
+                .await
+                .ok_or_else(shutdown_error)??
         } else {
-            self.stream.send_all(http_head).await?
+            shutdown
+                .until(id, shutdown_waker, CLOSED, stream.send_all(http_head))
+                .await
+                .ok_or_else(shutdown_error)??
         };
 
         if self.buf.is_empty() {
@@ -695,6 +966,19 @@ impl Connection {
         Ok(())
     }
 
+    /// Returns the extensions of the connection.
+    ///
+    /// Unlike the extensions of a [`Request`], these are kept for the lifetime
+    /// of the connection, i.e. across requests.
+    pub const fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Returns mutable access to the extensions of the connection.
+    pub const fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
     /// See [`TcpStream::peer_addr`].
     pub fn peer_addr(&mut self) -> io::Result<SocketAddr> {
         self.stream.peer_addr()
@@ -715,44 +999,110 @@ impl Connection {
         self.stream.nodelay()
     }
 
+    /// Set the maximum size of a request body, defaults to no limit.
+    ///
+    /// Requests with a "Content-Length" header larger than `max_body_size` are
+    /// rejected by [`Connection::next_request`] with
+    /// [`RequestError::BodyTooLarge`]. If the client send the "Expect:
+    /// 100-continue" header this happens before it sends the body.
+    pub fn set_max_body_size(&mut self, max_body_size: usize) {
+        self.max_body_size = max_body_size;
+    }
+
+    /// Returns the maximum size of a request body, see
+    /// [`Connection::set_max_body_size`].
+    pub fn max_body_size(&self) -> usize {
+        self.max_body_size
+    }
+
+    /// Send a 100 (Continue) response, RFC 9110 section 15.2.1.
+    async fn send_continue(&mut self) -> io::Result<()> {
+        let send = self.stream.send_all(CONTINUE_RESPONSE);
+        _ = self
+            .shutdown
+            .until(self.shutdown_id, &mut self.shutdown_waker, CLOSED, send)
+            .await
+            .ok_or_else(shutdown_error)??;
+        Ok(())
+    }
+
     async fn read_chunk(
         &mut self,
         // Fields of `BodyKind::Chunked`:
         left_in_chunk: &mut usize,
         read_complete: &mut bool,
+        after_data: &mut bool,
+        // Field of `Body`:
+        trailers: &mut Headers,
     ) -> Result<(), RequestError> {
-        loop {
-            match httparse::parse_chunk_size(&self.buf[self.parsed_bytes..]) {
-                #[allow(clippy::cast_possible_truncation)] // For truncate below.
-                Ok(httparse::Status::Complete((idx, chunk_size))) => {
-                    self.parsed_bytes += idx;
-                    if chunk_size == 0 {
+        while !self.parse_chunk(left_in_chunk, read_complete, after_data, trailers)? {
+            if self.recv().await? {
+                return Err(RequestError::IncompleteRequest);
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse the next chunk from the buffer, returns `false` if more bytes are
+    /// needed.
+    fn parse_chunk(
+        &mut self,
+        // Fields of `BodyKind::Chunked`:
+        left_in_chunk: &mut usize,
+        read_complete: &mut bool,
+        after_data: &mut bool,
+        // Field of `Body`:
+        trailers: &mut Headers,
+    ) -> Result<bool, RequestError> {
+        match parse_chunk(&self.buf[self.parsed_bytes..], *after_data) {
+            Ok(Some((idx, chunk))) => {
+                self.parsed_bytes += idx;
+                match chunk {
+                    Chunk::Data { size } => {
+                        *left_in_chunk = size;
+                        *after_data = true;
+                    }
+                    Chunk::Last { trailers: t } => {
                         *read_complete = true;
+                        *trailers = t;
                     }
-                    // FIXME: add check here. It's fine on 64 bit (only currently
-                    // supported).
-                    *left_in_chunk = chunk_size as usize;
-                    return Ok(());
                 }
-                Ok(httparse::Status::Partial) => {} // Read some more data below.
-                Err(_) => return Err(RequestError::InvalidChunkSize),
-            }
-
-            if self.recv().await? {
-                return Err(RequestError::IncompleteRequest);
+                Ok(true)
             }
+            Ok(None) => Ok(false),
+            Err(ChunkError::InvalidSize) => Err(RequestError::InvalidChunkSize),
+            Err(ChunkError::Trailer(err)) => Err(RequestError::from_httparse(err)),
         }
     }
 
     /// Returns true if we read all bytes (i.e. we read 0 bytes).
     async fn recv(&mut self) -> io::Result<bool> {
+        self.recv_until(CLOSED).await?.ok_or_else(shutdown_error)
+    }
+
+    /// Same as [`Connection::recv`], but returns `None` if the server reached
+    /// shutdown `state` before receiving any bytes.
+    ///
+    /// # Notes
+    ///
+    /// If `None` is returned the buffer is lost.
+    async fn recv_until(&mut self, state: u8) -> io::Result<Option<bool>> {
         // Ensure we have space in the buffer to read into.
         self.clear_buffer();
         self.buf.reserve(MIN_READ_SIZE);
 
         let buf_len = self.buf.len();
-        self.buf = self.stream.recv(take(&mut self.buf)).await?;
-        Ok(self.buf.len() == buf_len)
+        let recv = self.stream.recv(take(&mut self.buf));
+        let shutdown = self
+            .shutdown
+            .until(self.shutdown_id, &mut self.shutdown_waker, state, recv);
+        match shutdown.await {
+            Some(buf) => {
+                self.buf = buf?;
+                Ok(Some(self.buf.len() == buf_len))
+            }
+            None => Ok(None),
+        }
     }
 
     /// Clear parsed request(s) from the buffer.
@@ -768,6 +1118,183 @@ impl Connection {
     }
 }
 
+/// Server is running.
+const RUNNING: u8 = 0;
+/// Server stopped accepting new connections, draining existing connections.
+const DRAINING: u8 = 1;
+/// Grace period passed, all connections should be closed.
+const CLOSED: u8 = 2;
+
+/// Shutdown state shared between the HTTP server(s) and all its connections.
+#[derive(Debug)]
+struct Shutdown {
+    /// Either [`RUNNING`], [`DRAINING`] or [`CLOSED`].
+    state: AtomicU8,
+    connections: Mutex<Connections>,
+}
+
+#[derive(Debug)]
+struct Connections {
+    /// Id for the next connection.
+    next_id: usize,
+    /// Wakers for all connections, to wake them on state changes. The
+    /// connections only update their waker if it changed, so these are not
+    /// removed when woken.
+    wakers: HashMap<usize, Option<Waker>>,
+    /// Wakers for the server(s) waiting for all connections to be closed.
+    servers: Vec<Waker>,
+}
+
+impl Shutdown {
+    fn new() -> Shutdown {
+        Shutdown {
+            state: AtomicU8::new(RUNNING),
+            connections: Mutex::new(Connections {
+                next_id: 0,
+                wakers: HashMap::new(),
+                servers: Vec::new(),
+            }),
+        }
+    }
+
+    fn state(&self) -> u8 {
+        self.state.load(Ordering::Acquire)
+    }
+
+    /// Set the `state`, waking all connections.
+    fn set_state(&self, state: u8) {
+        _ = self.state.fetch_max(state, Ordering::AcqRel);
+        let connections = self.connections.lock().unwrap();
+        for waker in connections.wakers.values().flatten() {
+            waker.wake_by_ref();
+        }
+    }
+
+    /// Register a new connection, returning its id.
+    fn register(&self) -> usize {
+        let mut connections = self.connections.lock().unwrap();
+        let id = connections.next_id;
+        connections.next_id += 1;
+        _ = connections.wakers.insert(id, None);
+        id
+    }
+
+    /// Deregister the connection with `id`.
+    fn deregister(&self, id: usize) {
+        let mut connections = self.connections.lock().unwrap();
+        _ = connections.wakers.remove(&id);
+        if connections.wakers.is_empty() {
+            for waker in connections.servers.drain(..) {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Poll until all connections are deregistered.
+    fn poll_drained(&self, ctx: &mut task::Context<'_>) -> Poll<()> {
+        let mut connections = self.connections.lock().unwrap();
+        if connections.wakers.is_empty() {
+            return Poll::Ready(());
+        }
+        let waker = ctx.waker();
+        if !connections.servers.iter().any(|w| w.will_wake(waker)) {
+            connections.servers.push(waker.clone());
+        }
+        Poll::Pending
+    }
+
+    /// Poll until the server reached `state`.
+    ///
+    /// `registered` is the waker last registered for connection `id`, the
+    /// lock is only taken if the waker changed, which normally only happens the
+    /// first time.
+    fn poll_state(
+        &self,
+        id: usize,
+        registered: &mut Option<Waker>,
+        state: u8,
+        ctx: &mut task::Context<'_>,
+    ) -> Poll<()> {
+        if self.state() >= state {
+            return Poll::Ready(());
+        }
+        let waker = ctx.waker();
+        if !registered.as_ref().is_some_and(|w| w.will_wake(waker)) {
+            let mut connections = self.connections.lock().unwrap();
+            if let Some(w) = connections.wakers.get_mut(&id) {
+                *w = Some(waker.clone());
+            }
+            drop(connections);
+            *registered = Some(waker.clone());
+        }
+        // Check again in case the state changed while we registered the waker.
+        if self.state() >= state {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    /// Run `future` until it completes, or returns `None` if the server reaches
+    /// `state` first (for connection `id`), see [`Shutdown::poll_state`].
+    async fn until<Fut: Future>(
+        &self,
+        id: usize,
+        registered: &mut Option<Waker>,
+        state: u8,
+        future: Fut,
+    ) -> Option<Fut::Output> {
+        let state_reached = poll_fn(|ctx| self.poll_state(id, registered, state, ctx));
+        either(future, state_reached).await.ok()
+    }
+}
+
+/// Error returned when the connection is closed because the server is shutting
+/// down.
+fn shutdown_error() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::ConnectionAborted,
+        "HTTP server is shutting down, closing connection",
+    )
+}
+
+/// 100 (Continue) response, send when the client expects it before sending the
+/// request body.
+const CONTINUE_RESPONSE: &[u8] = b"HTTP/1.1 100 \r\n\r\n";
+
+/// 503 (Service Unavailable) response, send to connections rejected by the
+/// server's admission control.
+const SERVICE_UNAVAILABLE_RESPONSE: &[u8] =
+    b"HTTP/1.1 503 \r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+/// Status-line for a HTTP/1.1 200 OK response.
+const OK_STATUS_LINE: &[u8] = b"HTTP/1.1 200 \r\n";
+
+/// Length of the "Date" header, e.g. `Date: Sun, 06 Nov 1994 08:49:37 GMT\r\n`.
+const DATE_HEADER_LEN: usize = 37;
+
+thread_local! {
+    /// Cached "Date" header and the second (since the Unix epoch) at which it
+    /// was formatted, formatted at most once per second per thread.
+    static DATE_HEADER: Cell<(u64, [u8; DATE_HEADER_LEN])> =
+        const { Cell::new((u64::MAX, [0; DATE_HEADER_LEN])) };
+}
+
+/// Add the "Date" header for the current time to `buf`.
+fn extend_date_header(buf: &mut Vec<u8>) {
+    let now = SystemTime::now();
+    let secs = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    DATE_HEADER.with(|cached| {
+        let (cached_secs, mut header) = cached.get();
+        if cached_secs != secs {
+            let now = HttpDate::from(now);
+            write!(&mut header[..], "Date: {now}\r\n").unwrap();
+            cached.set((secs, header));
+        }
+        buf.extend_from_slice(&header);
+    });
+}
+
 /// Add "Content-Length" header to `buf`.
 fn extend_content_length_header(
     buf: &mut Vec<u8>,
@@ -779,16 +1306,35 @@ fn extend_content_length_header(
     buf.extend_from_slice(b"\r\n");
 }
 
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.shutdown.deregister(self.shutdown_id);
+    }
+}
+
 /// Body of HTTP [`Request`] read from a [`Connection`].
 ///
 /// # Notes
 ///
 /// If the body is not (completely) read before this is dropped it will still
-/// removed from the `Connection`.
+/// removed from the `Connection`. If that's not possible, e.g. because the
+/// client is waiting on a 100 (Continue) response before sending the body, the
+/// connection is closed after the response.
+///
+/// # Expect: 100-continue
+///
+/// If the client send the "Expect: 100-continue" header a 100 (Continue)
+/// response is automatically send when first receiving bytes from the body,
+/// i.e. in [`Body::recv`] or [`Body::recv_vectored`]. Responding without
+/// reading the body rejects it.
 #[derive(Debug)]
 pub struct Body<'a> {
     conn: &'a mut Connection,
     kind: BodyKind,
+    /// Trailer section of a chunked body.
+    trailers: Headers,
+    /// Client expects a 100 (Continue) response before sending the body.
+    expect_continue: bool,
 }
 
 #[derive(Debug)]
@@ -804,6 +1350,9 @@ enum BodyKind {
         left_in_chunk: usize,
         /// Read all chunks.
         read_complete: bool,
+        /// Read the data of a chunk, meaning the next chunk starts with the
+        /// CRLF ending that data.
+        after_data: bool,
     },
 }
 
@@ -845,6 +1394,7 @@ impl<'a> Body<'a> {
             BodyKind::Chunked {
                 left_in_chunk,
                 read_complete,
+                ..
             } => read_complete && left_in_chunk == 0,
         }
     }
@@ -854,6 +1404,15 @@ impl<'a> Body<'a> {
         matches!(self.kind, BodyKind::Chunked { .. })
     }
 
+    /// Returns the trailer section of a chunked body.
+    ///
+    /// The trailers are only available once the entire body is read, i.e. when
+    /// [`Body::is_empty`] returns `true`. Before that, or if the body is not
+    /// chunked, this returns no headers.
+    pub const fn trailers(&self) -> &Headers {
+        &self.trailers
+    }
+
     /// Receive bytes from the request body, writing them into `buf`.
     pub async fn recv<B: BufMut>(&mut self, mut buf: B) -> io::Result<B> {
         loop {
@@ -862,6 +1421,11 @@ impl<'a> Body<'a> {
                 return Ok(buf);
             }
 
+            if self.expect_continue {
+                self.conn.send_continue().await?;
+                self.expect_continue = false;
+            }
+
             // First try to copy already buffered bytes.
             let buf_bytes = self.buf_bytes();
             if !buf_bytes.is_empty() {
@@ -882,9 +1446,17 @@ impl<'a> Body<'a> {
                 BodyKind::Chunked {
                     left_in_chunk,
                     read_complete,
+                    after_data,
                 } => {
                     if *left_in_chunk == 0 {
-                        self.conn.read_chunk(left_in_chunk, read_complete).await?;
+                        self.conn
+                            .read_chunk(
+                                left_in_chunk,
+                                read_complete,
+                                after_data,
+                                &mut self.trailers,
+                            )
+                            .await?;
                         // Read from the client's buffer again.
                         continue;
                     }
@@ -893,9 +1465,15 @@ impl<'a> Body<'a> {
             };
 
             let len_before = buf.spare_capacity();
-            let limited_buf = self.conn.stream.recv(buf.limit(limit)).await?;
+            let conn = &mut *self.conn;
+            let recv = conn.stream.recv(buf.limit(limit));
+            let limited_buf = conn
+                .shutdown
+                .until(conn.shutdown_id, &mut conn.shutdown_waker, CLOSED, recv)
+                .await
+                .ok_or_else(shutdown_error)??;
             let buf = limited_buf.into_inner();
-            self.processed(buf.spare_capacity() - len_before);
+            self.received(len_before - buf.spare_capacity());
             return Ok(buf);
         }
     }
@@ -912,6 +1490,11 @@ impl<'a> Body<'a> {
                 return Ok(bufs);
             }
 
+            if self.expect_continue {
+                self.conn.send_continue().await?;
+                self.expect_continue = false;
+            }
+
             // First try to copy already buffered bytes.
             let buf_bytes = self.buf_bytes();
             if !buf_bytes.is_empty() {
@@ -932,9 +1515,17 @@ impl<'a> Body<'a> {
                 BodyKind::Chunked {
                     left_in_chunk,
                     read_complete,
+                    after_data,
                 } => {
                     if *left_in_chunk == 0 {
-                        self.conn.read_chunk(left_in_chunk, read_complete).await?;
+                        self.conn
+                            .read_chunk(
+                                left_in_chunk,
+                                read_complete,
+                                after_data,
+                                &mut self.trailers,
+                            )
+                            .await?;
                         // Read from the client's buffer again.
                         continue;
                     }
@@ -943,9 +1534,15 @@ impl<'a> Body<'a> {
             };
 
             let len_before = bufs.total_spare_capacity();
-            let limited_bufs = self.conn.stream.recv_vectored(bufs.limit(limit)).await?;
+            let conn = &mut *self.conn;
+            let recv = conn.stream.recv_vectored(bufs.limit(limit));
+            let limited_bufs = conn
+                .shutdown
+                .until(conn.shutdown_id, &mut conn.shutdown_waker, CLOSED, recv)
+                .await
+                .ok_or_else(shutdown_error)??;
             let bufs = limited_bufs.into_inner();
-            self.processed(bufs.total_spare_capacity() - len_before);
+            self.received(len_before - bufs.total_spare_capacity());
             return Ok(bufs);
         }
     }
@@ -967,14 +1564,20 @@ impl<'a> Body<'a> {
         }
     }
 
-    /// Mark `n` bytes are processed.
+    /// Mark `n` bytes in the buffer as processed.
     fn processed(&mut self, n: usize) {
+        self.received(n);
+        self.conn.parsed_bytes += n;
+    }
+
+    /// Mark `n` bytes as received directly from the stream, i.e. without
+    /// going through the buffer.
+    fn received(&mut self, n: usize) {
         // TODO: should this be `unsafe`? We don't do underflow checks...
         match &mut self.kind {
             BodyKind::Oneshot { left } => *left -= n,
             BodyKind::Chunked { left_in_chunk, .. } => *left_in_chunk -= n,
         }
-        self.conn.parsed_bytes += n;
     }
 }
 
@@ -985,6 +1588,18 @@ impl<'a> Drop for Body<'a> {
             return;
         }
 
+        if self.expect_continue {
+            // RFC 9110 section 10.1.1:
+            // > A server that responds with a final status code before reading
+            // > the entire request content SHOULD indicate whether it intends
+            // > to close the connection (e.g., see Section 9.6 of [HTTP/1.1])
+            // > or continue reading the request content.
+            // The client might not send the body at all, so we can't read the
+            // next request.
+            self.conn.closing = true;
+            return;
+        }
+
         // Mark the entire body as parsed.
         // NOTE: `Connection` handles the case where we didn't read the entire
         // body yet.
@@ -993,13 +1608,15 @@ impl<'a> Drop for Body<'a> {
             BodyKind::Chunked {
                 left_in_chunk,
                 read_complete,
+                ..
             } => {
                 if read_complete {
                     // Read all chunks.
                     debug_assert_eq!(left_in_chunk, 0);
                 } else {
-                    // FIXME: don't panic here.
-                    todo!("remove chunked body from connection");
+                    // We don't know where the body ends without reading it, so
+                    // we can't read the next request.
+                    self.conn.closing = true;
                 }
             }
         }
@@ -1052,6 +1669,13 @@ pub enum RequestError {
     UnknownMethod,
     /// Chunk size is invalid.
     InvalidChunkSize,
+    /// Request has an "Expect" header with an expectation other than
+    /// "100-continue".
+    ExpectationFailed,
+    /// Request body is larger than the [maximum body size].
+    ///
+    /// [maximum body size]: Connection::set_max_body_size
+    BodyTooLarge,
     /// I/O error.
     Io(io::Error),
 }
@@ -1086,6 +1710,8 @@ impl RequestError {
             // > implemented by an origin server, the origin server SHOULD
             // > respond with the 501 (Not Implemented) status code.
             | UnknownMethod => StatusCode::NOT_IMPLEMENTED,
+            ExpectationFailed => StatusCode::EXPECTATION_FAILED,
+            BodyTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
             Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -1111,6 +1737,9 @@ impl RequestError {
             | InvalidNewLine
             | InvalidVersion
             | InvalidChunkSize
+            // The client might be sending the body, which we don't read.
+            | ExpectationFailed
+            | BodyTooLarge
             | Io(_) => true,
             UnknownMethod => false,
         }
@@ -1168,6 +1797,8 @@ impl RequestError {
             InvalidVersion => "invalid version",
             UnknownMethod => "unknown method",
             InvalidChunkSize => "invalid chunk size",
+            ExpectationFailed => "expectation failed",
+            BodyTooLarge => "body too large",
             Io(_) => "I/O error",
         }
     }
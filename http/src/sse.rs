@@ -0,0 +1,314 @@
+//! Module with types to support server-sent events (SSE).
+//!
+//! Server-sent events allow the server to push events to the client over a
+//! long-lived HTTP response. See the [HTML specification] for more information.
+//!
+//! The [`EventStream`] response body keeps the connection open, sending each
+//! [`Event`] send using the accompanying [`ActorRef`] to the client. Once all
+//! actor references are dropped the response is completed.
+//!
+//! [HTML specification]: https://html.spec.whatwg.org/multipage/server-sent-events.html
+//!
+//! # Examples
+//!
+//! ```
+//! # #![allow(dead_code)]
+//! use std::io;
+//! use std::time::Duration;
+//!
+//! use heph::actor::{self, actor_fn};
+//! use heph::supervisor::NoSupervisor;
+//! use heph::ActorRef;
+//! use heph_http::sse::{Event, EventStream};
+//! use heph_http::Connection;
+//! use heph_rt::spawn::ActorOptions;
+//! use heph_rt::timer::Timer;
+//! use heph_rt::ThreadLocal;
+//!
+//! async fn http_actor(
+//!     mut ctx: actor::Context<(), ThreadLocal>,
+//!     mut connection: Connection,
+//! ) -> io::Result<()> {
+//!     // Read the request, ignoring the body.
+//!     drop(connection.next_request().await?);
+//!     let (stream, events) = EventStream::new(ctx.runtime_ref().clone());
+//!     // Push the events from another actor.
+//!     let ticker = actor_fn(ticker);
+//!     _ = ctx.runtime().spawn_local(NoSupervisor, ticker, events, ActorOptions::default());
+//!     // Send the response, this returns once all `events` are dropped.
+//!     connection.respond_with(stream.into_response()).await
+//! }
+//!
+//! async fn ticker(ctx: actor::Context<(), ThreadLocal>, events: ActorRef<Event>) {
+//!     for n in 0..10 {
+//!         Timer::after(ctx.runtime_ref().clone(), Duration::from_secs(1)).await;
+//!         let event = Event::new(format!("tick {n}")).with_event("tick");
+//!         if events.send(event).await.is_err() {
+//!             // Client disconnected.
+//!             return;
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::future::{poll_fn, Future};
+use std::io::{self, Write};
+use std::iter;
+use std::pin::{pin, Pin};
+use std::task::Poll;
+use std::time::Duration;
+
+use heph::ActorRef;
+use heph_rt::net::TcpStream;
+use heph_rt::timer::Timer;
+use heph_rt::Access;
+
+use crate::body::{BodyLength, PrivateBody, LAST_CHUNK};
+use crate::{Header, HeaderName, Response};
+
+/// Default interval after which a keep-alive comment is send, see
+/// [`EventStream::with_keep_alive`].
+pub const DEFAULT_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// Comment send to keep the connection alive.
+const KEEP_ALIVE_COMMENT: &[u8] = b": keep-alive\n\n";
+
+/// A single server-sent event.
+///
+/// # Notes
+///
+/// The event type and id may not contain line breaks (`\n` or `\r`), if they
+/// do they are removed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Event {
+    event: Option<String>,
+    data: String,
+    id: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl Event {
+    /// Create a new event with `data`.
+    ///
+    /// `data` may contain multiple lines (separated by `\r\n`, `\n` or `\r`),
+    /// each line will be send as separate `data:` field.
+    pub fn new<D: Into<String>>(data: D) -> Event {
+        Event {
+            event: None,
+            data: data.into(),
+            id: None,
+            retry: None,
+        }
+    }
+
+    /// Set the event type, send as the `event:` field.
+    pub fn with_event<E: Into<String>>(mut self, event: E) -> Event {
+        self.event = Some(remove_line_breaks(event.into()));
+        self
+    }
+
+    /// Set the event id, send as the `id:` field.
+    pub fn with_id<I: Into<String>>(mut self, id: I) -> Event {
+        self.id = Some(remove_line_breaks(id.into()));
+        self
+    }
+
+    /// Set the reconnection time, send as the `retry:` field.
+    pub const fn with_retry(mut self, retry: Duration) -> Event {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Returns the data of the event.
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    /// Returns the event type, if any.
+    pub fn event(&self) -> Option<&str> {
+        self.event.as_deref()
+    }
+
+    /// Returns the event id, if any.
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Returns the reconnection time, if any.
+    pub const fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+
+    /// Encode the event in the `text/event-stream` format, writing it to `buf`.
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        if let Some(event) = &self.event {
+            write_field(buf, "event", event);
+        }
+        if let Some(id) = &self.id {
+            write_field(buf, "id", id);
+        }
+        if let Some(retry) = self.retry {
+            let mut itoa_buf = itoa::Buffer::new();
+            write_field(buf, "retry", itoa_buf.format(retry.as_millis()));
+        }
+        for line in split_lines(&self.data) {
+            write_field(buf, "data", line);
+        }
+        // An empty line dispatches the event.
+        buf.push(b'\n');
+    }
+}
+
+/// Write a single field `name: value\n` to `buf`.
+fn write_field(buf: &mut Vec<u8>, name: &str, value: &str) {
+    buf.extend_from_slice(name.as_bytes());
+    buf.extend_from_slice(b": ");
+    buf.extend_from_slice(value.as_bytes());
+    buf.push(b'\n');
+}
+
+/// Splits `value` into lines on `\r\n`, `\n` and `\r`, the line breaks of
+/// the `text/event-stream` format. Unlike [`str::lines`] this doesn't ignore a
+/// trailing line break, it results in an empty last line. This means it always
+/// returns at least a single (possibly empty) line.
+fn split_lines(value: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(value);
+    iter::from_fn(move || {
+        let value = rest?;
+        if let Some(idx) = value.find(['\r', '\n']) {
+            let len = if value[idx..].starts_with("\r\n") {
+                2
+            } else {
+                1
+            };
+            rest = Some(&value[idx + len..]);
+            Some(&value[..idx])
+        } else {
+            rest = None;
+            Some(value)
+        }
+    })
+}
+
+/// Removes all `\n` and `\r` from `value`.
+fn remove_line_breaks(mut value: String) -> String {
+    value.retain(|c| c != '\n' && c != '\r');
+    value
+}
+
+/// Response body that streams server-sent events.
+///
+/// Events send using the [`ActorRef`] returned by [`EventStream::new`] are
+/// written to the connection. If no event is send for a certain amount of time
+/// (see [`EventStream::with_keep_alive`]) a comment is send to keep the
+/// connection alive.
+///
+/// The body is completed once all actor references are dropped. If the
+/// connection is closed by the client the actor references will be
+/// disconnected, causing sends to fail.
+///
+/// The body is send using chunked encoding.
+#[derive(Debug)]
+pub struct EventStream<RT> {
+    events: heph_inbox::Receiver<Event>,
+    rt: RT,
+    keep_alive: Duration,
+}
+
+impl<RT> EventStream<RT>
+where
+    RT: Access + Clone,
+{
+    /// Create a new event stream.
+    ///
+    /// Returns the response body and an actor reference to send events to it.
+    pub fn new(rt: RT) -> (EventStream<RT>, ActorRef<Event>) {
+        let (sender, receiver) = heph_inbox::new_small();
+        let stream = EventStream {
+            events: receiver,
+            rt,
+            keep_alive: DEFAULT_KEEP_ALIVE,
+        };
+        (stream, ActorRef::local(sender))
+    }
+
+    /// Set the interval after which a keep-alive comment is send if no events
+    /// were send, defaults to [`DEFAULT_KEEP_ALIVE`].
+    pub const fn with_keep_alive(mut self, keep_alive: Duration) -> EventStream<RT> {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    /// Create a 200 OK response using this stream as body.
+    ///
+    /// This sets the "Content-Type" (to `text/event-stream`) and
+    /// "Cache-Control" (to `no-cache`) headers.
+    pub fn into_response(self) -> Response<EventStream<RT>> {
+        let mut response = Response::ok();
+        let headers = response.headers_mut();
+        headers.append(Header::new(HeaderName::CONTENT_TYPE, b"text/event-stream"));
+        headers.append(Header::new(HeaderName::CACHE_CONTROL, b"no-cache"));
+        response.with_body(self)
+    }
+}
+
+impl<RT> crate::Body for EventStream<RT>
+where
+    RT: Access + Clone + 'static,
+{
+    fn length(&self) -> BodyLength {
+        BodyLength::Chunked
+    }
+}
+
+impl<RT> PrivateBody for EventStream<RT>
+where
+    RT: Access + Clone + 'static,
+{
+    type WriteFuture<'stream> = impl Future<Output = io::Result<Vec<u8>>> + 'stream;
+
+    fn write_message<'stream>(
+        mut self,
+        stream: &'stream mut TcpStream,
+        http_head: Vec<u8>,
+    ) -> Self::WriteFuture<'stream> {
+        async move {
+            let http_head = stream.send_all(http_head).await?;
+            let mut buf = Vec::new();
+            loop {
+                let mut timer = pin!(Timer::after(self.rt.clone(), self.keep_alive));
+                let mut recv = self.events.recv();
+                let event = poll_fn(|ctx| match Pin::new(&mut recv).poll(ctx) {
+                    Poll::Ready(event) => Poll::Ready(Some(event)),
+                    Poll::Pending => timer.as_mut().poll(ctx).map(|_| None),
+                })
+                .await;
+
+                buf.clear();
+                match event {
+                    Some(Some(event)) => {
+                        let mut frame = Vec::new();
+                        event.encode(&mut frame);
+                        write_chunk(&mut buf, &frame);
+                    }
+                    // All actor references are dropped, or the stream was
+                    // stopped using `ActorRef::stop`, end the response.
+                    Some(None) => {
+                        _ = stream.send_all(LAST_CHUNK).await?;
+                        return Ok(http_head);
+                    }
+                    // No events in a while, keep the connection alive.
+                    None => write_chunk(&mut buf, KEEP_ALIVE_COMMENT),
+                }
+                buf = stream.send_all(buf).await?;
+            }
+        }
+    }
+}
+
+/// Write `data` as a single chunk (using chunked encoding) to `buf`.
+fn write_chunk(buf: &mut Vec<u8>, data: &[u8]) {
+    write!(buf, "{:x}\r\n", data.len()).unwrap();
+    buf.extend_from_slice(data);
+    buf.extend_from_slice(b"\r\n");
+}
@@ -0,0 +1,528 @@
+//! Module with the [`ServeDir`] handler to serve static files.
+//!
+//! See [`serve_dir`] to create a new `ServeDir`.
+
+use std::ffi::OsStr;
+use std::future::Future;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use heph_rt::fs::File;
+use heph_rt::Access;
+
+use crate::body::{BodyLength, EmptyBody, FileBody, PrivateBody};
+use crate::conditional::Validators;
+use crate::handler::Handler;
+use crate::uri::percent_decode;
+use crate::{Header, HeaderName, Method, Request, Response, StatusCode, Uri};
+
+/// Name of the file served if a directory is requested.
+const INDEX_FILE: &str = "index.html";
+
+/// Create a new [`ServeDir`] handler serving the files in the directory
+/// `root`.
+///
+/// `root` is canonicalised, if that fails (e.g. because it doesn't exist yet)
+/// it's used as is, in which case no files will be served.
+pub fn serve_dir<P: Into<PathBuf>>(root: P) -> ServeDir {
+    let root = root.into();
+    let root = std::fs::canonicalize(&root).unwrap_or(root);
+    ServeDir { root: root.into() }
+}
+
+/// [`Handler`] that serves static files from a directory.
+///
+/// The path of the request is mapped to a file in the root directory, e.g. with
+/// a root of `/var/www` a request for `/css/main.css` is served using
+/// `/var/www/css/main.css`. If the path points to a directory `index.html` in
+/// that directory is served.
+///
+/// The handler supports the following:
+///  * Only `GET` and `HEAD` requests, other methods get a 405 Method Not Allowed
+///    response.
+///  * The "Content-Type" header is set based on the extension of the file.
+///  * The "ETag" and "Last-Modified" headers are set, conditional requests using
+///    the "If-None-Match" and "If-Modified-Since" headers are supported.
+///  * A single byte range using the "Range" header, optionally using
+///    "If-Range". Requests for multiple ranges get the entire file as response.
+///
+/// Paths containing `..` components are not allowed and always result in a 404
+/// Not Found response. Symbolic links are followed, but only if they point to
+/// a file inside the root directory, links pointing outside of it also result
+/// in a 404 Not Found response.
+///
+/// # Notes
+///
+/// io_uring doesn't support resolving paths, so the canonical path of the
+/// requested file is determined using the `realpath(3)` function, which may
+/// block. For files on local filesystems this is generally fast.
+///
+/// # Examples
+///
+/// ```
+/// # #![allow(dead_code)]
+/// use heph_http::static_files::{serve_dir, ServeBody, ServeDir};
+/// use heph_http::{Request, Response};
+/// use heph_rt::ThreadLocal;
+///
+/// async fn assets<B>(rt: ThreadLocal, request: Request<B>) -> Response<ServeBody> {
+///     // NOTE: in practice the handler should be created once and reused.
+///     let handler: ServeDir = serve_dir("/var/www");
+///     handler.serve(&rt, &request).await
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ServeDir {
+    root: Arc<Path>,
+}
+
+impl ServeDir {
+    /// Returns the root directory of the files served.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Serve the file requested in `request`.
+    pub async fn serve<RT, B>(&self, rt: &RT, request: &Request<B>) -> Response<ServeBody>
+    where
+        RT: Access,
+    {
+        serve(rt, &self.root, request).await
+    }
+}
+
+impl<RT, B> Handler<(RT, Request<B>)> for ServeDir
+where
+    RT: Access,
+{
+    type Response = Response<ServeBody>;
+    type Future = impl Future<Output = Self::Response>;
+
+    fn handle(&self, (rt, request): (RT, Request<B>)) -> Self::Future {
+        let root = Arc::clone(&self.root);
+        async move { serve(&rt, &root, &request).await }
+    }
+}
+
+/// Body returned by [`ServeDir`].
+#[derive(Debug)]
+pub enum ServeBody {
+    /// Empty body, e.g. used in 304 Not Modified and 404 Not Found responses.
+    Empty,
+    /// (Part of) the requested file.
+    File(FileBody),
+}
+
+impl crate::Body for ServeBody {
+    fn length(&self) -> BodyLength {
+        match self {
+            ServeBody::Empty => EmptyBody.length(),
+            ServeBody::File(body) => body.length(),
+        }
+    }
+}
+
+impl PrivateBody for ServeBody {
+    type WriteFuture<'stream> = impl Future<Output = io::Result<Vec<u8>>> + 'stream;
+
+    fn write_message<'stream>(
+        self,
+        stream: &'stream mut heph_rt::net::TcpStream,
+        http_head: Vec<u8>,
+    ) -> Self::WriteFuture<'stream> {
+        async move {
+            match self {
+                ServeBody::Empty => EmptyBody.write_message(stream, http_head).await,
+                ServeBody::File(body) => body.write_message(stream, http_head).await,
+            }
+        }
+    }
+}
+
+/// See [`ServeDir::serve`].
+async fn serve<RT, B>(rt: &RT, root: &Path, request: &Request<B>) -> Response<ServeBody>
+where
+    RT: Access,
+{
+    let method = request.method();
+    if !matches!(method, Method::Get | Method::Head) {
+        let mut response = Response::method_not_allowed();
+        response
+            .headers_mut()
+            .append(Header::new(HeaderName::ALLOW, b"GET, HEAD"));
+        return response.with_body(ServeBody::Empty);
+    }
+
+    let Some(path) = resolve_path(root, request.path()) else {
+        return Response::not_found().with_body(ServeBody::Empty);
+    };
+
+    let (path, file, metadata) = match open(rt, root, &path).await {
+        Ok((path, _, metadata)) if metadata.is_dir() => {
+            match open(rt, root, &path.join(INDEX_FILE)).await {
+                Ok(result) => result,
+                Err(err) => return error_response(&err),
+            }
+        }
+        Ok(result) => result,
+        Err(err) => return error_response(&err),
+    };
+    if !metadata.is_file() {
+        return Response::not_found().with_body(ServeBody::Empty);
+    }
+
+    let length = metadata.len();
+    let modified = metadata.modified();
+    let etag = etag(length, modified);
+    let validators = Validators::new()
+        .with_etag(etag.clone())
+        .with_last_modified(modified);
+
+    let mut response = if validators.is_not_modified(request) {
+        Response::not_modified().with_body(ServeBody::Empty)
+    } else {
+        // NOTE: the file's length might not fit in `usize` on 32 bit
+        // platforms, but we only support 64 bit.
+        #[allow(clippy::cast_possible_truncation)]
+        let file_length = length as usize;
+        let range = if matches!(method, Method::Get) && if_range_matches(request, &etag, modified) {
+            request
+                .headers()
+                .get_bytes(&HeaderName::RANGE)
+                .map_or(ByteRange::Full, |range| parse_range(range, file_length))
+        } else {
+            ByteRange::Full
+        };
+
+        match range {
+            ByteRange::Full => {
+                let body = FileBody::new(file, 0, file_length);
+                Response::ok().with_body(ServeBody::File(body))
+            }
+            ByteRange::Partial(start, end) => {
+                let content_range = format!("bytes {start}-{}/{file_length}", end - 1);
+                let mut response = Response::build_new(StatusCode::PARTIAL_CONTENT);
+                response.headers_mut().append(Header::new(
+                    HeaderName::CONTENT_RANGE,
+                    content_range.as_bytes(),
+                ));
+                let body = FileBody::new(file, start as u64, end - start);
+                response.with_body(ServeBody::File(body))
+            }
+            ByteRange::Unsatisfiable => {
+                let content_range = format!("bytes */{file_length}");
+                let mut response = Response::build_new(StatusCode::RANGE_NOT_SATISFIABLE);
+                response.headers_mut().append(Header::new(
+                    HeaderName::CONTENT_RANGE,
+                    content_range.as_bytes(),
+                ));
+                return response.with_body(ServeBody::Empty);
+            }
+        }
+    };
+
+    let headers = response.headers_mut();
+    headers.append(Header::new(
+        HeaderName::CONTENT_TYPE,
+        content_type(&path).as_bytes(),
+    ));
+    headers.append(Header::new(HeaderName::ACCEPT_RANGES, b"bytes"));
+    validators.append_headers(headers);
+    response
+}
+
+/// Open the file at `path`, which must be inside `root`, and retrieve its
+/// metadata. Returns the canonical path of the file.
+async fn open<RT>(
+    rt: &RT,
+    root: &Path,
+    path: &Path,
+) -> io::Result<(PathBuf, File, heph_rt::fs::Metadata)>
+where
+    RT: Access,
+{
+    let path = confine(root, path)?;
+    let file = File::open(rt, path.clone()).await?;
+    let metadata = file.metadata().await?;
+    Ok((path, file, metadata))
+}
+
+/// Returns the canonical version of `path`, resolving all symbolic links, if
+/// it's inside `root` (which must be canonical). If it's not inside `root` an
+/// error with kind [`io::ErrorKind::NotFound`] is returned.
+fn confine(root: &Path, path: &Path) -> io::Result<PathBuf> {
+    let path = std::fs::canonicalize(path)?;
+    if path.starts_with(root) {
+        Ok(path)
+    } else {
+        Err(io::ErrorKind::NotFound.into())
+    }
+}
+
+/// Returns the response for an error returned by opening a file.
+fn error_response(err: &io::Error) -> Response<ServeBody> {
+    let response = match err.kind() {
+        io::ErrorKind::NotFound | io::ErrorKind::NotADirectory => Response::not_found(),
+        io::ErrorKind::PermissionDenied => Response::forbidden(),
+        _ => Response::server_error(),
+    };
+    response.with_body(ServeBody::Empty)
+}
+
+/// Map the request `path` to a file in `root`.
+///
+/// Returns `None` if `path` is invalid or tries to escape `root`.
+fn resolve_path(root: &Path, path: &str) -> Option<PathBuf> {
+    let path = Uri::parse(path).path().strip_prefix('/')?;
+
+    let mut resolved = root.to_path_buf();
+    for component in path.split('/') {
+        let component = percent_decode(component)?;
+        match &*component {
+            b"" | b"." => continue,
+            b".." => return None,
+            // Don't allow components that would be interpreted as multiple
+            // components, or are invalid.
+            component if component.contains(&b'/') || component.contains(&0) => return None,
+            component => resolved.push(OsStr::from_bytes(component)),
+        }
+    }
+    Some(resolved)
+}
+
+/// Returns the value for the "ETag" header.
+fn etag(length: u64, modified: SystemTime) -> String {
+    let modified = modified
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |modified| modified.as_nanos());
+    format!("\"{length:x}-{modified:x}\"")
+}
+
+/// Returns `true` if the "If-Range" header is missing or matches the file
+/// (using `etag` and `modified`), i.e. if the "Range" header should be used.
+fn if_range_matches<B>(request: &Request<B>, etag: &str, modified: SystemTime) -> bool {
+    let Some(if_range) = request.headers().get_bytes(&HeaderName::IF_RANGE) else {
+        return true;
+    };
+    let if_range = crate::trim_ws(if_range);
+    if if_range.starts_with(b"\"") {
+        // Strong comparison.
+        if_range == etag.as_bytes()
+    } else {
+        match request.header::<SystemTime>(&HeaderName::IF_RANGE) {
+            Ok(Some(date)) => modified
+                .duration_since(date)
+                .is_ok_and(|diff| diff.as_secs() == 0),
+            Ok(None) | Err(_) => false,
+        }
+    }
+}
+
+/// Byte range requested.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum ByteRange {
+    /// The full file.
+    Full,
+    /// A part of the file, `start..end`.
+    Partial(usize, usize),
+    /// Range can't be satisfied.
+    Unsatisfiable,
+}
+
+/// Parse the "Range" header `value` for a file of `length` bytes.
+///
+/// Only a single byte range is supported, for invalid ranges or multiple ranges
+/// [`ByteRange::Full`] is returned (RFC 7233 section 3.1 allows a server to
+/// ignore the "Range" header).
+fn parse_range(value: &[u8], length: usize) -> ByteRange {
+    let Some(range) = crate::trim_ws(value).strip_prefix(b"bytes=") else {
+        return ByteRange::Full;
+    };
+    let Ok(range) = std::str::from_utf8(range) else {
+        return ByteRange::Full;
+    };
+    if range.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = range.trim().split_once('-') else {
+        return ByteRange::Full;
+    };
+
+    match (start.parse::<usize>(), end.parse::<usize>()) {
+        // `bytes=start-end`.
+        (Ok(start), Ok(end)) if start <= end => {
+            if start >= length {
+                ByteRange::Unsatisfiable
+            } else {
+                ByteRange::Partial(start, end.saturating_add(1).min(length))
+            }
+        }
+        // `bytes=start-`.
+        (Ok(start), Err(_)) if end.is_empty() => {
+            if start >= length {
+                ByteRange::Unsatisfiable
+            } else {
+                ByteRange::Partial(start, length)
+            }
+        }
+        // `bytes=-suffix_length`.
+        (Err(_), Ok(suffix_length)) if start.is_empty() => {
+            if suffix_length == 0 || length == 0 {
+                ByteRange::Unsatisfiable
+            } else {
+                ByteRange::Partial(length.saturating_sub(suffix_length), length)
+            }
+        }
+        _ => ByteRange::Full,
+    }
+}
+
+/// Returns the value for the "Content-Type" header based on the extension of
+/// `path`.
+fn content_type(path: &Path) -> &'static str {
+    let Some(extension) = path.extension().and_then(OsStr::to_str) else {
+        return "application/octet-stream";
+    };
+    match extension.to_ascii_lowercase().as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "map" => "application/json",
+        "txt" => "text/plain; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "xml" => "application/xml",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io;
+    use std::os::unix::fs::symlink;
+    use std::path::{Path, PathBuf};
+
+    use super::{confine, content_type, parse_range, resolve_path, ByteRange};
+
+    #[test]
+    fn resolving_paths() {
+        let root = Path::new("/var/www");
+        let tests = [
+            ("/", Some("/var/www")),
+            ("/index.html", Some("/var/www/index.html")),
+            ("/css/main.css", Some("/var/www/css/main.css")),
+            ("/css//./main.css", Some("/var/www/css/main.css")),
+            ("/main.css?v=1", Some("/var/www/main.css")),
+            ("/main.css#top", Some("/var/www/main.css")),
+            ("/hello%20world.txt", Some("/var/www/hello world.txt")),
+            ("/../etc/passwd", None),
+            ("/css/../../etc/passwd", None),
+            ("/%2e%2e/etc/passwd", None),
+            ("/a%2fb", None),
+            ("/a%00b", None),
+            ("/a%zz", None),
+            ("/a%2", None),
+            ("relative", None),
+        ];
+        for (path, expected) in tests {
+            assert_eq!(
+                resolve_path(root, path),
+                expected.map(PathBuf::from),
+                "path: {path}"
+            );
+        }
+    }
+
+    #[test]
+    fn confining_paths() {
+        let dir =
+            std::env::temp_dir().join(format!("heph_http.static_files.{}", std::process::id()));
+        let root = dir.join("root");
+        fs::create_dir_all(root.join("css")).unwrap();
+        fs::write(root.join("css/main.css"), "").unwrap();
+        fs::write(dir.join("secret.txt"), "").unwrap();
+        symlink(root.join("css/main.css"), root.join("inside.css")).unwrap();
+        symlink(dir.join("secret.txt"), root.join("outside.txt")).unwrap();
+        symlink(&dir, root.join("parent")).unwrap();
+        let root = fs::canonicalize(root).unwrap();
+
+        let got = confine(&root, &root.join("css/main.css")).unwrap();
+        assert_eq!(got, root.join("css/main.css"));
+        let got = confine(&root, &root.join("inside.css")).unwrap();
+        assert_eq!(got, root.join("css/main.css"));
+        for path in ["outside.txt", "parent/secret.txt", "missing.txt"] {
+            let err = confine(&root, &root.join(path)).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::NotFound, "path: {path}");
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn parsing_ranges() {
+        let tests: [(&[u8], ByteRange); 14] = [
+            (b"bytes=0-0", ByteRange::Partial(0, 1)),
+            (b"bytes=0-99", ByteRange::Partial(0, 100)),
+            (b"bytes=10-19", ByteRange::Partial(10, 20)),
+            (b"bytes=10-1000", ByteRange::Partial(10, 100)),
+            (b"bytes=10-", ByteRange::Partial(10, 100)),
+            (b"bytes=-10", ByteRange::Partial(90, 100)),
+            (b"bytes=-1000", ByteRange::Partial(0, 100)),
+            (b"bytes=100-", ByteRange::Unsatisfiable),
+            (b"bytes=100-200", ByteRange::Unsatisfiable),
+            (b"bytes=-0", ByteRange::Unsatisfiable),
+            (b"bytes=20-10", ByteRange::Full),
+            (b"bytes=0-1,5-6", ByteRange::Full),
+            (b"items=0-1", ByteRange::Full),
+            (b"bytes=a-b", ByteRange::Full),
+        ];
+        for (range, expected) in tests {
+            assert_eq!(
+                parse_range(range, 100),
+                expected,
+                "range: {}",
+                String::from_utf8_lossy(range)
+            );
+        }
+    }
+
+    #[test]
+    fn content_types() {
+        assert_eq!(
+            content_type(Path::new("index.html")),
+            "text/html; charset=utf-8"
+        );
+        assert_eq!(content_type(Path::new("IMAGE.PNG")), "image/png");
+        assert_eq!(
+            content_type(Path::new("unknown.ext")),
+            "application/octet-stream"
+        );
+        assert_eq!(
+            content_type(Path::new("no_ext")),
+            "application/octet-stream"
+        );
+    }
+}
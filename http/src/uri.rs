@@ -0,0 +1,293 @@
+//! Module with URI related types.
+//!
+//! See [`Uri`].
+
+use std::borrow::Cow;
+use std::str::FromStr;
+use std::{fmt, str};
+
+/// Request URI (the request-target), split into its path and query.
+///
+/// This supports the origin-form (`/path?query`) and absolute-form
+/// (`http://example.com/path?query`) request-targets, see RFC 7230 section
+/// 5.3. Any fragment is ignored.
+///
+/// The path and query are not decoded, see [`Uri::decoded_path`],
+/// [`Uri::normalised_path`] and [`Uri::query_param`] to get the decoded values.
+///
+/// # Examples
+///
+/// ```
+/// use heph_http::Uri;
+///
+/// let uri = Uri::parse("/users/../files/my%20file.txt?page=2&sort=name");
+/// assert_eq!(uri.path(), "/users/../files/my%20file.txt");
+/// assert_eq!(uri.normalised_path().unwrap(), "/files/my file.txt");
+/// assert_eq!(uri.query(), Some("page=2&sort=name"));
+/// assert_eq!(uri.query_param::<usize>("page"), Ok(Some(2)));
+/// assert_eq!(uri.query_param::<String>("sort").unwrap().as_deref(), Some("name"));
+/// assert_eq!(uri.query_param::<String>("filter"), Ok(None));
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Uri<'a> {
+    path: &'a str,
+    query: Option<&'a str>,
+}
+
+impl<'a> Uri<'a> {
+    /// Parse `uri`.
+    pub fn parse(uri: &'a str) -> Uri<'a> {
+        // Remove the fragment, it shouldn't be send, but let's be lenient.
+        let uri = uri.split_once('#').map_or(uri, |(uri, _)| uri);
+        let (path, query) = match uri.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (uri, None),
+        };
+        // Absolute-form, remove the scheme and authority.
+        let path = match path.split_once("://") {
+            Some((scheme, rest)) if !scheme.contains('/') => {
+                rest.find('/').map_or("/", |idx| &rest[idx..])
+            }
+            _ => path,
+        };
+        Uri { path, query }
+    }
+
+    /// Returns the raw (not decoded) path.
+    pub const fn path(&self) -> &'a str {
+        self.path
+    }
+
+    /// Returns the percent-decoded path.
+    pub fn decoded_path(&self) -> Result<Cow<'a, str>, UriError> {
+        let decoded = percent_decode(self.path).ok_or(UriError::InvalidPercentEncoding)?;
+        match decoded {
+            Cow::Borrowed(bytes) => {
+                // SAFETY: `bytes` is unchanged from `self.path`, which is a
+                // `str`, so it's valid UTF-8.
+                Ok(Cow::Borrowed(unsafe { str::from_utf8_unchecked(bytes) }))
+            }
+            Cow::Owned(bytes) => String::from_utf8(bytes)
+                .map(Cow::Owned)
+                .map_err(|_| UriError::InvalidUtf8),
+        }
+    }
+
+    /// Returns the normalised and percent-decoded path.
+    ///
+    /// This removes empty (`//`) and dot (`.` and `..`) segments, see RFC 3986
+    /// section 5.2.4. A `..` segment at the root is ignored, so the returned
+    /// path always starts with `/` and can't escape it.
+    ///
+    /// Returns an error if a segment contains an invalid percent-encoding,
+    /// isn't valid UTF-8 or contains an encoded `/` (`%2F`), as that would
+    /// change the meaning of the path.
+    pub fn normalised_path(&self) -> Result<String, UriError> {
+        let mut segments: Vec<Cow<'a, [u8]>> = Vec::new();
+        for segment in self.path.split('/') {
+            match segment {
+                "" | "." => {}
+                ".." => _ = segments.pop(),
+                segment => {
+                    let segment =
+                        percent_decode(segment).ok_or(UriError::InvalidPercentEncoding)?;
+                    if segment.contains(&b'/') {
+                        return Err(UriError::InvalidPath);
+                    }
+                    segments.push(segment);
+                }
+            }
+        }
+
+        let mut path = Vec::with_capacity(self.path.len());
+        for segment in &segments {
+            path.push(b'/');
+            path.extend_from_slice(segment);
+        }
+        let trailing_slash = self.path.len() > 1
+            && (self.path.ends_with('/')
+                || self.path.ends_with("/.")
+                || self.path.ends_with("/.."));
+        if path.is_empty() || trailing_slash {
+            path.push(b'/');
+        }
+        String::from_utf8(path).map_err(|_| UriError::InvalidUtf8)
+    }
+
+    /// Returns the raw (not decoded) query, if any.
+    pub const fn query(&self) -> Option<&'a str> {
+        self.query
+    }
+
+    /// Returns an iterator over the decoded query parameters.
+    ///
+    /// Query parameters are decoded using the `application/x-www-form-urlencoded`
+    /// rules, i.e. `+` is decoded as a space. Invalid percent-encodings are
+    /// not decoded.
+    pub fn query_params(&self) -> QueryParams<'a> {
+        QueryParams {
+            query: self.query.unwrap_or(""),
+        }
+    }
+
+    /// Returns the (first) value of the query parameter with `name`, if any.
+    ///
+    /// Also see [`Uri::query_params`] for how parameters are decoded.
+    pub fn query_param<T>(&self, name: &str) -> Result<Option<T>, T::Err>
+    where
+        T: FromStr,
+    {
+        match self.query_params().find(|(n, _)| n == name) {
+            Some((_, value)) => value.parse().map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Deserialise the query into `T`.
+    ///
+    /// If the request doesn't have a query it's treated as an empty query.
+    #[cfg(feature = "query")]
+    pub fn from_query<T>(&self) -> Result<T, QueryError>
+    where
+        T: serde::Deserialize<'a>,
+    {
+        serde_urlencoded::from_str(self.query.unwrap_or("")).map_err(QueryError)
+    }
+}
+
+impl<'a> fmt::Display for Uri<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.path)?;
+        if let Some(query) = self.query {
+            f.write_str("?")?;
+            f.write_str(query)?;
+        }
+        Ok(())
+    }
+}
+
+/// Iterator over the query parameters.
+///
+/// See [`Uri::query_params`].
+#[derive(Clone, Debug)]
+pub struct QueryParams<'a> {
+    query: &'a str,
+}
+
+impl<'a> Iterator for QueryParams<'a> {
+    type Item = (Cow<'a, str>, Cow<'a, str>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.query.is_empty() {
+                return None;
+            }
+            let (param, rest) = self.query.split_once('&').unwrap_or((self.query, ""));
+            self.query = rest;
+            if param.is_empty() {
+                continue;
+            }
+            let (name, value) = param.split_once('=').unwrap_or((param, ""));
+            return Some((decode_form(name), decode_form(value)));
+        }
+    }
+}
+
+/// Error returned by parsing the path of an [`Uri`].
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum UriError {
+    /// Invalid percent-encoding, e.g. `%zz`.
+    InvalidPercentEncoding,
+    /// Decoded path is not valid UTF-8.
+    InvalidUtf8,
+    /// Decoded path segment contains a `/`.
+    InvalidPath,
+}
+
+impl UriError {
+    /// Returns a description of the error.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            UriError::InvalidPercentEncoding => "invalid percent-encoding",
+            UriError::InvalidUtf8 => "invalid UTF-8",
+            UriError::InvalidPath => "invalid path",
+        }
+    }
+}
+
+impl fmt::Display for UriError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+/// Error returned by [`Uri::from_query`].
+#[cfg(feature = "query")]
+#[derive(Debug)]
+pub struct QueryError(serde_urlencoded::de::Error);
+
+#[cfg(feature = "query")]
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Percent-decode `input`, returns `None` if it contains an invalid escape.
+pub(crate) fn percent_decode(input: &str) -> Option<Cow<'_, [u8]>> {
+    let input = input.as_bytes();
+    if !input.contains(&b'%') {
+        return Some(Cow::Borrowed(input));
+    }
+    let mut output = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        if input[i] == b'%' {
+            output.push(decode_hex(input.get(i + 1..i + 3)?)?);
+            i += 3;
+        } else {
+            output.push(input[i]);
+            i += 1;
+        }
+    }
+    Some(Cow::Owned(output))
+}
+
+/// Decode `input` using `application/x-www-form-urlencoded` rules, leaving
+/// invalid escapes as is and replacing invalid UTF-8.
+fn decode_form(input: &str) -> Cow<'_, str> {
+    if !input.contains(['%', '+']) {
+        return Cow::Borrowed(input);
+    }
+    let input = input.as_bytes();
+    let mut output = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        match input[i] {
+            b'+' => output.push(b' '),
+            b'%' => {
+                if let Some(byte) = input.get(i + 1..i + 3).and_then(decode_hex) {
+                    output.push(byte);
+                    i += 3;
+                    continue;
+                }
+                output.push(b'%');
+            }
+            b => output.push(b),
+        }
+        i += 1;
+    }
+    match String::from_utf8(output) {
+        Ok(output) => Cow::Owned(output),
+        Err(err) => Cow::Owned(String::from_utf8_lossy(err.as_bytes()).into_owned()),
+    }
+}
+
+/// Decode two hexadecimal characters into a byte.
+fn decode_hex(hex: &[u8]) -> Option<u8> {
+    let high = (hex[0] as char).to_digit(16)?;
+    let low = (hex[1] as char).to_digit(16)?;
+    #[allow(clippy::cast_possible_truncation)] // Max is 255.
+    Some((high << 4 | low) as u8)
+}
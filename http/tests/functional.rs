@@ -15,15 +15,26 @@ fn assert_sync<T: Sync>() {}
 
 #[path = "functional"] // rustfmt can't find the files.
 mod functional {
+    mod access_log;
     mod body;
+    mod cache;
     mod client;
+    mod conditional;
+    mod cookie;
+    mod cors;
+    mod extensions;
     mod from_header_value;
     mod header;
+    #[cfg(feature = "json")]
+    mod json;
     mod message;
     mod method;
+    mod request_actor;
     mod route;
     mod server;
+    mod sse;
     mod status_code;
     mod transform;
+    mod uri;
     mod version;
 }
@@ -0,0 +1,89 @@
+//! Tests for the access_log module.
+
+use std::net::SocketAddr;
+
+use heph_http::access_log::AccessLog;
+use heph_http::body::OneshotBody;
+use heph_http::handler::Handler;
+use heph_http::{Request, Response, StatusCode};
+use heph_rt::test::block_on_future;
+use heph_rt::trace::{AttributeValue, EventTiming, Trace};
+
+const PEER_ADDR: &str = "127.0.0.1:12345";
+
+async fn handler<B>(request: Request<B>) -> Response<OneshotBody<&'static str>> {
+    if request.path() == "/" {
+        Response::ok().with_body(OneshotBody::new("index"))
+    } else {
+        Response::not_found().with_body(OneshotBody::new("not found"))
+    }
+}
+
+#[test]
+fn access_log() {
+    let middleware = AccessLog::new(handler);
+    let peer_addr: SocketAddr = PEER_ADDR.parse().unwrap();
+
+    let tests = [
+        ("/", StatusCode::OK, "index"),
+        ("/other", StatusCode::NOT_FOUND, "not found"),
+    ];
+    for (path, expected_status, expected_body) in tests {
+        let request = Request::get(path.into());
+        let response = block_on_future(middleware.handle((peer_addr, request)));
+        assert_eq!(response.status(), expected_status);
+        assert_eq!(response.body().into_inner(), expected_body);
+    }
+}
+
+/// [`Trace`] implementation that records the trace events.
+#[derive(Default)]
+struct TestTracer {
+    events: Vec<(String, Vec<String>)>,
+}
+
+impl Trace for TestTracer {
+    fn start_trace(&self) -> Option<EventTiming> {
+        None
+    }
+
+    fn finish_trace(
+        &mut self,
+        _: Option<EventTiming>,
+        description: &str,
+        attributes: &[(&str, &dyn AttributeValue)],
+    ) {
+        let keys = attributes.iter().map(|(key, _)| key.to_string()).collect();
+        self.events.push((description.to_owned(), keys));
+    }
+}
+
+#[test]
+fn access_log_trace() {
+    let middleware = AccessLog::new(handler);
+    let peer_addr: SocketAddr = PEER_ADDR.parse().unwrap();
+
+    let (response, tracer) = block_on_future(async move {
+        let mut tracer = TestTracer::default();
+        let request = Request::get("/".into());
+        let response = middleware.handle((&mut tracer, peer_addr, request)).await;
+        (response, tracer)
+    });
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body().into_inner(), "index");
+
+    assert_eq!(tracer.events.len(), 1);
+    let (description, keys) = &tracer.events[0];
+    assert_eq!(description, "HTTP request");
+    assert_eq!(
+        keys,
+        &[
+            "method",
+            "path",
+            "version",
+            "status",
+            "bytes",
+            "peer_address"
+        ]
+    );
+}
@@ -3,7 +3,16 @@ use std::mem::replace;
 use std::pin::Pin;
 use std::task::{self, Poll};
 
-use heph_http::body::{Body, BodyLength, ChunkedBody, EmptyBody, OneshotBody, StreamingBody};
+use heph::actor::{self, actor_fn};
+use heph::supervisor::NoSupervisor;
+use heph_http::body::{
+    Body, BodyLength, BodyMessage, BodyReceiver, ChunkedBody, EmptyBody, FileBody, OneshotBody,
+    StreamingBody,
+};
+use heph_rt::spawn::ActorOptions;
+use heph_rt::test::block_on_local_actor;
+use heph_rt::util::next;
+use heph_rt::ThreadLocal;
 
 use crate::{assert_send, assert_size, assert_sync};
 
@@ -36,26 +45,31 @@ impl<'a> AsyncIterator for SingleStream<'a> {
 
 #[test]
 fn size() {
-    assert_size::<ChunkedBody<()>>(0);
+    assert_size::<ChunkedBody<()>>(8);
     assert_size::<EmptyBody>(0);
     assert_size::<OneshotBody<&'static [u8]>>(16);
     assert_size::<StreamingBody<()>>(8);
+    assert_size::<BodyReceiver<()>>(16);
 }
 
 #[test]
 fn send() {
     assert_send::<ChunkedBody<()>>();
     assert_send::<EmptyBody>();
+    assert_send::<FileBody>();
     assert_send::<OneshotBody<&'static [u8]>>();
     assert_send::<StreamingBody<()>>();
+    assert_send::<BodyReceiver<()>>();
 }
 
 #[test]
 fn sync() {
     assert_sync::<ChunkedBody<()>>();
     assert_sync::<EmptyBody>();
+    assert_sync::<FileBody>();
     assert_sync::<OneshotBody<&'static [u8]>>();
     assert_sync::<StreamingBody<()>>();
+    assert_sync::<BodyReceiver<()>>();
 }
 
 #[test]
@@ -106,3 +120,32 @@ fn chunked_body() {
         BodyLength::Chunked
     );
 }
+
+#[test]
+fn body_from_actor() {
+    async fn producer(
+        mut ctx: actor::Context<BodyMessage<&'static str, &'static [u8]>, ThreadLocal>,
+    ) {
+        let msg = ctx.receive_next().await.unwrap();
+        assert_eq!(msg.request, "request");
+        assert!(msg.body.is_connected());
+        msg.body.send(BODY1).await.unwrap();
+        msg.body.try_send(BODY0).unwrap();
+    }
+
+    async fn consumer(mut ctx: actor::Context<!, ThreadLocal>) {
+        let options = ActorOptions::default();
+        let producer = ctx
+            .runtime()
+            .spawn_local(NoSupervisor, actor_fn(producer), (), options);
+        let mut body = BodyReceiver::from_actor(&producer, "request")
+            .await
+            .unwrap();
+        assert_eq!(next(&mut body).await, Some(BODY1));
+        assert_eq!(next(&mut body).await, Some(BODY0));
+        // Producer dropped the `BodySender`, ending the body.
+        assert_eq!(next(&mut body).await, None);
+    }
+
+    block_on_local_actor(actor_fn(consumer), ());
+}
@@ -0,0 +1,177 @@
+//! Tests for the cache module.
+
+use std::thread::sleep;
+use std::time::Duration;
+
+use heph_http::body::{EmptyBody, OneshotBody};
+use heph_http::cache::{CacheConfig, ResponseCache};
+use heph_http::request_actor::ActorResponse;
+use heph_http::{Header, HeaderName, Headers, Method, Request, Response, StatusCode, Version};
+
+use crate::{assert_send, assert_sync};
+
+const TTL: Duration = Duration::from_secs(60);
+
+fn request(method: Method, path: &str, headers: &[Header<'static, '_>]) -> Request<EmptyBody> {
+    let headers = Headers::from(headers);
+    Request::new(method, path.to_owned(), Version::Http11, headers, EmptyBody)
+}
+
+fn response(body: &str) -> ActorResponse {
+    Response::ok().with_body(OneshotBody::new(body.as_bytes().to_vec()))
+}
+
+fn body(response: ActorResponse) -> Vec<u8> {
+    response.split().1.into_inner()
+}
+
+#[test]
+fn response_cache_is_send_sync() {
+    assert_send::<ResponseCache>();
+    assert_sync::<ResponseCache>();
+}
+
+#[test]
+fn hit() {
+    let cache = ResponseCache::new(1024).with_route("/", CacheConfig::new(TTL));
+    let request = request(Method::Get, "/", &[]);
+    assert!(cache.get(&request).is_none());
+
+    cache.insert(&request, &response("Hello world"));
+    assert!(cache.size() > 0);
+    let got = cache.get(&request).unwrap();
+    assert_eq!(got.status(), StatusCode::OK);
+    assert_eq!(got.headers().get_bytes(&HeaderName::AGE), Some(&b"0"[..]));
+    assert_eq!(body(got), b"Hello world");
+
+    // Cache is shared between clones.
+    let cache2 = cache.clone();
+    assert!(cache2.get(&request).is_some());
+
+    cache.clear();
+    assert_eq!(cache.size(), 0);
+    assert!(cache2.get(&request).is_none());
+}
+
+#[test]
+fn not_configured_route() {
+    let cache = ResponseCache::new(1024).with_route("/", CacheConfig::new(TTL));
+    let request = request(Method::Get, "/other", &[]);
+    cache.insert(&request, &response("Hello world"));
+    assert!(cache.get(&request).is_none());
+    assert_eq!(cache.size(), 0);
+}
+
+#[test]
+fn default_route() {
+    let cache = ResponseCache::new(1024).with_default(CacheConfig::new(TTL));
+    let request = request(Method::Get, "/other", &[]);
+    cache.insert(&request, &response("Hello world"));
+    assert!(cache.get(&request).is_some());
+}
+
+#[test]
+fn method() {
+    let cache = ResponseCache::new(1024).with_default(CacheConfig::new(TTL));
+    let get = request(Method::Get, "/", &[]);
+    let head = request(Method::Head, "/", &[]);
+    let post = request(Method::Post, "/", &[]);
+    cache.insert(&get, &response("Hello world"));
+    cache.insert(&post, &response("Hello world"));
+    assert!(cache.get(&get).is_some());
+    assert!(cache.get(&head).is_none());
+    assert!(cache.get(&post).is_none());
+}
+
+#[test]
+fn vary_header() {
+    let config = CacheConfig::new(TTL).with_vary_header(HeaderName::ACCEPT_ENCODING);
+    let cache = ResponseCache::new(1024).with_default(config);
+    let gzip = request(
+        Method::Get,
+        "/",
+        &[Header::new(HeaderName::ACCEPT_ENCODING, b"gzip")],
+    );
+    let identity = request(Method::Get, "/", &[]);
+    cache.insert(&gzip, &response("compressed"));
+    assert!(cache.get(&identity).is_none());
+    cache.insert(&identity, &response("uncompressed"));
+    assert_eq!(body(cache.get(&gzip).unwrap()), b"compressed");
+    assert_eq!(body(cache.get(&identity).unwrap()), b"uncompressed");
+}
+
+#[test]
+fn ttl() {
+    let config = CacheConfig::new(Duration::from_millis(10));
+    let cache = ResponseCache::new(1024).with_default(config);
+    let request = request(Method::Get, "/", &[]);
+    cache.insert(&request, &response("Hello world"));
+    assert!(cache.get(&request).is_some());
+    sleep(Duration::from_millis(20));
+    assert!(cache.get(&request).is_none());
+    assert_eq!(cache.size(), 0);
+}
+
+#[test]
+fn evicts_least_recently_used() {
+    let cache = ResponseCache::new(30).with_default(CacheConfig::new(TTL));
+    let body = "0123456789";
+    let requests = [
+        request(Method::Get, "/1", &[]),
+        request(Method::Get, "/2", &[]),
+        request(Method::Get, "/3", &[]),
+    ];
+    cache.insert(&requests[0], &response(body));
+    cache.insert(&requests[1], &response(body));
+    // Use the first response, making the second the least recently used.
+    assert!(cache.get(&requests[0]).is_some());
+    let size = cache.size();
+    cache.insert(&requests[2], &response(body));
+    assert_eq!(cache.size(), size);
+    assert!(cache.get(&requests[0]).is_some());
+    assert!(cache.get(&requests[1]).is_none());
+    assert!(cache.get(&requests[2]).is_some());
+}
+
+#[test]
+fn too_large_response() {
+    let cache = ResponseCache::new(16).with_default(CacheConfig::new(TTL));
+    let request = request(Method::Get, "/", &[]);
+    cache.insert(&request, &response("this is a too large response body"));
+    assert!(cache.get(&request).is_none());
+}
+
+#[test]
+fn uncacheable_responses() {
+    let cache = ResponseCache::new(1024).with_default(CacheConfig::new(TTL));
+    let request = request(Method::Get, "/", &[]);
+
+    let response1 = Response::not_found().with_body(OneshotBody::new(Vec::new()));
+    let mut response2 = response("Hello world");
+    response2
+        .headers_mut()
+        .append(Header::new(HeaderName::SET_COOKIE, b"id=123"));
+    let mut response3 = response("Hello world");
+    response3.headers_mut().append(Header::new(
+        HeaderName::CACHE_CONTROL,
+        b"max-age=60, private",
+    ));
+    for response in [response1, response2, response3] {
+        cache.insert(&request, &response);
+        assert!(cache.get(&request).is_none());
+    }
+}
+
+#[test]
+fn request_no_cache() {
+    let cache = ResponseCache::new(1024).with_default(CacheConfig::new(TTL));
+    let request1 = request(Method::Get, "/", &[]);
+    cache.insert(&request1, &response("Hello world"));
+    let request2 = request(
+        Method::Get,
+        "/",
+        &[Header::new(HeaderName::CACHE_CONTROL, b"no-cache")],
+    );
+    assert!(cache.get(&request2).is_none());
+    assert!(cache.get(&request1).is_some());
+}
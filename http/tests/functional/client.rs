@@ -565,7 +565,7 @@ fn chunked_transfer_encoding() {
         );
 
         stream
-            .write_all(b"HTTP/1.1 200\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nOk0\r\n")
+            .write_all(b"HTTP/1.1 200\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nOk\r\n0\r\n\r\n")
             .unwrap();
 
         handle.join().unwrap();
@@ -602,7 +602,95 @@ fn slow_chunked_transfer_encoding() {
             .write_all(b"HTTP/1.1 200\r\nTransfer-Encoding: chunked\r\n\r\n")
             .unwrap();
         sleep(Duration::from_millis(100));
-        stream.write_all(b"2\r\nOk0\r\n").unwrap();
+        stream.write_all(b"2\r\nOk\r\n0\r\n\r\n").unwrap();
+
+        handle.join().unwrap();
+    });
+}
+
+#[test]
+fn slow_body() {
+    // Test reading the body directly from the stream, i.e. not from the
+    // client's buffer.
+    with_test_server!(|test_server| {
+        async fn http_actor(
+            ctx: actor::Context<!, ThreadSafe>,
+            address: SocketAddr,
+        ) -> io::Result<()> {
+            let mut client = Client::connect(ctx.runtime_ref(), address).await?;
+            let mut response = client.get("/").await?;
+            assert_eq!(response.status(), StatusCode::OK);
+            let mut buf = Vec::with_capacity(1024);
+            while !response.body().is_empty() {
+                buf = response.body_mut().recv(buf).await?;
+            }
+            assert_eq!(buf, b"Hello world");
+            Ok(())
+        }
+
+        let (mut stream, handle) =
+            test_server.accept(|address| init_actor(actor_fn(http_actor), address).unwrap().0);
+
+        expect_request(
+            &mut stream,
+            Method::Get,
+            "/",
+            Version::Http11,
+            &Headers::from([Header::new(HeaderName::USER_AGENT, USER_AGENT)]),
+            b"",
+        );
+
+        stream
+            .write_all(b"HTTP/1.1 200\r\nContent-Length: 11\r\n\r\n")
+            .unwrap();
+        sleep(Duration::from_millis(100));
+        stream.write_all(b"Hello").unwrap();
+        sleep(Duration::from_millis(100));
+        stream.write_all(b" world").unwrap();
+
+        handle.join().unwrap();
+    });
+}
+
+#[test]
+fn slow_chunk_data() {
+    // Test reading the body directly from the stream, i.e. not from the
+    // client's buffer.
+    with_test_server!(|test_server| {
+        async fn http_actor(
+            ctx: actor::Context<!, ThreadSafe>,
+            address: SocketAddr,
+        ) -> io::Result<()> {
+            let mut client = Client::connect(ctx.runtime_ref(), address).await?;
+            let mut response = client.get("/").await?;
+            assert_eq!(response.status(), StatusCode::OK);
+            let mut buf = Vec::with_capacity(1024);
+            while !response.body().is_empty() {
+                buf = response.body_mut().recv(buf).await?;
+            }
+            assert_eq!(buf, b"Hello world");
+            Ok(())
+        }
+
+        let (mut stream, handle) =
+            test_server.accept(|address| init_actor(actor_fn(http_actor), address).unwrap().0);
+
+        expect_request(
+            &mut stream,
+            Method::Get,
+            "/",
+            Version::Http11,
+            &Headers::from([Header::new(HeaderName::USER_AGENT, USER_AGENT)]),
+            b"",
+        );
+
+        stream
+            .write_all(b"HTTP/1.1 200\r\nTransfer-Encoding: chunked\r\n\r\n5\r\n")
+            .unwrap();
+        sleep(Duration::from_millis(100));
+        stream.write_all(b"Hello\r\n6\r\n").unwrap();
+        sleep(Duration::from_millis(100));
+        stream.write_all(b" world\r\n0\r\n\r\n").unwrap();
 
         handle.join().unwrap();
     });
@@ -635,7 +723,90 @@ fn empty_chunked_transfer_encoding() {
         );
 
         stream
-            .write_all(b"HTTP/1.1 200\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n")
+            .write_all(b"HTTP/1.1 200\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n")
+            .unwrap();
+
+        handle.join().unwrap();
+    });
+}
+
+#[test]
+fn chunked_transfer_encoding_with_trailers() {
+    with_test_server!(|test_server| {
+        async fn http_actor(
+            ctx: actor::Context<!, ThreadSafe>,
+            address: SocketAddr,
+        ) -> io::Result<()> {
+            let mut client = Client::connect(ctx.runtime_ref(), address).await?;
+            let mut response = client.get("/").await?;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert!(response.body().trailers().is_empty());
+            let mut buf = Vec::with_capacity(1024);
+            while !response.body().is_empty() {
+                buf = response.body_mut().recv(buf).await?;
+            }
+            assert_eq!(buf, b"Hello world");
+            let trailers = response.body().trailers();
+            assert_eq!(trailers.len(), 1);
+            assert_eq!(
+                trailers.get_bytes(&HeaderName::from_lowercase("x-checksum")),
+                Some(&b"123"[..])
+            );
+            Ok(())
+        }
+
+        let (mut stream, handle) =
+            test_server.accept(|address| init_actor(actor_fn(http_actor), address).unwrap().0);
+
+        expect_request(
+            &mut stream,
+            Method::Get,
+            "/",
+            Version::Http11,
+            &Headers::from([Header::new(HeaderName::USER_AGENT, USER_AGENT)]),
+            b"",
+        );
+
+        stream
+            .write_all(b"HTTP/1.1 200\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHello\r\n")
+            .unwrap();
+        sleep(Duration::from_millis(100));
+        stream
+            .write_all(b"6\r\n world\r\n0\r\nX-Checksum: 123\r\n\r\n")
+            .unwrap();
+
+        handle.join().unwrap();
+    });
+}
+
+#[test]
+fn ignore_informational_response() {
+    with_test_server!(|test_server| {
+        async fn http_actor(
+            ctx: actor::Context<!, ThreadSafe>,
+            address: SocketAddr,
+        ) -> io::Result<()> {
+            let mut client = Client::connect(ctx.runtime_ref(), address).await?;
+            let response = client.get("/").await?;
+            let headers = Headers::from([Header::new(HeaderName::CONTENT_LENGTH, b"2")]);
+            expect_response(response, Version::Http11, StatusCode::OK, &headers, b"Ok").await;
+            Ok(())
+        }
+
+        let (mut stream, handle) =
+            test_server.accept(|address| init_actor(actor_fn(http_actor), address).unwrap().0);
+
+        expect_request(
+            &mut stream,
+            Method::Get,
+            "/",
+            Version::Http11,
+            &Headers::from([Header::new(HeaderName::USER_AGENT, USER_AGENT)]),
+            b"",
+        );
+
+        stream
+            .write_all(b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200\r\nContent-Length: 2\r\n\r\nOk")
             .unwrap();
 
         handle.join().unwrap();
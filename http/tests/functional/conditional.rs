@@ -0,0 +1,112 @@
+//! Tests for the conditional module.
+
+use std::time::{Duration, SystemTime};
+
+use heph_http::body::EmptyBody;
+use heph_http::conditional::Validators;
+use heph_http::{Header, HeaderName, Headers, Method, Request, StatusCode, Version};
+use httpdate::fmt_http_date;
+
+const ETAG: &str = "\"v1\"";
+
+fn request(method: Method, headers: &[Header<'static, '_>]) -> Request<EmptyBody> {
+    let headers = Headers::from(headers);
+    Request::new(method, "/".to_owned(), Version::Http11, headers, EmptyBody)
+}
+
+fn validators() -> Validators {
+    Validators::new()
+        .with_etag(ETAG)
+        .with_last_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000))
+}
+
+#[test]
+fn unconditional_request() {
+    let request = request(Method::Get, &[]);
+    assert!(!validators().is_not_modified(&request));
+    assert!(validators().not_modified(&request).is_none());
+}
+
+#[test]
+fn if_none_match() {
+    let tests: [(&[u8], bool); 5] = [
+        (b"\"v1\"", true),
+        (b"W/\"v1\"", true),
+        (b"\"v0\", \"v1\"", true),
+        (b"*", true),
+        (b"\"v2\"", false),
+    ];
+    for (if_none_match, expected) in tests {
+        let request = request(
+            Method::Get,
+            &[Header::new(HeaderName::IF_NONE_MATCH, if_none_match)],
+        );
+        assert_eq!(validators().is_not_modified(&request), expected);
+    }
+}
+
+#[test]
+fn if_none_match_without_etag() {
+    let request = request(Method::Get, &[Header::new(HeaderName::IF_NONE_MATCH, b"*")]);
+    let validators = Validators::new().with_last_modified(SystemTime::UNIX_EPOCH);
+    assert!(!validators.is_not_modified(&request));
+}
+
+#[test]
+fn if_modified_since() {
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+    let tests = [
+        (modified, true),
+        (modified + Duration::from_secs(10), true),
+        (modified - Duration::from_secs(10), false),
+    ];
+    for (since, expected) in tests {
+        let since = fmt_http_date(since);
+        let request = request(
+            Method::Get,
+            &[Header::new(HeaderName::IF_MODIFIED_SINCE, since.as_bytes())],
+        );
+        assert_eq!(validators().is_not_modified(&request), expected);
+    }
+}
+
+#[test]
+fn if_none_match_takes_precedence() {
+    let since = fmt_http_date(SystemTime::now());
+    let request = request(
+        Method::Get,
+        &[
+            Header::new(HeaderName::IF_NONE_MATCH, b"\"v2\""),
+            Header::new(HeaderName::IF_MODIFIED_SINCE, since.as_bytes()),
+        ],
+    );
+    assert!(!validators().is_not_modified(&request));
+}
+
+#[test]
+fn only_get_and_head() {
+    let headers = [Header::new(HeaderName::IF_NONE_MATCH, ETAG.as_bytes())];
+    for method in [Method::Get, Method::Head] {
+        assert!(validators().is_not_modified(&request(method, &headers)));
+    }
+    for method in [Method::Post, Method::Put, Method::Delete] {
+        assert!(!validators().is_not_modified(&request(method, &headers)));
+    }
+}
+
+#[test]
+fn not_modified_response() {
+    let request = request(
+        Method::Head,
+        &[Header::new(HeaderName::IF_NONE_MATCH, ETAG.as_bytes())],
+    );
+    let response = validators().not_modified(&request).unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    let headers = response.headers();
+    assert_eq!(headers.get_bytes(&HeaderName::ETAG), Some(ETAG.as_bytes()));
+    let last_modified = fmt_http_date(validators().last_modified().unwrap());
+    assert_eq!(
+        headers.get_bytes(&HeaderName::LAST_MODIFIED),
+        Some(last_modified.as_bytes())
+    );
+}
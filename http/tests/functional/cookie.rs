@@ -0,0 +1,201 @@
+use std::time::{Duration, SystemTime};
+
+use heph_http::cookie::{Cookie, CookieJar, Cookies, ParseCookieError, SameSite};
+use heph_http::head::header::FromHeaderValue;
+use heph_http::{Header, HeaderName, Headers};
+
+use crate::assert_send;
+use crate::assert_sync;
+
+#[test]
+fn cookie_is_send_sync() {
+    assert_send::<Cookie>();
+    assert_sync::<Cookie>();
+    assert_send::<CookieJar>();
+    assert_sync::<CookieJar>();
+}
+
+#[test]
+fn format_cookie() {
+    let tests = &[
+        (Cookie::new("id", "123"), "id=123"),
+        (Cookie::new("id", ""), "id="),
+        (
+            Cookie::new("id", "123").with_expires(SystemTime::UNIX_EPOCH),
+            "id=123; Expires=Thu, 01 Jan 1970 00:00:00 GMT",
+        ),
+        (
+            Cookie::new("id", "123").with_max_age(Duration::from_secs(3600)),
+            "id=123; Max-Age=3600",
+        ),
+        (
+            Cookie::new("id", "123")
+                .with_domain("example.com")
+                .with_path("/docs"),
+            "id=123; Domain=example.com; Path=/docs",
+        ),
+        (
+            Cookie::new("id", "123")
+                .with_secure(true)
+                .with_http_only(true)
+                .with_same_site(SameSite::Strict),
+            "id=123; Secure; HttpOnly; SameSite=Strict",
+        ),
+    ];
+    for (cookie, expected) in tests {
+        assert_eq!(cookie.to_string(), *expected);
+    }
+}
+
+#[test]
+fn parse_set_cookie() {
+    let cookie = Cookie::from_bytes(b"id=123").unwrap();
+    assert_eq!(cookie, Cookie::new("id", "123"));
+
+    let cookie = Cookie::from_bytes(
+        b"id=\"abc\"; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=60; \
+        Domain=.Example.com; Path=/docs; Secure; HttpOnly; SameSite=Lax",
+    )
+    .unwrap();
+    assert_eq!(cookie.name(), "id");
+    assert_eq!(cookie.value(), "abc");
+    assert_eq!(cookie.expires(), Some(SystemTime::UNIX_EPOCH));
+    assert_eq!(cookie.max_age(), Some(Duration::from_secs(60)));
+    assert_eq!(cookie.domain(), Some("example.com"));
+    assert_eq!(cookie.path(), Some("/docs"));
+    assert!(cookie.secure());
+    assert!(cookie.http_only());
+    assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+}
+
+#[test]
+fn parse_set_cookie_attributes_case_insensitive() {
+    let cookie =
+        Cookie::from_bytes(b"id=123; max-age=10; SECURE; httponly; samesite=none").unwrap();
+    assert_eq!(cookie.max_age(), Some(Duration::from_secs(10)));
+    assert!(cookie.secure());
+    assert!(cookie.http_only());
+    assert_eq!(cookie.same_site(), Some(SameSite::None));
+}
+
+#[test]
+fn parse_set_cookie_invalid_attributes_ignored() {
+    let cookie = Cookie::from_bytes(
+        b"id=123; Expires=tomorrow; Max-Age=1a; Domain=; Path=docs; SameSite=sometimes; Unknown=1",
+    )
+    .unwrap();
+    assert_eq!(cookie, Cookie::new("id", "123"));
+}
+
+#[test]
+fn parse_set_cookie_negative_max_age() {
+    let cookie = Cookie::from_bytes(b"id=123; Max-Age=-1").unwrap();
+    assert_eq!(cookie.max_age(), Some(Duration::ZERO));
+}
+
+#[test]
+fn parse_set_cookie_roundtrip() {
+    let cookie = Cookie::new("id", "123")
+        .with_expires(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000))
+        .with_max_age(Duration::from_secs(60))
+        .with_domain("example.com")
+        .with_path("/")
+        .with_secure(true)
+        .with_http_only(true)
+        .with_same_site(SameSite::Lax);
+    let got = Cookie::from_bytes(cookie.to_string().as_bytes()).unwrap();
+    assert_eq!(got, cookie);
+}
+
+#[test]
+fn parse_set_cookie_errors() {
+    let tests: &[(&[u8], ParseCookieError)] = &[
+        (b"", ParseCookieError::MissingValue),
+        (b"id", ParseCookieError::MissingValue),
+        (b"id; Path=/", ParseCookieError::MissingValue),
+        (b"=123", ParseCookieError::EmptyName),
+        (&[b'i', b'd', b'=', 255], ParseCookieError::InvalidUtf8),
+    ];
+    for (input, expected) in tests {
+        assert_eq!(Cookie::from_bytes(input).unwrap_err(), *expected);
+    }
+}
+
+#[test]
+fn parse_cookies() {
+    let tests: &[(&str, &[(&str, &str)])] = &[
+        ("", &[]),
+        ("id=123", &[("id", "123")]),
+        ("id=123; lang=en", &[("id", "123"), ("lang", "en")]),
+        ("id=123;lang=en", &[("id", "123"), ("lang", "en")]),
+        ("id=\"123\"; lang=", &[("id", "123"), ("lang", "")]),
+        // Invalid pairs are skipped.
+        ("invalid; id=123; =abc", &[("id", "123")]),
+    ];
+    for (input, expected) in tests {
+        let got: Vec<(&str, &str)> = Cookies::from_bytes(input.as_bytes()).unwrap().collect();
+        assert_eq!(got, *expected, "input: {input}");
+    }
+}
+
+#[test]
+fn cookie_jar() {
+    let mut jar = CookieJar::new();
+    assert!(jar.is_empty());
+    assert_eq!(jar.to_header_value(), None);
+
+    jar.add(Cookie::new("id", "123"));
+    jar.add(Cookie::new("lang", "en"));
+    assert_eq!(jar.len(), 2);
+    assert_eq!(jar.get("id").unwrap().value(), "123");
+    assert_eq!(jar.to_header_value().as_deref(), Some("id=123; lang=en"));
+
+    // Replaces the existing cookie.
+    jar.add(Cookie::new("id", "456"));
+    assert_eq!(jar.len(), 2);
+    assert_eq!(jar.to_header_value().as_deref(), Some("lang=en; id=456"));
+
+    // Removes the existing cookie.
+    jar.add(Cookie::new("id", "").with_max_age(Duration::ZERO));
+    assert!(jar.get("id").is_none());
+    assert_eq!(jar.to_header_value().as_deref(), Some("lang=en"));
+
+    assert_eq!(jar.remove("lang"), Some(Cookie::new("lang", "en")));
+    assert_eq!(jar.remove("lang"), None);
+    assert!(jar.is_empty());
+}
+
+#[test]
+fn cookie_jar_expired() {
+    let mut jar = CookieJar::new();
+    jar.add(Cookie::new("old", "1").with_expires(SystemTime::UNIX_EPOCH));
+    assert!(jar.is_empty());
+
+    let expires = SystemTime::now() + Duration::from_secs(3600);
+    jar.add(Cookie::new("id", "123").with_expires(expires));
+    // `Max-Age` takes precedence over `Expires`.
+    jar.add(
+        Cookie::new("lang", "en")
+            .with_expires(SystemTime::UNIX_EPOCH)
+            .with_max_age(Duration::from_secs(3600)),
+    );
+    jar.remove_expired();
+    assert_eq!(jar.len(), 2);
+    let names: Vec<&str> = jar.iter().map(Cookie::name).collect();
+    assert_eq!(names, ["id", "lang"]);
+}
+
+#[test]
+fn cookie_jar_store() {
+    let headers = Headers::from([
+        Header::new(HeaderName::SET_COOKIE, b"id=123; Path=/; HttpOnly"),
+        Header::new(HeaderName::CONTENT_TYPE, b"text/plain"),
+        Header::new(HeaderName::SET_COOKIE, b"invalid"),
+        Header::new(HeaderName::SET_COOKIE, b"lang=en; Max-Age=60"),
+    ]);
+    let mut jar = CookieJar::new();
+    jar.store(&headers);
+    assert_eq!(jar.len(), 2);
+    assert!(jar.get("id").unwrap().http_only());
+    assert_eq!(jar.to_header_value().as_deref(), Some("id=123; lang=en"));
+}
@@ -0,0 +1,167 @@
+//! Tests for the cors module.
+
+use std::time::Duration;
+
+use heph_http::body::{EmptyBody, OneshotBody};
+use heph_http::cors::{Cors, CorsBody, CorsConfig};
+use heph_http::handler::Handler;
+use heph_http::{Header, HeaderName, Headers, Method, Request, Response, StatusCode, Version};
+use heph_rt::test::block_on_future;
+
+const ORIGIN: &str = "https://example.com";
+
+async fn handler<B>(request: Request<B>) -> Response<OneshotBody<&'static str>> {
+    match (request.method(), request.path()) {
+        (Method::Options, _) => Response::ok().with_body(OneshotBody::new("options")),
+        (_, "/pets") => Response::ok().with_body(OneshotBody::new("pets")),
+        _ => Response::not_found().with_body(OneshotBody::new("not found")),
+    }
+}
+
+fn request(method: Method, path: &str, headers: &[Header<'static, '_>]) -> Request<EmptyBody> {
+    let headers = Headers::from(headers);
+    Request::new(method, path.to_owned(), Version::Http11, headers, EmptyBody)
+}
+
+fn preflight(path: &str, origin: &str) -> Request<EmptyBody> {
+    let headers = [
+        Header::new(HeaderName::ORIGIN, origin.as_bytes()),
+        Header::new(HeaderName::ACCESS_CONTROL_REQUEST_METHOD, b"POST"),
+    ];
+    request(Method::Options, path, &headers)
+}
+
+fn header<B>(response: &Response<B>, name: &HeaderName<'_>) -> Option<String> {
+    let value = response.headers().get_bytes(name)?;
+    Some(String::from_utf8(value.to_vec()).unwrap())
+}
+
+fn body(response: Response<CorsBody<OneshotBody<&'static str>>>) -> Option<&'static str> {
+    let (_, body) = response.split();
+    body.into_inner().map(|body| body.into_inner())
+}
+
+fn pets_middleware<H>(handler: H, config: CorsConfig) -> Cors<H> {
+    Cors::new(handler).with_route("/pets", &[Method::Get, Method::Head, Method::Post], config)
+}
+
+#[test]
+fn preflight_request() {
+    let config = CorsConfig::new()
+        .with_allowed_origin(ORIGIN)
+        .with_allowed_header(HeaderName::CONTENT_TYPE)
+        .with_max_age(Duration::from_secs(600));
+    let middleware = pets_middleware(handler, config);
+
+    let response = block_on_future(middleware.handle((preflight("/pets", ORIGIN),)));
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let allow_origin = header(&response, &HeaderName::ACCESS_CONTROL_ALLOW_ORIGIN);
+    assert_eq!(allow_origin.as_deref(), Some(ORIGIN));
+    let vary = header(&response, &HeaderName::VARY);
+    assert_eq!(vary.as_deref(), Some("Origin"));
+    let allow_methods = header(&response, &HeaderName::ACCESS_CONTROL_ALLOW_METHODS);
+    assert_eq!(allow_methods.as_deref(), Some("GET, HEAD, POST"));
+    let allow_headers = header(&response, &HeaderName::ACCESS_CONTROL_ALLOW_HEADERS);
+    assert_eq!(allow_headers.as_deref(), Some("content-type"));
+    let max_age = header(&response, &HeaderName::ACCESS_CONTROL_MAX_AGE);
+    assert_eq!(max_age.as_deref(), Some("600"));
+    let credentials = header(&response, &HeaderName::ACCESS_CONTROL_ALLOW_CREDENTIALS);
+    assert_eq!(credentials, None);
+    assert_eq!(body(response), None);
+}
+
+#[test]
+fn preflight_request_allowed_methods() {
+    let config = CorsConfig::new().with_allowed_methods(&[Method::Get, Method::Post]);
+    let middleware = pets_middleware(handler, config);
+
+    let response = block_on_future(middleware.handle((preflight("/pets", ORIGIN),)));
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let allow_origin = header(&response, &HeaderName::ACCESS_CONTROL_ALLOW_ORIGIN);
+    assert_eq!(allow_origin.as_deref(), Some("*"));
+    let allow_methods = header(&response, &HeaderName::ACCESS_CONTROL_ALLOW_METHODS);
+    assert_eq!(allow_methods.as_deref(), Some("GET, POST"));
+}
+
+#[test]
+fn preflight_request_origin_not_allowed() {
+    let config = CorsConfig::new().with_allowed_origin(ORIGIN);
+    let middleware = pets_middleware(handler, config);
+
+    let request = preflight("/pets", "https://other.example.com");
+    let response = block_on_future(middleware.handle((request,)));
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let allow_origin = header(&response, &HeaderName::ACCESS_CONTROL_ALLOW_ORIGIN);
+    assert_eq!(allow_origin, None);
+    let allow_methods = header(&response, &HeaderName::ACCESS_CONTROL_ALLOW_METHODS);
+    assert_eq!(allow_methods, None);
+}
+
+#[test]
+fn preflight_request_unknown_route() {
+    let middleware = pets_middleware(handler, CorsConfig::new());
+
+    // Not a registered route, so it's passed to the handler.
+    let response = block_on_future(middleware.handle((preflight("/other", ORIGIN),)));
+    assert_eq!(response.status(), StatusCode::OK);
+    let allow_origin = header(&response, &HeaderName::ACCESS_CONTROL_ALLOW_ORIGIN);
+    assert_eq!(allow_origin, None);
+    assert_eq!(body(response), Some("options"));
+}
+
+#[test]
+fn preflight_request_default() {
+    let middleware = pets_middleware(handler, CorsConfig::new()).with_default(CorsConfig::new());
+
+    let response = block_on_future(middleware.handle((preflight("/other", ORIGIN),)));
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let allow_methods = header(&response, &HeaderName::ACCESS_CONTROL_ALLOW_METHODS);
+    assert_eq!(allow_methods.as_deref(), Some("GET, HEAD"));
+}
+
+#[test]
+fn options_request_without_request_method() {
+    let middleware = pets_middleware(handler, CorsConfig::new());
+
+    // Not a preflight request, so it's passed to the handler.
+    let headers = [Header::new(HeaderName::ORIGIN, ORIGIN.as_bytes())];
+    let request = request(Method::Options, "/pets", &headers);
+    let response = block_on_future(middleware.handle((request,)));
+    assert_eq!(response.status(), StatusCode::OK);
+    let allow_origin = header(&response, &HeaderName::ACCESS_CONTROL_ALLOW_ORIGIN);
+    assert_eq!(allow_origin.as_deref(), Some("*"));
+    assert_eq!(body(response), Some("options"));
+}
+
+#[test]
+fn cors_request() {
+    let config = CorsConfig::new()
+        .with_allowed_origin(ORIGIN)
+        .with_exposed_header(HeaderName::ETAG)
+        .with_credentials(true);
+    let middleware = pets_middleware(handler, config);
+
+    let headers = [Header::new(HeaderName::ORIGIN, ORIGIN.as_bytes())];
+    let request = request(Method::Get, "/pets", &headers);
+    let response = block_on_future(middleware.handle((request,)));
+    assert_eq!(response.status(), StatusCode::OK);
+    let allow_origin = header(&response, &HeaderName::ACCESS_CONTROL_ALLOW_ORIGIN);
+    assert_eq!(allow_origin.as_deref(), Some(ORIGIN));
+    let credentials = header(&response, &HeaderName::ACCESS_CONTROL_ALLOW_CREDENTIALS);
+    assert_eq!(credentials.as_deref(), Some("true"));
+    let expose = header(&response, &HeaderName::ACCESS_CONTROL_EXPOSE_HEADERS);
+    assert_eq!(expose.as_deref(), Some("etag"));
+    let allow_methods = header(&response, &HeaderName::ACCESS_CONTROL_ALLOW_METHODS);
+    assert_eq!(allow_methods, None);
+    assert_eq!(body(response), Some("pets"));
+}
+
+#[test]
+fn request_without_origin() {
+    let middleware = pets_middleware(handler, CorsConfig::new());
+
+    let response = block_on_future(middleware.handle((Request::get("/pets".into()),)));
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().is_empty());
+    assert_eq!(body(response), Some("pets"));
+}
@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use heph_http::{Extensions, Request};
+
+use crate::{assert_send, assert_size, assert_sync};
+
+#[derive(Clone, Debug, PartialEq)]
+struct RequestId(u64);
+
+#[derive(Clone, Debug, PartialEq)]
+struct User(Arc<str>);
+
+#[test]
+fn size() {
+    assert_size::<Extensions>(8);
+}
+
+#[test]
+fn is_send_sync() {
+    assert_send::<Extensions>();
+    assert_sync::<Extensions>();
+}
+
+#[test]
+fn empty() {
+    let mut extensions = Extensions::new();
+    assert!(extensions.is_empty());
+    assert_eq!(extensions.len(), 0);
+    assert!(!extensions.contains::<RequestId>());
+    assert_eq!(extensions.get::<RequestId>(), None);
+    assert_eq!(extensions.get_mut::<RequestId>(), None);
+    assert_eq!(extensions.remove::<RequestId>(), None);
+    extensions.clear();
+}
+
+#[test]
+fn multiple_types() {
+    let mut extensions = Extensions::new();
+    assert_eq!(extensions.insert(RequestId(1)), None);
+    assert_eq!(extensions.insert(User("Thomas".into())), None);
+    assert_eq!(extensions.len(), 2);
+    assert!(extensions.contains::<RequestId>());
+    assert!(extensions.contains::<User>());
+    assert!(!extensions.contains::<u64>());
+
+    extensions.get_mut::<RequestId>().unwrap().0 += 1;
+    assert_eq!(extensions.get::<RequestId>(), Some(&RequestId(2)));
+    assert_eq!(extensions.get::<User>(), Some(&User("Thomas".into())));
+
+    extensions.clear();
+    assert!(extensions.is_empty());
+    assert_eq!(extensions.get::<User>(), None);
+}
+
+#[test]
+fn clone() {
+    let mut extensions = Extensions::new();
+    _ = extensions.insert(RequestId(1));
+    let mut cloned = extensions.clone();
+    cloned.get_mut::<RequestId>().unwrap().0 = 2;
+    assert_eq!(extensions.get::<RequestId>(), Some(&RequestId(1)));
+    assert_eq!(cloned.get::<RequestId>(), Some(&RequestId(2)));
+}
+
+#[test]
+fn request_extensions() {
+    let mut request = Request::get("/".to_owned());
+    assert!(request.extensions().is_empty());
+    _ = request.extensions_mut().insert(RequestId(123));
+    let request = request.with_body(());
+    assert_eq!(
+        request.extensions().get::<RequestId>(),
+        Some(&RequestId(123))
+    );
+}
@@ -0,0 +1,101 @@
+//! Tests for the json module.
+
+use std::collections::HashMap;
+use std::io;
+
+use heph_http::body::OneshotBody;
+use heph_http::json::{Json, JsonError};
+use heph_http::{HeaderName, Response, StatusCode};
+
+type JsonResponse = Response<OneshotBody<Vec<u8>>>;
+
+#[test]
+fn json_into_response() {
+    let mut values = HashMap::new();
+    _ = values.insert("a", 1);
+    let response = Json(values).into_response().unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response
+            .headers()
+            .get_value::<&str>(&HeaderName::CONTENT_TYPE)
+            .unwrap()
+            .unwrap(),
+        "application/json",
+    );
+    let (_, body) = response.split();
+    assert_eq!(body.into_inner(), br#"{"a":1}"#);
+}
+
+#[test]
+fn json_from_response() {
+    let response = JsonResponse::from(Json(("a", [1, 2])));
+    assert_eq!(response.status(), StatusCode::OK);
+    let (_, body) = response.split();
+    assert_eq!(body.into_inner(), br#"["a",[1,2]]"#);
+}
+
+#[test]
+fn json_result_response() {
+    let result: Result<Json<u64>, JsonError> = Err(JsonError::BodyTooLarge);
+    let response = JsonResponse::from(result);
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let result: Result<Json<u64>, JsonError> = Ok(Json(123));
+    let response = JsonResponse::from(result);
+    assert_eq!(response.status(), StatusCode::OK);
+    let (_, body) = response.split();
+    assert_eq!(body.into_inner(), b"123");
+}
+
+#[test]
+fn json_error_response() {
+    let response = JsonResponse::from(JsonError::InvalidContentType);
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(
+        response
+            .headers()
+            .get_value::<&str>(&HeaderName::CONTENT_TYPE)
+            .unwrap()
+            .unwrap(),
+        "application/json",
+    );
+    let (_, body) = response.split();
+    assert_eq!(
+        body.into_inner(),
+        br#"{"error":"invalid JSON Content-Type header"}"#
+    );
+}
+
+#[test]
+fn json_error_proper_status_code() {
+    let syntax_err = serde_json::from_str::<u64>("1 2").unwrap_err();
+    let data_err = serde_json::from_str::<u64>("\"a\"").unwrap_err();
+    let tests = [
+        (
+            JsonError::InvalidContentType,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        ),
+        (JsonError::BodyTooLarge, StatusCode::PAYLOAD_TOO_LARGE),
+        (JsonError::IncompleteBody, StatusCode::BAD_REQUEST),
+        (JsonError::Deserialise(syntax_err), StatusCode::BAD_REQUEST),
+        (
+            JsonError::Deserialise(data_err),
+            StatusCode::UNPROCESSABLE_ENTITY,
+        ),
+        (
+            JsonError::Io(io::ErrorKind::ConnectionReset.into()),
+            StatusCode::BAD_REQUEST,
+        ),
+    ];
+    for (err, expected) in tests {
+        assert_eq!(err.proper_status_code(), expected, "{err}");
+    }
+}
+
+#[test]
+fn json_error_fmt() {
+    assert_eq!(JsonError::BodyTooLarge.to_string(), "JSON body too large");
+    let err = JsonError::Deserialise(serde_json::from_str::<u64>("\"a\"").unwrap_err());
+    assert!(err.to_string().starts_with("invalid JSON body: "), "{err}");
+}
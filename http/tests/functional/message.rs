@@ -10,8 +10,8 @@ const BODY1: &'static [u8] = b"Hello world!";
 
 #[test]
 fn size() {
-    assert_size::<RequestHead>(80);
-    assert_size::<ResponseHead>(56);
+    assert_size::<RequestHead>(112);
+    assert_size::<ResponseHead>(64);
 }
 
 #[test]
@@ -1,11 +1,11 @@
-use heph_http::head::method::UnknownMethod;
+use heph_http::head::method::{ExtensionMethod, UnknownMethod, MAX_EXTENSION_METHOD_LEN};
 use heph_http::Method::{self, *};
 
 use crate::assert_size;
 
 #[test]
 fn size() {
-    assert_size::<Method>(1);
+    assert_size::<Method>(24);
 }
 
 #[test]
@@ -32,6 +32,15 @@ fn is_idempotent() {
     }
 }
 
+#[test]
+fn is_head() {
+    assert!(Head.is_head());
+    let not_head = &[Get, Post, Put, Delete, Connect, Options, Trace, Patch];
+    for method in not_head {
+        assert!(!method.is_head());
+    }
+}
+
 #[test]
 fn expects_body() {
     let no_body = &[Head];
@@ -86,12 +95,85 @@ fn from_str() {
 
 #[test]
 fn from_invalid_str() {
-    let tests = &["abc", "abcd", "abcde", "abcdef", "abcdefg", "abcdefgh"];
+    let too_long = "A".repeat(MAX_EXTENSION_METHOD_LEN + 1);
+    let tests = &["", "GE T", "GET/", "G\"T", "MY\nGET", "MY:GET", &too_long];
+    for input in tests {
+        assert!(input.parse::<Method>().is_err(), "input: {input:?}");
+    }
+}
+
+#[test]
+fn extension_method() {
+    let longest = "A".repeat(MAX_EXTENSION_METHOD_LEN);
+    let tests = &[
+        "PROPFIND",
+        "propfind",
+        "MY_GET",
+        "abc",
+        "VERSION-CONTROL",
+        &longest,
+    ];
     for input in tests {
-        assert!(input.parse::<Method>().is_err());
+        let method: Method = input.parse().unwrap();
+        assert!(matches!(method, Method::Extension(_)));
+        // Case is kept as is.
+        assert_eq!(method.as_str(), *input);
+        assert_eq!(method.to_string(), *input);
+        assert_eq!(format!("{method:?}"), format!("Extension({input})"));
+        assert!(method.expects_body());
     }
 }
 
+#[test]
+fn extension_method_is_safe_is_idempotent() {
+    let tests = &[
+        // Method, safe, idempotent.
+        ("PROPFIND", true, true),
+        ("ProPFind", true, true),
+        ("REPORT", true, true),
+        ("SEARCH", true, true),
+        ("MKCOL", false, true),
+        ("COPY", false, true),
+        ("MOVE", false, true),
+        ("PROPPATCH", false, true),
+        ("UNLOCK", false, true),
+        ("LOCK", false, false),
+        // Unknown methods.
+        ("MY_GET", false, false),
+        ("PROPFIND2", false, false),
+    ];
+    for (input, safe, idempotent) in tests {
+        let method: Method = input.parse().unwrap();
+        assert_eq!(method.is_safe(), *safe, "method: {input}");
+        assert_eq!(method.is_idempotent(), *idempotent, "method: {input}");
+        let method: ExtensionMethod = input.parse().unwrap();
+        assert_eq!(method.is_safe(), *safe, "method: {input}");
+        assert_eq!(method.is_idempotent(), *idempotent, "method: {input}");
+    }
+}
+
+#[test]
+fn extension_method_cmp() {
+    let propfind: Method = "PROPFIND".parse().unwrap();
+    assert_eq!(propfind, "PROPFIND".parse::<Method>().unwrap());
+    assert_ne!(propfind, "MKCOL".parse::<Method>().unwrap());
+    assert_ne!(propfind, Get);
+    assert!(propfind.eq("PROPFIND"));
+    assert!(propfind.eq("propfind"));
+    assert!(!propfind.eq("MKCOL"));
+    assert!(!propfind.eq("GET"));
+    assert!(!Get.eq("PROPFIND"));
+}
+
+#[test]
+fn extension_method_from_invalid_str() {
+    assert!("GET".parse::<ExtensionMethod>().is_ok());
+    assert!("".parse::<ExtensionMethod>().is_err());
+    assert!("GE T".parse::<ExtensionMethod>().is_err());
+    let too_long = "A".repeat(MAX_EXTENSION_METHOD_LEN + 1);
+    assert!(too_long.parse::<ExtensionMethod>().is_err());
+}
+
 #[test]
 fn cmp_with_string() {
     let tests = &[
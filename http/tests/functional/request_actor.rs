@@ -0,0 +1,208 @@
+//! Tests for the request_actor module.
+
+use std::io::{self, Read, Write};
+use std::net::{self, SocketAddr};
+use std::str;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, sleep};
+use std::time::Duration;
+
+use heph::actor::{self, actor_fn};
+use heph::messages::Terminate;
+use heph::supervisor::{NoSupervisor, SupervisorStrategy};
+use heph::ActorRef;
+use heph_http::body::OneshotBody;
+use heph_http::request_actor::{HttpActor, Responder};
+use heph_http::server;
+use heph_http::{self as http, Method, Request, Response};
+use heph_rt::net::TcpStream;
+use heph_rt::spawn::options::ActorOptions;
+use heph_rt::{Runtime, ThreadLocal};
+
+#[test]
+fn handle_requests() {
+    let test_server = TestServer::new();
+    let mut stream = test_server.connect();
+
+    stream.write_all(b"GET /hello HTTP/1.1\r\n\r\n").unwrap();
+    let response = read_response(&mut stream);
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    assert!(response.ends_with("\r\n\r\nHello /hello"), "{response}");
+
+    // Body is passed to the actor.
+    stream
+        .write_all(b"POST /echo HTTP/1.1\r\nContent-Length: 11\r\n\r\nHello world")
+        .unwrap();
+    let response = read_response(&mut stream);
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    assert!(response.ends_with("\r\n\r\nHello world"), "{response}");
+
+    // Chunked body is read in full as well.
+    stream
+        .write_all(
+            b"POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHello\r\n0\r\n\r\n",
+        )
+        .unwrap();
+    let response = read_response(&mut stream);
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    assert!(response.ends_with("\r\n\r\nHello"), "{response}");
+
+    // Actor dropping the responder.
+    stream.write_all(b"GET /drop HTTP/1.1\r\n\r\n").unwrap();
+    let response = read_response(&mut stream);
+    assert!(response.starts_with("HTTP/1.1 500"), "{response}");
+
+    // Actor failing.
+    stream.write_all(b"GET /panic HTTP/1.1\r\n\r\n").unwrap();
+    let response = read_response(&mut stream);
+    assert!(response.starts_with("HTTP/1.1 500"), "{response}");
+
+    // Connection still usable.
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+    let response = read_response(&mut stream);
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+    drop(stream);
+    test_server.join();
+}
+
+#[test]
+fn too_large_body() {
+    let test_server = TestServer::new();
+    let mut stream = test_server.connect();
+
+    stream
+        .write_all(b"POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n10\r\n0123456789abcdef\r\n0\r\n\r\n")
+        .unwrap();
+    let response = read_response(&mut stream);
+    assert!(response.starts_with("HTTP/1.1 413"), "{response}");
+    assert!(response.contains("connection: close"), "{response}");
+    let mut buf = [0; 16];
+    assert_eq!(stream.read(&mut buf).unwrap(), 0);
+
+    drop(stream);
+    test_server.join();
+}
+
+/// Maximum request body size.
+const MAX_BODY_SIZE: usize = 12;
+
+fn read_response(stream: &mut net::TcpStream) -> String {
+    let mut buf = [0; 1024];
+    let n = stream.read(&mut buf).unwrap();
+    str::from_utf8(&buf[..n]).unwrap().to_owned()
+}
+
+/// Routes:
+/// GET /drop => drops the responder.
+/// GET /panic => panics.
+/// POST /echo => 200, $request_body.
+/// * => 200, Hello $path.
+async fn request_actor(
+    _: actor::Context<!, ThreadLocal>,
+    (request, responder): (Request<Vec<u8>>, Responder),
+) {
+    let body = match (request.method(), request.path()) {
+        (Method::Get, "/drop") => return,
+        (Method::Get, "/panic") => panic!("oops"),
+        (Method::Post, "/echo") => request.body().clone(),
+        (_, path) => format!("Hello {path}").into_bytes(),
+    };
+    let response = Response::ok().with_body(OneshotBody::new(body));
+    responder.respond(response).unwrap();
+}
+
+struct TestServer {
+    address: SocketAddr,
+    server_ref: ActorRef<Terminate>,
+    handle: thread::JoinHandle<()>,
+}
+
+impl TestServer {
+    fn new() -> TestServer {
+        const TIMEOUT: Duration = Duration::from_secs(1);
+
+        let server_ref = Arc::new((Mutex::new(None), Condvar::new()));
+        let set_ref = server_ref.clone();
+
+        let actor = HttpActor::new(
+            NoSupervisor,
+            actor_fn(request_actor),
+            ActorOptions::default(),
+        )
+        .with_max_body_size(MAX_BODY_SIZE);
+        let address = "127.0.0.1:0".parse().unwrap();
+        let server = server::setup(address, conn_supervisor, actor, ActorOptions::default())
+            .map_err(heph_rt::Error::setup)
+            .unwrap();
+        let address = server.local_addr();
+
+        let handle = thread::spawn(move || {
+            let mut runtime = Runtime::setup().num_threads(1).build().unwrap();
+            runtime
+                .run_on_workers(move |mut runtime_ref| -> Result<(), !> {
+                    let mut server_ref = set_ref.0.lock().unwrap();
+                    *server_ref = Some(
+                        runtime_ref
+                            .try_spawn_local(server_supervisor, server, (), ActorOptions::default())
+                            .unwrap()
+                            .map(),
+                    );
+                    set_ref.1.notify_all();
+                    Ok(())
+                })
+                .unwrap();
+
+            runtime.start().unwrap()
+        });
+        let mut server_ref = server_ref
+            .1
+            .wait_timeout_while(server_ref.0.lock().unwrap(), TIMEOUT, |r| r.is_none())
+            .unwrap()
+            .0;
+        let server_ref = server_ref.take().unwrap();
+        TestServer {
+            address,
+            server_ref,
+            handle,
+        }
+    }
+
+    fn connect(&self) -> net::TcpStream {
+        let stream = loop {
+            match net::TcpStream::connect(self.address) {
+                Ok(stream) => break stream,
+                Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => {
+                    // Give the server some time to start up.
+                    sleep(Duration::from_millis(1));
+                }
+                Err(err) => panic!("failed to connect to {}: {err}", self.address),
+            }
+        };
+        stream.set_nodelay(true).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        stream
+            .set_write_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        stream
+    }
+
+    fn join(self) {
+        _ = self.server_ref.try_send(Terminate);
+        self.handle.join().unwrap()
+    }
+}
+
+fn server_supervisor(err: http::server::Error<!>) -> SupervisorStrategy<()> {
+    use http::server::Error::*;
+    match err {
+        Accept(err) => panic!("error accepting new connection: {err}"),
+        NewActor(_) => unreachable!(),
+    }
+}
+
+fn conn_supervisor(err: io::Error) -> SupervisorStrategy<TcpStream> {
+    panic!("error handling connection: {err}")
+}
@@ -1,15 +1,18 @@
+use std::async_iter::AsyncIterator;
 use std::borrow::Cow;
 use std::io::{self, Read, Write};
 use std::net::{self, Shutdown, SocketAddr};
+use std::pin::Pin;
 use std::str;
 use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::task::{self, Poll};
 use std::thread::{self, sleep};
 use std::time::{Duration, SystemTime};
 
 use heph::actor::{self, actor_fn};
 use heph::messages::Terminate;
 use heph::{ActorRef, SupervisorStrategy};
-use heph_http::body::OneshotBody;
+use heph_http::body::{ChunkedBody, OneshotBody};
 use heph_http::server::{self, RequestError};
 use heph_http::{self as http, Header, HeaderName, Headers, Method, StatusCode, Version};
 use heph_rt::net::TcpStream;
@@ -69,7 +72,8 @@ fn head() {
         let mut headers = Headers::EMPTY;
         let now = fmt_http_date(SystemTime::now());
         headers.append(Header::new(HeaderName::DATE, now.as_bytes()));
-        headers.append(Header::new(HeaderName::CONTENT_LENGTH, b"0"));
+        // Same length as for the GET request, without the body.
+        headers.append(Header::new(HeaderName::CONTENT_LENGTH, b"2"));
         let body = b"";
         expect_response(&mut stream, Version::Http11, StatusCode::OK, &headers, body);
     });
@@ -140,7 +144,10 @@ fn deny_incomplete_request() {
 #[test]
 fn deny_unknown_method() {
     with_test_server!(|stream| {
-        stream.write_all(b"MY_GET / HTTP/1.1\r\n\r\n").unwrap();
+        // Too long for an extension method.
+        stream
+            .write_all(b"MY_VERY_LONG_CUSTOM_METHOD / HTTP/1.1\r\n\r\n")
+            .unwrap();
         stream.shutdown(Shutdown::Write).unwrap();
         let status = StatusCode::NOT_IMPLEMENTED;
         let mut headers = Headers::EMPTY;
@@ -358,7 +365,7 @@ fn deny_chunked_transfer_encoding_and_content_length_request() {
 fn empty_body_chunked_transfer_encoding() {
     with_test_server!(|stream| {
         stream
-            .write_all(b"POST /echo-body HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n")
+            .write_all(b"POST /echo-body HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n")
             .unwrap();
         let status = StatusCode::OK;
         let mut headers = Headers::EMPTY;
@@ -396,7 +403,7 @@ fn read_partial_chunk_size_chunked_transfer_encoding() {
             .write_all(b"POST /echo-body HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n")
             .unwrap();
         sleep(Duration::from_millis(200));
-        stream.write_all(b"0\r\n").unwrap();
+        stream.write_all(b"0\r\n\r\n").unwrap();
         let status = StatusCode::OK;
         let mut headers = Headers::EMPTY;
         let now = fmt_http_date(SystemTime::now());
@@ -407,6 +414,167 @@ fn read_partial_chunk_size_chunked_transfer_encoding() {
     });
 }
 
+#[test]
+fn chunked_transfer_encoding_with_trailers() {
+    with_test_server!(|stream| {
+        stream
+            .write_all(
+                b"POST /echo-trailers HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nAbc\r\n",
+            )
+            .unwrap();
+        sleep(Duration::from_millis(200));
+        stream
+            .write_all(b"2\r\nde\r\n0\r\nX-Checksum: 123\r\n\r\n")
+            .unwrap();
+        let status = StatusCode::OK;
+        let mut headers = Headers::EMPTY;
+        let now = fmt_http_date(SystemTime::now());
+        headers.append(Header::new(HeaderName::DATE, now.as_bytes()));
+        headers.append(Header::new(HeaderName::CONTENT_LENGTH, b"5"));
+        headers.append(Header::new(
+            HeaderName::from_lowercase("x-checksum"),
+            b"123",
+        ));
+        let body = b"Abcde";
+        expect_response(&mut stream, Version::Http11, status, &headers, body);
+    });
+}
+
+#[test]
+fn slow_body() {
+    // Test reading the body directly from the stream, i.e. not from the
+    // connection's buffer.
+    with_test_server!(|stream| {
+        stream
+            .write_all(b"POST /echo-trailers HTTP/1.1\r\nContent-Length: 5\r\n\r\n")
+            .unwrap();
+        sleep(Duration::from_millis(200));
+        stream.write_all(b"Abc").unwrap();
+        sleep(Duration::from_millis(200));
+        stream.write_all(b"de").unwrap();
+        let status = StatusCode::OK;
+        let mut headers = Headers::EMPTY;
+        let now = fmt_http_date(SystemTime::now());
+        headers.append(Header::new(HeaderName::DATE, now.as_bytes()));
+        headers.append(Header::new(HeaderName::CONTENT_LENGTH, b"5"));
+        let body = b"Abcde";
+        expect_response(&mut stream, Version::Http11, status, &headers, body);
+    });
+}
+
+#[test]
+fn slow_chunk_data() {
+    // Same as `slow_body`, but using chunked transfer encoding.
+    with_test_server!(|stream| {
+        stream
+            .write_all(b"POST /echo-trailers HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\n")
+            .unwrap();
+        sleep(Duration::from_millis(200));
+        stream.write_all(b"Abc\r\n2\r\n").unwrap();
+        sleep(Duration::from_millis(200));
+        stream.write_all(b"de\r\n0\r\n\r\n").unwrap();
+        let status = StatusCode::OK;
+        let mut headers = Headers::EMPTY;
+        let now = fmt_http_date(SystemTime::now());
+        headers.append(Header::new(HeaderName::DATE, now.as_bytes()));
+        headers.append(Header::new(HeaderName::CONTENT_LENGTH, b"5"));
+        let body = b"Abcde";
+        expect_response(&mut stream, Version::Http11, status, &headers, body);
+    });
+}
+
+#[test]
+fn chunked_body() {
+    with_test_server!(|stream| {
+        stream.write_all(b"GET /chunked HTTP/1.1\r\n\r\n").unwrap();
+        let status = StatusCode::OK;
+        let mut headers = Headers::EMPTY;
+        let now = fmt_http_date(SystemTime::now());
+        headers.append(Header::new(HeaderName::DATE, now.as_bytes()));
+        headers.append(Header::new(HeaderName::TRANSFER_ENCODING, b"chunked"));
+        // Every chunk must be prefixed with its size and end with CRLF.
+        let body = b"5\r\nHello\r\n6\r\n world\r\n0\r\n\r\n";
+        expect_response(&mut stream, Version::Http11, status, &headers, body);
+    });
+}
+
+#[test]
+fn expect_continue() {
+    with_test_server!(|stream| {
+        stream
+            .write_all(
+                b"POST /echo-body HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 3\r\n\r\n",
+            )
+            .unwrap();
+        let mut buf = [0; 17];
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"HTTP/1.1 100 \r\n\r\n");
+        stream.write_all(b"Abc").unwrap();
+        let status = StatusCode::OK;
+        let mut headers = Headers::EMPTY;
+        let now = fmt_http_date(SystemTime::now());
+        headers.append(Header::new(HeaderName::DATE, now.as_bytes()));
+        headers.append(Header::new(HeaderName::CONTENT_LENGTH, b"3"));
+        let body = b"Abc";
+        expect_response(&mut stream, Version::Http11, status, &headers, body);
+    });
+}
+
+#[test]
+fn expect_continue_body_not_read() {
+    with_test_server!(|stream| {
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 3\r\n\r\n")
+            .unwrap();
+        // Not reading the body means we can't read the next request, so the
+        // connection is closed.
+        let status = StatusCode::OK;
+        let mut headers = Headers::EMPTY;
+        let now = fmt_http_date(SystemTime::now());
+        headers.append(Header::new(HeaderName::DATE, now.as_bytes()));
+        headers.append(Header::new(HeaderName::CONTENT_LENGTH, b"2"));
+        headers.append(Header::new(HeaderName::CONNECTION, b"close"));
+        let body = b"OK";
+        expect_response(&mut stream, Version::Http11, status, &headers, body);
+    });
+}
+
+#[test]
+fn deny_unknown_expectation() {
+    with_test_server!(|stream| {
+        stream
+            .write_all(b"POST /echo-body HTTP/1.1\r\nExpect: 200-ok\r\nContent-Length: 3\r\n\r\n")
+            .unwrap();
+        let status = StatusCode::EXPECTATION_FAILED;
+        let mut headers = Headers::EMPTY;
+        let now = fmt_http_date(SystemTime::now());
+        headers.append(Header::new(HeaderName::DATE, now.as_bytes()));
+        headers.append(Header::new(HeaderName::CONTENT_LENGTH, b"31"));
+        headers.append(Header::new(HeaderName::CONNECTION, b"close"));
+        let body = b"Bad request: expectation failed";
+        expect_response(&mut stream, Version::Http11, status, &headers, body);
+    });
+}
+
+#[test]
+fn deny_too_large_body() {
+    with_test_server!(|stream| {
+        stream
+            .write_all(
+                b"POST /echo-body HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 1025\r\n\r\n",
+            )
+            .unwrap();
+        let status = StatusCode::PAYLOAD_TOO_LARGE;
+        let mut headers = Headers::EMPTY;
+        let now = fmt_http_date(SystemTime::now());
+        headers.append(Header::new(HeaderName::DATE, now.as_bytes()));
+        headers.append(Header::new(HeaderName::CONTENT_LENGTH, b"27"));
+        headers.append(Header::new(HeaderName::CONNECTION, b"close"));
+        let body = b"Bad request: body too large";
+        expect_response(&mut stream, Version::Http11, status, &headers, body);
+    });
+}
+
 #[test]
 fn too_large_http_head() {
     // Tests `heph_http::MAX_HEAD_SIZE`.
@@ -510,6 +678,98 @@ fn too_many_header() {
     });
 }
 
+#[test]
+fn graceful_shutdown() {
+    let test_server = Arc::new(TestServer::new(Duration::from_secs(5)));
+    let connect = || {
+        let mut stream = loop {
+            match net::TcpStream::connect(test_server.address) {
+                Ok(stream) => break stream,
+                Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => {
+                    // Give the server some time to start up.
+                    sleep(Duration::from_millis(1));
+                }
+                Err(err) => panic!("failed to connect to {}: {err}", test_server.address),
+            }
+        };
+        stream
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        let mut buf = [0; 1024];
+        let n = stream.read(&mut buf).unwrap();
+        let response = str::from_utf8(&buf[..n]).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(!response.contains("Connection: close"), "{response}");
+        stream
+    };
+    let mut idle = connect();
+    let mut active = connect();
+    // Start a request, but don't complete it yet.
+    active.write_all(b"GET / HTTP/1.1\r\n").unwrap();
+    sleep(Duration::from_millis(100));
+
+    test_server.server_ref.try_send(Terminate).unwrap();
+    sleep(Duration::from_millis(100));
+
+    // Idle connection should be closed.
+    let mut buf = [0; 1024];
+    assert_eq!(idle.read(&mut buf).unwrap(), 0);
+
+    // In-flight request should get a response with "Connection: close".
+    active.write_all(b"\r\n").unwrap();
+    let n = active.read(&mut buf).unwrap();
+    let response = str::from_utf8(&buf[..n]).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    assert!(response.contains("Connection: close"), "{response}");
+    assert_eq!(active.read(&mut buf).unwrap(), 0);
+
+    // All connections are closed, so the server should stop before the grace
+    // period.
+    test_server.join();
+}
+
+#[test]
+fn max_connections() {
+    let test_server = Arc::new(TestServer::with_max_connections(
+        server::DEFAULT_GRACE_PERIOD,
+        1,
+    ));
+    let connect = || loop {
+        match net::TcpStream::connect(test_server.address) {
+            Ok(stream) => {
+                stream
+                    .set_read_timeout(Some(Duration::from_secs(1)))
+                    .unwrap();
+                break stream;
+            }
+            Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => {
+                // Give the server some time to start up.
+                sleep(Duration::from_millis(1));
+            }
+            Err(err) => panic!("failed to connect to {}: {err}", test_server.address),
+        }
+    };
+    let mut buf = [0; 1024];
+
+    let mut accepted = connect();
+    accepted.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+    let n = accepted.read(&mut buf).unwrap();
+    let response = str::from_utf8(&buf[..n]).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+    // Over the limit, the connection should be rejected.
+    let mut rejected = connect();
+    let n = rejected.read(&mut buf).unwrap();
+    let response = str::from_utf8(&buf[..n]).unwrap();
+    assert!(response.starts_with("HTTP/1.1 503"), "{response}");
+    assert!(response.contains("Connection: close"), "{response}");
+    assert_eq!(rejected.read(&mut buf).unwrap(), 0);
+
+    drop(accepted);
+    test_server.join();
+}
+
 fn expect_response(
     stream: &mut net::TcpStream,
     // Expected values:
@@ -519,14 +779,26 @@ fn expect_response(
     body: &[u8],
 ) {
     let mut buf = [0; 1024];
-    let n = stream.read(&mut buf).unwrap();
+    let mut n = stream.read(&mut buf).unwrap();
+    // The body can be written separately from the head, ensure we read all of
+    // it.
+    let mut h = [httparse::EMPTY_HEADER; 64];
+    let head_len = httparse::Response::new(&mut h)
+        .parse(&buf[..n])
+        .unwrap()
+        .unwrap();
+    while n < head_len + body.len() {
+        let read = stream.read(&mut buf[n..]).unwrap();
+        assert!(read != 0, "unexpected end of response");
+        n += read;
+    }
     let buf = &buf[..n];
 
-    eprintln!("read response: {:?}", str::from_utf8(&buf[..n]));
+    eprintln!("read response: {:?}", str::from_utf8(buf));
 
     let mut h = [httparse::EMPTY_HEADER; 64];
     let mut response = httparse::Response::new(&mut h);
-    let parsed_n = response.parse(&buf).unwrap().unwrap();
+    let parsed_n = response.parse(buf).unwrap().unwrap();
 
     assert_eq!(response.version, Some(version.minor()));
     assert_eq!(response.code.unwrap(), status.0);
@@ -569,13 +841,17 @@ impl TestServer {
             test_server
         } else {
             // Start a new server.
-            let new_server = Arc::new(TestServer::new());
+            let new_server = Arc::new(TestServer::new(server::DEFAULT_GRACE_PERIOD));
             *test_server = Arc::downgrade(&new_server);
             new_server
         }
     }
 
-    fn new() -> TestServer {
+    fn new(grace_period: Duration) -> TestServer {
+        TestServer::with_max_connections(grace_period, usize::MAX)
+    }
+
+    fn with_max_connections(grace_period: Duration, max_connections: usize) -> TestServer {
         const TIMEOUT: Duration = Duration::from_secs(1);
 
         let server_ref = Arc::new((Mutex::new(None), Condvar::new()));
@@ -585,7 +861,9 @@ impl TestServer {
         let address = "127.0.0.1:0".parse().unwrap();
         let server = server::setup(address, conn_supervisor, actor, ActorOptions::default())
             .map_err(heph_rt::Error::setup)
-            .unwrap();
+            .unwrap()
+            .with_grace_period(grace_period)
+            .with_max_connections(max_connections);
         let address = server.local_addr();
 
         let handle = thread::spawn(move || {
@@ -622,7 +900,8 @@ impl TestServer {
 
     fn join(mut self: Arc<TestServer>) {
         if let Some(this) = Arc::get_mut(&mut self) {
-            this.server_ref.try_send(Terminate).unwrap();
+            // NOTE: the server could already be stopped.
+            _ = this.server_ref.try_send(Terminate);
             this.handle.take().unwrap().join().unwrap()
         }
     }
@@ -640,20 +919,28 @@ fn conn_supervisor(err: io::Error) -> SupervisorStrategy<TcpStream> {
     panic!("error handling connection: {err}")
 }
 
+/// Maximum request body size.
+const MAX_BODY_SIZE: usize = 1024;
+
 /// Routes:
 /// GET / => 200, OK.
 /// POST /echo-body => 200, $request_body.
+/// POST /echo-trailers => 200, $request_body, with the X-Checksum trailer as
+/// header.
+/// GET /chunked => 200, "Hello world" using a chunked body.
 /// * => 404, Not found.
 async fn http_actor(
     _: actor::Context<!, ThreadLocal>,
     mut connection: http::Connection,
 ) -> io::Result<()> {
     connection.set_nodelay(true)?;
+    connection.set_max_body_size(MAX_BODY_SIZE);
 
     let mut headers = Headers::EMPTY;
     loop {
         let mut got_version = None;
         let mut got_method = None;
+        let mut chunked = false;
         let (code, body, should_close) = match connection.next_request().await {
             Ok(Some(mut request)) => {
                 got_version = Some(request.version());
@@ -673,6 +960,22 @@ async fn http_actor(
                         let body = String::from_utf8(buf).unwrap().into();
                         (StatusCode::OK, body, false)
                     }
+                    (Method::Post, "/echo-trailers") => {
+                        let mut buf = Vec::with_capacity(1024);
+                        while !request.body().is_empty() {
+                            buf = request.body_mut().recv(buf).await?;
+                        }
+                        let name = HeaderName::from_lowercase("x-checksum");
+                        if let Some(value) = request.body().trailers().get_bytes(&name) {
+                            headers.append(Header::new(name, value));
+                        }
+                        let body = String::from_utf8(buf).unwrap().into();
+                        (StatusCode::OK, body, false)
+                    }
+                    (Method::Get, "/chunked") => {
+                        chunked = true;
+                        (StatusCode::OK, "".into(), false)
+                    }
                     _ => (StatusCode::NOT_FOUND, "Not found".into(), false),
                 }
             }
@@ -695,9 +998,14 @@ async fn http_actor(
             headers.append(Header::new(HeaderName::CONNECTION, b"close"));
         }
 
-        connection
-            .respond(code, &headers, OneshotBody::new(body))
-            .await?;
+        if chunked {
+            let body = ChunkedBody::new(Chunks(&[b"Hello", b" world"]));
+            connection.respond(code, &headers, body).await?;
+        } else {
+            connection
+                .respond(code, &headers, OneshotBody::new(body))
+                .await?;
+        }
         if should_close {
             return Ok(());
         }
@@ -706,6 +1014,23 @@ async fn http_actor(
     }
 }
 
+/// Stream of body chunks.
+struct Chunks(&'static [&'static [u8]]);
+
+impl AsyncIterator for Chunks {
+    type Item = &'static [u8];
+
+    fn poll_next(mut self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.0.split_first() {
+            Some((chunk, rest)) => {
+                self.0 = rest;
+                Poll::Ready(Some(chunk))
+            }
+            None => Poll::Ready(None),
+        }
+    }
+}
+
 #[test]
 fn request_error_proper_status_code() {
     use RequestError::*;
@@ -725,6 +1050,8 @@ fn request_error_proper_status_code() {
         (InvalidChunkSize, StatusCode::BAD_REQUEST),
         (UnsupportedTransferEncoding, StatusCode::NOT_IMPLEMENTED),
         (UnknownMethod, StatusCode::NOT_IMPLEMENTED),
+        (ExpectationFailed, StatusCode::EXPECTATION_FAILED),
+        (BodyTooLarge, StatusCode::PAYLOAD_TOO_LARGE),
     ];
 
     for (error, expected) in tests {
@@ -750,6 +1077,8 @@ fn request_should_close() {
         (InvalidNewLine, true),
         (InvalidVersion, true),
         (InvalidChunkSize, true),
+        (ExpectationFailed, true),
+        (BodyTooLarge, true),
         (UnknownMethod, false),
     ];
 
@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use heph::actor::{self, actor_fn};
+use heph_http::body::{Body, BodyLength};
+use heph_http::sse::{Event, EventStream};
+use heph_http::{HeaderName, StatusCode};
+use heph_rt::test::block_on_local_actor;
+use heph_rt::{ThreadLocal, ThreadSafe};
+
+use crate::{assert_send, assert_sync};
+
+#[test]
+fn is_send_sync() {
+    assert_send::<Event>();
+    assert_sync::<Event>();
+    assert_send::<EventStream<ThreadSafe>>();
+    assert_sync::<EventStream<ThreadSafe>>();
+}
+
+#[test]
+fn encode_event() {
+    let tests = [
+        (Event::new("hello"), "data: hello\n\n"),
+        (Event::new(""), "data: \n\n"),
+        (Event::new("line1\nline2"), "data: line1\ndata: line2\n\n"),
+        (
+            Event::new("a\r\nb\rc\nd"),
+            "data: a\ndata: b\ndata: c\ndata: d\n\n",
+        ),
+        (Event::new("line\n"), "data: line\ndata: \n\n"),
+        (Event::new("\r\n\r"), "data: \ndata: \ndata: \n\n"),
+        (
+            Event::new("hello").with_event("greeting"),
+            "event: greeting\ndata: hello\n\n",
+        ),
+        (
+            Event::new("hello")
+                .with_event("greeting")
+                .with_id("1")
+                .with_retry(Duration::from_secs(3)),
+            "event: greeting\nid: 1\nretry: 3000\ndata: hello\n\n",
+        ),
+        (
+            Event::new("hello").with_event("a\nb").with_id("1\r\n2"),
+            "event: ab\nid: 12\ndata: hello\n\n",
+        ),
+    ];
+    for (event, expected) in tests {
+        let mut buf = Vec::new();
+        event.encode(&mut buf);
+        assert_eq!(buf, expected.as_bytes(), "event: {event:?}");
+    }
+}
+
+#[test]
+fn event_stream_response() {
+    block_on_local_actor(actor_fn(event_stream_response_actor), ());
+}
+
+async fn event_stream_response_actor(ctx: actor::Context<!, ThreadLocal>) {
+    let (stream, _events) = EventStream::new(ctx.runtime_ref().clone());
+    let response = stream.into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    let headers = response.headers();
+    assert_eq!(
+        headers.get_bytes(&HeaderName::CONTENT_TYPE),
+        Some(&b"text/event-stream"[..])
+    );
+    assert_eq!(
+        headers.get_bytes(&HeaderName::CACHE_CONTROL),
+        Some(&b"no-cache"[..])
+    );
+    assert_eq!(response.body().length(), BodyLength::Chunked);
+}
@@ -0,0 +1,145 @@
+use std::borrow::Cow;
+
+use heph_http::uri::UriError;
+use heph_http::{Headers, Method, Request, Uri, Version};
+
+#[test]
+fn parse() {
+    let tests = [
+        ("/", "/", None),
+        ("/path", "/path", None),
+        ("/path?", "/path", Some("")),
+        ("/path?a=b", "/path", Some("a=b")),
+        ("/path?a=b#fragment", "/path", Some("a=b")),
+        ("/path#fragment?a=b", "/path", None),
+        ("http://example.com", "/", None),
+        ("http://example.com/path?a=b", "/path", Some("a=b")),
+        ("*", "*", None),
+    ];
+    for (input, path, query) in tests {
+        let uri = Uri::parse(input);
+        assert_eq!(uri.path(), path, "input: {input}");
+        assert_eq!(uri.query(), query, "input: {input}");
+    }
+}
+
+#[test]
+fn display() {
+    let tests = [
+        ("/path", "/path"),
+        ("/path?a=b", "/path?a=b"),
+        ("http://example.com/path?a=b#f", "/path?a=b"),
+    ];
+    for (input, expected) in tests {
+        assert_eq!(Uri::parse(input).to_string(), expected);
+    }
+}
+
+#[test]
+fn decoded_path() {
+    let tests = [
+        ("/path", Ok(Cow::Borrowed("/path"))),
+        ("/my%20file", Ok(Cow::Owned("/my file".to_owned()))),
+        ("/a+b", Ok(Cow::Borrowed("/a+b"))),
+        ("/%C3%A9", Ok(Cow::Owned("/\u{e9}".to_owned()))),
+        ("/%zz", Err(UriError::InvalidPercentEncoding)),
+        ("/%2", Err(UriError::InvalidPercentEncoding)),
+        ("/%FF", Err(UriError::InvalidUtf8)),
+    ];
+    for (input, expected) in tests {
+        assert_eq!(Uri::parse(input).decoded_path(), expected, "input: {input}");
+    }
+}
+
+#[test]
+fn normalised_path() {
+    let tests = [
+        ("/", Ok("/")),
+        ("", Ok("/")),
+        ("/a/b/c", Ok("/a/b/c")),
+        ("/a/b/c/", Ok("/a/b/c/")),
+        ("//a///b", Ok("/a/b")),
+        ("/a/./b", Ok("/a/b")),
+        ("/a/../b", Ok("/b")),
+        ("/a/b/..", Ok("/a/")),
+        ("/a/b/.", Ok("/a/b/")),
+        ("/../../etc/passwd", Ok("/etc/passwd")),
+        ("/my%20file.txt", Ok("/my file.txt")),
+        ("/%2e%2e/secret", Ok("/../secret")),
+        ("/a%2Fb", Err(UriError::InvalidPath)),
+        ("/%zz", Err(UriError::InvalidPercentEncoding)),
+        ("/%FF", Err(UriError::InvalidUtf8)),
+    ];
+    for (input, expected) in tests {
+        let got = Uri::parse(input).normalised_path();
+        assert_eq!(got, expected.map(str::to_owned), "input: {input}");
+    }
+}
+
+#[test]
+fn query_params() {
+    let uri = Uri::parse("/?a=1&b=hello+world&&c&d=%41%zz&a=2&e=");
+    let got: Vec<(Cow<'_, str>, Cow<'_, str>)> = uri.query_params().collect();
+    let expected = [
+        ("a", "1"),
+        ("b", "hello world"),
+        ("c", ""),
+        ("d", "A%zz"),
+        ("a", "2"),
+        ("e", ""),
+    ];
+    assert_eq!(got.len(), expected.len());
+    for ((name, value), (expected_name, expected_value)) in got.iter().zip(expected) {
+        assert_eq!(name, expected_name);
+        assert_eq!(value, expected_value);
+    }
+
+    assert_eq!(Uri::parse("/").query_params().count(), 0);
+}
+
+#[test]
+fn query_param() {
+    let uri = Uri::parse("/?page=2&name=Thomas&a=1&a=2&invalid=abc");
+    assert_eq!(uri.query_param::<usize>("page"), Ok(Some(2)));
+    assert_eq!(
+        uri.query_param::<String>("name"),
+        Ok(Some("Thomas".to_owned()))
+    );
+    // Returns the first value.
+    assert_eq!(uri.query_param::<u8>("a"), Ok(Some(1)));
+    assert_eq!(uri.query_param::<u8>("missing"), Ok(None));
+    assert!(uri.query_param::<u8>("invalid").is_err());
+}
+
+#[test]
+#[cfg(feature = "query")]
+fn from_query() {
+    let uri = Uri::parse("/?a=1&b=hello+world");
+    let got: Vec<(String, String)> = uri.from_query().unwrap();
+    assert_eq!(
+        got,
+        [
+            ("a".to_owned(), "1".to_owned()),
+            ("b".to_owned(), "hello world".to_owned())
+        ]
+    );
+
+    let got: Vec<(String, String)> = Uri::parse("/").from_query().unwrap();
+    assert!(got.is_empty());
+
+    assert!(uri.from_query::<Vec<(String, u8)>>().is_err());
+}
+
+#[test]
+fn request_uri() {
+    let request = Request::new(
+        Method::Get,
+        "/path?a=1".to_owned(),
+        Version::Http11,
+        Headers::EMPTY,
+        (),
+    );
+    let uri = request.uri();
+    assert_eq!(uri.path(), "/path");
+    assert_eq!(uri.query_param::<u8>("a"), Ok(Some(1)));
+}
@@ -0,0 +1,322 @@
+//! Compatibility layer with [`std::sync::mpsc`].
+//!
+//! This module provides [`sync_channel`], which mirrors the API of
+//! [`std::sync::mpsc::sync_channel`] on top of the channel of this crate. It
+//! uses the same error types as the standard library, making it (mostly) a
+//! drop-in replacement. This makes it possible to migrate threaded code to
+//! actors incrementally: the threaded side can keep using the blocking API,
+//! while the other side is converted into an actor using [`into_inner`].
+//!
+//! [`into_inner`]: SyncSender::into_inner
+//!
+//! # Notes
+//!
+//! Unlike the standard library the capacity of the channel is limited to
+//! [`MAX_CAP`] and a bound of zero (a rendezvous channel) is not supported,
+//! see [`sync_channel`].
+//!
+//! [`MAX_CAP`]: crate::MAX_CAP
+//!
+//! # Examples
+//!
+//! ```
+//! use std::thread;
+//!
+//! use heph_inbox::compat::sync_channel;
+//!
+//! let (sender, receiver) = sync_channel(2);
+//!
+//! let handle = thread::spawn(move || {
+//!     for n in 0..10 {
+//!         // Blocks if the channel is full.
+//!         sender.send(n).unwrap();
+//!     }
+//! });
+//!
+//! // Receive values until all senders are dropped.
+//! let total: usize = receiver.iter().sum();
+//! assert_eq!(total, 45);
+//! handle.join().unwrap();
+//! ```
+
+use std::cell::RefCell;
+use std::fmt;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::mpsc::{RecvError, RecvTimeoutError, SendError, TryRecvError, TrySendError};
+use std::sync::Arc;
+use std::task::{self, Poll, Wake};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+use crate::{MAX_CAP, MIN_CAP};
+
+/// Create a new bounded channel, mirroring [`std::sync::mpsc::sync_channel`].
+///
+/// The capacity of the channel is `bound`, clamped to the range
+/// [`MIN_CAP`]`..=`[`MAX_CAP`]. This means that a `bound` of zero creates a
+/// channel with a single slot, rather than a rendezvous channel.
+///
+/// [`MIN_CAP`]: crate::MIN_CAP
+/// [`MAX_CAP`]: crate::MAX_CAP
+pub fn sync_channel<T>(bound: usize) -> (SyncSender<T>, Receiver<T>) {
+    let (sender, receiver) = crate::new(bound.clamp(MIN_CAP, MAX_CAP));
+    let sender = SyncSender { inner: sender };
+    let receiver = Receiver {
+        inner: RefCell::new(receiver),
+    };
+    (sender, receiver)
+}
+
+/// Sending half of the channel, see [`sync_channel`].
+///
+/// Mirrors [`std::sync::mpsc::SyncSender`].
+pub struct SyncSender<T> {
+    inner: crate::Sender<T>,
+}
+
+impl<T> SyncSender<T> {
+    /// Send a value, blocking until a slot is available in the channel.
+    ///
+    /// Returns an error, containing `value`, if the receiver is disconnected.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        match self.inner.try_send(value) {
+            Ok(()) => Ok(()),
+            Err(crate::SendError::Full(value)) => block_on(self.inner.send(value), None)
+                .expect("blocked without deadline")
+                .map_err(SendError),
+            Err(crate::SendError::Disconnected(value)) => Err(SendError(value)),
+        }
+    }
+
+    /// Attempt to send a value, without blocking.
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        self.inner.try_send(value).map_err(|err| match err {
+            crate::SendError::Full(value) => TrySendError::Full(value),
+            crate::SendError::Disconnected(value) => TrySendError::Disconnected(value),
+        })
+    }
+
+    /// Returns the [`Sender`] of this crate, e.g. to use it in an actor.
+    ///
+    /// [`Sender`]: crate::Sender
+    pub fn into_inner(self) -> crate::Sender<T> {
+        self.inner
+    }
+}
+
+impl<T> From<crate::Sender<T>> for SyncSender<T> {
+    fn from(sender: crate::Sender<T>) -> SyncSender<T> {
+        SyncSender { inner: sender }
+    }
+}
+
+impl<T> Clone for SyncSender<T> {
+    fn clone(&self) -> SyncSender<T> {
+        SyncSender {
+            inner: self.inner.clone(),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.inner.clone_from(&source.inner);
+    }
+}
+
+impl<T> fmt::Debug for SyncSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyncSender").finish_non_exhaustive()
+    }
+}
+
+/// Receiving half of the channel, see [`sync_channel`].
+///
+/// Mirrors [`std::sync::mpsc::Receiver`].
+pub struct Receiver<T> {
+    // NOTE: `RefCell` is used to mirror the `&self` API of the standard
+    // library, which also means the receiver is not `Sync`.
+    inner: RefCell<crate::Receiver<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// Receive a value, blocking until one is available.
+    ///
+    /// Returns an error if all senders are disconnected and the channel is
+    /// empty.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        match self.try_recv() {
+            Ok(value) => Ok(value),
+            Err(TryRecvError::Disconnected) => Err(RecvError),
+            Err(TryRecvError::Empty) => {
+                let mut receiver = self.inner.borrow_mut();
+                block_on(receiver.recv(), None)
+                    .expect("blocked without deadline")
+                    .ok_or(RecvError)
+            }
+        }
+    }
+
+    /// Attempt to receive a value, without blocking.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.inner.borrow_mut().try_recv().map_err(|err| match err {
+            crate::RecvError::Empty => TryRecvError::Empty,
+            crate::RecvError::Disconnected => TryRecvError::Disconnected,
+        })
+    }
+
+    /// Receive a value, blocking for at most `timeout`.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.recv_deadline(deadline),
+            // Practically no deadline.
+            None => self.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        }
+    }
+
+    /// Receive a value, blocking until the `deadline` has passed.
+    pub fn recv_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+        match self.try_recv() {
+            Ok(value) => Ok(value),
+            Err(TryRecvError::Disconnected) => Err(RecvTimeoutError::Disconnected),
+            Err(TryRecvError::Empty) => {
+                let mut receiver = self.inner.borrow_mut();
+                match block_on(receiver.recv(), Some(deadline)) {
+                    Some(Some(value)) => Ok(value),
+                    Some(None) => Err(RecvTimeoutError::Disconnected),
+                    None => Err(RecvTimeoutError::Timeout),
+                }
+            }
+        }
+    }
+
+    /// Returns an iterator that blocks waiting for values, until all senders
+    /// are disconnected.
+    pub const fn iter(&self) -> Iter<'_, T> {
+        Iter { receiver: self }
+    }
+
+    /// Returns an iterator that returns all values currently in the channel,
+    /// without blocking.
+    pub const fn try_iter(&self) -> TryIter<'_, T> {
+        TryIter { receiver: self }
+    }
+
+    /// Returns the [`Receiver`] of this crate, e.g. to use it in an actor.
+    ///
+    /// [`Receiver`]: crate::Receiver
+    pub fn into_inner(self) -> crate::Receiver<T> {
+        self.inner.into_inner()
+    }
+}
+
+impl<T> From<crate::Receiver<T>> for Receiver<T> {
+    fn from(receiver: crate::Receiver<T>) -> Receiver<T> {
+        Receiver {
+            inner: RefCell::new(receiver),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Receiver<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for Receiver<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { receiver: self }
+    }
+}
+
+/// Iterator behind [`Receiver::iter`].
+#[derive(Debug)]
+pub struct Iter<'a, T> {
+    receiver: &'a Receiver<T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv().ok()
+    }
+}
+
+/// Iterator behind [`Receiver::try_iter`].
+#[derive(Debug)]
+pub struct TryIter<'a, T> {
+    receiver: &'a Receiver<T>,
+}
+
+impl<'a, T> Iterator for TryIter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+/// Iterator behind [`Receiver`]'s [`IntoIterator`] implementation.
+#[derive(Debug)]
+pub struct IntoIter<T> {
+    receiver: Receiver<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv().ok()
+    }
+}
+
+/// Block the current thread on `future`, until the optional `deadline`.
+///
+/// Returns `None` if the deadline passed before the future completed.
+fn block_on<Fut: Future>(future: Fut, deadline: Option<Instant>) -> Option<Fut::Output> {
+    let mut future = pin!(future);
+    let waker = task::Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut ctx = task::Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut ctx) {
+            return Some(output);
+        }
+
+        match deadline {
+            Some(deadline) => {
+                let now = Instant::now();
+                if now >= deadline {
+                    return None;
+                }
+                thread::park_timeout(deadline - now);
+            }
+            None => thread::park(),
+        }
+    }
+}
+
+/// [`Wake`] implementation that unparks a thread.
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
@@ -0,0 +1,438 @@
+//! Duplex channel.
+//!
+//! The channel is used to send requests and receive a response for each of
+//! them, i.e. a [Remote Procedure Call (RPC)] protocol between two (or more)
+//! parties. It combines a bounded channel, used to send the requests, with a
+//! [one-shot channel] per request, used to send the response back to the
+//! requester. This way every response is correlated to its request without
+//! having to wire up two separate channels.
+//!
+//! The channel has two halves:
+//!  * [`Requests`]: used to send requests and wait for the response, this can
+//!    be cloned to allow multiple requesters.
+//!  * [`Responses`]: used to receive requests, each with a [`Responder`] used
+//!    to send the response.
+//!
+//! [Remote Procedure Call (RPC)]: https://en.wikipedia.org/wiki/Remote_procedure_call
+//! [one-shot channel]: crate::oneshot
+//!
+//! # Examples
+//!
+//! Simple creation of a channel and making a request.
+//!
+//! ```
+//! use std::thread;
+//!
+//! use heph_inbox::duplex::new_duplex;
+//! use heph_inbox::RecvError;
+//!
+//! // Create a new duplex channel with capacity for 8 requests.
+//! let (requests, mut responses) = new_duplex::<usize, String>(8);
+//!
+//! let server_handle = thread::spawn(move || {
+//!     // NOTE: this is just an example don't actually use a loop like this, it
+//!     // will waste CPU cycles when the channel is empty!
+//!     loop {
+//!         match responses.try_recv() {
+//!             Ok((request, responder)) => {
+//!                 let response = format!("Request #{}: {request}", responder.id());
+//!                 // Requester could have stopped waiting, we ignore that.
+//!                 _ = responder.respond(response);
+//!             }
+//!             Err(RecvError::Empty) => continue,
+//!             Err(RecvError::Disconnected) => break,
+//!         }
+//!     }
+//! });
+//!
+//! let mut response = match requests.try_request(123) {
+//!     Ok(response) => response,
+//!     Err(err) => panic!("failed to send request: {err}"),
+//! };
+//! drop(requests);
+//! let response = loop {
+//!     if let Ok(response) = response.try_recv() {
+//!         break response;
+//!     }
+//! #   #[cfg(not(miri))] // `sleep` not supported.
+//! #   thread::sleep(std::time::Duration::from_millis(1)); // Don't waste cycles.
+//! };
+//! assert_eq!(response, "Request #0: 123");
+//!
+//! server_handle.join().unwrap();
+//! ```
+
+use alloc::sync::Arc;
+use core::error::Error;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::task::{self, Poll};
+
+use crate::oneshot::{self, new_oneshot};
+use crate::{new, RecvError, SendError, SendValue};
+
+/// Create a new duplex channel with room for `capacity` requests.
+///
+/// # Panics
+///
+/// This will panic if `capacity` is not within the range
+/// [`MIN_CAP`]..=[`MAX_CAP`], see [`new`].
+///
+/// [`MIN_CAP`]: crate::MIN_CAP
+/// [`MAX_CAP`]: crate::MAX_CAP
+pub fn new_duplex<Req, Res>(capacity: usize) -> (Requests<Req, Res>, Responses<Req, Res>) {
+    let (sender, receiver) = new(capacity);
+    let next_id = Arc::new(AtomicU64::new(0));
+    let requests = Requests {
+        sender,
+        next_id: next_id.clone(),
+    };
+    (requests, Responses { receiver, next_id })
+}
+
+/// Request as send across the channel.
+struct Request<Req, Res> {
+    id: RequestId,
+    request: Req,
+    response: oneshot::Sender<Res>,
+}
+
+/// Identifier of a request, unique per duplex channel.
+///
+/// Can be used to correlate logs of the requester and responder, see
+/// [`PendingResponse::id`] and [`Responder::id`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct RequestId(u64);
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Requesting half of the duplex channel.
+///
+/// See [`new_duplex`] to create a channel.
+pub struct Requests<Req, Res> {
+    sender: crate::Sender<Request<Req, Res>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl<Req, Res> Requests<Req, Res> {
+    /// Attempts to send the `request`.
+    ///
+    /// Returns a [`PendingResponse`] that can be used to receive the response
+    /// to the request.
+    pub fn try_request(&self, request: Req) -> Result<PendingResponse<Res>, SendError<Req>> {
+        let (request, response) = self.new_request(request);
+        match self.sender.try_send(request) {
+            Ok(()) => Ok(response),
+            Err(SendError::Full(request)) => Err(SendError::Full(request.request)),
+            Err(SendError::Disconnected(request)) => Err(SendError::Disconnected(request.request)),
+        }
+    }
+
+    /// Send the `request` and wait for the response.
+    ///
+    /// If the channel is full this will wait until a slot is available, see
+    /// [`Sender::send`].
+    ///
+    /// [`Sender::send`]: crate::Sender::send
+    pub fn request(&self, request: Req) -> SendRequest<'_, Req, Res> {
+        let (request, response) = self.new_request(request);
+        SendRequest {
+            state: RequestState::Sending {
+                send: self.sender.send(request),
+                response: Some(response),
+            },
+        }
+    }
+
+    /// Create a new request to send.
+    fn new_request(&self, request: Req) -> (Request<Req, Res>, PendingResponse<Res>) {
+        let id = RequestId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let (sender, receiver) = new_oneshot();
+        let request = Request {
+            id,
+            request,
+            response: sender,
+        };
+        (request, PendingResponse { id, receiver })
+    }
+
+    /// Returns `true` if the [`Responses`] half is connected.
+    pub fn is_connected(&self) -> bool {
+        self.sender.is_connected()
+    }
+
+    /// Returns `true` if `self` sends requests to `responses`.
+    pub fn sends_to(&self, responses: &Responses<Req, Res>) -> bool {
+        self.sender.sends_to(&responses.receiver)
+    }
+}
+
+impl<Req, Res> Clone for Requests<Req, Res> {
+    fn clone(&self) -> Requests<Req, Res> {
+        Requests {
+            sender: self.sender.clone(),
+            next_id: self.next_id.clone(),
+        }
+    }
+}
+
+impl<Req, Res> fmt::Debug for Requests<Req, Res> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Requests")
+            .field("sender", &self.sender)
+            .finish()
+    }
+}
+
+/// Responding half of the duplex channel.
+///
+/// See [`new_duplex`] to create a channel.
+pub struct Responses<Req, Res> {
+    receiver: crate::Receiver<Request<Req, Res>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl<Req, Res> Responses<Req, Res> {
+    /// Attempts to receive a request.
+    ///
+    /// Returns the request and the [`Responder`] to send the response with.
+    pub fn try_recv(&mut self) -> Result<(Req, Responder<Res>), RecvError> {
+        self.receiver.try_recv().map(Request::into_parts)
+    }
+
+    /// Returns a future that receives a request.
+    ///
+    /// If all [`Requests`] are dropped this will return `None`.
+    pub fn recv(&mut self) -> RecvRequest<'_, Req, Res> {
+        RecvRequest {
+            receiver: &mut self.receiver,
+        }
+    }
+
+    /// Create a new [`Requests`] half that sends requests to this channel.
+    pub fn new_requests(&self) -> Requests<Req, Res> {
+        Requests {
+            sender: self.receiver.new_sender(),
+            next_id: self.next_id.clone(),
+        }
+    }
+
+    /// Returns `true` if any [`Requests`] are connected.
+    pub fn is_connected(&self) -> bool {
+        self.receiver.is_connected()
+    }
+}
+
+impl<Req, Res> fmt::Debug for Responses<Req, Res> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Responses")
+            .field("capacity", &self.receiver.capacity())
+            .field("is_connected", &self.receiver.is_connected())
+            .finish()
+    }
+}
+
+impl<Req, Res> Request<Req, Res> {
+    fn into_parts(self) -> (Req, Responder<Res>) {
+        let responder = Responder {
+            id: self.id,
+            sender: self.response,
+        };
+        (self.request, responder)
+    }
+}
+
+/// Used to send the response to a single request.
+///
+/// If the `Responder` is dropped without sending a response the requester
+/// will receive no response.
+pub struct Responder<Res> {
+    id: RequestId,
+    sender: oneshot::Sender<Res>,
+}
+
+impl<Res> Responder<Res> {
+    /// Returns the id of the request.
+    pub const fn id(&self) -> RequestId {
+        self.id
+    }
+
+    /// Send the `response` to the requester.
+    ///
+    /// Returns the response if the requester is no longer waiting for it.
+    pub fn respond(self, response: Res) -> Result<(), Res> {
+        self.sender.try_send(response)
+    }
+
+    /// Returns `true` if the requester is still waiting for the response.
+    pub fn is_connected(&self) -> bool {
+        self.sender.is_connected()
+    }
+}
+
+impl<Res> fmt::Debug for Responder<Res> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Responder")
+            .field("id", &self.id)
+            .field("sender", &self.sender)
+            .finish()
+    }
+}
+
+/// Response to a request send using [`Requests::try_request`].
+///
+/// This implements [`Future`] which returns the response, or `None` if the
+/// [`Responder`] was dropped without sending a response.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct PendingResponse<Res> {
+    id: RequestId,
+    receiver: oneshot::Receiver<Res>,
+}
+
+impl<Res> PendingResponse<Res> {
+    /// Returns the id of the request.
+    pub const fn id(&self) -> RequestId {
+        self.id
+    }
+
+    /// Attempts to receive the response.
+    pub fn try_recv(&mut self) -> Result<Res, oneshot::RecvError> {
+        self.receiver.try_recv()
+    }
+}
+
+impl<Res> Future for PendingResponse<Res> {
+    type Output = Option<Res>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context) -> Poll<Self::Output> {
+        Pin::new(&mut self.receiver.recv()).poll(ctx)
+    }
+}
+
+impl<Res> Unpin for PendingResponse<Res> {}
+
+impl<Res> fmt::Debug for PendingResponse<Res> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PendingResponse")
+            .field("id", &self.id)
+            .field("receiver", &self.receiver)
+            .finish()
+    }
+}
+
+/// [`Future`] implementation behind [`Requests::request`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendRequest<'r, Req, Res> {
+    state: RequestState<'r, Req, Res>,
+}
+
+enum RequestState<'r, Req, Res> {
+    /// Sending the request.
+    Sending {
+        send: SendValue<'r, Request<Req, Res>>,
+        /// Always `Some`, used to move it into `Waiting`.
+        response: Option<PendingResponse<Res>>,
+    },
+    /// Request send, waiting for the response.
+    Waiting(PendingResponse<Res>),
+}
+
+impl<'r, Req, Res> Future for SendRequest<'r, Req, Res> {
+    type Output = Result<Res, RequestError<Req>>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context) -> Poll<Self::Output> {
+        loop {
+            match &mut self.state {
+                RequestState::Sending { send, response } => match Pin::new(send).poll(ctx) {
+                    Poll::Ready(Ok(())) => {
+                        let response = response
+                            .take()
+                            .expect("SendRequest polled after completion");
+                        self.state = RequestState::Waiting(response);
+                    }
+                    Poll::Ready(Err(request)) => {
+                        return Poll::Ready(Err(RequestError::Disconnected(request.request)))
+                    }
+                    Poll::Pending => return Poll::Pending,
+                },
+                RequestState::Waiting(response) => {
+                    return match Pin::new(response).poll(ctx) {
+                        Poll::Ready(Some(response)) => Poll::Ready(Ok(response)),
+                        Poll::Ready(None) => Poll::Ready(Err(RequestError::NoResponse)),
+                        Poll::Pending => Poll::Pending,
+                    }
+                }
+            }
+        }
+    }
+}
+
+// The request is never pinned, it's moved into the channel, so `SendRequest`
+// can be `Unpin` even if `Req` is not.
+impl<'r, Req, Res> Unpin for SendRequest<'r, Req, Res> {}
+
+impl<'r, Req, Res> fmt::Debug for SendRequest<'r, Req, Res> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("SendRequest");
+        match &self.state {
+            RequestState::Sending { response, .. } => {
+                let id = response.as_ref().map(PendingResponse::id);
+                f.field("id", &id).field("state", &"sending")
+            }
+            RequestState::Waiting(response) => {
+                f.field("id", &response.id()).field("state", &"waiting")
+            }
+        }
+        .finish()
+    }
+}
+
+/// Error returned by [`Requests::request`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RequestError<Req> {
+    /// [`Responses`] half is disconnected, returning the request.
+    Disconnected(Req),
+    /// The [`Responder`] was dropped without sending a response.
+    NoResponse,
+}
+
+impl<Req> fmt::Display for RequestError<Req> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::Disconnected(..) => f.pad("responder is disconnected"),
+            RequestError::NoResponse => f.pad("no response"),
+        }
+    }
+}
+
+impl<Req: fmt::Debug> Error for RequestError<Req> {}
+
+/// [`Future`] implementation behind [`Responses::recv`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvRequest<'r, Req, Res> {
+    receiver: &'r mut crate::Receiver<Request<Req, Res>>,
+}
+
+impl<'r, Req, Res> Future for RecvRequest<'r, Req, Res> {
+    type Output = Option<(Req, Responder<Res>)>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context) -> Poll<Self::Output> {
+        Pin::new(&mut self.receiver.recv())
+            .poll(ctx)
+            .map(|request| request.map(Request::into_parts))
+    }
+}
+
+impl<'r, Req, Res> Unpin for RecvRequest<'r, Req, Res> {}
+
+impl<'r, Req, Res> fmt::Debug for RecvRequest<'r, Req, Res> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecvRequest").finish()
+    }
+}
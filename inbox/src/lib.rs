@@ -16,6 +16,31 @@
 //! on a best effort basis. In return it means that a slow `Sender` does not
 //! block the receiving of other messages.
 //!
+//! # Features
+//!
+//! The `std` feature, enabled by default, enables the use of the standard
+//! library. Without it the crate is `no_std` and only requires `alloc`, using a
+//! spin lock to store the wakers of the senders. The `watch` channel and the
+//! `std::sync::mpsc` compatibility layer in the `compat` module are only
+//! available with the `std` feature. Note that without the standard library a
+//! panicking [`Sender`] can't be detected, it's reported as finished instead.
+//!
+//! The `debug-ordering` feature enables a memory ordering audit mode. It keeps
+//! a shadow state machine for each slot in the channel and asserts that every
+//! transition observed via the atomic operations follows the legal path. This
+//! is slow and only meant to catch regressions during development.
+//!
+//! The `cache-padded` feature pads the status, reference count and receiver's
+//! waker of the channel, as well as the slots, to their own cache line. This
+//! prevents false sharing between the senders and the receiver when they run
+//! on different CPU cores, at the cost of a larger channel (e.g. 512 instead of
+//! 168 bytes for a channel with a single slot on x86_64 Linux).
+//!
+//! The `test` feature enables [`Receiver::debug_slots`], which returns a
+//! snapshot of the internal state of the channel. This allows tests (and fuzz
+//! harnesses) to assert invariants of the channel without decoding the raw
+//! status of the channel.
+//!
 //! # Examples
 //!
 //! Simple creation of a channel and sending a message over it.
@@ -53,6 +78,7 @@
 //! ```
 
 #![feature(cfg_sanitize)]
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(
     missing_debug_implementations,
     missing_docs,
@@ -64,19 +90,23 @@
 // Disallow warnings in examples, we want to set a good example after all.
 #![doc(test(attr(deny(warnings))))]
 
-use std::alloc::{alloc, handle_alloc_error, Layout};
-use std::cell::UnsafeCell;
-use std::error::Error;
-use std::fmt;
-use std::future::Future;
-use std::mem::{drop as unlock, replace, take, MaybeUninit};
-use std::ops::Deref;
-use std::panic::{RefUnwindSafe, UnwindSafe};
-use std::pin::Pin;
-use std::ptr::{self, NonNull};
-use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
-use std::sync::Mutex;
-use std::task::{self, Poll};
+extern crate alloc;
+
+use alloc::alloc::{alloc, handle_alloc_error, Layout};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::error::Error;
+use core::fmt;
+use core::future::Future;
+use core::mem::{drop as unlock, forget, take, ManuallyDrop, MaybeUninit};
+use core::ops::Deref;
+use core::panic::{RefUnwindSafe, UnwindSafe};
+use core::pin::Pin;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicU32, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use core::task::{self, Poll};
 
 #[cfg(test)]
 mod tests;
@@ -88,7 +118,7 @@ mod tests;
 macro_rules! fence {
     ($val: expr, $ordering: expr) => {
         #[cfg(not(sanitize = "thread"))]
-        std::sync::atomic::fence($ordering);
+        core::sync::atomic::fence($ordering);
         #[cfg(sanitize = "thread")]
         {
             _ = $val.load($ordering);
@@ -96,11 +126,22 @@ macro_rules! fence {
     };
 }
 
+#[cfg(feature = "std")]
+pub mod compat;
+pub mod duplex;
 pub mod oneshot;
+#[cfg(feature = "std")]
+pub mod watch;
+
+mod lock;
+use lock::Mutex;
 
 mod waker;
 use waker::WakerRegistration;
 
+#[cfg(feature = "debug-ordering")]
+mod ordering;
+
 /// The capacity of a small channel.
 const SMALL_CAP: usize = 8;
 /// Maximum capacity of a channel.
@@ -128,6 +169,97 @@ pub fn new<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
     (sender, receiver)
 }
 
+/// Create a new bounded channel, using `on_drop` to determine what happens to
+/// the messages still in the channel when the [`Receiver`] is dropped.
+///
+/// See [`new`] and [`OnDrop`].
+///
+/// # Examples
+///
+/// ```
+/// use heph_inbox::OnDrop;
+///
+/// // Channel that receives all messages not processed by the first channel.
+/// let (fallback, mut fallback_receiver) = heph_inbox::new_small();
+/// let (sender, receiver) = heph_inbox::new_with_on_drop(8, OnDrop::Forward(fallback));
+///
+/// sender.try_send("Hello world!").unwrap();
+/// drop(receiver);
+/// assert_eq!(fallback_receiver.try_recv(), Ok("Hello world!"));
+/// ```
+pub fn new_with_on_drop<T>(capacity: usize, on_drop: OnDrop<T>) -> (Sender<T>, Receiver<T>)
+where
+    T: Send + 'static,
+{
+    let (sender, receiver) = new(capacity);
+    sender.channel().set_on_drop(on_drop);
+    (sender, receiver)
+}
+
+/// What to do with the messages still in the channel when the [`Receiver`] is
+/// dropped, see [`new_with_on_drop`].
+///
+/// # Notes
+///
+/// If the channel has a [`Manager`] the messages are kept for the next
+/// `Receiver`, this only applies once both the `Receiver` and the `Manager`
+/// are dropped, see [`Manager::new_channel_with_on_drop`].
+pub enum OnDrop<T> {
+    /// Drop the messages, this is the default for channels created using
+    /// [`new`].
+    Drop,
+    /// Call the function with each message.
+    Call(Box<dyn FnMut(T) + Send>),
+    /// Forward the messages to another channel, e.g. to an actor that can
+    /// handle the remaining work.
+    ///
+    /// Messages that can't be send, because the channel is full or
+    /// disconnected, are dropped. Note that the `Sender` is kept alive until
+    /// the `Receiver` is dropped.
+    Forward(Sender<T>),
+}
+
+impl<T> fmt::Debug for OnDrop<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OnDrop::Drop => f.write_str("OnDrop::Drop"),
+            OnDrop::Call(_) => f.write_str("OnDrop::Call"),
+            OnDrop::Forward(sender) => f.debug_tuple("OnDrop::Forward").field(sender).finish(),
+        }
+    }
+}
+
+/// Attempts to send `value` into all channels of `senders`.
+///
+/// This is useful for broadcasting a large message to many channels: only the
+/// [`Arc`] is cloned, i.e. only the reference count is increased, for each
+/// channel that has capacity. See [`Sender::try_send_ref`].
+///
+/// Returns the number of channels the value was sent to. To get the errors
+/// for each channel use [`Sender::try_send_ref`] directly.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// let (sender1, mut receiver1) = heph_inbox::new_small();
+/// let (sender2, mut receiver2) = heph_inbox::new_small();
+///
+/// let msg = Arc::new("Hello world!".to_owned());
+/// let sent = heph_inbox::try_send_slice(&[sender1, sender2], &msg);
+/// assert_eq!(sent, 2);
+///
+/// assert!(Arc::ptr_eq(&receiver1.try_recv().unwrap(), &msg));
+/// assert!(Arc::ptr_eq(&receiver2.try_recv().unwrap(), &msg));
+/// ```
+pub fn try_send_slice<T>(senders: &[Sender<Arc<T>>], value: &Arc<T>) -> usize {
+    senders
+        .iter()
+        .filter(|sender| sender.try_send_ref(value).is_ok())
+        .count()
+}
+
 /// Bit mask to mark the receiver as alive.
 const RECEIVER_ALIVE: usize = 1 << (usize::BITS - 1);
 /// Bit mask to mark the receiver still has access to the channel. See the
@@ -141,6 +273,8 @@ const MANAGER_ALIVE: usize = 1 << (usize::BITS - 4);
 /// Bit mask to mark the manager has access to the channel. See the `Drop` impl
 /// for [`Manager`].
 const MANAGER_ACCESS: usize = 1 << (usize::BITS - 5);
+/// Bit mask to mark the channel as closed, see [`Sender::close`].
+const CLOSED: usize = 1 << (usize::BITS - 6);
 
 /// Return `true` if the receiver or manager is alive in `ref_count`.
 const fn has_receiver(ref_count: usize) -> bool {
@@ -157,9 +291,40 @@ const fn has_receiver_or_manager(ref_count: usize) -> bool {
     ref_count & (RECEIVER_ALIVE | MANAGER_ALIVE) != 0
 }
 
+/// Returns `true` if the channel is closed in `ref_count`.
+const fn is_closed(ref_count: usize) -> bool {
+    ref_count & CLOSED != 0
+}
+
+/// Returns `true` if a sender is alive and the channel is not closed in
+/// `ref_count`, i.e. if values can still be send into the channel.
+const fn has_sender(ref_count: usize) -> bool {
+    sender_count(ref_count) > 0 && !is_closed(ref_count)
+}
+
+/// Maximum number of [`Sender`]s that can be alive concurrently.
+///
+/// This is half of the space available for the sender count, leaving room for
+/// concurrent increments before checking the limit without flowing into the
+/// status bits above (similar to `Arc::clone`).
+const MAX_SENDERS: usize = CLOSED >> 1;
+
 /// Returns the number of senders connected in `ref_count`.
 const fn sender_count(ref_count: usize) -> usize {
-    ref_count & !(RECEIVER_ALIVE | RECEIVER_ACCESS | SENDER_ACCESS | MANAGER_ALIVE | MANAGER_ACCESS)
+    ref_count
+        & !(RECEIVER_ALIVE
+            | RECEIVER_ACCESS
+            | SENDER_ACCESS
+            | MANAGER_ALIVE
+            | MANAGER_ACCESS
+            | CLOSED)
+}
+
+/// Panic because [`MAX_SENDERS`] is reached.
+#[cold]
+#[track_caller]
+fn too_many_senders() -> ! {
+    panic!("too many `Sender`s alive for the channel, maximum is {MAX_SENDERS}")
 }
 
 // Bits to mark the status of a slot.
@@ -184,6 +349,20 @@ const fn is_available(status: u64, slot: usize) -> bool {
     has_status(status, slot, EMPTY)
 }
 
+/// Returns the number of slots, of the first `capacity` slots, in `status` that
+/// are not available, i.e. filled or in the process of being filled or read.
+const fn used_slots(status: u64, capacity: usize) -> usize {
+    let mut used = 0;
+    let mut slot = 0;
+    while slot < capacity {
+        if !is_available(status, slot) {
+            used += 1;
+        }
+        slot += 1;
+    }
+    used
+}
+
 /// Returns `true` if `slot` in `status` is filled.
 const fn is_filled(status: u64, slot: usize) -> bool {
     has_status(status, slot, FILLED)
@@ -218,13 +397,20 @@ const fn dbg_status(slot_status: u64) -> &'static str {
     }
 }
 
+/// Bit mask to mark the receiver is waiting for a value in any slot, rather
+/// than the slot at its position, see [`RecvMatching`]. It's part of the status
+/// so that the sender gets it from the same operation that marks a slot as
+/// filled.
+const RECEIVER_MATCHING: u64 = 1 << (STATUS_BITS * MAX_CAP as u64);
+
 // Bits to mark the position of the receiver.
-const MARK_NEXT_POS: u64 = 1 << (STATUS_BITS * MAX_CAP as u64); // Add to increase position by 1.
+const POS_SHIFT: u64 = STATUS_BITS * MAX_CAP as u64 + 1;
+const MARK_NEXT_POS: u64 = 1 << POS_SHIFT; // Add to increase position by 1.
 
 /// Returns the position of the receiver. Will be in 0..[`MAX_CAP`] range.
 #[allow(clippy::cast_possible_truncation)]
 const fn receiver_pos(status: u64, capacity: usize) -> usize {
-    (status >> (STATUS_BITS * MAX_CAP as u64)) as usize % capacity
+    (status >> POS_SHIFT) as usize % capacity
 }
 
 /// Sending side of the channel.
@@ -242,16 +428,61 @@ pub enum SendError<T> {
     Disconnected(T),
 }
 
+impl<T> SendError<T> {
+    /// Returns the kind of error, without the value.
+    ///
+    /// Unlike `SendError` itself the returned [`SendErrorKind`] doesn't
+    /// require `T` to implement [`fmt::Debug`] to be logged or used as
+    /// [`Error`].
+    pub const fn kind(&self) -> SendErrorKind {
+        match self {
+            SendError::Full(..) => SendErrorKind::Full,
+            SendError::Disconnected(..) => SendErrorKind::Disconnected,
+        }
+    }
+
+    /// Returns the value that failed to be sent.
+    pub fn into_inner(self) -> T {
+        match self {
+            SendError::Full(value) | SendError::Disconnected(value) => value,
+        }
+    }
+}
+
 impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)
+    }
+}
+
+impl<T: fmt::Debug> Error for SendError<T> {}
+
+/// Kind of [`SendError`], without the value that failed to be sent. See
+/// [`SendError::kind`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SendErrorKind {
+    /// Channel is full.
+    Full,
+    /// [`Receiver`] and [`Manager`] are disconnected.
+    Disconnected,
+}
+
+impl<T> From<SendError<T>> for SendErrorKind {
+    fn from(err: SendError<T>) -> SendErrorKind {
+        err.kind()
+    }
+}
+
+impl fmt::Display for SendErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            SendError::Full(..) => f.pad("channel is full"),
-            SendError::Disconnected(..) => f.pad("receiver is disconnected"),
+            SendErrorKind::Full => f.pad("channel is full"),
+            SendErrorKind::Disconnected => f.pad("receiver is disconnected"),
         }
     }
 }
 
-impl<T: fmt::Debug> Error for SendError<T> {}
+impl Error for SendErrorKind {}
 
 impl<T> Sender<T> {
     /// Attempts to send the `value` into the channel.
@@ -259,6 +490,67 @@ impl<T> Sender<T> {
         try_send(self.channel(), value)
     }
 
+    /// Attempts to send the value returned by `create` into the channel.
+    ///
+    /// `create` is only called once a slot is acquired, so the value is never
+    /// created if the channel is full or disconnected, in which case the error
+    /// contains `create`. This is useful if creating the value is expensive.
+    ///
+    /// If `create` panics the acquired slot is released again, so the capacity
+    /// of the channel is not reduced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let (sender, mut receiver) = heph_inbox::new_small();
+    ///
+    /// assert!(sender.try_send_with(|| "Hello world".to_owned()).is_ok());
+    ///
+    /// assert_eq!(receiver.try_recv().unwrap(), "Hello world");
+    /// ```
+    pub fn try_send_with<F>(&self, create: F) -> Result<(), SendError<F>>
+    where
+        F: FnOnce() -> T,
+    {
+        try_send_with(self.channel(), create, |create| create(), false)
+    }
+
+    /// Attempts to send the priority `value` into the channel.
+    ///
+    /// Priority values are received before all other values in the channel,
+    /// i.e. they jump the queue. Receiving the values in the channel that are
+    /// not a priority value is not otherwise affected. Priority values still
+    /// take up a slot in the channel, this fails if the channel is full.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let (sender, mut receiver) = heph_inbox::new_small();
+    ///
+    /// sender.try_send("bulk work").unwrap();
+    /// sender.try_send_priority("health check").unwrap();
+    ///
+    /// assert_eq!(receiver.try_recv().unwrap(), "health check");
+    /// assert_eq!(receiver.try_recv().unwrap(), "bulk work");
+    /// ```
+    pub fn try_send_priority(&self, value: T) -> Result<(), SendError<T>> {
+        try_send_with(self.channel(), value, |value| value, true)
+    }
+
+    /// Returns a future that sends a priority value into the channel, waiting
+    /// if the channel is full.
+    ///
+    /// See [`Sender::try_send_priority`] for priority values and
+    /// [`Sender::send`] for the returned future.
+    pub fn send_priority(&self, value: T) -> SendValue<T> {
+        SendValue {
+            channel: self.channel(),
+            value: Some(value),
+            priority: true,
+            registered_waker: None,
+        }
+    }
+
     /// Returns a future that sends a value into the channel, waiting if the
     /// channel is full.
     ///
@@ -273,6 +565,7 @@ impl<T> Sender<T> {
         SendValue {
             channel: self.channel(),
             value: Some(value),
+            priority: false,
             registered_waker: None,
         }
     }
@@ -288,11 +581,111 @@ impl<T> Sender<T> {
         }
     }
 
+    /// Returns a [`Future`] that completes once the channel is closed, i.e.
+    /// once the [`Receiver`] and [`Manager`] are dropped.
+    ///
+    /// This allows a producer to stop generating values as soon as nobody is
+    /// listening, rather than discovering the disconnection on the next send,
+    /// e.g. by racing it against the work it's doing.
+    ///
+    /// This is the same future as returned by [`Sender::join`]. Any sender
+    /// waiting in [`Sender::send`] is also woken once the channel is closed.
+    pub fn closed(&self) -> Join<T> {
+        self.join()
+    }
+
+    /// Close the channel.
+    ///
+    /// Once closed sending values fails with [`SendError::Disconnected`], for
+    /// all senders. The [`Receiver`] can still receive the values already in
+    /// the channel, after which it returns [`RecvError::Disconnected`] with
+    /// [`DisconnectReason::Closed`]. The channel stays closed, new senders
+    /// created by the [`Manager`] can't send values either.
+    ///
+    /// Returns `true` if this call closed the channel, `false` if it was
+    /// already closed.
+    ///
+    /// # Notes
+    ///
+    /// This doesn't disconnect the `Receiver` or `Manager`, so it doesn't
+    /// complete the [`Future`] returned by [`Sender::closed`].
+    ///
+    /// Values send concurrently with closing the channel may or may not be
+    /// received.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use heph_inbox::{new_small, DisconnectReason, RecvError, SendError};
+    ///
+    /// let (sender, mut receiver) = new_small::<usize>();
+    /// sender.try_send(1).unwrap();
+    /// assert!(sender.close());
+    ///
+    /// // No more values can be send.
+    /// assert_eq!(sender.try_send(2), Err(SendError::Disconnected(2)));
+    /// // But the values already send can still be received.
+    /// assert_eq!(receiver.try_recv(), Ok(1));
+    /// assert_eq!(receiver.try_recv(), Err(RecvError::Disconnected));
+    /// assert_eq!(receiver.disconnect_reason(), Some(DisconnectReason::Closed));
+    /// ```
+    pub fn close(&self) -> bool {
+        let channel = self.channel();
+        // SAFETY: `Release` is required here to ensure the values send before
+        // closing the channel are visible to the receiver once it sees the
+        // channel is closed, see `try_recv`.
+        let old_ref_count = channel.ref_count.fetch_or(CLOSED, Ordering::Release);
+        if is_closed(old_ref_count) {
+            return false;
+        }
+        // Wake the receiver so it can see the channel is closed, and all
+        // senders waiting for a slot so they can return an error.
+        channel.wake_receiver();
+        channel.wake_all_senders();
+        true
+    }
+
+    /// Returns a [`Future`] that waits until less than `watermark` slots in the
+    /// channel are filled.
+    ///
+    /// This can be used to pace a producer: once the channel is [full] stop
+    /// sending values until the receiver caught up, e.g. until the channel is
+    /// half empty, instead of waiting for a single slot to become available
+    /// for each value (as [`Sender::send`] does).
+    ///
+    /// The future also completes if the [`Receiver`] and [`Manager`] are
+    /// [disconnected].
+    ///
+    /// [full]: Sender::is_full
+    /// [disconnected]: Sender::is_connected
+    ///
+    /// # Panics
+    ///
+    /// Panics if `watermark` is zero.
+    pub fn watermark(&self, watermark: usize) -> Watermark<'_, T> {
+        assert!(watermark != 0, "watermark can't be zero");
+        Watermark {
+            channel: self.channel(),
+            watermark,
+            registered_waker: None,
+        }
+    }
+
     /// Returns the capacity of the channel.
     pub fn capacity(&self) -> usize {
         self.channel().slots.len()
     }
 
+    /// Returns `true` if all slots in the channel are used, i.e.
+    /// [`Sender::try_send`] would return [`SendError::Full`].
+    pub fn is_full(&self) -> bool {
+        // Relaxed is fine here since there is always a bit of a race condition
+        // when using this method (and then doing something based on it).
+        let status = self.channel().status.load(Ordering::Relaxed);
+        let capacity = self.channel().slots.len();
+        used_slots(status, capacity) == capacity
+    }
+
     /// Returns `true` if the [`Receiver`] and or the [`Manager`] are connected.
     ///
     /// # Notes
@@ -323,9 +716,21 @@ impl<T> Sender<T> {
         ptr::addr_eq(self.channel.as_ptr(), receiver.channel.as_ptr())
     }
 
-    /// Returns the id of this sender.
+    /// Returns the id of the channel this sender sends into.
     pub fn id(&self) -> Id {
-        Id(self.channel.as_ptr().cast_const().cast::<()>() as usize)
+        Id(self.channel().id)
+    }
+
+    /// Attempt to clone the sender.
+    ///
+    /// Returns `None` if the maximum number of senders are alive, see the
+    /// [`Clone`] implementation.
+    pub fn try_clone(&self) -> Option<Sender<T>> {
+        let old_ref_count = self.channel().add_sender()?;
+        debug_assert!(old_ref_count & SENDER_ACCESS != 0);
+        Some(Sender {
+            channel: self.channel,
+        })
     }
 
     fn channel(&self) -> &Channel<T> {
@@ -333,9 +738,53 @@ impl<T> Sender<T> {
     }
 }
 
+impl<T> Sender<Arc<T>> {
+    /// Attempts to send a clone of `value` into the channel.
+    ///
+    /// The channel only stores the pointer to the shared value, so large
+    /// messages are never copied or allocated again. Furthermore `value` is only
+    /// cloned, i.e. its reference count increased, if the channel has capacity,
+    /// in all other cases the error contains the original `value`.
+    ///
+    /// To send the same value to multiple channels see [`try_send_slice`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// let (sender, mut receiver) = heph_inbox::new_small();
+    ///
+    /// let msg = Arc::new(vec![0u8; 1024]);
+    /// sender.try_send_ref(&msg).unwrap();
+    ///
+    /// let received = receiver.try_recv().unwrap();
+    /// assert!(Arc::ptr_eq(&received, &msg));
+    /// ```
+    pub fn try_send_ref<'a>(&self, value: &'a Arc<T>) -> Result<(), SendError<&'a Arc<T>>> {
+        try_send_with(self.channel(), value, Arc::clone, false)
+    }
+}
+
 /// See [`Sender::try_send`].
 fn try_send<T>(channel: &Channel<T>, value: T) -> Result<(), SendError<T>> {
-    if !has_receiver_or_manager(channel.ref_count.load(Ordering::Relaxed)) {
+    try_send_with(channel, value, |value| value, false)
+}
+
+/// Same as [`try_send`], but only calls `into_value` to create the value to
+/// send once a slot is acquired. If `priority` is true the value is send as
+/// priority value, see [`Sender::try_send_priority`].
+fn try_send_with<T, V, F>(
+    channel: &Channel<T>,
+    value: V,
+    into_value: F,
+    priority: bool,
+) -> Result<(), SendError<V>>
+where
+    F: FnOnce(V) -> T,
+{
+    let ref_count = channel.ref_count.load(Ordering::Relaxed);
+    if !has_receiver_or_manager(ref_count) || is_closed(ref_count) {
         return Err(SendError::Disconnected(value));
     }
 
@@ -363,29 +812,44 @@ fn try_send<T>(channel: &Channel<T>, value: T) -> Result<(), SendError<T>> {
         // reader will overwrite it with EMPTY later. If we overwrite EMPTY
         // (00) we can reuse the slot safely, but the message will be in a
         // different order.
-        status = channel
-            .status
-            .fetch_or(mark_slot(slot, MARK_TAKEN), Ordering::AcqRel);
+        status = channel.transition(slot, TAKEN, |status| {
+            status.fetch_or(mark_slot(slot, MARK_TAKEN), Ordering::AcqRel)
+        });
         if !is_available(status, slot) {
             // Another thread beat us to taking the slot.
             continue;
         }
 
+        // If `into_value` panics we need to revert the slot to `EMPTY`,
+        // otherwise the slot would be leaked.
+        let guard = RevertTaken { channel, slot };
+        let value = into_value(value);
+        forget(guard);
+
         // SAFETY: we've acquired the slot above so we're ensured unique
         // access to the slot.
         unsafe {
             let _: &mut T = (*channel.slots[slot].get()).write(value);
         }
 
+        if priority {
+            // NOTE: relaxed ordering is fine as marking the slot as filled
+            // below releases the mark to the receiver.
+            _ = channel.priority.fetch_or(1 << slot, Ordering::Relaxed);
+        }
+
         // Now we've writing to the slot we can mark it slot as filled.
-        let old_status = channel
-            .status
-            .fetch_or(mark_slot(slot, MARK_FILLED), Ordering::AcqRel);
+        let old_status = channel.transition(slot, FILLED, |status| {
+            status.fetch_or(mark_slot(slot, MARK_FILLED), Ordering::AcqRel)
+        });
         // Debug assertion to check the slot was in the TAKEN status.
         debug_assert!(has_status(old_status, slot, TAKEN));
 
-        // If the receiver is waiting for this lot we wake it.
-        if receiver_pos(old_status, cap) == slot {
+        // If the receiver is waiting for this slot, or for any slot (see
+        // `RecvMatching`), we wake it. A priority value is received before the
+        // value at the receiver's position, so we always wake it.
+        if priority || receiver_pos(old_status, cap) == slot || old_status & RECEIVER_MATCHING != 0
+        {
             channel.wake_receiver();
         }
 
@@ -395,17 +859,38 @@ fn try_send<T>(channel: &Channel<T>, value: T) -> Result<(), SendError<T>> {
     Err(SendError::Full(value))
 }
 
-/// # Safety
+/// Reverts `slot` from `TAKEN` to `EMPTY` when dropped, used in
+/// [`try_send_with`] to not leak the slot when creating the value panics, e.g.
+/// in [`Sender::try_send_with`].
+struct RevertTaken<'a, T> {
+    channel: &'a Channel<T>,
+    slot: usize,
+}
+
+impl<T> Drop for RevertTaken<'_, T> {
+    fn drop(&mut self) {
+        let slot = self.slot;
+        let old_status = self.channel.transition(slot, EMPTY, |status| {
+            status.fetch_and(!mark_slot(slot, MARK_EMPTIED), Ordering::AcqRel)
+        });
+        // Debug assertion to check the slot was in the TAKEN status.
+        debug_assert!(has_status(old_status, slot, TAKEN));
+        // The slot is available again.
+        self.channel.wake_next_sender();
+    }
+}
+
+/// # Panics
 ///
-/// Only `2 ^ 30` (a billion) `Sender`s may be alive concurrently, more than
-/// enough for most practical use cases.
+/// Only `2 ^ (usize::BITS - 7)` `Sender`s may be alive concurrently (`2 ^ 25`
+/// on 32 bit and `2 ^ 57` on 64 bit targets), more than enough for most
+/// practical use cases. Cloning more senders panics, see [`Sender::try_clone`]
+/// for a fallible version.
 impl<T> Clone for Sender<T> {
     fn clone(&self) -> Sender<T> {
-        // SAFETY: for the reasoning behind this relaxed ordering see `Arc::clone`.
-        let old_ref_count = self.channel().ref_count.fetch_add(1, Ordering::Relaxed);
-        debug_assert!(old_ref_count & SENDER_ACCESS != 0);
-        Sender {
-            channel: self.channel,
+        match self.try_clone() {
+            Some(sender) => sender,
+            None => too_many_senders(),
         }
     }
 }
@@ -430,6 +915,21 @@ impl<T: RefUnwindSafe> UnwindSafe for Sender<T> {}
 impl<T> Drop for Sender<T> {
     #[rustfmt::skip]
     fn drop(&mut self) {
+        // Record why we're disconnecting, in case we're the last sender. This
+        // needs to happen before we decrement the ref count below, so that it's
+        // visible to the receiver once it sees all senders are disconnected.
+        #[cfg(feature = "std")]
+        let panicking = std::thread::panicking();
+        // Can't detect panics without the standard library.
+        #[cfg(not(feature = "std"))]
+        let panicking = false;
+        let reason = if panicking {
+            DisconnectReason::SenderPanicked
+        } else {
+            DisconnectReason::Finished
+        };
+        self.channel().disconnect_reason.store(reason as u8, Ordering::Relaxed);
+
         // SAFETY: for the reasoning behind this ordering see `Arc::drop`.
         let old_ref_count = self.channel().ref_count.fetch_sub(1, Ordering::Release);
         if sender_count(old_ref_count) != 1 {
@@ -466,6 +966,8 @@ impl<T> Drop for Sender<T> {
 pub struct SendValue<'s, T> {
     channel: &'s Channel<T>,
     value: Option<T>,
+    /// Send as priority value, see [`Sender::send_priority`].
+    priority: bool,
     registered_waker: Option<task::Waker>,
 }
 
@@ -473,9 +975,7 @@ impl<'s, T> Future for SendValue<'s, T> {
     type Output = Result<(), T>;
 
     fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context) -> Poll<Self::Output> {
-        // SAFETY: only `waker_node` is pinned, which is only used by
-        // `register_waker`.
-        let this = unsafe { self.as_mut().get_unchecked_mut() };
+        let this = &mut *self;
         let value = this
             .value
             .take()
@@ -483,7 +983,7 @@ impl<'s, T> Future for SendValue<'s, T> {
 
         // First we try to send the value, if this succeeds we don't have to
         // allocate in the waker list.
-        match try_send(this.channel, value) {
+        match try_send_with(this.channel, value, |value| value, this.priority) {
             Ok(()) => Poll::Ready(Ok(())),
             Err(SendError::Full(value)) => {
                 let registered_waker = register_waker(
@@ -499,7 +999,7 @@ impl<'s, T> Future for SendValue<'s, T> {
                 // the time after we tried to send the value and before we added
                 // the our waker to list. So we try to send a value again to
                 // ensure we don't awoken and the channel has a slot available.
-                match try_send(this.channel, value) {
+                match try_send_with(this.channel, value, |value| value, this.priority) {
                     Ok(()) => Poll::Ready(Ok(())),
                     Err(SendError::Full(value)) => {
                         // Channel is still full, we'll have to wait.
@@ -514,13 +1014,17 @@ impl<'s, T> Future for SendValue<'s, T> {
     }
 }
 
+// The value is never pinned, it's moved into the channel, so `SendValue` can
+// be `Unpin` even if `T` is not.
+impl<'s, T> Unpin for SendValue<'s, T> {}
+
 unsafe impl<'s, T> Sync for SendValue<'s, T> {}
 
 impl<'s, T> Drop for SendValue<'s, T> {
     fn drop(&mut self) {
         // If we registered a waker remove ourselves from the list.
         if let Some(waker) = self.registered_waker.take() {
-            let mut sender_wakers = self.channel.sender_wakers.lock().unwrap();
+            let mut sender_wakers = self.channel.sender_wakers.lock();
             let idx = sender_wakers.iter().position(|w| w.will_wake(&waker));
             if let Some(idx) = idx {
                 let waker = sender_wakers.swap_remove(idx);
@@ -570,7 +1074,7 @@ unsafe impl<'s, T> Sync for Join<'s, T> {}
 impl<'s, T> Drop for Join<'s, T> {
     fn drop(&mut self) {
         if let Some(waker) = self.registered_waker.take() {
-            let mut join_wakers = self.channel.join_wakers.lock().unwrap();
+            let mut join_wakers = self.channel.join_wakers.lock();
             let idx = join_wakers.iter().position(|w| w.will_wake(&waker));
             if let Some(idx) = idx {
                 let waker = join_wakers.swap_remove(idx);
@@ -581,6 +1085,84 @@ impl<'s, T> Drop for Join<'s, T> {
     }
 }
 
+/// [`Future`] implementation behind [`Sender::watermark`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Watermark<'s, T> {
+    channel: &'s Channel<T>,
+    watermark: usize,
+    registered_waker: Option<task::Waker>,
+}
+
+impl<'s, T> Watermark<'s, T> {
+    /// Returns `true` if the channel is below the watermark or the other side
+    /// is disconnected.
+    fn is_ready(&self) -> bool {
+        let status = self.channel.status.load(Ordering::Acquire);
+        used_slots(status, self.channel.slots.len()) < self.watermark
+            || !has_receiver_or_manager(self.channel.ref_count.load(Ordering::Acquire))
+    }
+}
+
+impl<'s, T> Future for Watermark<'s, T> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context) -> Poll<Self::Output> {
+        if self.is_ready() {
+            return Poll::Ready(());
+        }
+
+        // NOTE: unlike `SendValue` and `Join` we can't rely on
+        // `register_waker` here as the `Receiver` removes our waker from the
+        // list when waking us, while the channel can be filled up again before
+        // we're polled. So we always check if our waker is still in the list.
+        let this = &mut *self;
+        let waker = ctx.waker();
+        let mut watermark_wakers = this.channel.watermark_wakers.lock();
+        let idx = this.registered_waker.as_ref().and_then(|registered| {
+            watermark_wakers
+                .iter()
+                .position(|(_, w)| w.will_wake(registered))
+        });
+        if let Some(idx) = idx {
+            watermark_wakers[idx].1.clone_from(waker);
+        } else {
+            watermark_wakers.push((this.watermark, waker.clone()));
+        }
+        unlock(watermark_wakers);
+        match this.registered_waker.as_mut() {
+            Some(registered) => registered.clone_from(waker),
+            None => this.registered_waker = Some(waker.clone()),
+        }
+
+        // It could be the case that the receiver received a value after our
+        // check above and before we added our waker to the list.
+        if this.is_ready() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+unsafe impl<'s, T> Sync for Watermark<'s, T> {}
+
+impl<'s, T> Drop for Watermark<'s, T> {
+    fn drop(&mut self) {
+        if let Some(waker) = self.registered_waker.take() {
+            let mut watermark_wakers = self.channel.watermark_wakers.lock();
+            let idx = watermark_wakers
+                .iter()
+                .position(|(_, w)| w.will_wake(&waker));
+            if let Some(idx) = idx {
+                let (_, waker) = watermark_wakers.swap_remove(idx);
+                unlock(watermark_wakers);
+                drop(waker);
+            }
+        }
+    }
+}
+
 /// Registers `waker` in `channel_wakers` if `registered_waker` is `None` or is
 /// different from `waker`. Return `true` if `waker` was registered, `false`
 /// otherwise.
@@ -594,20 +1176,19 @@ fn register_waker(
         Some(w) if w.will_wake(waker) => false,
         // Different waker, replace the old one.
         Some(w) => {
-            let waker = waker.clone();
-            let old_waker = replace(w, waker.clone());
-
-            let mut channel_wakers = channel_wakers.lock().unwrap();
-            let idx = channel_wakers.iter().position(|w| w.will_wake(&old_waker));
+            let mut channel_wakers = channel_wakers.lock();
+            let idx = channel_wakers.iter().position(|cw| cw.will_wake(w));
             if let Some(idx) = idx {
                 // Replace the old waker with the new one.
-                channel_wakers[idx] = waker;
+                channel_wakers[idx].clone_from(waker);
             } else {
                 // This can happen if `Sender` (or `Manager`) is being
                 // dropped, most likely this `push` is pointless and we
                 // return `Poll::Ready` below, but just in case.
-                channel_wakers.push(waker);
+                channel_wakers.push(waker.clone());
             }
+            unlock(channel_wakers);
+            w.clone_from(waker);
             true
         }
         // Haven't registered waker yet.
@@ -615,7 +1196,7 @@ fn register_waker(
             let waker = waker.clone();
             *registered_waker = Some(waker.clone());
 
-            channel_wakers.lock().unwrap().push(waker);
+            channel_wakers.lock().push(waker);
             true
         }
     }
@@ -632,8 +1213,10 @@ pub struct Receiver<T> {
 pub enum RecvError {
     /// Channel is empty.
     Empty,
-    /// All [`Sender`]s (but not necessarily the [`Manager`]) are disconnected
-    /// and the channel is empty, see [`Receiver::is_connected`].
+    /// All [`Sender`]s (but not necessarily the [`Manager`]) are disconnected,
+    /// or the channel is closed, and the channel is empty, see
+    /// [`Receiver::is_connected`]. Use [`Receiver::disconnect_reason`] to
+    /// determine why.
     Disconnected,
 }
 
@@ -648,6 +1231,51 @@ impl fmt::Display for RecvError {
 
 impl Error for RecvError {}
 
+/// Reason why all [`Sender`]s are disconnected, see
+/// [`Receiver::disconnect_reason`].
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DisconnectReason {
+    /// The last [`Sender`] was dropped normally.
+    Finished = 1,
+    /// The last [`Sender`] was dropped while its thread was panicking.
+    ///
+    /// Only detected with the `std` feature enabled.
+    SenderPanicked = 2,
+    /// The [`Manager`] was dropped while no [`Sender`]s were connected, so no
+    /// new senders can be created.
+    ManagerDropped = 3,
+    /// The channel was closed, see [`Sender::close`].
+    Closed = 4,
+}
+
+/// No [`DisconnectReason`] is recorded (yet).
+const NO_REASON: u8 = 0;
+
+impl DisconnectReason {
+    /// Returns the reason stored in `Inner::disconnect_reason`, if any.
+    const fn from_u8(reason: u8) -> Option<DisconnectReason> {
+        match reason {
+            1 => Some(DisconnectReason::Finished),
+            2 => Some(DisconnectReason::SenderPanicked),
+            3 => Some(DisconnectReason::ManagerDropped),
+            4 => Some(DisconnectReason::Closed),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DisconnectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisconnectReason::Finished => f.pad("all senders finished"),
+            DisconnectReason::SenderPanicked => f.pad("sender panicked"),
+            DisconnectReason::ManagerDropped => f.pad("manager dropped"),
+            DisconnectReason::Closed => f.pad("channel closed"),
+        }
+    }
+}
+
 impl<T> Receiver<T> {
     /// Attempts to receive a value from this channel.
     pub fn try_recv(&mut self) -> Result<T, RecvError> {
@@ -660,7 +1288,8 @@ impl<T> Receiver<T> {
     /// If the returned [`Future`] returns `None` it means all [`Sender`]s are
     /// [disconnected]. This is the same error as [`RecvError::Disconnected`].
     /// [`RecvError::Empty`] will never be returned, the `Future` will return
-    /// [`Poll::Pending`] instead.
+    /// [`Poll::Pending`] instead. See [`Receiver::disconnect_reason`] for why
+    /// the senders disconnected.
     ///
     /// [disconnected]: Receiver::is_connected
     pub fn recv(&mut self) -> RecvValue<T> {
@@ -669,6 +1298,54 @@ impl<T> Receiver<T> {
         }
     }
 
+    /// Attempts to receive a value for which `predicate` returns `true`.
+    ///
+    /// All values for which `predicate` returns `false` are left in the
+    /// channel, to be received later. This allows selective receiving, e.g.
+    /// waiting for a specific response while other messages arrive.
+    ///
+    /// Returns [`RecvError::Empty`] if no value matches. Unlike
+    /// [`Receiver::try_recv`] this returns [`RecvError::Disconnected`] if all
+    /// [`Sender`]s are disconnected and no value matches, even if the channel
+    /// still contains values, as no matching value can arrive any more.
+    ///
+    /// # Notes
+    ///
+    /// `predicate` is called while the value is still in the channel, it's
+    /// called at most once per value in each call to this function.
+    pub fn try_recv_matching<F>(&mut self, mut predicate: F) -> Result<T, RecvError>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        try_recv_matching(self.channel(), &mut predicate)
+    }
+
+    /// Returns a future that receives a value for which `predicate` returns
+    /// `true`, waiting if no such value is in the channel.
+    ///
+    /// This is the asynchronous version of [`Receiver::try_recv_matching`].
+    /// If the returned [`Future`] returns `None` it means all [`Sender`]s are
+    /// [disconnected] and no value matches.
+    ///
+    /// # Notes
+    ///
+    /// The non-matching values take up space in the channel. If the channel
+    /// is filled with non-matching values no new values can be send, meaning
+    /// the future will never complete (until all senders are disconnected).
+    /// Ensure the non-matching values are received, e.g. by limiting the
+    /// number of outstanding requests.
+    ///
+    /// [disconnected]: Receiver::is_connected
+    pub fn recv_matching<F>(&mut self, predicate: F) -> RecvMatching<T, F>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        RecvMatching {
+            channel: self.channel(),
+            predicate,
+        }
+    }
+
     /// Attempts to peek a value from this channel.
     pub fn try_peek(&mut self) -> Result<&T, RecvError> {
         try_peek(self.channel())
@@ -691,15 +1368,16 @@ impl<T> Receiver<T> {
 
     /// Create a new [`Sender`] that sends to this channel.
     ///
-    /// # Safety
+    /// # Panics
     ///
     /// The same restrictions apply to this function as they do to
     /// [`Sender::clone`].
     ///
     /// [`Sender::clone`]: struct.Sender.html#impl-Clone-for-Sender<T>
     pub fn new_sender(&self) -> Sender<T> {
-        // For the reasoning behind this relaxed ordering see `Arc::clone`.
-        let old_ref_count = self.channel().ref_count.fetch_add(1, Ordering::Relaxed);
+        let Some(old_ref_count) = self.channel().add_sender() else {
+            too_many_senders()
+        };
         if old_ref_count & SENDER_ACCESS != 0 {
             _ = self
                 .channel()
@@ -717,7 +1395,8 @@ impl<T> Receiver<T> {
         self.channel().slots.len()
     }
 
-    /// Returns `false` if all [`Sender`]s are disconnected.
+    /// Returns `false` if all [`Sender`]s are disconnected, or if the channel
+    /// is closed (see [`Sender::close`]).
     ///
     /// # Notes
     ///
@@ -728,7 +1407,16 @@ impl<T> Receiver<T> {
     pub fn is_connected(&self) -> bool {
         // Relaxed is fine here since there is always a bit of a race condition
         // when using this method (and then doing something based on it).
-        sender_count(self.channel().ref_count.load(Ordering::Relaxed)) > 0
+        has_sender(self.channel().ref_count.load(Ordering::Relaxed))
+    }
+
+    /// Returns `true` if the channel is closed, see [`Sender::close`].
+    ///
+    /// The channel may still contain values send before it was closed.
+    pub fn is_closed(&self) -> bool {
+        // Relaxed is fine here since there is always a bit of a race condition
+        // when using this method (and then doing something based on it).
+        is_closed(self.channel().ref_count.load(Ordering::Relaxed))
     }
 
     /// Returns `true` if the [`Manager`] is connected.
@@ -738,6 +1426,34 @@ impl<T> Receiver<T> {
         has_manager(self.channel().ref_count.load(Ordering::Relaxed))
     }
 
+    /// Returns the reason why all [`Sender`]s are disconnected, or `None` if a
+    /// sender is still connected and the channel isn't closed.
+    ///
+    /// This can be used after [`Receiver::try_recv`] returned
+    /// [`RecvError::Disconnected`] or [`Receiver::recv`] returned `None` to
+    /// distinguish a graceful shutdown from a crash.
+    ///
+    /// # Notes
+    ///
+    /// The reason is recorded by the last [`Sender`] or the [`Manager`] to
+    /// be dropped. If multiple senders are dropped at the same time the reason
+    /// of any of them can be returned.
+    ///
+    /// Like [`Receiver::is_connected`] this doesn't take the [`Manager`] into
+    /// account, it can still create new senders.
+    pub fn disconnect_reason(&self) -> Option<DisconnectReason> {
+        // SAFETY: `Acquire` is required here to ensure it syncs with the
+        // `Release` in the `Drop` impls of `Sender` and `Manager`, which
+        // stored the reason.
+        let ref_count = self.channel().ref_count.load(Ordering::Acquire);
+        if is_closed(ref_count) {
+            return Some(DisconnectReason::Closed);
+        } else if sender_count(ref_count) > 0 {
+            return None;
+        }
+        DisconnectReason::from_u8(self.channel().disconnect_reason.load(Ordering::Relaxed))
+    }
+
     /// Set the receiver's waker to `waker`, if they are different. Returns
     /// `true` if the waker is changed, `false` otherwise.
     ///
@@ -747,9 +1463,78 @@ impl<T> Receiver<T> {
         self.channel().receiver_waker.register(waker)
     }
 
-    /// Returns the id of this receiver.
+    /// Attempt to reset the channel.
+    ///
+    /// If all [`Sender`]s and the [`Manager`] are disconnected this drops all
+    /// values left in the channel and returns a new `Sender`, effectively
+    /// creating a new channel reusing the allocation of the current one. The
+    /// channel gets a new [`Id`] and is no longer closed. If a sender or the
+    /// manager is still connected this will return `None`.
+    ///
+    /// This is useful when restarting an actor without a `Manager`.
+    pub fn try_reset(&mut self) -> Option<Sender<T>> {
+        // SAFETY: `Acquire` is required here to ensure it syncs with the
+        // `Release` in the `Drop` impls of `Sender` and `Manager`.
+        let ref_count = self.channel().ref_count.load(Ordering::Acquire);
+        // NOTE: we need to check the access bits here as we're going to
+        // overwrite (`store`) the reference count below. If a `Sender` or the
+        // `Manager` was not yet fully dropped this can lead to use-after-free
+        // and double-free.
+        if ref_count & !CLOSED != (RECEIVER_ALIVE | RECEIVER_ACCESS) {
+            return None;
+        }
+
+        // Drop all values still in the channel.
+        while let Ok(msg) = self.try_recv() {
+            drop(msg);
+        }
+
+        // SAFETY: all senders and the manager are dropped, so we have unique
+        // access to the channel.
+        unsafe { ptr::addr_of_mut!((*self.channel.as_ptr()).inner.id).write(Id::next()) };
+        let channel = self.channel();
+        #[cfg(feature = "debug-ordering")]
+        channel.shadow.assert_empty();
+        channel.status.store(0, Ordering::Relaxed);
+        channel
+            .disconnect_reason
+            .store(NO_REASON, Ordering::Relaxed);
+        debug_assert!(channel.sender_wakers.lock().is_empty());
+        debug_assert!(channel.join_wakers.lock().is_empty());
+        debug_assert!(channel.watermark_wakers.lock().is_empty());
+        channel.ref_count.store(
+            RECEIVER_ALIVE | RECEIVER_ACCESS | SENDER_ACCESS | 1,
+            Ordering::Release,
+        );
+
+        Some(Sender {
+            channel: self.channel,
+        })
+    }
+
+    /// Returns the id of the channel this receiver receives from.
     pub fn id(&self) -> Id {
-        Id(self.channel.as_ptr().cast_const().cast::<()>() as usize)
+        Id(self.channel().id)
+    }
+
+    /// Returns a snapshot of the internal state of the channel.
+    ///
+    /// Only meant for testing, e.g. to assert invariants of the channel.
+    /// Requires the `test` feature.
+    #[cfg(any(test, feature = "test"))]
+    pub fn debug_slots(&self) -> DebugSlots {
+        let channel = self.channel();
+        let status = channel.status.load(Ordering::Acquire);
+        let capacity = channel.slots.len();
+        let slots = (0..capacity)
+            .map(|slot| SlotStatus::from_bits(slot_status(status, slot)))
+            .collect();
+        let sender_wakers = channel.sender_wakers.lock().len();
+        DebugSlots {
+            slots,
+            receiver_position: receiver_pos(status, capacity),
+            sender_wakers,
+        }
     }
 
     fn channel(&self) -> &Channel<T> {
@@ -757,6 +1542,47 @@ impl<T> Receiver<T> {
     }
 }
 
+/// Snapshot of the internal state of a channel, see [`Receiver::debug_slots`].
+#[cfg(any(test, feature = "test"))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DebugSlots {
+    /// Status of each slot, the length is equal to the capacity of the
+    /// channel.
+    pub slots: Vec<SlotStatus>,
+    /// Position of the receiver, i.e. the slot it will attempt to read next.
+    pub receiver_position: usize,
+    /// Number of [`Sender`]s waiting for a slot to become available.
+    pub sender_wakers: usize,
+}
+
+/// Status of a slot in the channel, see [`DebugSlots`].
+#[cfg(any(test, feature = "test"))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SlotStatus {
+    /// Slot is empty.
+    Empty,
+    /// A [`Sender`] acquired write access, it's currently writing to it.
+    Taken,
+    /// A [`Sender`] wrote a value into the slot.
+    Filled,
+    /// The [`Receiver`] is reading from the slot.
+    Reading,
+}
+
+#[cfg(any(test, feature = "test"))]
+impl SlotStatus {
+    /// Create a `SlotStatus` from the status bits of a single slot.
+    const fn from_bits(slot_status: u64) -> SlotStatus {
+        match slot_status {
+            EMPTY => SlotStatus::Empty,
+            TAKEN => SlotStatus::Taken,
+            FILLED => SlotStatus::Filled,
+            READING => SlotStatus::Reading,
+            _ => unreachable!(),
+        }
+    }
+}
+
 /// See [`Receiver::try_recv`].
 fn try_recv<T>(channel: &Channel<T>) -> Result<T, RecvError> {
     // We check if we are connected **before** checking for messages. This
@@ -773,7 +1599,10 @@ fn try_recv<T>(channel: &Channel<T>) -> Result<T, RecvError> {
     // again later. In `RecvValue` this is solved by calling `try_recv`
     // after registering the task waker, ensuring no wake-up events are
     // missed.
-    let is_connected = sender_count(channel.ref_count.load(Ordering::Relaxed)) > 0;
+    // SAFETY: `Acquire` is required here to ensure it syncs with the
+    // `Release` in `Sender::close`, ensuring we see all values send before the
+    // channel was closed.
+    let is_connected = has_sender(channel.ref_count.load(Ordering::Acquire));
 
     // Since we subtract from the `status` this will overflow at some point. But
     // `fetch_add` wraps-around on overflow, so the position will "reset" itself
@@ -782,40 +1611,75 @@ fn try_recv<T>(channel: &Channel<T>) -> Result<T, RecvError> {
     let mut status = channel.status.fetch_add(MARK_NEXT_POS, Ordering::AcqRel);
     let cap = channel.slots.len();
     let start = receiver_pos(status, cap);
+
+    // Priority values are received first, see `Sender::try_send_priority`.
+    // NOTE: the `Acquire` above ensures we see the mark of all filled slots.
+    let priority = channel.priority.load(Ordering::Relaxed);
+    if priority != 0 {
+        for slot in (0..cap).cycle().skip(start).take(cap) {
+            if priority & (1 << slot) == 0 || !is_filled(status, slot) {
+                continue;
+            }
+
+            if let Ok(value) = recv_slot(channel, slot) {
+                // We didn't receive the value at our position, so we don't
+                // move the position.
+                _ = channel.status.fetch_sub(MARK_NEXT_POS, Ordering::Relaxed);
+                return Ok(value);
+            }
+        }
+    }
+
     for slot in (0..cap).cycle().skip(start).take(cap) {
         if !is_filled(status, slot) {
             continue;
         }
 
-        // Mark the slot as being read.
-        status = channel
-            .status
-            .fetch_xor(mark_slot(slot, MARK_READING), Ordering::AcqRel);
-        if !is_filled(status, slot) {
+        match recv_slot(channel, slot) {
+            Ok(value) => return Ok(value),
             // Slot isn't available after all.
-            continue;
+            Err(new_status) => status = new_status,
         }
+    }
 
-        // SAFETY: we've acquired unique access to the slot above and we're
-        // ensured the slot is filled.
-        let value = unsafe { (*channel.slots[slot].get()).assume_init_read() };
+    if is_connected {
+        Err(RecvError::Empty)
+    } else {
+        Err(RecvError::Disconnected)
+    }
+}
 
-        // Mark the slot as empty.
-        let old_status = channel
-            .status
-            .fetch_and(!mark_slot(slot, MARK_EMPTIED), Ordering::AcqRel);
-
-        // Debug assertion to check the slot was in the READING or FILLED
-        // status. The slot can be in the FILLED status if the sender tried
-        // to mark this slot as TAKEN (01) after we marked it as READING
-        // (10) (01 | 10 = 11 (FILLED)).
-        debug_assert!(
-            has_status(old_status, slot, READING) || has_status(old_status, slot, FILLED)
-        );
+/// See [`Receiver::try_recv_matching`].
+fn try_recv_matching<T, F>(channel: &Channel<T>, predicate: &mut F) -> Result<T, RecvError>
+where
+    F: FnMut(&T) -> bool,
+{
+    // See `try_recv` why we do this first, and why we need `Acquire`.
+    let is_connected = has_sender(channel.ref_count.load(Ordering::Acquire));
+
+    // NOTE: unlike `try_recv` we don't move the receiver's position, the
+    // non-matching values are left in their slots. We still use a
+    // read-modify-write operation to synchronise with the senders, see
+    // `RecvMatching`.
+    let mut status = channel.status.fetch_add(0, Ordering::AcqRel);
+    let cap = channel.slots.len();
+    let start = receiver_pos(status, cap);
+    for slot in (0..cap).cycle().skip(start).take(cap) {
+        if !is_filled(status, slot) {
+            continue;
+        }
 
-        channel.wake_next_sender();
+        // SAFETY: only the `Receiver` empties filled slots, so the slot stays
+        // filled while we hold the reference.
+        let value = unsafe { (*channel.slots[slot].get()).assume_init_ref() };
+        if !predicate(value) {
+            continue;
+        }
 
-        return Ok(value);
+        match recv_slot(channel, slot) {
+            Ok(value) => return Ok(value),
+            Err(new_status) => status = new_status,
+        }
     }
 
     if is_connected {
@@ -825,19 +1689,63 @@ fn try_recv<T>(channel: &Channel<T>) -> Result<T, RecvError> {
     }
 }
 
+/// Receive the value in the filled `slot`, emptying it.
+///
+/// Returns the updated status if the slot isn't filled after all.
+fn recv_slot<T>(channel: &Channel<T>, slot: usize) -> Result<T, u64> {
+    // Mark the slot as being read.
+    let status = channel.transition(slot, READING, |status| {
+        status.fetch_xor(mark_slot(slot, MARK_READING), Ordering::AcqRel)
+    });
+    if !is_filled(status, slot) {
+        return Err(status);
+    }
+
+    // SAFETY: we've acquired unique access to the slot above and we're
+    // ensured the slot is filled.
+    let value = unsafe { (*channel.slots[slot].get()).assume_init_read() };
+
+    // Remove the priority mark, if any, before a sender can reuse the slot.
+    // NOTE: marking the slot as reading above acquired the mark.
+    let mark = 1 << slot;
+    if channel.priority.load(Ordering::Relaxed) & mark != 0 {
+        _ = channel.priority.fetch_and(!mark, Ordering::Relaxed);
+    }
+
+    // Mark the slot as empty.
+    let old_status = channel.transition(slot, EMPTY, |status| {
+        status.fetch_and(!mark_slot(slot, MARK_EMPTIED), Ordering::AcqRel)
+    });
+
+    // Debug assertion to check the slot was in the READING or FILLED
+    // status. The slot can be in the FILLED status if the sender tried
+    // to mark this slot as TAKEN (01) after we marked it as READING
+    // (10) (01 | 10 = 11 (FILLED)).
+    debug_assert!(has_status(old_status, slot, READING) || has_status(old_status, slot, FILLED));
+
+    channel.wake_next_sender();
+    channel.wake_watermarks(used_slots(old_status, channel.slots.len()) - 1);
+
+    Ok(value)
+}
+
 /// See [`Receiver::try_peek`].
 fn try_peek<T>(channel: &Channel<T>) -> Result<&T, RecvError> {
-    // See `try_recv` why we do this first.
-    let is_connected = sender_count(channel.ref_count.load(Ordering::Relaxed)) > 0;
+    // See `try_recv` why we do this first, and why we need `Acquire`.
+    let is_connected = has_sender(channel.ref_count.load(Ordering::Acquire));
 
     let status = channel.status.load(Ordering::Acquire);
     let cap = channel.slots.len();
     let start = receiver_pos(status, cap);
-    for slot in (0..cap).cycle().skip(start).take(cap) {
-        if !is_filled(status, slot) {
-            continue;
-        }
-
+    // Peek the same value as `try_recv` would receive, i.e. priority values
+    // first.
+    let priority = channel.priority.load(Ordering::Relaxed);
+    let mut slots = (0..cap).cycle().skip(start).take(cap);
+    let slot = slots
+        .clone()
+        .find(|slot| priority & (1 << slot) != 0 && is_filled(status, *slot))
+        .or_else(|| slots.find(|slot| is_filled(status, *slot)));
+    if let Some(slot) = slot {
         // SAFETY: we've acquired unique access to the slot above and we're
         // ensured the slot is filled.
         return Ok(unsafe { (*channel.slots[slot].get()).assume_init_ref() });
@@ -890,12 +1798,28 @@ impl<T> Drop for Receiver<T> {
         // to this channel. However if this `Receiver` is dropped it won't drop
         // the `oneshot::Sender` without the emptying below. This causes
         // `oneshot::Receiver::recv` to wait forever, while holding a `Sender`.
+        // SAFETY: only the `Receiver` accesses `on_drop` and we're the only
+        // `Receiver`.
+        let mut on_drop = unsafe { (*self.channel().on_drop.get()).take() };
         while let Ok(msg) = self.try_recv() {
-            drop(msg);
+            if let Some(on_drop) = on_drop.as_mut() {
+                // NOTE: `on_drop` takes ownership of the message, so we must
+                // not drop it.
+                let mut msg = ManuallyDrop::new(msg);
+                on_drop(ptr::addr_of_mut!(*msg).cast());
+            } else {
+                drop(msg);
+            }
         }
+        drop(on_drop);
 
         // Let all senders know the sender is disconnected.
         self.channel().wake_all_join();
+        // Senders waiting on a slot will find the channel disconnected.
+        self.channel().wake_all_senders();
+        // Zero wakes all senders waiting on a watermark, see
+        // `Channel::wake_watermarks`.
+        self.channel().wake_watermarks(0);
 
         // If the previous value was `RECEIVER_ACCESS` it means that all senders
         // and the manager were all dropped, so we need to do the deallocating.
@@ -950,6 +1874,74 @@ impl<'r, T> Future for RecvValue<'r, T> {
 
 impl<'r, T> Unpin for RecvValue<'r, T> {}
 
+/// [`Future`] implementation behind [`Receiver::recv_matching`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvMatching<'r, T, F> {
+    channel: &'r Channel<T>,
+    predicate: F,
+}
+
+impl<'r, T, F> Future for RecvMatching<'r, T, F>
+where
+    F: FnMut(&T) -> bool,
+{
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context) -> Poll<Self::Output> {
+        // `RecvMatching` is `Unpin`, so we can safely get mutable access.
+        let this = Pin::into_inner(self);
+        match try_recv_matching(this.channel, &mut this.predicate) {
+            Ok(value) => Poll::Ready(Some(value)),
+            Err(RecvError::Empty) => {
+                // No matching value, we'll set the waker. The matching value
+                // can be send into any slot, not just the one at our
+                // position, so we need to be woken for all of them.
+                _ = this.channel.receiver_waker.register(ctx.waker());
+                // NOTE: this must be a read-modify-write operation on the
+                // status, it ensures that either the sender sees our waker
+                // after filling a slot (and wakes us), or we see the filled
+                // slot below.
+                _ = this
+                    .channel
+                    .status
+                    .fetch_or(RECEIVER_MATCHING, Ordering::AcqRel);
+
+                // Unlike `RecvValue` we always need to check again, even if
+                // the waker was already set, as the slot could be filled
+                // before we set `RECEIVER_MATCHING` (e.g. the first time we're
+                // polled).
+                match try_recv_matching(this.channel, &mut this.predicate) {
+                    Ok(value) => Poll::Ready(Some(value)),
+                    // The `Sender` will wake us when a new message is send.
+                    Err(RecvError::Empty) => Poll::Pending,
+                    Err(RecvError::Disconnected) => Poll::Ready(None),
+                }
+            }
+            Err(RecvError::Disconnected) => Poll::Ready(None),
+        }
+    }
+}
+
+impl<'r, T, F> Unpin for RecvMatching<'r, T, F> {}
+
+impl<'r, T, F> Drop for RecvMatching<'r, T, F> {
+    fn drop(&mut self) {
+        // Only wake the receiver for the slot at its position again.
+        _ = self
+            .channel
+            .status
+            .fetch_and(!RECEIVER_MATCHING, Ordering::Relaxed);
+    }
+}
+
+impl<'r, T, F> fmt::Debug for RecvMatching<'r, T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecvMatching")
+            .field("channel", &self.channel)
+            .finish_non_exhaustive()
+    }
+}
+
 /// [`Future`] implementation behind [`Receiver::peek`].
 #[derive(Debug)]
 #[must_use = "futures do nothing unless you `.await` or poll them"]
@@ -999,6 +1991,11 @@ struct Channel<T> {
 ///
 /// This is only in a different struct to calculate the `Layout` of `Channel`,
 /// see [`Channel::new`].
+///
+/// If the `cache-padded` feature is enabled the hot fields are put on their
+/// own cache line, see [`CachePadded`]. Because the alignment of `Inner` is
+/// then that of a cache line the `slots` in `Channel` also start on a new cache
+/// line.
 struct Inner {
     /// Status of the slots.
     ///
@@ -1006,16 +2003,40 @@ struct Inner {
     /// [`STATUS_BITS`] bits to describe if the slot is taken or not.
     ///
     /// The first `STATUS_BITS * MAX_CAP` bits are the statuses for the `slots`
-    /// field. The remaining bits are used by the `Sender` to indicate its
-    /// current reading position (modulo [`MAX_CAP`]).
-    status: AtomicU64,
+    /// field. The next bit is the [`RECEIVER_MATCHING`] bit. The remaining
+    /// bits are used by the `Sender` to indicate its current reading position
+    /// (modulo [`MAX_CAP`]).
+    status: CachePadded<AtomicU64>,
     /// The number of senders alive. If the [`RECEIVER_ALIVE`] bit is set the
     /// [`Receiver`] is alive. If the [`MANAGER_ALIVE`] bit is the [`Manager`]
     /// is alive.
-    ref_count: AtomicUsize,
+    ref_count: CachePadded<AtomicUsize>,
+    receiver_waker: CachePadded<WakerRegistration>,
+    /// Bit mask of the slots that contain a priority value, see
+    /// [`Sender::try_send_priority`]. Bit `n` is set by the [`Sender`] before
+    /// slot `n` is marked as filled and unset by the [`Receiver`] before the
+    /// slot is marked as empty again.
+    priority: AtomicU32,
     sender_wakers: Mutex<Vec<task::Waker>>,
     join_wakers: Mutex<Vec<task::Waker>>,
-    receiver_waker: WakerRegistration,
+    /// Wakers waiting for the channel to drop below a watermark, see
+    /// [`Sender::watermark`].
+    watermark_wakers: Mutex<Vec<(usize, task::Waker)>>,
+    /// Reason all senders disconnected, see [`DisconnectReason`] and
+    /// [`NO_REASON`].
+    disconnect_reason: AtomicU8,
+    /// Unique id of the channel, see [`Id`].
+    id: usize,
+    /// Called with each message still in the channel when the [`Receiver`]
+    /// is dropped, see [`OnDrop`]. The argument is a pointer to the message
+    /// (`T`), which is moved out of the pointer.
+    ///
+    /// Only accessed by the `Receiver`, or when the channel is created.
+    #[allow(clippy::type_complexity)]
+    on_drop: UnsafeCell<Option<Box<dyn FnMut(*mut ()) + Send>>>,
+    /// Shadow status of the slots, see the `ordering` module.
+    #[cfg(feature = "debug-ordering")]
+    shadow: ordering::Shadow,
 }
 
 // SAFETY: if the value can be send across thread than so can the channel.
@@ -1027,10 +2048,10 @@ impl<T> Channel<T> {
     /// Allocates a new `Channel` on the heap.
     ///
     /// `capacity` must small enough to ensure each slot has 2 bits for the
-    /// status, while ensuring that the remaining bits can store `capacity` (in
-    /// binary) to keep track of the reading position. This means following must
-    /// hold true where $N is capacity: `2 ^ (64 - ($N * 2)) >= $N`. The maximum
-    /// is 29.
+    /// status, while ensuring that the remaining bits, minus the
+    /// [`RECEIVER_MATCHING`] bit, can store `capacity` (in binary) to keep
+    /// track of the reading position. This means following must hold true
+    /// where $N is capacity: `2 ^ (63 - ($N * 2)) >= $N`. The maximum is 29.
     ///
     /// Marks a single [`Receiver`] and [`Sender`] as alive.
     fn new(capacity: usize) -> NonNull<Channel<T>> {
@@ -1052,23 +2073,96 @@ impl<T> Channel<T> {
 
         // Initialise all fields (that need it).
         unsafe {
-            ptr::addr_of_mut!((*ptr).inner.status).write(AtomicU64::new(0));
-            ptr::addr_of_mut!((*ptr).inner.ref_count).write(AtomicUsize::new(
+            ptr::addr_of_mut!((*ptr).inner.status).write(CachePadded(AtomicU64::new(0)));
+            ptr::addr_of_mut!((*ptr).inner.ref_count).write(CachePadded(AtomicUsize::new(
                 RECEIVER_ALIVE | RECEIVER_ACCESS | SENDER_ACCESS | 1,
-            ));
+            )));
+            ptr::addr_of_mut!((*ptr).inner.receiver_waker)
+                .write(CachePadded(WakerRegistration::new()));
+            ptr::addr_of_mut!((*ptr).inner.priority).write(AtomicU32::new(0));
             ptr::addr_of_mut!((*ptr).inner.sender_wakers).write(Mutex::new(Vec::new()));
             ptr::addr_of_mut!((*ptr).inner.join_wakers).write(Mutex::new(Vec::new()));
-            ptr::addr_of_mut!((*ptr).inner.receiver_waker).write(WakerRegistration::new());
+            ptr::addr_of_mut!((*ptr).inner.watermark_wakers).write(Mutex::new(Vec::new()));
+            ptr::addr_of_mut!((*ptr).inner.disconnect_reason).write(AtomicU8::new(NO_REASON));
+            ptr::addr_of_mut!((*ptr).inner.id).write(Id::next());
+            ptr::addr_of_mut!((*ptr).inner.on_drop).write(UnsafeCell::new(None));
+            #[cfg(feature = "debug-ordering")]
+            ptr::addr_of_mut!((*ptr).inner.shadow).write(ordering::Shadow::new());
         }
 
         // SAFETY: checked if the pointer is null above.
         unsafe { NonNull::new_unchecked(ptr) }
     }
 
+    /// Set the `on_drop` handler, may only be called when creating the
+    /// channel.
+    fn set_on_drop(&self, on_drop: OnDrop<T>)
+    where
+        T: Send + 'static,
+    {
+        let on_drop: Option<Box<dyn FnMut(*mut ()) + Send>> = match on_drop {
+            OnDrop::Drop => None,
+            OnDrop::Call(mut f) => Some(Box::new(move |msg| {
+                // SAFETY: see `Receiver`'s `Drop` implementation.
+                f(unsafe { msg.cast::<T>().read() });
+            })),
+            OnDrop::Forward(fallback) => Some(Box::new(move |msg| {
+                // SAFETY: see `Receiver`'s `Drop` implementation.
+                let msg = unsafe { msg.cast::<T>().read() };
+                // NOTE: if the fallback channel is full or disconnected we have
+                // no other choice but to drop the message.
+                _ = fallback.try_send(msg);
+            })),
+        };
+        // SAFETY: per the requirements of the function, we're the only ones with
+        // access to the channel.
+        unsafe { *self.on_drop.get() = on_drop };
+    }
+
+    /// Transition `slot` to the status `to` using `op`, which must perform the
+    /// atomic operation on `status` and return the previous status.
+    #[cfg(not(feature = "debug-ordering"))]
+    #[inline(always)]
+    fn transition<F>(&self, _: usize, _: u64, op: F) -> u64
+    where
+        F: FnOnce(&AtomicU64) -> u64,
+    {
+        op(&self.status)
+    }
+
+    /// Transition `slot` to the status `to` using `op`, which must perform the
+    /// atomic operation on `status` and return the previous status.
+    ///
+    /// Checks the transition, see the `ordering` module.
+    #[cfg(feature = "debug-ordering")]
+    fn transition<F>(&self, slot: usize, to: u64, op: F) -> u64
+    where
+        F: FnOnce(&AtomicU64) -> u64,
+    {
+        self.shadow.transition(slot, to, || op(&self.status))
+    }
+
+    /// Increase the number of senders by one, returning the old reference
+    /// count.
+    ///
+    /// Returns `None` if [`MAX_SENDERS`] senders are already alive, leaving the
+    /// reference count unchanged.
+    fn add_sender(&self) -> Option<usize> {
+        // For the reasoning behind this relaxed ordering see `Arc::clone`.
+        let old_ref_count = self.ref_count.fetch_add(1, Ordering::Relaxed);
+        if sender_count(old_ref_count) >= MAX_SENDERS {
+            // Revert our increment. Because of the room left above
+            // `MAX_SENDERS` concurrent increments can't reach the status bits.
+            _ = self.ref_count.fetch_sub(1, Ordering::Relaxed);
+            return None;
+        }
+        Some(old_ref_count)
+    }
+
     /// Returns the next `task::Waker` to wake, if any.
     fn wake_next_sender(&self) {
         let waker = {
-            let mut sender_wakers = self.sender_wakers.lock().unwrap();
+            let mut sender_wakers = self.sender_wakers.lock();
             (!sender_wakers.is_empty()).then(|| sender_wakers.swap_remove(0))
         };
         if let Some(waker) = waker {
@@ -1076,9 +2170,35 @@ impl<T> Channel<T> {
         }
     }
 
+    /// Wakes all senders waiting on a slot to become available.
+    fn wake_all_senders(&self) {
+        let wakers = take(&mut *self.sender_wakers.lock());
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
     /// Wakes all wakers waiting on the sender to disconnect.
     fn wake_all_join(&self) {
-        let wakers = take(&mut *self.join_wakers.lock().unwrap());
+        let wakers = take(&mut *self.join_wakers.lock());
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Wakes all wakers waiting on a watermark larger than `used` slots.
+    fn wake_watermarks(&self, used: usize) {
+        let mut wakers = Vec::new();
+        let mut watermark_wakers = self.watermark_wakers.lock();
+        let mut idx = 0;
+        while idx < watermark_wakers.len() {
+            if watermark_wakers[idx].0 > used {
+                wakers.push(watermark_wakers.swap_remove(idx).1);
+            } else {
+                idx += 1;
+            }
+        }
+        unlock(watermark_wakers);
         for waker in wakers {
             waker.wake();
         }
@@ -1090,6 +2210,35 @@ impl<T> Channel<T> {
     }
 }
 
+/// Pads and aligns `T` to the size of a cache line if the `cache-padded`
+/// feature is enabled, a no-op otherwise.
+///
+/// Modern x86_64 and aarch64 CPUs prefetch cache lines in pairs, so we use
+/// twice the cache line size for them (128 bytes), same as e.g. crossbeam.
+#[cfg_attr(
+    all(
+        feature = "cache-padded",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ),
+    repr(align(128))
+)]
+#[cfg_attr(
+    all(
+        feature = "cache-padded",
+        not(any(target_arch = "x86_64", target_arch = "aarch64"))
+    ),
+    repr(align(64))
+)]
+struct CachePadded<T>(T);
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
 // NOTE: this is here so we don't have to type `self.channel().inner`
 // everywhere.
 impl<T> Deref for Channel<T> {
@@ -1113,9 +2262,11 @@ impl<T> fmt::Debug for Channel<T> {
         }
         let slots = &slots[..self.slots.len()];
         f.debug_struct("Channel")
+            .field("id", &self.id)
             .field("senders_alive", &sender_count)
             .field("receiver_alive", &has_receiver(ref_count))
             .field("manager_alive", &has_manager(ref_count))
+            .field("closed", &is_closed(ref_count))
             .field("receiver_position", &recv_pos)
             .field("slots", &slots)
             .finish()
@@ -1170,6 +2321,23 @@ impl<T> Manager<T> {
         Manager::new_channel(SMALL_CAP)
     }
 
+    /// Create a bounded channel with a `Manager`, using `on_drop` to determine
+    /// what happens to the messages still in the channel when both the
+    /// [`Receiver`] and the `Manager` are dropped.
+    ///
+    /// Same as [`new_with_on_drop`] but with a `Manager`.
+    pub fn new_channel_with_on_drop(
+        capacity: usize,
+        on_drop: OnDrop<T>,
+    ) -> (Manager<T>, Sender<T>, Receiver<T>)
+    where
+        T: Send + 'static,
+    {
+        let (manager, sender, receiver) = Manager::new_channel(capacity);
+        manager.channel().set_on_drop(on_drop);
+        (manager, sender, receiver)
+    }
+
     /// Create a bounded channel with a `Manager`.
     ///
     /// Same as [`new`] but with a `Manager`.
@@ -1188,15 +2356,16 @@ impl<T> Manager<T> {
 
     /// Create a new [`Sender`].
     ///
-    /// # Safety
+    /// # Panics
     ///
-    /// See the [safety nodes] on `Sender`'s [`Clone`] implemenation, the same
+    /// See the [panics notes] on `Sender`'s [`Clone`] implemenation, the same
     /// conditions apply here.
     ///
-    /// [safety nodes]: struct.Sender.html#impl-Clone
+    /// [panics notes]: struct.Sender.html#impl-Clone
     pub fn new_sender(&self) -> Sender<T> {
-        // For the reasoning behind this relaxed ordering see `Arc::clone`.
-        let old_ref_count = self.channel().ref_count.fetch_add(1, Ordering::Relaxed);
+        let Some(old_ref_count) = self.channel().add_sender() else {
+            too_many_senders()
+        };
         if old_ref_count & SENDER_ACCESS != 0 {
             _ = self
                 .channel()
@@ -1227,9 +2396,23 @@ impl<T> Manager<T> {
         }
     }
 
+    /// Returns the number of values in the channel.
+    ///
+    /// This includes values in the process of being send or received, so it's
+    /// only an approximation. Useful for logging and metrics.
+    pub fn len(&self) -> usize {
+        let status = self.channel().status.load(Ordering::Relaxed);
+        used_slots(status, self.channel().slots.len())
+    }
+
+    /// Returns `true` if the channel holds no values, see [`Manager::len`].
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Returns the id of the channel.
     pub fn id(&self) -> Id {
-        Id(self.channel.as_ptr().cast_const().cast::<()>() as usize)
+        Id(self.channel().id)
     }
 
     fn channel(&self) -> &Channel<T> {
@@ -1255,6 +2438,13 @@ impl<T> Unpin for Manager<T> {}
 impl<T> Drop for Manager<T> {
     #[rustfmt::skip]
     fn drop(&mut self) {
+        // If no senders are connected we're the last one that could have
+        // created one, so we record ourselves as the reason for disconnecting.
+        if sender_count(self.channel().ref_count.load(Ordering::Relaxed)) == 0 {
+            let reason = DisconnectReason::ManagerDropped as u8;
+            self.channel().disconnect_reason.store(reason, Ordering::Relaxed);
+        }
+
         // First mark the manager as dropped.
         // SAFETY: for the reasoning behind this ordering see `Arc::drop`.
         let old_ref_count = self.channel().ref_count.fetch_and(!MANAGER_ALIVE, Ordering::Release);
@@ -1280,25 +2470,40 @@ impl<T> Drop for Manager<T> {
 
 /// Identifier of a channel.
 ///
-/// This type can be created by calling [`Sender::id`] or [`Receiver::id`] and
-/// be used to identify channels. Its only use case is to compare two ids with
-/// one another, if two id are the same the sender(s) and receiver(s) point to
-/// the same channel.
+/// This type can be created by calling [`Sender::id`], [`Receiver::id`] or
+/// [`Manager::id`] and be used to identify channels. If two ids are the same
+/// the sender(s) and receiver(s) point to the same channel.
 ///
-/// # Notes
+/// Each channel is assigned a unique id when it's created, ids are never
+/// reused within a single process. Unlike the address of the channel this
+/// makes the id usable to correlate logs (e.g. from both ends of a channel),
+/// the [`fmt::Display`] implementation can be used for that.
 ///
-/// The id is only valid for the lifetime of the channel. Once the channel is
-/// dropped all ids of the channel are invalidated and might return incorrect
-/// results after.
+/// # Notes
 ///
 /// The methods [`Sender::same_channel`] and [`Sender::sends_to`] should be
 /// preferred over using this type as they are less error-prone.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct Id(usize);
 
+/// The id of the next channel, see [`Id::next`].
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
 impl Id {
+    /// Returns the next unique id.
+    fn next() -> usize {
+        // Relaxed is fine here, we only need the value to be unique.
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    }
+
     #[doc(hidden)] // Not part of the stable API.
     pub const fn as_usize(self) -> usize {
         self.0
     }
 }
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
@@ -0,0 +1,111 @@
+//! Module with the [`Mutex`] used to store the wakers.
+//!
+//! With the `std` feature enabled this wraps [`std::sync::Mutex`], without it a
+//! simple spin lock is used. The locks are only held for a short time (to add
+//! or remove a waker) and are mostly uncontested, making the spin lock a
+//! reasonable fallback.
+
+#[cfg(not(feature = "std"))]
+use core::cell::UnsafeCell;
+#[cfg(not(feature = "std"))]
+use core::hint::spin_loop;
+#[cfg(not(feature = "std"))]
+use core::ops::{Deref, DerefMut};
+#[cfg(not(feature = "std"))]
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Mutual exclusion lock.
+#[cfg(feature = "std")]
+pub(crate) struct Mutex<T> {
+    inner: std::sync::Mutex<T>,
+}
+
+#[cfg(feature = "std")]
+impl<T> Mutex<T> {
+    /// Create a new unlocked lock.
+    pub(crate) const fn new(value: T) -> Mutex<T> {
+        Mutex {
+            inner: std::sync::Mutex::new(value),
+        }
+    }
+
+    /// Lock the mutex, blocking until it's available.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the lock is poisoned.
+    pub(crate) fn lock(&self) -> std::sync::MutexGuard<'_, T> {
+        self.inner.lock().unwrap()
+    }
+}
+
+/// Mutual exclusion lock.
+#[cfg(not(feature = "std"))]
+pub(crate) struct Mutex<T> {
+    locked: AtomicBool,
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: access to `value` is protected by `locked`.
+#[cfg(not(feature = "std"))]
+unsafe impl<T: Send> Send for Mutex<T> {}
+#[cfg(not(feature = "std"))]
+unsafe impl<T: Send> Sync for Mutex<T> {}
+
+#[cfg(not(feature = "std"))]
+impl<T> Mutex<T> {
+    /// Create a new unlocked lock.
+    pub(crate) const fn new(value: T) -> Mutex<T> {
+        Mutex {
+            locked: AtomicBool::new(false),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Lock the mutex, spinning until it's available.
+    pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            // Wait until the lock is released before trying again, this
+            // avoids writing to the cache line while it's locked.
+            while self.locked.load(Ordering::Relaxed) {
+                spin_loop();
+            }
+        }
+        MutexGuard { mutex: self }
+    }
+}
+
+/// Guard returned by [`Mutex::lock`], unlocks the mutex when dropped.
+#[cfg(not(feature = "std"))]
+pub(crate) struct MutexGuard<'a, T> {
+    mutex: &'a Mutex<T>,
+}
+
+#[cfg(not(feature = "std"))]
+impl<'a, T> Deref for MutexGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: we hold the lock.
+        unsafe { &*self.mutex.value.get() }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<'a, T> DerefMut for MutexGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: we hold the lock.
+        unsafe { &mut *self.mutex.value.get() }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<'a, T> Drop for MutexGuard<'a, T> {
+    fn drop(&mut self) {
+        self.mutex.locked.store(false, Ordering::Release);
+    }
+}
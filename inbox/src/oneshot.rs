@@ -43,16 +43,21 @@
 //! receiver_handle.join().unwrap();
 //! ```
 
-use std::cell::UnsafeCell;
-use std::fmt;
-use std::future::Future;
-use std::mem::MaybeUninit;
-use std::panic::{RefUnwindSafe, UnwindSafe};
-use std::pin::Pin;
-use std::ptr::{self, NonNull};
-use std::sync::atomic::{AtomicU8, Ordering};
-use std::sync::Mutex;
-use std::task::{self, Poll};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::task::Wake;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::future::Future;
+use core::mem::{self, MaybeUninit};
+use core::panic::{RefUnwindSafe, UnwindSafe};
+use core::pin::Pin;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicU8, Ordering};
+use core::task::{self, Poll};
+
+use crate::lock::Mutex;
 
 /// Create a new one-shot channel.
 pub fn new_oneshot<T>() -> (Sender<T>, Receiver<T>) {
@@ -66,6 +71,8 @@ const RECEIVER_ALIVE: u8 = 0b1000_0000;
 const SENDER_ALIVE: u8 = 0b0100_0000;
 /// Bit mask to mark the sender still has access to the shared data.
 const SENDER_ACCESS: u8 = 0b0010_0000;
+/// Bit mask to mark the receiver still has access to the shared data.
+const RECEIVER_ACCESS: u8 = 0b0001_0000;
 
 /// Return `true` if the receiver is alive in `status`.
 const fn has_receiver(status: u8) -> bool {
@@ -82,6 +89,11 @@ const fn has_sender_access(status: u8) -> bool {
     status & SENDER_ACCESS != 0
 }
 
+/// Return `true` if the receiver has access in `status`.
+const fn has_receiver_access(status: u8) -> bool {
+    status & RECEIVER_ACCESS != 0
+}
+
 // Status of the message in `Shared`.
 const EMPTY: u8 = 0b0000_0000;
 const FILLED: u8 = 0b0000_0001;
@@ -90,7 +102,7 @@ const FILLED: u8 = 0b0000_0001;
 const MARK_FILLED: u8 = 0b0000_0001; // ADD to go from EMPTY -> FILLED.
 const MARK_EMPTY: u8 = !MARK_FILLED; // AND to go from FILLED -> EMPTY.
 /// Initial state value, also used to reset the status.
-const INITIAL: u8 = RECEIVER_ALIVE | SENDER_ALIVE | SENDER_ACCESS | EMPTY;
+const INITIAL: u8 = RECEIVER_ALIVE | RECEIVER_ACCESS | SENDER_ALIVE | SENDER_ACCESS | EMPTY;
 
 /// Returns `true` if `status` is empty.
 const fn is_empty(status: u8) -> bool {
@@ -135,6 +147,49 @@ impl<T> Sender<T> {
         Ok(())
     }
 
+    /// Send a `value` into the channel, returning a future that completes once
+    /// the [`Receiver`] has received the value.
+    ///
+    /// Unlike [`Sender::try_send`], which completes as soon as the value is
+    /// stored in the channel, this allows the sender to know whether or not the
+    /// value was actually received. This is useful for RPC responses, where the
+    /// caller may have stopped waiting for the response.
+    ///
+    /// If the future returns an error it means the receiver has disconnected
+    /// (has been dropped) without receiving the value, the value is returned.
+    ///
+    /// # Notes
+    ///
+    /// The value is send before the future is polled for the first time, i.e.
+    /// the receiver can receive it even if the returned future is never
+    /// polled. While the future is alive the `Sender` is considered connected.
+    pub fn send_and_wait(self, value: T) -> SendAndWait<T> {
+        if !self.is_connected() {
+            return SendAndWait {
+                sender: self,
+                value: Some(value),
+            };
+        }
+
+        let shared = self.shared();
+        // This is safe because we're the only sender.
+        unsafe { ptr::write(shared.message.get(), MaybeUninit::new(value)) };
+        // SAFETY: see `try_send` for the ordering.
+        let old_status = shared.status.fetch_add(MARK_FILLED, Ordering::AcqRel);
+        debug_assert!(is_empty(old_status));
+
+        if has_receiver(old_status) {
+            if let Some(waker) = shared.receiver_waker.lock().take() {
+                waker.wake();
+            }
+        }
+
+        SendAndWait {
+            sender: self,
+            value: None,
+        }
+    }
+
     /// Returns `true` if the [`Receiver`] is connected.
     pub fn is_connected(&self) -> bool {
         // Relaxed is fine here since there is always a bit of a race condition
@@ -176,14 +231,14 @@ impl<T> Drop for Sender<T> {
 
         if has_receiver(old_status) {
             // Receiver is still alive, so we need to wake it.
-            if let Some(waker) = shared.receiver_waker.lock().unwrap().take() {
+            if let Some(waker) = shared.receiver_waker.lock().take() {
                 waker.wake();
             }
         }
 
         // Now mark that we don't have access anymore.
         let old_status = shared.status.fetch_and(!SENDER_ACCESS, Ordering::AcqRel);
-        if !has_receiver(old_status) {
+        if !has_receiver_access(old_status) {
             // Receiver is already dropped so we need to drop the shared memory.
             unsafe { drop(Box::from_raw(self.shared.as_ptr())) }
         }
@@ -224,7 +279,7 @@ impl<T> Receiver<T> {
     /// If it succeeds it returns the value and resets the channel, returning a
     /// new [`Sender`] (which can send a value to this `Receiver`).
     pub fn try_recv(&mut self) -> Result<T, RecvError> {
-        let shared = self.shared();
+        let shared = self.shared_data();
         // SAFETY: `AcqRel` is required here to ensure it syncs with
         // `Sender::try_send`'s status update after the write.
         let status = shared.status.fetch_and(MARK_EMPTY, Ordering::AcqRel);
@@ -241,6 +296,13 @@ impl<T> Receiver<T> {
             // SAFETY: since this is a one-shot channel, after the sender send
             // it's only message we're the only thread with access this is safe.
             let msg = unsafe { (*shared.message.get()).assume_init_read() };
+            if has_sender(status) {
+                // Sender might be waiting for us to receive the value, see
+                // `Sender::send_and_wait`.
+                if let Some(waker) = shared.sender_waker.lock().take() {
+                    waker.wake();
+                }
+            }
             Ok(msg)
         }
     }
@@ -266,6 +328,36 @@ impl<T> Receiver<T> {
         RecvOnce { receiver: self }
     }
 
+    /// Returns a future that receives a value from the channel, like
+    /// [`Receiver::recv_once`], and maps it using `map`.
+    ///
+    /// `map` is only called if a value is received.
+    pub fn map<F, U>(self, map: F) -> Map<T, F>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Map {
+            recv: self.recv_once(),
+            map: Some(map),
+        }
+    }
+
+    /// Convert the receiver into a [`SharedReceiver`], which can be cloned to
+    /// allow multiple waiters to receive (a clone of) the same value.
+    pub fn shared(self) -> SharedReceiver<T>
+    where
+        T: Clone,
+    {
+        SharedReceiver {
+            inner: Arc::new(SharedInner {
+                state: Mutex::new(SharedState::Waiting(self)),
+                wakers: Arc::new(WakeAll {
+                    wakers: Mutex::new(Vec::new()),
+                }),
+            }),
+        }
+    }
+
     /// Attempt to reset the channel.
     ///
     /// If the sender is disconnected this will return a new `Sender`. If the
@@ -275,7 +367,7 @@ impl<T> Receiver<T> {
     ///
     /// If the channel contains a value it will be dropped.
     pub fn try_reset(&mut self) -> Option<Sender<T>> {
-        let shared = self.shared();
+        let shared = self.shared_data();
         // SAFETY: `Acquire` is required here to ensure it syncs with
         // `Sender::try_send`'s status update after the write.
         let status = shared.status.load(Ordering::Acquire);
@@ -298,6 +390,8 @@ impl<T> Receiver<T> {
         // SAFETY: since the `Sender` has been dropped we have unique access to
         // `shared` making Relaxed ordering fine.
         shared.status.store(INITIAL, Ordering::Release);
+        // Don't keep the waker of the previous sender alive.
+        drop(shared.sender_waker.lock().take());
 
         Some(Sender {
             shared: self.shared,
@@ -308,7 +402,7 @@ impl<T> Receiver<T> {
     pub fn is_connected(&self) -> bool {
         // Relaxed is fine here since there is always a bit of a race condition
         // when using the method (and then doing something based on it).
-        let status = self.shared().status.load(Ordering::Relaxed);
+        let status = self.shared_data().status.load(Ordering::Relaxed);
         has_sender(status)
     }
 
@@ -318,8 +412,8 @@ impl<T> Receiver<T> {
     /// This is useful if you can't call [`Receiver::recv`] but still want a
     /// wake-up notification once messages are added to the inbox.
     pub fn register_waker(&mut self, waker: &task::Waker) -> bool {
-        let shared = self.shared();
-        let mut receiver_waker = shared.receiver_waker.lock().unwrap();
+        let shared = self.shared_data();
+        let mut receiver_waker = shared.receiver_waker.lock();
 
         if let Some(receiver_waker) = &*receiver_waker {
             if receiver_waker.will_wake(waker) {
@@ -334,7 +428,7 @@ impl<T> Receiver<T> {
     }
 
     /// Reference the shared data.
-    fn shared(&self) -> &Shared<T> {
+    fn shared_data(&self) -> &Shared<T> {
         // SAFETY: see `shared` field.
         unsafe { self.shared.as_ref() }
     }
@@ -355,10 +449,20 @@ impl<T: RefUnwindSafe> UnwindSafe for Receiver<T> {}
 
 impl<T> Drop for Receiver<T> {
     fn drop(&mut self) {
-        // Mark ourselves as dropped.
-        let shared = self.shared();
+        // Mark ourselves as dropped, but still holding access.
+        let shared = self.shared_data();
         let old_status = shared.status.fetch_and(!RECEIVER_ALIVE, Ordering::AcqRel);
 
+        if has_sender(old_status) {
+            // Sender is still alive, it might be waiting for us to receive the
+            // value, see `Sender::send_and_wait`.
+            if let Some(waker) = shared.sender_waker.lock().take() {
+                waker.wake();
+            }
+        }
+
+        // Now mark that we don't have access anymore.
+        let old_status = shared.status.fetch_and(!RECEIVER_ACCESS, Ordering::AcqRel);
         if !has_sender_access(old_status) {
             // Sender was already dropped, we need to drop the shared memory.
             unsafe { drop(Box::from_raw(self.shared.as_ptr())) }
@@ -426,6 +530,236 @@ impl<T> Future for RecvOnce<T> {
 
 impl<T> Unpin for RecvOnce<T> {}
 
+/// [`Future`] implementation behind [`Receiver::map`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Map<T, F> {
+    recv: RecvOnce<T>,
+    /// `None` after the value was received.
+    map: Option<F>,
+}
+
+impl<T, F, U> Future for Map<T, F>
+where
+    F: FnOnce(T) -> U,
+{
+    type Output = Option<U>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context) -> Poll<Self::Output> {
+        match Pin::new(&mut self.recv).poll(ctx) {
+            Poll::Ready(value) => {
+                let map = self.map.take().expect("polled `Map` after completion");
+                Poll::Ready(value.map(map))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<T, F> Unpin for Map<T, F> {}
+
+impl<T, F> fmt::Debug for Map<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Map")
+    }
+}
+
+/// Receiving half of the one-shot channel that can be shared between multiple
+/// waiters, see [`Receiver::shared`].
+///
+/// All clones receive a clone of the same value. Once the value is received it
+/// stays available, i.e. receiving it again returns another clone.
+pub struct SharedReceiver<T> {
+    inner: Arc<SharedInner<T>>,
+}
+
+/// Data shared between all clones of a [`SharedReceiver`].
+struct SharedInner<T> {
+    state: Mutex<SharedState<T>>,
+    /// Wakers of all waiting [`RecvShared`] futures. This is registered as the
+    /// waker of the [`Receiver`].
+    wakers: Arc<WakeAll>,
+}
+
+/// State of a [`SharedReceiver`].
+enum SharedState<T> {
+    /// Still waiting for a value.
+    Waiting(Receiver<T>),
+    /// Received the value, or `None` if the [`Sender`] disconnected without
+    /// sending a value.
+    Done(Option<T>),
+}
+
+/// Waker that wakes all registered wakers.
+struct WakeAll {
+    wakers: Mutex<Vec<task::Waker>>,
+}
+
+impl Wake for WakeAll {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        let wakers = mem::take(&mut *self.wakers.lock());
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+impl<T: Clone> SharedReceiver<T> {
+    /// Attempts to receive a clone of the value.
+    ///
+    /// See [`Receiver::try_recv`].
+    pub fn try_recv(&self) -> Result<T, RecvError> {
+        let mut state = self.inner.state.lock();
+        match &mut *state {
+            SharedState::Waiting(receiver) => match receiver.try_recv() {
+                Ok(value) => {
+                    *state = SharedState::Done(Some(value.clone()));
+                    Ok(value)
+                }
+                Err(RecvError::NoValue) => Err(RecvError::NoValue),
+                Err(RecvError::Disconnected) => {
+                    *state = SharedState::Done(None);
+                    Err(RecvError::Disconnected)
+                }
+            },
+            SharedState::Done(Some(value)) => Ok(value.clone()),
+            SharedState::Done(None) => Err(RecvError::Disconnected),
+        }
+    }
+
+    /// Returns a future that receives a clone of the value, waiting if no value
+    /// was send yet.
+    ///
+    /// See [`Receiver::recv`].
+    pub fn recv(&self) -> RecvShared<'_, T> {
+        RecvShared { receiver: self }
+    }
+
+    /// Register `waker` to be woken once the value is send or the [`Sender`]
+    /// disconnects.
+    fn register_waker(&self, waker: &task::Waker) {
+        {
+            let mut wakers = self.inner.wakers.wakers.lock();
+            if !wakers.iter().any(|w| w.will_wake(waker)) {
+                wakers.push(waker.clone());
+            }
+        }
+        if let SharedState::Waiting(receiver) = &mut *self.inner.state.lock() {
+            _ = receiver.register_waker(&task::Waker::from(self.inner.wakers.clone()));
+        }
+    }
+}
+
+impl<T> Clone for SharedReceiver<T> {
+    fn clone(&self) -> SharedReceiver<T> {
+        SharedReceiver {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for SharedReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedReceiver")
+    }
+}
+
+/// [`Future`] implementation behind [`SharedReceiver::recv`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvShared<'r, T> {
+    receiver: &'r SharedReceiver<T>,
+}
+
+impl<'r, T: Clone> Future for RecvShared<'r, T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context) -> Poll<Self::Output> {
+        match self.receiver.try_recv() {
+            Ok(value) => return Poll::Ready(Some(value)),
+            Err(RecvError::Disconnected) => return Poll::Ready(None),
+            Err(RecvError::NoValue) => {}
+        }
+
+        self.receiver.register_waker(ctx.waker());
+
+        // It could be the value was send after we checked it above, but before
+        // we registered the waker, so try again.
+        match self.receiver.try_recv() {
+            Ok(value) => Poll::Ready(Some(value)),
+            Err(RecvError::Disconnected) => Poll::Ready(None),
+            Err(RecvError::NoValue) => Poll::Pending,
+        }
+    }
+}
+
+impl<'r, T> Unpin for RecvShared<'r, T> {}
+
+/// [`Future`] implementation behind [`Sender::send_and_wait`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendAndWait<T> {
+    sender: Sender<T>,
+    /// Value if the receiver was disconnected before it was send.
+    value: Option<T>,
+}
+
+impl<T> SendAndWait<T> {
+    /// Check if the receiver has received the value.
+    fn check(&mut self) -> Poll<Result<(), T>> {
+        let shared = self.sender.shared();
+        // SAFETY: `Acquire` is required here to ensure it syncs with
+        // `Receiver::try_recv`'s status update.
+        let status = shared.status.load(Ordering::Acquire);
+        if is_empty(status) {
+            // Receiver received the value.
+            Poll::Ready(Ok(()))
+        } else if !has_receiver(status) {
+            // Receiver was dropped without receiving the value, take it back.
+            let status = shared.status.fetch_and(MARK_EMPTY, Ordering::AcqRel);
+            debug_assert!(is_filled(status));
+            // SAFETY: the receiver is dropped, so we're the only one with
+            // access to the value.
+            let value = unsafe { (*shared.message.get()).assume_init_read() };
+            Poll::Ready(Err(value))
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl<T> Future for SendAndWait<T> {
+    type Output = Result<(), T>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context) -> Poll<Self::Output> {
+        if let Some(value) = self.value.take() {
+            return Poll::Ready(Err(value));
+        }
+
+        if let Poll::Ready(result) = self.check() {
+            return Poll::Ready(result);
+        }
+
+        let shared = self.sender.shared();
+        let mut sender_waker = shared.sender_waker.lock();
+        match &*sender_waker {
+            Some(waker) if waker.will_wake(ctx.waker()) => {}
+            _ => *sender_waker = Some(ctx.waker().clone()),
+        }
+        drop(sender_waker);
+
+        // It could be the case that the receiver received the value (or was
+        // dropped) in the time between we last checked and we actually marked
+        // ourselves as needing a wake up, so we need to check again.
+        self.check()
+    }
+}
+
+impl<T> Unpin for SendAndWait<T> {}
+
 /// Data shared between [`Sender`] and [`Receiver`].
 struct Shared<T> {
     /// A merging of the status of `message` and the liveness of the sender and
@@ -435,6 +769,8 @@ struct Shared<T> {
     message: UnsafeCell<MaybeUninit<T>>,
     /// Waker used to wake the receiving end.
     receiver_waker: Mutex<Option<task::Waker>>,
+    /// Waker used to wake the sending end, see [`Sender::send_and_wait`].
+    sender_waker: Mutex<Option<task::Waker>>,
 }
 
 impl<T> Shared<T> {
@@ -444,6 +780,7 @@ impl<T> Shared<T> {
             status: AtomicU8::new(INITIAL),
             message: UnsafeCell::new(MaybeUninit::uninit()),
             receiver_waker: Mutex::new(None),
+            sender_waker: Mutex::new(None),
         }
     }
 }
@@ -0,0 +1,94 @@
+//! Memory ordering audit, enabled by the `debug-ordering` feature.
+//!
+//! Keeps a shadow copy of the status of each slot, protected by a mutex, and
+//! asserts that every transition observed via the atomic status follows the
+//! only legal path: `EMPTY` -> `TAKEN` -> `FILLED` -> `READING` -> `EMPTY`.
+//! The only exception is a sender reverting `TAKEN` -> `EMPTY` if creating the
+//! value to send panicked.
+//!
+//! This is slow, as it serialises all transitions of a channel, and is only
+//! meant to catch misuse and regressions during development.
+
+use crate::lock::Mutex;
+use crate::{dbg_status, slot_status, EMPTY, FILLED, MAX_CAP, READING, TAKEN};
+
+/// Shadow state machine of the slots in a channel.
+pub(crate) struct Shadow {
+    slots: Mutex<[u64; MAX_CAP]>,
+}
+
+impl Shadow {
+    /// Create a new shadow with all slots `EMPTY`.
+    pub(crate) const fn new() -> Shadow {
+        Shadow {
+            slots: Mutex::new([EMPTY; MAX_CAP]),
+        }
+    }
+
+    /// Transition `slot` to the status `to` using `op`, which must perform the
+    /// atomic operation on the channel's status and return the previous
+    /// status.
+    ///
+    /// If `op` fails to acquire the slot, i.e. when going to `TAKEN` or
+    /// `READING`, no transition is recorded.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the transition is illegal or if the status observed by
+    /// `op` doesn't match the shadow status.
+    pub(crate) fn transition<F>(&self, slot: usize, to: u64, op: F) -> u64
+    where
+        F: FnOnce() -> u64,
+    {
+        let mut slots = self.slots.lock();
+        let from = slots[slot];
+        let status = op();
+        let observed = slot_status(status, slot);
+        if (to == TAKEN || to == READING) && observed != previous(to) {
+            // Failed to acquire the slot.
+            return status;
+        }
+
+        assert!(
+            // A sender reverts a slot to `EMPTY` if creating the value to
+            // send panicked, see `try_send_with`.
+            from == previous(to) || (from == TAKEN && to == EMPTY),
+            "illegal transition of slot {slot}: {} -> {}",
+            dbg_status(from),
+            dbg_status(to),
+        );
+        // A sender can mark a slot as `TAKEN` (01) after the receiver marked
+        // it as `READING` (10), making it `FILLED` (11), see `try_recv`.
+        assert!(
+            observed == from || (from == READING && observed == FILLED),
+            "observed status {} of slot {slot} doesn't match expected status {} (transitioning to {})",
+            dbg_status(observed),
+            dbg_status(from),
+            dbg_status(to),
+        );
+        slots[slot] = to;
+        status
+    }
+
+    /// Assert all slots are `EMPTY`.
+    pub(crate) fn assert_empty(&self) {
+        let slots = self.slots.lock();
+        for (slot, status) in slots.iter().enumerate() {
+            assert!(
+                *status == EMPTY,
+                "slot {slot} not empty: {}",
+                dbg_status(*status),
+            );
+        }
+    }
+}
+
+/// Returns the status a slot must be in before going to status `to`.
+const fn previous(to: u64) -> u64 {
+    match to {
+        TAKEN => EMPTY,
+        FILLED => TAKEN,
+        READING => FILLED,
+        _ => READING,
+    }
+}
@@ -1,15 +1,22 @@
 //! Tests for the internal API.
 
 use std::future::Future;
-use std::mem::{size_of, size_of_val};
+#[cfg(feature = "cache-padded")]
+use std::mem::align_of_val;
+use std::mem::size_of;
+#[cfg(not(feature = "debug-ordering"))]
+use std::mem::size_of_val;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+#[cfg(feature = "cache-padded")]
+use std::ptr;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::task::{self, Poll, Wake};
 
 use crate::{
-    has_status, new_small, receiver_pos, slot_status, Channel, Join, Receiver, SendValue, Sender,
-    ALL_STATUSES_MASK, EMPTY, FILLED, MARK_EMPTIED, MARK_NEXT_POS, MARK_READING, READING,
-    SMALL_CAP, TAKEN,
+    has_status, new_small, receiver_pos, slot_status, try_send_with, Channel, Join, Receiver,
+    SendError, SendValue, Sender, Watermark, ALL_STATUSES_MASK, EMPTY, FILLED, MARK_EMPTIED,
+    MARK_NEXT_POS, MARK_READING, MAX_CAP, MAX_SENDERS, READING, SMALL_CAP, TAKEN,
 };
 
 /// Number of times the waker was awoken.
@@ -51,15 +58,46 @@ fn new_count_waker() -> (task::Waker, AwokenCount) {
 
 #[test]
 fn size_assertions() {
-    let channel = unsafe { Box::from_raw(Channel::<()>::new(1).as_ptr()) };
-    #[cfg(target_os = "linux")]
-    assert_eq!(size_of_val(&**channel), 120);
-    #[cfg(not(target_os = "linux"))]
-    assert_eq!(size_of_val(&**channel), 136);
+    // The `debug-ordering` feature adds a shadow status to the channel.
+    #[cfg(not(any(feature = "debug-ordering", feature = "cache-padded")))]
+    {
+        let channel = unsafe { Box::from_raw(Channel::<()>::new(1).as_ptr()) };
+        #[cfg(target_os = "linux")]
+        assert_eq!(size_of_val(&**channel), 168);
+        #[cfg(not(target_os = "linux"))]
+        assert_eq!(size_of_val(&**channel), 192);
+    }
+    #[cfg(all(
+        feature = "cache-padded",
+        not(feature = "debug-ordering"),
+        target_os = "linux",
+        target_arch = "x86_64"
+    ))]
+    {
+        let channel = unsafe { Box::from_raw(Channel::<()>::new(1).as_ptr()) };
+        assert_eq!(size_of_val(&**channel), 512);
+    }
     assert_eq!(size_of::<Sender<()>>(), 16);
     assert_eq!(size_of::<Receiver<()>>(), 16);
     assert_eq!(size_of::<SendValue<()>>(), 40);
     assert_eq!(size_of::<Join<()>>(), 32);
+    assert_eq!(size_of::<Watermark<()>>(), 40);
+}
+
+#[test]
+#[cfg(feature = "cache-padded")]
+fn cache_padded() {
+    let channel = unsafe { Box::from_raw(Channel::<u64>::new(2).as_ptr()) };
+    let line = align_of_val(&channel.status);
+    assert!(line >= 64);
+    let status = ptr::addr_of!(channel.status) as usize;
+    let ref_count = ptr::addr_of!(channel.ref_count) as usize;
+    let receiver_waker = ptr::addr_of!(channel.receiver_waker) as usize;
+    let slots = channel.slots.as_ptr() as usize;
+    assert_ne!(status / line, ref_count / line);
+    assert_ne!(ref_count / line, receiver_waker / line);
+    assert_ne!(receiver_waker / line, slots / line);
+    assert_eq!(slots % line, 0);
 }
 
 #[test]
@@ -86,6 +124,9 @@ fn assertions() {
     assert_eq!(FILLED & !MARK_EMPTIED, EMPTY);
     assert_eq!(READING & !MARK_EMPTIED, EMPTY);
 
+    // Each slot has a bit in the priority mask.
+    assert!(MAX_CAP <= u32::BITS as usize);
+
     // Changing `Receiver` position doesn't change status of slots.
     const ORIGINAL_STATUS: u64 = 0b1110010011100100;
     assert_eq!(
@@ -183,22 +224,22 @@ fn test_receiver_pos() {
     #[rustfmt::skip]
     let tests = &[
         (0b0000000000000000000000000000000000000000000000000000000000000000, 0),
-        (0b0000010000000000000000000000000000000000000000000000000000000000, 1),
-        (0b0000100000000000000000000000000000000000000000000000000000000000, 2),
-        (0b0000110000000000000000000000000000000000000000000000000000000000, 3),
-        (0b0001000000000000000000000000000000000000000000000000000000000000, 4),
-        (0b0001010000000000000000000000000000000000000000000000000000000000, 5),
-        (0b0001100000000000000000000000000000000000000000000000000000000000, 6),
-        (0b0001110000000000000000000000000000000000000000000000000000000000, 7),
+        (0b0000100000000000000000000000000000000000000000000000000000000000, 1),
+        (0b0001000000000000000000000000000000000000000000000000000000000000, 2),
+        (0b0001100000000000000000000000000000000000000000000000000000000000, 3),
+        (0b0010000000000000000000000000000000000000000000000000000000000000, 4),
+        (0b0010100000000000000000000000000000000000000000000000000000000000, 5),
+        (0b0011000000000000000000000000000000000000000000000000000000000000, 6),
+        (0b0011100000000000000000000000000000000000000000000000000000000000, 7),
         // Additional bits are ignored.
         (0b0000000000000000000000000000000000000000000000000000000000000000, 0),
-        (0b1000010000000000000000000000000000000000000000000000000000000000, 1),
-        (0b1000100000000000000000000000000000000000000000000000000000000000, 2),
-        (0b0100110000000000000000000000000000000000000000000000000000000000, 3),
-        (0b0011000000000000000000000000000000000000000000000000000000000000, 4),
-        (0b0101010000000000000000000000000000000000000000000000000000000000, 5),
-        (0b1001100000000000000000000000000000000000000000000000000000000000, 6),
-        (0b1001110000000000000000000000000000000000000000000000000000000000, 7),
+        (0b1000110000000000000000000000000000000000000000000000000000001100, 1),
+        (0b1001000000000000000000000000000000000000000000000000000000110000, 2),
+        (0b0001110000000000000000000000000000000000000000000000000011000000, 3),
+        (0b0010000000000000000000000000000000000000000000000000001100000000, 4),
+        (0b1010110000000000000000000000000000000000000000000000110000000000, 5),
+        (0b1011000000000000000000000000000000000000000000000011000000000000, 6),
+        (0b1011110000000000000000000000000000000000000000001100000000000000, 7),
     ];
 
     for (input, want) in tests.into_iter().copied() {
@@ -226,11 +267,11 @@ fn channel_next_sender_waker_single_waker() {
     let channel = test_channel();
     let (waker, count) = new_count_waker();
 
-    channel.sender_wakers.lock().unwrap().push(waker);
+    channel.sender_wakers.lock().push(waker);
 
     channel.wake_next_sender();
     assert_eq!(count, 1);
-    assert!(channel.sender_wakers.lock().unwrap().is_empty());
+    assert!(channel.sender_wakers.lock().is_empty());
 }
 
 #[test]
@@ -241,7 +282,7 @@ fn channel_next_sender_waker_two_wakers() {
     let (waker2, count2) = new_count_waker();
 
     {
-        let mut sender_wakers = channel.sender_wakers.lock().unwrap();
+        let mut sender_wakers = channel.sender_wakers.lock();
         sender_wakers.push(waker1);
         sender_wakers.push(waker2);
     }
@@ -252,7 +293,7 @@ fn channel_next_sender_waker_two_wakers() {
     channel.wake_next_sender();
     assert_eq!(count1, 1);
     assert_eq!(count2, 1);
-    assert!(channel.sender_wakers.lock().unwrap().is_empty());
+    assert!(channel.sender_wakers.lock().is_empty());
 }
 
 #[test]
@@ -264,7 +305,7 @@ fn channel_next_sender_waker_three_wakers() {
     let (waker3, count3) = new_count_waker();
 
     {
-        let mut sender_wakers = channel.sender_wakers.lock().unwrap();
+        let mut sender_wakers = channel.sender_wakers.lock();
         sender_wakers.push(waker1);
         sender_wakers.push(waker2);
         sender_wakers.push(waker3);
@@ -282,7 +323,47 @@ fn channel_next_sender_waker_three_wakers() {
     assert_eq!(count1, 1);
     assert_eq!(count2, 1);
     assert_eq!(count3, 1);
-    assert!(channel.sender_wakers.lock().unwrap().is_empty());
+    assert!(channel.sender_wakers.lock().is_empty());
+}
+
+#[test]
+fn try_send_with_panic_reverts_slot() {
+    let (sender, mut receiver) = new_small::<usize>();
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        try_send_with(sender.channel(), 1, |_| -> usize { panic!("oops") }, false)
+    }));
+    assert!(result.is_err());
+
+    // The slot should be available again.
+    for n in 0..sender.capacity() {
+        sender.try_send(n).unwrap();
+    }
+    assert_eq!(sender.try_send(0), Err(SendError::Full(0)));
+    for n in 0..receiver.capacity() {
+        assert_eq!(receiver.try_recv().unwrap(), n);
+    }
+}
+
+#[test]
+fn max_senders() {
+    let (sender, receiver) = new_small::<usize>();
+    let ref_count = &sender.channel().ref_count;
+
+    // Pretend the maximum number of senders is alive.
+    let extra = MAX_SENDERS - 1;
+    _ = ref_count.fetch_add(extra, Ordering::Relaxed);
+    let start = ref_count.load(Ordering::Relaxed);
+
+    assert!(sender.try_clone().is_none());
+    assert!(catch_unwind(AssertUnwindSafe(|| sender.clone())).is_err());
+    assert!(catch_unwind(AssertUnwindSafe(|| receiver.new_sender())).is_err());
+    // Failing to add a sender shouldn't change the reference count.
+    assert_eq!(ref_count.load(Ordering::Relaxed), start);
+
+    _ = ref_count.fetch_sub(extra, Ordering::Relaxed);
+    let sender2 = sender.try_clone().unwrap();
+    assert!(sender2.same_channel(&sender));
 }
 
 #[test]
@@ -301,7 +382,7 @@ fn send_value_removes_waker_from_list_on_drop() {
 
     // Dropping the `SendValue` future should remove the waker from the list.
     drop(future);
-    assert!(receiver.channel().sender_wakers.lock().unwrap().is_empty());
+    assert!(receiver.channel().sender_wakers.lock().is_empty());
 
     for _ in 0..receiver.capacity() {
         assert_eq!(receiver.try_recv().unwrap(), 123);
@@ -330,7 +411,7 @@ fn send_value_removes_waker_from_list_on_drop_polled_with_different_wakers() {
 
     // Dropping the `SendValue` future should remove the waker from the list.
     drop(future);
-    assert!(receiver.channel().sender_wakers.lock().unwrap().is_empty());
+    assert!(receiver.channel().sender_wakers.lock().is_empty());
 
     for _ in 0..receiver.capacity() {
         assert_eq!(receiver.try_recv().unwrap(), 123);
@@ -340,3 +421,99 @@ fn send_value_removes_waker_from_list_on_drop_polled_with_different_wakers() {
     assert_eq!(count1, 0);
     assert_eq!(count2, 0);
 }
+
+#[test]
+fn receiver_waker_registration_doesnt_clone_same_waker() {
+    let channel = test_channel();
+    let inner = Arc::new(WakerInner {
+        count: AtomicUsize::new(0),
+    });
+    let waker = task::Waker::from(inner.clone());
+    assert_eq!(Arc::strong_count(&inner), 2);
+
+    assert!(channel.receiver_waker.register(&waker));
+    assert_eq!(Arc::strong_count(&inner), 3);
+    // Registering the same waker again shouldn't clone it.
+    assert!(!channel.receiver_waker.register(&waker));
+    assert!(!channel.receiver_waker.register(&waker));
+    assert_eq!(Arc::strong_count(&inner), 3);
+
+    channel.receiver_waker.wake();
+    assert_eq!(inner.count.load(Ordering::Acquire), 1);
+    // Waking doesn't consume the waker.
+    assert_eq!(Arc::strong_count(&inner), 3);
+    // Only wake once per registration.
+    channel.receiver_waker.wake();
+    assert_eq!(inner.count.load(Ordering::Acquire), 1);
+
+    assert!(!channel.receiver_waker.register(&waker));
+    channel.receiver_waker.wake();
+    assert_eq!(inner.count.load(Ordering::Acquire), 2);
+}
+
+#[test]
+fn receiver_waker_registration_different_waker() {
+    let channel = test_channel();
+    let (waker1, count1) = new_count_waker();
+    let (waker2, count2) = new_count_waker();
+
+    assert!(channel.receiver_waker.register(&waker1));
+    assert!(channel.receiver_waker.register(&waker2));
+    channel.receiver_waker.wake();
+    assert_eq!(count1, 0);
+    assert_eq!(count2, 1);
+}
+
+#[cfg(feature = "debug-ordering")]
+mod debug_ordering {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use crate::ordering::Shadow;
+    use crate::{mark_slot, EMPTY, FILLED, MARK_EMPTIED, MARK_FILLED, READING, TAKEN};
+
+    #[test]
+    fn legal_transitions() {
+        let shadow = Shadow::new();
+        let status = AtomicU64::new(0);
+        let mark = |m| status.fetch_or(mark_slot(1, m), Ordering::AcqRel);
+        _ = shadow.transition(1, TAKEN, || mark(TAKEN));
+        _ = shadow.transition(1, FILLED, || mark(FILLED));
+        _ = shadow.transition(1, READING, || {
+            status.fetch_xor(mark_slot(1, FILLED ^ READING), Ordering::AcqRel)
+        });
+        _ = shadow.transition(1, EMPTY, || {
+            status.fetch_and(!mark_slot(1, MARK_EMPTIED), Ordering::AcqRel)
+        });
+        shadow.assert_empty();
+    }
+
+    #[test]
+    fn failed_acquire() {
+        let shadow = Shadow::new();
+        let status = AtomicU64::new(mark_slot(0, MARK_FILLED));
+        // Observed slot not `EMPTY`, so the transition isn't recorded.
+        _ = shadow.transition(0, TAKEN, || status.load(Ordering::Relaxed));
+        shadow.assert_empty();
+    }
+
+    #[test]
+    #[should_panic = "illegal transition of slot 0: EMPTY -> FILLED"]
+    fn illegal_transition() {
+        let shadow = Shadow::new();
+        let status = AtomicU64::new(0);
+        _ = shadow.transition(0, FILLED, || status.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    #[should_panic = "observed status EMPTY of slot 0 doesn't match expected status TAKEN"]
+    fn observed_status_mismatch() {
+        let shadow = Shadow::new();
+        let status = AtomicU64::new(0);
+        _ = shadow.transition(0, TAKEN, || {
+            status.fetch_or(mark_slot(0, TAKEN), Ordering::AcqRel)
+        });
+        // Status got reset behind our back.
+        status.store(0, Ordering::Relaxed);
+        _ = shadow.transition(0, FILLED, || status.load(Ordering::Relaxed));
+    }
+}
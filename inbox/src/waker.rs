@@ -1,47 +1,94 @@
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::RwLock;
-use std::task;
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use core::task;
 
 /// Registration of a [`task::Waker`].
+///
+/// This is based on the `AtomicWaker` type found in the futures crate, but
+/// keeps the waker around after waking it so that registering the same waker
+/// again doesn't require a clone.
+///
+/// # Notes
+///
+/// Only a single thread, the owner of the `Receiver`, may call
+/// [`WakerRegistration::register`] at a time. Any thread may call
+/// [`WakerRegistration::wake`].
 pub(crate) struct WakerRegistration {
     /// This will be `true` if this waker needs to be awoken, `false` otherwise.
     needs_wakeup: AtomicBool,
+    /// Status of `waker`, see [`WAITING`], [`REGISTERING`] and [`WAKING`].
+    state: AtomicU8,
     /// The actual waking mechanism.
-    waker: RwLock<Option<task::Waker>>,
+    ///
+    /// Only written to by `register` while holding the [`REGISTERING`] state,
+    /// `wake` only reads it while holding the [`WAKING`] state.
+    waker: UnsafeCell<Option<task::Waker>>,
 }
 
+/// Nobody is accessing `waker`.
+const WAITING: u8 = 0b00;
+/// `register` is changing `waker`.
+const REGISTERING: u8 = 0b01;
+/// `wake` is waking `waker`.
+const WAKING: u8 = 0b10;
+
 impl WakerRegistration {
     /// Create a new empty registration.
     pub(crate) const fn new() -> WakerRegistration {
         WakerRegistration {
             needs_wakeup: AtomicBool::new(false),
-            waker: RwLock::new(None),
+            state: AtomicU8::new(WAITING),
+            waker: UnsafeCell::new(None),
         }
     }
 
     /// Register `waker`.
+    ///
+    /// Returns `true` if the waker is changed, `false` otherwise.
     pub(crate) fn register(&self, waker: &task::Waker) -> bool {
-        let stored_waker = self.waker.read().unwrap();
-        if let Some(stored_waker) = &*stored_waker {
+        // SAFETY: we're the only thread that writes to `waker` (see the notes
+        // on the type), so reading it is safe.
+        if let Some(stored_waker) = unsafe { &*self.waker.get() } {
             if stored_waker.will_wake(waker) {
                 self.needs_wakeup.store(true, Ordering::Release);
                 return false;
             }
         }
-        drop(stored_waker); // Unlock read lock.
 
-        let mut stored_waker = self.waker.write().unwrap();
-        // Since another thread could have changed the waker since we dropped
-        // the read lock and we got the write lock, we have to check the waker
-        // again.
-        if let Some(stored_waker) = &*stored_waker {
-            if stored_waker.will_wake(waker) {
-                self.needs_wakeup.store(true, Ordering::Release);
-                return false;
+        match self.state.compare_exchange(
+            WAITING,
+            REGISTERING,
+            Ordering::Acquire,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => {
+                // SAFETY: we hold the `REGISTERING` state, so no other thread
+                // is accessing `waker`.
+                unsafe { *self.waker.get() = Some(waker.clone()) };
+
+                let res = self.state.compare_exchange(
+                    REGISTERING,
+                    WAITING,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                );
+                if let Err(state) = res {
+                    // A call to `wake` happened while we were changing the
+                    // waker, but it didn't wake anything (as we held the
+                    // `REGISTERING` state), so we need to wake ourselves.
+                    debug_assert_eq!(state, REGISTERING | WAKING);
+                    self.state.store(WAITING, Ordering::Release);
+                    waker.wake_by_ref();
+                }
+            }
+            Err(state) => {
+                // Another thread is waking the old waker, so we can't change
+                // it. Wake ourselves so that we get polled (and register)
+                // again.
+                debug_assert_eq!(state, WAKING);
+                waker.wake_by_ref();
             }
         }
-        *stored_waker = Some(waker.clone());
-        drop(stored_waker);
 
         self.needs_wakeup.store(true, Ordering::Release);
         true
@@ -55,10 +102,21 @@ impl WakerRegistration {
         }
 
         // Mark that we've woken and after actually do the waking.
-        if self.needs_wakeup.swap(false, Ordering::AcqRel) {
-            if let Some(waker) = &*self.waker.read().unwrap() {
+        if self.needs_wakeup.swap(false, Ordering::AcqRel)
+            && self.state.fetch_or(WAKING, Ordering::AcqRel) == WAITING
+        {
+            // SAFETY: we hold the `WAKING` state, so `register` will not
+            // change `waker`.
+            if let Some(waker) = unsafe { &*self.waker.get() } {
                 waker.wake_by_ref();
             }
+            _ = self.state.fetch_and(!WAKING, Ordering::Release);
         }
+        // Else `register` is changing the waker (and will wake itself) or
+        // another thread is already waking it.
     }
 }
+
+// SAFETY: access to `waker` is synchronised using `state`, see
+// `WakerRegistration::register` and `WakerRegistration::wake`.
+unsafe impl Sync for WakerRegistration {}
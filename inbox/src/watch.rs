@@ -0,0 +1,417 @@
+//! Watch channel.
+//!
+//! The channel holds a single value, the latest value send. Unlike the other
+//! channels in this crate the values are not queued, a [`Receiver`] only sees
+//! the latest value and can wait for it to change. It is designed to be used
+//! for values such as configuration or cluster membership, where only the
+//! latest version is relevant.
+//!
+//! The channel has a single [`Sender`], but can have many receivers, see
+//! [`Receiver`]'s `Clone` implementation and [`Sender::subscribe`].
+//!
+//! # Examples
+//!
+//! Simple creation of a channel and sending a value over it.
+//!
+//! ```
+//! use std::thread;
+//!
+//! use heph_inbox::watch::{RecvError, new_watch};
+//!
+//! // Create a new watch channel with an initial value.
+//! let (sender, mut receiver) = new_watch("initial".to_owned());
+//!
+//! // The initial value is considered seen by the receiver.
+//! assert_eq!(receiver.try_recv(), Err(RecvError::Unchanged));
+//! assert_eq!(*receiver.borrow(), "initial");
+//!
+//! let sender_handle = thread::spawn(move || {
+//!     sender.send("updated".to_owned());
+//! });
+//!
+//! let receiver_handle = thread::spawn(move || {
+//! #   #[cfg(not(miri))] // `sleep` not supported.
+//! #   thread::sleep(std::time::Duration::from_millis(1)); // Don't waste cycles.
+//!     // NOTE: this is just an example don't actually use a loop like this, it
+//!     // will waste CPU cycles when the value is unchanged!
+//!     loop {
+//!         match receiver.try_recv() {
+//!             Ok(value) => println!("Got a new value: {value}"),
+//!             Err(RecvError::Unchanged) => continue,
+//!             Err(RecvError::Disconnected) => break,
+//!         }
+//!     }
+//! });
+//!
+//! sender_handle.join().unwrap();
+//! receiver_handle.join().unwrap();
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::mem::{drop as unlock, replace};
+use std::ops::Deref;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::task::{self, Poll};
+
+use crate::lock::Mutex;
+use crate::register_waker;
+
+/// Create a new watch channel with an initial `value`.
+///
+/// The initial value is considered seen by the returned [`Receiver`].
+pub fn new_watch<T>(value: T) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        value: RwLock::new(value),
+        version: AtomicUsize::new(INITIAL_VERSION),
+        receiver_wakers: Mutex::new(Vec::new()),
+    });
+    let receiver = Receiver {
+        shared: shared.clone(),
+        seen: INITIAL_VERSION,
+        registered_waker: None,
+    };
+    (Sender { shared }, receiver)
+}
+
+/// Bit set in the version if the sender is disconnected.
+const CLOSED: usize = 0b1;
+/// Amount the version is increased by when the value changes, the first bit
+/// is used by [`CLOSED`].
+const VERSION_INCREMENT: usize = 0b10;
+/// Version of the initial value.
+const INITIAL_VERSION: usize = 0;
+
+/// Returns `true` if the sender is connected in `version`.
+const fn has_sender(version: usize) -> bool {
+    version & CLOSED == 0
+}
+
+/// Returns the version of the value in `version`, without the [`CLOSED`] bit.
+const fn value_version(version: usize) -> usize {
+    version & !CLOSED
+}
+
+/// The sending half of the [watch channel].
+///
+/// [watch channel]: crate::watch::new_watch
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Send a new `value`, replacing the current value.
+    ///
+    /// All [`Receiver`]s will be notified of the change. The value is stored
+    /// even if no receivers are connected, see [`Sender::subscribe`].
+    pub fn send(&self, value: T) {
+        let mut current = self.shared.value.write().unwrap();
+        let old_value = replace(&mut *current, value);
+        // NOTE: we bump the version while holding the lock so that the
+        // receivers always see the value and version in sync.
+        _ = self
+            .shared
+            .version
+            .fetch_add(VERSION_INCREMENT, Ordering::AcqRel);
+        unlock(current);
+        drop(old_value);
+        self.shared.wake_receivers();
+    }
+
+    /// Modify the current value in place using `modify`.
+    ///
+    /// All [`Receiver`]s will be notified of the change, same as with
+    /// [`Sender::send`].
+    pub fn send_modify<F>(&self, modify: F)
+    where
+        F: FnOnce(&mut T),
+    {
+        let mut current = self.shared.value.write().unwrap();
+        modify(&mut current);
+        _ = self
+            .shared
+            .version
+            .fetch_add(VERSION_INCREMENT, Ordering::AcqRel);
+        unlock(current);
+        self.shared.wake_receivers();
+    }
+
+    /// Returns a reference to the current value.
+    ///
+    /// # Notes
+    ///
+    /// While the returned reference is alive the value can't be changed, i.e.
+    /// calls to [`Sender::send`] will block.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref {
+            guard: self.shared.value.read().unwrap(),
+        }
+    }
+
+    /// Create a new [`Receiver`] for this channel.
+    ///
+    /// The current value is considered seen by the returned receiver.
+    pub fn subscribe(&self) -> Receiver<T> {
+        Receiver {
+            shared: self.shared.clone(),
+            seen: value_version(self.shared.version.load(Ordering::Acquire)),
+            registered_waker: None,
+        }
+    }
+
+    /// Returns `true` if at least one [`Receiver`] is connected.
+    pub fn is_connected(&self) -> bool {
+        // NOTE: there is always a bit of a race condition when using this
+        // method (and then doing something based on it).
+        Arc::strong_count(&self.shared) > 1
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Sender")
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        _ = self.shared.version.fetch_or(CLOSED, Ordering::AcqRel);
+        self.shared.wake_receivers();
+    }
+}
+
+/// The receiving half of the [watch channel].
+///
+/// The receiver can be cloned, the cloned receiver will have seen the same
+/// version of the value as the original receiver.
+///
+/// [watch channel]: crate::watch::new_watch
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    /// Version of the value last seen by this receiver, without the [`CLOSED`]
+    /// bit.
+    seen: usize,
+    /// Waker registered in `Shared::receiver_wakers`, if any.
+    registered_waker: Option<task::Waker>,
+}
+
+/// Error returned by [`Receiver::try_recv`].
+#[derive(Debug, Eq, PartialEq)]
+pub enum RecvError {
+    /// The value hasn't changed since it was last seen, but the sender is still
+    /// connected.
+    Unchanged,
+    /// Sender is disconnected and the value hasn't changed since it was last
+    /// seen.
+    Disconnected,
+}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecvError::Unchanged => f.write_str("value unchanged"),
+            RecvError::Disconnected => f.write_str("sender disconnected"),
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Attempts to receive the value, if it changed since it was last seen.
+    ///
+    /// If it succeeds the value is marked as seen.
+    pub fn try_recv(&mut self) -> Result<T, RecvError>
+    where
+        T: Clone,
+    {
+        let value = self.shared.value.read().unwrap();
+        // NOTE: the version is only changed while holding the write lock, so
+        // it matches the value.
+        let version = self.shared.version.load(Ordering::Acquire);
+        if value_version(version) != self.seen {
+            self.seen = value_version(version);
+            Ok(value.clone())
+        } else if has_sender(version) {
+            Err(RecvError::Unchanged)
+        } else {
+            Err(RecvError::Disconnected)
+        }
+    }
+
+    /// Returns a future that receives the value once it changed since it was
+    /// last seen.
+    ///
+    /// If the returned [`Future`] returns `None` it means the [`Sender`] is
+    /// [disconnected] without changing the value. This is the same error as
+    /// [`RecvError::Disconnected`]. [`RecvError::Unchanged`] will never be
+    /// returned, the `Future` will return [`Poll::Pending`] instead.
+    ///
+    /// [disconnected]: Receiver::is_connected
+    pub fn recv(&mut self) -> RecvValue<'_, T>
+    where
+        T: Clone,
+    {
+        RecvValue { receiver: self }
+    }
+
+    /// Returns a reference to the current value, without marking it as seen.
+    ///
+    /// # Notes
+    ///
+    /// While the returned reference is alive the value can't be changed, i.e.
+    /// calls to [`Sender::send`] will block.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref {
+            guard: self.shared.value.read().unwrap(),
+        }
+    }
+
+    /// Returns a reference to the current value, marking it as seen.
+    ///
+    /// Also see the notes on [`Receiver::borrow`].
+    pub fn borrow_and_update(&mut self) -> Ref<'_, T> {
+        let guard = self.shared.value.read().unwrap();
+        self.seen = value_version(self.shared.version.load(Ordering::Acquire));
+        Ref { guard }
+    }
+
+    /// Returns `true` if the value changed since it was last seen.
+    pub fn has_changed(&self) -> bool {
+        let version = self.shared.version.load(Ordering::Acquire);
+        value_version(version) != self.seen
+    }
+
+    /// Returns `true` if the [`Sender`] is connected.
+    pub fn is_connected(&self) -> bool {
+        // Relaxed is fine here since there is always a bit of a race condition
+        // when using the method (and then doing something based on it).
+        has_sender(self.shared.version.load(Ordering::Relaxed))
+    }
+
+    /// Set the receiver's waker to `waker`, if they are different. Returns
+    /// `true` if the waker is changed, `false` otherwise.
+    ///
+    /// This is useful if you can't call [`Receiver::recv`] but still want a
+    /// wake-up notification once the value changes.
+    pub fn register_waker(&mut self, waker: &task::Waker) -> bool {
+        register_waker(
+            &mut self.registered_waker,
+            &self.shared.receiver_wakers,
+            waker,
+        )
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Receiver<T> {
+        Receiver {
+            shared: self.shared.clone(),
+            seen: self.seen,
+            registered_waker: None,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("seen", &self.seen)
+            .finish()
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        // If we registered a waker remove ourselves from the list.
+        if let Some(waker) = self.registered_waker.take() {
+            let mut receiver_wakers = self.shared.receiver_wakers.lock();
+            let idx = receiver_wakers.iter().position(|w| w.will_wake(&waker));
+            if let Some(idx) = idx {
+                let waker = receiver_wakers.swap_remove(idx);
+                unlock(receiver_wakers);
+                drop(waker);
+            }
+        }
+    }
+}
+
+/// [`Future`] implementation behind [`Receiver::recv`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvValue<'r, T> {
+    receiver: &'r mut Receiver<T>,
+}
+
+impl<'r, T: Clone> Future for RecvValue<'r, T> {
+    type Output = Option<T>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context) -> Poll<Self::Output> {
+        match self.receiver.try_recv() {
+            Ok(value) => Poll::Ready(Some(value)),
+            Err(RecvError::Unchanged) => {
+                // The value hasn't changed yet, we'll set the waker.
+                if !self.receiver.register_waker(ctx.waker()) {
+                    // Waker already set.
+                    return Poll::Pending;
+                }
+
+                // It could be the case that the sender changed the value in the
+                // time between we last checked and we actually registered our
+                // waker, so we need to check again.
+                match self.receiver.try_recv() {
+                    Ok(value) => Poll::Ready(Some(value)),
+                    // The `Sender` will wake us when the value changes.
+                    Err(RecvError::Unchanged) => Poll::Pending,
+                    Err(RecvError::Disconnected) => Poll::Ready(None),
+                }
+            }
+            Err(RecvError::Disconnected) => Poll::Ready(None),
+        }
+    }
+}
+
+impl<'r, T> Unpin for RecvValue<'r, T> {}
+
+/// Reference to the value in a watch channel.
+///
+/// See [`Sender::borrow`] and [`Receiver::borrow`].
+pub struct Ref<'a, T> {
+    guard: RwLockReadGuard<'a, T>,
+}
+
+impl<'a, T> Deref for Ref<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for Ref<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+/// Data shared between [`Sender`] and [`Receiver`]s.
+struct Shared<T> {
+    /// The latest value.
+    value: RwLock<T>,
+    /// Version of `value`, increased by [`VERSION_INCREMENT`] each time the
+    /// value changes. Also contains the [`CLOSED`] bit.
+    version: AtomicUsize,
+    /// Wakers of the receivers, a receiver's waker stays registered until it's
+    /// dropped.
+    receiver_wakers: Mutex<Vec<task::Waker>>,
+}
+
+impl<T> Shared<T> {
+    /// Wake all receivers.
+    fn wake_receivers(&self) {
+        let receiver_wakers = self.receiver_wakers.lock();
+        for waker in receiver_wakers.iter() {
+            waker.wake_by_ref();
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! Tests for the `std::sync::mpsc` compatibility layer.
+
+#[macro_use]
+mod util;
+
+mod functional {
+    use std::sync::mpsc::{RecvTimeoutError, SendError, TryRecvError, TrySendError};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use heph_inbox::compat::{sync_channel, Receiver, SyncSender};
+    use heph_inbox::MAX_CAP;
+
+    use crate::util::{assert_send, assert_sync};
+
+    #[test]
+    fn sender_is_send() {
+        assert_send::<SyncSender<()>>();
+    }
+
+    #[test]
+    fn sender_is_sync() {
+        assert_sync::<SyncSender<()>>();
+    }
+
+    #[test]
+    fn receiver_is_send() {
+        assert_send::<Receiver<()>>();
+    }
+
+    #[test]
+    fn send_recv() {
+        let (sender, receiver) = sync_channel(2);
+        sender.send(1).unwrap();
+        sender.send(2).unwrap();
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.recv(), Ok(2));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn try_send_full() {
+        let (sender, receiver) = sync_channel(1);
+        sender.try_send(1).unwrap();
+        assert_eq!(sender.try_send(2), Err(TrySendError::Full(2)));
+        assert_eq!(receiver.try_recv(), Ok(1));
+    }
+
+    #[test]
+    fn bound_is_clamped() {
+        let (sender, _receiver) = sync_channel(0);
+        sender.try_send(1).unwrap();
+        assert_eq!(sender.try_send(2), Err(TrySendError::Full(2)));
+
+        let (sender, _receiver) = sync_channel(usize::MAX);
+        for n in 0..MAX_CAP {
+            sender.try_send(n).unwrap();
+        }
+        assert_eq!(sender.try_send(100), Err(TrySendError::Full(100)));
+    }
+
+    #[test]
+    fn send_blocks_when_full() {
+        let (sender, receiver) = sync_channel(1);
+        sender.send(1).unwrap();
+
+        let handle = thread::spawn(move || {
+            // Blocks until the first value is received.
+            sender.send(2).unwrap();
+        });
+
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.recv(), Ok(2));
+        handle.join().unwrap();
+        assert!(receiver.recv().is_err());
+    }
+
+    #[test]
+    fn recv_blocks_when_empty() {
+        let (sender, receiver) = sync_channel(1);
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            sender.send(1).unwrap();
+        });
+
+        assert_eq!(receiver.recv(), Ok(1));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn send_disconnected() {
+        let (sender, receiver) = sync_channel(1);
+        drop(receiver);
+        assert_eq!(sender.send(1), Err(SendError(1)));
+        assert_eq!(sender.try_send(2), Err(TrySendError::Disconnected(2)));
+    }
+
+    #[test]
+    fn blocked_send_disconnected() {
+        let (sender, receiver) = sync_channel(1);
+        sender.send(1).unwrap();
+
+        let handle = thread::spawn(move || sender.send(2));
+
+        thread::sleep(Duration::from_millis(10));
+        drop(receiver);
+        assert_eq!(handle.join().unwrap(), Err(SendError(2)));
+    }
+
+    #[test]
+    fn recv_disconnected() {
+        let (sender, receiver) = sync_channel(1);
+        sender.send(1).unwrap();
+        drop(sender);
+        // Values send before the disconnect can still be received.
+        assert_eq!(receiver.recv(), Ok(1));
+        assert!(receiver.recv().is_err());
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn recv_timeout() {
+        let (sender, receiver) = sync_channel(1);
+
+        let start = Instant::now();
+        let timeout = Duration::from_millis(10);
+        assert_eq!(
+            receiver.recv_timeout(timeout),
+            Err(RecvTimeoutError::Timeout)
+        );
+        assert!(start.elapsed() >= timeout);
+
+        sender.send(1).unwrap();
+        assert_eq!(receiver.recv_timeout(timeout), Ok(1));
+
+        drop(sender);
+        assert_eq!(
+            receiver.recv_timeout(timeout),
+            Err(RecvTimeoutError::Disconnected)
+        );
+    }
+
+    #[test]
+    fn iter() {
+        let (sender, receiver) = sync_channel(2);
+
+        let handle = thread::spawn(move || {
+            for n in 0..10 {
+                sender.send(n).unwrap();
+            }
+        });
+
+        let values: Vec<usize> = receiver.iter().collect();
+        assert_eq!(values, (0..10).collect::<Vec<_>>());
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn try_iter() {
+        let (sender, receiver) = sync_channel(4);
+        sender.send(1).unwrap();
+        sender.send(2).unwrap();
+
+        let values: Vec<usize> = receiver.try_iter().collect();
+        assert_eq!(values, [1, 2]);
+        // Doesn't block on an empty, but connected, channel.
+        assert_eq!(receiver.try_iter().next(), None);
+    }
+
+    #[test]
+    fn into_iter() {
+        let (sender, receiver) = sync_channel(4);
+        let sender2 = sender.clone();
+        sender.send(1).unwrap();
+        sender2.send(2).unwrap();
+        drop(sender);
+        drop(sender2);
+
+        let values: Vec<usize> = receiver.into_iter().collect();
+        assert_eq!(values, [1, 2]);
+    }
+
+    #[test]
+    fn into_inner() {
+        let (sender, receiver) = sync_channel(1);
+        let sender = sender.into_inner();
+        let mut receiver = receiver.into_inner();
+        sender.try_send(1).unwrap();
+        assert_eq!(receiver.try_recv(), Ok(1));
+    }
+}
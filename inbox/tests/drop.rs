@@ -1,6 +1,9 @@
 //! Tests for memory deallocation.
 
-use heph_inbox::{self as inbox, new, Manager};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use heph_inbox::{self as inbox, new, new_with_on_drop, Manager, OnDrop, RecvError};
 
 #[macro_use]
 mod util;
@@ -245,6 +248,108 @@ fn value_received_with_manager() {
     });
 }
 
+#[test]
+fn reset() {
+    with_all_capacities!(|capacity| {
+        let (sender, mut receiver) = new(capacity);
+        let _checks: Vec<IsDropped> = (0..capacity)
+            .map(|_| {
+                let (value, check) = DropTest::new();
+                sender.try_send(value).unwrap();
+                check
+            })
+            .collect();
+        drop(sender);
+        let sender = receiver.try_reset().unwrap();
+        let (value, _check) = DropTest::new();
+        sender.try_send(value).unwrap();
+        drop(receiver);
+        drop(sender);
+    });
+}
+
+#[test]
+fn on_drop_call() {
+    with_all_capacities!(|capacity| {
+        let received = Arc::new(AtomicUsize::new(0));
+        let r = received.clone();
+        let on_drop = OnDrop::Call(Box::new(move |value: DropTest| {
+            _ = r.fetch_add(1, Ordering::Relaxed);
+            drop(value);
+        }));
+        let (sender, receiver) = new_with_on_drop(capacity, on_drop);
+        let _checks: Vec<IsDropped> = (0..capacity)
+            .map(|_| {
+                let (value, check) = DropTest::new();
+                sender.try_send(value).unwrap();
+                check
+            })
+            .collect();
+        drop(receiver);
+        assert_eq!(received.load(Ordering::Relaxed), capacity);
+        drop(sender);
+    });
+}
+
+#[test]
+fn on_drop_forward() {
+    with_all_capacities!(|capacity| {
+        let (fallback, mut fallback_receiver) = new(capacity);
+        let (sender, receiver) = new_with_on_drop(capacity, OnDrop::Forward(fallback));
+        let _checks: Vec<IsDropped> = (0..capacity)
+            .map(|_| {
+                let (value, check) = DropTest::new();
+                sender.try_send(value).unwrap();
+                check
+            })
+            .collect();
+        assert!(fallback_receiver.is_connected());
+        drop(receiver);
+        // Sender to the fallback channel should be dropped with the receiver.
+        assert!(!fallback_receiver.is_connected());
+        for _ in 0..capacity {
+            drop(fallback_receiver.try_recv().unwrap());
+        }
+        drop(sender);
+    });
+}
+
+#[test]
+fn on_drop_forward_full() {
+    with_all_capacities!(|capacity| {
+        let (fallback, fallback_receiver) = new(1);
+        let (sender, receiver) = new_with_on_drop(capacity, OnDrop::Forward(fallback));
+        let _checks: Vec<IsDropped> = (0..capacity)
+            .map(|_| {
+                let (value, check) = DropTest::new();
+                sender.try_send(value).unwrap();
+                check
+            })
+            .collect();
+        // Messages that don't fit should be dropped.
+        drop(receiver);
+        drop(fallback_receiver);
+        drop(sender);
+    });
+}
+
+#[test]
+fn on_drop_with_manager() {
+    let (fallback, mut fallback_receiver) = new(8);
+    let (manager, sender, receiver) =
+        Manager::new_channel_with_on_drop(8, OnDrop::Forward(fallback));
+    let (value, _check) = DropTest::new();
+    sender.try_send(value).unwrap();
+    // Messages are kept for the next receiver.
+    drop(receiver);
+    assert_eq!(fallback_receiver.try_recv().unwrap_err(), RecvError::Empty);
+    let receiver = manager.new_receiver().unwrap();
+    drop(receiver);
+    drop(manager);
+    drop(fallback_receiver.try_recv().unwrap());
+    drop(sender);
+}
+
 mod threaded {
     use std::cmp::min;
     use std::thread;
@@ -0,0 +1,241 @@
+//! Tests for the duplex channel.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{self, Poll};
+
+use heph_inbox::duplex::{
+    new_duplex, PendingResponse, RequestError, Requests, Responder, Responses,
+};
+use heph_inbox::{oneshot, RecvError, SendError};
+
+#[macro_use]
+mod util;
+
+use util::{assert_send, assert_sync, assert_unpin, new_count_waker};
+
+#[test]
+fn requests_is_send() {
+    assert_send::<Requests<(), ()>>();
+}
+
+#[test]
+fn requests_is_sync() {
+    assert_sync::<Requests<(), ()>>();
+}
+
+#[test]
+fn responses_is_send() {
+    assert_send::<Responses<(), ()>>();
+}
+
+#[test]
+fn responder_is_send() {
+    assert_send::<Responder<()>>();
+}
+
+#[test]
+fn pending_response_is_send() {
+    assert_send::<PendingResponse<()>>();
+}
+
+#[test]
+fn pending_response_is_unpin() {
+    assert_unpin::<PendingResponse<()>>();
+}
+
+#[test]
+fn try_request_respond() {
+    let (requests, mut responses) = new_duplex::<usize, String>(2);
+    assert!(requests.sends_to(&responses));
+
+    let mut response = requests.try_request(1).unwrap();
+    assert_eq!(response.try_recv(), Err(oneshot::RecvError::NoValue));
+
+    let (request, responder) = responses.try_recv().unwrap();
+    assert_eq!(request, 1);
+    assert_eq!(responder.id(), response.id());
+    assert!(responder.is_connected());
+    responder.respond("one".to_owned()).unwrap();
+    assert_eq!(response.try_recv(), Ok("one".to_owned()));
+    assert_eq!(responses.try_recv().unwrap_err(), RecvError::Empty);
+}
+
+#[test]
+fn responses_are_correlated() {
+    let (requests, mut responses) = new_duplex::<usize, usize>(4);
+    let requests2 = requests.clone();
+
+    let mut response1 = requests.try_request(1).unwrap();
+    let mut response2 = requests2.try_request(2).unwrap();
+    let mut response3 = responses.new_requests().try_request(3).unwrap();
+    assert_ne!(response1.id(), response2.id());
+    assert_ne!(response2.id(), response3.id());
+
+    let mut responders = Vec::new();
+    while let Ok(request) = responses.try_recv() {
+        responders.push(request);
+    }
+    assert_eq!(responders.len(), 3);
+    // Respond in reverse order.
+    for (request, responder) in responders.into_iter().rev() {
+        responder.respond(request * 10).unwrap();
+    }
+
+    assert_eq!(response1.try_recv(), Ok(10));
+    assert_eq!(response2.try_recv(), Ok(20));
+    assert_eq!(response3.try_recv(), Ok(30));
+}
+
+#[test]
+fn try_request_full() {
+    let (requests, _responses) = new_duplex::<usize, ()>(1);
+    let _response = requests.try_request(1).unwrap();
+    assert_eq!(requests.try_request(2).unwrap_err(), SendError::Full(2));
+}
+
+#[test]
+fn try_request_disconnected() {
+    let (requests, responses) = new_duplex::<usize, ()>(1);
+    drop(responses);
+    assert!(!requests.is_connected());
+    assert_eq!(
+        requests.try_request(1).unwrap_err(),
+        SendError::Disconnected(1)
+    );
+}
+
+#[test]
+fn responses_disconnected() {
+    let (requests, mut responses) = new_duplex::<(), ()>(1);
+    assert!(responses.is_connected());
+    drop(requests);
+    assert!(!responses.is_connected());
+    assert_eq!(responses.try_recv().unwrap_err(), RecvError::Disconnected);
+}
+
+#[test]
+fn responder_dropped() {
+    let (requests, mut responses) = new_duplex::<(), ()>(1);
+    let mut response = requests.try_request(()).unwrap();
+    let (_, responder) = responses.try_recv().unwrap();
+    drop(responder);
+    assert_eq!(response.try_recv(), Err(oneshot::RecvError::Disconnected));
+}
+
+#[test]
+fn pending_response_dropped() {
+    let (requests, mut responses) = new_duplex::<(), usize>(1);
+    let response = requests.try_request(()).unwrap();
+    drop(response);
+    let (_, responder) = responses.try_recv().unwrap();
+    assert!(!responder.is_connected());
+    assert_eq!(responder.respond(1), Err(1));
+}
+
+#[test]
+fn request_future() {
+    let (waker, count) = new_count_waker();
+    let mut ctx = task::Context::from_waker(&waker);
+
+    let (requests, mut responses) = new_duplex::<usize, usize>(1);
+    let mut request = requests.request(1);
+    assert_eq!(Pin::new(&mut request).poll(&mut ctx), Poll::Pending);
+
+    let mut recv = responses.recv();
+    let (value, responder) = match Pin::new(&mut recv).poll(&mut ctx) {
+        Poll::Ready(Some(request)) => request,
+        Poll::Ready(None) | Poll::Pending => panic!("expected a request"),
+    };
+    assert_eq!(value, 1);
+    responder.respond(2).unwrap();
+    assert_eq!(count, 1);
+    assert_eq!(Pin::new(&mut request).poll(&mut ctx), Poll::Ready(Ok(2)));
+}
+
+#[test]
+fn request_future_waits_for_capacity() {
+    let (waker, count) = new_count_waker();
+    let mut ctx = task::Context::from_waker(&waker);
+
+    let (requests, mut responses) = new_duplex::<usize, usize>(1);
+    let _response = requests.try_request(1).unwrap();
+    let mut request = requests.request(2);
+    assert_eq!(Pin::new(&mut request).poll(&mut ctx), Poll::Pending);
+
+    let (_, responder1) = responses.try_recv().unwrap();
+    assert_eq!(count, 1);
+    assert_eq!(Pin::new(&mut request).poll(&mut ctx), Poll::Pending);
+    let (value, responder2) = responses.try_recv().unwrap();
+    assert_eq!(value, 2);
+    assert_ne!(responder1.id(), responder2.id());
+    responder2.respond(20).unwrap();
+    assert_eq!(Pin::new(&mut request).poll(&mut ctx), Poll::Ready(Ok(20)));
+}
+
+#[test]
+fn request_future_no_response() {
+    let (waker, _) = new_count_waker();
+    let mut ctx = task::Context::from_waker(&waker);
+
+    let (requests, mut responses) = new_duplex::<usize, usize>(1);
+    let mut request = requests.request(1);
+    assert_eq!(Pin::new(&mut request).poll(&mut ctx), Poll::Pending);
+    drop(responses.try_recv().unwrap());
+    assert_eq!(
+        Pin::new(&mut request).poll(&mut ctx),
+        Poll::Ready(Err(RequestError::NoResponse))
+    );
+}
+
+#[test]
+fn request_future_disconnected() {
+    let (waker, _) = new_count_waker();
+    let mut ctx = task::Context::from_waker(&waker);
+
+    let (requests, responses) = new_duplex::<usize, usize>(1);
+    drop(responses);
+    let mut request = requests.request(1);
+    assert_eq!(
+        Pin::new(&mut request).poll(&mut ctx),
+        Poll::Ready(Err(RequestError::Disconnected(1)))
+    );
+}
+
+#[test]
+fn recv_future_disconnected() {
+    let (waker, _) = new_count_waker();
+    let mut ctx = task::Context::from_waker(&waker);
+
+    let (requests, mut responses) = new_duplex::<usize, usize>(1);
+    let mut recv = responses.recv();
+    assert!(Pin::new(&mut recv).poll(&mut ctx).is_pending());
+    drop(requests);
+    assert!(matches!(
+        Pin::new(&mut recv).poll(&mut ctx),
+        Poll::Ready(None)
+    ));
+}
+
+#[test]
+fn pending_response_future() {
+    let (waker, count) = new_count_waker();
+    let mut ctx = task::Context::from_waker(&waker);
+
+    let (requests, mut responses) = new_duplex::<(), usize>(1);
+    let mut response = requests.try_request(()).unwrap();
+    assert_eq!(Pin::new(&mut response).poll(&mut ctx), Poll::Pending);
+    let (_, responder) = responses.try_recv().unwrap();
+    responder.respond(1).unwrap();
+    assert_eq!(count, 1);
+    assert_eq!(Pin::new(&mut response).poll(&mut ctx), Poll::Ready(Some(1)));
+}
+
+#[test]
+fn request_error_display() {
+    assert_eq!(
+        RequestError::Disconnected(()).to_string(),
+        "responder is disconnected"
+    );
+    assert_eq!(RequestError::<()>::NoResponse.to_string(), "no response");
+}
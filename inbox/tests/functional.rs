@@ -1,13 +1,18 @@
 //! Functional tests.
 
+use std::marker::PhantomPinned;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+
 use heph_inbox::{
-    self as inbox, new, Manager, Receiver, RecvError, SendError, SendValue, Sender, MAX_CAP,
+    self as inbox, new, DisconnectReason, Manager, Receiver, RecvError, SendError, SendErrorKind,
+    SendValue, Sender, MAX_CAP,
 };
 
 #[macro_use]
 mod util;
 
-use util::{assert_send, assert_sync};
+use util::{assert_send, assert_sync, assert_unpin};
 
 #[test]
 fn sender_is_send() {
@@ -49,6 +54,12 @@ fn send_value_is_sync() {
     assert_sync::<SendValue<'_, ()>>();
 }
 
+#[test]
+fn send_value_is_unpin() {
+    assert_unpin::<SendValue<'_, ()>>();
+    assert_unpin::<SendValue<'_, PhantomPinned>>();
+}
+
 #[test]
 #[should_panic = "inbox channel capacity must be between 1 and 29"]
 fn capacity_of_zero_should_panic() {
@@ -94,6 +105,27 @@ fn identifiers() {
     });
 }
 
+#[test]
+fn identifiers_are_not_reused() {
+    with_all_capacities!(|capacity| {
+        let (sender, receiver) = new::<()>(capacity);
+        let id = sender.id();
+        drop(sender);
+        drop(receiver);
+
+        let (sender, _receiver) = new::<()>(capacity);
+        assert_ne!(sender.id(), id);
+    });
+}
+
+#[test]
+fn identifier_in_debug_output() {
+    let (sender, receiver) = inbox::new_small::<()>();
+    let id = sender.id().to_string();
+    assert!(format!("{sender:?}").contains(&format!("id: {id}")));
+    assert!(format!("{receiver:?}").contains(&format!("id: {id}")));
+}
+
 #[test]
 fn sending_and_receiving_value() {
     with_all_capacities!(|capacity| {
@@ -149,6 +181,72 @@ fn peeking_from_disconnected_channel() {
     });
 }
 
+#[test]
+fn receiving_matching_value() {
+    with_all_capacities!(|capacity| {
+        if capacity < 3 {
+            // Requires us to send a minimum of three values.
+            continue;
+        }
+
+        let (sender, mut receiver) = new::<usize>(capacity);
+        sender.try_send(1).unwrap();
+        sender.try_send(2).unwrap();
+        sender.try_send(3).unwrap();
+        assert_eq!(receiver.try_recv_matching(|v| *v == 2).unwrap(), 2);
+        assert_eq!(
+            receiver.try_recv_matching(|v| *v == 2).unwrap_err(),
+            RecvError::Empty
+        );
+        // Non-matching values are left in the channel.
+        let mut values = vec![receiver.try_recv().unwrap(), receiver.try_recv().unwrap()];
+        values.sort_unstable();
+        assert_eq!(values, [1, 3]);
+        assert_eq!(receiver.try_recv().unwrap_err(), RecvError::Empty);
+    });
+}
+
+#[test]
+fn receiving_matching_value_from_empty_channel() {
+    with_all_capacities!(|capacity| {
+        let (_sender, mut receiver) = new::<usize>(capacity);
+        assert_eq!(
+            receiver.try_recv_matching(|_| true).unwrap_err(),
+            RecvError::Empty
+        );
+    });
+}
+
+#[test]
+fn receiving_matching_value_from_disconnected_channel() {
+    with_all_capacities!(|capacity| {
+        let (sender, mut receiver) = new::<usize>(capacity);
+        sender.try_send(1).unwrap();
+        drop(sender);
+        // No matching value can arrive any more.
+        assert_eq!(
+            receiver.try_recv_matching(|v| *v == 2).unwrap_err(),
+            RecvError::Disconnected
+        );
+        assert_eq!(receiver.try_recv_matching(|v| *v == 1).unwrap(), 1);
+    });
+}
+
+#[test]
+fn receiving_matching_value_full_channel() {
+    with_all_capacities!(|capacity| {
+        let (sender, mut receiver) = new::<usize>(capacity);
+        for value in 0..capacity {
+            sender.try_send(value).unwrap();
+        }
+        let last = capacity - 1;
+        assert_eq!(receiver.try_recv_matching(|v| *v == last).unwrap(), last);
+        // Frees a slot.
+        sender.try_send(100).unwrap();
+        assert_eq!(receiver.try_recv_matching(|v| *v == 100).unwrap(), 100);
+    });
+}
+
 #[test]
 fn sending_into_full_channel() {
     with_all_capacities!(|capacity| {
@@ -164,6 +262,122 @@ fn sending_into_full_channel() {
     });
 }
 
+#[test]
+fn sending_with() {
+    with_all_capacities!(|capacity| {
+        let (sender, mut receiver) = new::<usize>(capacity);
+        for value in 0..capacity {
+            assert!(sender.try_send_with(|| value).is_ok());
+        }
+        // Not called if the channel is full.
+        let Err(err) = sender.try_send_with(|| -> usize { panic!("called create") }) else {
+            panic!("unexpected send");
+        };
+        assert_eq!(err.kind(), SendErrorKind::Full);
+        for value in 0..capacity {
+            assert_eq!(receiver.try_recv().unwrap(), value);
+        }
+    });
+}
+
+#[test]
+fn sending_with_panic_releases_slot() {
+    with_all_capacities!(|capacity| {
+        let (sender, mut receiver) = new::<usize>(capacity);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            sender.try_send_with(|| -> usize { panic!("oops") })
+        }));
+        assert!(result.is_err());
+
+        // The slot should be available again.
+        for value in 0..capacity {
+            sender.try_send(value).unwrap();
+        }
+        assert_eq!(sender.try_send(0), Err(SendError::Full(0)));
+        for value in 0..capacity {
+            assert_eq!(receiver.try_recv().unwrap(), value);
+        }
+    });
+}
+
+#[test]
+fn sending_priority_values() {
+    with_all_capacities!(|capacity| {
+        let (sender, mut receiver) = new::<usize>(capacity);
+        let half = capacity / 2;
+        for value in 0..half {
+            sender.try_send(value).unwrap();
+        }
+        for value in half..capacity {
+            sender.try_send_priority(value).unwrap();
+        }
+        assert_eq!(
+            sender.try_send_priority(capacity),
+            Err(SendError::Full(capacity))
+        );
+
+        // Priority values are received first.
+        for want in (half..capacity).chain(0..half) {
+            assert_eq!(receiver.try_peek(), Ok(&want));
+            assert_eq!(receiver.try_recv(), Ok(want));
+        }
+        assert_eq!(receiver.try_recv(), Err(RecvError::Empty));
+    });
+}
+
+#[test]
+fn priority_mark_removed_after_receiving() {
+    let (sender, mut receiver) = new::<usize>(3);
+    sender.try_send(0).unwrap();
+    sender.try_send_priority(1).unwrap();
+    assert_eq!(receiver.try_recv(), Ok(1));
+
+    // Reuses the slot of the priority value, but is a normal value.
+    sender.try_send(2).unwrap();
+    assert_eq!(receiver.try_recv(), Ok(0));
+    assert_eq!(receiver.try_recv(), Ok(2));
+    assert_eq!(receiver.try_recv(), Err(RecvError::Empty));
+}
+
+#[test]
+fn sender_is_full() {
+    with_all_capacities!(|capacity| {
+        let (sender, mut receiver) = new::<usize>(capacity);
+        for value in 0..capacity {
+            assert!(!sender.is_full());
+            sender.try_send(value).unwrap();
+        }
+        assert!(sender.is_full());
+        _ = receiver.try_recv().unwrap();
+        assert!(!sender.is_full());
+    });
+}
+
+#[test]
+fn send_error_kind() {
+    /// Not `Debug`.
+    struct NotDebug(usize);
+
+    let (sender, receiver) = new::<NotDebug>(1);
+    sender
+        .try_send(NotDebug(1))
+        .map_err(SendErrorKind::from)
+        .unwrap();
+
+    let err = sender.try_send(NotDebug(2)).unwrap_err();
+    assert_eq!(err.kind(), SendErrorKind::Full);
+    assert_eq!(err.to_string(), "channel is full");
+    assert_eq!(err.into_inner().0, 2);
+
+    drop(receiver);
+    let err = sender.try_send(NotDebug(3)).unwrap_err();
+    assert_eq!(err.kind(), SendErrorKind::Disconnected);
+    assert_eq!(err.kind().to_string(), "receiver is disconnected");
+    let kind: SendErrorKind = err.into();
+    assert_eq!(kind, SendErrorKind::Disconnected);
+}
+
 #[test]
 fn send_len_values_send_then_recv() {
     with_all_capacities!(|capacity| {
@@ -340,6 +554,71 @@ fn receiver_is_connected() {
     });
 }
 
+#[test]
+fn receiver_disconnect_reason() {
+    with_all_capacities!(|capacity| {
+        let (sender, receiver) = new::<usize>(capacity);
+        let sender2 = sender.clone();
+        assert_eq!(receiver.disconnect_reason(), None);
+        drop(sender);
+        assert_eq!(receiver.disconnect_reason(), None);
+        drop(sender2);
+        assert_eq!(
+            receiver.disconnect_reason(),
+            Some(DisconnectReason::Finished)
+        );
+    });
+}
+
+#[test]
+fn receiver_disconnect_reason_sender_panicked() {
+    let (sender, mut receiver) = new::<usize>(2);
+    let res = panic::catch_unwind(AssertUnwindSafe(move || {
+        sender.try_send(1).unwrap();
+        panic!("oops");
+    }));
+    assert!(res.is_err());
+    assert_eq!(receiver.try_recv(), Ok(1));
+    assert_eq!(receiver.try_recv(), Err(RecvError::Disconnected));
+    assert_eq!(
+        receiver.disconnect_reason(),
+        Some(DisconnectReason::SenderPanicked)
+    );
+}
+
+#[test]
+fn receiver_disconnect_reason_manager_dropped() {
+    let (manager, sender, receiver) = Manager::<usize>::new_small_channel();
+    drop(sender);
+    assert_eq!(
+        receiver.disconnect_reason(),
+        Some(DisconnectReason::Finished)
+    );
+
+    // Manager can create a new sender.
+    let sender = manager.new_sender();
+    assert_eq!(receiver.disconnect_reason(), None);
+    drop(sender);
+
+    drop(manager);
+    assert_eq!(
+        receiver.disconnect_reason(),
+        Some(DisconnectReason::ManagerDropped)
+    );
+}
+
+#[test]
+fn receiver_disconnect_reason_manager_dropped_before_sender() {
+    let (manager, sender, receiver) = Manager::<usize>::new_small_channel();
+    drop(manager);
+    assert_eq!(receiver.disconnect_reason(), None);
+    drop(sender);
+    assert_eq!(
+        receiver.disconnect_reason(),
+        Some(DisconnectReason::Finished)
+    );
+}
+
 #[test]
 fn same_channel() {
     with_all_capacities!(|capacity| {
@@ -409,15 +688,123 @@ fn receiver_new_sender() {
     });
 }
 
+#[test]
+fn receiver_try_reset() {
+    with_all_capacities!(|capacity| {
+        let (sender, mut receiver) = new::<usize>(capacity);
+        let id = receiver.id();
+
+        // Sender still connected.
+        assert!(receiver.try_reset().is_none());
+        sender.try_send(123).unwrap();
+        drop(sender);
+
+        let sender = receiver.try_reset().unwrap();
+        assert!(sender.sends_to(&receiver));
+        assert_ne!(receiver.id(), id);
+        assert_eq!(sender.id(), receiver.id());
+        assert!(sender.is_connected());
+        assert!(receiver.is_connected());
+        // Value send before the reset should be dropped.
+        assert_eq!(receiver.try_recv(), Err(RecvError::Empty));
+
+        for value in 0..capacity {
+            sender.try_send(value).unwrap();
+        }
+        assert_eq!(sender.try_send(capacity), Err(SendError::Full(capacity)));
+        for _ in 0..capacity {
+            assert!(receiver.try_recv().is_ok());
+        }
+
+        drop(sender);
+        assert_eq!(receiver.try_recv(), Err(RecvError::Disconnected));
+        assert_eq!(
+            receiver.disconnect_reason(),
+            Some(DisconnectReason::Finished)
+        );
+    });
+}
+
+#[test]
+fn closing_channel() {
+    with_all_capacities!(|capacity| {
+        let (sender, mut receiver) = new::<usize>(capacity);
+        let sender2 = sender.clone();
+        for value in 0..capacity {
+            sender.try_send(value).unwrap();
+        }
+        assert!(!receiver.is_closed());
+        assert!(sender2.close());
+        assert!(!sender.close());
+        assert!(receiver.is_closed());
+        assert!(!receiver.is_connected());
+
+        // Can't send any more values.
+        assert_eq!(
+            sender.try_send(capacity),
+            Err(SendError::Disconnected(capacity))
+        );
+        assert_eq!(
+            sender2.try_send_priority(capacity),
+            Err(SendError::Disconnected(capacity))
+        );
+
+        // But the values send before closing are still received.
+        for want in 0..capacity {
+            assert_eq!(receiver.try_recv(), Ok(want));
+        }
+        assert_eq!(receiver.try_recv(), Err(RecvError::Disconnected));
+        assert_eq!(receiver.disconnect_reason(), Some(DisconnectReason::Closed));
+    });
+}
+
+#[test]
+fn closing_channel_with_manager() {
+    let (manager, sender, receiver) = Manager::<usize>::new_small_channel();
+    assert!(sender.close());
+    drop(sender);
+
+    // Closing is permanent, new senders can't send either.
+    let sender = manager.new_sender();
+    assert_eq!(sender.try_send(1), Err(SendError::Disconnected(1)));
+    drop(sender);
+    drop(receiver);
+    let mut receiver = manager.new_receiver().unwrap();
+    assert!(receiver.is_closed());
+    assert_eq!(receiver.try_recv(), Err(RecvError::Disconnected));
+}
+
+#[test]
+fn receiver_try_reset_closed_channel() {
+    let (sender, mut receiver) = new::<usize>(2);
+    assert!(sender.close());
+    drop(sender);
+
+    let sender = receiver.try_reset().unwrap();
+    assert!(!receiver.is_closed());
+    sender.try_send(1).unwrap();
+    assert_eq!(receiver.try_recv(), Ok(1));
+}
+
+#[test]
+fn receiver_try_reset_with_manager() {
+    let (manager, sender, mut receiver) = Manager::<usize>::new_small_channel();
+    drop(sender);
+    assert!(receiver.try_reset().is_none());
+    drop(manager);
+    assert!(receiver.try_reset().is_some());
+}
+
 mod future {
     //! Tests for the `Future` implementations.
 
     use std::cmp::min;
     use std::future::Future;
+    use std::marker::PhantomPinned;
     use std::pin::Pin;
     use std::task::{self, Poll};
 
-    use heph_inbox::{self as inbox, new};
+    use heph_inbox::{self as inbox, new, Manager};
 
     use crate::util::new_count_waker;
 
@@ -476,6 +863,84 @@ mod future {
         });
     }
 
+    #[test]
+    fn send_value_channel_closed() {
+        with_all_capacities!(|capacity| {
+            let (sender, _receiver) = new::<usize>(capacity);
+            // Fill the channel.
+            for value in 0..capacity {
+                sender.try_send(value).unwrap();
+            }
+
+            let (waker, count) = new_count_waker();
+            let mut ctx = task::Context::from_waker(&waker);
+
+            let future = sender.send(capacity);
+            pin_stack!(future);
+
+            // Channel should be full.
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Pending);
+            assert_eq!(count, 0);
+
+            // Closing the channel should wake the sender.
+            assert!(sender.close());
+            assert_eq!(count, 1);
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(Err(capacity)));
+        });
+    }
+
+    #[test]
+    fn send_priority_value() {
+        with_all_capacities!(|capacity| {
+            let (sender, mut receiver) = new::<usize>(capacity);
+            // Fill the channel.
+            for value in 0..capacity {
+                sender.try_send(value).unwrap();
+            }
+
+            let (waker, count) = new_count_waker();
+            let mut ctx = task::Context::from_waker(&waker);
+
+            let future = sender.send_priority(capacity);
+            pin_stack!(future);
+
+            // Channel should be full.
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Pending);
+            assert_eq!(receiver.try_recv(), Ok(0));
+            assert_eq!(count, 1);
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(Ok(())));
+
+            // Priority value is received first.
+            assert_eq!(receiver.try_recv(), Ok(capacity));
+            for want in 1..capacity {
+                assert_eq!(receiver.try_recv(), Ok(want));
+            }
+        });
+    }
+
+    #[test]
+    fn send_value_not_unpin() {
+        /// Message that can't be moved once pinned.
+        #[derive(Debug, PartialEq)]
+        struct NotUnpin(usize, PhantomPinned);
+
+        let (sender, mut receiver) = new::<NotUnpin>(1);
+        sender.try_send(NotUnpin(0, PhantomPinned)).unwrap();
+
+        let (waker, count) = new_count_waker();
+        let mut ctx = task::Context::from_waker(&waker);
+
+        // `SendValue` should be `Unpin`, even if the message isn't.
+        let mut future = sender.send(NotUnpin(1, PhantomPinned));
+        assert_eq!(Pin::new(&mut future).poll(&mut ctx), Poll::Pending);
+        assert_eq!(count, 0);
+
+        assert_eq!(receiver.try_recv(), Ok(NotUnpin(0, PhantomPinned)));
+        assert_eq!(count, 1);
+        assert_eq!(Pin::new(&mut future).poll(&mut ctx), Poll::Ready(Ok(())));
+        assert_eq!(receiver.try_recv(), Ok(NotUnpin(1, PhantomPinned)));
+    }
+
     #[test]
     fn send_many_values() {
         with_all_capacities!(|capacity| {
@@ -779,6 +1244,27 @@ mod future {
         });
     }
 
+    #[test]
+    fn recv_value_channel_closed() {
+        with_all_capacities!(|capacity| {
+            let (waker, count) = new_count_waker();
+            let (sender, mut receiver) = new::<usize>(capacity);
+
+            let mut ctx = task::Context::from_waker(&waker);
+
+            let future = receiver.recv();
+            pin_stack!(future);
+
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Pending);
+
+            // Closing the channel should notify the receiver.
+            assert!(sender.close());
+            assert_eq!(count, 1);
+
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(None));
+        });
+    }
+
     #[test]
     fn recv_value_all_senders_disconnected_not_empty() {
         with_all_capacities!(|capacity| {
@@ -885,6 +1371,56 @@ mod future {
         });
     }
 
+    #[test]
+    fn recv_matching() {
+        with_all_capacities!(|capacity| {
+            if capacity < 2 {
+                // Requires us to send a minimum of two values.
+                continue;
+            }
+
+            let (waker, count) = new_count_waker();
+            let (sender, mut receiver) = new::<usize>(capacity);
+
+            let mut ctx = task::Context::from_waker(&waker);
+
+            {
+                let future = receiver.recv_matching(|v| *v == 20);
+                pin_stack!(future);
+
+                assert_eq!(future.as_mut().poll(&mut ctx), Poll::Pending);
+
+                sender.try_send(10).unwrap();
+                assert_eq!(count, 1);
+                assert_eq!(future.as_mut().poll(&mut ctx), Poll::Pending);
+
+                sender.try_send(20).unwrap();
+                assert_eq!(count, 2);
+                assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(Some(20)));
+            }
+
+            // Non-matching value is left in the channel.
+            assert_eq!(receiver.try_recv(), Ok(10));
+        });
+    }
+
+    #[test]
+    fn recv_matching_all_senders_disconnected() {
+        with_all_capacities!(|capacity| {
+            let (waker, _) = new_count_waker();
+            let (sender, mut receiver) = new::<usize>(capacity);
+
+            let mut ctx = task::Context::from_waker(&waker);
+
+            sender.try_send(10).unwrap();
+            drop(sender);
+
+            let future = receiver.recv_matching(|v| *v == 20);
+            pin_stack!(future);
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(None));
+        });
+    }
+
     #[test]
     fn peek_value() {
         with_all_capacities!(|capacity| {
@@ -1166,6 +1702,188 @@ mod future {
             assert_eq!(count, 0);
         });
     }
+
+    #[test]
+    fn sender_closed() {
+        with_all_capacities!(|capacity| {
+            let (sender, receiver) = new::<usize>(capacity);
+
+            let (waker, count) = new_count_waker();
+            let mut ctx = task::Context::from_waker(&waker);
+
+            let future = sender.closed();
+            pin_stack!(future);
+
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Pending);
+            assert_eq!(count, 0);
+
+            drop(receiver);
+            assert_eq!(count, 1);
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(()));
+        });
+    }
+
+    #[test]
+    fn sender_closed_with_manager() {
+        let (manager, sender, receiver) = Manager::<usize>::new_small_channel();
+
+        let (waker, count) = new_count_waker();
+        let mut ctx = task::Context::from_waker(&waker);
+
+        let future = sender.closed();
+        pin_stack!(future);
+
+        assert_eq!(future.as_mut().poll(&mut ctx), Poll::Pending);
+        drop(receiver);
+        // Manager can still create a new receiver.
+        assert_eq!(count, 0);
+        assert_eq!(future.as_mut().poll(&mut ctx), Poll::Pending);
+
+        drop(manager);
+        assert_eq!(count, 1);
+        assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(()));
+    }
+
+    #[test]
+    fn receiver_drop_wakes_all_waiting_senders() {
+        let (sender, receiver) = new::<usize>(1);
+        sender.try_send(0).unwrap();
+
+        let (waker, count) = new_count_waker();
+        let mut ctx = task::Context::from_waker(&waker);
+
+        // More senders waiting than values in the channel.
+        let mut futures = (1..4)
+            .map(|value| Box::pin(sender.send(value)))
+            .collect::<Vec<_>>();
+        for future in &mut futures {
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Pending);
+        }
+
+        drop(receiver);
+        assert_eq!(count, 3);
+        for (value, future) in (1..4).zip(&mut futures) {
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(Err(value)));
+        }
+    }
+
+    #[test]
+    fn sender_watermark() {
+        with_all_capacities!(|capacity| {
+            let (sender, mut receiver) = new::<usize>(capacity);
+            for value in 0..capacity {
+                sender.try_send(value).unwrap();
+            }
+
+            let (waker, count) = new_count_waker();
+            let mut ctx = task::Context::from_waker(&waker);
+
+            let watermark = (capacity / 2).max(1);
+            let future = sender.watermark(watermark);
+            pin_stack!(future);
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Pending);
+
+            // Still at or above the watermark.
+            for _ in watermark..capacity {
+                _ = receiver.try_recv().unwrap();
+                assert_eq!(future.as_mut().poll(&mut ctx), Poll::Pending);
+            }
+            assert_eq!(count, 0);
+
+            _ = receiver.try_recv().unwrap();
+            assert_eq!(count, 1);
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(()));
+        });
+    }
+
+    #[test]
+    fn sender_watermark_already_below() {
+        with_all_capacities!(|capacity| {
+            let (sender, _receiver) = new::<usize>(capacity);
+
+            let (waker, count) = new_count_waker();
+            let mut ctx = task::Context::from_waker(&waker);
+
+            let future = sender.watermark(1);
+            pin_stack!(future);
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(()));
+            assert_eq!(count, 0);
+        });
+    }
+
+    #[test]
+    fn sender_watermark_refilled_after_wake_up() {
+        with_all_capacities!(|capacity| {
+            let (sender, mut receiver) = new::<usize>(capacity);
+            for value in 0..capacity {
+                sender.try_send(value).unwrap();
+            }
+
+            let (waker, count) = new_count_waker();
+            let mut ctx = task::Context::from_waker(&waker);
+
+            let future = sender.watermark(capacity);
+            pin_stack!(future);
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Pending);
+
+            _ = receiver.try_recv().unwrap();
+            assert_eq!(count, 1);
+            // Channel is filled up again before the future is polled.
+            sender.try_send(123).unwrap();
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Pending);
+
+            // Should be woken again.
+            _ = receiver.try_recv().unwrap();
+            assert_eq!(count, 2);
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(()));
+        });
+    }
+
+    #[test]
+    fn sender_watermark_disconnected() {
+        with_all_capacities!(|capacity| {
+            let (sender, receiver) = new::<usize>(capacity);
+            for value in 0..capacity {
+                sender.try_send(value).unwrap();
+            }
+
+            let (waker, count) = new_count_waker();
+            let mut ctx = task::Context::from_waker(&waker);
+
+            let future = sender.watermark(1);
+            pin_stack!(future);
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Pending);
+
+            drop(receiver);
+            assert_eq!(count, 1);
+            assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(()));
+        });
+    }
+
+    #[test]
+    fn sender_watermark_no_wakeup_after_drop() {
+        with_all_capacities!(|capacity| {
+            let (sender, mut receiver) = new::<usize>(capacity);
+            for value in 0..capacity {
+                sender.try_send(value).unwrap();
+            }
+
+            let (waker, count) = new_count_waker();
+            let mut ctx = task::Context::from_waker(&waker);
+
+            {
+                // NOTE: putting `future` in a code block to ensure it's dropped.
+                let future = Box::pin(sender.watermark(capacity));
+                pin_stack!(future);
+
+                assert_eq!(future.as_mut().poll(&mut ctx), Poll::Pending);
+                assert_eq!(count, 0);
+            }
+
+            _ = receiver.try_recv().unwrap();
+            assert_eq!(count, 0);
+        });
+    }
 }
 
 mod manager {
@@ -1201,6 +1919,21 @@ mod manager {
         assert_eq!(receiver.try_recv().unwrap(), 456);
     }
 
+    #[test]
+    fn len() {
+        let (manager, sender, mut receiver) = Manager::<usize>::new_channel(3);
+        assert_eq!(manager.len(), 0);
+        assert!(manager.is_empty());
+
+        sender.try_send(123).unwrap();
+        sender.try_send(456).unwrap();
+        assert_eq!(manager.len(), 2);
+        assert!(!manager.is_empty());
+
+        assert_eq!(receiver.try_recv().unwrap(), 123);
+        assert_eq!(manager.len(), 1);
+    }
+
     #[test]
     fn new_receiver_already_exists() {
         let (manager, _sender, _receiver) = Manager::<usize>::new_channel(1);
@@ -1312,3 +2045,87 @@ mod manager {
         assert!(sender2b.sends_to(&receiver2));
     }
 }
+
+#[test]
+fn try_send_ref() {
+    let (sender, mut receiver) = new(1);
+    let value = Arc::new(123);
+
+    sender.try_send_ref(&value).unwrap();
+    assert_eq!(Arc::strong_count(&value), 2);
+    // Shouldn't clone the value if the channel is full.
+    assert!(
+        matches!(sender.try_send_ref(&value), Err(SendError::Full(v)) if Arc::ptr_eq(v, &value))
+    );
+    assert_eq!(Arc::strong_count(&value), 2);
+
+    let got = receiver.try_recv().unwrap();
+    assert!(Arc::ptr_eq(&got, &value));
+    drop(got);
+
+    drop(receiver);
+    assert!(matches!(
+        sender.try_send_ref(&value),
+        Err(SendError::Disconnected(_))
+    ));
+    assert_eq!(Arc::strong_count(&value), 1);
+}
+
+#[test]
+fn try_send_slice() {
+    let (sender1, mut receiver1) = new(1);
+    let (sender2, receiver2) = new(1);
+    let (sender3, mut receiver3) = new(1);
+    sender2.try_send(Arc::new(0)).unwrap(); // Fill the channel.
+    drop(receiver2);
+    let senders = [sender1, sender2, sender3];
+
+    let value = Arc::new(1);
+    assert_eq!(inbox::try_send_slice(&senders, &value), 2);
+    assert_eq!(Arc::strong_count(&value), 3);
+    assert!(Arc::ptr_eq(&receiver1.try_recv().unwrap(), &value));
+    assert!(Arc::ptr_eq(&receiver3.try_recv().unwrap(), &value));
+    assert_eq!(Arc::strong_count(&value), 1);
+}
+
+#[cfg(feature = "test")]
+mod debug_slots {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{self, Poll};
+
+    use heph_inbox::{new, DebugSlots, SlotStatus};
+
+    use crate::util::new_count_waker;
+
+    #[test]
+    fn debug_slots() {
+        let (sender, mut receiver) = new::<usize>(2);
+        assert_eq!(
+            receiver.debug_slots(),
+            DebugSlots {
+                slots: vec![SlotStatus::Empty, SlotStatus::Empty],
+                receiver_position: 0,
+                sender_wakers: 0,
+            }
+        );
+
+        sender.try_send(1).unwrap();
+        sender.try_send(2).unwrap();
+        let debug = receiver.debug_slots();
+        assert_eq!(debug.slots, [SlotStatus::Filled, SlotStatus::Filled]);
+
+        // Sender waiting for a slot.
+        let (waker, _) = new_count_waker();
+        let mut ctx = task::Context::from_waker(&waker);
+        let mut future = pin!(sender.send(3));
+        assert_eq!(future.as_mut().poll(&mut ctx), Poll::Pending);
+        assert_eq!(receiver.debug_slots().sender_wakers, 1);
+
+        assert_eq!(receiver.try_recv(), Ok(1));
+        let debug = receiver.debug_slots();
+        assert_eq!(debug.slots, [SlotStatus::Empty, SlotStatus::Filled]);
+        assert_eq!(debug.receiver_position, 1);
+        assert_eq!(debug.sender_wakers, 0);
+    }
+}
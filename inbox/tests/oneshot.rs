@@ -4,7 +4,7 @@
 mod util;
 
 mod functional {
-    use heph_inbox::oneshot::{new_oneshot, Receiver, RecvError, Sender};
+    use heph_inbox::oneshot::{new_oneshot, Receiver, RecvError, Sender, SharedReceiver};
 
     use crate::util::{assert_send, assert_sync, new_count_waker};
 
@@ -28,6 +28,16 @@ mod functional {
         assert_sync::<Receiver<()>>();
     }
 
+    #[test]
+    fn shared_receiver_is_send() {
+        assert_send::<SharedReceiver<()>>();
+    }
+
+    #[test]
+    fn shared_receiver_is_sync() {
+        assert_sync::<SharedReceiver<()>>();
+    }
+
     #[test]
     fn single_send_recv() {
         let (sender, mut receiver) = new_oneshot();
@@ -110,7 +120,7 @@ mod future {
     use std::pin::Pin;
     use std::task::{self, Poll};
 
-    use heph_inbox::oneshot::new_oneshot;
+    use heph_inbox::oneshot::{new_oneshot, RecvError};
 
     use crate::util::new_count_waker;
 
@@ -250,6 +260,186 @@ mod future {
         assert_eq!(count, 1);
         assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(None));
     }
+
+    #[test]
+    fn send_and_wait_received() {
+        let (sender, mut receiver) = new_oneshot::<usize>();
+
+        let (waker, count) = new_count_waker();
+        let mut ctx = task::Context::from_waker(&waker);
+
+        let future = sender.send_and_wait(1);
+        pin_stack!(future);
+
+        assert!(future.as_mut().poll(&mut ctx).is_pending());
+        assert_eq!(count, 0);
+
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_eq!(count, 1);
+        assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(Ok(())));
+    }
+
+    #[test]
+    fn send_and_wait_wakes_receiver() {
+        let (sender, mut receiver) = new_oneshot::<usize>();
+
+        let (waker, count) = new_count_waker();
+        let mut ctx = task::Context::from_waker(&waker);
+
+        let future = receiver.recv();
+        pin_stack!(future);
+
+        assert!(future.as_mut().poll(&mut ctx).is_pending());
+        assert_eq!(count, 0);
+
+        let send_future = sender.send_and_wait(1);
+        assert_eq!(count, 1);
+        assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(Some(1)));
+        drop(send_future);
+    }
+
+    #[test]
+    fn send_and_wait_receiver_dropped() {
+        let (sender, receiver) = new_oneshot::<usize>();
+
+        let (waker, count) = new_count_waker();
+        let mut ctx = task::Context::from_waker(&waker);
+
+        let future = sender.send_and_wait(1);
+        pin_stack!(future);
+
+        assert!(future.as_mut().poll(&mut ctx).is_pending());
+        assert_eq!(count, 0);
+
+        drop(receiver);
+        assert_eq!(count, 1);
+        assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(Err(1)));
+    }
+
+    #[test]
+    fn send_and_wait_no_receiver() {
+        let (sender, receiver) = new_oneshot::<usize>();
+        drop(receiver);
+
+        let (waker, _count) = new_count_waker();
+        let mut ctx = task::Context::from_waker(&waker);
+
+        let future = sender.send_and_wait(1);
+        pin_stack!(future);
+        assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(Err(1)));
+    }
+
+    #[test]
+    fn send_and_wait_received_before_poll() {
+        let (sender, mut receiver) = new_oneshot::<usize>();
+
+        let (waker, count) = new_count_waker();
+        let mut ctx = task::Context::from_waker(&waker);
+
+        let future = sender.send_and_wait(1);
+        pin_stack!(future);
+
+        assert_eq!(receiver.try_recv(), Ok(1));
+        drop(receiver);
+        assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(Ok(())));
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn send_and_wait_keeps_sender_connected() {
+        let (sender, mut receiver) = new_oneshot::<usize>();
+
+        let future = sender.send_and_wait(1);
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert!(receiver.is_connected());
+        assert!(receiver.try_reset().is_none());
+
+        drop(future);
+        assert!(!receiver.is_connected());
+        assert!(receiver.try_reset().is_some());
+    }
+
+    #[test]
+    fn map() {
+        let (sender, receiver) = new_oneshot::<usize>();
+
+        let (waker, count) = new_count_waker();
+        let mut ctx = task::Context::from_waker(&waker);
+
+        let future = receiver.map(|value| value.to_string());
+        pin_stack!(future);
+
+        assert!(future.as_mut().poll(&mut ctx).is_pending());
+        sender.try_send(1).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(
+            future.as_mut().poll(&mut ctx),
+            Poll::Ready(Some("1".to_owned()))
+        );
+    }
+
+    #[test]
+    fn map_no_sender() {
+        let (sender, receiver) = new_oneshot::<usize>();
+
+        let (waker, _) = new_count_waker();
+        let mut ctx = task::Context::from_waker(&waker);
+
+        let future = receiver.map(|_| -> usize { panic!("unexpected call to map") });
+        pin_stack!(future);
+
+        drop(sender);
+        assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(None));
+    }
+
+    #[test]
+    fn shared_wakes_all_waiters() {
+        let (sender, receiver) = new_oneshot::<usize>();
+        let receiver1 = receiver.shared();
+        let receiver2 = receiver1.clone();
+
+        let (waker1, count1) = new_count_waker();
+        let mut ctx1 = task::Context::from_waker(&waker1);
+        let (waker2, count2) = new_count_waker();
+        let mut ctx2 = task::Context::from_waker(&waker2);
+
+        let future1 = receiver1.recv();
+        pin_stack!(future1);
+        let future2 = receiver2.recv();
+        pin_stack!(future2);
+
+        assert!(future1.as_mut().poll(&mut ctx1).is_pending());
+        assert!(future2.as_mut().poll(&mut ctx2).is_pending());
+        assert_eq!(receiver1.try_recv(), Err(RecvError::NoValue));
+
+        sender.try_send(1).unwrap();
+        assert_eq!(count1, 1);
+        assert_eq!(count2, 1);
+        assert_eq!(future1.as_mut().poll(&mut ctx1), Poll::Ready(Some(1)));
+        assert_eq!(future2.as_mut().poll(&mut ctx2), Poll::Ready(Some(1)));
+        // Value stays available.
+        assert_eq!(receiver1.try_recv(), Ok(1));
+        assert_eq!(receiver2.clone().try_recv(), Ok(1));
+    }
+
+    #[test]
+    fn shared_no_sender() {
+        let (sender, receiver) = new_oneshot::<usize>();
+        let receiver1 = receiver.shared();
+        let receiver2 = receiver1.clone();
+
+        let (waker, count) = new_count_waker();
+        let mut ctx = task::Context::from_waker(&waker);
+
+        let future = receiver1.recv();
+        pin_stack!(future);
+        assert!(future.as_mut().poll(&mut ctx).is_pending());
+
+        drop(sender);
+        assert_eq!(count, 1);
+        assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(None));
+        assert_eq!(receiver2.try_recv(), Err(RecvError::Disconnected));
+    }
 }
 
 mod drop {
@@ -312,6 +502,24 @@ mod drop {
         drop(receiver);
     }
 
+    #[test]
+    fn send_and_wait_not_received() {
+        let (sender, receiver) = new_oneshot();
+        let (value, _check) = DropTest::new();
+        let future = sender.send_and_wait(value);
+        drop(receiver);
+        drop(future);
+    }
+
+    #[test]
+    fn send_and_wait_future_dropped() {
+        let (sender, receiver) = new_oneshot();
+        let (value, _check) = DropTest::new();
+        let future = sender.send_and_wait(value);
+        drop(future);
+        drop(receiver);
+    }
+
     #[test]
     fn send_and_reset_rs() {
         let (sender, mut receiver) = new_oneshot();
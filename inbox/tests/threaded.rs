@@ -1,7 +1,11 @@
 //! Tests using multiple threads.
 
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{self, Poll, Wake};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use heph_inbox::{self as inbox, new, Manager, RecvError, SendError};
 
@@ -99,6 +103,32 @@ fn receive_no_sender() {
     });
 }
 
+#[test]
+#[cfg_attr(miri, ignore)] // Doesn't finish.
+fn receive_closed_channel() {
+    with_all_capacities!(|capacity| {
+        let (sender, mut receiver) = new::<usize>(capacity);
+
+        start_threads!(
+            {
+                expect_send!(sender, 1);
+                assert!(sender.close());
+            },
+            {
+                // Value send before closing must be received.
+                expect_recv!(receiver, 1);
+                r#loop! {
+                    match receiver.try_recv() {
+                        Ok(..) => panic!("unexpected receive of value"),
+                        Err(RecvError::Empty) => {} // Try again.
+                        Err(RecvError::Disconnected) => break,
+                    }
+                }
+            }
+        );
+    });
+}
+
 #[test]
 #[cfg_attr(miri, ignore)] // Doesn't finish.
 fn peek_no_sender() {
@@ -224,3 +254,67 @@ fn receiver_has_manager() {
         }
     );
 }
+
+#[test]
+#[cfg_attr(miri, ignore)] // Doesn't finish.
+fn receive_matching_wake_up() {
+    const N: usize = 1000;
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Waker that unparks the thread.
+    struct ThreadWaker(thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    // Receive the values in pairs, in reverse order, so that the value the
+    // receiver is waiting for is not in the slot at its position. This requires
+    // a capacity of at least two.
+    for capacity in 2..=inbox::MAX_CAP {
+        let (sender, mut receiver) = new::<usize>(capacity);
+
+        start_threads!(
+            {
+                for value in 0..N {
+                    let mut value = value;
+                    r#loop! {
+                        match sender.try_send(value) {
+                            Ok(()) => break,
+                            Err(SendError::Full(v)) => {
+                                value = v;
+                                thread::yield_now();
+                            }
+                            Err(SendError::Disconnected(..)) => panic!("unexpected disconnect"),
+                        }
+                    }
+                }
+            },
+            {
+                let waker = Arc::new(ThreadWaker(thread::current())).into();
+                let mut ctx = task::Context::from_waker(&waker);
+                for expected in (0..N).map(|n| n ^ 1) {
+                    let mut recv = receiver.recv_matching(|value| *value == expected);
+                    loop {
+                        match Pin::new(&mut recv).poll(&mut ctx) {
+                            Poll::Ready(value) => {
+                                assert_eq!(value, Some(expected));
+                                break;
+                            }
+                            Poll::Pending => {
+                                // The sender is never blocked for long, if we
+                                // don't get woken up in time the wake-up was
+                                // lost.
+                                let start = Instant::now();
+                                thread::park_timeout(TIMEOUT);
+                                assert!(start.elapsed() < TIMEOUT, "lost wake-up");
+                            }
+                        }
+                    }
+                }
+            }
+        );
+    }
+}
@@ -10,6 +10,7 @@ use std::thread;
 
 pub fn assert_send<T: Send>() {}
 pub fn assert_sync<T: Sync>() {}
+pub fn assert_unpin<T: Unpin>() {}
 
 /// Number of times the waker was awoken.
 ///
@@ -0,0 +1,244 @@
+//! Tests for the watch channel.
+
+#[macro_use]
+mod util;
+
+mod functional {
+    use heph_inbox::watch::{new_watch, Receiver, RecvError, Sender};
+
+    use crate::util::{assert_send, assert_sync};
+
+    #[test]
+    fn sender_is_send() {
+        assert_send::<Sender<()>>();
+    }
+
+    #[test]
+    fn sender_is_sync() {
+        assert_sync::<Sender<()>>();
+    }
+
+    #[test]
+    fn receiver_is_send() {
+        assert_send::<Receiver<()>>();
+    }
+
+    #[test]
+    fn receiver_is_sync() {
+        assert_sync::<Receiver<()>>();
+    }
+
+    #[test]
+    fn initial_value_is_seen() {
+        let (sender, mut receiver) = new_watch(1);
+        assert!(!receiver.has_changed());
+        assert_eq!(receiver.try_recv(), Err(RecvError::Unchanged));
+        assert_eq!(*receiver.borrow(), 1);
+        assert_eq!(*sender.borrow(), 1);
+    }
+
+    #[test]
+    fn send_recv() {
+        let (sender, mut receiver) = new_watch(1);
+        sender.send(2);
+        assert!(receiver.has_changed());
+        assert_eq!(receiver.try_recv(), Ok(2));
+        assert!(!receiver.has_changed());
+        assert_eq!(receiver.try_recv(), Err(RecvError::Unchanged));
+    }
+
+    #[test]
+    fn only_latest_value() {
+        let (sender, mut receiver) = new_watch(1);
+        sender.send(2);
+        sender.send(3);
+        assert_eq!(receiver.try_recv(), Ok(3));
+        assert_eq!(receiver.try_recv(), Err(RecvError::Unchanged));
+    }
+
+    #[test]
+    fn send_modify() {
+        let (sender, mut receiver) = new_watch(vec![1]);
+        sender.send_modify(|values| values.push(2));
+        assert_eq!(receiver.try_recv(), Ok(vec![1, 2]));
+    }
+
+    #[test]
+    fn borrow_doesnt_mark_seen() {
+        let (sender, mut receiver) = new_watch(1);
+        sender.send(2);
+        assert_eq!(*receiver.borrow(), 2);
+        assert!(receiver.has_changed());
+        assert_eq!(*receiver.borrow_and_update(), 2);
+        assert!(!receiver.has_changed());
+    }
+
+    #[test]
+    fn multiple_receivers() {
+        let (sender, mut receiver1) = new_watch(1);
+        let mut receiver2 = receiver1.clone();
+        sender.send(2);
+        assert_eq!(receiver1.try_recv(), Ok(2));
+        let mut receiver3 = receiver1.clone();
+        assert_eq!(receiver2.try_recv(), Ok(2));
+        assert_eq!(receiver3.try_recv(), Err(RecvError::Unchanged));
+        let mut receiver4 = sender.subscribe();
+        assert_eq!(receiver4.try_recv(), Err(RecvError::Unchanged));
+    }
+
+    #[test]
+    fn receive_no_sender() {
+        let (sender, mut receiver) = new_watch(1);
+        drop(sender);
+        assert!(!receiver.is_connected());
+        assert_eq!(receiver.try_recv(), Err(RecvError::Disconnected));
+        assert_eq!(*receiver.borrow(), 1);
+    }
+
+    #[test]
+    fn receive_after_sender_dropped() {
+        let (sender, mut receiver) = new_watch(1);
+        sender.send(2);
+        drop(sender);
+        assert_eq!(receiver.try_recv(), Ok(2));
+        assert_eq!(receiver.try_recv(), Err(RecvError::Disconnected));
+    }
+
+    #[test]
+    fn sender_is_connected() {
+        let (sender, receiver) = new_watch(1);
+        assert!(sender.is_connected());
+        let receiver2 = receiver.clone();
+        drop(receiver);
+        assert!(sender.is_connected());
+        drop(receiver2);
+        assert!(!sender.is_connected());
+        // Value is still stored.
+        sender.send(2);
+        let mut receiver = sender.subscribe();
+        assert_eq!(*receiver.borrow(), 2);
+        assert_eq!(receiver.try_recv(), Err(RecvError::Unchanged));
+    }
+}
+
+mod future {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{self, Poll};
+
+    use heph_inbox::watch::new_watch;
+
+    use crate::util::new_count_waker;
+
+    #[test]
+    fn sending_wakes_receiver() {
+        let (sender, mut receiver) = new_watch(1);
+
+        let (waker, count) = new_count_waker();
+        let mut ctx = task::Context::from_waker(&waker);
+
+        let mut future = receiver.recv();
+        assert!(Pin::new(&mut future).poll(&mut ctx).is_pending());
+        assert_eq!(count, 0);
+
+        sender.send(2);
+        assert_eq!(count, 1);
+        assert_eq!(Pin::new(&mut future).poll(&mut ctx), Poll::Ready(Some(2)));
+    }
+
+    #[test]
+    fn sending_wakes_all_receivers() {
+        let (sender, mut receiver1) = new_watch(1);
+        let mut receiver2 = receiver1.clone();
+
+        let (waker1, count1) = new_count_waker();
+        let mut ctx1 = task::Context::from_waker(&waker1);
+        let (waker2, count2) = new_count_waker();
+        let mut ctx2 = task::Context::from_waker(&waker2);
+
+        let mut future1 = receiver1.recv();
+        assert!(Pin::new(&mut future1).poll(&mut ctx1).is_pending());
+        let mut future2 = receiver2.recv();
+        assert!(Pin::new(&mut future2).poll(&mut ctx2).is_pending());
+
+        sender.send(2);
+        assert_eq!(count1, 1);
+        assert_eq!(count2, 1);
+        assert_eq!(Pin::new(&mut future1).poll(&mut ctx1), Poll::Ready(Some(2)));
+        assert_eq!(Pin::new(&mut future2).poll(&mut ctx2), Poll::Ready(Some(2)));
+    }
+
+    #[test]
+    fn dropping_sender_wakes_receiver() {
+        let (sender, mut receiver) = new_watch(1);
+
+        let (waker, count) = new_count_waker();
+        let mut ctx = task::Context::from_waker(&waker);
+
+        let mut future = receiver.recv();
+        assert!(Pin::new(&mut future).poll(&mut ctx).is_pending());
+        assert_eq!(count, 0);
+
+        drop(sender);
+        assert_eq!(count, 1);
+        assert_eq!(Pin::new(&mut future).poll(&mut ctx), Poll::Ready(None));
+    }
+
+    #[test]
+    fn dropped_receiver_is_not_woken() {
+        let (sender, mut receiver1) = new_watch(1);
+        let mut receiver2 = receiver1.clone();
+
+        let (waker1, count1) = new_count_waker();
+        let mut ctx1 = task::Context::from_waker(&waker1);
+        let (waker2, count2) = new_count_waker();
+        let mut ctx2 = task::Context::from_waker(&waker2);
+
+        let mut future1 = receiver1.recv();
+        assert!(Pin::new(&mut future1).poll(&mut ctx1).is_pending());
+        let mut future2 = receiver2.recv();
+        assert!(Pin::new(&mut future2).poll(&mut ctx2).is_pending());
+        drop(receiver1);
+
+        sender.send(2);
+        assert_eq!(count1, 0);
+        assert_eq!(count2, 1);
+        assert_eq!(Pin::new(&mut future2).poll(&mut ctx2), Poll::Ready(Some(2)));
+    }
+}
+
+mod threaded {
+    use heph_inbox::watch::{new_watch, RecvError};
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // Doesn't finish.
+    fn receives_latest_value() {
+        const LAST: usize = 100;
+
+        let (sender, mut receiver) = new_watch(0);
+
+        start_threads!(
+            {
+                for value in 1..=LAST {
+                    sender.send(value);
+                }
+            },
+            {
+                let mut last = 0;
+                r#loop! {
+                    match receiver.try_recv() {
+                        Ok(value) => {
+                            assert!(value > last, "received old value");
+                            last = value;
+                            if value == LAST {
+                                break;
+                            }
+                        }
+                        Err(RecvError::Unchanged) => {}
+                        Err(RecvError::Disconnected) => panic!("sender disconnected"),
+                    }
+                }
+            }
+        );
+    }
+}
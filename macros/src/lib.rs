@@ -0,0 +1,573 @@
+//! Procedural macros for Heph.
+//!
+//! This crate shouldn't be used directly, instead enable the `macros` feature
+//! of the `heph` crate and use the macros from the `heph::macros` module.
+//! See that module for the documentation.
+
+#![warn(
+    anonymous_parameters,
+    bare_trait_objects,
+    missing_debug_implementations,
+    missing_docs,
+    rust_2018_idioms,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_results,
+    variant_size_differences
+)]
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Expr, Fields, FnArg, Ident, ImplItem, ItemImpl,
+    ItemStruct, LitStr, ReturnType, Token, Type, Visibility,
+};
+
+/// Derive [`From`] implementations for a message enum.
+///
+/// See `heph::macros::Message` for the documentation.
+#[proc_macro_derive(Message, attributes(message))]
+pub fn derive_message(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match message(&input) {
+        Ok(output) => output.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+fn message(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new(
+            Span::call_site(),
+            "`Message` can only be derived for enums",
+        ));
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut output = TokenStream2::new();
+    for variant in &data.variants {
+        let mut skip = false;
+        for attr in &variant.attrs {
+            if attr.path().is_ident("message") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("skip") {
+                        skip = true;
+                        Ok(())
+                    } else {
+                        Err(meta.error("unknown `message` attribute, expected `skip`"))
+                    }
+                })?;
+            }
+        }
+        if skip {
+            continue;
+        }
+
+        let field = match &variant.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0],
+            _ => {
+                return Err(Error::new_spanned(
+                    variant,
+                    "`Message` requires variants with a single unnamed field, use `#[message(skip)]` to skip this variant",
+                ))
+            }
+        };
+        let ty = &field.ty;
+        let variant = &variant.ident;
+        output.extend(quote! {
+            impl #impl_generics ::std::convert::From<#ty> for #name #ty_generics #where_clause {
+                fn from(msg: #ty) -> #name #ty_generics {
+                    #name::#variant(msg)
+                }
+            }
+        });
+    }
+    Ok(output)
+}
+
+/// Create a supervisor that logs the error and restarts the actor.
+///
+/// See `heph::macros::restart_supervisor` for the documentation.
+#[proc_macro_attribute]
+pub fn restart_supervisor(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut options = SupervisorOptions::default();
+    let parser = syn::meta::parser(|meta| options.parse(meta));
+    parse_macro_input!(args with parser);
+    let input = parse_macro_input!(input as ItemStruct);
+    match supervisor(options, input) {
+        Ok(output) => output.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// Options for the [`restart_supervisor`] attribute.
+#[derive(Default)]
+struct SupervisorOptions {
+    max_restarts: Option<Expr>,
+    max_duration: Option<Expr>,
+    log_extra: Option<LitStr>,
+    log_args: Vec<Expr>,
+}
+
+impl SupervisorOptions {
+    fn parse(&mut self, meta: syn::meta::ParseNestedMeta<'_>) -> Result<(), Error> {
+        if meta.path.is_ident("max_restarts") {
+            self.max_restarts = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("max_duration") {
+            self.max_duration = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("log_extra") {
+            self.log_extra = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("log_args") {
+            let content;
+            _ = syn::parenthesized!(content in meta.input);
+            let args = Punctuated::<Expr, Token![,]>::parse_terminated(&content)?;
+            self.log_args = args.into_iter().collect();
+        } else {
+            return Err(meta.error(
+                "unknown option, expected `max_restarts`, `max_duration`, `log_extra` or `log_args`",
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn supervisor(options: SupervisorOptions, input: ItemStruct) -> Result<TokenStream2, Error> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "restart supervisor can't be generic",
+        ));
+    }
+    let types: Vec<_> = match &input.fields {
+        Fields::Unit => Vec::new(),
+        Fields::Unnamed(fields) => fields.unnamed.iter().map(|f| &f.ty).collect(),
+        Fields::Named(fields) => {
+            return Err(Error::new_spanned(
+                fields,
+                "restart supervisor must be a unit or tuple struct, with the fields being the actor's arguments",
+            ))
+        }
+    };
+    if options.log_extra.is_none() && !options.log_args.is_empty() {
+        return Err(Error::new(
+            Span::call_site(),
+            "`log_args` requires `log_extra` to be set",
+        ));
+    }
+
+    let ItemStruct {
+        attrs, vis, ident, ..
+    } = &input;
+    // Match `NewActor::Argument`: unit, a single type or a tuple.
+    let args_ty = match types.as_slice() {
+        [ty] => quote!(#ty),
+        types => quote!(( #( #types ),* )),
+    };
+    let names: Vec<_> = match types.as_slice() {
+        [_] => vec![format_ident!("arg")],
+        types => (0..types.len()).map(|i| format_ident!("arg{i}")).collect(),
+    };
+    let args_value = match names.as_slice() {
+        [name] => quote!(#name),
+        names => quote!(( #( #names ),* )),
+    };
+
+    let max_restarts = match &options.max_restarts {
+        Some(max_restarts) => quote!(#max_restarts),
+        None => quote!(5),
+    };
+    let max_duration = match &options.max_duration {
+        Some(max_duration) => quote!(#max_duration),
+        None => quote!(::std::time::Duration::from_secs(5)),
+    };
+    let doc = attrs
+        .iter()
+        .all(|attr| !attr.path().is_ident("doc"))
+        .then(|| {
+            quote! {
+                #[doc = ::std::concat!(
+                    "Restart supervisor.\n\n",
+                    "Maximum number of restarts: `", ::std::stringify!(#max_restarts), "`, ",
+                    "within a duration of: `", ::std::stringify!(#max_duration), "`.",
+                )]
+            }
+        });
+    let new_doc = format!("Create a new `{ident}`.");
+
+    let log_extra = options.log_extra.as_ref().map(LitStr::value);
+    let log_extra = log_extra.as_deref().unwrap_or("");
+    let log_args = &options.log_args;
+    // Gives `log_args` access to the arguments.
+    let args_binding = (!log_args.is_empty()).then(|| quote!(let args = &self.args;));
+    let log = |format: &str, values: TokenStream2| {
+        let format = format!("{format}{log_extra}");
+        quote! {
+            #args_binding
+            ::log::warn!(#format, #values #( , #log_args )*);
+        }
+    };
+    let decide_impl = |kind: &str, err: TokenStream2| {
+        let restart_log = log(
+            &format!("{{}} {kind}, restarting it ({{}}/{{}} restarts left): {{}}"),
+            quote!(NA::name(), self.restarts_left, Self::MAX_RESTARTS, #err),
+        );
+        let stop_log = log(
+            &format!("{{}} {kind}, stopping it (no restarts left): {{}}"),
+            quote!(NA::name(), #err),
+        );
+        quote! {
+            let now = ::std::time::Instant::now();
+            let last_restart = self.last_restart.replace(now);
+
+            // If enough time has passed between the last restart and now we
+            // reset the `restarts_left` left counter.
+            if let ::std::option::Option::Some(last_restart) = last_restart {
+                if now - last_restart > Self::MAX_DURATION {
+                    self.restarts_left = Self::MAX_RESTARTS;
+                }
+            }
+
+            if self.restarts_left >= 1 {
+                self.restarts_left -= 1;
+                #restart_log
+                ::heph::SupervisorStrategy::Restart(::std::clone::Clone::clone(&self.args))
+            } else {
+                #stop_log
+                ::heph::SupervisorStrategy::Stop
+            }
+        }
+    };
+    let decide = decide_impl("failed", quote!(err));
+    let decide_on_panic = decide_impl("panicked", quote!(msg));
+    let restart_error_log = log(
+        "{} actor failed to restart, trying again ({}/{} restarts left): {}",
+        quote!(NA::name(), self.restarts_left, Self::MAX_RESTARTS, err),
+    );
+    let restart_error_stop_log = log(
+        "{} actor failed to restart, stopping it (no restarts left): {}",
+        quote!(NA::name(), err),
+    );
+    let second_restart_error_log = log(
+        "{} actor failed to restart a second time, stopping it: {}",
+        quote!(NA::name(), err),
+    );
+
+    Ok(quote! {
+        #doc
+        #( #attrs )*
+        #[derive(::std::fmt::Debug)]
+        #vis struct #ident {
+            /// The number of restarts left.
+            restarts_left: ::std::primitive::usize,
+            /// Time of the last restart.
+            last_restart: ::std::option::Option<::std::time::Instant>,
+            /// Arguments used to restart the actor.
+            args: #args_ty,
+        }
+
+        impl #ident {
+            /// Maximum number of restarts within a [`Self::MAX_DURATION`] time
+            /// period before the actor is stopped.
+            #vis const MAX_RESTARTS: ::std::primitive::usize = #max_restarts;
+
+            /// Maximum duration between errors to be considered of the same
+            /// cause. If `MAX_DURATION` has elapsed between errors the restart
+            /// counter gets reset to [`MAX_RESTARTS`].
+            ///
+            /// [`MAX_RESTARTS`]: Self::MAX_RESTARTS
+            #vis const MAX_DURATION: ::std::time::Duration = #max_duration;
+
+            #[doc = #new_doc]
+            #[allow(dead_code)]
+            #vis const fn new( #( #names: #types ),* ) -> #ident {
+                #ident {
+                    restarts_left: Self::MAX_RESTARTS,
+                    last_restart: ::std::option::Option::None,
+                    args: #args_value,
+                }
+            }
+        }
+
+        impl<NA> ::heph::supervisor::Supervisor<NA> for #ident
+        where
+            NA: ::heph::NewActor<Argument = #args_ty>,
+            NA::Error: ::std::fmt::Display,
+            <NA::Actor as ::heph::Actor>::Error: ::std::fmt::Display,
+        {
+            fn decide(&mut self, err: <NA::Actor as ::heph::Actor>::Error) -> ::heph::SupervisorStrategy<NA::Argument> {
+                #decide
+            }
+
+            fn decide_on_restart_error(&mut self, err: NA::Error) -> ::heph::SupervisorStrategy<NA::Argument> {
+                self.last_restart = ::std::option::Option::Some(::std::time::Instant::now());
+
+                if self.restarts_left >= 1 {
+                    self.restarts_left -= 1;
+                    #restart_error_log
+                    ::heph::SupervisorStrategy::Restart(::std::clone::Clone::clone(&self.args))
+                } else {
+                    #restart_error_stop_log
+                    ::heph::SupervisorStrategy::Stop
+                }
+            }
+
+            fn second_restart_error(&mut self, err: NA::Error) {
+                #second_restart_error_log
+            }
+
+            fn decide_on_panic(&mut self, panic: ::std::boxed::Box<dyn ::std::any::Any + ::std::marker::Send + 'static>) -> ::heph::SupervisorStrategy<NA::Argument> {
+                let msg = ::heph::panic_message(&*panic);
+                #decide_on_panic
+            }
+        }
+
+        impl<NA> ::heph::supervisor::SyncSupervisor<NA> for #ident
+        where
+            NA: ::heph::sync::SyncActor<Argument = #args_ty>,
+            NA::Error: ::std::fmt::Display,
+        {
+            fn decide(&mut self, err: NA::Error) -> ::heph::SupervisorStrategy<NA::Argument> {
+                #decide
+            }
+
+            fn decide_on_panic(&mut self, panic: ::std::boxed::Box<dyn ::std::any::Any + ::std::marker::Send + 'static>) -> ::heph::SupervisorStrategy<NA::Argument> {
+                let msg = ::heph::panic_message(&*panic);
+                #decide_on_panic
+            }
+        }
+    })
+}
+
+/// Generate a message type and receive loop for an actor from its handlers.
+///
+/// See `heph::macros::actor` for the documentation.
+#[proc_macro_attribute]
+pub fn actor(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut options = ActorOptions::default();
+    let parser = syn::meta::parser(|meta| options.parse(meta));
+    parse_macro_input!(args with parser);
+    let input = parse_macro_input!(input as ItemImpl);
+    match actor_impl(options, input) {
+        Ok(output) => output.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// Options for the [`actor`] attribute.
+#[derive(Default)]
+struct ActorOptions {
+    message: Option<(Visibility, Ident)>,
+}
+
+impl ActorOptions {
+    fn parse(&mut self, meta: syn::meta::ParseNestedMeta<'_>) -> Result<(), Error> {
+        if meta.path.is_ident("message") {
+            let input = meta.value()?;
+            let vis = input.parse()?;
+            let name = input.parse()?;
+            self.message = Some((vis, name));
+            Ok(())
+        } else {
+            Err(meta.error("unknown option, expected `message`"))
+        }
+    }
+}
+
+/// A single `handle_*` method.
+struct Handler<'a> {
+    method: &'a Ident,
+    variant: Ident,
+    docs: Vec<&'a syn::Attribute>,
+    argument: &'a Type,
+    /// `None` if the handler doesn't return anything.
+    response: Option<&'a Type>,
+}
+
+fn actor_impl(options: ActorOptions, input: ItemImpl) -> Result<TokenStream2, Error> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "`actor` can't be used on generic implementations",
+        ));
+    }
+    if let Some((_, path, _)) = &input.trait_ {
+        return Err(Error::new_spanned(
+            path,
+            "`actor` can't be used on trait implementations",
+        ));
+    }
+    let self_ty = &input.self_ty;
+    let (vis, message) = match options.message {
+        Some(message) => message,
+        None => {
+            let Type::Path(path) = &**self_ty else {
+                return Err(Error::new_spanned(
+                    self_ty,
+                    "can't determine message type name, use `#[actor(message = MyMessage)]`",
+                ));
+            };
+            let ident = &path.path.segments.last().unwrap().ident;
+            (Visibility::Inherited, format_ident!("{ident}Message"))
+        }
+    };
+
+    let mut handlers = Vec::new();
+    for item in &input.items {
+        let ImplItem::Fn(method) = item else {
+            continue;
+        };
+        let sig = &method.sig;
+        let Some(name) = sig
+            .ident
+            .to_string()
+            .strip_prefix("handle_")
+            .map(variant_name)
+        else {
+            continue;
+        };
+        if name.is_empty() {
+            return Err(Error::new_spanned(
+                &sig.ident,
+                "handler name must be `handle_` followed by the message name",
+            ));
+        }
+        if sig.asyncness.is_none() {
+            return Err(Error::new_spanned(sig, "handlers must be `async`"));
+        }
+        let mut inputs = sig.inputs.iter();
+        let receiver = match inputs.next() {
+            Some(FnArg::Receiver(receiver)) => receiver,
+            _ => return Err(Error::new_spanned(sig, "handlers must take `&mut self`")),
+        };
+        if receiver.reference.is_none() || receiver.mutability.is_none() {
+            return Err(Error::new_spanned(
+                receiver,
+                "handlers must take `&mut self`",
+            ));
+        }
+        let argument = match (inputs.next(), inputs.next()) {
+            (Some(FnArg::Typed(arg)), None) => &*arg.ty,
+            _ => {
+                return Err(Error::new_spanned(
+                    &sig.inputs,
+                    "handlers must take a single message argument",
+                ))
+            }
+        };
+        let response = match &sig.output {
+            ReturnType::Type(_, ty) if !matches!(&**ty, Type::Tuple(t) if t.elems.is_empty()) => {
+                Some(&**ty)
+            }
+            _ => None,
+        };
+        handlers.push(Handler {
+            method: &sig.ident,
+            variant: Ident::new(&name, sig.ident.span()),
+            docs: method
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("doc"))
+                .collect(),
+            argument,
+            response,
+        });
+    }
+    if handlers.is_empty() {
+        return Err(Error::new_spanned(
+            self_ty,
+            "`actor` requires at least one `async fn handle_*(&mut self, msg)` method",
+        ));
+    }
+
+    let rpc = quote!(::heph::actor_ref::RpcMessage);
+    let mut variants = Vec::with_capacity(handlers.len());
+    let mut from_impls = Vec::with_capacity(handlers.len());
+    let mut arms = Vec::with_capacity(handlers.len());
+    for handler in &handlers {
+        let Handler {
+            method,
+            variant,
+            docs,
+            argument,
+            response,
+        } = handler;
+        let ty = match response {
+            Some(response) => quote!(#rpc<#argument, #response>),
+            None => quote!(#argument),
+        };
+        variants.push(quote! {
+            #( #docs )*
+            #variant(#ty)
+        });
+        from_impls.push(quote! {
+            impl ::std::convert::From<#ty> for #message {
+                fn from(msg: #ty) -> #message {
+                    #message::#variant(msg)
+                }
+            }
+        });
+        arms.push(match response {
+            // If the caller is no longer waiting for the response there is
+            // nothing to do, so we ignore the error.
+            Some(_) => quote! {
+                #message::#variant(msg) => {
+                    _ = msg.handle(|msg| actor.#method(msg)).await;
+                }
+            },
+            None => quote! {
+                #message::#variant(msg) => actor.#method(msg).await,
+            },
+        });
+    }
+
+    let message_doc = format!(
+        "Message type of [`{}`], generated by the `actor` attribute.",
+        quote!(#self_ty).to_string().replace(' ', "")
+    );
+    Ok(quote! {
+        #input
+
+        #[doc = #message_doc]
+        #[allow(missing_debug_implementations)]
+        #vis enum #message {
+            #( #variants ),*
+        }
+
+        #( #from_impls )*
+
+        impl #self_ty {
+            /// Run the actor, calling the handler for each message received
+            /// until all actor references are dropped.
+            ///
+            /// Use [`actor_fn`] to create a [`NewActor`] from this function.
+            ///
+            /// [`actor_fn`]: ::heph::actor::actor_fn
+            /// [`NewActor`]: ::heph::actor::NewActor
+            #vis async fn run<RT>(mut ctx: ::heph::actor::Context<#message, RT>, mut actor: Self) {
+                while let ::std::result::Result::Ok(msg) = ctx.receive_next().await {
+                    match msg {
+                        #( #arms )*
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Converts the `snake_case` name of a handler (without the `handle_` prefix)
+/// into a `CamelCase` variant name.
+fn variant_name(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect()
+}
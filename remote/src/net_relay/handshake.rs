@@ -0,0 +1,303 @@
+//! Module with the handshake used to negotiate the message types.
+
+use std::borrow::Cow;
+use std::{fmt, io};
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use crate::net_relay::{DeIter, Serde, Transport};
+
+/// Value send first in the handshake, to detect a remote node that doesn't
+/// send a handshake.
+const HANDSHAKE_MAGIC: &str = "heph-remote handshake";
+/// Minimum amount of spare capacity used when receiving the handshake.
+const MIN_RECV_SIZE: usize = 1 << 10; // 1kb.
+
+/// Message types used by the relay.
+///
+/// If set using [`Config::with_registry`] the registry is exchanged with the
+/// remote node once the connection is established. If the message types don't
+/// match the relay actor will stop with an error (wrapping a
+/// [`HandshakeError`]), which is passed to its supervisor. Without a registry a
+/// mismatch would only show up as a failure to deserialise the messages.
+///
+/// The message types are described by a name and a hash of their schema, see
+/// [`MessageType`].
+///
+/// [`Config::with_registry`]: crate::net_relay::Config::with_registry
+///
+/// # Examples
+///
+#[cfg_attr(feature = "json", doc = "```")]
+#[cfg_attr(not(feature = "json"), doc = "```rust,ignore")]
+/// use heph::ActorRef;
+/// use heph_remote::net_relay::{self, MessageType, Registry, Relay};
+/// use heph_rt::ThreadSafe;
+///
+/// # fn setup(actor_ref: ActorRef<String>) {
+/// // The message types we send and receive. If the format of the messages
+/// // changes so should the schema hash, here we use a version number.
+/// let registry = Registry::new(
+///     MessageType::new("Request", MessageType::schema_hash("v2")),
+///     MessageType::new("Response", MessageType::schema_hash("v1")),
+/// );
+///
+/// let relay = net_relay::Config::<_, _, _, String, String, ThreadSafe>::new()
+///     .tcp()
+///     .json()
+///     .route(Relay::to(actor_ref))
+///     .with_registry(registry);
+/// # _ = relay;
+/// # }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Registry {
+    /// Type of the messages send to the remote node.
+    sends: MessageType,
+    /// Type of the messages received from the remote node.
+    receives: MessageType,
+}
+
+impl Registry {
+    /// Create a new registry for a relay that `sends` and `receives` messages
+    /// of the provided types.
+    pub const fn new(sends: MessageType, receives: MessageType) -> Registry {
+        Registry { sends, receives }
+    }
+
+    /// Returns the type of the messages send to the remote node.
+    pub const fn sends(&self) -> &MessageType {
+        &self.sends
+    }
+
+    /// Returns the type of the messages received from the remote node.
+    pub const fn receives(&self) -> &MessageType {
+        &self.receives
+    }
+
+    /// Check if the `remote` registry is compatible with ours.
+    fn check(&self, remote: &Registry) -> Result<(), HandshakeError> {
+        if self.sends != remote.receives {
+            Err(HandshakeError {
+                sending: true,
+                local: self.sends.clone(),
+                remote: remote.receives.clone(),
+            })
+        } else if self.receives != remote.sends {
+            Err(HandshakeError {
+                sending: false,
+                local: self.receives.clone(),
+                remote: remote.sends.clone(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Serialize for Registry {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (HANDSHAKE_MAGIC, &self.sends, &self.receives).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Registry {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (magic, sends, receives): (String, _, _) = Deserialize::deserialize(deserializer)?;
+        if magic != HANDSHAKE_MAGIC {
+            return Err(de::Error::custom("not a heph-remote handshake"));
+        }
+        Ok(Registry { sends, receives })
+    }
+}
+
+/// Description of a message type.
+///
+/// The type is described by a name, e.g. the name of the Rust type, and a
+/// hash of its schema. The hash should change each time the serialised format
+/// of the type changes. [`MessageType::schema_hash`] can be used to create a
+/// hash from e.g. a version number or the definition of the type.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MessageType {
+    name: Cow<'static, str>,
+    schema_hash: u64,
+}
+
+impl MessageType {
+    /// Create a new message type.
+    pub const fn new(name: &'static str, schema_hash: u64) -> MessageType {
+        MessageType {
+            name: Cow::Borrowed(name),
+            schema_hash,
+        }
+    }
+
+    /// Returns the hash of a description of the `schema`.
+    ///
+    /// Unlike the hashers in the standard library this hash is stable, i.e.
+    /// it's the same on all nodes.
+    pub const fn schema_hash(schema: &str) -> u64 {
+        // FNV-1a.
+        let schema = schema.as_bytes();
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut i = 0;
+        while i < schema.len() {
+            hash ^= schema[i] as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+            i += 1;
+        }
+        hash
+    }
+
+    /// Returns the name of the type.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the hash of the schema of the type.
+    pub const fn hash(&self) -> u64 {
+        self.schema_hash
+    }
+}
+
+impl fmt::Display for MessageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` (schema hash {:016x})", self.name, self.schema_hash)
+    }
+}
+
+impl Serialize for MessageType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (&*self.name, self.schema_hash).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for MessageType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (name, schema_hash): (String, u64) = Deserialize::deserialize(deserializer)?;
+        Ok(MessageType {
+            name: Cow::Owned(name),
+            schema_hash,
+        })
+    }
+}
+
+/// Error returned if the message types of the local and remote node don't
+/// match.
+///
+/// This error is wrapped in an [`io::Error`] (with kind
+/// [`io::ErrorKind::InvalidData`]) returned by the relay actor, use
+/// [`io::Error::get_ref`] and [`downcast_ref`] to get it.
+///
+/// [`downcast_ref`]: std::error::Error::downcast_ref
+#[derive(Clone, Debug)]
+pub struct HandshakeError {
+    /// Whether the mismatch is in the message send (or received) by this
+    /// node.
+    sending: bool,
+    local: MessageType,
+    remote: MessageType,
+}
+
+impl HandshakeError {
+    /// Returns `true` if the mismatch is for the messages send to the remote
+    /// node, `false` if it's for the messages received from it.
+    pub const fn is_sending(&self) -> bool {
+        self.sending
+    }
+
+    /// Returns the message type used by the local node.
+    pub const fn local(&self) -> &MessageType {
+        &self.local
+    }
+
+    /// Returns the message type used by the remote node.
+    pub const fn remote(&self) -> &MessageType {
+        &self.remote
+    }
+}
+
+impl fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.sending {
+            write!(
+                f,
+                "message type mismatch: sending {}, but remote node receives {}",
+                self.local, self.remote
+            )
+        } else {
+            write!(
+                f,
+                "message type mismatch: receiving {}, but remote node sends {}",
+                self.local, self.remote
+            )
+        }
+    }
+}
+
+impl std::error::Error for HandshakeError {}
+
+/// Exchange the `registry` with the remote node.
+///
+/// Returns the buffer with any data received after the handshake.
+pub(crate) async fn handshake<S, T>(
+    transport: &T,
+    registry: &Registry,
+    mut buf: Vec<u8>,
+) -> io::Result<Vec<u8>>
+where
+    S: Serde,
+    T: Transport,
+{
+    let mut send_buf = Vec::new();
+    if let Err(err) = S::to_buf(&mut send_buf, registry) {
+        let msg = format!("failed to serialise handshake: {err}");
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+    }
+    _ = transport.send_frame(send_buf).await?;
+
+    let remote = loop {
+        let n = buf.len();
+        buf.reserve(MIN_RECV_SIZE);
+        buf = transport.recv_frame(buf).await?;
+        if buf.len() == n {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "remote node closed the connection during the handshake",
+            ));
+        }
+
+        let mut deserialiser = S::iter::<Registry>(&buf);
+        let remote = match deserialiser.next() {
+            Some(Ok(remote)) => remote,
+            Some(Err(err)) => {
+                let msg = format!("invalid handshake from remote node: {err}");
+                return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+            }
+            // Only received whitespace, try again.
+            None => continue,
+        };
+        let n = deserialiser.byte_offset();
+        drop(deserialiser);
+        drop(buf.drain(..n));
+        break remote;
+    };
+
+    registry
+        .check(&remote)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    Ok(buf)
+}
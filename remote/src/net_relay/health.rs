@@ -0,0 +1,230 @@
+//! Module with the per-peer circuit breaker used by the UDP relay.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use heph::ActorRef;
+use log::{debug, warn};
+
+/// Circuit breaker for the [`Udp`] relay.
+///
+/// If set using [`Config::with_circuit_breaker`] the relay tracks the health of
+/// each peer it sends messages to. After a number of consecutive failures,
+/// i.e. errors sending a message or (when using [`AtLeastOnce`]) messages not
+/// acknowledged after the maximum number of attempts, the circuit for the peer
+/// is opened: all messages to the peer are dropped for the open timeout. Once
+/// the timeout has passed the circuit is half-open, the next messages are send
+/// again and the first success (acknowledgement or message received from the
+/// peer) closes the circuit, while a failure opens it again.
+///
+/// Without a circuit breaker errors sending a message stop the relay actor,
+/// with a circuit breaker they're counted as failures for the peer.
+///
+/// Changes in the state of the peers can be send to an actor using
+/// [`CircuitBreaker::with_notify`].
+///
+/// [`Udp`]: crate::net_relay::Udp
+/// [`Config::with_circuit_breaker`]: crate::net_relay::Config::with_circuit_breaker
+/// [`AtLeastOnce`]: crate::net_relay::AtLeastOnce
+///
+/// # Notes
+///
+/// The circuit is only moved from open to half-open when a message for the
+/// peer is relayed after the open timeout has passed.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "json", doc = "```")]
+#[cfg_attr(not(feature = "json"), doc = "```rust,ignore")]
+/// use std::time::Duration;
+///
+/// use heph::ActorRef;
+/// use heph_remote::net_relay::{self, AtLeastOnce, CircuitBreaker, PeerStateChange, Relay};
+/// use heph_rt::ThreadSafe;
+///
+/// # fn setup(actor_ref: ActorRef<String>, health_ref: ActorRef<PeerStateChange>) {
+/// let circuit_breaker = CircuitBreaker::new()
+///     .with_failure_threshold(3)
+///     .with_open_timeout(Duration::from_secs(30))
+///     .with_notify(health_ref);
+///
+/// let relay = net_relay::Config::<_, _, _, String, String, ThreadSafe>::new()
+///     .udp()
+///     .json()
+///     .route(Relay::to(actor_ref))
+///     .with_at_least_once(AtLeastOnce::new())
+///     .with_circuit_breaker(circuit_breaker);
+/// # _ = relay;
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_timeout: Duration,
+    notify: Option<ActorRef<PeerStateChange>>,
+}
+
+impl CircuitBreaker {
+    /// Create a new configuration using the default values.
+    ///
+    /// The defaults are:
+    ///  * failure threshold: 5 consecutive failures,
+    ///  * open timeout: 10 seconds,
+    ///  * no actor is notified of state changes.
+    pub const fn new() -> CircuitBreaker {
+        CircuitBreaker {
+            failure_threshold: 5,
+            open_timeout: Duration::from_secs(10),
+            notify: None,
+        }
+    }
+
+    /// Set the number of consecutive failures after which the circuit for a
+    /// peer is opened.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `failures` is zero.
+    pub const fn with_failure_threshold(mut self, failures: u32) -> Self {
+        assert!(failures != 0, "failure threshold must not be zero");
+        self.failure_threshold = failures;
+        self
+    }
+
+    /// Set the time the circuit for a peer stays open, i.e. the time no
+    /// messages are send to the peer.
+    pub const fn with_open_timeout(mut self, timeout: Duration) -> Self {
+        self.open_timeout = timeout;
+        self
+    }
+
+    /// Send changes in the state of the peers to the actor `notify`.
+    pub fn with_notify(mut self, notify: ActorRef<PeerStateChange>) -> Self {
+        self.notify = Some(notify);
+        self
+    }
+
+    /// Returns the number of consecutive failures after which the circuit is
+    /// opened.
+    pub const fn failure_threshold(&self) -> u32 {
+        self.failure_threshold
+    }
+
+    /// Returns the time the circuit stays open.
+    pub const fn open_timeout(&self) -> Duration {
+        self.open_timeout
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> CircuitBreaker {
+        CircuitBreaker::new()
+    }
+}
+
+/// State of the circuit for a peer, see [`CircuitBreaker`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PeerState {
+    /// Peer is healthy, messages are send.
+    Closed,
+    /// Peer is unhealthy, messages are dropped.
+    Open,
+    /// Peer was unhealthy, messages are send again to see if the peer
+    /// recovered.
+    HalfOpen,
+}
+
+/// Message send to the actor set in [`CircuitBreaker::with_notify`] when the
+/// state of a peer changes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PeerStateChange {
+    /// Address of the peer.
+    pub peer: SocketAddr,
+    /// New state of the peer.
+    pub state: PeerState,
+}
+
+/// Health of the peers, using a [`CircuitBreaker`].
+pub(crate) struct Health {
+    config: CircuitBreaker,
+    peers: HashMap<SocketAddr, Peer>,
+}
+
+/// Health of a single peer.
+enum Peer {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen,
+}
+
+impl Health {
+    /// Create a new state using `config`.
+    pub(crate) fn new(config: CircuitBreaker) -> Health {
+        Health {
+            config,
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Returns `true` if a message can be send to `peer`.
+    pub(crate) fn allow(&mut self, peer: SocketAddr, now: Instant) -> bool {
+        match self.peers.get(&peer) {
+            None | Some(Peer::Closed { .. } | Peer::HalfOpen) => true,
+            Some(Peer::Open { until }) if *until <= now => {
+                self.set(peer, Peer::HalfOpen, PeerState::HalfOpen);
+                true
+            }
+            Some(Peer::Open { .. }) => false,
+        }
+    }
+
+    /// Mark an operation with `peer` as successful, e.g. a message was
+    /// acknowledged.
+    pub(crate) fn success(&mut self, peer: SocketAddr) {
+        match self.peers.get_mut(&peer) {
+            // Peers are added on the first failure.
+            None => {}
+            Some(Peer::Closed { failures }) => *failures = 0,
+            Some(Peer::Open { .. } | Peer::HalfOpen) => {
+                self.set(peer, Peer::Closed { failures: 0 }, PeerState::Closed);
+            }
+        }
+    }
+
+    /// Mark an operation with `peer` as failed.
+    ///
+    /// Returns `true` if the circuit was opened.
+    pub(crate) fn failure(&mut self, peer: SocketAddr, now: Instant) -> bool {
+        let peer_state = self
+            .peers
+            .entry(peer)
+            .or_insert(Peer::Closed { failures: 0 });
+        let open = match peer_state {
+            Peer::Closed { failures } => {
+                *failures += 1;
+                *failures >= self.config.failure_threshold
+            }
+            Peer::HalfOpen => true,
+            // Failure of a message send before the circuit was opened.
+            Peer::Open { .. } => false,
+        };
+        if open {
+            warn!("too many failures for peer {peer}, dropping messages for it");
+            let until = now + self.config.open_timeout;
+            self.set(peer, Peer::Open { until }, PeerState::Open);
+        }
+        open
+    }
+
+    /// Set the state of `peer` to `new`, notifying the actor (if any).
+    fn set(&mut self, peer: SocketAddr, new: Peer, state: PeerState) {
+        debug!("changing state of peer {peer} to {state:?}");
+        _ = self.peers.insert(peer, new);
+        if let Some(notify) = self.config.notify.as_ref() {
+            if let Err(err) = notify.try_send(PeerStateChange { peer, state }) {
+                debug!("failed to send peer state change: {err}");
+            }
+        }
+    }
+}
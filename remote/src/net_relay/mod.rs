@@ -16,6 +16,27 @@
 //! When sending large messages or streaming large amounts of data you should
 //! consider setting up a [`TcpStream`] instead.
 //!
+//! When using a [`Tcp`] connection the message types can be negotiated with the
+//! remote node, to detect mismatched types (e.g. during a rolling upgrade) when
+//! the connection is established, see [`Registry`].
+//!
+//! When using a [`Udp`] connection messages can be resend until the remote node
+//! acknowledges them, see [`AtLeastOnce`], and peers that keep failing can be
+//! skipped for a while, see [`CircuitBreaker`]. Control-plane messages can be
+//! send ahead of bulk transfers, and marked as such for the network, using
+//! [`Qos`] classes.
+//!
+//! For communication between processes on the same machine, e.g. a deployment
+//! with a process per CPU core, a [`Uds`] connection can be used.
+//!
+//! Messages can also be relayed over other systems, e.g. a message queue, by
+//! implementing the [`Transport`] trait and using [`Config::transport`]. The
+//! relay still takes care of routing and (de)serialising the messages.
+//!
+//! To follow a message across nodes a [`TraceContext`] can be send along with
+//! it, which is passed to the router on the remote node, see
+//! [`Route::route_traced`].
+//!
 //! [`TcpStream`]: heph_rt::net::TcpStream
 //!
 //! # Examples
@@ -96,22 +117,47 @@ use std::{fmt, io};
 
 use heph::actor::{self, Actor, NewActor};
 use heph_rt as rt;
+use heph_rt::net::uds::{UnixAddr, UnixStream};
+use heph_rt::net::TcpStream;
+use heph_rt::trace::{EventTiming, Trace};
 use serde::de::{self, Deserialize, DeserializeOwned, Deserializer, MapAccess, Visitor};
 use serde::ser::{Serialize, SerializeStruct, Serializer};
 
+mod handshake;
+mod health;
+mod qos;
+mod reliable;
 pub mod routers;
 mod tcp;
+mod trace;
+mod transport;
 mod udp;
+mod uds;
 mod uuid;
 
+use reliable::SeqNum;
 use uuid::Uuid;
 
+#[doc(inline)]
+pub use handshake::{HandshakeError, MessageType, Registry};
+#[doc(inline)]
+pub use health::{CircuitBreaker, PeerState, PeerStateChange};
+#[doc(inline)]
+pub use qos::{Qos, QosClass};
+#[doc(inline)]
+pub use reliable::AtLeastOnce;
 #[doc(no_inline)]
 pub use routers::{Relay, RelayGroup};
 #[doc(inline)]
 pub use tcp::RelayMessage;
 #[doc(inline)]
+pub use trace::{InvalidTraceContext, TraceContext};
+#[doc(inline)]
+pub use transport::Transport;
+#[doc(inline)]
 pub use udp::UdpRelayMessage;
+#[doc(inline)]
+pub use uds::UdsPeer;
 
 /// Use a [TCP] connection.
 ///
@@ -147,13 +193,89 @@ pub enum Tcp {}
 ///   performance.
 /// * Delivery of the message is not guaranteed, *but the same is true for any
 ///   actor reference*. To ensure delivery use [RPC] to acknowledge when a
-///   message is successfully processed by the remote actor.
+///   message is successfully processed by the remote actor. Alternatively
+///   [`AtLeastOnce`] delivery can be enabled to resend messages until the
+///   remote node has received them.
 ///
 /// [RPC]: heph::actor_ref::rpc
 #[allow(missing_debug_implementations)]
 #[allow(clippy::empty_enum)]
 pub enum Udp {}
 
+/// Use a [Unix Domain Socket] (UDS) connection.
+///
+/// This is meant for communication between processes on the same machine, for
+/// example a supervisor process and a process per CPU core. It uses a Unix
+/// stream, thus messages are delivered in order and without loss (as long as
+/// the connection remains open), and doesn't exchange any message types with
+/// the peer. See [`UdsPeer`] for how the connection is setup.
+///
+/// Incoming messages are routed with the address of the peer as source, which
+/// means the [`Route`]r must use [`UnixAddr`] as source address type. All
+/// routers in the [`routers`] module support this.
+///
+/// This uses the [`RelayMessage`] type, the same as [`Tcp`].
+///
+/// [Unix Domain Socket]: heph_rt::net::uds
+/// [`UnixAddr`]: heph_rt::net::uds::UnixAddr
+///
+/// # Examples
+///
+/// Spawning a relay in a child process, connecting to its supervisor process.
+///
+#[cfg_attr(feature = "json", doc = "```")]
+#[cfg_attr(not(feature = "json"), doc = "```rust,ignore")]
+/// use heph::actor::{self, actor_fn};
+/// use heph::supervisor::NoSupervisor;
+/// use heph::{restart_supervisor, ActorRef};
+/// use heph_remote::net_relay::{self, Relay, RelayMessage, UdsPeer};
+/// use heph_rt::net::uds::UnixAddr;
+/// use heph_rt::spawn::ActorOptions;
+/// use heph_rt::{self as rt, Runtime};
+///
+/// # fn main() -> Result<(), rt::Error> {
+/// # return Ok(()); // Don't want to connect.
+/// let mut runtime = Runtime::new()?;
+///
+/// // Actor that receives the messages from the supervisor process.
+/// async fn local_actor<RT>(mut ctx: actor::Context<String, RT>) {
+///     while let Ok(msg) = ctx.receive_next().await {
+///         println!("received message: {msg}");
+///     }
+/// }
+/// let local_actor = actor_fn(local_actor);
+/// let local_actor_ref = runtime.spawn(NoSupervisor, local_actor, (), ActorOptions::default());
+///
+/// // The supervisor process uses `UdsPeer::Accept` with the same address.
+/// let address = UnixAddr::from_pathname("/run/my_app/worker-1.sock").unwrap();
+/// let router: Relay<String> = Relay::to(local_actor_ref);
+/// let relay = net_relay::Config::new().uds().json().route(router);
+/// restart_supervisor!(RelaySupervisor, UdsPeer);
+/// let peer = UdsPeer::Connect(address);
+/// let supervisor = RelaySupervisor::new(peer.clone());
+/// let supervisor_ref: ActorRef<RelayMessage<String>> =
+///     runtime.spawn(supervisor, relay, peer, ActorOptions::default());
+///
+/// supervisor_ref.try_send("Hello supervisor!".to_owned()).unwrap();
+/// # drop(supervisor_ref);
+///
+/// runtime.start()
+/// # }
+/// ```
+#[allow(missing_debug_implementations)]
+#[allow(clippy::empty_enum)]
+pub enum Uds {}
+
+/// Use a custom [`Transport`] `T`.
+///
+/// This uses the [`RelayMessage`] type, the same as [`Tcp`], and
+/// [`Transport::Peer`] as argument for the relay actor. Incoming messages are
+/// routed using [`Transport::Address`] as source address.
+///
+/// See [`Config::transport`].
+#[allow(missing_debug_implementations)]
+pub struct CustomTransport<T>(PhantomData<T>);
+
 /// Use JSON serialisation.
 #[cfg(feature = "json")]
 #[allow(missing_debug_implementations)]
@@ -164,7 +286,8 @@ pub enum Json {}
 ///
 /// The following configuration opotions are available:
 ///  * `R`: [`Route`]r to route incoming message.
-///  * `CT`: contection to use, either [`Udp`] or [`Tcp`].
+///  * `CT`: contection to use, either [`Udp`], [`Tcp`], [`Uds`] or a
+///    [`CustomTransport`].
 ///  * `S`: serialisation format, currently only [`Json`] is supported.
 ///  * `Out`: outgoing message type.
 ///  * `In`: incoming message type (those that are routed by `R`).
@@ -181,6 +304,14 @@ pub struct Config<R, CT, S, Out, In, RT> {
     connection_type: PhantomData<CT>,
     /// Type of serialisation to use.
     serialisation: PhantomData<S>,
+    /// Message types exchanged in the handshake, only used by [`Tcp`].
+    registry: Option<Registry>,
+    /// At-least-once delivery, only used by [`Udp`].
+    at_least_once: Option<AtLeastOnce>,
+    /// Circuit breaker for the peers, only used by [`Udp`].
+    circuit_breaker: Option<CircuitBreaker>,
+    /// Quality of Service, only used by [`Udp`].
+    qos: Option<Qos>,
     /// Types needed in the `NewActor` implementation.
     _types: PhantomData<(Out, In, RT)>,
 }
@@ -198,6 +329,10 @@ impl<Out, In, RT> Config<(), (), (), Out, In, RT> {
             router: (),
             connection_type: PhantomData,
             serialisation: PhantomData,
+            registry: None,
+            at_least_once: None,
+            circuit_breaker: None,
+            qos: None,
             _types: PhantomData,
         }
     }
@@ -205,14 +340,24 @@ impl<Out, In, RT> Config<(), (), (), Out, In, RT> {
 
 impl<CT, S, Out, In, RT> Config<(), CT, S, Out, In, RT> {
     /// Use the `router` to route incoming messages.
+    ///
+    /// The `router` must implement [`Route`], using a [`SocketAddr`] as source
+    /// for [`Tcp`] and [`Udp`] connections and a [`UnixAddr`] for [`Uds`]
+    /// connections.
+    ///
+    /// [`UnixAddr`]: heph_rt::net::uds::UnixAddr
     pub fn route<R>(self, router: R) -> Config<R, CT, S, Out, In, RT>
     where
-        R: Route<In> + Clone,
+        R: Clone,
     {
         Config {
             router,
             connection_type: self.connection_type,
             serialisation: self.serialisation,
+            registry: self.registry,
+            at_least_once: self.at_least_once,
+            circuit_breaker: self.circuit_breaker,
+            qos: self.qos,
             _types: PhantomData,
         }
     }
@@ -225,6 +370,10 @@ impl<R, S, Out, In, RT> Config<R, (), S, Out, In, RT> {
             router: self.router,
             connection_type: PhantomData,
             serialisation: self.serialisation,
+            registry: self.registry,
+            at_least_once: self.at_least_once,
+            circuit_breaker: self.circuit_breaker,
+            qos: self.qos,
             _types: PhantomData,
         }
     }
@@ -235,11 +384,86 @@ impl<R, S, Out, In, RT> Config<R, (), S, Out, In, RT> {
             router: self.router,
             connection_type: PhantomData,
             serialisation: self.serialisation,
+            registry: self.registry,
+            at_least_once: self.at_least_once,
+            circuit_breaker: self.circuit_breaker,
+            qos: self.qos,
+            _types: PhantomData,
+        }
+    }
+
+    /// Use a [`Uds`] connection.
+    pub fn uds(self) -> Config<R, Uds, S, Out, In, RT> {
+        Config {
+            router: self.router,
+            connection_type: PhantomData,
+            serialisation: self.serialisation,
+            registry: self.registry,
+            at_least_once: self.at_least_once,
+            circuit_breaker: self.circuit_breaker,
+            qos: self.qos,
+            _types: PhantomData,
+        }
+    }
+
+    /// Use the custom [`Transport`] `T`.
+    pub fn transport<T>(self) -> Config<R, CustomTransport<T>, S, Out, In, RT>
+    where
+        T: Transport,
+    {
+        Config {
+            router: self.router,
+            connection_type: PhantomData,
+            serialisation: self.serialisation,
+            registry: self.registry,
+            at_least_once: self.at_least_once,
+            circuit_breaker: self.circuit_breaker,
+            qos: self.qos,
             _types: PhantomData,
         }
     }
 }
 
+impl<R, S, Out, In, RT> Config<R, Tcp, S, Out, In, RT> {
+    /// Exchange the message types in `registry` with the remote node once
+    /// connected, see [`Registry`].
+    pub fn with_registry(mut self, registry: Registry) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+}
+
+impl<R, T, S, Out, In, RT> Config<R, CustomTransport<T>, S, Out, In, RT> {
+    /// Exchange the message types in `registry` with the peer once connected,
+    /// see [`Registry`].
+    pub fn with_registry(mut self, registry: Registry) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+}
+
+impl<R, S, Out, In, RT> Config<R, Udp, S, Out, In, RT> {
+    /// Resend messages until the remote node acknowledges them, see
+    /// [`AtLeastOnce`].
+    pub fn with_at_least_once(mut self, at_least_once: AtLeastOnce) -> Self {
+        self.at_least_once = Some(at_least_once);
+        self
+    }
+
+    /// Stop sending messages to unhealthy peers, see [`CircuitBreaker`].
+    pub fn with_circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    /// Send messages in order of their [`QosClass`] and mark the packets
+    /// accordingly, see [`Qos`].
+    pub fn with_qos(mut self, qos: Qos) -> Self {
+        self.qos = Some(qos);
+        self
+    }
+}
+
 impl<R, CT, Out, In, RT> Config<R, CT, (), Out, In, RT> {
     /// Use [`Json`] serialisation.
     #[cfg(feature = "json")]
@@ -248,6 +472,10 @@ impl<R, CT, Out, In, RT> Config<R, CT, (), Out, In, RT> {
             router: self.router,
             connection_type: self.connection_type,
             serialisation: PhantomData,
+            registry: self.registry,
+            at_least_once: self.at_least_once,
+            circuit_breaker: self.circuit_breaker,
+            qos: self.qos,
             _types: PhantomData,
         }
     }
@@ -272,10 +500,11 @@ where
         ctx: actor::Context<Self::Message, Self::RuntimeAccess>,
         remote_address: Self::Argument,
     ) -> Result<Self::Actor, Self::Error> {
-        Ok(tcp::remote_relay::<S, Out, In, R, RT>(
+        Ok(transport::remote_relay::<TcpStream, S, Out, In, R, RT>(
             ctx,
             remote_address,
             self.router.clone(),
+            self.registry.clone(),
         ))
     }
 }
@@ -285,7 +514,7 @@ where
     R: Route<In> + Clone,
     In: DeserializeOwned,
     S: Serde,
-    RT: rt::Access,
+    RT: rt::Access + Clone,
     Out: Serialize,
 {
     type Message = UdpRelayMessage<Out>;
@@ -303,6 +532,66 @@ where
             ctx,
             local_address,
             self.router.clone(),
+            self.at_least_once,
+            self.circuit_breaker.clone(),
+            self.qos,
+        ))
+    }
+}
+
+impl<R, S, Out, In, RT> NewActor for Config<R, Uds, S, Out, In, RT>
+where
+    R: Route<In, UnixAddr> + Clone,
+    In: DeserializeOwned,
+    S: Serde,
+    RT: rt::Access,
+    Out: Serialize,
+{
+    type Message = RelayMessage<Out>;
+    type Argument = UdsPeer;
+    type Actor = impl Actor<Error = io::Error>;
+    type Error = !;
+    type RuntimeAccess = RT;
+
+    fn new(
+        &mut self,
+        ctx: actor::Context<Self::Message, Self::RuntimeAccess>,
+        peer: Self::Argument,
+    ) -> Result<Self::Actor, Self::Error> {
+        Ok(transport::remote_relay::<UnixStream, S, Out, In, R, RT>(
+            ctx,
+            peer,
+            self.router.clone(),
+            None,
+        ))
+    }
+}
+
+impl<R, T, S, Out, In, RT> NewActor for Config<R, CustomTransport<T>, S, Out, In, RT>
+where
+    R: Route<In, T::Address> + Clone,
+    T: Transport,
+    In: DeserializeOwned,
+    S: Serde,
+    RT: rt::Access,
+    Out: Serialize,
+{
+    type Message = RelayMessage<Out>;
+    type Argument = T::Peer;
+    type Actor = impl Actor<Error = io::Error>;
+    type Error = !;
+    type RuntimeAccess = RT;
+
+    fn new(
+        &mut self,
+        ctx: actor::Context<Self::Message, Self::RuntimeAccess>,
+        peer: Self::Argument,
+    ) -> Result<Self::Actor, Self::Error> {
+        Ok(transport::remote_relay::<T, S, Out, In, R, RT>(
+            ctx,
+            peer,
+            self.router.clone(),
+            self.registry.clone(),
         ))
     }
 }
@@ -317,6 +606,10 @@ where
             router: self.router.clone(),
             connection_type: self.connection_type,
             serialisation: self.serialisation,
+            registry: self.registry.clone(),
+            at_least_once: self.at_least_once,
+            circuit_breaker: self.circuit_breaker.clone(),
+            qos: self.qos,
             _types: self._types,
         }
     }
@@ -326,6 +619,10 @@ where
         self.router.clone_from(&source.router);
         self.connection_type.clone_from(&source.connection_type);
         self.serialisation.clone_from(&source.serialisation);
+        self.registry.clone_from(&source.registry);
+        self.at_least_once.clone_from(&source.at_least_once);
+        self.circuit_breaker.clone_from(&source.circuit_breaker);
+        self.qos.clone_from(&source.qos);
         self._types.clone_from(&source._types);
     }
 }
@@ -379,8 +676,10 @@ mod private {
 
     #[cfg(feature = "json")]
     impl Serde for Json {
-        type Iter<'a, T> = serde_json::StreamDeserializer<'a, serde_json::de::SliceRead<'a>, T>
-            where T: DeserializeOwned;
+        type Iter<'a, T>
+            = serde_json::StreamDeserializer<'a, serde_json::de::SliceRead<'a>, T>
+        where
+            T: DeserializeOwned;
         type Error = serde_json::Error;
 
         fn from_slice<'a, T>(buf: &'a [u8]) -> Result<T, Self::Error>
@@ -422,7 +721,10 @@ mod private {
 use private::{DeIter, Serde};
 
 /// Trait that determines how to route a message.
-pub trait Route<M> {
+///
+/// `A` is the type of the source address, [`SocketAddr`] for [`Tcp`] and
+/// [`Udp`] connections and [`UnixAddr`] for [`Uds`] connections.
+pub trait Route<M, A = SocketAddr> {
     /// [`Future`] that determines how to route a message, see [`route`].
     ///
     /// [`route`]: Route::route
@@ -444,13 +746,58 @@ pub trait Route<M> {
     /// use of a `Future`, in that case [`ready`] can be used.
     ///
     /// [`ready`]: std::future::ready
-    fn route<'a>(&'a mut self, msg: M, source: SocketAddr) -> Self::Route<'a>;
+    fn route<'a>(&'a mut self, msg: M, source: A) -> Self::Route<'a>;
+
+    /// Route a `msg` from `source` address, that was send with the `trace`
+    /// context, to the correct destination.
+    ///
+    /// This is called for all incoming messages, `trace` is `None` if the
+    /// message was send without a trace context. Routers can use this to
+    /// continue the trace on this node, e.g. by creating a [child context].
+    ///
+    /// The default implementation ignores the trace context and calls
+    /// [`route`].
+    ///
+    /// [child context]: TraceContext::child
+    /// [`route`]: Route::route
+    fn route_traced<'a>(
+        &'a mut self,
+        msg: M,
+        source: A,
+        trace: Option<TraceContext>,
+    ) -> Self::Route<'a> {
+        _ = trace;
+        self.route(msg, source)
+    }
+}
+
+/// Finish the runtime trace event for relaying a message with `trace` context.
+///
+/// The ids are added as attributes in the same format as used by
+/// [`TraceContext`]'s `Display` implementation.
+fn finish_relay_trace<T>(ctx: &mut T, timing: Option<EventTiming>, trace: TraceContext)
+where
+    T: Trace,
+{
+    if timing.is_some() {
+        let trace_id = format!("{:032x}", trace.trace_id());
+        let span_id = format!("{:016x}", trace.span_id());
+        ctx.finish_trace(
+            timing,
+            "Relaying traced message",
+            &[("trace_id", &trace_id), ("span_id", &span_id)],
+        );
+    }
 }
 
 /// Message type used in communicating.
 struct Message<M> {
     uuid: Uuid,
     msg: M,
+    /// Sequence number, only set when using [`AtLeastOnce`] delivery.
+    seq: Option<SeqNum>,
+    /// Trace context, only set if the message was send with one.
+    trace: Option<TraceContext>,
 }
 
 // NOTE: manually implementing this instead of deriving to not pull in a bunch
@@ -466,6 +813,8 @@ where
         enum Field {
             Uuid,
             Msg,
+            Seq,
+            Trace,
         }
 
         impl<'de> Deserialize<'de> for Field {
@@ -479,7 +828,7 @@ where
                     type Value = Field;
 
                     fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
-                        formatter.write_str("`uuid` or `message`")
+                        formatter.write_str("`uuid`, `message`, `seq` or `trace`")
                     }
 
                     fn visit_str<E>(self, value: &str) -> Result<Field, E>
@@ -489,6 +838,8 @@ where
                         match value {
                             "uuid" => Ok(Field::Uuid),
                             "message" => Ok(Field::Msg),
+                            "seq" => Ok(Field::Seq),
+                            "trace" => Ok(Field::Trace),
                             _ => Err(de::Error::unknown_field(value, FIELDS)),
                         }
                     }
@@ -516,6 +867,8 @@ where
             {
                 let mut uuid = None;
                 let mut msg = None;
+                let mut seq = None;
+                let mut trace = None;
                 while let Some(key) = map.next_key()? {
                     match key {
                         Field::Uuid => {
@@ -530,15 +883,32 @@ where
                             }
                             msg = Some(map.next_value()?);
                         }
+                        Field::Seq => {
+                            if seq.is_some() {
+                                return Err(de::Error::duplicate_field("seq"));
+                            }
+                            seq = Some(map.next_value()?);
+                        }
+                        Field::Trace => {
+                            if trace.is_some() {
+                                return Err(de::Error::duplicate_field("trace"));
+                            }
+                            trace = Some(map.next_value()?);
+                        }
                     }
                 }
                 let uuid = uuid.ok_or_else(|| de::Error::missing_field("uuid"))?;
                 let msg = msg.ok_or_else(|| de::Error::missing_field("message"))?;
-                Ok(Message { uuid, msg })
+                Ok(Message {
+                    uuid,
+                    msg,
+                    seq,
+                    trace,
+                })
             }
         }
 
-        const FIELDS: &[&str] = &["uuid", "message"];
+        const FIELDS: &[&str] = &["uuid", "message", "seq", "trace"];
         deserializer.deserialize_struct("Message", FIELDS, MessageVisitor(PhantomData))
     }
 }
@@ -551,9 +921,16 @@ where
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("Message", 2)?;
+        let len = 2 + usize::from(self.seq.is_some()) + usize::from(self.trace.is_some());
+        let mut state = serializer.serialize_struct("Message", len)?;
         state.serialize_field("uuid", &self.uuid)?;
         state.serialize_field("message", &self.msg)?;
+        if let Some(seq) = &self.seq {
+            state.serialize_field("seq", seq)?;
+        }
+        if let Some(trace) = &self.trace {
+            state.serialize_field("trace", trace)?;
+        }
         state.end()
     }
 }
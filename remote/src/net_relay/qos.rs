@@ -0,0 +1,221 @@
+//! Module with the Quality of Service (QoS) classes used by the UDP relay.
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+
+use crate::net_relay::TraceContext;
+
+/// Quality of Service (QoS) class of a relayed message.
+///
+/// The class determines the order in which queued messages are send and the
+/// Differentiated Services Code Point (DSCP) the packets are marked with, see
+/// [`Qos`].
+///
+/// Messages are send using a class with [`UdpRelayMessage::RelayQos`], all
+/// other messages use [`QosClass::Standard`].
+///
+/// [`UdpRelayMessage::RelayQos`]: crate::net_relay::UdpRelayMessage::RelayQos
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum QosClass {
+    /// Control-plane messages, e.g. membership or health checks between
+    /// nodes.
+    ///
+    /// Send before all other messages. Marked with Class Selector 6 (CS6,
+    /// network control) by default.
+    Control,
+    /// Regular messages.
+    ///
+    /// Not marked by default (DSCP 0, best effort).
+    #[default]
+    Standard,
+    /// Bulk transfers.
+    ///
+    /// Send after all other messages. Marked with Class Selector 1 (CS1, lower
+    /// effort) by default.
+    Bulk,
+}
+
+impl QosClass {
+    /// All classes, in the order in which they're send.
+    const ALL: [QosClass; 3] = [QosClass::Control, QosClass::Standard, QosClass::Bulk];
+
+    /// Returns the default DSCP of the class.
+    pub const fn default_dscp(self) -> u8 {
+        match self {
+            QosClass::Control => 48, // CS6.
+            QosClass::Standard => 0,
+            QosClass::Bulk => 8, // CS1.
+        }
+    }
+
+    const fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Quality of Service (QoS) for the [`Udp`] relay.
+///
+/// If set using [`Config::with_qos`] the relay keeps a separate queue for
+/// each [`QosClass`]. All messages in the relay's inbox are collected into the
+/// queues and send in order of their class, so that control-plane messages
+/// aren't stuck behind bulk transfers. Within a class messages are send in the
+/// order they're received.
+///
+/// The packets are also marked with the Differentiated Services Code Point
+/// (DSCP) of their class, using the `IP_TOS` (or `IPV6_TCLASS`) socket
+/// option, allowing the network to prioritise them as well.
+///
+/// Without it set messages are send in the order they're received and the
+/// class of the message is ignored.
+///
+/// [`Udp`]: crate::net_relay::Udp
+/// [`Config::with_qos`]: crate::net_relay::Config::with_qos
+///
+/// # Notes
+///
+/// Changing the DSCP requires a system call, which is only done when the class
+/// differs from the previous packet send.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "json", doc = "```")]
+#[cfg_attr(not(feature = "json"), doc = "```rust,ignore")]
+/// use heph::ActorRef;
+/// use heph_remote::net_relay::{self, Qos, QosClass, Relay, UdpRelayMessage};
+/// use heph_rt::ThreadSafe;
+///
+/// # fn setup(actor_ref: ActorRef<String>, relay_ref: ActorRef<UdpRelayMessage<String>>) {
+/// // Mark control messages as Expedited Forwarding (EF).
+/// let qos = Qos::new().with_dscp(QosClass::Control, 46);
+///
+/// let relay = net_relay::Config::<_, _, _, String, String, ThreadSafe>::new()
+///     .udp()
+///     .json()
+///     .route(Relay::to(actor_ref))
+///     .with_qos(qos);
+/// # _ = relay;
+///
+/// // Sending a control message.
+/// let msg = UdpRelayMessage::RelayQos {
+///     message: "ping".to_owned(),
+///     target: "127.0.0.1:9001".parse().unwrap(),
+///     class: QosClass::Control,
+///     trace: None,
+/// };
+/// # _ = msg;
+/// # _ = relay_ref;
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Qos {
+    dscp: [Option<u8>; 3],
+    max_queued: usize,
+}
+
+impl Qos {
+    /// Create a new configuration using the default values.
+    ///
+    /// The defaults are:
+    ///  * DSCP marking using [`QosClass::default_dscp`],
+    ///  * maximum number of queued messages: 64.
+    pub const fn new() -> Qos {
+        Qos {
+            dscp: [
+                Some(QosClass::Control.default_dscp()),
+                Some(QosClass::Standard.default_dscp()),
+                Some(QosClass::Bulk.default_dscp()),
+            ],
+            max_queued: 64,
+        }
+    }
+
+    /// Set the DSCP used to mark packets of `class`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dscp` is larger than 63, the DSCP is only 6 bits.
+    pub const fn with_dscp(mut self, class: QosClass, dscp: u8) -> Self {
+        assert!(dscp < 64, "DSCP must be smaller than 64");
+        self.dscp[class.index()] = Some(dscp);
+        self
+    }
+
+    /// Don't mark the packets, only use the separate queues.
+    pub const fn without_marking(mut self) -> Self {
+        self.dscp = [None; 3];
+        self
+    }
+
+    /// Set the maximum number of messages collected from the inbox into the
+    /// queues at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero.
+    pub const fn with_max_queued(mut self, max: usize) -> Self {
+        assert!(
+            max != 0,
+            "maximum number of queued messages must not be zero"
+        );
+        self.max_queued = max;
+        self
+    }
+
+    /// Returns the DSCP used to mark packets of `class`, if any.
+    pub const fn dscp(&self, class: QosClass) -> Option<u8> {
+        self.dscp[class.index()]
+    }
+
+    /// Returns the maximum number of messages collected from the inbox into
+    /// the queues at once.
+    pub const fn max_queued(&self) -> usize {
+        self.max_queued
+    }
+}
+
+impl Default for Qos {
+    fn default() -> Qos {
+        Qos::new()
+    }
+}
+
+/// Message queued to be relayed.
+pub(crate) type Queued<M> = (M, SocketAddr, Option<TraceContext>, QosClass);
+
+/// Per [`QosClass`] queues of outgoing messages.
+pub(crate) struct Queues<M> {
+    queues: [VecDeque<Queued<M>>; 3],
+    len: usize,
+}
+
+impl<M> Queues<M> {
+    pub(crate) const fn new() -> Queues<M> {
+        Queues {
+            queues: [VecDeque::new(), VecDeque::new(), VecDeque::new()],
+            len: 0,
+        }
+    }
+
+    /// Returns the number of queued messages.
+    pub(crate) const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Add `msg` to the queue of its class.
+    pub(crate) fn push(&mut self, msg: Queued<M>) {
+        self.queues[msg.3.index()].push_back(msg);
+        self.len += 1;
+    }
+
+    /// Remove the next message to send, i.e. the first message of the highest
+    /// class.
+    pub(crate) fn pop(&mut self) -> Option<Queued<M>> {
+        for class in QosClass::ALL {
+            if let Some(msg) = self.queues[class.index()].pop_front() {
+                self.len -= 1;
+                return Some(msg);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,393 @@
+//! Module with the at-least-once delivery used by the UDP relay.
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant, SystemTime};
+
+use getrandom::getrandom;
+use log::{debug, warn};
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+/// Value send first in an acknowledgement, to distinguish it from a message.
+const ACK_MAGIC: &str = "heph-remote ack";
+/// Maximum number of out of order sequence numbers tracked per remote node,
+/// see [`Received`].
+const MAX_OUT_OF_ORDER: usize = 1024;
+
+/// At-least-once delivery for the [`Udp`] relay.
+///
+/// If set using [`Config::with_at_least_once`] each message is given a
+/// sequence number and kept in a resend buffer until the remote node
+/// acknowledges it. Messages not acknowledged within the resend timeout are
+/// send again, doubling the timeout after each attempt. Once the maximum number
+/// of attempts is reached the message is dropped (and a warning is logged).
+///
+/// When the resend buffer is full the relay stops sending new messages until
+/// space is available again, meaning the messages will queue up in the relay's
+/// inbox.
+///
+/// The receiving relay acknowledges each message, including duplicates (in
+/// case the earlier acknowledgement was lost), but only routes the first copy
+/// of a message it receives. The messages are **not** reordered, they are
+/// routed in the order they are received.
+///
+/// [`Udp`]: crate::net_relay::Udp
+/// [`Config::with_at_least_once`]: crate::net_relay::Config::with_at_least_once
+///
+/// # Notes
+///
+/// This must be enabled on both the local and the remote node. A relay without
+/// it enabled doesn't acknowledge any messages, causing all messages to be send
+/// the maximum number of times.
+///
+/// "Delivered" here means routed by the remote relay, it doesn't guarantee
+/// the message is processed by the remote actor. Use [RPC] for that.
+///
+/// [RPC]: heph::actor_ref::rpc
+///
+/// # Examples
+///
+#[cfg_attr(feature = "json", doc = "```")]
+#[cfg_attr(not(feature = "json"), doc = "```rust,ignore")]
+/// use std::time::Duration;
+///
+/// use heph::ActorRef;
+/// use heph_remote::net_relay::{self, AtLeastOnce, Relay};
+/// use heph_rt::ThreadSafe;
+///
+/// # fn setup(actor_ref: ActorRef<String>) {
+/// let at_least_once = AtLeastOnce::new()
+///     .with_resend_timeout(Duration::from_millis(100))
+///     .with_max_attempts(10);
+///
+/// let relay = net_relay::Config::<_, _, _, String, String, ThreadSafe>::new()
+///     .udp()
+///     .json()
+///     .route(Relay::to(actor_ref))
+///     .with_at_least_once(at_least_once);
+/// # _ = relay;
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct AtLeastOnce {
+    resend_buffer_size: usize,
+    resend_timeout: Duration,
+    max_attempts: u32,
+}
+
+impl AtLeastOnce {
+    /// Create a new configuration using the default values.
+    ///
+    /// The defaults are:
+    ///  * resend buffer size: 1024 messages,
+    ///  * resend timeout: 200 milliseconds,
+    ///  * maximum number of attempts: 5.
+    pub const fn new() -> AtLeastOnce {
+        AtLeastOnce {
+            resend_buffer_size: 1024,
+            resend_timeout: Duration::from_millis(200),
+            max_attempts: 5,
+        }
+    }
+
+    /// Set the maximum number of unacknowledged messages kept in the resend
+    /// buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub const fn with_resend_buffer_size(mut self, size: usize) -> Self {
+        assert!(size != 0, "resend buffer size must not be zero");
+        self.resend_buffer_size = size;
+        self
+    }
+
+    /// Set the timeout after which an unacknowledged message is send again.
+    ///
+    /// The timeout is doubled after each attempt.
+    pub const fn with_resend_timeout(mut self, timeout: Duration) -> Self {
+        self.resend_timeout = timeout;
+        self
+    }
+
+    /// Set the maximum number of times a message is send, including the
+    /// first attempt.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `attempts` is zero.
+    pub const fn with_max_attempts(mut self, attempts: u32) -> Self {
+        assert!(attempts != 0, "maximum number of attempts must not be zero");
+        self.max_attempts = attempts;
+        self
+    }
+
+    /// Returns the maximum number of unacknowledged messages kept in the
+    /// resend buffer.
+    pub const fn resend_buffer_size(&self) -> usize {
+        self.resend_buffer_size
+    }
+
+    /// Returns the timeout after which an unacknowledged message is send
+    /// again.
+    pub const fn resend_timeout(&self) -> Duration {
+        self.resend_timeout
+    }
+
+    /// Returns the maximum number of times a message is send.
+    pub const fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+}
+
+impl Default for AtLeastOnce {
+    fn default() -> AtLeastOnce {
+        AtLeastOnce::new()
+    }
+}
+
+/// Sequence number of a message.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct SeqNum {
+    /// Epoch of the sending relay, see [`Reliable::epoch`].
+    pub(crate) epoch: u64,
+    /// Sequence number of the message, per target.
+    pub(crate) n: u64,
+}
+
+impl Serialize for SeqNum {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (self.epoch, self.n).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SeqNum {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (epoch, n) = Deserialize::deserialize(deserializer)?;
+        Ok(SeqNum { epoch, n })
+    }
+}
+
+/// Acknowledgement of a received message.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Ack(pub(crate) SeqNum);
+
+impl Serialize for Ack {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (ACK_MAGIC, self.0.epoch, self.0.n).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Ack {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (magic, epoch, n): (String, _, _) = Deserialize::deserialize(deserializer)?;
+        if magic != ACK_MAGIC {
+            return Err(de::Error::custom("not a heph-remote acknowledgement"));
+        }
+        Ok(Ack(SeqNum { epoch, n }))
+    }
+}
+
+/// State of the at-least-once delivery.
+pub(crate) struct Reliable {
+    config: AtLeastOnce,
+    /// Random value identifying this relay, it changes when the relay is
+    /// restarted (and starts using sequence numbers from zero again).
+    epoch: u64,
+    /// Next sequence number to use per target.
+    next_seq: HashMap<SocketAddr, u64>,
+    /// Messages send, but not yet acknowledged.
+    unacked: VecDeque<Unacked>,
+    /// Received messages per source.
+    received: HashMap<SocketAddr, Received>,
+}
+
+/// Message that hasn't been acknowledged yet.
+struct Unacked {
+    target: SocketAddr,
+    n: u64,
+    /// Serialised message.
+    packet: Vec<u8>,
+    /// Number of times the message is send.
+    attempts: u32,
+    /// When to send the message again.
+    resend_at: Instant,
+}
+
+/// Sequence numbers of the messages received from a single source.
+///
+/// All sequence numbers below `next` have been received, `out_of_order`
+/// contains the sequence numbers received after a gap. As the gap might never
+/// be filled (if the sender gives up) the size of `out_of_order` is limited to
+/// [`MAX_OUT_OF_ORDER`], after which the lowest missing sequence number is
+/// skipped.
+struct Received {
+    epoch: u64,
+    next: u64,
+    out_of_order: BTreeSet<u64>,
+}
+
+impl Received {
+    /// Returns `true` if `n` wasn't received before.
+    fn insert(&mut self, n: u64) -> bool {
+        if n < self.next || !self.out_of_order.insert(n) {
+            return false;
+        }
+        if self.out_of_order.len() > MAX_OUT_OF_ORDER {
+            // Give up on the gap.
+            self.next = self.out_of_order.pop_first().unwrap() + 1;
+        }
+        while self.out_of_order.remove(&self.next) {
+            self.next += 1;
+        }
+        true
+    }
+}
+
+impl Reliable {
+    /// Create a new state using `config`.
+    pub(crate) fn new(config: AtLeastOnce) -> Reliable {
+        Reliable {
+            config,
+            epoch: new_epoch(),
+            next_seq: HashMap::new(),
+            unacked: VecDeque::new(),
+            received: HashMap::new(),
+        }
+    }
+
+    /// Returns `true` if the resend buffer is full.
+    pub(crate) fn is_full(&self) -> bool {
+        self.unacked.len() >= self.config.resend_buffer_size
+    }
+
+    /// Returns the sequence number to use for the next message to `target`.
+    ///
+    /// The sequence number is only used once the message is added using
+    /// [`Reliable::add`].
+    pub(crate) fn next_seq(&self, target: SocketAddr) -> SeqNum {
+        SeqNum {
+            epoch: self.epoch,
+            n: self.next_seq.get(&target).copied().unwrap_or(0),
+        }
+    }
+
+    /// Add the serialised message in `packet` (using the sequence number
+    /// returned by [`Reliable::next_seq`]) to the resend buffer.
+    pub(crate) fn add(&mut self, target: SocketAddr, packet: Vec<u8>) {
+        let next = self.next_seq.entry(target).or_insert(0);
+        let n = *next;
+        *next += 1;
+        self.unacked.push_back(Unacked {
+            target,
+            n,
+            packet,
+            attempts: 1,
+            resend_at: Instant::now() + self.config.resend_timeout,
+        });
+    }
+
+    /// Remove the message acknowledged by `ack` from `source`.
+    pub(crate) fn acknowledged(&mut self, Ack(seq): Ack, source: SocketAddr) {
+        if seq.epoch != self.epoch {
+            debug!("received acknowledgement for a previous relay (from {source})");
+            return;
+        }
+        let pos = self
+            .unacked
+            .iter()
+            .position(|msg| msg.target == source && msg.n == seq.n);
+        if let Some(pos) = pos {
+            drop(self.unacked.remove(pos));
+        }
+    }
+
+    /// Returns `true` if the message with sequence number `seq` from `source`
+    /// wasn't received before, i.e. it should be routed.
+    pub(crate) fn received(&mut self, seq: SeqNum, source: SocketAddr) -> bool {
+        let received = self.received.entry(source).or_insert_with(|| Received {
+            epoch: seq.epoch,
+            next: 0,
+            out_of_order: BTreeSet::new(),
+        });
+        if received.epoch != seq.epoch {
+            // Remote relay restarted.
+            received.epoch = seq.epoch;
+            received.next = 0;
+            received.out_of_order.clear();
+        }
+        received.insert(seq.n)
+    }
+
+    /// Returns the deadline at which the next message needs to be resend, if
+    /// any.
+    pub(crate) fn next_resend(&self) -> Option<Instant> {
+        self.unacked.iter().map(|msg| msg.resend_at).min()
+    }
+
+    /// Returns the next message that needs to be resend (before `now`), or
+    /// the target of a message that was dropped because it reached the maximum
+    /// number of attempts.
+    pub(crate) fn pop_resend(&mut self, now: Instant) -> Option<Resend> {
+        let pos = self.unacked.iter().position(|msg| msg.resend_at <= now)?;
+        if self.unacked[pos].attempts < self.config.max_attempts {
+            let msg = &mut self.unacked[pos];
+            msg.attempts += 1;
+            let timeout = self.config.resend_timeout * (1 << (msg.attempts - 1).min(16));
+            msg.resend_at = now + timeout;
+            return Some(Resend::Send(msg.target, msg.packet.clone()));
+        }
+
+        let msg = self.unacked.remove(pos).unwrap();
+        warn!(
+            "message not acknowledged (by {}) after {} attempts, dropping it",
+            msg.target, msg.attempts
+        );
+        Some(Resend::Dropped(msg.target))
+    }
+
+    /// Drop all unacknowledged messages to `target`.
+    pub(crate) fn remove_target(&mut self, target: SocketAddr) {
+        let before = self.unacked.len();
+        self.unacked.retain(|msg| msg.target != target);
+        let dropped = before - self.unacked.len();
+        if dropped != 0 {
+            debug!("dropping {dropped} unacknowledged message(s) (for {target})");
+        }
+    }
+}
+
+/// Returned by [`Reliable::pop_resend`].
+pub(crate) enum Resend {
+    /// Send the `packet` to `target` again.
+    Send(SocketAddr, Vec<u8>),
+    /// Message to `target` was dropped.
+    Dropped(SocketAddr),
+}
+
+/// Returns a random epoch.
+fn new_epoch() -> u64 {
+    let mut bytes = [0; 8];
+    match getrandom(&mut bytes) {
+        Ok(()) => u64::from_ne_bytes(bytes),
+        Err(err) => {
+            warn!("unable to get random bytes, using a fallback: {err}");
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+        }
+    }
+}
@@ -8,15 +8,14 @@
 use std::fmt;
 use std::future::Future;
 use std::future::{ready, Ready};
-use std::net::SocketAddr;
 
 use heph::actor_ref::{ActorGroup, ActorRef, SendError, SendValue};
 
 use crate::net_relay::Route;
 
-impl<F, M, Fut, E> Route<M> for F
+impl<F, M, A, Fut, E> Route<M, A> for F
 where
-    F: FnMut(M, SocketAddr) -> Fut,
+    F: FnMut(M, A) -> Fut,
     Fut: Future<Output = Result<(), E>>,
     E: fmt::Display,
 {
@@ -24,7 +23,7 @@ where
         where Self: 'a;
     type Error = E;
 
-    fn route<'a>(&'a mut self, msg: M, source: SocketAddr) -> Self::Route<'a> {
+    fn route<'a>(&'a mut self, msg: M, source: A) -> Self::Route<'a> {
         (self)(msg, source)
     }
 }
@@ -51,7 +50,7 @@ impl<M> Clone for Relay<M> {
     }
 }
 
-impl<M> Route<M> for Relay<M>
+impl<M, A> Route<M, A> for Relay<M>
 where
     M: 'static + Unpin,
 {
@@ -59,7 +58,7 @@ where
     type Route<'a> = SendValue<'a, M>
         where Self: 'a;
 
-    fn route<'a>(&'a mut self, msg: M, _: SocketAddr) -> Self::Route<'a> {
+    fn route<'a>(&'a mut self, msg: M, _: A) -> Self::Route<'a> {
         self.actor_ref.send(msg)
     }
 }
@@ -100,7 +99,7 @@ impl<M> Clone for RelayGroup<M> {
     }
 }
 
-impl<M> Route<M> for RelayGroup<M>
+impl<M, A> Route<M, A> for RelayGroup<M>
 where
     M: Clone + Unpin + 'static,
 {
@@ -108,7 +107,7 @@ where
     type Route<'a> = Ready<Result<(), Self::Error>>
         where Self: 'a;
 
-    fn route<'a>(&'a mut self, msg: M, _: SocketAddr) -> Self::Route<'a> {
+    fn route<'a>(&'a mut self, msg: M, _: A) -> Self::Route<'a> {
         _ = match self.delivery {
             Delivery::ToAll => self.actor_group.try_send_to_all(msg),
             Delivery::ToOne => self.actor_group.try_send_to_one(msg),
@@ -121,12 +120,12 @@ where
 #[derive(Copy, Clone, Debug)]
 pub struct Drop;
 
-impl<M> Route<M> for Drop {
+impl<M, A> Route<M, A> for Drop {
     type Error = !;
     type Route<'a> = Ready<Result<(), Self::Error>>
         where Self: 'a;
 
-    fn route<'a>(&'a mut self, _: M, _: SocketAddr) -> Self::Route<'a> {
+    fn route<'a>(&'a mut self, _: M, _: A) -> Self::Route<'a> {
         ready(Ok(()))
     }
 }
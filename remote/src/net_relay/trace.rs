@@ -0,0 +1,202 @@
+//! Module with the trace context propagated with relayed messages.
+
+use std::collections::hash_map::RandomState;
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::str::FromStr;
+
+use getrandom::getrandom;
+use serde::de::{self, Deserialize, Deserializer, Unexpected, Visitor};
+use serde::{Serialize, Serializer};
+
+/// Version of the W3C Trace Context format we support.
+const VERSION: &str = "00";
+/// Trace flags, we always mark the trace as sampled.
+const FLAGS: &str = "01";
+/// Length of the formatted trace context, `00-{trace_id}-{span_id}-01`.
+const FORMATTED_LEN: usize = 2 + 1 + 32 + 1 + 16 + 1 + 2;
+
+/// Trace context propagated with relayed messages.
+///
+/// The trace context allows a trace, e.g. of a request handled by actors on
+/// multiple nodes, to continue across nodes. It's send along with the message
+/// (see [`RelayMessage::RelayTraced`] and [`UdpRelayMessage::RelayTraced`]) and
+/// passed to the router on the remote node using [`Route::route_traced`].
+///
+/// The context uses the [W3C Trace Context] `traceparent` format, both for its
+/// [`fmt::Display`] and [`FromStr`] implementations and on the wire, so it can
+/// be converted to and from the context used by other tracing libraries, e.g.
+/// OpenTelemetry.
+///
+/// [`RelayMessage::RelayTraced`]: crate::net_relay::RelayMessage::RelayTraced
+/// [`UdpRelayMessage::RelayTraced`]: crate::net_relay::UdpRelayMessage::RelayTraced
+/// [`Route::route_traced`]: crate::net_relay::Route::route_traced
+/// [W3C Trace Context]: https://www.w3.org/TR/trace-context/
+///
+/// # Examples
+///
+/// ```
+/// use heph_remote::net_relay::TraceContext;
+///
+/// let trace = TraceContext::start();
+/// // Continuing the trace on the remote node.
+/// let child = trace.child();
+/// assert_eq!(trace.trace_id(), child.trace_id());
+/// assert_ne!(trace.span_id(), child.span_id());
+///
+/// let trace: TraceContext = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".parse().unwrap();
+/// assert_eq!(trace.trace_id(), 0x4bf92f3577b34da6a3ce929d0e0e4736);
+/// assert_eq!(trace.span_id(), 0x00f067aa0ba902b7);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TraceContext {
+    trace_id: u128,
+    span_id: u64,
+}
+
+impl TraceContext {
+    /// Start a new trace, using a random trace and span id.
+    pub fn start() -> TraceContext {
+        let trace_id = (u128::from(random()) << 64) | u128::from(random());
+        TraceContext {
+            trace_id: trace_id.max(1),
+            span_id: random().max(1),
+        }
+    }
+
+    /// Create a trace context from existing ids, e.g. from another tracing
+    /// library.
+    ///
+    /// Returns `None` if either id is zero, which is invalid.
+    pub const fn from_ids(trace_id: u128, span_id: u64) -> Option<TraceContext> {
+        if trace_id == 0 || span_id == 0 {
+            None
+        } else {
+            Some(TraceContext { trace_id, span_id })
+        }
+    }
+
+    /// Returns the trace id.
+    pub const fn trace_id(&self) -> u128 {
+        self.trace_id
+    }
+
+    /// Returns the span id.
+    pub const fn span_id(&self) -> u64 {
+        self.span_id
+    }
+
+    /// Create a child context, part of the same trace, with a new random span
+    /// id.
+    pub fn child(&self) -> TraceContext {
+        TraceContext {
+            trace_id: self.trace_id,
+            span_id: random().max(1),
+        }
+    }
+}
+
+/// Returns a random number.
+fn random() -> u64 {
+    let mut bytes = [0; 8];
+    if getrandom(&mut bytes).is_ok() {
+        u64::from_ne_bytes(bytes)
+    } else {
+        // Fallback to the random keys used by `RandomState`, not great but
+        // good enough for ids.
+        RandomState::new().build_hasher().finish()
+    }
+}
+
+impl fmt::Display for TraceContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{VERSION}-{:032x}-{:016x}-{FLAGS}",
+            self.trace_id, self.span_id
+        )
+    }
+}
+
+impl FromStr for TraceContext {
+    type Err = InvalidTraceContext;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        // NOTE: the trace flags are ignored.
+        let b = input.as_bytes();
+        if b.len() != FORMATTED_LEN || !input.starts_with(VERSION) {
+            return Err(InvalidTraceContext);
+        }
+        if b[2] != b'-' || b[35] != b'-' || b[52] != b'-' {
+            return Err(InvalidTraceContext);
+        }
+        let trace_id = parse_hex(&b[3..35]).ok_or(InvalidTraceContext)?;
+        let span_id = parse_hex(&b[36..52]).ok_or(InvalidTraceContext)?;
+        _ = parse_hex(&b[53..55]).ok_or(InvalidTraceContext)?;
+        #[allow(clippy::cast_possible_truncation)] // 16 hex characters fit in 64 bits.
+        TraceContext::from_ids(trace_id, span_id as u64).ok_or(InvalidTraceContext)
+    }
+}
+
+/// Parse lowercase hexadecimal `input`, must be at most 32 characters long.
+fn parse_hex(input: &[u8]) -> Option<u128> {
+    let mut value = 0;
+    for b in input {
+        let n = match *b {
+            b'a'..=b'f' => b - b'a' + 10,
+            b'0'..=b'9' => b - b'0',
+            _ => return None,
+        };
+        value = (value << 4) | u128::from(n);
+    }
+    Some(value)
+}
+
+/// Error returned when parsing a [`TraceContext`] fails.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct InvalidTraceContext;
+
+impl fmt::Display for InvalidTraceContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid trace context")
+    }
+}
+
+impl Error for InvalidTraceContext {}
+
+// NOTE: manually implementing this instead of deriving to not pull in a bunch
+// of dependencies.
+impl<'de> Deserialize<'de> for TraceContext {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct StrVisitor;
+
+        impl<'de> Visitor<'de> for StrVisitor {
+            type Value = TraceContext;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("trace context string")
+            }
+
+            fn visit_str<E>(self, input: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                input
+                    .parse()
+                    .map_err(|_| de::Error::invalid_value(Unexpected::Str(input), &self))
+            }
+        }
+
+        deserializer.deserialize_str(StrVisitor)
+    }
+}
+
+impl Serialize for TraceContext {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
@@ -0,0 +1,233 @@
+//! Module with the [`Transport`] trait and the relay actor build on top of it.
+
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::pin;
+
+use heph::actor::{self, NoMessages};
+use heph_rt as rt;
+use heph_rt::net::uds::{UnixAddr, UnixStream};
+use heph_rt::net::TcpStream;
+use heph_rt::trace::Trace;
+use heph_rt::util::either;
+use log::warn;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::net_relay::handshake::{handshake, Registry};
+use crate::net_relay::tcp::route_messages;
+use crate::net_relay::uuid::UuidGenerator;
+use crate::net_relay::{
+    finish_relay_trace, Message, RelayMessage, Route, Serde, TraceContext, UdsPeer,
+};
+
+const INITIAL_BUF_SIZE: usize = 1 << 12; // 4kb.
+
+/// Transport used by the net relay to exchange messages with a peer.
+///
+/// The relay handles the routing and (de)serialisation of the messages, the
+/// transport only has to move bytes between the relay and its peer. This
+/// allows messages to be relayed over other systems, e.g. a message queue,
+/// using [`Config::transport`].
+///
+/// The bytes are send and received in frames. A frame send by the relay
+/// contains one or more complete serialised messages. A received frame may
+/// contain any number of messages, including partial messages, as the relay
+/// buffers the data until a message is complete. This means stream based
+/// transports, such as [`TcpStream`], can send and receive the bytes as is.
+///
+/// Both [`send_frame`] and [`recv_frame`] can be called while another call
+/// (to the other method) is in progress, which is why they take `&self`.
+///
+/// [`Config::transport`]: crate::net_relay::Config::transport
+/// [`send_frame`]: Transport::send_frame
+/// [`recv_frame`]: Transport::recv_frame
+pub trait Transport: Sized {
+    /// Peer to connect to, this is the argument of the relay actor.
+    type Peer;
+
+    /// Address of the peer, used as source address when [routing] incoming
+    /// messages.
+    ///
+    /// [routing]: Route
+    type Address: Clone;
+
+    /// Connect to `peer`.
+    ///
+    /// Returns the transport and the address of the peer.
+    fn connect<RT>(
+        rt: &RT,
+        peer: Self::Peer,
+    ) -> impl Future<Output = io::Result<(Self, Self::Address)>>
+    where
+        RT: rt::Access;
+
+    /// Send the frame in `buf` to the peer.
+    ///
+    /// Returns the buffer, allowing it to be reused.
+    fn send_frame(&self, buf: Vec<u8>) -> impl Future<Output = io::Result<Vec<u8>>>;
+
+    /// Receive a frame from the peer, appending it to `buf`.
+    ///
+    /// The bytes must be written into the spare capacity of `buf`, without
+    /// touching the bytes already in it. If no bytes are added to `buf` the
+    /// relay considers the peer to have closed the connection.
+    fn recv_frame(&self, buf: Vec<u8>) -> impl Future<Output = io::Result<Vec<u8>>>;
+}
+
+/// Uses a TCP connection, see [`Tcp`].
+///
+/// [`Tcp`]: crate::net_relay::Tcp
+impl Transport for TcpStream {
+    type Peer = SocketAddr;
+    type Address = SocketAddr;
+
+    async fn connect<RT>(rt: &RT, address: SocketAddr) -> io::Result<(Self, SocketAddr)>
+    where
+        RT: rt::Access,
+    {
+        let stream = TcpStream::connect(rt, address).await?;
+        stream.set_nodelay(true)?;
+        Ok((stream, address))
+    }
+
+    async fn send_frame(&self, buf: Vec<u8>) -> io::Result<Vec<u8>> {
+        self.send_all(buf).await
+    }
+
+    async fn recv_frame(&self, buf: Vec<u8>) -> io::Result<Vec<u8>> {
+        self.recv(buf).await
+    }
+}
+
+/// Uses a Unix stream, see [`Uds`].
+///
+/// [`Uds`]: crate::net_relay::Uds
+impl Transport for UnixStream {
+    type Peer = UdsPeer;
+    type Address = UnixAddr;
+
+    async fn connect<RT>(rt: &RT, peer: UdsPeer) -> io::Result<(Self, UnixAddr)>
+    where
+        RT: rt::Access,
+    {
+        let stream = peer.connect(rt).await?;
+        Ok((stream, peer.address().clone()))
+    }
+
+    async fn send_frame(&self, buf: Vec<u8>) -> io::Result<Vec<u8>> {
+        self.send_all(buf).await
+    }
+
+    async fn recv_frame(&self, buf: Vec<u8>) -> io::Result<Vec<u8>> {
+        self.recv(buf).await
+    }
+}
+
+/// Actor that relays messages using the transport `T`.
+///
+/// It receives `Out`going messages from it's inbox and sends them to the
+/// `peer` using the transport. Any `In`coming message received by the
+/// transport will be routed using the `R`outer.
+///
+/// If a `registry` is provided it's exchanged with the peer before any message
+/// is send or received.
+pub(crate) async fn remote_relay<T, S, Out, In, R, RT>(
+    mut ctx: actor::Context<RelayMessage<Out>, RT>,
+    peer: T::Peer,
+    mut router: R,
+    registry: Option<Registry>,
+) -> io::Result<()>
+where
+    T: Transport,
+    S: Serde,
+    Out: Serialize,
+    In: DeserializeOwned,
+    RT: rt::Access,
+    R: Route<In, T::Address>,
+{
+    let (transport, source) = T::connect(ctx.runtime_ref(), peer).await?;
+
+    let mut recv_buf = Vec::with_capacity(INITIAL_BUF_SIZE);
+    if let Some(registry) = registry {
+        recv_buf = handshake::<S, T>(&transport, &registry, recv_buf).await?;
+        // Peer could have send messages right after the handshake.
+        route_messages::<S, R, In, _>(&mut router, &mut recv_buf, &source).await?;
+    }
+
+    let mut uuid_gen = UuidGenerator::new();
+    let mut send_buf = Vec::with_capacity(INITIAL_BUF_SIZE);
+
+    // Number of bytes of a partially received message left in the buffer.
+    let mut partial = recv_buf.len();
+    recv_buf.reserve(INITIAL_BUF_SIZE);
+    let mut recv_data = pin!(transport.recv_frame(recv_buf));
+    loop {
+        match either(ctx.receive_next(), recv_data.as_mut()).await {
+            // Received an outgoing message we want to relay to the peer.
+            Ok(Ok(RelayMessage::Relay(msg))) => {
+                send_buf =
+                    send_message::<T, S, Out>(&transport, send_buf, &mut uuid_gen, &msg, None)
+                        .await?;
+                send_buf.clear();
+            }
+            Ok(Ok(RelayMessage::RelayTraced(msg, trace))) => {
+                let timing = ctx.start_trace();
+                send_buf = send_message::<T, S, Out>(
+                    &transport,
+                    send_buf,
+                    &mut uuid_gen,
+                    &msg,
+                    Some(trace),
+                )
+                .await?;
+                send_buf.clear();
+                finish_relay_trace(&mut ctx, timing, trace);
+            }
+            Ok(Ok(RelayMessage::Terminate) | Err(NoMessages)) => return Ok(()),
+            // Peer closed the connection.
+            Err(Ok(buf)) if buf.len() == partial => return Ok(()),
+            // Received some incoming data.
+            Err(Ok(mut buf)) => {
+                route_messages::<S, R, In, _>(&mut router, &mut buf, &source).await?;
+                partial = buf.len();
+                // Ensure there is space to receive into.
+                buf.reserve(INITIAL_BUF_SIZE);
+                recv_data.set(transport.recv_frame(buf));
+            }
+            // Error receiving data.
+            Err(Err(err)) => return Err(err),
+        }
+    }
+}
+
+/// Send a `msg` to the peer, using `transport`.
+async fn send_message<T, S, M>(
+    transport: &T,
+    mut buf: Vec<u8>,
+    uuid_gen: &mut UuidGenerator,
+    msg: &M,
+    trace: Option<TraceContext>,
+) -> io::Result<Vec<u8>>
+where
+    T: Transport,
+    S: Serde,
+    M: Serialize,
+{
+    // Serialise the message to our buffer first.
+    let uuid = uuid_gen.next();
+    let msg = Message {
+        uuid,
+        msg,
+        seq: None,
+        trace,
+    };
+    if let Err(err) = S::to_buf(&mut buf, &msg) {
+        warn!("error serialising message: {err}");
+        // Don't want to stop the actor for this.
+        return Ok(buf);
+    }
+
+    transport.send_frame(buf).await
+}
@@ -1,20 +1,27 @@
 //! Module with the UDP implementation of the net relay.
 
+use std::future::pending;
 use std::io;
 use std::net::SocketAddr;
 use std::pin::pin;
+use std::time::Instant;
 
 use heph::actor::{self, NoMessages};
 use heph::messages::Terminate;
 use heph_rt::net::UdpSocket;
+use heph_rt::timer::{DeadlinePassed, Timer};
+use heph_rt::trace::Trace;
 use heph_rt::util::either;
 use heph_rt::{self as rt, Signal};
-use log::warn;
+use log::{debug, warn};
 use serde::de::DeserializeOwned;
 use serde::ser::Serialize;
 
+use crate::net_relay::health::{CircuitBreaker, Health};
+use crate::net_relay::qos::{Qos, QosClass, Queued, Queues};
+use crate::net_relay::reliable::{Ack, AtLeastOnce, Reliable, Resend, SeqNum};
 use crate::net_relay::uuid::UuidGenerator;
-use crate::net_relay::{Message, Route, Serde};
+use crate::net_relay::{finish_relay_trace, Message, Route, Serde, TraceContext};
 
 const MAX_PACKET_SIZE: usize = 1 << 16; // ~65kb.
 const INITIAL_SEND_BUF_SIZE: usize = 1 << 12; // 4kb.
@@ -56,10 +63,57 @@ pub enum UdpRelayMessage<M> {
         /// Target to send the message to.
         target: SocketAddr,
     },
+    /// Relay message `M` to `target` along with a trace context.
+    RelayTraced {
+        /// Message to send.
+        message: M,
+        /// Target to send the message to.
+        target: SocketAddr,
+        /// Trace context to send along with the message.
+        trace: TraceContext,
+    },
+    /// Relay message `M` to `target` using the Quality of Service `class`,
+    /// see [`Qos`].
+    ///
+    /// [`Qos`]: crate::net_relay::Qos
+    RelayQos {
+        /// Message to send.
+        message: M,
+        /// Target to send the message to.
+        target: SocketAddr,
+        /// QoS class of the message.
+        class: QosClass,
+        /// Trace context to send along with the message, if any.
+        trace: Option<TraceContext>,
+    },
     /// Stop the relay.
     Terminate,
 }
 
+impl<M> UdpRelayMessage<M> {
+    /// Returns the message, target, trace context and QoS class, or `None` for
+    /// [`UdpRelayMessage::Terminate`].
+    fn into_parts(self) -> Option<Queued<M>> {
+        match self {
+            UdpRelayMessage::Relay { message, target } => {
+                Some((message, target, None, QosClass::Standard))
+            }
+            UdpRelayMessage::RelayTraced {
+                message,
+                target,
+                trace,
+            } => Some((message, target, Some(trace), QosClass::Standard)),
+            UdpRelayMessage::RelayQos {
+                message,
+                target,
+                class,
+                trace,
+            } => Some((message, target, trace, class)),
+            UdpRelayMessage::Terminate => None,
+        }
+    }
+}
+
 impl<M> From<Terminate> for UdpRelayMessage<M> {
     fn from(_: Terminate) -> UdpRelayMessage<M> {
         UdpRelayMessage::Terminate
@@ -88,73 +142,237 @@ pub(crate) async fn remote_relay<S, Out, In, R, RT>(
     mut ctx: actor::Context<UdpRelayMessage<Out>, RT>,
     local_address: SocketAddr,
     mut router: R,
+    at_least_once: Option<AtLeastOnce>,
+    circuit_breaker: Option<CircuitBreaker>,
+    qos: Option<Qos>,
 ) -> io::Result<()>
 where
     S: Serde,
     Out: Serialize,
     In: DeserializeOwned,
-    RT: rt::Access,
+    RT: rt::Access + Clone,
     R: Route<In>,
 {
     let socket = UdpSocket::bind(ctx.runtime_ref(), local_address).await?;
 
+    let rt = ctx.runtime_ref().clone();
     let mut uuid_gen = UuidGenerator::new();
+    let mut reliable = at_least_once.map(Reliable::new);
+    let mut health = circuit_breaker.map(Health::new);
     let mut send_buf = Vec::with_capacity(INITIAL_SEND_BUF_SIZE);
+    let mut queues = Queues::new();
+    // Class of the last packet send, used to only change the DSCP marking of
+    // the socket when needed.
+    let mut marked_class = None;
 
     let mut recv_data = pin!(socket.recv_from(Vec::with_capacity(MAX_PACKET_SIZE)));
     loop {
-        match either(ctx.receive_next(), recv_data.as_mut()).await {
+        // Don't accept more messages if we can't store them in the resend
+        // buffer.
+        let accept = !reliable.as_ref().is_some_and(Reliable::is_full);
+        let resend_deadline = reliable.as_ref().and_then(Reliable::next_resend);
+        let receive_msg = async {
+            if accept {
+                ctx.receive_next().await
+            } else {
+                pending().await
+            }
+        };
+        let resend = async {
+            match resend_deadline {
+                Some(deadline) => Timer::at(rt.clone(), deadline).await,
+                None => pending().await,
+            }
+        };
+        let event = either(either(receive_msg, recv_data.as_mut()), resend).await;
+        match event {
             // Received an outgoing message we want to relay to a remote
             // actor.
-            Ok(Ok(UdpRelayMessage::Relay { message, target })) => {
-                send_buf =
-                    send_message::<S, Out>(&socket, send_buf, &mut uuid_gen, target, &message)
-                        .await?;
-                send_buf.clear();
+            Ok(Ok(Ok(msg))) => {
+                let Some(msg) = msg.into_parts() else {
+                    // Received `UdpRelayMessage::Terminate`.
+                    return Ok(());
+                };
+                queues.push(msg);
+                let mut terminate = false;
+                if let Some(qos) = qos.as_ref() {
+                    // Collect the messages already in our inbox, so that we
+                    // can send them in order of their QoS class.
+                    while queues.len() < qos.max_queued()
+                        && !reliable.as_ref().is_some_and(Reliable::is_full)
+                    {
+                        match ctx.try_receive_next().map(UdpRelayMessage::into_parts) {
+                            Ok(Some(msg)) => queues.push(msg),
+                            Ok(None) => {
+                                // Still send the queued messages before
+                                // stopping.
+                                terminate = true;
+                                break;
+                            }
+                            Err(_) => break,
+                        }
+                    }
+                }
+
+                while let Some((message, target, trace, class)) = queues.pop() {
+                    let timing = trace.and_then(|_| ctx.start_trace());
+                    let allowed = health
+                        .as_mut()
+                        .is_none_or(|health| health.allow(target, Instant::now()));
+                    let seq = reliable.as_ref().map(|r| r.next_seq(target));
+                    if !allowed {
+                        debug!("peer {target} is unhealthy, dropping message");
+                    } else if serialise_message::<S, Out>(
+                        &mut send_buf,
+                        &mut uuid_gen,
+                        target,
+                        &message,
+                        seq,
+                        trace,
+                    ) {
+                        if let Some(reliable) = reliable.as_mut() {
+                            reliable.add(target, send_buf.clone());
+                        }
+                        if let Some(qos) = qos.as_ref() {
+                            mark_class(&socket, qos, &mut marked_class, class)?;
+                        }
+                        send_buf = match send_packet(&socket, send_buf, target).await {
+                            Ok(send_buf) => {
+                                // When using at-least-once delivery the
+                                // acknowledgement marks the success.
+                                if let (Some(health), None) = (health.as_mut(), reliable.as_ref()) {
+                                    health.success(target);
+                                }
+                                send_buf
+                            }
+                            Err(err) => {
+                                send_failed(health.as_mut(), reliable.as_mut(), target, err)?;
+                                Vec::with_capacity(INITIAL_SEND_BUF_SIZE)
+                            }
+                        };
+                    }
+                    send_buf.clear();
+                    if let Some(trace) = trace {
+                        finish_relay_trace(&mut ctx, timing, trace);
+                    }
+                }
+                if terminate {
+                    return Ok(());
+                }
             }
-            Ok(Ok(UdpRelayMessage::Terminate) | Err(NoMessages)) => return Ok(()),
+            Ok(Ok(Err(NoMessages))) => return Ok(()),
             // Received an incoming packet.
-            Err(Ok((mut buf, source))) => {
-                route_message::<S, R, In>(&mut router, &buf, source).await?;
+            Ok(Err(Ok((mut buf, source)))) => {
+                if let Some(health) = health.as_mut() {
+                    health.success(source);
+                }
+                if let Some(reliable) = reliable.as_mut() {
+                    send_buf = receive_reliable::<S, R, In>(
+                        &socket,
+                        &mut router,
+                        reliable,
+                        &buf,
+                        source,
+                        send_buf,
+                    )
+                    .await?;
+                    send_buf.clear();
+                } else {
+                    route_message::<S, R, In>(&mut router, &buf, source).await?;
+                }
                 buf.clear();
                 recv_data.set(socket.recv_from(buf));
             }
             // Error receiving a packet.
-            Err(Err(err)) => return Err(err),
+            Ok(Err(Err(err))) => return Err(err),
+            // Need to resend unacknowledged messages.
+            Err(DeadlinePassed) => {
+                if let Some(reliable) = reliable.as_mut() {
+                    let now = Instant::now();
+                    while let Some(resend) = reliable.pop_resend(now) {
+                        match resend {
+                            Resend::Send(target, packet) => {
+                                if let Err(err) = send_packet(&socket, packet, target).await {
+                                    send_failed(health.as_mut(), Some(reliable), target, err)?;
+                                }
+                            }
+                            Resend::Dropped(target) => {
+                                if let Some(health) = health.as_mut() {
+                                    peer_failed(health, Some(reliable), target);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
         }
     }
 }
 
-/// Send a `msg` to a remote actor at `target` address, using `socket`.
-async fn send_message<S, M>(
-    socket: &UdpSocket,
-    mut buf: Vec<u8>,
+/// Serialise `msg` for a remote actor at `target` address into `buf`.
+///
+/// Returns `false` if the message can't be send, the error is logged using
+/// `warn!`.
+fn serialise_message<S, M>(
+    buf: &mut Vec<u8>,
     uuid_gen: &mut UuidGenerator,
     target: SocketAddr,
     msg: &M,
-) -> io::Result<Vec<u8>>
+    seq: Option<SeqNum>,
+    trace: Option<TraceContext>,
+) -> bool
 where
     S: Serde,
     M: Serialize,
 {
-    // Serialise the message to our buffer first.
     let uuid = uuid_gen.next();
-    let msg = Message { uuid, msg };
-    if let Err(err) = S::to_buf(&mut buf, &msg) {
+    let msg = Message {
+        uuid,
+        msg,
+        seq,
+        trace,
+    };
+    if let Err(err) = S::to_buf(buf, &msg) {
         warn!("error serialising message (for {target}): {err}");
         // Don't want to stop the actor for this.
-        return Ok(buf);
+        return false;
     }
 
-    // Then send the buffer as a single packet.
     if buf.len() > MAX_PACKET_SIZE {
         let len = buf.len();
         warn!(
             "message too large (for {target}): (serialised) message size {len}, max is {MAX_PACKET_SIZE}",
         );
         // Don't want to stop the actor for this.
-        return Ok(buf);
+        return false;
+    }
+    true
+}
+
+/// Mark the packets send on `socket` with the DSCP for `class`, if it differs
+/// from the `marked` class (`None` if nothing was marked yet).
+///
+/// Resends (when using at-least-once delivery) and acknowledgements use the
+/// marking of the last message send.
+fn mark_class(
+    socket: &UdpSocket,
+    qos: &Qos,
+    marked: &mut Option<QosClass>,
+    class: QosClass,
+) -> io::Result<()> {
+    if *marked == Some(class) {
+        return Ok(());
     }
+    if let Some(dscp) = qos.dscp(class) {
+        // DSCP is stored in the upper six bits of the TOS field.
+        socket.set_tos(u32::from(dscp) << 2)?;
+    }
+    *marked = Some(class);
+    Ok(())
+}
+
+/// Send `buf` as a single packet to `target` address, using `socket`.
+async fn send_packet(socket: &UdpSocket, buf: Vec<u8>, target: SocketAddr) -> io::Result<Vec<u8>> {
     let (buf, bytes_send) = socket.send_to(buf, target).await?;
     if bytes_send == buf.len() {
         Ok(buf)
@@ -163,6 +381,34 @@ where
     }
 }
 
+/// Handle the error `err` sending a packet to `target`.
+///
+/// Without a circuit breaker (`health`) the error is returned, otherwise it's
+/// logged and counted as a failure for `target`.
+fn send_failed(
+    health: Option<&mut Health>,
+    reliable: Option<&mut Reliable>,
+    target: SocketAddr,
+    err: io::Error,
+) -> io::Result<()> {
+    let Some(health) = health else {
+        return Err(err);
+    };
+    warn!("error sending message (to {target}): {err}");
+    peer_failed(health, reliable, target);
+    Ok(())
+}
+
+/// Mark a failure for `target`, dropping all unacknowledged messages for it if
+/// its circuit is opened.
+fn peer_failed(health: &mut Health, reliable: Option<&mut Reliable>, target: SocketAddr) {
+    if health.failure(target, Instant::now()) {
+        if let Some(reliable) = reliable {
+            reliable.remove_target(target);
+        }
+    }
+}
+
 /// Routes a message in `buf` using `router`.
 ///
 /// Returns an error if the message can't be routed. Errors from deserialising
@@ -174,13 +420,7 @@ where
     M: DeserializeOwned,
 {
     match S::from_slice::<Message<M>>(buf) {
-        Ok(msg) => match router.route(msg.msg, source).await {
-            Ok(()) => Ok(()),
-            Err(err) => {
-                let msg = format!("failed to route message (from {source}): {err}");
-                Err(io::Error::new(io::ErrorKind::Other, msg))
-            }
-        },
+        Ok(msg) => route(router, msg.msg, source, msg.trace).await,
         Err(err) => {
             warn!("error deserialising message (from {source}): {err}");
             // Don't want to stop the relay actor over this.
@@ -188,3 +428,74 @@ where
         }
     }
 }
+
+/// Same as [`route_message`], but using at-least-once delivery.
+///
+/// This handles acknowledgements, acknowledges received messages (using
+/// `send_buf` to serialise the acknowledgement) and drops duplicate messages.
+async fn receive_reliable<S, R, M>(
+    socket: &UdpSocket,
+    router: &mut R,
+    reliable: &mut Reliable,
+    buf: &[u8],
+    source: SocketAddr,
+    mut send_buf: Vec<u8>,
+) -> io::Result<Vec<u8>>
+where
+    S: Serde,
+    R: Route<M>,
+    M: DeserializeOwned,
+{
+    if let Ok(ack) = S::from_slice::<Ack>(buf) {
+        reliable.acknowledged(ack, source);
+        return Ok(send_buf);
+    }
+
+    let msg = match S::from_slice::<Message<M>>(buf) {
+        Ok(msg) => msg,
+        Err(err) => {
+            warn!("error deserialising message (from {source}): {err}");
+            // Don't want to stop the relay actor over this.
+            return Ok(send_buf);
+        }
+    };
+    let Some(seq) = msg.seq else {
+        // Remote node doesn't use at-least-once delivery.
+        route(router, msg.msg, source, msg.trace).await?;
+        return Ok(send_buf);
+    };
+
+    // Always acknowledge the message, even if it's a duplicate, as our
+    // previous acknowledgement might have been lost.
+    if let Err(err) = S::to_buf(&mut send_buf, &Ack(seq)) {
+        warn!("error serialising acknowledgement (for {source}): {err}");
+    } else {
+        send_buf = send_packet(socket, send_buf, source).await?;
+    }
+
+    if reliable.received(seq, source) {
+        route(router, msg.msg, source, msg.trace).await?;
+    } else {
+        debug!("dropping duplicate message (from {source})");
+    }
+    Ok(send_buf)
+}
+
+/// Route `msg` from `source`, send with `trace` context, using `router`.
+async fn route<R, M>(
+    router: &mut R,
+    msg: M,
+    source: SocketAddr,
+    trace: Option<TraceContext>,
+) -> io::Result<()>
+where
+    R: Route<M>,
+{
+    match router.route_traced(msg, source, trace).await {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            let msg = format!("failed to route message (from {source}): {err}");
+            Err(io::Error::new(io::ErrorKind::Other, msg))
+        }
+    }
+}
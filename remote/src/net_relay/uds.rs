@@ -0,0 +1,59 @@
+//! Module with the Unix Domain Socket (UDS) specific types of the net relay.
+//!
+//! The relay itself is implemented in the `transport` module.
+
+use std::io;
+
+use heph_rt as rt;
+use heph_rt::net::uds::{UnixAddr, UnixListener, UnixStream};
+use log::warn;
+
+/// Peer of a net relay using a [`Uds`] connection.
+///
+/// A multi-process deployment, for example a supervisor process with a child
+/// process per CPU core, can use [`UdsPeer::Accept`] in one process and
+/// [`UdsPeer::Connect`] (using the same address) in the other process.
+///
+/// [`Uds`]: crate::net_relay::Uds
+#[derive(Clone, Debug)]
+pub enum UdsPeer {
+    /// Connect to a Unix socket listening on the address.
+    Connect(UnixAddr),
+    /// Bind a Unix socket to the address and accept a single connection.
+    ///
+    /// Once the connection is accepted the socket file is removed, allowing
+    /// the address to be reused, e.g. if the relay is restarted.
+    Accept(UnixAddr),
+}
+
+impl UdsPeer {
+    /// Returns the address of the peer.
+    pub const fn address(&self) -> &UnixAddr {
+        match self {
+            UdsPeer::Connect(address) | UdsPeer::Accept(address) => address,
+        }
+    }
+
+    /// Connect to, or accept a connection from, the peer.
+    pub(super) async fn connect<RT>(&self, rt: &RT) -> io::Result<UnixStream>
+    where
+        RT: rt::Access,
+    {
+        match self {
+            UdsPeer::Connect(address) => UnixStream::connect(rt, address.clone()).await,
+            UdsPeer::Accept(address) => {
+                let listener = UnixListener::bind(rt, address.clone()).await?;
+                let result = listener.accept().await;
+                if let Some(path) = address.as_pathname() {
+                    if let Err(err) = std::fs::remove_file(path) {
+                        warn!(
+                            "failed to remove Unix socket file '{}': {err}",
+                            path.display()
+                        );
+                    }
+                }
+                result.map(|(stream, _)| stream)
+            }
+        }
+    }
+}
@@ -290,6 +290,18 @@ impl ThreadSafe {
     {
         self.rt.spawn_future(future, options);
     }
+
+    /// Returns the runtime-wide shared resource of type `T`, if any.
+    ///
+    /// See [`Setup::with_resource`] for more documentation.
+    ///
+    /// [`Setup::with_resource`]: crate::Setup::with_resource
+    pub fn resource<T>(&self) -> Option<Arc<T>>
+    where
+        T: Send + std::marker::Sync + 'static,
+    {
+        self.rt.resource()
+    }
 }
 
 impl From<&Runtime> for ThreadSafe {
@@ -391,7 +403,12 @@ where
 /// actors.
 ///
 /// It implements [`Spawn`] to spawn new thread-safe actors and [`spawn_future`]
-/// to spawn thread-safe [`Future`]s.
+/// to spawn thread-safe [`Future`]s. It also implements [`rt::Access`], which
+/// means it can be used to create timers, e.g. [`Timer`], which can be awaited
+/// using [`sync::Context::block_on`].
+///
+/// [`rt::Access`]: crate::Access
+/// [`Timer`]: crate::timer::Timer
 ///
 /// This is usually a part of the actor's [`sync::Context`], see it for more
 /// information.
@@ -420,6 +437,62 @@ impl Sync {
     {
         self.rt.spawn_future(future, options);
     }
+
+    /// Returns the runtime-wide shared resource of type `T`, if any.
+    ///
+    /// See [`Setup::with_resource`] for more documentation.
+    ///
+    /// [`Setup::with_resource`]: crate::Setup::with_resource
+    pub fn resource<T>(&self) -> Option<Arc<T>>
+    where
+        T: Send + std::marker::Sync + 'static,
+    {
+        self.rt.resource()
+    }
+}
+
+impl Access for Sync {}
+
+impl PrivateAccess for Sync {
+    fn submission_queue(&self) -> a10::SubmissionQueue {
+        self.rt.submission_queue().clone()
+    }
+
+    fn add_timer(&mut self, deadline: Instant, waker: task::Waker) -> TimerToken {
+        let token = self.rt.add_timer(deadline, waker);
+        // Synchronous actors don't run on a worker thread, so we need to wake
+        // one to ensure it includes the new timer in its polling timeout.
+        self.rt.wake_workers(1);
+        token
+    }
+
+    fn remove_timer(&mut self, deadline: Instant, token: TimerToken) {
+        self.rt.remove_timer(deadline, token);
+    }
+
+    fn cpu(&self) -> Option<usize> {
+        None
+    }
+
+    fn start_trace(&self) -> Option<trace::EventTiming> {
+        trace::start(&self.trace_log)
+    }
+
+    fn finish_trace(
+        &mut self,
+        timing: Option<trace::EventTiming>,
+        substream_id: u64,
+        description: &str,
+        attributes: &[(&str, &dyn trace::AttributeValue)],
+    ) {
+        trace::finish(
+            self.trace_log.as_mut(),
+            timing,
+            substream_id,
+            description,
+            attributes,
+        );
+    }
 }
 
 impl<S, NA> Spawn<S, NA, ThreadSafe> for Sync
@@ -0,0 +1,251 @@
+//! Memory accounting.
+//!
+//! Finding which of hundreds of actors is leaking memory is hard when all you
+//! have is the total memory usage of the process. The [`TrackAllocator`] can be
+//! used as global allocator to attribute heap usage to the actor (or future)
+//! that is currently running.
+//!
+//! When the allocator is installed the memory usage of every thread-local
+//! process is included in the worker metrics, logged when the process receives
+//! the [`User2`] signal, and the memory usage of each process run is included
+//! in the trace level logs of the scheduler.
+//!
+//! [`User2`]: crate::Signal::User2
+//!
+//! # Notes
+//!
+//! Memory is attributed to the process that is running when the allocation or
+//! deallocation is done. This means that memory allocated by one process and
+//! freed by another process (e.g. a message) shows up as allocated memory of
+//! the first process and as deallocated memory of the second. Memory allocated
+//! while no process is running, e.g. by the runtime itself, is not attributed
+//! to any process.
+//!
+//! # Examples
+//!
+//! Installing the allocator.
+//!
+//! ```
+//! use std::alloc::System;
+//!
+//! use heph_rt::alloc::TrackAllocator;
+//!
+//! #[global_allocator]
+//! static ALLOCATOR: TrackAllocator = TrackAllocator::new(System);
+//! #
+//! # fn main() {}
+//! ```
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Global allocator that attributes allocated memory to the currently running
+/// process.
+///
+/// See the [module documentation] for more information.
+///
+/// [module documentation]: crate::alloc
+#[derive(Debug)]
+pub struct TrackAllocator<A = System> {
+    allocator: A,
+}
+
+impl<A> TrackAllocator<A> {
+    /// Wrap `allocator` to track memory usage.
+    pub const fn new(allocator: A) -> TrackAllocator<A> {
+        TrackAllocator { allocator }
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for TrackAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.allocator.alloc(layout);
+        if !ptr.is_null() {
+            track(|usage| usage.add_allocated(layout.size()));
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.allocator.dealloc(ptr, layout);
+        track(|usage| usage.add_deallocated(layout.size()));
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.allocator.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            track(|usage| usage.add_allocated(layout.size()));
+        }
+        ptr
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.allocator.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            track(|usage| {
+                usage.add_deallocated(layout.size());
+                usage.add_allocated(new_size);
+            });
+        }
+        new_ptr
+    }
+}
+
+/// Set to `true` once the [`TrackAllocator`] tracked an allocation.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// Memory usage of the currently running process, or null if no process is
+    /// running.
+    ///
+    /// NOTE: this must not allocate or have a destructor as it's accessed from
+    /// within the global allocator.
+    static CURRENT: Cell<*const MemoryUsage> = const { Cell::new(ptr::null()) };
+}
+
+/// Call `f` with the memory usage of the currently running process, if any.
+fn track<F: FnOnce(&MemoryUsage)>(f: F) {
+    // NOTE: `try_with` fails if the thread local was already destroyed, which
+    // can happen for allocations made when the thread is stopping.
+    let Ok(current) = CURRENT.try_with(Cell::get) else {
+        return;
+    };
+    // SAFETY: `MemoryUsage::track` ensures the pointer is valid while set.
+    if let Some(usage) = unsafe { current.as_ref() } {
+        if !ENABLED.load(Ordering::Relaxed) {
+            ENABLED.store(true, Ordering::Relaxed);
+        }
+        f(usage);
+    }
+}
+
+/// Returns `true` if the [`TrackAllocator`] is used as global allocator.
+pub(crate) fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Memory usage of a single process.
+///
+/// Uses atomics because thread-safe processes can be run on different threads,
+/// though only one thread runs the process at a time.
+#[derive(Debug)]
+pub(crate) struct MemoryUsage {
+    /// Total number of bytes allocated.
+    allocated: AtomicUsize,
+    /// Total number of bytes deallocated.
+    deallocated: AtomicUsize,
+}
+
+impl MemoryUsage {
+    /// Create a new, empty, `MemoryUsage`.
+    pub(crate) const fn new() -> MemoryUsage {
+        MemoryUsage {
+            allocated: AtomicUsize::new(0),
+            deallocated: AtomicUsize::new(0),
+        }
+    }
+
+    /// Call `f` attributing all (de)allocations it makes to `self`.
+    pub(crate) fn track<F: FnOnce() -> T, T>(&self, f: F) -> T {
+        /// Resets the current memory usage, also on panics.
+        struct Reset(*const MemoryUsage);
+
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                CURRENT.with(|current| current.set(self.0));
+            }
+        }
+
+        let previous = CURRENT.with(|current| current.replace(ptr::addr_of!(*self)));
+        let _reset = Reset(previous);
+        f()
+    }
+
+    /// Total number of bytes allocated.
+    pub(crate) fn allocated(&self) -> usize {
+        self.allocated.load(Ordering::Relaxed)
+    }
+
+    /// Total number of bytes deallocated.
+    pub(crate) fn deallocated(&self) -> usize {
+        self.deallocated.load(Ordering::Relaxed)
+    }
+
+    /// Number of bytes in use, i.e. allocated minus deallocated bytes.
+    ///
+    /// This can be negative if the process deallocated more memory than it
+    /// allocated, e.g. when dropping messages.
+    #[allow(clippy::cast_possible_wrap)]
+    pub(crate) fn in_use(&self) -> isize {
+        self.allocated().wrapping_sub(self.deallocated()) as isize
+    }
+
+    fn add_allocated(&self, size: usize) {
+        _ = self.allocated.fetch_add(size, Ordering::Relaxed);
+    }
+
+    fn add_deallocated(&self, size: usize) {
+        _ = self.deallocated.fetch_add(size, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::{GlobalAlloc, Layout, System};
+
+    use super::{MemoryUsage, TrackAllocator};
+
+    #[test]
+    fn tracks_memory_usage() {
+        let allocator = TrackAllocator::new(System);
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        let usage = MemoryUsage::new();
+
+        let ptr = usage.track(|| unsafe { allocator.alloc(layout) });
+        assert!(!ptr.is_null());
+        assert_eq!(usage.allocated(), 64);
+        assert_eq!(usage.deallocated(), 0);
+        assert_eq!(usage.in_use(), 64);
+
+        let ptr = usage.track(|| unsafe { allocator.realloc(ptr, layout, 128) });
+        assert!(!ptr.is_null());
+        assert_eq!(usage.allocated(), 192);
+        assert_eq!(usage.deallocated(), 64);
+        assert_eq!(usage.in_use(), 128);
+
+        // Not tracked.
+        let layout = Layout::from_size_align(128, 8).unwrap();
+        unsafe { allocator.dealloc(ptr, layout) };
+        assert_eq!(usage.in_use(), 128);
+    }
+
+    #[test]
+    fn deallocated_by_other_process() {
+        let allocator = TrackAllocator::new(System);
+        let layout = Layout::from_size_align(32, 8).unwrap();
+        let usage1 = MemoryUsage::new();
+        let usage2 = MemoryUsage::new();
+
+        let ptr = usage1.track(|| unsafe { allocator.alloc_zeroed(layout) });
+        usage2.track(|| unsafe { allocator.dealloc(ptr, layout) });
+        assert_eq!(usage1.in_use(), 32);
+        assert_eq!(usage2.in_use(), -32);
+    }
+
+    #[test]
+    fn nested_tracking() {
+        let allocator = TrackAllocator::new(System);
+        let layout = Layout::from_size_align(16, 8).unwrap();
+        let outer = MemoryUsage::new();
+        let inner = MemoryUsage::new();
+
+        outer.track(|| {
+            inner.track(|| unsafe { allocator.dealloc(allocator.alloc(layout), layout) });
+            unsafe { allocator.dealloc(allocator.alloc(layout), layout) };
+        });
+        assert_eq!(inner.allocated(), 16);
+        assert_eq!(outer.allocated(), 16);
+    }
+}
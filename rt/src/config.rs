@@ -0,0 +1,305 @@
+//! Runtime configuration loaded from the environment or a configuration file.
+//!
+//! See [`Setup::from_env`] and [`Setup::from_config_file`] for the supported
+//! settings.
+//!
+//! [`Setup::from_env`]: crate::Setup::from_env
+//! [`Setup::from_config_file`]: crate::Setup::from_config_file
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use std::{env, fmt, fs, io};
+
+use log::{warn, LevelFilter};
+
+/// Prefix of the environment variables.
+const ENV_PREFIX: &str = "HEPH_";
+
+/// Runtime configuration, all settings are optional.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Config {
+    pub(crate) name: Option<String>,
+    pub(crate) workers: Option<Workers>,
+    pub(crate) cpu_affinity: Option<bool>,
+    pub(crate) ring_entries: Option<u32>,
+    pub(crate) timer_granularity: Option<Duration>,
+    pub(crate) trace: Option<PathBuf>,
+    pub(crate) log_level: Option<LevelFilter>,
+}
+
+/// Number of worker threads.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Workers {
+    /// Fixed number of workers.
+    Count(usize),
+    /// One worker per CPU core.
+    AllCores,
+}
+
+impl Config {
+    /// Load the configuration from the environment variables.
+    pub(crate) fn from_env() -> Result<Config, Error> {
+        Config::from_vars(env::vars_os())
+    }
+
+    /// Load the configuration from `vars`, ignoring all variables not prefixed
+    /// with [`ENV_PREFIX`].
+    fn from_vars<I>(vars: I) -> Result<Config, Error>
+    where
+        I: IntoIterator<Item = (OsString, OsString)>,
+    {
+        let mut config = Config::default();
+        for (name, value) in vars {
+            let Some(key) = name.to_str().and_then(|n| n.strip_prefix(ENV_PREFIX)) else {
+                continue;
+            };
+            let Some(value) = value.to_str() else {
+                return Err(Error::InvalidValue {
+                    key: name.to_string_lossy().into_owned(),
+                    value: value.to_string_lossy().into_owned(),
+                });
+            };
+            match config.set(&key.to_ascii_lowercase(), value) {
+                Ok(()) => {}
+                // Other applications might also use the same prefix.
+                Err(Error::UnknownKey { .. }) => {
+                    warn!(
+                        "ignoring unknown environment variable: {}",
+                        name.to_string_lossy()
+                    );
+                }
+                Err(Error::InvalidValue { value, .. }) => {
+                    let key = name.to_string_lossy().into_owned();
+                    return Err(Error::InvalidValue { key, value });
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(config)
+    }
+
+    /// Load the configuration from the file at `path`.
+    pub(crate) fn from_file(path: &Path) -> Result<Config, Error> {
+        let contents = fs::read_to_string(path).map_err(Error::Io)?;
+        Config::parse(&contents)
+    }
+
+    /// Parse the configuration file `contents`.
+    ///
+    /// This supports a subset of TOML: top-level `key = value` pairs, where the
+    /// value is a string, integer or boolean, and comments.
+    fn parse(contents: &str) -> Result<Config, Error> {
+        let mut config = Config::default();
+        for (n, line) in contents.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(Error::Syntax { line: n + 1 });
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let value = match value.strip_prefix('"') {
+                Some(value) => match value.strip_suffix('"') {
+                    Some(value) if !value.contains('"') => value,
+                    _ => return Err(Error::Syntax { line: n + 1 }),
+                },
+                None => value,
+            };
+            if key.is_empty() {
+                return Err(Error::Syntax { line: n + 1 });
+            }
+            config.set(key, value)?;
+        }
+        Ok(config)
+    }
+
+    /// Set the setting `key` to `value`.
+    fn set(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        let invalid = || Error::InvalidValue {
+            key: key.to_owned(),
+            value: value.to_owned(),
+        };
+        match key {
+            "name" if !value.is_empty() => self.name = Some(value.to_owned()),
+            "workers" if value == "all" => self.workers = Some(Workers::AllCores),
+            "workers" => match value.parse() {
+                Ok(0) | Err(_) => return Err(invalid()),
+                Ok(n) => self.workers = Some(Workers::Count(n)),
+            },
+            "cpu_affinity" => self.cpu_affinity = Some(value.parse().map_err(|_| invalid())?),
+            "ring_entries" => match value.parse() {
+                Ok(0) | Err(_) => return Err(invalid()),
+                Ok(n) => self.ring_entries = Some(n),
+            },
+            "timer_granularity_ms" => {
+                let ms = value.parse().map_err(|_| invalid())?;
+                self.timer_granularity = Some(Duration::from_millis(ms));
+            }
+            "trace" if !value.is_empty() => self.trace = Some(PathBuf::from(value)),
+            "log_level" => {
+                let level = LevelFilter::from_str(value).map_err(|_| invalid())?;
+                self.log_level = Some(level);
+            }
+            "name" | "trace" => return Err(invalid()),
+            _ => {
+                let key = key.to_owned();
+                return Err(Error::UnknownKey { key });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Removes a comment (starting with `#`) from `line`, ignoring `#` in strings.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Error loading the runtime configuration.
+#[derive(Debug)]
+pub(crate) enum Error {
+    /// Error reading the configuration file.
+    Io(io::Error),
+    /// Invalid line in the configuration file.
+    Syntax { line: usize },
+    /// Unknown setting.
+    UnknownKey { key: String },
+    /// Invalid value for a setting.
+    InvalidValue { key: String, value: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "error reading configuration file: {err}"),
+            Error::Syntax { line } => write!(f, "invalid syntax on line {line}"),
+            Error::UnknownKey { key } => write!(f, "unknown setting '{key}'"),
+            Error::InvalidValue { key, value } => {
+                write!(f, "invalid value '{value}' for setting '{key}'")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(ref err) => Some(err),
+            Error::Syntax { .. } | Error::UnknownKey { .. } | Error::InvalidValue { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use log::LevelFilter;
+
+    use super::{Config, Error, Workers};
+
+    #[test]
+    fn parse() {
+        let contents = r#"
+# Runtime configuration.
+name = "my_app" # Comment.
+workers = 4
+cpu_affinity = true
+ring_entries = 256
+timer_granularity_ms = 10
+trace = "/tmp/trace#1.bin"
+log_level = "debug"
+"#;
+        let expected = Config {
+            name: Some("my_app".to_owned()),
+            workers: Some(Workers::Count(4)),
+            cpu_affinity: Some(true),
+            ring_entries: Some(256),
+            timer_granularity: Some(Duration::from_millis(10)),
+            trace: Some(PathBuf::from("/tmp/trace#1.bin")),
+            log_level: Some(LevelFilter::Debug),
+        };
+        assert_eq!(Config::parse(contents).unwrap(), expected);
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+        let config = Config::parse("workers = \"all\"").unwrap();
+        assert_eq!(config.workers, Some(Workers::AllCores));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let tests = [
+            ("workers", "invalid syntax on line 1"),
+            ("\n= 1", "invalid syntax on line 2"),
+            ("name = \"my_app", "invalid syntax on line 1"),
+            ("unknown = 1", "unknown setting 'unknown'"),
+            ("workers = 0", "invalid value '0' for setting 'workers'"),
+            (
+                "workers = many",
+                "invalid value 'many' for setting 'workers'",
+            ),
+            (
+                "cpu_affinity = 1",
+                "invalid value '1' for setting 'cpu_affinity'",
+            ),
+            (
+                "ring_entries = -1",
+                "invalid value '-1' for setting 'ring_entries'",
+            ),
+            (
+                "log_level = loud",
+                "invalid value 'loud' for setting 'log_level'",
+            ),
+            ("name = \"\"", "invalid value '' for setting 'name'"),
+        ];
+        for (contents, expected) in tests {
+            let err = Config::parse(contents).unwrap_err();
+            assert_eq!(err.to_string(), expected, "contents: {contents:?}");
+        }
+    }
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(OsString, OsString)> {
+        vars.iter()
+            .map(|(name, value)| (OsString::from(name), OsString::from(value)))
+            .collect()
+    }
+
+    #[test]
+    fn from_vars() {
+        let config = Config::from_vars(vars(&[
+            ("PATH", "/usr/bin"),
+            ("HEPH_WORKERS", "all"),
+            ("HEPH_CPU_AFFINITY", "false"),
+            ("HEPH_LOG_LEVEL", "warn"),
+            // Unknown variables are ignored.
+            ("HEPH_UNKNOWN", "1"),
+        ]))
+        .unwrap();
+        let expected = Config {
+            workers: Some(Workers::AllCores),
+            cpu_affinity: Some(false),
+            log_level: Some(LevelFilter::Warn),
+            ..Config::default()
+        };
+        assert_eq!(config, expected);
+
+        let err = Config::from_vars(vars(&[("HEPH_RING_ENTRIES", "0")])).unwrap_err();
+        assert!(matches!(err, Error::InvalidValue { .. }));
+        assert_eq!(
+            err.to_string(),
+            "invalid value '0' for setting 'HEPH_RING_ENTRIES'"
+        );
+    }
+}
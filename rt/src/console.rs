@@ -0,0 +1,426 @@
+//! Live introspection of the runtime.
+//!
+//! Similar to [tokio-console], the console allows a companion tool, e.g. a
+//! TUI, to connect to a running application and inspect the state of the
+//! scheduler of each worker thread, the processes it ran and why processes
+//! were woken up. This is useful to debug stalls in production, where
+//! attaching a debugger or enabling tracing isn't an option.
+//!
+//! The console is only available when the `console` feature is enabled. It can
+//! be enabled using [`Setup::enable_console`], which binds a Unix domain
+//! socket to accept connections on.
+//!
+//! [tokio-console]: https://github.com/tokio-rs/console
+//! [`Setup::enable_console`]: crate::Setup::enable_console
+//!
+//! # Format
+//!
+//! Once connected a client receives a snapshot of the runtime every
+//! [`INTERVAL`]. Each snapshot is a single JSON object on a single line, i.e.
+//! the stream is [JSON Lines]. The layout of the object is as follows (split
+//! over multiple lines for readability).
+//!
+//! [JSON Lines]: https://jsonlines.org
+//!
+//! ```json
+//! {
+//!   "timestamp_ms": 1700000000000,
+//!   "workers": [
+//!     {
+//!       "id": 1,
+//!       "state": "running",
+//!       "iterations": 1024,
+//!       "processes_run": 4096,
+//!       "budget_exhausted": 12,
+//!       "local_queue": 3,
+//!       "shared_queue": 0,
+//!       "wakes": { "waker": 3000, "local_timer": 20, "shared_timer": 5 },
+//!       "processes": [
+//!         { "pid": 5, "name": "my_actor", "polls": 30, "run_time_ns": 12000 }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! * `timestamp_ms`: time the snapshot was taken, in milliseconds since the
+//!   Unix epoch.
+//! * `workers`: all worker threads, ordered by `id`.
+//! * `state`: what the worker is doing, one of `running` (running processes),
+//!   `scheduling` (scheduling processes based on events) or `polling` (waiting
+//!   for OS events).
+//! * `iterations`: number of event loop iterations.
+//! * `processes_run`: number of times a process was run, i.e. polled.
+//! * `budget_exhausted`: number of event loop iterations in which the worker
+//!   stopped running processes because it reached the maximum number of
+//!   processes or time per iteration, rather than running out of processes.
+//! * `local_queue` and `shared_queue`: number of thread-local and thread-safe
+//!   processes ready to run at the end of the last event loop iteration.
+//! * `wakes`: number of processes woken by the worker per reason. `waker` for
+//!   user space wake-ups of thread-local processes using a [`task::Waker`],
+//!   e.g. a message being send or an I/O operation completing, `local_timer`
+//!   and `shared_timer` for expired thread-local and thread-safe timers,
+//!   respectively.
+//! * `processes`: the processes run on the worker thread that haven't
+//!   completed yet, ordered by `pid`. Note that thread-safe processes can run
+//!   on multiple workers, meaning they can show up in multiple workers.
+//!
+//! [`task::Waker`]: std::task::Waker
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io::{self, Write as _};
+use std::num::NonZeroUsize;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, SystemTime};
+use std::{fmt, fs, thread};
+
+use log::{debug, warn};
+
+use crate::process::ProcessId;
+use crate::shared;
+
+/// Time between two snapshots send to the clients.
+pub const INTERVAL: Duration = Duration::from_secs(1);
+
+/// State of a worker thread, see [`WorkerStats::set_state`].
+#[repr(u8)]
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum WorkerState {
+    /// Running processes.
+    Running = 0,
+    /// Scheduling processes based on events.
+    Scheduling = 1,
+    /// Waiting for OS events.
+    Polling = 2,
+}
+
+impl WorkerState {
+    /// Returns the state based on the value stored in `WorkerStats::state`.
+    const fn from_u8(state: u8) -> WorkerState {
+        match state {
+            0 => WorkerState::Running,
+            1 => WorkerState::Scheduling,
+            _ => WorkerState::Polling,
+        }
+    }
+
+    /// Returns the name used in the snapshot.
+    const fn as_str(self) -> &'static str {
+        match self {
+            WorkerState::Running => "running",
+            WorkerState::Scheduling => "scheduling",
+            WorkerState::Polling => "polling",
+        }
+    }
+}
+
+/// Reason why processes were woken, see [`WorkerStats::record_wakes`].
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum WakeReason {
+    /// User space wake-up using a [`task::Waker`].
+    ///
+    /// [`task::Waker`]: std::task::Waker
+    Waker = 0,
+    /// Expired thread-local timer.
+    LocalTimer = 1,
+    /// Expired thread-safe timer.
+    SharedTimer = 2,
+}
+
+/// Number of [`WakeReason`]s.
+const WAKE_REASONS: usize = 3;
+
+/// Registry of the statistics of all worker threads, part of the shared
+/// runtime internals.
+#[derive(Debug)]
+pub(crate) struct Console {
+    workers: Mutex<Vec<Arc<WorkerStats>>>,
+}
+
+impl Console {
+    /// Create an empty registry.
+    pub(crate) const fn new() -> Console {
+        Console {
+            workers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Register the statistics of a worker thread.
+    ///
+    /// If a worker with the same id is already registered, i.e. the worker
+    /// thread is restarted, it's replaced.
+    pub(crate) fn register(&self, stats: Arc<WorkerStats>) {
+        let mut workers = self.workers.lock().unwrap();
+        workers.retain(|worker| worker.id != stats.id);
+        workers.push(stats);
+        workers.sort_unstable_by_key(|worker| worker.id);
+    }
+
+    /// Write a snapshot of all workers to `buf`, see the [module
+    /// documentation] for the format.
+    ///
+    /// [module documentation]: crate::console#format
+    fn snapshot(&self, buf: &mut String) {
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+            .as_millis();
+        _ = write!(buf, "{{\"timestamp_ms\":{timestamp},\"workers\":[");
+        let workers = self.workers.lock().unwrap();
+        for (i, worker) in workers.iter().enumerate() {
+            if i != 0 {
+                buf.push(',');
+            }
+            worker.snapshot(buf);
+        }
+        buf.push_str("]}\n");
+    }
+}
+
+/// Statistics of a single worker thread.
+///
+/// Only written to by the worker thread, read by the console thread.
+#[derive(Debug)]
+pub(crate) struct WorkerStats {
+    id: NonZeroUsize,
+    /// [`WorkerState`] as `u8`.
+    state: AtomicU8,
+    iterations: AtomicU64,
+    processes_run: AtomicU64,
+    budget_exhausted: AtomicU64,
+    local_queue: AtomicUsize,
+    shared_queue: AtomicUsize,
+    /// Indexed by [`WakeReason`].
+    wakes: [AtomicU64; WAKE_REASONS],
+    /// Processes that haven't completed yet.
+    processes: Mutex<HashMap<ProcessId, ProcessStats>>,
+}
+
+/// Statistics of a single process, see [`WorkerStats`].
+#[derive(Debug)]
+struct ProcessStats {
+    name: &'static str,
+    polls: u64,
+    run_time: Duration,
+}
+
+impl WorkerStats {
+    /// Create new statistics for the worker with `id`.
+    pub(crate) fn new(id: NonZeroUsize) -> WorkerStats {
+        WorkerStats {
+            id,
+            state: AtomicU8::new(WorkerState::Running as u8),
+            iterations: AtomicU64::new(0),
+            processes_run: AtomicU64::new(0),
+            budget_exhausted: AtomicU64::new(0),
+            local_queue: AtomicUsize::new(0),
+            shared_queue: AtomicUsize::new(0),
+            wakes: [const { AtomicU64::new(0) }; WAKE_REASONS],
+            processes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Set the current state of the worker.
+    pub(crate) fn set_state(&self, state: WorkerState) {
+        self.state.store(state as u8, Ordering::Relaxed);
+    }
+
+    /// Record the end of an event loop iteration.
+    ///
+    /// `budget_exhausted` indicates whether or not the worker stopped running
+    /// processes because it exhausted its budget. `local_queue` and
+    /// `shared_queue` are the number of ready thread-local and thread-safe
+    /// processes, respectively.
+    pub(crate) fn record_iteration(
+        &self,
+        budget_exhausted: bool,
+        local_queue: usize,
+        shared_queue: usize,
+    ) {
+        _ = self.iterations.fetch_add(1, Ordering::Relaxed);
+        if budget_exhausted {
+            _ = self.budget_exhausted.fetch_add(1, Ordering::Relaxed);
+        }
+        self.local_queue.store(local_queue, Ordering::Relaxed);
+        self.shared_queue.store(shared_queue, Ordering::Relaxed);
+    }
+
+    /// Record that `amount` processes were woken because of `reason`.
+    pub(crate) fn record_wakes(&self, reason: WakeReason, amount: usize) {
+        _ = self.wakes[reason as usize].fetch_add(amount as u64, Ordering::Relaxed);
+    }
+
+    /// Record a run of the process with `pid` and `name`, which ran for
+    /// `elapsed`. If the process is `completed` it's removed.
+    pub(crate) fn record_run(
+        &self,
+        pid: ProcessId,
+        name: &'static str,
+        elapsed: Duration,
+        completed: bool,
+    ) {
+        _ = self.processes_run.fetch_add(1, Ordering::Relaxed);
+        let mut processes = self.processes.lock().unwrap();
+        if completed {
+            _ = processes.remove(&pid);
+            return;
+        }
+        let stats = processes.entry(pid).or_insert(ProcessStats {
+            name,
+            polls: 0,
+            run_time: Duration::ZERO,
+        });
+        stats.polls += 1;
+        stats.run_time += elapsed;
+    }
+
+    /// Write a snapshot of the worker to `buf`.
+    fn snapshot(&self, buf: &mut String) {
+        let state = WorkerState::from_u8(self.state.load(Ordering::Relaxed));
+        _ = write!(
+            buf,
+            "{{\"id\":{},\"state\":\"{}\",\"iterations\":{},\"processes_run\":{},\"budget_exhausted\":{},\"local_queue\":{},\"shared_queue\":{},\"wakes\":{{\"waker\":{},\"local_timer\":{},\"shared_timer\":{}}},\"processes\":[",
+            self.id,
+            state.as_str(),
+            self.iterations.load(Ordering::Relaxed),
+            self.processes_run.load(Ordering::Relaxed),
+            self.budget_exhausted.load(Ordering::Relaxed),
+            self.local_queue.load(Ordering::Relaxed),
+            self.shared_queue.load(Ordering::Relaxed),
+            self.wakes[WakeReason::Waker as usize].load(Ordering::Relaxed),
+            self.wakes[WakeReason::LocalTimer as usize].load(Ordering::Relaxed),
+            self.wakes[WakeReason::SharedTimer as usize].load(Ordering::Relaxed),
+        );
+        let processes = self.processes.lock().unwrap();
+        let mut pids: Vec<ProcessId> = processes.keys().copied().collect();
+        pids.sort_unstable();
+        for (i, pid) in pids.into_iter().enumerate() {
+            let stats = &processes[&pid];
+            if i != 0 {
+                buf.push(',');
+            }
+            _ = write!(
+                buf,
+                "{{\"pid\":{},\"name\":{},\"polls\":{},\"run_time_ns\":{}}}",
+                pid.0,
+                JsonStr(stats.name),
+                stats.polls,
+                stats.run_time.as_nanos(),
+            );
+        }
+        buf.push_str("]}");
+    }
+}
+
+/// Quoted and escaped JSON string.
+struct JsonStr<'a>(&'a str);
+
+impl<'a> fmt::Display for JsonStr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+/// Listener for console clients, see [`Setup::enable_console`].
+///
+/// [`Setup::enable_console`]: crate::Setup::enable_console
+#[derive(Debug)]
+pub(crate) struct Listener {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl Listener {
+    /// Bind a Unix socket to `path`.
+    pub(crate) fn bind(path: &Path) -> io::Result<Listener> {
+        let listener = UnixListener::bind(path)?;
+        listener.set_nonblocking(true)?;
+        Ok(Listener {
+            listener,
+            path: path.to_owned(),
+        })
+    }
+
+    /// Start the console thread, which sends snapshots of the runtime to all
+    /// connected clients.
+    ///
+    /// The thread stops once the runtime `internals` are dropped, removing the
+    /// socket.
+    pub(crate) fn start(self, internals: Weak<shared::RuntimeInternals>) -> io::Result<()> {
+        thread::Builder::new()
+            .name("Console".to_owned())
+            .spawn(move || self.run(&internals))
+            .map(|_| ())
+    }
+
+    /// Run the console.
+    fn run(self, internals: &Weak<shared::RuntimeInternals>) {
+        debug!(path:? = self.path; "starting console");
+        let mut clients = Vec::new();
+        let mut buf = String::new();
+        loop {
+            thread::sleep(INTERVAL);
+            let Some(internals) = internals.upgrade() else {
+                break;
+            };
+
+            self.accept(&mut clients);
+            if clients.is_empty() {
+                continue;
+            }
+
+            buf.clear();
+            internals.console().snapshot(&mut buf);
+            drop(internals);
+            clients.retain_mut(|client| match client.write_all(buf.as_bytes()) {
+                Ok(()) => true,
+                Err(err) => {
+                    debug!("dropping console client: {err}");
+                    false
+                }
+            });
+        }
+
+        debug!(path:? = self.path; "stopping console");
+        if let Err(err) = fs::remove_file(&self.path) {
+            warn!(path:? = self.path; "failed to remove console socket: {err}");
+        }
+    }
+
+    /// Accept all pending clients.
+    fn accept(&self, clients: &mut Vec<UnixStream>) {
+        loop {
+            match self.listener.accept() {
+                Ok((client, _)) => {
+                    // Writing the snapshot should block, but not forever.
+                    if let Err(err) = client
+                        .set_nonblocking(false)
+                        .and_then(|()| client.set_write_timeout(Some(INTERVAL)))
+                    {
+                        warn!("failed to setup console client: {err}");
+                        continue;
+                    }
+                    clients.push(client);
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => return,
+                Err(err) => {
+                    warn!("failed to accept console client: {err}");
+                    return;
+                }
+            }
+        }
+    }
+}
@@ -12,14 +12,23 @@
 //! * A (sync) worker thread stopping because all actors have finished running,
 //!   the worker hit an error or the thread panicked.
 //!
+//! If a worker thread panicked the coordinator can restart it, see
+//! [`Setup::with_worker_restarts`].
+//!
+//! Once all (sync) workers have stopped the coordinator runs the shutdown hooks,
+//! see [`RuntimeRef::on_shutdown`], before dropping the runtime resources.
+//!
 //! [worker threads]: crate::worker
 //! [sync worker threads]: crate::sync_worker
+//! [`Setup::with_worker_restarts`]: crate::Setup::with_worker_restarts
+//! [`RuntimeRef::on_shutdown`]: crate::RuntimeRef::on_shutdown
 
-use std::cmp::max;
+use std::cmp::{max, min};
 use std::env::consts::ARCH;
+use std::num::NonZeroUsize;
 use std::os::unix::process::parent_id;
 use std::sync::Arc;
-use std::task::{self, Poll};
+use std::task::{self, Poll, Wake};
 use std::time::{Duration, Instant};
 use std::{fmt, io, process};
 
@@ -27,11 +36,17 @@ use a10::signals::{ReceiveSignals, Signals};
 use heph::actor_ref::ActorGroup;
 use log::{debug, error, info, trace};
 
-use crate::setup::{host_id, host_info, Uuid};
+use crate::ring::RingMetrics;
+use crate::setup::{host_id, host_info, LocalDataInit, Uuid};
 use crate::{self as rt, cpu_usage, shared, sync_worker, trace, worker, Signal};
 
 /// Setup the [`Coordinator`].
-pub(crate) fn setup(app_name: Box<str>, threads: usize) -> Result<CoordinatorSetup, rt::Error> {
+pub(crate) fn setup(
+    app_name: Box<str>,
+    threads: usize,
+    restarts: WorkerRestarts,
+    shutdown_timeout: Duration,
+) -> Result<CoordinatorSetup, rt::Error> {
     let (host_os, host_name) = host_info().map_err(rt::Error::init_coordinator)?;
     let host_id = host_id().map_err(rt::Error::init_coordinator)?;
 
@@ -62,6 +77,8 @@ pub(crate) fn setup(app_name: Box<str>, threads: usize) -> Result<CoordinatorSet
     Ok(CoordinatorSetup {
         ring,
         signals,
+        restarts,
+        shutdown_timeout,
         app_name,
         host_os,
         host_name,
@@ -79,6 +96,8 @@ pub(crate) fn setup(app_name: Box<str>, threads: usize) -> Result<CoordinatorSet
 pub(crate) struct CoordinatorSetup {
     ring: a10::Ring,
     signals: ReceiveSignals,
+    restarts: WorkerRestarts,
+    shutdown_timeout: Duration,
     app_name: Box<str>,
     host_os: Box<str>,
     host_name: Box<str>,
@@ -107,6 +126,8 @@ impl CoordinatorSetup {
             sync_workers,
             signals: self.signals,
             signal_refs,
+            restarts: self.restarts,
+            shutdown_timeout: self.shutdown_timeout,
             trace_log,
             start: Instant::now(),
             app_name: self.app_name,
@@ -131,6 +152,10 @@ pub(crate) struct Coordinator {
     signals: ReceiveSignals,
     /// Actor that want to receive a process signal.
     signal_refs: ActorGroup<Signal>,
+    /// Configuration to restart panicked worker threads.
+    restarts: WorkerRestarts,
+    /// Maximum time to run the shutdown hooks.
+    shutdown_timeout: Duration,
     /// Trace log for the coordinator.
     trace_log: Option<trace::CoordinatorLog>,
     // Data used in [`Coordinator::log_metrics`].
@@ -194,6 +219,10 @@ impl Coordinator {
 
             // Once all (sync) workers are done running we can return.
             if self.workers.is_empty() && self.sync_workers.is_empty() {
+                // NOTE: hooks can use the resources, so they're run first.
+                self.run_shutdown_hooks()?;
+                debug!("dropping runtime resources");
+                self.internals.clear_resources();
                 return Ok(());
             }
 
@@ -295,11 +324,13 @@ impl Coordinator {
             parent_process_id = parent_id(),
             uptime:? = self.start.elapsed(),
             worker_threads = self.workers.len(),
+            worker_restarts_left = self.restarts.left,
             sync_actors = self.sync_workers.len(),
             shared_scheduler_ready = shared_metrics.scheduler_ready,
             shared_scheduler_inactive = shared_metrics.scheduler_inactive,
             shared_timers_total = shared_metrics.timers_total,
             shared_timers_next:? = shared_metrics.timers_next,
+            shared_timers_wakeups_avoided = shared_metrics.timers_wakeups_avoided,
             process_signals:? = Signal::ALL,
             process_signal_receivers = self.signal_refs.len(),
             cpu_time:? = cpu_usage(libc::CLOCK_THREAD_CPUTIME_ID),
@@ -308,6 +339,22 @@ impl Coordinator {
             trace_counter = trace_metrics.map_or(0, |m| m.counter);
             "coordinator metrics",
         );
+        let ring = RingMetrics::read(&self.ring).unwrap_or_default();
+        let shared_ring = shared_metrics.ring;
+        info!(
+            target: "metrics",
+            sq_pending = ring.sq_pending,
+            cq_pending = ring.cq_pending,
+            submissions = ring.submissions,
+            completions = ring.completions,
+            cq_overflows = ring.cq_overflows,
+            shared_sq_pending = shared_ring.sq_pending,
+            shared_cq_pending = shared_ring.cq_pending,
+            shared_submissions = shared_ring.submissions,
+            shared_completions = shared_ring.completions,
+            shared_cq_overflows = shared_ring.cq_overflows;
+            "io_uring metrics",
+        );
         trace::finish_rt(
             self.trace_log.as_mut(),
             timing,
@@ -319,13 +366,26 @@ impl Coordinator {
     /// Check if the (sync) workers are still alive, removing any that are not.
     fn check_workers(&mut self, worker_stopped: &mut bool) -> Result<(), rt::Error> {
         let timing = trace::start(&self.trace_log);
+        let mut restart = Vec::new();
         for worker in self.workers.extract_if(|w| w.is_finished()) {
             *worker_stopped = true;
-            debug!(worker_id = worker.id(); "worker thread stopped");
-            worker
-                .join()
-                .map_err(rt::Error::worker_panic)
-                .and_then(|res| res)?;
+            let worker_id = worker.id();
+            debug!(worker_id = worker_id; "worker thread stopped");
+            match worker.join() {
+                Ok(result) => result?,
+                Err(panic) if self.restarts.left > 0 => {
+                    self.restarts.left -= 1;
+                    error!(
+                        worker_id = worker_id, restarts_left = self.restarts.left;
+                        "worker thread panicked, restarting it: {}", rt::error::convert_panic(panic),
+                    );
+                    restart.push(worker_id);
+                }
+                Err(panic) => return Err(rt::Error::worker_panic(panic)),
+            }
+        }
+        for worker_id in restart {
+            self.restart_worker(worker_id)?;
         }
 
         for sync_worker in self.sync_workers.extract_if(|w| w.is_finished()) {
@@ -342,6 +402,151 @@ impl Coordinator {
         );
         Ok(())
     }
+
+    /// Run the shutdown hooks in order of registration, see
+    /// [`RuntimeRef::on_shutdown`].
+    ///
+    /// The hooks that didn't complete before the shutdown deadline are
+    /// dropped.
+    ///
+    /// [`RuntimeRef::on_shutdown`]: crate::RuntimeRef::on_shutdown
+    fn run_shutdown_hooks(&mut self) -> Result<(), rt::Error> {
+        let hooks = self.internals.take_shutdown_hooks();
+        if hooks.is_empty() {
+            return Ok(());
+        }
+
+        let timing = trace::start(&self.trace_log);
+        let amount = hooks.len();
+        debug!(amount = amount; "running shutdown hooks");
+        let deadline = Instant::now() + self.shutdown_timeout;
+        let waker = Arc::new(CoordinatorWaker(self.ring.submission_queue().clone()));
+        let waker = task::Waker::from(waker);
+        let mut ctx = task::Context::from_waker(&waker);
+        'hooks: for (n, mut hook) in hooks.into_iter().enumerate() {
+            loop {
+                if hook.as_mut().poll(&mut ctx).is_ready() {
+                    continue 'hooks;
+                }
+
+                let now = Instant::now();
+                let timeout = match deadline.checked_duration_since(now) {
+                    Some(timeout) if !timeout.is_zero() => timeout,
+                    _ => {
+                        error!(
+                            hooks_left = amount - n;
+                            "shutdown hooks didn't complete before the deadline, dropping the remaining hooks",
+                        );
+                        break 'hooks;
+                    }
+                };
+                // The worker threads, which normally handle the I/O and timers
+                // of thread-safe futures, are stopped, so we have to.
+                let timeout = min(timeout, SHUTDOWN_POLL_TIMEOUT);
+                let timeout = self.internals.next_timeout(now, Some(timeout));
+                self.poll_os(timeout)?;
+                _ = self
+                    .internals
+                    .try_poll_ring()
+                    .map_err(|err| rt::Error::coordinator(Error::Polling(err)))?;
+                _ = self.internals.expire_timers(Instant::now());
+            }
+        }
+        trace::finish_rt(
+            self.trace_log.as_mut(),
+            timing,
+            "Running shutdown hooks",
+            &[("amount", &amount)],
+        );
+        Ok(())
+    }
+
+    /// Start a new worker thread with `worker_id`, replacing the one that
+    /// panicked.
+    ///
+    /// # Notes
+    ///
+    /// If the worker thread panicked while running a thread-safe process that
+    /// process is dropped (while unwinding), it's not added back to the shared
+    /// scheduler.
+    fn restart_worker(&mut self, worker_id: usize) -> Result<(), rt::Error> {
+        let timing = trace::start(&self.trace_log);
+        let id = NonZeroUsize::new(worker_id).unwrap();
+        let auto_cpu_affinity = self.restarts.auto_cpu_affinity;
+        let (worker_setup, worker_sq) = worker::setup(
+            id,
+            auto_cpu_affinity,
+            self.restarts.ring_entries,
+            self.ring.submission_queue(),
+        )
+        .map_err(rt::Error::start_worker)?;
+        // Ensure the thread-safe actors and futures wake the new worker, not
+        // the old one.
+        self.internals.replace_worker_sq(id, worker_sq);
+        #[allow(clippy::cast_possible_truncation)]
+        let trace_log = self
+            .trace_log
+            .as_ref()
+            .map(|trace_log| trace_log.new_stream(worker_id as u32));
+        let worker = worker_setup
+            .start(self.internals.clone(), auto_cpu_affinity, trace_log)
+            .map_err(rt::Error::start_worker)?;
+        if let Some(local_data) = &self.restarts.local_data {
+            local_data
+                .send_to(&worker)
+                .map_err(|err| rt::Error::coordinator(Error::SendingFunc(err)))?;
+        }
+        worker
+            .send_runtime_started()
+            .map_err(|err| rt::Error::coordinator(Error::SendingStartSignal(err)))?;
+        // Keep the workers sorted based on id, see `Coordinator::run`.
+        let idx = self.workers.partition_point(|w| w.id() < worker_id);
+        self.workers.insert(idx, worker);
+        // The panicked worker might have been woken to run thread-safe
+        // processes, so let all workers check the shared scheduler.
+        self.internals.wake_all_workers();
+        trace::finish_rt(
+            self.trace_log.as_mut(),
+            timing,
+            "Restarting worker thread",
+            &[("id", &worker_id)],
+        );
+        Ok(())
+    }
+}
+
+/// Maximum time to block while running the shutdown hooks, see
+/// [`Coordinator::run_shutdown_hooks`].
+///
+/// Completions of the shared io_uring don't wake the coordinator, so we have to
+/// check it periodically.
+const SHUTDOWN_POLL_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// [`Wake`] implementation that wakes the coordinator.
+struct CoordinatorWaker(a10::SubmissionQueue);
+
+impl Wake for CoordinatorWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.wake();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.wake();
+    }
+}
+
+/// Configuration to restart panicked worker threads, see
+/// [`Setup::with_worker_restarts`].
+///
+/// [`Setup::with_worker_restarts`]: crate::Setup::with_worker_restarts
+#[derive(Debug)]
+pub(crate) struct WorkerRestarts {
+    /// Number of restarts left.
+    pub(crate) left: usize,
+    /// Settings used to create the new worker thread.
+    pub(crate) auto_cpu_affinity: bool,
+    pub(crate) ring_entries: u32,
+    pub(crate) local_data: Option<LocalDataInit>,
 }
 
 #[allow(clippy::missing_fields_in_debug)]
@@ -350,6 +555,7 @@ impl fmt::Debug for Coordinator {
         f.debug_struct("Coordinator")
             .field("ring", &self.ring)
             .field("internals", &self.internals)
+            .field("shutdown_timeout", &self.shutdown_timeout)
             .field("start", &self.start)
             .field("app_name", &self.app_name)
             .field("host_os", &self.host_os)
@@ -3,7 +3,7 @@
 use std::any::Any;
 use std::{fmt, io};
 
-use crate::{coordinator, worker};
+use crate::{config, coordinator, panic_message, worker};
 
 /// Error returned by running a [`Runtime`].
 ///
@@ -18,6 +18,11 @@ enum ErrorInner {
     Setup(StringError),
     /// Error setting up tracing infrastructure.
     SetupTrace(io::Error),
+    /// Error setting up the console.
+    #[cfg(feature = "console")]
+    SetupConsole(io::Error),
+    /// Error loading the runtime configuration.
+    SetupConfig(config::Error),
 
     /// Error initialising coordinator.
     InitCoordinator(io::Error),
@@ -58,6 +63,19 @@ impl Error {
         }
     }
 
+    #[cfg(feature = "console")]
+    pub(crate) const fn setup_console(err: io::Error) -> Error {
+        Error {
+            inner: ErrorInner::SetupConsole(err),
+        }
+    }
+
+    pub(crate) const fn setup_config(err: config::Error) -> Error {
+        Error {
+            inner: ErrorInner::SetupConfig(err),
+        }
+    }
+
     pub(crate) const fn init_coordinator(err: io::Error) -> Error {
         Error {
             inner: ErrorInner::InitCoordinator(err),
@@ -101,16 +119,13 @@ impl Error {
     }
 }
 
-/// Maps a boxed panic messages to a [`StringError`]
-fn convert_panic(err: Box<dyn Any + Send + 'static>) -> StringError {
-    let msg = match err.downcast::<&'static str>() {
-        Ok(s) => (*s).to_owned(),
-        Err(err) => match err.downcast::<String>() {
-            Ok(s) => *s,
-            Err(..) => "<unknown>".to_owned(),
-        },
-    };
-    StringError(msg)
+/// Maps a boxed panic messages to a [`StringError`], including the
+/// [`worker::PanicReport`] if the panic payload is one.
+pub(crate) fn convert_panic(err: Box<dyn Any + Send + 'static>) -> StringError {
+    match err.downcast::<worker::PanicReport>() {
+        Ok(report) => StringError(report.to_string()),
+        Err(err) => StringError(panic_message(&*err).to_owned()),
+    }
 }
 
 /// We implement [`Debug`] by using [`Display`] implementation because the
@@ -136,6 +151,13 @@ impl fmt::Display for Error {
             ErrorInner::SetupTrace(ref err) => {
                 write!(f, "{DESC}: error setting up trace infrastructure: {err}")
             }
+            #[cfg(feature = "console")]
+            ErrorInner::SetupConsole(ref err) => {
+                write!(f, "{DESC}: error setting up console: {err}")
+            }
+            ErrorInner::SetupConfig(ref err) => {
+                write!(f, "{DESC}: error loading runtime configuration: {err}")
+            }
             ErrorInner::InitCoordinator(ref err) => {
                 write!(f, "{DESC}: error creating coordinator: {err}")
             }
@@ -165,6 +187,9 @@ impl std::error::Error for Error {
             | ErrorInner::InitCoordinator(ref err)
             | ErrorInner::StartWorker(ref err)
             | ErrorInner::StartSyncActor(ref err) => Some(err),
+            #[cfg(feature = "console")]
+            ErrorInner::SetupConsole(ref err) => Some(err),
+            ErrorInner::SetupConfig(ref err) => Some(err),
             ErrorInner::Coordinator(ref err) => Some(err),
             ErrorInner::Worker(ref err) => Some(err),
             // All `StringError`.
@@ -1,11 +1,18 @@
 //! Filesystem manipulation operations.
 //!
-//! To open a [`File`] use [`File::open`] or [`OpenOptions`].
-
-use std::os::fd::{AsFd, BorrowedFd};
-use std::path::PathBuf;
+//! To open a [`File`] use [`File::open`] or [`OpenOptions`]. Directories can be
+//! created using [`create_dir`] and [`create_dir_all`] and read using
+//! [`read_dir`].
+
+use std::async_iter::AsyncIterator;
+use std::ffi::OsStr;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{self, Poll};
 use std::time::SystemTime;
-use std::{fmt, io};
+use std::{fmt, io, mem};
 
 use a10::{AsyncFd, Extract};
 
@@ -405,7 +412,7 @@ impl fmt::Debug for OpenOptions {
 
 /// Metadata information about a file.
 ///
-/// See [`File::metadata`].
+/// See [`File::metadata`] and [`metadata`].
 pub struct Metadata {
     inner: a10::fs::Metadata,
 }
@@ -682,3 +689,242 @@ where
 {
     NoRing(a10::fs::remove_dir(rt.submission_queue(), path)).await
 }
+
+/// Creates a new, empty directory, creating all missing parent directories.
+///
+/// Returns `Ok(())` if the directory already exists.
+pub async fn create_dir_all<RT>(rt: &RT, path: PathBuf) -> io::Result<()>
+where
+    RT: Access,
+{
+    // Directories that are missing, deepest first.
+    let mut missing = Vec::new();
+    let mut dir = path;
+    loop {
+        match create_dir(rt, dir.clone()).await {
+            Ok(()) => break,
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                if missing.is_empty() && !metadata(rt, dir).await?.is_dir() {
+                    return Err(err);
+                }
+                break;
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                let Some(parent) = dir.parent().filter(|p| !p.as_os_str().is_empty()) else {
+                    return Err(err);
+                };
+                let parent = parent.to_path_buf();
+                missing.push(mem::replace(&mut dir, parent));
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    // Create the missing directories, parents first.
+    while let Some(dir) = missing.pop() {
+        match create_dir(rt, dir).await {
+            // Directory could be created concurrently, that's fine.
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// Retrieve metadata about the file or directory at `path`, following symbolic
+/// links.
+///
+/// # Notes
+///
+/// This opens the file (in read-only mode) to retrieve the metadata, which
+/// means the file must be readable.
+pub async fn metadata<RT>(rt: &RT, path: PathBuf) -> io::Result<Metadata>
+where
+    RT: Access,
+{
+    File::open(rt, path).await?.metadata().await
+}
+
+/// Returns an [`AsyncIterator`] over the entries in the directory at `path`.
+///
+/// The entries `.` and `..` are skipped. The order in which the entries are
+/// returned is not specified.
+///
+/// # Notes
+///
+/// io_uring doesn't support reading directories, so while the directory is
+/// opened asynchronously the entries are read using the `getdents64(2)`
+/// system call, which may block. For directories on local filesystems this is
+/// generally fast.
+pub async fn read_dir<RT>(rt: &RT, path: PathBuf) -> io::Result<ReadDir>
+where
+    RT: Access,
+{
+    let dir = File::open(rt, path.clone()).await?;
+    Ok(ReadDir {
+        dir,
+        path,
+        buf: Vec::with_capacity(READ_DIR_BUF_SIZE),
+        pos: 0,
+        done: false,
+    })
+}
+
+/// Size of the buffer used by [`ReadDir`].
+const READ_DIR_BUF_SIZE: usize = 4096;
+
+/// [`AsyncIterator`] over the entries in a directory.
+///
+/// See [`read_dir`].
+#[must_use = "AsyncIterators do nothing unless polled"]
+pub struct ReadDir {
+    dir: File,
+    path: PathBuf,
+    /// Buffer filled by `getdents64(2)`, containing `linux_dirent64`s.
+    buf: Vec<u8>,
+    /// Position of the next entry in `buf`.
+    pos: usize,
+    /// All entries have been read.
+    done: bool,
+}
+
+impl ReadDir {
+    /// Returns the next entry, reading more entries if needed.
+    fn next_entry(&mut self) -> io::Result<Option<DirEntry>> {
+        /// Offset of the `d_reclen`, `d_type` and `d_name` fields in
+        /// `linux_dirent64`.
+        const RECLEN: usize = 16;
+        const TYPE: usize = 18;
+        const NAME: usize = 19;
+
+        loop {
+            if self.pos >= self.buf.len() && (self.done || self.fill_buf()? == 0) {
+                self.done = true;
+                return Ok(None);
+            }
+
+            let buf = &self.buf[self.pos..];
+            let ino = u64::from_ne_bytes(buf[..8].try_into().unwrap());
+            let reclen = usize::from(u16::from_ne_bytes([buf[RECLEN], buf[RECLEN + 1]]));
+            let kind = buf[TYPE];
+            let name = &buf[NAME..reclen];
+            // The name is NULL terminated and may be followed by padding.
+            let name = &name[..name.iter().position(|b| *b == 0).unwrap_or(name.len())];
+            self.pos += reclen;
+            if name == b"." || name == b".." {
+                continue;
+            }
+            return Ok(Some(DirEntry {
+                path: self.path.join(OsStr::from_bytes(name)),
+                ino,
+                kind,
+            }));
+        }
+    }
+
+    /// Fill `buf` with entries, returns the number of bytes read.
+    fn fill_buf(&mut self) -> io::Result<usize> {
+        self.buf.clear();
+        self.pos = 0;
+        let n = syscall!(syscall(
+            libc::SYS_getdents64,
+            self.dir.as_fd().as_raw_fd(),
+            self.buf.as_mut_ptr(),
+            self.buf.capacity(),
+        ))? as usize;
+        // SAFETY: the kernel initialised `n` bytes for us.
+        unsafe { self.buf.set_len(n) };
+        Ok(n)
+    }
+}
+
+impl AsyncIterator for ReadDir {
+    type Item = io::Result<DirEntry>;
+
+    fn poll_next(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.get_mut().next_entry().transpose())
+    }
+}
+
+impl fmt::Debug for ReadDir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadDir").field("path", &self.path).finish()
+    }
+}
+
+/// Entry in a directory.
+///
+/// See [`read_dir`].
+pub struct DirEntry {
+    path: PathBuf,
+    ino: u64,
+    /// `d_type` of `linux_dirent64`.
+    kind: u8,
+}
+
+impl DirEntry {
+    /// Returns the full path to the entry, i.e. the directory joined with
+    /// [`DirEntry::file_name`].
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the full path to the entry, see [`DirEntry::path`].
+    pub fn into_path(self) -> PathBuf {
+        self.path
+    }
+
+    /// Returns the file name of the entry.
+    pub fn file_name(&self) -> &OsStr {
+        self.path.file_name().unwrap_or_default()
+    }
+
+    /// Returns the inode number of the entry.
+    pub const fn ino(&self) -> u64 {
+        self.ino
+    }
+
+    /// Returns `true` if the entry is a directory.
+    ///
+    /// # Notes
+    ///
+    /// Not all filesystems return the type of the entries, in which case this
+    /// returns `false`. Use [`DirEntry::metadata`] to be certain.
+    pub const fn is_dir(&self) -> bool {
+        self.kind == libc::DT_DIR
+    }
+
+    /// Returns `true` if the entry is a file.
+    ///
+    /// See the notes of [`DirEntry::is_dir`].
+    pub const fn is_file(&self) -> bool {
+        self.kind == libc::DT_REG
+    }
+
+    /// Returns `true` if the entry is a symbolic link.
+    ///
+    /// See the notes of [`DirEntry::is_dir`].
+    pub const fn is_symlink(&self) -> bool {
+        self.kind == libc::DT_LNK
+    }
+
+    /// Retrieve metadata about the entry, following symbolic links.
+    ///
+    /// See [`metadata`].
+    pub async fn metadata<RT>(&self, rt: &RT) -> io::Result<Metadata>
+    where
+        RT: Access,
+    {
+        metadata(rt, self.path.clone()).await
+    }
+}
+
+impl fmt::Debug for DirEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DirEntry")
+            .field("path", &self.path)
+            .field("ino", &self.ino)
+            .finish()
+    }
+}
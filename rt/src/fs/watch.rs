@@ -50,6 +50,39 @@
 //! # _ = actor::<heph_rt::ThreadLocal>; // Silence dead code warnings.
 //! ```
 //!
+//! Instead of handling the events in the same actor the [`watcher`] actor can
+//! be used to send the events as messages to another actor, see [`EventBuf`].
+//!
+//! ```
+//! # #![feature(never_type)]
+//! use std::io;
+//! use std::path::PathBuf;
+//!
+//! use heph::actor::{self, actor_fn};
+//! use heph::supervisor::{NoSupervisor, StopSupervisor};
+//! use heph_rt::fs::watch::{self, EventBuf, Watch};
+//! use heph_rt::spawn::ActorOptions;
+//! use heph_rt::ThreadLocal;
+//!
+//! async fn setup(mut ctx: actor::Context<!, ThreadLocal>, config_path: PathBuf) -> io::Result<()> {
+//!     let config_ref = ctx.runtime().spawn_local(NoSupervisor, actor_fn(config_actor), (), ActorOptions::default());
+//!
+//!     // Watch the configuration file for changes.
+//!     let mut watch = Watch::new(ctx.runtime_ref())?;
+//!     watch.watch_file(config_path, watch::Interest::CLOSE_WRITE)?;
+//!     let watcher = actor_fn(watch::watcher::<ThreadLocal>);
+//!     ctx.runtime().spawn_local(StopSupervisor, watcher, (watch, config_ref), ActorOptions::default());
+//!     Ok(())
+//! }
+//!
+//! async fn config_actor(mut ctx: actor::Context<EventBuf, ThreadLocal>) {
+//!     while let Ok(event) = ctx.receive_next().await {
+//!         println!("reloading configuration from '{}'", event.path().display());
+//!     }
+//! }
+//! # _ = setup; // Silence dead code warnings.
+//! ```
+//!
 //! # Notes
 //!
 //! This implementation is based on [`inotify(7)`], which has a number of
@@ -89,7 +122,8 @@ use std::path::{Path, PathBuf};
 use std::{fmt, io, ptr};
 
 use a10::AsyncFd;
-use log::warn;
+use heph::{actor, ActorRef};
+use log::{debug, warn};
 
 use crate::access::Access;
 
@@ -208,6 +242,15 @@ pub struct Events<'w> {
 }
 
 impl<'w> Events<'w> {
+    /// Returns the next event as [`EventBuf`], using the full path of the
+    /// event (see [`Events::path_for`]).
+    pub fn next_buf(&mut self) -> Option<EventBuf> {
+        let event = self.next()?;
+        let path = self.path_for(event).into_owned();
+        let mask = event.event.mask;
+        Some(EventBuf { path, mask })
+    }
+
     /// Returns the path for `event`.
     ///
     /// # Notes
@@ -356,9 +399,9 @@ impl Event {
 
 /// Macro to create functions to check bits set.
 macro_rules! bit_checks {
-    ( $self: ident . $field: ident . $field2: ident ) => {
+    ( $self: ident $( . $field: ident )+ ) => {
         bit_checks!(
-            $self.$field.$field2,
+            $self, ($self $( . $field )+),
             /// Return true if the subject of this event is a directory.
             is_dir, IN_ISDIR;
             /// Returns true if:
@@ -446,13 +489,13 @@ macro_rules! bit_checks {
         );
     };
     (
-        $self: ident . $field: ident . $field2: ident,
+        $self: ident, $mask: tt,
         $( $(#[$meta: meta])* $fn_name: ident, $bit: ident ; )+
     ) => {
         $(
         $( #[$meta] )*
         pub const fn $fn_name(&$self) -> bool {
-            $self.$field.$field2 & libc::$bit != 0
+            $mask & libc::$bit != 0
         }
         )+
 
@@ -480,6 +523,76 @@ impl fmt::Debug for Event {
     }
 }
 
+/// Owned version of [`Event`].
+///
+/// Unlike `Event` this contains the full path, see [`Events::path_for`]. This
+/// can be created using [`Events::next_buf`] and is the message send by the
+/// [`watcher`] actor.
+#[derive(Clone)]
+pub struct EventBuf {
+    path: PathBuf,
+    mask: u32,
+}
+
+impl EventBuf {
+    /// Full path to the file or directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the full path, consuming the event.
+    pub fn into_path(self) -> PathBuf {
+        self.path
+    }
+
+    // Getters for the events.
+    bit_checks!(self.mask);
+}
+
+#[allow(clippy::missing_fields_in_debug)] // `mask` is included as fields.
+impl fmt::Debug for EventBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("EventBuf");
+        _ = f.field("path", &self.path).field("mask", &self.mask);
+        self.fmt_event_fields(&mut f);
+        f.finish()
+    }
+}
+
+/// Actor that sends all filesystem events from `watch` to `actor_ref`.
+///
+/// This allows an actor to receive filesystem events as messages, e.g. to
+/// reload configuration or TLS certificates once they change on disk. See the
+/// [module documentation] for an example.
+///
+/// # Notes
+///
+/// This actor will stop once `actor_ref` is disconnected, i.e. once the actor
+/// receiving the events stopped.
+///
+/// [module documentation]: crate::fs::watch
+pub async fn watcher<RT>(
+    _: actor::Context<!, RT>,
+    mut watch: Watch,
+    actor_ref: ActorRef<EventBuf>,
+) -> io::Result<()> {
+    let mut batch = Vec::new();
+    loop {
+        let mut events = watch.events().await?;
+        while let Some(event) = events.next_buf() {
+            batch.push(event);
+        }
+        drop(events);
+
+        for event in batch.drain(..) {
+            if actor_ref.send(event).await.is_err() {
+                debug!("actor receiving filesystem events stopped, stopping `fs::watch::watcher`");
+                return Ok(());
+            }
+        }
+    }
+}
+
 /// What kind of filesystem changes we're interested in monitering.
 #[derive(Copy, Clone, Debug)]
 pub struct Interest(u32);
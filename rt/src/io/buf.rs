@@ -7,6 +7,8 @@ use std::mem::MaybeUninit;
 use std::slice;
 use std::sync::Arc;
 
+use crate::io::checksum::{Checksum, Checksummed};
+
 /// Trait that defines the behaviour of buffers used in reading, which requires
 /// mutable access.
 ///
@@ -117,6 +119,17 @@ pub unsafe trait BufMut: 'static {
     {
         Limited { buf: self, limit }
     }
+
+    /// Wrap the buffer in `Checksummed`, which computes a checksum over all
+    /// bytes written into the buffer.
+    ///
+    /// See [`Checksummed`] for more information.
+    fn checksum<C: Checksum>(self, checksum: C) -> Checksummed<Self, C>
+    where
+        Self: Sized,
+    {
+        Checksummed::for_buf_mut(self, checksum)
+    }
 }
 
 /// Copies bytes from `src` to `dst`, copies up to `min(dst_len, src.len())`,
@@ -366,6 +379,17 @@ pub unsafe trait Buf: 'static {
     {
         Limited { buf: self, limit }
     }
+
+    /// Wrap the buffer in `Checksummed`, which computes a checksum over all
+    /// bytes in the buffer.
+    ///
+    /// See [`Checksummed`] for more information.
+    fn checksum<C: Checksum>(self, checksum: C) -> Checksummed<Self, C>
+    where
+        Self: Sized,
+    {
+        Checksummed::for_buf(self, checksum)
+    }
 }
 
 // SAFETY: `Vec<u8>` manages the allocation of the bytes, so as long as it's
@@ -660,6 +684,8 @@ unsafe impl<B: BufMut> a10::io::BufMut for BufWrapper<B> {
     #[allow(clippy::cast_possible_truncation)]
     unsafe fn parts_mut(&mut self) -> (*mut u8, u32) {
         let (ptr, size) = self.0.parts_mut();
+        #[cfg(any(test, feature = "test"))]
+        let size = crate::test::short_read_limit(size);
         (ptr, size as u32)
     }
 
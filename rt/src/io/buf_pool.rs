@@ -57,6 +57,11 @@ impl ReadBufPool {
             inner: self.inner.get(),
         }
     }
+
+    /// Returns the underlying A10 buffer pool.
+    pub(crate) fn into_inner(self) -> a10::io::ReadBufPool {
+        self.inner
+    }
 }
 
 /// Buffer reference from a [`ReadBufPool`].
@@ -74,6 +79,11 @@ pub struct ReadBuf {
 }
 
 impl ReadBuf {
+    /// Wrap an A10 read buffer.
+    pub(crate) const fn from_inner(inner: a10::io::ReadBuf) -> ReadBuf {
+        ReadBuf { inner }
+    }
+
     /// Returns the capacity of the buffer.
     pub fn capacity(&self) -> usize {
         self.inner.capacity()
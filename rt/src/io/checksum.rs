@@ -0,0 +1,408 @@
+//! Streaming checksums.
+//!
+//! See [`Checksummed`] for computing a checksum over the bytes passing through
+//! an I/O operation.
+
+use std::slice;
+
+use crate::io::{Buf, BufMut};
+
+/// Checksum algorithm that can be computed incrementally.
+///
+/// Implemented by [`Crc32c`] and [`XxHash64`].
+pub trait Checksum: 'static {
+    /// Result of the checksum, e.g. `u32` for CRC32C.
+    type Output;
+
+    /// Add `bytes` to the checksum.
+    fn update(&mut self, bytes: &[u8]);
+
+    /// Returns the checksum of all bytes passed to [`Checksum::update`] so
+    /// far.
+    ///
+    /// This doesn't reset the state, more bytes can be added after calling
+    /// this method.
+    fn finish(&self) -> Self::Output;
+}
+
+/// CRC32C (Castagnoli) checksum.
+///
+/// This is the CRC used by iSCSI, ext4 and many storage and replication
+/// protocols.
+///
+/// # Examples
+///
+/// ```
+/// use heph_rt::io::{Checksum, Crc32c};
+///
+/// let mut crc = Crc32c::new();
+/// crc.update(b"12345");
+/// crc.update(b"6789");
+/// assert_eq!(crc.finish(), 0xE306_9283);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Crc32c {
+    /// Inverted CRC.
+    state: u32,
+}
+
+/// Reversed CRC32C polynomial.
+const CRC32C_POLY: u32 = 0x82F6_3B78;
+
+/// Lookup tables for the slicing-by-8 algorithm.
+static CRC32C_TABLE: [[u32; 256]; 8] = crc32c_table();
+
+#[allow(clippy::cast_possible_truncation)]
+const fn crc32c_table() -> [[u32; 256]; 8] {
+    let mut table = [[0; 256]; 8];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut j = 0;
+        while j < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ CRC32C_POLY
+            } else {
+                crc >> 1
+            };
+            j += 1;
+        }
+        table[0][i] = crc;
+        i += 1;
+    }
+    let mut i = 0;
+    while i < 256 {
+        let mut t = 1;
+        while t < 8 {
+            let prev = table[t - 1][i];
+            table[t][i] = (prev >> 8) ^ table[0][(prev & 0xFF) as usize];
+            t += 1;
+        }
+        i += 1;
+    }
+    table
+}
+
+impl Crc32c {
+    /// Create a new CRC32C checksum.
+    pub const fn new() -> Crc32c {
+        Crc32c { state: !0 }
+    }
+}
+
+impl Default for Crc32c {
+    fn default() -> Crc32c {
+        Crc32c::new()
+    }
+}
+
+impl Checksum for Crc32c {
+    type Output = u32;
+
+    fn update(&mut self, bytes: &[u8]) {
+        let t = &CRC32C_TABLE;
+        let mut crc = self.state;
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            let low = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) ^ crc;
+            crc = t[7][(low & 0xFF) as usize]
+                ^ t[6][((low >> 8) & 0xFF) as usize]
+                ^ t[5][((low >> 16) & 0xFF) as usize]
+                ^ t[4][(low >> 24) as usize]
+                ^ t[3][chunk[4] as usize]
+                ^ t[2][chunk[5] as usize]
+                ^ t[1][chunk[6] as usize]
+                ^ t[0][chunk[7] as usize];
+        }
+        for byte in chunks.remainder() {
+            crc = (crc >> 8) ^ t[0][((crc ^ u32::from(*byte)) & 0xFF) as usize];
+        }
+        self.state = crc;
+    }
+
+    fn finish(&self) -> u32 {
+        !self.state
+    }
+}
+
+/// 64 bit xxHash (XXH64).
+///
+/// A fast non-cryptographic hash, **not** suitable to protect against
+/// malicious modifications.
+///
+/// # Examples
+///
+/// ```
+/// use heph_rt::io::{Checksum, XxHash64};
+///
+/// let mut hash = XxHash64::new();
+/// hash.update(b"a");
+/// assert_eq!(hash.finish(), 0xD24E_C4F1_A98C_6E5B);
+/// ```
+#[derive(Clone, Debug)]
+pub struct XxHash64 {
+    seed: u64,
+    /// Accumulators, only used once 32 or more bytes have been processed.
+    acc: [u64; 4],
+    /// Total number of bytes processed.
+    total_len: u64,
+    /// Bytes not yet processed as part of a full stripe.
+    buf: [u8; 32],
+    buf_len: usize,
+}
+
+const XXH_PRIME64_1: u64 = 0x9E37_79B1_85EB_CA87;
+const XXH_PRIME64_2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const XXH_PRIME64_3: u64 = 0x1656_67B1_9E37_79F9;
+const XXH_PRIME64_4: u64 = 0x85EB_CA77_C2B2_AE63;
+const XXH_PRIME64_5: u64 = 0x27D4_EB2F_1656_67C5;
+
+impl XxHash64 {
+    /// Create a new xxHash using a seed of zero.
+    pub const fn new() -> XxHash64 {
+        XxHash64::with_seed(0)
+    }
+
+    /// Create a new xxHash using `seed`.
+    pub const fn with_seed(seed: u64) -> XxHash64 {
+        XxHash64 {
+            seed,
+            acc: [
+                seed.wrapping_add(XXH_PRIME64_1).wrapping_add(XXH_PRIME64_2),
+                seed.wrapping_add(XXH_PRIME64_2),
+                seed,
+                seed.wrapping_sub(XXH_PRIME64_1),
+            ],
+            total_len: 0,
+            buf: [0; 32],
+            buf_len: 0,
+        }
+    }
+
+    /// Process a single 32 byte stripe.
+    fn stripe(&mut self, stripe: &[u8]) {
+        for (acc, lane) in self.acc.iter_mut().zip(stripe.chunks_exact(8)) {
+            *acc = xxh64_round(*acc, read_u64(lane));
+        }
+    }
+}
+
+impl Default for XxHash64 {
+    fn default() -> XxHash64 {
+        XxHash64::new()
+    }
+}
+
+impl Checksum for XxHash64 {
+    type Output = u64;
+
+    fn update(&mut self, mut bytes: &[u8]) {
+        self.total_len += bytes.len() as u64;
+
+        if self.buf_len != 0 {
+            let n = (32 - self.buf_len).min(bytes.len());
+            self.buf[self.buf_len..self.buf_len + n].copy_from_slice(&bytes[..n]);
+            self.buf_len += n;
+            bytes = &bytes[n..];
+            if self.buf_len < 32 {
+                return;
+            }
+            let buf = self.buf;
+            self.stripe(&buf);
+            self.buf_len = 0;
+        }
+
+        let mut stripes = bytes.chunks_exact(32);
+        for stripe in &mut stripes {
+            self.stripe(stripe);
+        }
+        let remainder = stripes.remainder();
+        self.buf[..remainder.len()].copy_from_slice(remainder);
+        self.buf_len = remainder.len();
+    }
+
+    fn finish(&self) -> u64 {
+        let mut hash = if self.total_len >= 32 {
+            let [acc1, acc2, acc3, acc4] = self.acc;
+            let mut hash = acc1
+                .rotate_left(1)
+                .wrapping_add(acc2.rotate_left(7))
+                .wrapping_add(acc3.rotate_left(12))
+                .wrapping_add(acc4.rotate_left(18));
+            for acc in self.acc {
+                hash = xxh64_merge_round(hash, acc);
+            }
+            hash
+        } else {
+            self.seed.wrapping_add(XXH_PRIME64_5)
+        };
+        hash = hash.wrapping_add(self.total_len);
+
+        let mut left = &self.buf[..self.buf_len];
+        while left.len() >= 8 {
+            hash ^= xxh64_round(0, read_u64(left));
+            hash = hash
+                .rotate_left(27)
+                .wrapping_mul(XXH_PRIME64_1)
+                .wrapping_add(XXH_PRIME64_4);
+            left = &left[8..];
+        }
+        if left.len() >= 4 {
+            let value = u32::from_le_bytes([left[0], left[1], left[2], left[3]]);
+            hash ^= u64::from(value).wrapping_mul(XXH_PRIME64_1);
+            hash = hash
+                .rotate_left(23)
+                .wrapping_mul(XXH_PRIME64_2)
+                .wrapping_add(XXH_PRIME64_3);
+            left = &left[4..];
+        }
+        for byte in left {
+            hash ^= u64::from(*byte).wrapping_mul(XXH_PRIME64_5);
+            hash = hash.rotate_left(11).wrapping_mul(XXH_PRIME64_1);
+        }
+
+        // Avalanche.
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(XXH_PRIME64_2);
+        hash ^= hash >> 29;
+        hash = hash.wrapping_mul(XXH_PRIME64_3);
+        hash ^= hash >> 32;
+        hash
+    }
+}
+
+const fn xxh64_round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(XXH_PRIME64_2))
+        .rotate_left(31)
+        .wrapping_mul(XXH_PRIME64_1)
+}
+
+const fn xxh64_merge_round(acc: u64, value: u64) -> u64 {
+    (acc ^ xxh64_round(0, value))
+        .wrapping_mul(XXH_PRIME64_1)
+        .wrapping_add(XXH_PRIME64_4)
+}
+
+/// Reads the first 8 bytes of `bytes` as little-endian `u64`.
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut value = [0; 8];
+    value.copy_from_slice(&bytes[..8]);
+    u64::from_le_bytes(value)
+}
+
+/// Buffer wrapper that computes a checksum over the bytes passing through it.
+///
+/// Created by [`BufMut::checksum`] and [`Buf::checksum`].
+///
+/// When used as [`BufMut`], e.g. in `recv_n` or `read_n`, the checksum is
+/// updated with the bytes written into the buffer as they are written. Bytes
+/// already in the buffer when it's wrapped are not included. Note that reading
+/// into a [`ReadBuf`] that doesn't yet have a buffer assigned is not supported.
+///
+/// When used as [`Buf`], e.g. in `send_all` or `write_all`, the checksum is
+/// computed over all bytes in the buffer when it's wrapped, as an operation
+/// might need to write the same bytes multiple times (e.g. in case of a short
+/// write).
+///
+/// Use [`Checksummed::into_parts`] to get the checksum state back, which can
+/// be passed to the next buffer to compute a checksum over multiple I/O
+/// operations.
+///
+/// [`ReadBuf`]: crate::io::ReadBuf
+///
+/// # Examples
+///
+/// Computing a CRC32C over a message while receiving it.
+///
+/// ```
+/// # #![allow(dead_code)]
+/// use std::io;
+///
+/// use heph_rt::io::{BufMut, Checksum, Crc32c};
+/// use heph_rt::net::TcpStream;
+///
+/// async fn recv_message(stream: &TcpStream, length: usize) -> io::Result<Vec<u8>> {
+///     let buf = Vec::with_capacity(length + 4);
+///     let buf = stream.recv_n(buf.checksum(Crc32c::new()), length).await?;
+///     let (buf, crc) = buf.into_parts();
+///
+///     // Message is followed by the CRC of the message.
+///     let mut buf = stream.recv_n(buf.limit(4), 4).await?.into_inner();
+///     let got = u32::from_le_bytes(buf[length..].try_into().unwrap());
+///     if got != crc.finish() {
+///         return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid checksum"));
+///     }
+///     buf.truncate(length);
+///     Ok(buf)
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Checksummed<B, C> {
+    buf: B,
+    checksum: C,
+}
+
+impl<B, C: Checksum> Checksummed<B, C> {
+    /// Wrap `buf`, checksumming the bytes written into it.
+    pub(super) fn for_buf_mut(buf: B, checksum: C) -> Checksummed<B, C>
+    where
+        B: BufMut,
+    {
+        Checksummed { buf, checksum }
+    }
+
+    /// Wrap `buf`, checksumming the bytes in it.
+    pub(super) fn for_buf(buf: B, mut checksum: C) -> Checksummed<B, C>
+    where
+        B: Buf,
+    {
+        checksum.update(buf.as_slice());
+        Checksummed { buf, checksum }
+    }
+
+    /// Returns the checksum of the bytes processed so far.
+    pub fn finish(&self) -> C::Output {
+        self.checksum.finish()
+    }
+
+    /// Returns the underlying buffer.
+    pub fn into_inner(self) -> B {
+        self.buf
+    }
+
+    /// Returns the underlying buffer and the checksum state.
+    pub fn into_parts(self) -> (B, C) {
+        (self.buf, self.checksum)
+    }
+}
+
+unsafe impl<B: BufMut, C: Checksum> BufMut for Checksummed<B, C> {
+    unsafe fn parts_mut(&mut self) -> (*mut u8, usize) {
+        self.buf.parts_mut()
+    }
+
+    unsafe fn update_length(&mut self, n: usize) {
+        // The `n` bytes are written at the start of the spare capacity, which
+        // is still returned by `parts_mut` as long as we haven't updated the
+        // length of the underlying buffer.
+        let (ptr, len) = self.buf.parts_mut();
+        debug_assert!(n <= len);
+        // SAFETY: caller must ensure the `n` bytes are initialised.
+        self.checksum.update(slice::from_raw_parts(ptr, n));
+        self.buf.update_length(n);
+    }
+
+    fn spare_capacity(&self) -> usize {
+        self.buf.spare_capacity()
+    }
+
+    fn has_spare_capacity(&self) -> bool {
+        self.buf.has_spare_capacity()
+    }
+}
+
+unsafe impl<B: Buf, C: Checksum> Buf for Checksummed<B, C> {
+    unsafe fn parts(&self) -> (*const u8, usize) {
+        self.buf.parts()
+    }
+}
@@ -10,6 +10,7 @@ use std::task::{self, Poll};
 use a10::extract::Extractor;
 
 use crate::io::buf::{Buf, BufMut, BufMutSlice, BufSlice, BufWrapper};
+use crate::io::inject_io_fault;
 use crate::wakers::no_ring_ctx;
 
 /// [`Future`] behind write implementations.
@@ -19,6 +20,7 @@ impl<'a, B: Buf> Future for Write<'a, B> {
     type Output = io::Result<(B, usize)>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -34,6 +36,7 @@ impl<'a, B: Buf> Future for WriteAll<'a, B> {
     type Output = io::Result<B>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -51,6 +54,7 @@ impl<'a, B: BufSlice<N>, const N: usize> Future for WriteVectored<'a, B, N> {
     type Output = io::Result<(B, usize)>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -68,6 +72,7 @@ impl<'a, B: BufSlice<N>, const N: usize> Future for WriteAllVectored<'a, B, N> {
     type Output = io::Result<B>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -83,6 +88,7 @@ impl<'a, B: BufMut> Future for Read<'a, B> {
     type Output = io::Result<B>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -100,6 +106,7 @@ impl<'a, B: BufMutSlice<N>, const N: usize> Future for ReadVectored<'a, B, N> {
     type Output = io::Result<B>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -115,6 +122,7 @@ impl<'a, B: BufMut> Future for ReadN<'a, B> {
     type Output = io::Result<B>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -132,6 +140,7 @@ impl<'a, B: BufMutSlice<N>, const N: usize> Future for ReadNVectored<'a, B, N> {
     type Output = io::Result<B>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
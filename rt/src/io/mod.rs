@@ -42,6 +42,10 @@
 //! [`ReadBufPool`] is a specialised read buffer pool that can only be used in
 //! read operations done by the kernel, i.e. no in-memory operations.
 //!
+//! [`Checksummed`] can be used to compute a checksum, e.g. [`Crc32c`] or
+//! [`XxHash64`], over the bytes read into or written from a buffer, see
+//! [`BufMut::checksum`] and [`Buf::checksum`].
+//!
 //! # Working with Standard I/O Streams
 //!
 //! The [`stdin`], [`stdout`] and [`stderr`] function provide handles to
@@ -59,6 +63,9 @@ mod buf;
 pub(crate) use buf::BufWrapper;
 pub use buf::{Buf, BufMut, BufMutSlice, BufSlice, Limited};
 
+mod checksum;
+pub use checksum::{Checksum, Checksummed, Crc32c, XxHash64};
+
 mod buf_pool;
 pub use buf_pool::{ReadBuf, ReadBufPool};
 
@@ -170,7 +177,21 @@ macro_rules! impl_write {
     };
 }
 
-pub(crate) use {impl_read, impl_write};
+/// Inject an I/O fault set using [`test::inject_io_faults`], if any.
+///
+/// Returns from the `Future::poll` implementation if a fault is injected.
+///
+/// [`test::inject_io_faults`]: crate::test::inject_io_faults
+macro_rules! inject_io_fault {
+    ($ctx: ident) => {
+        #[cfg(any(test, feature = "test"))]
+        if let Some(poll) = $crate::test::inject_io_fault($ctx) {
+            return poll.map(Err);
+        }
+    };
+}
+
+pub(crate) use {impl_read, impl_write, inject_io_fault};
 
 impl_read!(Stdin, &Stdin);
 impl_write!(Stdout, &Stdout);
@@ -48,7 +48,10 @@
 //! option is the number of threads the runtime uses, this can configured with
 //! the [`num_threads`] and [`use_all_cores`] methods. When using
 //! `use_all_cores` the CPU affinity can automatically be set using
-//! [`auto_cpu_affinity`].
+//! [`auto_cpu_affinity`]. To tune the runtime without recompiling the
+//! application the configuration can also be loaded from environment variables
+//! or a configuration file, using [`Setup::from_env`] or
+//! [`Setup::from_config_file`] respectively.
 //!
 //! Once the runtime is fully configured it can be [`build`], which returns the
 //! [`Runtime`] type.
@@ -170,8 +173,13 @@
 //!
 //! ## Features
 //!
-//! This crate has one optional: `test`. The `test` feature will enable the
-//! `test` module which adds testing facilities.
+//! This crate has four optional features: `test`, `console`, `alloc` and
+//! `metrics`. The `test` feature will enable the `test` module which adds
+//! testing facilities. The `console` feature will enable the `console` module,
+//! which allows live introspection of a running runtime. The `alloc` feature
+//! will enable the `alloc` module, which attributes heap usage to the running
+//! actors. The `metrics` feature will record the scheduling latency of the
+//! actors, which is included in the worker metrics.
 
 #![feature(
     async_iterator,
@@ -245,7 +253,12 @@ use heph::supervisor::{Supervisor, SyncSupervisor};
 use heph::{ActorFutureBuilder, NewActor, SyncActor};
 
 pub mod access;
+#[cfg(feature = "alloc")]
+pub mod alloc;
 mod channel;
+mod config;
+#[cfg(feature = "console")]
+pub mod console;
 mod coordinator;
 mod error;
 pub mod fs;
@@ -254,7 +267,9 @@ mod local;
 pub mod log;
 pub mod net;
 pub mod pipe;
-mod process;
+pub mod process;
+mod ring;
+pub mod schedule;
 mod scheduler;
 mod setup;
 mod shared;
@@ -268,6 +283,7 @@ pub mod test;
 pub mod timer;
 mod timers;
 pub mod trace;
+pub mod upgrade;
 #[doc(hidden)]
 pub mod util;
 mod wakers;
@@ -278,8 +294,9 @@ use process::ProcessId;
 #[doc(no_inline)]
 pub use access::{Access, Sync, ThreadLocal, ThreadSafe};
 pub use error::Error;
+pub use local::LocalData;
 pub use setup::Setup;
-pub use signal::Signal;
+pub use signal::{RecvSignal, Signal, SignalReceiver};
 
 use crate::process::{FutureProcess, Process};
 use coordinator::CoordinatorSetup;
@@ -442,6 +459,16 @@ impl Runtime {
         Ok(())
     }
 
+    /// Returns the runtime-wide shared resource of type `T`, if any.
+    ///
+    /// See [`Setup::with_resource`] for more documentation.
+    pub fn resource<T>(&self) -> Option<Arc<T>>
+    where
+        T: Send + std::marker::Sync + 'static,
+    {
+        self.internals.resource()
+    }
+
     /// Receive [process signals] as messages.
     ///
     /// This adds the `actor_ref` to the list of actor references that will
@@ -454,6 +481,17 @@ impl Runtime {
         self.signals.add(actor_ref);
     }
 
+    /// Create a new [`SignalReceiver`] to receive [process signals].
+    ///
+    /// See [`RuntimeRef::signal_receiver`] for more documentation.
+    ///
+    /// [process signals]: Signal
+    pub fn signal_receiver(&mut self) -> SignalReceiver {
+        let (receiver, actor_ref) = SignalReceiver::new();
+        self.receive_signals(actor_ref);
+        receiver
+    }
+
     /// Run the runtime.
     ///
     /// This will wait until all spawned workers have finished, which happens
@@ -631,6 +669,107 @@ impl RuntimeRef {
             .add_unique(actor_ref);
     }
 
+    /// Create a new [`SignalReceiver`] to receive [process signals].
+    ///
+    /// Unlike [`RuntimeRef::receive_signals`] the signals are not received as
+    /// messages in the actor's inbox, but using the returned receiver. This
+    /// can be combined with receiving messages using
+    /// [`ReceiveMessage::or_signal`].
+    ///
+    /// [process signals]: Signal
+    /// [`ReceiveMessage::or_signal`]: heph::actor::ReceiveMessage::or_signal
+    pub fn signal_receiver(&mut self) -> SignalReceiver {
+        let (receiver, actor_ref) = SignalReceiver::new();
+        self.receive_signals(actor_ref);
+        receiver
+    }
+
+    /// Get access to the worker-local data.
+    ///
+    /// This calls `f` with the data of the worker thread this is called on.
+    /// See [`LocalData`] for more information.
+    ///
+    /// # Panics
+    ///
+    /// This panics if `local_data` is called again inside of `f`.
+    pub fn local_data<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut LocalData) -> T,
+    {
+        f(&mut self.internals.data.borrow_mut())
+    }
+
+    /// Returns the runtime-wide shared resource of type `T`, if any.
+    ///
+    /// See [`Setup::with_resource`] for more documentation.
+    pub fn resource<T>(&self) -> Option<Arc<T>>
+    where
+        T: Send + std::marker::Sync + 'static,
+    {
+        self.internals.shared.resource()
+    }
+
+    /// Register a `hook` to run when the runtime shuts down.
+    ///
+    /// Once all actors and futures have stopped running, and thus all worker
+    /// threads have stopped, the coordinator runs the hooks one by one, in
+    /// order of registration. This can be used to flush metrics or close
+    /// connection pools for example. The hooks are run before the runtime-wide
+    /// shared resources are dropped, so they can still use them (see
+    /// [`Setup::with_resource`]).
+    ///
+    /// # Notes
+    ///
+    /// All hooks together must complete within the shutdown timeout, see
+    /// [`Setup::with_shutdown_timeout`]. The hooks that haven't completed by
+    /// then are dropped.
+    ///
+    /// The hooks are run on the coordinator thread, this means that they
+    /// can't use thread-local runtime access ([`ThreadLocal`]), but the
+    /// thread-safe version ([`ThreadSafe`]) can be used.
+    ///
+    /// If the runtime stops because of an error the hooks are not run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(never_type)]
+    /// use heph_rt::{self as rt, Runtime, RuntimeRef};
+    ///
+    /// fn main() -> Result<(), rt::Error> {
+    ///     let mut runtime = Runtime::new()?;
+    ///     runtime.run_on_workers(setup)?;
+    ///     runtime.start()
+    /// }
+    ///
+    /// fn setup(mut runtime_ref: RuntimeRef) -> Result<(), !> {
+    ///     runtime_ref.on_shutdown(async {
+    ///         println!("flushing metrics");
+    ///     });
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn on_shutdown<Fut>(&mut self, hook: Fut)
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.internals.shared.add_shutdown_hook(hook);
+    }
+
+    /// Log the recent events of the worker thread this is called on.
+    ///
+    /// Each worker thread keeps a small, always enabled, ring buffer of its
+    /// most recent events, e.g. running processes and polling for OS events,
+    /// called the flight recorder. This logs all events in it using the
+    /// `flight_recorder` log target, oldest first. This is also done when a
+    /// worker thread panics and when the process receives [`Signal::User2`].
+    ///
+    /// To dump the flight recorder of all worker threads use
+    /// [`Runtime::run_on_workers`].
+    pub fn dump_flight_recorder(&self) {
+        self.internals.dump_flight_recorder();
+    }
+
     /// Add a timer.
     pub(crate) fn add_timer(&self, deadline: Instant, waker: task::Waker) -> TimerToken {
         ::log::trace!(deadline:? = deadline; "adding timer");
@@ -694,6 +833,8 @@ where
         let (process, actor_ref) = ActorFutureBuilder::new()
             .with_rt(rt)
             .with_inbox_size(options.inbox_size())
+            .with_keep_on_restart(options.keep_on_restart())
+            .with_budget(options.budget())
             .build(supervisor, new_actor, arg)?;
         let pid = self
             .internals
@@ -754,7 +895,10 @@ fn panic_message<'a>(panic: &'a (dyn Any + Send + 'static)) -> &'a str {
         Some(s) => s,
         None => match panic.downcast_ref::<String>() {
             Some(s) => s,
-            None => "<unknown>",
+            None => match panic.downcast_ref::<worker::PanicReport>() {
+                Some(report) => &report.message,
+                None => "<unknown>",
+            },
         },
     }
 }
@@ -0,0 +1,131 @@
+//! Module containing the [`LocalData`] type.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Worker-local data.
+///
+/// Type map, storing at most one value per type, that is owned by a worker
+/// thread. Because the values never leave the worker thread they don't have to
+/// be [`Send`] or [`Sync`], making it possible to share per-thread caches,
+/// random number generators or database handles between thread-local actors
+/// without using `Arc<Mutex<..>>`.
+///
+/// The data can be accessed using [`RuntimeRef::local_data`], which is also
+/// available to thread-local actors via [`ThreadLocal`]. To initialise the data
+/// on all worker threads see [`Setup::with_local_data`].
+///
+/// [`RuntimeRef::local_data`]: crate::RuntimeRef::local_data
+/// [`ThreadLocal`]: crate::ThreadLocal
+/// [`Setup::with_local_data`]: crate::Setup::with_local_data
+///
+/// # Examples
+///
+/// ```
+/// # #![feature(never_type)]
+/// use std::cell::Cell;
+///
+/// use heph::actor;
+/// use heph_rt::ThreadLocal;
+///
+/// /// Number of requests handled by all actors on the worker thread.
+/// struct Requests(Cell<usize>);
+///
+/// async fn actor(mut ctx: actor::Context<String, ThreadLocal>) {
+///     while let Ok(msg) = ctx.receive_next().await {
+///         ctx.runtime().local_data(|data| {
+///             let requests = data.get_or_insert_with(|| Requests(Cell::new(0)));
+///             requests.0.set(requests.0.get() + 1);
+///         });
+///         println!("Got a message: {msg}");
+///     }
+/// }
+/// # _ = actor; // Silence dead code warnings.
+/// ```
+#[derive(Default)]
+pub struct LocalData {
+    map: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl LocalData {
+    /// Create empty data.
+    pub(crate) fn new() -> LocalData {
+        LocalData {
+            map: HashMap::new(),
+        }
+    }
+
+    /// Insert `value`, returning the previous value of the same type (if any).
+    pub fn insert<T: 'static>(&mut self, value: T) -> Option<T> {
+        self.map
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .map(downcast)
+    }
+
+    /// Returns a reference to the value of type `T`, if any.
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
+
+    /// Returns a mutable reference to the value of type `T`, if any.
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.map
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_mut())
+    }
+
+    /// Returns a mutable reference to the value of type `T`, inserting the
+    /// value returned by `f` if no value is present.
+    pub fn get_or_insert_with<T, F>(&mut self, f: F) -> &mut T
+    where
+        T: 'static,
+        F: FnOnce() -> T,
+    {
+        self.map
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(f()))
+            .downcast_mut()
+            .unwrap()
+    }
+
+    /// Returns `true` if a value of type `T` is present.
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.map.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Remove the value of type `T`, returning it (if any).
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        self.map.remove(&TypeId::of::<T>()).map(downcast)
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Remove all values.
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+}
+
+/// Downcast a value stored under `TypeId::of::<T>()`.
+fn downcast<T: 'static>(value: Box<dyn Any>) -> T {
+    *value.downcast().unwrap()
+}
+
+impl fmt::Debug for LocalData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalData")
+            .field("len", &self.len())
+            .finish()
+    }
+}
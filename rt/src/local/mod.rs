@@ -9,11 +9,21 @@ use std::sync::Arc;
 use heph::actor_ref::{ActorGroup, SendError};
 use log::{info, trace};
 
+#[cfg(feature = "alloc")]
+use crate::alloc;
+#[cfg(feature = "console")]
+use crate::console::WorkerStats;
+use crate::ring::RingMetrics;
+use crate::scheduler::latency::SchedulingLatency;
 use crate::scheduler::Scheduler;
 use crate::timers::Timers;
 use crate::wakers::Wakers;
 use crate::{cpu_usage, panic_message, shared, trace, worker, RuntimeRef, Signal};
 
+mod data;
+
+pub use data::LocalData;
+
 /// Internals of the runtime, to which `RuntimeRef`s have a reference.
 #[derive(Debug)]
 pub(crate) struct RuntimeInternals {
@@ -33,8 +43,19 @@ pub(crate) struct RuntimeInternals {
     pub(crate) signal_receivers: RefCell<ActorGroup<Signal>>,
     /// CPU affinity of the worker thread, or `None` if not set.
     pub(crate) cpu: Option<usize>,
+    /// Worker-local data.
+    pub(crate) data: RefCell<LocalData>,
     /// Log used for tracing, `None` is tracing is disabled.
     pub(crate) trace_log: RefCell<Option<trace::Log>>,
+    /// Recent events of the worker, always enabled.
+    pub(crate) flight_recorder: RefCell<trace::FlightRecorder>,
+    /// Scheduling latency of the processes run by the worker, both thread-local
+    /// and thread-safe.
+    pub(crate) scheduling_latency: RefCell<SchedulingLatency>,
+    /// Statistics of the worker for the console, also registered in the
+    /// shared internals.
+    #[cfg(feature = "console")]
+    pub(crate) console: Arc<WorkerStats>,
     /// Whether or not the runtime was started.
     ///
     /// This is here because the worker threads are started before
@@ -59,16 +80,28 @@ impl RuntimeInternals {
         cpu: Option<usize>,
         trace_log: Option<trace::Log>,
     ) -> RuntimeInternals {
+        let timer_granularity = shared_internals.timer_granularity();
+        #[cfg(feature = "console")]
+        let console = {
+            let stats = Arc::new(WorkerStats::new(id));
+            shared_internals.console().register(stats.clone());
+            stats
+        };
         RuntimeInternals {
             id,
             shared: shared_internals,
             wakers: RefCell::new(wakers),
             scheduler: RefCell::new(Scheduler::new()),
             ring: RefCell::new(ring),
-            timers: RefCell::new(Timers::new()),
+            timers: RefCell::new(Timers::new().with_granularity(timer_granularity)),
             signal_receivers: RefCell::new(ActorGroup::empty()),
             cpu,
+            data: RefCell::new(LocalData::new()),
             trace_log: RefCell::new(trace_log),
+            flight_recorder: RefCell::new(trace::FlightRecorder::new()),
+            scheduling_latency: RefCell::new(SchedulingLatency::new()),
+            #[cfg(feature = "console")]
+            console,
             started: Cell::new(false),
             error: RefCell::new(None),
         }
@@ -79,9 +112,11 @@ impl RuntimeInternals {
     pub(crate) fn relay_signal(&self, signal: Signal) {
         let timing = trace::start(&*self.trace_log.borrow());
         trace!(worker_id = self.id.get(), signal:? = signal; "received process signal");
+        self.record_event("Relaying process signal", signal.to_signo() as u64);
 
         if let Signal::User2 = signal {
             self.log_metrics();
+            self.dump_flight_recorder();
         }
 
         let mut receivers = self.signal_receivers.borrow_mut();
@@ -114,6 +149,7 @@ impl RuntimeInternals {
             cpu_affinity = self.cpu,
             scheduler_ready = scheduler.ready(),
             scheduler_inactive = scheduler.inactive(),
+            scheduler_reclaimed = scheduler.reclaimed(),
             timers_total = timers.len(),
             timers_next:? = timers.next_timer(),
             process_signal_receivers = self.signal_receivers.borrow().len(),
@@ -121,6 +157,49 @@ impl RuntimeInternals {
             trace_counter = trace_metrics.map_or(0, |m| m.counter);
             "worker metrics",
         );
+        let ring = RingMetrics::read(&self.ring.borrow()).unwrap_or_default();
+        info!(
+            target: "metrics",
+            worker_id = self.id.get(),
+            sq_pending = ring.sq_pending,
+            cq_pending = ring.cq_pending,
+            submissions = ring.submissions,
+            completions = ring.completions,
+            cq_overflows = ring.cq_overflows;
+            "io_uring metrics",
+        );
+        for (priority, latency) in self.scheduling_latency.borrow().iter() {
+            if latency.count() == 0 {
+                continue;
+            }
+            info!(
+                target: "metrics",
+                worker_id = self.id.get(),
+                priority:? = priority,
+                count = latency.count(),
+                p50:? = latency.percentile(50),
+                p90:? = latency.percentile(90),
+                p99:? = latency.percentile(99),
+                max:? = latency.max();
+                "scheduling latency metrics",
+            );
+        }
+        #[cfg(feature = "alloc")]
+        if alloc::is_enabled() {
+            for process in scheduler.processes() {
+                let memory = process.memory();
+                info!(
+                    target: "metrics",
+                    worker_id = self.id.get(),
+                    pid = process.id().0,
+                    name = process.name(),
+                    memory_allocated = memory.allocated(),
+                    memory_deallocated = memory.deallocated(),
+                    memory_in_use = memory.in_use();
+                    "process memory metrics",
+                );
+            }
+        }
         trace::finish_rt(
             self.trace_log.borrow_mut().as_mut(),
             timing,
@@ -129,12 +208,38 @@ impl RuntimeInternals {
         );
     }
 
+    /// Record an event in the flight recorder.
+    pub(crate) fn record_event(&self, description: &'static str, value: u64) {
+        self.flight_recorder.borrow_mut().record(description, value);
+    }
+
+    /// Returns the last `n` events in the flight recorder, see
+    /// [`trace::FlightRecorder::recent`].
+    pub(crate) fn recent_events(&self, n: usize) -> Vec<String> {
+        // NOTE: this is also called when panicking, at which point the flight
+        // recorder could still be borrowed.
+        self.flight_recorder
+            .try_borrow()
+            .map(|flight_recorder| flight_recorder.recent(n))
+            .unwrap_or_default()
+    }
+
+    /// Log the events in the flight recorder.
+    pub(crate) fn dump_flight_recorder(&self) {
+        // NOTE: this is also called when panicking, at which point the flight
+        // recorder could still be borrowed.
+        if let Ok(flight_recorder) = self.flight_recorder.try_borrow() {
+            flight_recorder.dump(self.id.get());
+        }
+    }
+
     /// Run user function `f`, setting the error if it fails.
     pub(crate) fn run_user_function(
         self: &Rc<Self>,
         f: Box<dyn FnOnce(RuntimeRef) -> Result<(), String>>,
     ) {
         let timing = trace::start(&*self.trace_log.borrow());
+        self.record_event("Running user function", 0);
         let runtime_ref = RuntimeRef {
             internals: self.clone(),
         };
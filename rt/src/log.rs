@@ -30,3 +30,105 @@
 //!     runtime.start()
 //! }
 //! ```
+//!
+//! # Actor context
+//!
+//! Logs emitted while an actor (or other process) is being run by the runtime
+//! can include the process id (`pid`) and name of the actor (`actor`). This
+//! is done by adding the [`ActorContext`] key-value source to the logger.
+//! Using `std-logger` this can be done using `Config::with_kvs`, other logging
+//! implementations can use [`ActorContext`] in their implementation of
+//! [`Log::log`].
+//!
+//! [`Log::log`]: log::Log::log
+//!
+//! ```
+//! #![feature(never_type)]
+//!
+//! use heph_rt::log::ActorContext;
+//! use heph_rt::Runtime;
+//!
+//! fn main() -> Result<(), heph_rt::Error> {
+//!     // Enable logging, adding the actor context to all logs.
+//!     std_logger::Config::logfmt().with_kvs(ActorContext).init();
+//!
+//!     let runtime = Runtime::new()?;
+//!     // Runtime setup etc.
+//!     runtime.start()
+//! }
+//! ```
+
+use std::cell::Cell;
+
+use log::kv::{self, Key, Value, VisitSource};
+
+use crate::process::ProcessId;
+
+thread_local! {
+    /// Pid and name of the currently running process, if any.
+    static CURRENT: Cell<Option<(ProcessId, &'static str)>> = const { Cell::new(None) };
+}
+
+/// Run `f` with the log context of the process with `pid` and `name`.
+pub(crate) fn with_context<F: FnOnce() -> T, T>(pid: ProcessId, name: &'static str, f: F) -> T {
+    /// Resets the current context, also on panics.
+    struct Reset(Option<(ProcessId, &'static str)>);
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            CURRENT.with(|current| current.set(self.0));
+        }
+    }
+
+    let previous = CURRENT.with(|current| current.replace(Some((pid, name))));
+    let _reset = Reset(previous);
+    f()
+}
+
+/// Key-value [`Source`] of the currently running actor.
+///
+/// This adds the process id (`pid`) and the name of the actor (`actor`) to the
+/// key-values if the log was emitted while an actor is being run, see the
+/// [module documentation] for an example.
+///
+/// [`Source`]: kv::Source
+/// [module documentation]: crate::log#actor-context
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ActorContext;
+
+impl kv::Source for ActorContext {
+    fn visit<'kvs>(&'kvs self, visitor: &mut dyn VisitSource<'kvs>) -> Result<(), kv::Error> {
+        // NOTE: `try_with` fails if the thread local was already destroyed,
+        // which can happen for logs emitted when the thread is stopping.
+        if let Ok(Some((pid, name))) = CURRENT.try_with(Cell::get) {
+            visitor.visit_pair(Key::from("pid"), Value::from(pid.0))?;
+            visitor.visit_pair(Key::from("actor"), Value::from(name))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use log::kv::Source;
+
+    use super::{with_context, ActorContext};
+    use crate::process::ProcessId;
+
+    #[test]
+    fn actor_context() {
+        assert_eq!(ActorContext.count(), 0);
+        with_context(ProcessId(1), "my_actor", || {
+            assert_eq!(ActorContext.get("pid".into()).unwrap().to_u64(), Some(1));
+            let name = ActorContext.get("actor".into()).unwrap().to_string();
+            assert_eq!(name, "my_actor");
+
+            // Nested context.
+            with_context(ProcessId(2), "other", || {
+                assert_eq!(ActorContext.get("pid".into()).unwrap().to_u64(), Some(2));
+            });
+            assert_eq!(ActorContext.get("pid".into()).unwrap().to_u64(), Some(1));
+        });
+        assert_eq!(ActorContext.count(), 0);
+    }
+}
@@ -0,0 +1,276 @@
+//! Module with [`SocketConfig`].
+
+use std::io;
+#[cfg(target_os = "linux")]
+use std::mem::size_of;
+use std::net::SocketAddr;
+#[cfg(target_os = "linux")]
+use std::os::fd::AsRawFd;
+#[cfg(target_os = "linux")]
+use std::ptr;
+use std::time::Duration;
+
+use socket2::{Protocol, Socket};
+
+use crate::net::tcp::stream::KeepAlive;
+
+/// Socket options applied when creating a socket.
+///
+/// Some options, e.g. the buffer sizes and bind-to-device, are best (or can
+/// only be) set before the socket is bound or connected, which isn't possible
+/// with the `set_*` methods on the sockets. This configuration is applied after
+/// the socket is created, but before it is bound or connected, see
+/// [`TcpStream::connect_with`], [`TcpListener::bind_with`] and
+/// [`UdpSocket::bind_with`].
+///
+/// All options are optional, options that are not set are left at the default
+/// set by the OS. TCP only options, such as `TCP_NODELAY` and the keepalive
+/// options, are ignored for UDP sockets.
+///
+/// For proxy and anycast deployments the options to bind to non-local
+/// addresses ([`with_freebind`] and [`with_transparent`]) and to control
+/// dual-stack IPv6 sockets ([`with_only_v6`]) are also available.
+///
+/// [`with_freebind`]: SocketConfig::with_freebind
+/// [`with_transparent`]: SocketConfig::with_transparent
+/// [`with_only_v6`]: SocketConfig::with_only_v6
+///
+/// [`TcpStream::connect_with`]: crate::net::TcpStream::connect_with
+/// [`TcpListener::bind_with`]: crate::net::TcpListener::bind_with
+/// [`UdpSocket::bind_with`]: crate::net::UdpSocket::bind_with
+///
+/// # Examples
+///
+/// ```
+/// #![feature(never_type)]
+///
+/// use std::io;
+/// use std::time::Duration;
+///
+/// use heph::actor;
+/// use heph_rt::net::tcp::stream::KeepAlive;
+/// use heph_rt::net::{SocketConfig, TcpStream};
+/// use heph_rt::ThreadLocal;
+///
+/// async fn actor(ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+///     let keepalive = KeepAlive::new()
+///         .with_time(Duration::from_secs(60))
+///         .with_interval(Duration::from_secs(10))
+///         .with_retries(3);
+///     let config = SocketConfig::new()
+///         .with_nodelay(true)
+///         .with_keepalive(keepalive)
+///         .with_send_buffer_size(64 * 1024);
+///
+///     let address = "127.0.0.1:12345".parse().unwrap();
+///     let stream = TcpStream::connect_with(ctx.runtime_ref(), address, &config).await?;
+///
+///     stream.send_all("Hello world!").await?;
+///     Ok(())
+/// }
+/// # _ = actor; // Silent dead code warnings.
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SocketConfig {
+    ttl: Option<u32>,
+    nodelay: Option<bool>,
+    keepalive: Option<KeepAlive>,
+    user_timeout: Option<Duration>,
+    send_buffer_size: Option<usize>,
+    recv_buffer_size: Option<usize>,
+    tos: Option<u32>,
+    bind_device: Option<Vec<u8>>,
+    only_v6: Option<bool>,
+    freebind: Option<bool>,
+    transparent: Option<bool>,
+}
+
+impl SocketConfig {
+    /// Create a new configuration that doesn't set any options.
+    pub const fn new() -> SocketConfig {
+        SocketConfig {
+            ttl: None,
+            nodelay: None,
+            keepalive: None,
+            user_timeout: None,
+            send_buffer_size: None,
+            recv_buffer_size: None,
+            tos: None,
+            bind_device: None,
+            only_v6: None,
+            freebind: None,
+            transparent: None,
+        }
+    }
+
+    /// Set the value for the `IP_TTL` option, or the `IPV6_UNICAST_HOPS`
+    /// option for IPv6 sockets.
+    pub const fn with_ttl(mut self, ttl: u32) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Set the value of the `TCP_NODELAY` option.
+    pub const fn with_nodelay(mut self, nodelay: bool) -> Self {
+        self.nodelay = Some(nodelay);
+        self
+    }
+
+    /// Enable `SO_KEEPALIVE` using the `keepalive` parameters.
+    pub const fn with_keepalive(mut self, keepalive: KeepAlive) -> Self {
+        self.keepalive = Some(keepalive);
+        self
+    }
+
+    /// Set the value of the `TCP_USER_TIMEOUT` option.
+    pub const fn with_user_timeout(mut self, timeout: Duration) -> Self {
+        self.user_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the value of the `SO_SNDBUF` option.
+    pub const fn with_send_buffer_size(mut self, size: usize) -> Self {
+        self.send_buffer_size = Some(size);
+        self
+    }
+
+    /// Set the value of the `SO_RCVBUF` option.
+    pub const fn with_recv_buffer_size(mut self, size: usize) -> Self {
+        self.recv_buffer_size = Some(size);
+        self
+    }
+
+    /// Set the value of the `IP_TOS` option, or the `IPV6_TCLASS` (traffic
+    /// class) option for IPv6 sockets.
+    pub const fn with_tos(mut self, tos: u32) -> Self {
+        self.tos = Some(tos);
+        self
+    }
+
+    /// Bind the socket to the network interface with the name `interface`,
+    /// e.g. `eth0` (`SO_BINDTODEVICE`).
+    ///
+    /// This is only supported on Linux, on other OSs this option is ignored.
+    pub fn with_bind_device<I>(mut self, interface: I) -> Self
+    where
+        I: Into<Vec<u8>>,
+    {
+        self.bind_device = Some(interface.into());
+        self
+    }
+
+    /// Set the value of the `IPV6_V6ONLY` option.
+    ///
+    /// If `false` an IPv6 socket bound to the unspecified address (`[::]`)
+    /// also accepts IPv4 traffic (dual-stack), if `true` only IPv6 traffic is
+    /// accepted, which allows another socket to bind to the same port using
+    /// IPv4. The default depends on the OS configuration
+    /// (`net.ipv6.bindv6only` on Linux). This option is ignored for IPv4
+    /// sockets.
+    pub const fn with_only_v6(mut self, only_v6: bool) -> Self {
+        self.only_v6 = Some(only_v6);
+        self
+    }
+
+    /// Set the value of the `IP_FREEBIND` option, or the `IPV6_FREEBIND`
+    /// option for IPv6 sockets.
+    ///
+    /// This allows binding to an address that is non-local or doesn't exist
+    /// (yet), e.g. a floating IP address that is assigned to the host later.
+    ///
+    /// This is only supported on Linux, on other OSs this option is ignored.
+    pub const fn with_freebind(mut self, freebind: bool) -> Self {
+        self.freebind = Some(freebind);
+        self
+    }
+
+    /// Set the value of the `IP_TRANSPARENT` option, or the
+    /// `IPV6_TRANSPARENT` option for IPv6 sockets.
+    ///
+    /// This enables transparent proxying, allowing the socket to bind to a
+    /// non-local address and to accept connections for (and send packets from)
+    /// foreign addresses, e.g. in combination with the iptables `TPROXY`
+    /// target. This requires the `CAP_NET_ADMIN` capability.
+    ///
+    /// This is only supported on Linux, on other OSs this option is ignored.
+    pub const fn with_transparent(mut self, transparent: bool) -> Self {
+        self.transparent = Some(transparent);
+        self
+    }
+
+    /// Apply the configuration to `socket`, which will be bound or connected
+    /// to `address` using `protocol`.
+    pub(crate) fn apply(
+        &self,
+        socket: &Socket,
+        address: SocketAddr,
+        protocol: Protocol,
+    ) -> io::Result<()> {
+        if let Some(ttl) = self.ttl {
+            match address {
+                SocketAddr::V4(_) => socket.set_ttl(ttl)?,
+                SocketAddr::V6(_) => socket.set_unicast_hops_v6(ttl)?,
+            }
+        }
+        if let Some(tos) = self.tos {
+            match address {
+                SocketAddr::V4(_) => socket.set_tos(tos)?,
+                SocketAddr::V6(_) => socket.set_tclass_v6(tos)?,
+            }
+        }
+        if let Some(size) = self.send_buffer_size {
+            socket.set_send_buffer_size(size)?;
+        }
+        if let Some(size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(size)?;
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(interface) = &self.bind_device {
+            socket.bind_device(Some(interface))?;
+        }
+        if let (Some(only_v6), SocketAddr::V6(_)) = (self.only_v6, address) {
+            socket.set_only_v6(only_v6)?;
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(freebind) = self.freebind {
+            match address {
+                SocketAddr::V4(_) => socket.set_freebind(freebind)?,
+                SocketAddr::V6(_) => socket.set_freebind_ipv6(freebind)?,
+            }
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(transparent) = self.transparent {
+            match address {
+                SocketAddr::V4(_) => socket.set_ip_transparent(transparent)?,
+                SocketAddr::V6(_) => set_ipv6_transparent(socket, transparent)?,
+            }
+        }
+
+        if protocol == Protocol::TCP {
+            if let Some(nodelay) = self.nodelay {
+                socket.set_nodelay(nodelay)?;
+            }
+            if let Some(keepalive) = &self.keepalive {
+                socket.set_tcp_keepalive(&keepalive.as_socket2())?;
+            }
+            if let Some(timeout) = self.user_timeout {
+                socket.set_tcp_user_timeout(Some(timeout))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Set the `IPV6_TRANSPARENT` option, which socket2 doesn't support.
+#[cfg(target_os = "linux")]
+fn set_ipv6_transparent(socket: &Socket, transparent: bool) -> io::Result<()> {
+    let value = libc::c_int::from(transparent);
+    syscall!(setsockopt(
+        socket.as_raw_fd(),
+        libc::SOL_IPV6,
+        libc::IPV6_TRANSPARENT,
+        ptr::addr_of!(value).cast(),
+        size_of::<libc::c_int>() as libc::socklen_t,
+    ))
+    .map(|_| ())
+}
@@ -9,7 +9,7 @@ use std::task::{self, Poll};
 
 use a10::extract::Extractor;
 
-use crate::io::{Buf, BufMut, BufMutSlice, BufSlice, BufWrapper};
+use crate::io::{inject_io_fault, Buf, BufMut, BufMutSlice, BufSlice, BufWrapper};
 use crate::wakers::no_ring_ctx;
 
 /// [`Future`] behind `recv` implementations.
@@ -19,6 +19,7 @@ impl<'a, B: BufMut> Future for Recv<'a, B> {
     type Output = io::Result<B>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -34,6 +35,7 @@ impl<'a, B: BufMut> Future for RecvN<'a, B> {
     type Output = io::Result<B>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -51,6 +53,7 @@ impl<'a, B: BufMutSlice<N>, const N: usize> Future for RecvVectored<'a, B, N> {
     type Output = io::Result<B>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -68,6 +71,7 @@ impl<'a, B: BufMutSlice<N>, const N: usize> Future for RecvNVectored<'a, B, N> {
     type Output = io::Result<B>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -83,6 +87,7 @@ impl<'a, B: BufMut, A: a10::net::SocketAddress> Future for RecvFrom<'a, B, A> {
     type Output = io::Result<(B, A)>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -102,6 +107,7 @@ impl<'a, B: BufMutSlice<N>, A: a10::net::SocketAddress, const N: usize> Future
     type Output = io::Result<(B, A)>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -117,6 +123,7 @@ impl<'a, B: Buf> Future for Send<'a, B> {
     type Output = io::Result<(B, usize)>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -132,6 +139,7 @@ impl<'a, B: Buf> Future for SendAll<'a, B> {
     type Output = io::Result<B>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -149,6 +157,7 @@ impl<'a, B: BufSlice<N>, const N: usize> Future for SendVectored<'a, B, N> {
     type Output = io::Result<(B, usize)>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -166,6 +175,7 @@ impl<'a, B: BufSlice<N>, const N: usize> Future for SendAllVectored<'a, B, N> {
     type Output = io::Result<B>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -181,6 +191,7 @@ impl<'a, B: Buf, A: a10::net::SocketAddress> Future for SendTo<'a, B, A> {
     type Output = io::Result<(B, usize)>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -200,6 +211,7 @@ impl<'a, B: BufSlice<N>, A: a10::net::SocketAddress, const N: usize> Future
     type Output = io::Result<(B, usize)>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        inject_io_fault!(ctx);
         no_ring_ctx!(ctx);
         // SAFETY: not moving the `Future`.
         unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
@@ -22,16 +22,26 @@
 //! [Unix stream]: crate::net::UnixStream
 //! [Unix listening socket]: crate::net::UnixListener
 //! [Unix datagram socket]: crate::net::UnixDatagram
+//!
+//! Socket options that need to be set before a socket is bound or connected
+//! can be set using [`SocketConfig`].
 
+use std::async_iter::AsyncIterator;
 use std::mem::{size_of, MaybeUninit};
 use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::pin::Pin;
+use std::task::{self, Poll};
 use std::{fmt, io, ptr};
 
+use crate::io::ReadBuf;
+
+mod config;
 mod futures;
 pub mod tcp;
 pub mod udp;
 pub mod uds;
 
+pub use config::SocketConfig;
 #[doc(no_inline)]
 pub use tcp::{TcpListener, TcpStream};
 #[doc(no_inline)]
@@ -54,6 +64,23 @@ pub enum Unconnected {}
 #[allow(clippy::empty_enum)]
 pub enum Connected {}
 
+/// The [`AsyncIterator`] behind [`TcpStream::recv_stream`] and
+/// [`UdpSocket::recv_stream`].
+#[derive(Debug)]
+#[must_use = "AsyncIterators do nothing unless polled"]
+pub struct RecvStream<'a>(a10::net::MultishotRecv<'a>);
+
+impl<'a> AsyncIterator for RecvStream<'a> {
+    type Item = io::Result<ReadBuf>;
+
+    fn poll_next(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        // SAFETY: not moving the `Future`.
+        unsafe { Pin::map_unchecked_mut(self, |s| &mut s.0) }
+            .poll_next(ctx)
+            .map_ok(ReadBuf::from_inner)
+    }
+}
+
 /// Convert a `socket2:::SockAddr` into a `std::net::SocketAddr`.
 #[allow(clippy::needless_pass_by_value)]
 fn convert_address(address: socket2::SockAddr) -> io::Result<SocketAddr> {
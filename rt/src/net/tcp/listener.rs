@@ -11,7 +11,8 @@ use a10::AsyncFd;
 use socket2::{Domain, Protocol, SockRef, Socket, Type};
 
 use crate::access::Access;
-use crate::net::{convert_address, SockAddr, TcpStream};
+use crate::net::tcp::proxy::{self, ProxyHeader};
+use crate::net::{convert_address, SockAddr, SocketConfig, TcpStream};
 use crate::wakers::NoRing;
 
 /// A TCP socket listener.
@@ -110,6 +111,25 @@ impl TcpListener {
         TcpListener::bind_setup(rt, address, |_| Ok(())).await
     }
 
+    /// Same as [`TcpListener::bind`], but applies `config` to the socket before
+    /// binding it.
+    ///
+    /// Most options, e.g. `TCP_NODELAY`, are inherited by the accepted
+    /// [`TcpStream`]s.
+    pub async fn bind_with<RT>(
+        rt: &RT,
+        address: SocketAddr,
+        config: &SocketConfig,
+    ) -> io::Result<TcpListener>
+    where
+        RT: Access,
+    {
+        TcpListener::bind_setup(rt, address, |socket| {
+            config.apply(socket, address, Protocol::TCP)
+        })
+        .await
+    }
+
     pub(crate) async fn bind_setup<RT, F>(
         rt: &RT,
         address: SocketAddr,
@@ -198,6 +218,29 @@ impl TcpListener {
             .map(|(fd, addr)| (TcpStream { fd }, addr.into()))
     }
 
+    /// Accept a new incoming [`TcpStream`] made via a proxy using the PROXY
+    /// protocol.
+    ///
+    /// Same as [`accept`], but also reads the PROXY protocol header from the
+    /// stream, see the [`proxy`] module. The returned address is of the proxy,
+    /// the header contains the address of the client.
+    ///
+    /// [`accept`]: TcpListener::accept
+    /// [`proxy`]: crate::net::tcp::proxy
+    ///
+    /// # Notes
+    ///
+    /// This waits for the peer to send the header before returning, a slow peer
+    /// will block accepting other connections. To prevent this use [`accept`]
+    /// and call [`proxy::read_header`] in the actor handling the connection.
+    ///
+    /// [`proxy::read_header`]: crate::net::tcp::proxy::read_header
+    pub async fn accept_proxied(&self) -> io::Result<(TcpStream, SocketAddr, ProxyHeader)> {
+        let (stream, address) = self.accept().await?;
+        let header = proxy::read_header(&stream).await?;
+        Ok((stream, address, header))
+    }
+
     /// Returns a stream of incoming [`TcpStream`]s.
     ///
     /// Note that unlike [`accept`] this doesn't return the address because it
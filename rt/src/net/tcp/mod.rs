@@ -7,10 +7,14 @@
 //!  * [TCP server] is an [`Actor`] that listens for incoming connections and
 //!    starts a new actor for each.
 //!
+//! Connections made via a load balancer or proxy that uses the PROXY protocol
+//! can be handled using the [`proxy`] module.
+//!
 //! [TCP server]: crate::net::tcp::server
 //! [`Actor`]: heph::actor::Actor
 
 pub mod listener;
+pub mod proxy;
 pub mod server;
 pub mod stream;
 
@@ -0,0 +1,449 @@
+//! PROXY protocol support.
+//!
+//! Load balancers and proxies, such as HAProxy or AWS' Network Load Balancer,
+//! can use the [PROXY protocol] to pass the address of the client to the
+//! server. The proxy sends a header, before any other data, which contains the
+//! source and destination address of the original connection. Both version 1
+//! (text based) and version 2 (binary) of the protocol are supported.
+//!
+//! The header can be read using [`read_header`], or
+//! [`TcpListener::accept_proxied`]. Note that the header must only be read
+//! when the connection is known to come from a proxy, otherwise clients can
+//! spoof their address.
+//!
+//! [PROXY protocol]: https://www.haproxy.org/download/2.9/doc/proxy-protocol.txt
+//! [`TcpListener::accept_proxied`]: crate::net::TcpListener::accept_proxied
+//!
+//! # Examples
+//!
+//! ```
+//! #![feature(never_type)]
+//!
+//! use std::io;
+//!
+//! use heph::actor;
+//! use heph_rt::net::tcp::proxy;
+//! use heph_rt::net::TcpStream;
+//! use heph_rt::ThreadLocal;
+//!
+//! async fn conn_actor(_: actor::Context<!, ThreadLocal>, stream: TcpStream) -> io::Result<()> {
+//!     // Read the header send by the proxy before anything else.
+//!     let header = proxy::read_header(&stream).await?;
+//!     let client = match header.source() {
+//!         Some(address) => address,
+//!         // Connection made by the proxy itself, e.g. a health check.
+//!         None => stream.peer_addr()?,
+//!     };
+//!     stream.send_all(format!("Your address is {client}\n")).await?;
+//!     Ok(())
+//! }
+//! # _ = conn_actor; // Silence dead code warnings.
+//! ```
+
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::str;
+
+use crate::io::{BufMut, Limited};
+use crate::net::TcpStream;
+
+/// Signature of a version 2 header.
+const V2_SIGNATURE: &[u8; 12] = b"\r\n\r\n\0\r\nQUIT\n";
+/// Length of the fixed part of a version 2 header.
+const V2_HEADER_LEN: usize = 16;
+/// Maximum length of a version 1 header, including the CRLF.
+const V1_MAX_LEN: usize = 107;
+/// Number of bytes that are always part of the header. The shortest valid
+/// header is `PROXY UNKNOWN\r\n`.
+const MIN_LEN: usize = 15;
+
+/// Type of the TLV holding the authority, i.e. the host name the client
+/// requested using TLS SNI.
+const PP2_TYPE_AUTHORITY: u8 = 0x02;
+
+/// PROXY protocol header, see the [module documentation].
+///
+/// [module documentation]: crate::net::tcp::proxy
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProxyHeader {
+    version: u8,
+    source: Option<SocketAddr>,
+    destination: Option<SocketAddr>,
+    authority: Option<String>,
+}
+
+impl ProxyHeader {
+    /// Returns the version of the PROXY protocol used, 1 or 2.
+    pub const fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Returns the address of the client.
+    ///
+    /// Returns `None` if the connection was made by the proxy itself, e.g. for
+    /// health checks, or if the address family is not supported.
+    pub const fn source(&self) -> Option<SocketAddr> {
+        self.source
+    }
+
+    /// Returns the address the client connected to, i.e. the address of the
+    /// proxy.
+    ///
+    /// Returns `None` in the same cases as [`ProxyHeader::source`].
+    pub const fn destination(&self) -> Option<SocketAddr> {
+        self.destination
+    }
+
+    /// Returns the authority, the host name the client requested using TLS
+    /// Server Name Indication (SNI), if send by the proxy.
+    ///
+    /// Only supported by version 2 of the protocol.
+    pub fn authority(&self) -> Option<&str> {
+        self.authority.as_deref()
+    }
+}
+
+/// Read the PROXY protocol header from `stream`.
+///
+/// This reads exactly the header, leaving any data send after it in `stream`.
+/// Returns an error if the stream doesn't start with a valid header.
+pub async fn read_header(stream: &TcpStream) -> io::Result<ProxyHeader> {
+    let mut buf = Vec::with_capacity(V1_MAX_LEN);
+    // Both versions are always longer than this, so this can't read beyond
+    // the header.
+    buf = recv_exact(stream, buf, MIN_LEN).await?;
+    if buf.starts_with(V2_SIGNATURE) {
+        buf = recv_exact(stream, buf, V2_HEADER_LEN - MIN_LEN).await?;
+        let length = usize::from(u16::from_be_bytes([buf[14], buf[15]]));
+        buf = recv_exact(stream, buf, length).await?;
+    } else if buf.starts_with(b"PROXY ") {
+        // The header ends with CRLF. Peek at the data to determine how much
+        // to read, so we don't read beyond the header.
+        while !buf.ends_with(b"\r\n") {
+            if buf.len() >= V1_MAX_LEN {
+                return Err(invalid("PROXY protocol v1 header too long"));
+            }
+            let peeked = stream
+                .peek(Vec::with_capacity(V1_MAX_LEN - buf.len()))
+                .await?;
+            let n = if buf.ends_with(b"\r") && peeked.starts_with(b"\n") {
+                1
+            } else {
+                match peeked.windows(2).position(|b| b == b"\r\n") {
+                    Some(idx) => idx + 2,
+                    None if peeked.is_empty() => return Err(io::ErrorKind::UnexpectedEof.into()),
+                    None => peeked.len(),
+                }
+            };
+            buf = recv_exact(stream, buf, n).await?;
+        }
+    } else {
+        return Err(invalid("missing PROXY protocol header"));
+    }
+
+    match parse(&buf)? {
+        Some((header, _)) => Ok(header),
+        None => Err(invalid("incomplete PROXY protocol header")),
+    }
+}
+
+/// Receive exactly `n` bytes from `stream` into `buf`.
+async fn recv_exact(stream: &TcpStream, mut buf: Vec<u8>, n: usize) -> io::Result<Vec<u8>> {
+    if buf.spare_capacity() < n {
+        buf.reserve(n);
+    }
+    stream
+        .recv_n(buf.limit(n), n)
+        .await
+        .map(Limited::into_inner)
+}
+
+/// Parse a PROXY protocol header from `buf`.
+///
+/// Returns the header and its length, or `None` if `buf` doesn't contain the
+/// entire header.
+pub(crate) fn parse(buf: &[u8]) -> io::Result<Option<(ProxyHeader, usize)>> {
+    if buf.starts_with(b"PROXY ") {
+        parse_v1(buf)
+    } else if buf.len() >= V2_SIGNATURE.len() {
+        if buf.starts_with(V2_SIGNATURE) {
+            parse_v2(buf)
+        } else {
+            Err(invalid("missing PROXY protocol header"))
+        }
+    } else if V2_SIGNATURE.starts_with(buf) || b"PROXY ".starts_with(buf) {
+        Ok(None)
+    } else {
+        Err(invalid("missing PROXY protocol header"))
+    }
+}
+
+/// Parse a version 1 header, e.g. `PROXY TCP4 1.2.3.4 5.6.7.8 1234 80\r\n`.
+fn parse_v1(buf: &[u8]) -> io::Result<Option<(ProxyHeader, usize)>> {
+    let search = &buf[..buf.len().min(V1_MAX_LEN)];
+    let Some(end) = search.windows(2).position(|b| b == b"\r\n") else {
+        return if buf.len() >= V1_MAX_LEN {
+            Err(invalid("PROXY protocol v1 header too long"))
+        } else {
+            Ok(None)
+        };
+    };
+    let line =
+        str::from_utf8(&buf[..end]).map_err(|_| invalid("invalid PROXY protocol v1 header"))?;
+    let mut parts = line.split(' ').skip(1); // Skip `PROXY`.
+    let (source, destination) = match parts.next() {
+        // Rest of the line must be ignored.
+        Some("UNKNOWN") => (None, None),
+        Some("TCP4" | "TCP6") => {
+            let mut next = || {
+                parts
+                    .next()
+                    .ok_or_else(|| invalid("invalid PROXY protocol v1 header"))
+            };
+            let (src_ip, dst_ip, src_port, dst_port) = (next()?, next()?, next()?, next()?);
+            let source = parse_v1_address(src_ip, src_port)?;
+            let destination = parse_v1_address(dst_ip, dst_port)?;
+            if parts.next().is_some() {
+                return Err(invalid("invalid PROXY protocol v1 header"));
+            }
+            (Some(source), Some(destination))
+        }
+        _ => return Err(invalid("invalid PROXY protocol v1 protocol")),
+    };
+    let header = ProxyHeader {
+        version: 1,
+        source,
+        destination,
+        authority: None,
+    };
+    Ok(Some((header, end + 2)))
+}
+
+fn parse_v1_address(ip: &str, port: &str) -> io::Result<SocketAddr> {
+    let ip = ip
+        .parse()
+        .map_err(|_| invalid("invalid address in PROXY protocol v1 header"))?;
+    let port = port
+        .parse()
+        .map_err(|_| invalid("invalid port in PROXY protocol v1 header"))?;
+    Ok(SocketAddr::new(ip, port))
+}
+
+/// Parse a version 2 header.
+fn parse_v2(buf: &[u8]) -> io::Result<Option<(ProxyHeader, usize)>> {
+    if buf.len() < V2_HEADER_LEN {
+        return Ok(None);
+    }
+    let version_command = buf[12];
+    let family = buf[13];
+    let length = usize::from(u16::from_be_bytes([buf[14], buf[15]]));
+    let total_length = V2_HEADER_LEN + length;
+    if buf.len() < total_length {
+        return Ok(None);
+    }
+    if version_command >> 4 != 2 {
+        return Err(invalid("invalid PROXY protocol v2 version"));
+    }
+    let data = &buf[V2_HEADER_LEN..total_length];
+
+    let (addresses, tlvs) = match version_command & 0xF {
+        // LOCAL command, connection made by the proxy itself. Addresses must
+        // be ignored.
+        0x0 => (None, data),
+        // PROXY command.
+        0x1 => match family >> 4 {
+            // AF_INET.
+            0x1 if data.len() >= 12 => {
+                let src = Ipv4Addr::from(<[u8; 4]>::try_from(&data[0..4]).unwrap());
+                let dst = Ipv4Addr::from(<[u8; 4]>::try_from(&data[4..8]).unwrap());
+                let src_port = u16::from_be_bytes([data[8], data[9]]);
+                let dst_port = u16::from_be_bytes([data[10], data[11]]);
+                let source = SocketAddr::V4(SocketAddrV4::new(src, src_port));
+                let destination = SocketAddr::V4(SocketAddrV4::new(dst, dst_port));
+                (Some((source, destination)), &data[12..])
+            }
+            // AF_INET6.
+            0x2 if data.len() >= 36 => {
+                let src = Ipv6Addr::from(<[u8; 16]>::try_from(&data[0..16]).unwrap());
+                let dst = Ipv6Addr::from(<[u8; 16]>::try_from(&data[16..32]).unwrap());
+                let src_port = u16::from_be_bytes([data[32], data[33]]);
+                let dst_port = u16::from_be_bytes([data[34], data[35]]);
+                let source = SocketAddr::V6(SocketAddrV6::new(src, src_port, 0, 0));
+                let destination = SocketAddr::V6(SocketAddrV6::new(dst, dst_port, 0, 0));
+                (Some((source, destination)), &data[36..])
+            }
+            0x1 | 0x2 => return Err(invalid("invalid PROXY protocol v2 address")),
+            // AF_UNSPEC, AF_UNIX or unknown, addresses are ignored. Unix
+            // addresses are always 216 bytes.
+            0x3 if data.len() >= 216 => (None, &data[216..]),
+            _ => (None, &[][..]),
+        },
+        _ => return Err(invalid("invalid PROXY protocol v2 command")),
+    };
+
+    let header = ProxyHeader {
+        version: 2,
+        source: addresses.map(|(source, _)| source),
+        destination: addresses.map(|(_, destination)| destination),
+        authority: parse_authority(tlvs),
+    };
+    Ok(Some((header, total_length)))
+}
+
+/// Returns the authority TLV from `tlvs`, if any.
+fn parse_authority(mut tlvs: &[u8]) -> Option<String> {
+    while tlvs.len() >= 3 {
+        let kind = tlvs[0];
+        let length = usize::from(u16::from_be_bytes([tlvs[1], tlvs[2]]));
+        let value = tlvs.get(3..3 + length)?;
+        if kind == PP2_TYPE_AUTHORITY {
+            return str::from_utf8(value).ok().map(ToOwned::to_owned);
+        }
+        tlvs = &tlvs[3 + length..];
+    }
+    None
+}
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use super::{parse, ProxyHeader};
+
+    #[test]
+    fn parse_v1() {
+        let tests: &[(&[u8], Option<(&str, &str)>)] = &[
+            (
+                b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\n",
+                Some(("192.168.0.1:56324", "192.168.0.11:443")),
+            ),
+            (
+                b"PROXY TCP6 ::1 ::2 56324 443\r\n",
+                Some(("[::1]:56324", "[::2]:443")),
+            ),
+            (b"PROXY UNKNOWN\r\n", None),
+            (b"PROXY UNKNOWN ignored\r\n", None),
+        ];
+        for (input, expected) in tests {
+            let mut data = input.to_vec();
+            data.extend_from_slice(b"GET / HTTP/1.1\r\n");
+            let (header, length) = parse(&data).unwrap().unwrap();
+            assert_eq!(length, input.len());
+            assert_eq!(header.version(), 1);
+            let expected = expected.map(|(src, dst)| {
+                (
+                    src.parse::<SocketAddr>().unwrap(),
+                    dst.parse::<SocketAddr>().unwrap(),
+                )
+            });
+            assert_eq!(header.source(), expected.map(|(src, _)| src));
+            assert_eq!(header.destination(), expected.map(|(_, dst)| dst));
+        }
+    }
+
+    #[test]
+    fn parse_v1_incomplete() {
+        assert!(parse(b"PRO").unwrap().is_none());
+        assert!(parse(b"PROXY TCP4 192.168.0.1").unwrap().is_none());
+    }
+
+    #[test]
+    fn parse_v1_invalid() {
+        let tests: &[&[u8]] = &[
+            b"GET / HTTP/1.1\r\n",
+            b"PROXY UDP4 192.168.0.1 192.168.0.11 56324 443\r\n",
+            b"PROXY TCP4 192.168.0.1 192.168.0.11 56324\r\n",
+            b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 443 1\r\n",
+            b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 99999\r\n",
+            b"PROXY TCP4 not_an_ip 192.168.0.11 56324 443\r\n",
+        ];
+        for input in tests {
+            assert!(
+                parse(input).is_err(),
+                "{:?}",
+                String::from_utf8_lossy(input)
+            );
+        }
+        let too_long = [b'A'; 120];
+        let mut input = b"PROXY ".to_vec();
+        input.extend_from_slice(&too_long);
+        assert!(parse(&input).is_err());
+    }
+
+    #[test]
+    fn parse_v2() {
+        let mut input = b"\r\n\r\n\0\r\nQUIT\n".to_vec();
+        input.extend_from_slice(&[0x21, 0x11]); // Version 2, PROXY, TCP over IPv4.
+        input.extend_from_slice(&(12_u16 + 3 + 11).to_be_bytes());
+        input.extend_from_slice(&[192, 168, 0, 1, 192, 168, 0, 11]);
+        input.extend_from_slice(&56324_u16.to_be_bytes());
+        input.extend_from_slice(&443_u16.to_be_bytes());
+        // Authority TLV.
+        input.extend_from_slice(&[0x02, 0, 11]);
+        input.extend_from_slice(b"example.com");
+        let length = input.len();
+        input.extend_from_slice(b"GET / HTTP/1.1\r\n");
+
+        let (header, got_length) = parse(&input).unwrap().unwrap();
+        assert_eq!(got_length, length);
+        let expected = ProxyHeader {
+            version: 2,
+            source: Some("192.168.0.1:56324".parse().unwrap()),
+            destination: Some("192.168.0.11:443".parse().unwrap()),
+            authority: Some("example.com".to_owned()),
+        };
+        assert_eq!(header, expected);
+        assert_eq!(header.authority(), Some("example.com"));
+
+        // Incomplete.
+        assert!(parse(&input[..length - 1]).unwrap().is_none());
+        assert!(parse(&input[..10]).unwrap().is_none());
+    }
+
+    #[test]
+    fn parse_v2_ipv6() {
+        let mut input = b"\r\n\r\n\0\r\nQUIT\n".to_vec();
+        input.extend_from_slice(&[0x21, 0x21]); // Version 2, PROXY, TCP over IPv6.
+        input.extend_from_slice(&36_u16.to_be_bytes());
+        input.extend_from_slice(&"::1".parse::<std::net::Ipv6Addr>().unwrap().octets());
+        input.extend_from_slice(&"::2".parse::<std::net::Ipv6Addr>().unwrap().octets());
+        input.extend_from_slice(&56324_u16.to_be_bytes());
+        input.extend_from_slice(&443_u16.to_be_bytes());
+
+        let (header, length) = parse(&input).unwrap().unwrap();
+        assert_eq!(length, input.len());
+        assert_eq!(header.source(), Some("[::1]:56324".parse().unwrap()));
+        assert_eq!(header.destination(), Some("[::2]:443".parse().unwrap()));
+        assert_eq!(header.authority(), None);
+    }
+
+    #[test]
+    fn parse_v2_local() {
+        let mut input = b"\r\n\r\n\0\r\nQUIT\n".to_vec();
+        input.extend_from_slice(&[0x20, 0x00]); // Version 2, LOCAL.
+        input.extend_from_slice(&0_u16.to_be_bytes());
+
+        let (header, length) = parse(&input).unwrap().unwrap();
+        assert_eq!(length, 16);
+        assert_eq!(header.source(), None);
+        assert_eq!(header.destination(), None);
+    }
+
+    #[test]
+    fn parse_v2_invalid() {
+        let mut input = b"\r\n\r\n\0\r\nQUIT\n".to_vec();
+        input.extend_from_slice(&[0x11, 0x11]); // Version 1.
+        input.extend_from_slice(&0_u16.to_be_bytes());
+        assert!(parse(&input).is_err());
+
+        input[12] = 0x2F; // Invalid command.
+        assert!(parse(&input).is_err());
+
+        input[12] = 0x21; // Address too short.
+        assert!(parse(&input).is_err());
+    }
+}
@@ -11,6 +11,35 @@
 //! recommended. The third example below shows how to run the actor as
 //! thread-safe actor.
 //!
+//! # Admission control
+//!
+//! By default the TCP server accepts all connections and spawns an actor for
+//! each one. Under load, or a SYN flood, this can overload the system. To
+//! protect against this the number of concurrent connection actors can be
+//! capped using [`Setup::with_max_connections`] and the rate at which
+//! connections are accepted using [`Setup::with_max_accept_rate`]. Connections
+//! accepted while over one of the limits are rejected: the
+//! [rejection response], if any, is written to it after which the connection
+//! is closed. The number of rejected connections is available via
+//! [`Setup::rejected_connections`].
+//!
+//! [rejection response]: Setup::with_rejection_response
+//!
+//! # Connection metadata
+//!
+//! When running behind a load balancer or proxy the peer address of the
+//! [`TcpStream`] is that of the proxy, not the client. If the proxy uses the
+//! PROXY protocol the connection actor should start by reading the header using
+//! [`tcp::proxy::read_header`], which returns the client's address. Reading the
+//! header in the connection actor, rather than in the server, ensures a slow
+//! (or malicious) peer can't stall accepting new connections.
+//!
+//! For connections redirected using `iptables`/`nftables` the original
+//! destination address can be retrieved using
+//! [`TcpStream::original_destination`].
+//!
+//! [`tcp::proxy::read_header`]: crate::net::tcp::proxy::read_header
+//!
 //! # Graceful shutdown
 //!
 //! Graceful shutdown is done by sending it a [`Terminate`] message, see below
@@ -221,14 +250,19 @@
 //! }
 //! ```
 
+use std::any::Any;
 use std::future::Future;
 use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::task::{self, Poll};
+use std::time::Instant;
 use std::{fmt, io};
 
-use heph::actor::{self, NewActor, NoMessages};
+use heph::actor::{self, Actor, NewActor, NoMessages};
 use heph::messages::Terminate;
-use heph::supervisor::Supervisor;
+use heph::supervisor::{Supervisor, SupervisorStrategy};
 use log::{debug, trace};
 use socket2::{Domain, Protocol, Socket, Type};
 
@@ -282,7 +316,9 @@ where
                 supervisor,
                 new_actor,
                 options,
+                connections: Arc::new(Connections::new()),
             }),
+            limits: Limits::default(),
         })
     })
 }
@@ -325,6 +361,8 @@ pub struct Setup<S, NA> {
     /// All fields are in an `Arc` to allow `Setup` to cheaply be cloned and
     /// still be `Send` and `Sync` for use in the setup function of `Runtime`.
     inner: Arc<SetupInner<S, NA>>,
+    /// Limits used in accepting connections.
+    limits: Limits,
 }
 
 #[derive(Debug)]
@@ -340,6 +378,8 @@ struct SetupInner<S, NA> {
     new_actor: NA,
     /// Options used to spawn the actor.
     options: ActorOptions,
+    /// Shared between all server actors and the connection actors they spawn.
+    connections: Arc<Connections>,
 }
 
 impl<S, NA> Setup<S, NA> {
@@ -347,13 +387,61 @@ impl<S, NA> Setup<S, NA> {
     pub fn local_addr(&self) -> SocketAddr {
         self.inner.address
     }
+
+    /// Set the maximum number of concurrent connection actors, defaults to no
+    /// limit.
+    ///
+    /// The limit is shared between all server actors started using this
+    /// `Setup` (and its clones). Connections accepted while `max` connection
+    /// actors are running are rejected.
+    ///
+    /// # Notes
+    ///
+    /// The limit is checked before spawning the actor, when running multiple
+    /// server actors (e.g. one per worker thread) the limit can be exceeded by
+    /// at most the number of server actors.
+    pub const fn with_max_connections(mut self, max: usize) -> Setup<S, NA> {
+        self.limits.max_connections = Some(max);
+        self
+    }
+
+    /// Set the maximum number of connections accepted per second, defaults to
+    /// no limit.
+    ///
+    /// The rate is shared between all server actors started using this `Setup`
+    /// (and its clones). It allows a burst of up to `max` connections, after
+    /// which a new connection is admitted every `1s / max`. Connections
+    /// accepted faster than that are rejected.
+    pub const fn with_max_accept_rate(mut self, max: u32) -> Setup<S, NA> {
+        self.limits.max_accept_rate = Some(max);
+        self
+    }
+
+    /// Set the response written to rejected connections before closing them,
+    /// defaults to an empty response (i.e. the connection is closed
+    /// immediately).
+    pub const fn with_rejection_response(mut self, response: &'static [u8]) -> Setup<S, NA> {
+        self.limits.rejection_response = response;
+        self
+    }
+
+    /// Returns the number of connection actors currently running.
+    pub fn active_connections(&self) -> usize {
+        self.inner.connections.active.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of connections rejected because one of the limits
+    /// was hit.
+    pub fn rejected_connections(&self) -> u64 {
+        self.inner.connections.rejected.load(Ordering::Relaxed)
+    }
 }
 
 impl<S, NA> NewActor for Setup<S, NA>
 where
     S: Supervisor<NA> + Clone + 'static,
     NA: NewActor<Argument = TcpStream> + Clone + 'static,
-    NA::RuntimeAccess: Access + Spawn<S, NA, NA::RuntimeAccess>,
+    NA::RuntimeAccess: Access + Spawn<TrackedSupervisor<S>, TrackedNewActor<NA>, NA::RuntimeAccess>,
 {
     type Message = Message;
     type Argument = ();
@@ -373,6 +461,8 @@ where
             this.supervisor.clone(),
             this.new_actor.clone(),
             this.options.clone(),
+            this.connections.clone(),
+            self.limits,
         ))
     }
 }
@@ -381,6 +471,7 @@ impl<S, NA> Clone for Setup<S, NA> {
     fn clone(&self) -> Setup<S, NA> {
         Setup {
             inner: self.inner.clone(),
+            limits: self.limits,
         }
     }
 }
@@ -391,11 +482,13 @@ async fn tcp_server<S, NA>(
     supervisor: S,
     new_actor: NA,
     options: ActorOptions,
+    connections: Arc<Connections>,
+    limits: Limits,
 ) -> Result<(), Error<NA::Error>>
 where
     S: Supervisor<NA> + Clone + 'static,
     NA: NewActor<Argument = TcpStream> + Clone + 'static,
-    NA::RuntimeAccess: Access + Spawn<S, NA, NA::RuntimeAccess>,
+    NA::RuntimeAccess: Access + Spawn<TrackedSupervisor<S>, TrackedNewActor<NA>, NA::RuntimeAccess>,
 {
     let listener = TcpListener::bind_setup(ctx.runtime_ref(), local, set_listener_options)
         .await
@@ -409,15 +502,29 @@ where
             Ok(Some(Ok(stream))) => {
                 trace!("TCP server accepted connection");
                 drop(receive); // Can't double borrow `ctx`.
-                stream.set_auto_cpu_affinity(ctx.runtime_ref());
-                _ = ctx
-                    .try_spawn(
-                        supervisor.clone(),
-                        new_actor.clone(),
-                        stream,
-                        options.clone(),
-                    )
-                    .map_err(Error::NewActor)?;
+                if let Err(reason) = connections.admit(&limits) {
+                    debug!(reason = reason; "TCP server rejecting connection");
+                    if !limits.rejection_response.is_empty() {
+                        // The connection is closed below, so we don't care
+                        // about any errors.
+                        _ = stream.send_all(limits.rejection_response).await;
+                    }
+                    drop(stream);
+                } else {
+                    stream.set_auto_cpu_affinity(ctx.runtime_ref());
+                    let new_actor = TrackedNewActor {
+                        new_actor: new_actor.clone(),
+                        connections: connections.clone(),
+                    };
+                    _ = ctx
+                        .try_spawn(
+                            TrackedSupervisor(supervisor.clone()),
+                            new_actor,
+                            stream,
+                            options.clone(),
+                        )
+                        .map_err(Error::NewActor)?;
+                }
                 receive = ctx.receive_next();
             }
             Ok(Some(Err(err))) => return Err(Error::Accept(err)),
@@ -437,6 +544,186 @@ where
     }
 }
 
+/// Limits used in accepting connections, see [`Setup::with_max_connections`],
+/// [`Setup::with_max_accept_rate`] and [`Setup::with_rejection_response`].
+#[derive(Copy, Clone, Debug, Default)]
+struct Limits {
+    max_connections: Option<usize>,
+    max_accept_rate: Option<u32>,
+    rejection_response: &'static [u8],
+}
+
+/// Number of nanoseconds in a second, used for the accept rate.
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+/// Connection accounting shared between all server and connection actors.
+#[derive(Debug)]
+struct Connections {
+    /// Number of running connection actors.
+    active: AtomicUsize,
+    /// Number of connections rejected.
+    rejected: AtomicU64,
+    /// Reference point for `next_accept`.
+    start: Instant,
+    /// Theoretical arrival time of the next connection, in nanoseconds since
+    /// `start`, see `check_limits`.
+    next_accept: AtomicU64,
+}
+
+impl Connections {
+    fn new() -> Connections {
+        Connections {
+            active: AtomicUsize::new(0),
+            rejected: AtomicU64::new(0),
+            start: Instant::now(),
+            next_accept: AtomicU64::new(0),
+        }
+    }
+
+    /// Determine if a new connection can be admitted based on `limits`,
+    /// returning the reason if not.
+    fn admit(&self, limits: &Limits) -> Result<(), &'static str> {
+        let res = self.check_limits(limits);
+        if res.is_err() {
+            _ = self.rejected.fetch_add(1, Ordering::Relaxed);
+        }
+        res
+    }
+
+    fn check_limits(&self, limits: &Limits) -> Result<(), &'static str> {
+        if let Some(max) = limits.max_connections {
+            if self.active.load(Ordering::Relaxed) >= max {
+                return Err("maximum number of connections reached");
+            }
+        }
+
+        if let Some(max) = limits.max_accept_rate {
+            // This uses the generic cell rate algorithm (GCRA), a sliding
+            // window version of a token bucket. Every accepted connection
+            // moves `next_accept` forward by `interval`, a connection is
+            // rejected if that would put it more than `burst` in the future.
+            let Some(interval) = NANOS_PER_SEC.checked_div(u64::from(max)) else {
+                return Err("maximum accept rate reached");
+            };
+            let burst = NANOS_PER_SEC - interval;
+            // Overflows after 584 years.
+            let now = u64::try_from(self.start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+            let res = self
+                .next_accept
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |next_accept| {
+                    let next_accept = next_accept.max(now);
+                    (next_accept - now <= burst).then(|| next_accept + interval)
+                });
+            if res.is_err() {
+                return Err("maximum accept rate reached");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Decrements the number of active connections when dropped.
+#[derive(Debug)]
+struct ConnectionGuard(Arc<Connections>);
+
+impl ConnectionGuard {
+    fn new(connections: Arc<Connections>) -> ConnectionGuard {
+        _ = connections.active.fetch_add(1, Ordering::Relaxed);
+        ConnectionGuard(connections)
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        _ = self.0.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// [`NewActor`] wrapper used by the TCP server to keep track of the number of
+/// running connection actors.
+#[derive(Debug, Clone)]
+pub struct TrackedNewActor<NA> {
+    new_actor: NA,
+    connections: Arc<Connections>,
+}
+
+impl<NA> NewActor for TrackedNewActor<NA>
+where
+    NA: NewActor,
+{
+    type Message = NA::Message;
+    type Argument = NA::Argument;
+    type Actor = TrackedActor<NA::Actor>;
+    type Error = NA::Error;
+    type RuntimeAccess = NA::RuntimeAccess;
+
+    fn new(
+        &mut self,
+        ctx: actor::Context<Self::Message, Self::RuntimeAccess>,
+        arg: Self::Argument,
+    ) -> Result<Self::Actor, Self::Error> {
+        let actor = self.new_actor.new(ctx, arg)?;
+        Ok(TrackedActor {
+            actor,
+            _guard: ConnectionGuard::new(self.connections.clone()),
+        })
+    }
+
+    fn name() -> &'static str {
+        NA::name()
+    }
+}
+
+/// [`Actor`] created by [`TrackedNewActor`].
+#[derive(Debug)]
+pub struct TrackedActor<A> {
+    actor: A,
+    _guard: ConnectionGuard,
+}
+
+impl<A: Actor> Actor for TrackedActor<A> {
+    type Error = A::Error;
+
+    fn try_poll(
+        self: Pin<&mut Self>,
+        ctx: &mut task::Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        // SAFETY: not moving the actor.
+        unsafe { Pin::map_unchecked_mut(self, |s| &mut s.actor) }.try_poll(ctx)
+    }
+}
+
+/// [`Supervisor`] wrapper for [`TrackedNewActor`], delegating all decisions to
+/// the wrapped supervisor.
+#[derive(Debug, Clone)]
+pub struct TrackedSupervisor<S>(S);
+
+impl<S, NA> Supervisor<TrackedNewActor<NA>> for TrackedSupervisor<S>
+where
+    S: Supervisor<NA>,
+    NA: NewActor,
+{
+    fn decide(&mut self, error: <NA::Actor as Actor>::Error) -> SupervisorStrategy<NA::Argument> {
+        self.0.decide(error)
+    }
+
+    fn decide_on_restart_error(&mut self, error: NA::Error) -> SupervisorStrategy<NA::Argument> {
+        self.0.decide_on_restart_error(error)
+    }
+
+    fn second_restart_error(&mut self, error: NA::Error) {
+        self.0.second_restart_error(error);
+    }
+
+    fn decide_on_panic(
+        &mut self,
+        panic: Box<dyn Any + Send + 'static>,
+    ) -> SupervisorStrategy<NA::Argument> {
+        self.0.decide_on_panic(panic)
+    }
+}
+
 /// The message type used by TCP server actor.
 ///
 /// The message implements [`From`]`<`[`Terminate`]`>` and
@@ -3,15 +3,18 @@
 use std::io;
 use std::net::{Shutdown, SocketAddr};
 use std::os::fd::{AsFd, BorrowedFd};
+use std::time::Duration;
 
 use a10::{AsyncFd, Extract};
-use socket2::{Domain, Protocol, SockRef, Type};
+use socket2::{Domain, Protocol, SockRef, TcpKeepalive, Type};
 
 use crate::access::Access;
-use crate::io::{impl_read, impl_write, Buf, BufMut, BufMutSlice, BufSlice, BufWrapper};
+use crate::io::{
+    impl_read, impl_write, Buf, BufMut, BufMutSlice, BufSlice, BufWrapper, Limited, ReadBufPool,
+};
 use crate::net::{
-    convert_address, Recv, RecvN, RecvNVectored, RecvVectored, Send, SendAll, SendAllVectored,
-    SendVectored, SockAddr,
+    convert_address, Recv, RecvN, RecvNVectored, RecvStream, RecvVectored, Send, SendAll,
+    SendAllVectored, SendVectored, SockAddr, SocketConfig,
 };
 use crate::wakers::NoRing;
 
@@ -50,6 +53,19 @@ impl TcpStream {
     /// Create a new TCP stream and issues a non-blocking connect to the
     /// specified `address`.
     pub async fn connect<RT>(rt: &RT, address: SocketAddr) -> io::Result<TcpStream>
+    where
+        RT: Access,
+    {
+        TcpStream::connect_with(rt, address, &SocketConfig::new()).await
+    }
+
+    /// Same as [`TcpStream::connect`], but applies `config` to the socket
+    /// before connecting.
+    pub async fn connect_with<RT>(
+        rt: &RT,
+        address: SocketAddr,
+        config: &SocketConfig,
+    ) -> io::Result<TcpStream>
     where
         RT: Access,
     {
@@ -63,6 +79,7 @@ impl TcpStream {
         .await?;
         let socket = TcpStream { fd };
         socket.set_auto_cpu_affinity(rt);
+        socket.with_ref(|socket| config.apply(&socket, address, Protocol::TCP))?;
         NoRing(socket.fd.connect(SockAddr::from(address))).await?;
         Ok(socket)
     }
@@ -129,6 +146,24 @@ impl TcpStream {
         self.with_ref(|socket| socket.local_addr().and_then(convert_address))
     }
 
+    /// Returns the original destination address of this TCP connection, before
+    /// it was redirected using `iptables`/`nftables` (e.g. using the `REDIRECT`
+    /// or `DNAT` targets).
+    ///
+    /// This uses `SO_ORIGINAL_DST` (or `IP6T_SO_ORIGINAL_DST` for IPv6
+    /// sockets).
+    #[cfg(target_os = "linux")]
+    pub fn original_destination(&self) -> io::Result<SocketAddr> {
+        self.with_ref(|socket| {
+            let address = if socket.local_addr()?.is_ipv6() {
+                socket.original_dst_ipv6()?
+            } else {
+                socket.original_dst()?
+            };
+            convert_address(address)
+        })
+    }
+
     /// Sets the value for the `IP_TTL` option on this socket.
     pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
         self.with_ref(|socket| socket.set_ttl(ttl))
@@ -149,14 +184,48 @@ impl TcpStream {
         self.with_ref(|socket| socket.nodelay())
     }
 
-    /// Returns `true` if `SO_KEEPALIVE` is set.
-    pub fn keepalive(&self) -> io::Result<bool> {
-        self.with_ref(|socket| socket.keepalive())
+    /// Returns the keepalive parameters if `SO_KEEPALIVE` is set, or `None` if
+    /// keepalive is disabled.
+    ///
+    /// All parameters of the returned [`KeepAlive`] are set.
+    pub fn keepalive(&self) -> io::Result<Option<KeepAlive>> {
+        self.with_ref(|socket| {
+            if !socket.keepalive()? {
+                return Ok(None);
+            }
+            Ok(Some(KeepAlive {
+                time: Some(socket.keepalive_time()?),
+                interval: Some(socket.keepalive_interval()?),
+                retries: Some(socket.keepalive_retries()?),
+            }))
+        })
+    }
+
+    /// Enables `SO_KEEPALIVE` setting the parameters in `keepalive`, or
+    /// disables it if `keepalive` is `None`.
+    ///
+    /// Parameters not set in `keepalive` are left unchanged.
+    pub fn set_keepalive(&self, keepalive: Option<KeepAlive>) -> io::Result<()> {
+        self.with_ref(|socket| match keepalive {
+            Some(keepalive) => socket.set_tcp_keepalive(&keepalive.as_socket2()),
+            None => socket.set_keepalive(false),
+        })
+    }
+
+    /// Gets the value of the `TCP_USER_TIMEOUT` option on this socket.
+    ///
+    /// Returns `None` if the system default is used.
+    pub fn user_timeout(&self) -> io::Result<Option<Duration>> {
+        self.with_ref(|socket| socket.tcp_user_timeout())
     }
 
-    /// Enables or disables `SO_KEEPALIVE`.
-    pub fn set_keepalive(&self, enable: bool) -> io::Result<()> {
-        self.with_ref(|socket| socket.set_keepalive(enable))
+    /// Sets the value of the `TCP_USER_TIMEOUT` option on this socket.
+    ///
+    /// This is the maximum amount of time that transmitted data may remain
+    /// unacknowledged before the connection is closed. `None` resets it to the
+    /// system default.
+    pub fn set_user_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.with_ref(|socket| socket.set_tcp_user_timeout(timeout))
     }
 
     /// Send the bytes in `buf` to the peer.
@@ -263,6 +332,58 @@ impl TcpStream {
         RecvN(self.fd.recv_n(BufWrapper(buf), n)).await
     }
 
+    /// Returns a stream of buffers, from `pool`, filled with received bytes.
+    ///
+    /// This uses io_uring's multishot receive (making it faster then calling
+    /// `recv` in a loop), the kernel selects a buffer from `pool` every time
+    /// it has data available.
+    ///
+    /// When the peer shuts down its writing side of the connection an empty
+    /// buffer is returned, after which the stream ends.
+    ///
+    /// # Notes
+    ///
+    /// This will return `ENOBUFS` if no buffer is available in the `pool` to
+    /// read into. Be careful when using this as a peer sending a lot data might
+    /// take up all your buffers from your pool, make sure to drop (or
+    /// [`release`]) the buffers once they're processed.
+    ///
+    /// [`release`]: crate::io::ReadBuf::release
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(never_type)]
+    ///
+    /// use std::io;
+    ///
+    /// use heph::actor;
+    /// use heph_rt::io::ReadBufPool;
+    /// use heph_rt::net::TcpStream;
+    /// use heph_rt::util::next;
+    /// use heph_rt::ThreadLocal;
+    ///
+    /// async fn actor(ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+    ///     let address = "127.0.0.1:12345".parse().unwrap();
+    ///     let stream = TcpStream::connect(ctx.runtime_ref(), address).await?;
+    ///
+    ///     let pool = ReadBufPool::new(ctx.runtime_ref(), 8, 4 * 1024)?; // 8 x 4 KB.
+    ///     let mut bufs = stream.recv_stream(pool);
+    ///     while let Some(buf) = next(&mut bufs).await {
+    ///         let buf = buf?;
+    ///         println!("read {} bytes: {buf:?}", buf.len());
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// #
+    /// # _ = actor; // Silent dead code warnings.
+    /// ```
+    #[allow(clippy::doc_markdown)] // For "io_uring".
+    pub fn recv_stream(&self, pool: ReadBufPool) -> RecvStream<'_> {
+        RecvStream(self.fd.multishot_recv(pool.into_inner(), 0))
+    }
+
     /// Receive messages from the stream, using vectored I/O.
     pub async fn recv_vectored<B: BufMutSlice<N>, const N: usize>(&self, bufs: B) -> io::Result<B> {
         RecvVectored(self.fd.recv_vectored(BufWrapper(bufs), 0)).await
@@ -295,6 +416,110 @@ impl TcpStream {
         RecvVectored(self.fd.recv_vectored(BufWrapper(bufs), libc::MSG_PEEK)).await
     }
 
+    /// Receive a single length-prefixed frame into `buf`.
+    ///
+    /// This peeks at the first `N` bytes, the header of the frame, and calls
+    /// `frame_len` with it to determine the total length of the frame,
+    /// including the header. Then it receives exactly the entire frame into
+    /// `buf`, header included, leaving any following frames in the stream.
+    /// This means the caller doesn't have to keep track of partially received
+    /// frames.
+    ///
+    /// If the peer shut down the stream before sending the frame `buf` is
+    /// returned unchanged. If the peer shuts down the stream in the middle of
+    /// the frame this returns [`io::ErrorKind::UnexpectedEof`].
+    ///
+    /// If the frame doesn't fit in the spare capacity of `buf` an
+    /// [`io::ErrorKind::InvalidData`] error is returned. If the entire header
+    /// was available in the stream (the common case) no bytes are removed from
+    /// the stream, which allows the call to be retried with a larger buffer.
+    ///
+    /// # Examples
+    ///
+    /// Receiving frames prefixed with a 32 bit big-endian length.
+    ///
+    /// ```
+    /// #![feature(never_type)]
+    ///
+    /// use std::io;
+    ///
+    /// use heph::actor;
+    /// use heph_rt::net::TcpStream;
+    /// use heph_rt::ThreadLocal;
+    ///
+    /// async fn actor(ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+    ///     let address = "127.0.0.1:12345".parse().unwrap();
+    ///     let stream = TcpStream::connect(ctx.runtime_ref(), address).await?;
+    ///
+    ///     let mut buf = Vec::with_capacity(4 * 1024); // 4 KB.
+    ///     loop {
+    ///         buf.clear();
+    ///         buf = stream.recv_exact_into(buf, |header: [u8; 4]| {
+    ///             Ok(4 + u32::from_be_bytes(header) as usize)
+    ///         }).await?;
+    ///         if buf.is_empty() {
+    ///             return Ok(()); // Peer shut down the stream.
+    ///         }
+    ///         println!("received frame: {:?}", &buf[4..]);
+    ///     }
+    /// }
+    /// #
+    /// # _ = actor; // Silent dead code warnings.
+    /// ```
+    pub async fn recv_exact_into<B, F, const N: usize>(
+        &self,
+        mut buf: B,
+        frame_len: F,
+    ) -> io::Result<B>
+    where
+        B: BufMut,
+        F: FnOnce([u8; N]) -> io::Result<usize>,
+    {
+        let header = BufMut::limit(Vec::with_capacity(N), N);
+        let header = self.peek(header).await?.into_inner();
+        if header.is_empty() {
+            return Ok(buf);
+        }
+
+        let (header, received) = if header.len() == N {
+            (header, 0)
+        } else {
+            // Only part of the header was available, we have to wait for the
+            // remainder by actually receiving it.
+            let mut header = header;
+            header.clear();
+            let header = BufMut::limit(header, N);
+            let header = self.recv_n(header, N).await?.into_inner();
+            (header, N)
+        };
+
+        let mut h = [0; N];
+        h.copy_from_slice(&header[..N]);
+        let length = frame_len(h)?;
+        if length < N {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "frame length smaller than header",
+            ));
+        } else if length > buf.spare_capacity() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "frame larger than buffer",
+            ));
+        }
+
+        if received != 0 {
+            _ = buf.extend_from_slice(&header);
+        }
+        let n = length - received;
+        if n == 0 {
+            return Ok(buf);
+        }
+        self.recv_n(BufMut::limit(buf, n), n)
+            .await
+            .map(Limited::into_inner)
+    }
+
     /* TODO: add `sendfile(2)` wrappers io_uring at the time of writing doesn't support this.
     /// Send the `file` out this stream.
     ///
@@ -390,3 +615,97 @@ impl AsFd for TcpStream {
         self.fd.as_fd()
     }
 }
+
+/// TCP keepalive parameters.
+///
+/// Keepalive probes are used to detect dead peers on idle connections. See
+/// [`TcpStream::set_keepalive`] and [`SocketConfig::with_keepalive`].
+///
+/// Parameters that are not set use the system default.
+///
+/// [`SocketConfig::with_keepalive`]: crate::net::SocketConfig::with_keepalive
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use heph_rt::net::tcp::stream::KeepAlive;
+///
+/// // Start sending probes after being idle for a minute, sending a probe every
+/// // 10 seconds, dropping the connection after 3 unanswered probes.
+/// let keepalive = KeepAlive::new()
+///     .with_time(Duration::from_secs(60))
+///     .with_interval(Duration::from_secs(10))
+///     .with_retries(3);
+/// # _ = keepalive;
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct KeepAlive {
+    time: Option<Duration>,
+    interval: Option<Duration>,
+    retries: Option<u32>,
+}
+
+impl KeepAlive {
+    /// Create new keepalive parameters, using the system defaults.
+    pub const fn new() -> KeepAlive {
+        KeepAlive {
+            time: None,
+            interval: None,
+            retries: None,
+        }
+    }
+
+    /// Set the amount of time the connection must be idle before keepalive
+    /// probes are send (`TCP_KEEPIDLE`).
+    pub const fn with_time(mut self, time: Duration) -> KeepAlive {
+        self.time = Some(time);
+        self
+    }
+
+    /// Returns the amount of time the connection must be idle before keepalive
+    /// probes are send, if set.
+    pub const fn time(&self) -> Option<Duration> {
+        self.time
+    }
+
+    /// Set the time between keepalive probes (`TCP_KEEPINTVL`).
+    pub const fn with_interval(mut self, interval: Duration) -> KeepAlive {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Returns the time between keepalive probes, if set.
+    pub const fn interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    /// Set the maximum number of keepalive probes send before dropping the
+    /// connection (`TCP_KEEPCNT`).
+    pub const fn with_retries(mut self, retries: u32) -> KeepAlive {
+        self.retries = Some(retries);
+        self
+    }
+
+    /// Returns the maximum number of keepalive probes send before dropping the
+    /// connection, if set.
+    pub const fn retries(&self) -> Option<u32> {
+        self.retries
+    }
+
+    /// Convert the parameters into the socket2 type.
+    pub(crate) fn as_socket2(&self) -> TcpKeepalive {
+        let mut keepalive = TcpKeepalive::new();
+        if let Some(time) = self.time {
+            keepalive = keepalive.with_time(time);
+        }
+        if let Some(interval) = self.interval {
+            keepalive = keepalive.with_interval(interval);
+        }
+        if let Some(retries) = self.retries {
+            keepalive = keepalive.with_retries(retries);
+        }
+        keepalive
+    }
+}
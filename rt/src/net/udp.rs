@@ -3,18 +3,19 @@
 //! See [`UdpSocket`].
 
 use std::marker::PhantomData;
+use std::mem::{self, size_of, MaybeUninit};
 use std::net::SocketAddr;
-use std::os::fd::{AsFd, BorrowedFd};
-use std::{fmt, io};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+use std::{fmt, io, ptr};
 
 use a10::{AsyncFd, Extract};
 use socket2::{Domain, Protocol, SockRef, Type};
 
 use crate::access::Access;
-use crate::io::{Buf, BufMut, BufMutSlice, BufSlice, BufWrapper};
+use crate::io::{Buf, BufMut, BufMutSlice, BufSlice, BufWrapper, ReadBufPool};
 use crate::net::{
-    convert_address, Recv, RecvFrom, RecvFromVectored, RecvVectored, Send, SendTo, SendToVectored,
-    SendVectored, SockAddr,
+    convert_address, Recv, RecvFrom, RecvFromVectored, RecvStream, RecvVectored, Send, SendTo,
+    SendToVectored, SendVectored, SockAddr, SocketConfig,
 };
 use crate::wakers::NoRing;
 
@@ -89,6 +90,8 @@ pub use crate::net::{Connected, Unconnected};
 /// ```
 pub struct UdpSocket<M = Unconnected> {
     fd: AsyncFd,
+    /// Used to poll for errors, see [`UdpSocket::recv_error`].
+    sq: a10::SubmissionQueue,
     /// The mode in which the socket is in, this determines what methods are
     /// available.
     mode: PhantomData<M>,
@@ -100,8 +103,22 @@ impl UdpSocket {
     where
         RT: Access,
     {
+        UdpSocket::bind_with(rt, local, &SocketConfig::new()).await
+    }
+
+    /// Same as [`UdpSocket::bind`], but applies `config` to the socket before
+    /// binding it.
+    pub async fn bind_with<RT>(
+        rt: &RT,
+        local: SocketAddr,
+        config: &SocketConfig,
+    ) -> io::Result<UdpSocket<Unconnected>>
+    where
+        RT: Access,
+    {
+        let sq = rt.submission_queue();
         let fd = NoRing(a10::net::socket(
-            rt.submission_queue(),
+            sq.clone(),
             Domain::for_address(local).into(),
             Type::DGRAM.cloexec().into(),
             Protocol::UDP.into(),
@@ -111,6 +128,7 @@ impl UdpSocket {
 
         let socket = UdpSocket {
             fd,
+            sq,
             mode: PhantomData,
         };
 
@@ -122,6 +140,7 @@ impl UdpSocket {
                 }
             }
 
+            config.apply(&socket, local, Protocol::UDP)?;
             socket.bind(&local.into())?;
 
             Ok(())
@@ -138,6 +157,7 @@ impl<M> UdpSocket<M> {
         NoRing(self.fd.connect(SockAddr::from(remote))).await?;
         Ok(UdpSocket {
             fd: self.fd,
+            sq: self.sq,
             mode: PhantomData,
         })
     }
@@ -154,8 +174,10 @@ impl<M> UdpSocket<M> {
     where
         RT: Access,
     {
+        let sq = rt.submission_queue();
         UdpSocket {
-            fd: AsyncFd::new(socket.into(), rt.submission_queue()),
+            fd: AsyncFd::new(socket.into(), sq.clone()),
+            sq,
             mode: PhantomData,
         }
     }
@@ -165,6 +187,7 @@ impl<M> UdpSocket<M> {
     pub fn try_clone(&self) -> io::Result<UdpSocket<M>> {
         Ok(UdpSocket {
             fd: self.fd.try_clone()?,
+            sq: self.sq.clone(),
             mode: PhantomData,
         })
     }
@@ -188,6 +211,286 @@ impl<M> UdpSocket<M> {
         self.with_ref(|socket| socket.take_error())
     }
 
+    /// Set the value of the `IP_TOS` option, or the `IPV6_TCLASS` option for
+    /// IPv6 sockets.
+    ///
+    /// This can be used to mark the packets send with a Differentiated
+    /// Services Code Point (DSCP), which is stored in the upper six bits of
+    /// `tos`. Also see [`SocketConfig::with_tos`].
+    ///
+    /// [`SocketConfig::with_tos`]: crate::net::SocketConfig::with_tos
+    pub fn set_tos(&self, tos: u32) -> io::Result<()> {
+        let local = self.local_addr()?;
+        self.with_ref(|socket| match local {
+            SocketAddr::V4(_) => socket.set_tos(tos),
+            SocketAddr::V6(_) => socket.set_tclass_v6(tos),
+        })
+    }
+
+    /// Set the value of the `IP_RECVERR` option, or the `IPV6_RECVERR` option
+    /// for IPv6 sockets.
+    ///
+    /// If enabled errors, such as ICMP errors, are queued on the socket's error
+    /// queue, which can be read using [`UdpSocket::recv_error`].
+    pub fn set_recv_error(&self, recv_error: bool) -> io::Result<()> {
+        let (level, name) = match self.local_addr()? {
+            SocketAddr::V4(_) => (libc::SOL_IP, libc::IP_RECVERR),
+            SocketAddr::V6(_) => (libc::SOL_IPV6, libc::IPV6_RECVERR),
+        };
+        let value = libc::c_int::from(recv_error);
+        syscall!(setsockopt(
+            self.fd.as_fd().as_raw_fd(),
+            level,
+            name,
+            ptr::addr_of!(value).cast(),
+            size_of::<libc::c_int>() as libc::socklen_t,
+        ))
+        .map(|_| ())
+    }
+
+    /// Receive an error from the socket's error queue.
+    ///
+    /// This waits until an error is queued. Errors are only queued if
+    /// [`UdpSocket::set_recv_error`] is enabled. If it isn't enabled, but a
+    /// socket error is pending (see [`UdpSocket::take_error`]) that error is
+    /// returned instead.
+    pub async fn recv_error(&self) -> io::Result<ExtendedError> {
+        loop {
+            if let Some(err) = self.try_recv_error()? {
+                return Ok(err);
+            }
+
+            let event = self
+                .sq
+                .oneshot_poll(self.fd.as_fd(), libc::POLLERR.into())
+                .await?;
+            if event.is_error() {
+                if let Some(err) = self.try_recv_error()? {
+                    return Ok(err);
+                }
+                // Error wasn't queued, meaning the error queue is not enabled.
+                // Return the pending error to ensure we don't keep polling the
+                // socket, which would return the same event.
+                if let Some(err) = self.take_error()? {
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    /// Attempt to receive an error from the socket's error queue.
+    ///
+    /// Same as [`UdpSocket::recv_error`], but returns `None` if no error is
+    /// queued instead of waiting for one.
+    pub fn try_recv_error(&self) -> io::Result<Option<ExtendedError>> {
+        // Large enough for an IPv6 address.
+        let mut address: MaybeUninit<libc::sockaddr_storage> = MaybeUninit::zeroed();
+        // Large enough for `sock_extended_err` followed by the offender's
+        // address.
+        let mut control: MaybeUninit<[u64; 16]> = MaybeUninit::zeroed();
+        // SAFETY: all zero is valid for `msghdr`.
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_name = address.as_mut_ptr().cast();
+        msg.msg_namelen = size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen = size_of::<[u64; 16]>();
+
+        let flags = libc::MSG_ERRQUEUE | libc::MSG_DONTWAIT;
+        match syscall!(recvmsg(self.fd.as_fd().as_raw_fd(), &mut msg, flags)) {
+            Ok(_) => {}
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+            Err(err) => return Err(err),
+        }
+
+        // SAFETY: the kernel initialised `msg_namelen` bytes of the address.
+        let destination = unsafe { to_socket_addr(address.as_ptr(), msg.msg_namelen) };
+        // SAFETY: the kernel filled the control messages for us.
+        let mut cmsg = unsafe { libc::CMSG_FIRSTHDR(&msg) };
+        while !cmsg.is_null() {
+            // SAFETY: checked that the pointer is not null above.
+            let (level, kind) = unsafe { ((*cmsg).cmsg_level, (*cmsg).cmsg_type) };
+            if (level == libc::SOL_IP && kind == libc::IP_RECVERR)
+                || (level == libc::SOL_IPV6 && kind == libc::IPV6_RECVERR)
+            {
+                // SAFETY: per `ip(7)` the data is a `sock_extended_err`,
+                // followed by the offender's address.
+                let err = unsafe { libc::CMSG_DATA(cmsg).cast::<libc::sock_extended_err>() };
+                let extended = unsafe { err.read_unaligned() };
+                let offender = unsafe {
+                    let offender = libc::SO_EE_OFFENDER(err);
+                    let len = (cmsg as usize + (*cmsg).cmsg_len) - offender as usize;
+                    to_socket_addr(offender.cast(), len as libc::socklen_t)
+                };
+                return Ok(Some(ExtendedError {
+                    errno: extended.ee_errno as i32,
+                    origin: extended.ee_origin,
+                    icmp_type: extended.ee_type,
+                    icmp_code: extended.ee_code,
+                    info: extended.ee_info,
+                    offender,
+                    destination,
+                }));
+            }
+            // SAFETY: `cmsg` is a valid control message of `msg`.
+            cmsg = unsafe { libc::CMSG_NXTHDR(&msg, cmsg) };
+        }
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "missing extended error in message from error queue",
+        ))
+    }
+
+    /// Set the value of the `UDP_GRO` option.
+    ///
+    /// If enabled the kernel may coalesce multiple datagrams from the same
+    /// peer into a single receive (Generic Receive Offload). Use
+    /// [`UdpSocket::recv_segmented`] or [`UdpSocket::recv_from_segmented`] to
+    /// receive such coalesced datagrams, other receive methods can't tell
+    /// where one datagram ends and the next begins.
+    pub fn set_gro(&self, gro: bool) -> io::Result<()> {
+        let value = libc::c_int::from(gro);
+        syscall!(setsockopt(
+            self.fd.as_fd().as_raw_fd(),
+            libc::SOL_UDP,
+            libc::UDP_GRO,
+            ptr::addr_of!(value).cast(),
+            size_of::<libc::c_int>() as libc::socklen_t,
+        ))
+        .map(|_| ())
+    }
+
+    /// Send `buf` as multiple datagrams of `segment_size` bytes, optionally to
+    /// `address`.
+    async fn send_segments<B: Buf>(
+        &self,
+        buf: B,
+        segment_size: u16,
+        address: Option<SocketAddr>,
+    ) -> io::Result<(B, usize)> {
+        let address = address.map(socket2::SockAddr::from);
+        loop {
+            match self.try_send_segments(&buf, segment_size, address.as_ref()) {
+                Ok(n) => return Ok((buf, n)),
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                Err(err) => return Err(err),
+            }
+            _ = self
+                .sq
+                .oneshot_poll(self.fd.as_fd(), libc::POLLOUT.into())
+                .await?;
+        }
+    }
+
+    /// Attempt to send `buf` using `sendmsg(2)` with an `UDP_SEGMENT` control
+    /// message.
+    fn try_send_segments<B: Buf>(
+        &self,
+        buf: &B,
+        segment_size: u16,
+        address: Option<&socket2::SockAddr>,
+    ) -> io::Result<usize> {
+        // SAFETY: `Buf` ensures the pointer and length are valid.
+        let (ptr, len) = unsafe { buf.parts() };
+        let mut iovec = libc::iovec {
+            iov_base: ptr.cast_mut().cast(),
+            iov_len: len,
+        };
+        // Large enough for a single `u16` control message.
+        let mut control: MaybeUninit<[u64; 4]> = MaybeUninit::zeroed();
+        // SAFETY: all zero is valid for `msghdr`.
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        if let Some(address) = address {
+            msg.msg_name = address.as_ptr().cast_mut().cast();
+            msg.msg_namelen = address.len();
+        }
+        msg.msg_iov = &mut iovec;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr().cast();
+        // SAFETY: `CMSG_SPACE` is always safe to call.
+        msg.msg_controllen = unsafe { libc::CMSG_SPACE(size_of::<u16>() as u32) } as usize;
+        // SAFETY: `msg_control` points to `control`, which is large enough to
+        // hold the control message.
+        unsafe {
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_UDP;
+            (*cmsg).cmsg_type = libc::UDP_SEGMENT;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(size_of::<u16>() as u32) as usize;
+            libc::CMSG_DATA(cmsg)
+                .cast::<u16>()
+                .write_unaligned(segment_size);
+        }
+
+        let flags = libc::MSG_DONTWAIT;
+        syscall!(sendmsg(self.fd.as_fd().as_raw_fd(), &msg, flags)).map(|n| n as usize)
+    }
+
+    /// Receive (coalesced) datagrams into `buf`, returning the segment size
+    /// and the address of the sender.
+    async fn recv_segments<B: BufMut>(
+        &self,
+        mut buf: B,
+    ) -> io::Result<(B, usize, Option<SocketAddr>)> {
+        loop {
+            match self.try_recv_segments(&mut buf) {
+                Ok((segment_size, address)) => return Ok((buf, segment_size, address)),
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                Err(err) => return Err(err),
+            }
+            _ = self
+                .sq
+                .oneshot_poll(self.fd.as_fd(), libc::POLLIN.into())
+                .await?;
+        }
+    }
+
+    /// Attempt to receive datagrams using `recvmsg(2)`, reading the `UDP_GRO`
+    /// control message.
+    fn try_recv_segments<B: BufMut>(&self, buf: &mut B) -> io::Result<(usize, Option<SocketAddr>)> {
+        // SAFETY: `BufMut` ensures the pointer and length are valid, the
+        // kernel only writes initialised bytes to it.
+        let (ptr, len) = unsafe { buf.parts_mut() };
+        let mut iovec = libc::iovec {
+            iov_base: ptr.cast(),
+            iov_len: len,
+        };
+        let mut address: MaybeUninit<libc::sockaddr_storage> = MaybeUninit::zeroed();
+        // Large enough for a single `c_int` control message.
+        let mut control: MaybeUninit<[u64; 4]> = MaybeUninit::zeroed();
+        // SAFETY: all zero is valid for `msghdr`.
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_name = address.as_mut_ptr().cast();
+        msg.msg_namelen = size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+        msg.msg_iov = &mut iovec;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen = size_of::<[u64; 4]>();
+
+        let flags = libc::MSG_DONTWAIT;
+        let n = syscall!(recvmsg(self.fd.as_fd().as_raw_fd(), &mut msg, flags))? as usize;
+        // SAFETY: the kernel initialised `n` bytes of the buffer.
+        unsafe { buf.update_length(n) };
+
+        // SAFETY: the kernel initialised `msg_namelen` bytes of the address.
+        let address = unsafe { to_socket_addr(address.as_ptr(), msg.msg_namelen) };
+        // If no control message is present a single datagram was received.
+        let mut segment_size = n;
+        // SAFETY: the kernel filled the control messages for us.
+        let mut cmsg = unsafe { libc::CMSG_FIRSTHDR(&msg) };
+        while !cmsg.is_null() {
+            // SAFETY: checked that the pointer is not null above.
+            let (level, kind) = unsafe { ((*cmsg).cmsg_level, (*cmsg).cmsg_type) };
+            if level == libc::SOL_UDP && kind == libc::UDP_GRO {
+                // SAFETY: per `udp(7)` the data is an `int`.
+                let size = unsafe { libc::CMSG_DATA(cmsg).cast::<libc::c_int>().read_unaligned() };
+                segment_size = size as usize;
+                break;
+            }
+            // SAFETY: `cmsg` is a valid control message of `msg`.
+            cmsg = unsafe { libc::CMSG_NXTHDR(&msg, cmsg) };
+        }
+        Ok((segment_size, address))
+    }
+
     fn with_ref<F, T>(&self, f: F) -> io::Result<T>
     where
         F: FnOnce(SockRef<'_>) -> io::Result<T>,
@@ -258,6 +561,37 @@ impl UdpSocket<Unconnected> {
         )
         .await
     }
+
+    /// Sends `buf` as multiple datagrams of `segment_size` bytes to
+    /// `address`, the last datagram may be smaller.
+    ///
+    /// See [`UdpSocket::send_segmented`] for more information.
+    pub async fn send_to_segmented<B: Buf>(
+        &self,
+        buf: B,
+        segment_size: u16,
+        address: SocketAddr,
+    ) -> io::Result<(B, usize)> {
+        self.send_segments(buf, segment_size, Some(address)).await
+    }
+
+    /// Receives (coalesced) datagrams, returning the buffer, the segment size
+    /// and the address of the sender.
+    ///
+    /// See [`UdpSocket::recv_segmented`] for more information.
+    pub async fn recv_from_segmented<B: BufMut>(
+        &self,
+        buf: B,
+    ) -> io::Result<(B, usize, SocketAddr)> {
+        let (buf, segment_size, address) = self.recv_segments(buf).await?;
+        match address {
+            Some(address) => Ok((buf, segment_size, address)),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid address received",
+            )),
+        }
+    }
 }
 
 impl UdpSocket<Connected> {
@@ -266,6 +600,25 @@ impl UdpSocket<Connected> {
         Recv(self.fd.recv(BufWrapper(buf), 0)).await
     }
 
+    /// Returns a stream of buffers, from `pool`, filled with received
+    /// datagrams.
+    ///
+    /// This uses io_uring's multishot receive (making it faster then calling
+    /// `recv` in a loop), the kernel selects a buffer from `pool` for every
+    /// datagram it receives.
+    ///
+    /// # Notes
+    ///
+    /// This will return `ENOBUFS` if no buffer is available in the `pool` to
+    /// read into. Make sure to drop (or [`release`]) the buffers once they're
+    /// processed.
+    ///
+    /// [`release`]: crate::io::ReadBuf::release
+    #[allow(clippy::doc_markdown)] // For "io_uring".
+    pub fn recv_stream(&self, pool: ReadBufPool) -> RecvStream<'_> {
+        RecvStream(self.fd.multishot_recv(pool.into_inner(), 0))
+    }
+
     /// Receives data from the connected socket, using vectored I/O.
     pub async fn recv_vectored<B: BufMutSlice<N>, const N: usize>(&self, bufs: B) -> io::Result<B> {
         RecvVectored(self.fd.recv_vectored(BufWrapper(bufs), 0)).await
@@ -295,6 +648,155 @@ impl UdpSocket<Connected> {
     ) -> io::Result<(B, usize)> {
         SendVectored(self.fd.send_vectored(BufWrapper(bufs), 0).extract()).await
     }
+
+    /// Sends `buf` as multiple datagrams of `segment_size` bytes to the
+    /// connected socket, the last datagram may be smaller.
+    ///
+    /// This uses UDP Generic Segmentation Offload (GSO, `UDP_SEGMENT`),
+    /// allowing many datagrams to be send in a single system call. The
+    /// segmentation is done by the kernel or, if supported, by the network
+    /// card. `buf` may contain at most 64 segments and 64 KB.
+    pub async fn send_segmented<B: Buf>(
+        &self,
+        buf: B,
+        segment_size: u16,
+    ) -> io::Result<(B, usize)> {
+        self.send_segments(buf, segment_size, None).await
+    }
+
+    /// Receives (coalesced) datagrams from the connected socket.
+    ///
+    /// Returns the buffer and the segment size. If Generic Receive Offload is
+    /// enabled, using [`UdpSocket::set_gro`], multiple datagrams may be
+    /// received at once. Each datagram in the buffer is `segment_size` bytes,
+    /// except the last which may be smaller, e.g. use
+    /// `buf.chunks(segment_size)` to iterate over them. If a single datagram
+    /// is received the segment size is its length.
+    pub async fn recv_segmented<B: BufMut>(&self, buf: B) -> io::Result<(B, usize)> {
+        self.recv_segments(buf)
+            .await
+            .map(|(buf, segment_size, _)| (buf, segment_size))
+    }
+}
+
+/// Convert the socket address at `address`, of `len` bytes, into a
+/// `SocketAddr`, if it's an IPv4 or IPv6 address.
+///
+/// # Safety
+///
+/// `address` must point to `len` initialised bytes.
+unsafe fn to_socket_addr(
+    address: *const libc::sockaddr_storage,
+    len: libc::socklen_t,
+) -> Option<SocketAddr> {
+    if (len as usize) < size_of::<libc::sa_family_t>() {
+        return None;
+    }
+    let mut storage: libc::sockaddr_storage = mem::zeroed();
+    let len = len.min(size_of::<libc::sockaddr_storage>() as libc::socklen_t);
+    ptr::copy_nonoverlapping(
+        address.cast::<u8>(),
+        ptr::addr_of_mut!(storage).cast::<u8>(),
+        len as usize,
+    );
+    socket2::SockAddr::new(storage, len).as_socket()
+}
+
+/// Error received from the socket's error queue, see
+/// [`UdpSocket::recv_error`].
+///
+/// See `ip(7)` (`IP_RECVERR`) for more information.
+#[derive(Clone, Debug)]
+pub struct ExtendedError {
+    errno: i32,
+    origin: u8,
+    icmp_type: u8,
+    icmp_code: u8,
+    info: u32,
+    offender: Option<SocketAddr>,
+    destination: Option<SocketAddr>,
+}
+
+impl ExtendedError {
+    /// The error, e.g. `ECONNREFUSED` for a destination (port) unreachable
+    /// error or `EMSGSIZE` if the packet is larger than the path's MTU.
+    pub fn error(&self) -> io::Error {
+        io::Error::from_raw_os_error(self.errno)
+    }
+
+    /// Where the error originated.
+    pub const fn origin(&self) -> ErrorOrigin {
+        match self.origin {
+            libc::SO_EE_ORIGIN_LOCAL => ErrorOrigin::Local,
+            libc::SO_EE_ORIGIN_ICMP => ErrorOrigin::Icmp,
+            libc::SO_EE_ORIGIN_ICMP6 => ErrorOrigin::Icmp6,
+            origin => ErrorOrigin::Other(origin),
+        }
+    }
+
+    /// The ICMP type, only valid for errors originating from [ICMP] or
+    /// [ICMPv6].
+    ///
+    /// [ICMP]: ErrorOrigin::Icmp
+    /// [ICMPv6]: ErrorOrigin::Icmp6
+    pub const fn icmp_type(&self) -> u8 {
+        self.icmp_type
+    }
+
+    /// The ICMP code, only valid for errors originating from [ICMP] or
+    /// [ICMPv6].
+    ///
+    /// [ICMP]: ErrorOrigin::Icmp
+    /// [ICMPv6]: ErrorOrigin::Icmp6
+    pub const fn icmp_code(&self) -> u8 {
+        self.icmp_code
+    }
+
+    /// Additional information about the error, e.g. the path MTU for `EMSGSIZE`
+    /// errors.
+    pub const fn info(&self) -> u32 {
+        self.info
+    }
+
+    /// Returns the path MTU if the packet was too large.
+    pub const fn mtu(&self) -> Option<u32> {
+        if self.errno == libc::EMSGSIZE {
+            Some(self.info)
+        } else {
+            None
+        }
+    }
+
+    /// The address of the node that caused the error, e.g. the router that
+    /// send the ICMP message.
+    pub const fn offender(&self) -> Option<SocketAddr> {
+        self.offender
+    }
+
+    /// The destination address of the packet that caused the error.
+    pub const fn destination(&self) -> Option<SocketAddr> {
+        self.destination
+    }
+}
+
+impl fmt::Display for ExtendedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error().fmt(f)
+    }
+}
+
+/// Origin of an [`ExtendedError`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorOrigin {
+    /// Error generated locally, e.g. a packet larger than the known path MTU.
+    Local,
+    /// Received an ICMP message.
+    Icmp,
+    /// Received an ICMPv6 message.
+    Icmp6,
+    /// Other origin, e.g. transmit timestamps or zero copy notifications.
+    Other(u8),
 }
 
 impl<M> AsFd for UdpSocket<M> {
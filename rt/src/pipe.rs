@@ -6,9 +6,11 @@
 //! In addition to creating a new pipe it's also possible to create a pipe from
 //! a process' standard I/O when [spawning another process]. For this use
 //! [`Sender::from_child_stdin`], [`Receiver::from_child_stdout`] and
-//! [`Receiver::from_child_stderr`] methods. See the example below.
+//! [`Receiver::from_child_stderr`] methods. See the example below. The
+//! [`process::Command`] type does this conversion for all piped standard I/O.
 //!
 //! [spawning another process]: std::process::Command
+//! [`process::Command`]: crate::process::Command
 //!
 //! # Examples
 //!
@@ -0,0 +1,289 @@
+//! Module containing [`Command`] and [`Child`].
+
+use std::ffi::OsStr;
+use std::os::unix::process::ExitStatusExt;
+use std::path::Path;
+use std::process::{self, ExitStatus, Stdio};
+use std::{fmt, io};
+
+use crate::access::Access;
+use crate::pipe;
+
+/// Builder for spawning a child process.
+///
+/// This is a wrapper around [`std::process::Command`], but spawns a [`Child`]
+/// which standard I/O pipes and exit status can be awaited without blocking
+/// the runtime.
+///
+/// By default the child process inherits the standard in, out and error of
+/// the current process, use [`Stdio::piped`] to create a pipe instead.
+///
+/// # Examples
+///
+/// ```
+/// # #![feature(never_type)]
+/// use std::io;
+/// use std::process::Stdio;
+///
+/// use heph::actor;
+/// use heph_rt::io::{Read, Write};
+/// use heph_rt::process::Command;
+/// use heph_rt::{self as rt};
+///
+/// const DATA: &[u8] = b"Hello, world!";
+///
+/// async fn process_handler<RT>(ctx: actor::Context<!, RT>) -> io::Result<()>
+///     where RT: rt::Access,
+/// {
+///     // Spawn the "cat" command that echos everything it reads from standard
+///     // in to standard out.
+///     let mut child = Command::new("cat")
+///         .stdin(Stdio::piped())
+///         .stdout(Stdio::piped())
+///         .stderr(Stdio::null())
+///         .spawn(ctx.runtime_ref())?;
+///
+///     // Write some data.
+///     let stdin = child.stdin.take().unwrap();
+///     (&stdin).write_all(DATA).await?;
+///     drop(stdin); // Close standard in for the child process.
+///
+///     // And read the data back.
+///     let stdout = child.stdout.take().unwrap();
+///     let buf = (&stdout).read_n(Vec::with_capacity(DATA.len() + 1), DATA.len()).await?;
+///     assert_eq!(buf, DATA);
+///
+///     // Wait for the process to exit.
+///     let status = child.wait().await?;
+///     assert!(status.success());
+///     Ok(())
+/// }
+/// #
+/// # heph_rt::test::block_on_local_actor(heph::actor::actor_fn(process_handler), ());
+/// ```
+pub struct Command {
+    inner: process::Command,
+}
+
+impl Command {
+    /// Create a new `Command` for launching `program`.
+    ///
+    /// See [`std::process::Command::new`].
+    pub fn new<S: AsRef<OsStr>>(program: S) -> Command {
+        Command {
+            inner: process::Command::new(program),
+        }
+    }
+
+    /// Add an argument to pass to the program.
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Command {
+        _ = self.inner.arg(arg);
+        self
+    }
+
+    /// Add multiple arguments to pass to the program.
+    pub fn args<I, S>(&mut self, args: I) -> &mut Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        _ = self.inner.args(args);
+        self
+    }
+
+    /// Set an environment variable for the child process.
+    pub fn env<K, V>(&mut self, key: K, value: V) -> &mut Command
+    where
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        _ = self.inner.env(key, value);
+        self
+    }
+
+    /// Set multiple environment variables for the child process.
+    pub fn envs<I, K, V>(&mut self, vars: I) -> &mut Command
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        _ = self.inner.envs(vars);
+        self
+    }
+
+    /// Remove an environment variable for the child process.
+    pub fn env_remove<K: AsRef<OsStr>>(&mut self, key: K) -> &mut Command {
+        _ = self.inner.env_remove(key);
+        self
+    }
+
+    /// Clear all environment variables for the child process.
+    pub fn env_clear(&mut self) -> &mut Command {
+        _ = self.inner.env_clear();
+        self
+    }
+
+    /// Set the working directory for the child process.
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Command {
+        _ = self.inner.current_dir(dir);
+        self
+    }
+
+    /// Set the configuration for the child process's standard in.
+    pub fn stdin<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Command {
+        _ = self.inner.stdin(cfg);
+        self
+    }
+
+    /// Set the configuration for the child process's standard out.
+    pub fn stdout<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Command {
+        _ = self.inner.stdout(cfg);
+        self
+    }
+
+    /// Set the configuration for the child process's standard error.
+    pub fn stderr<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Command {
+        _ = self.inner.stderr(cfg);
+        self
+    }
+
+    /// Spawn the command as a child process.
+    ///
+    /// Any [piped] standard I/O is converted into a [`pipe::Sender`] or
+    /// [`pipe::Receiver`].
+    ///
+    /// [piped]: Stdio::piped
+    pub fn spawn<RT>(&mut self, rt: &RT) -> io::Result<Child>
+    where
+        RT: Access,
+    {
+        let mut inner = self.inner.spawn()?;
+        let stdin = match inner.stdin.take() {
+            Some(stdin) => Some(pipe::Sender::from_child_stdin(rt, stdin)?),
+            None => None,
+        };
+        let stdout = match inner.stdout.take() {
+            Some(stdout) => Some(pipe::Receiver::from_child_stdout(rt, stdout)?),
+            None => None,
+        };
+        let stderr = match inner.stderr.take() {
+            Some(stderr) => Some(pipe::Receiver::from_child_stderr(rt, stderr)?),
+            None => None,
+        };
+        Ok(Child {
+            stdin,
+            stdout,
+            stderr,
+            inner,
+            status: None,
+            sq: rt.submission_queue(),
+        })
+    }
+}
+
+impl Command {
+    /// Returns the underlying [`std::process::Command`].
+    pub(crate) fn inner_mut(&mut self) -> &mut process::Command {
+        &mut self.inner
+    }
+}
+
+impl From<process::Command> for Command {
+    fn from(inner: process::Command) -> Command {
+        Command { inner }
+    }
+}
+
+impl fmt::Debug for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+/// A spawned child process.
+///
+/// Created by [`Command::spawn`].
+///
+/// # Notes
+///
+/// Like [`std::process::Child`] dropping a `Child` does **not** stop or wait
+/// for the child process.
+#[derive(Debug)]
+pub struct Child {
+    /// Standard in of the child process, if [piped].
+    ///
+    /// [piped]: Stdio::piped
+    pub stdin: Option<pipe::Sender>,
+    /// Standard out of the child process, if [piped].
+    ///
+    /// [piped]: Stdio::piped
+    pub stdout: Option<pipe::Receiver>,
+    /// Standard error of the child process, if [piped].
+    ///
+    /// [piped]: Stdio::piped
+    pub stderr: Option<pipe::Receiver>,
+    inner: process::Child,
+    /// Exit status of the process, once awaited.
+    status: Option<ExitStatus>,
+    sq: a10::SubmissionQueue,
+}
+
+impl Child {
+    /// Returns the OS-assigned process identifier of the child process.
+    pub fn id(&self) -> u32 {
+        self.inner.id()
+    }
+
+    /// Send a `SIGKILL` signal to the child process.
+    ///
+    /// Does nothing if the process already exited and was awaited.
+    pub fn kill(&mut self) -> io::Result<()> {
+        if self.status.is_some() {
+            // Process is already reaped, its pid might be reused.
+            return Ok(());
+        }
+        self.inner.kill()
+    }
+
+    /// Wait for the child process to exit, returning its exit status.
+    ///
+    /// This doesn't close the standard in of the child process, which might
+    /// cause the process to wait for input forever.
+    pub async fn wait(&mut self) -> io::Result<ExitStatus> {
+        if let Some(status) = self.status {
+            return Ok(status);
+        }
+        let info = a10::process::wait_on(self.sq.clone(), &self.inner, libc::WEXITED).await?;
+        let status = exit_status(&info);
+        self.status = Some(status);
+        Ok(status)
+    }
+
+    /// Returns the exit status of the child process, if it already exited.
+    ///
+    /// Unlike [`Child::wait`] this doesn't wait for the process to exit.
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        if let Some(status) = self.status {
+            return Ok(Some(status));
+        }
+        let status = self.inner.try_wait()?;
+        self.status = status;
+        Ok(status)
+    }
+}
+
+/// Convert the `info` returned by `waitid(2)` into an `ExitStatus`.
+fn exit_status(info: &libc::siginfo_t) -> ExitStatus {
+    // SAFETY: the kernel filled `info` for a `SIGCHLD` signal, which makes
+    // the `si_status` field valid.
+    let status = unsafe { info.si_status() };
+    // Convert into the format used by `waitpid(2)`, which is what `ExitStatus`
+    // expects.
+    let raw = match info.si_code {
+        libc::CLD_EXITED => (status & 0xff) << 8,
+        libc::CLD_DUMPED => status | 0x80,
+        _ => status, // `CLD_KILLED`.
+    };
+    ExitStatus::from_raw(raw)
+}
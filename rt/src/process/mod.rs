@@ -1,4 +1,9 @@
-//! Module containing the `Process` trait, related types and implementations.
+//! Child process management.
+//!
+//! See [`Command`] for spawning a child process, which returns a [`Child`].
+//!
+//! Internally this module also contains the `Process` trait, used by the
+//! schedulers to run actors and futures, and its implementations.
 
 use std::cmp::Ordering;
 use std::future::Future;
@@ -13,12 +18,17 @@ use heph::supervisor::Supervisor;
 use heph::{ActorFuture, NewActor};
 use log::{error, trace};
 
+#[cfg(feature = "alloc")]
+use crate::alloc::MemoryUsage;
 use crate::panic_message;
 use crate::spawn::options::Priority;
 
+mod command;
 #[cfg(test)]
 mod tests;
 
+pub use command::{Child, Command};
+
 /// Process id, or pid for short, is an identifier for a process in the runtime.
 ///
 /// This can only be created by one of the schedulers and should be seen as an
@@ -59,6 +69,12 @@ pub(crate) trait Process: Future<Output = ()> {
 
     /// Return the name of this process, used in logging.
     fn name(&self) -> &'static str;
+
+    /// Returns the number of messages in the inbox of the process, if it has
+    /// one. Used in panic reports.
+    fn inbox_len(&self) -> Option<usize> {
+        None
+    }
 }
 
 /// Wrapper around a [`Future`] to implement [`Process`].
@@ -99,13 +115,13 @@ where
     NA: NewActor,
     NA::RuntimeAccess: Clone,
 {
-    fn id(self: Pin<&Self>, _: ProcessId) -> ProcessId {
-        ProcessId(self.pid())
-    }
-
     fn name(&self) -> &'static str {
         NA::name()
     }
+
+    fn inbox_len(&self) -> Option<usize> {
+        Some(ActorFuture::inbox_len(self))
+    }
 }
 
 /// Data related to a process.
@@ -121,6 +137,15 @@ pub(crate) struct ProcessData<P: ?Sized> {
     priority: Priority,
     /// Fair runtime of the process, which is `actual runtime * priority`.
     fair_runtime: Duration,
+    /// Memory used by the process, see the [`alloc`] module.
+    ///
+    /// [`alloc`]: crate::alloc
+    #[cfg(feature = "alloc")]
+    memory: MemoryUsage,
+    /// Time at which the process was marked as ready to run, used to determine
+    /// the scheduling latency, see [`RunStats::latency`].
+    #[cfg(feature = "metrics")]
+    ready_since: Option<Instant>,
     process: Pin<Box<P>>,
 }
 
@@ -129,10 +154,28 @@ impl<P: ?Sized> ProcessData<P> {
         ProcessData {
             priority,
             fair_runtime: Duration::ZERO,
+            #[cfg(feature = "alloc")]
+            memory: MemoryUsage::new(),
+            #[cfg(feature = "metrics")]
+            ready_since: None,
             process,
         }
     }
 
+    /// Returns the priority of the process.
+    pub(crate) const fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Mark the process as ready to run, called when the process is added to
+    /// the ready queue of a scheduler.
+    pub(crate) fn set_ready(&mut self) {
+        #[cfg(feature = "metrics")]
+        {
+            self.ready_since = Some(Instant::now());
+        }
+    }
+
     #[cfg(test)]
     pub(crate) fn set_fair_runtime(&mut self, fair_runtime: Duration) {
         self.fair_runtime = fair_runtime;
@@ -151,6 +194,17 @@ impl<P: Process + ?Sized> ProcessData<P> {
         self.process.name()
     }
 
+    /// Returns the memory usage of the process.
+    #[cfg(feature = "alloc")]
+    pub(crate) const fn memory(&self) -> &MemoryUsage {
+        &self.memory
+    }
+
+    /// Returns the number of messages in the inbox of the process, if any.
+    pub(crate) fn inbox_len(&self) -> Option<usize> {
+        self.process.inbox_len()
+    }
+
     /// Run the process.
     ///
     /// Returns the completion state of the process.
@@ -159,17 +213,36 @@ impl<P: Process + ?Sized> ProcessData<P> {
         let name = self.process.name();
         trace!(pid = pid.0, name = name; "running process");
 
+        let this = &mut *self;
         let start = Instant::now();
-        let result = self.process.as_mut().poll(ctx);
+        #[cfg(feature = "metrics")]
+        let latency = this.ready_since.take().map(|ready| start - ready);
+        #[cfg(not(feature = "metrics"))]
+        let latency = None;
+        let process = this.process.as_mut();
+        let poll = || crate::log::with_context(pid, name, || process.poll(ctx));
+        #[cfg(feature = "alloc")]
+        let result = this.memory.track(poll);
+        #[cfg(not(feature = "alloc"))]
+        let result = poll();
         let elapsed = start.elapsed();
         let fair_elapsed = elapsed * self.priority;
         self.fair_runtime += fair_elapsed;
 
+        #[cfg(feature = "alloc")]
+        let memory_in_use = Some(self.memory.in_use());
+        #[cfg(not(feature = "alloc"))]
+        let memory_in_use: Option<usize> = None;
         trace!(
-            pid = pid.0, name = name, elapsed:? = elapsed, result:? = result;
+            pid = pid.0, name = name, elapsed:? = elapsed, latency:? = latency,
+            result:? = result, memory_in_use:? = memory_in_use;
             "finished running process",
         );
-        RunStats { elapsed, result }
+        RunStats {
+            elapsed,
+            latency,
+            result,
+        }
     }
 }
 
@@ -179,6 +252,11 @@ impl<P: Process + ?Sized> ProcessData<P> {
 pub(crate) struct RunStats {
     /// The duration for which the process ran.
     pub(crate) elapsed: Duration,
+    /// Scheduling latency, the time between the process being marked as ready
+    /// to run and it actually running. `None` if the process was run without
+    /// being added to a scheduler's ready queue, or if the `metrics` feature is
+    /// disabled.
+    pub(crate) latency: Option<Duration>,
     /// The result of the process run.
     pub(crate) result: Poll<()>,
 }
@@ -208,11 +286,20 @@ impl<P: Process + ?Sized> PartialOrd for ProcessData<P> {
 #[allow(clippy::missing_fields_in_debug)]
 impl<P: Process + ?Sized> fmt::Debug for ProcessData<P> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Process")
+        let mut f = f.debug_struct("Process");
+        _ = f
             .field("id", &self.id())
             .field("name", &self.name())
             .field("priority", &self.priority)
-            .field("fair_runtime", &self.fair_runtime)
-            .finish()
+            .field("fair_runtime", &self.fair_runtime);
+        #[cfg(feature = "alloc")]
+        {
+            _ = f.field("memory", &self.memory);
+        }
+        #[cfg(feature = "metrics")]
+        {
+            _ = f.field("ready_since", &self.ready_since);
+        }
+        f.finish()
     }
 }
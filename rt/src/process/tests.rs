@@ -27,7 +27,11 @@ fn pid() {
 fn size_assertions() {
     assert_size::<ProcessId>(8);
     assert_size::<Priority>(1);
-    assert_size::<ProcessData<Box<dyn Process>>>(32);
+    // `MemoryUsage` is only included with the `alloc` feature and the time the
+    // process was marked as ready with the `metrics` feature.
+    let memory = if cfg!(feature = "alloc") { 16 } else { 0 };
+    let ready_since = if cfg!(feature = "metrics") { 16 } else { 0 };
+    assert_size::<ProcessData<Box<dyn Process>>>(32 + memory + ready_since);
 }
 
 #[derive(Debug)]
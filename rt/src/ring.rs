@@ -0,0 +1,121 @@
+//! io_uring metrics.
+//!
+//! a10 doesn't expose the state of the io_uring queues, so we read it from the
+//! `/proc/self/fdinfo` file of the ring instead. This file is formatted by the
+//! kernel, see `io_uring_show_fdinfo` in `io_uring/fdinfo.c` in the Linux
+//! source.
+
+use std::fs;
+use std::io;
+use std::os::fd::{AsFd, AsRawFd};
+
+/// Metrics about an io_uring ring.
+///
+/// The `submissions` and `completions` counters are the kernel's head and tail
+/// indices of the queues, which wrap around at `u32::MAX`.
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct RingMetrics {
+    /// Number of submission queue entries not yet read by the kernel.
+    pub(crate) sq_pending: u32,
+    /// Number of completion queue entries not yet read by us.
+    pub(crate) cq_pending: u32,
+    /// Total number of submissions read by the kernel.
+    pub(crate) submissions: u32,
+    /// Total number of completions added by the kernel.
+    pub(crate) completions: u32,
+    /// Number of completions that didn't fit in the completion queue and are
+    /// waiting in the kernel's overflow list.
+    pub(crate) cq_overflows: usize,
+}
+
+impl RingMetrics {
+    /// Read the metrics for `ring`.
+    pub(crate) fn read(ring: &a10::Ring) -> io::Result<RingMetrics> {
+        let path = format!("/proc/self/fdinfo/{}", ring.as_fd().as_raw_fd());
+        fs::read_to_string(path).map(|fdinfo| RingMetrics::parse(&fdinfo))
+    }
+
+    /// Parse the metrics from the `fdinfo` of a ring.
+    fn parse(fdinfo: &str) -> RingMetrics {
+        let (mut sq_head, mut sq_tail, mut cq_head, mut cq_tail) = (0, 0, 0, 0);
+        let mut cq_overflows = 0;
+        let mut in_overflow_list = false;
+        for line in fdinfo.lines() {
+            if in_overflow_list {
+                // Entries in the list are indented, the list ends at the next
+                // field.
+                if line.starts_with(char::is_whitespace) {
+                    cq_overflows += 1;
+                    continue;
+                }
+                in_overflow_list = false;
+            }
+
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim().parse::<u32>().unwrap_or(0);
+            match name {
+                "SqHead" => sq_head = value,
+                "SqTail" => sq_tail = value,
+                "CqHead" => cq_head = value,
+                "CqTail" => cq_tail = value,
+                "CqOverflowList" => in_overflow_list = true,
+                _ => {}
+            }
+        }
+        RingMetrics {
+            sq_pending: sq_tail.wrapping_sub(sq_head),
+            cq_pending: cq_tail.wrapping_sub(cq_head),
+            submissions: sq_head,
+            completions: cq_tail,
+            cq_overflows,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RingMetrics;
+
+    #[test]
+    fn parse_fdinfo() {
+        const FDINFO: &str = "pos:\t0
+flags:\t02000002
+mnt_id:\t16
+ino:\t1050
+SqMask:\t0x7f
+SqHead:\t10
+SqTail:\t12
+CachedSqHead:\t12
+CqMask:\t0xff
+CqHead:\t6
+CqTail:\t9
+CachedCqTail:\t9
+SQEs:\t0
+CQEs:\t3
+SqThread:\t-1
+SqThreadCpu:\t-1
+UserFiles:\t0
+UserBufs:\t0
+PollList:
+CqOverflowList:
+  user_data=1, res=0, flags=0
+  user_data=2, res=0, flags=0
+";
+        let metrics = RingMetrics::parse(FDINFO);
+        assert_eq!(metrics.sq_pending, 2);
+        assert_eq!(metrics.cq_pending, 3);
+        assert_eq!(metrics.submissions, 10);
+        assert_eq!(metrics.completions, 9);
+        assert_eq!(metrics.cq_overflows, 2);
+    }
+
+    #[test]
+    fn read() {
+        let ring = a10::Ring::new(2).unwrap();
+        let metrics = RingMetrics::read(&ring).unwrap();
+        assert_eq!(metrics.sq_pending, 0);
+        assert_eq!(metrics.cq_overflows, 0);
+    }
+}
@@ -0,0 +1,421 @@
+//! Scheduling of periodic messages.
+//!
+//! The [`scheduler`] actor sends a message to an actor each time the
+//! [`Schedule`] passes. A schedule is either a fixed interval
+//! ([`Schedule::Every`]) or a cron-like expression ([`Schedule::Cron`]), see
+//! [`Cron`] for the supported syntax.
+//!
+//! # Examples
+//!
+//! Cleaning up a cache every night at 03:30.
+//!
+//! ```
+//! # #![feature(never_type)]
+//! use heph::actor::{self, actor_fn};
+//! use heph::supervisor::NoSupervisor;
+//! use heph_rt::schedule::{self, Schedule};
+//! use heph_rt::spawn::ActorOptions;
+//! use heph_rt::ThreadLocal;
+//!
+//! /// Message send to the cache.
+//! struct Cleanup;
+//!
+//! async fn setup(mut ctx: actor::Context<!, ThreadLocal>) {
+//!     let cache_ref = ctx.runtime().spawn_local(NoSupervisor, actor_fn(cache), (), ActorOptions::default());
+//!
+//!     let schedule = Schedule::Cron("30 3 * * *".parse().unwrap());
+//!     let scheduler = actor_fn(schedule::scheduler);
+//!     ctx.runtime().spawn_local(NoSupervisor, scheduler, (schedule, cache_ref, || Cleanup), ActorOptions::default());
+//! }
+//!
+//! async fn cache(mut ctx: actor::Context<Cleanup, ThreadLocal>) {
+//!     while let Ok(Cleanup) = ctx.receive_next().await {
+//!         println!("cleaning up cache");
+//!     }
+//! }
+//! # _ = setup; // Silence dead code warnings.
+//! ```
+
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime};
+use std::{cmp, fmt};
+
+use heph::{actor, ActorRef};
+use log::debug;
+
+use crate::access::Access;
+use crate::timer::Timer;
+
+/// Actor that sends the message created by `create_msg` to `actor_ref` each
+/// time `schedule` passes.
+///
+/// See the [module documentation] for an example.
+///
+/// # Notes
+///
+/// This actor will stop once `actor_ref` is disconnected, i.e. once the actor
+/// receiving the messages stopped, or if the schedule will never pass (again).
+///
+/// The messages are send using [`ActorRef::send`], if the inbox of the
+/// receiving actor is full the next deadline is delayed until the message is
+/// send.
+///
+/// [module documentation]: crate::schedule
+pub async fn scheduler<RT, M, F>(
+    ctx: actor::Context<!, RT>,
+    schedule: Schedule,
+    actor_ref: ActorRef<M>,
+    mut create_msg: F,
+) where
+    RT: Access + Clone,
+    F: FnMut() -> M,
+{
+    let mut deadlines = Deadlines::new(schedule);
+    loop {
+        let Some(deadline) = deadlines.next() else {
+            debug!("schedule will never pass, stopping `schedule::scheduler`");
+            return;
+        };
+        _ = Timer::at(ctx.runtime_ref().clone(), deadline).await;
+
+        if actor_ref.send(create_msg()).await.is_err() {
+            debug!("actor receiving scheduled messages stopped, stopping `schedule::scheduler`");
+            return;
+        }
+    }
+}
+
+/// When to send a message, see [`scheduler`].
+#[derive(Clone, Debug)]
+pub enum Schedule {
+    /// Every interval, starting one interval after the scheduler started.
+    ///
+    /// Same as [`Interval`] the next deadline is always set exactly the
+    /// interval after the last deadline.
+    ///
+    /// [`Interval`]: crate::timer::Interval
+    Every(Duration),
+    /// Cron-like expression.
+    Cron(Cron),
+}
+
+/// Iterator over the deadlines of a [`Schedule`].
+enum Deadlines {
+    Every { next: Instant, interval: Duration },
+    Cron { cron: Cron, last: SystemTime },
+}
+
+impl Deadlines {
+    fn new(schedule: Schedule) -> Deadlines {
+        match schedule {
+            Schedule::Every(interval) => Deadlines::Every {
+                next: Instant::now() + interval,
+                interval,
+            },
+            Schedule::Cron(cron) => Deadlines::Cron {
+                cron,
+                last: SystemTime::UNIX_EPOCH,
+            },
+        }
+    }
+}
+
+impl Iterator for Deadlines {
+    type Item = Instant;
+
+    fn next(&mut self) -> Option<Instant> {
+        match self {
+            Deadlines::Every { next, interval } => {
+                let deadline = *next;
+                *next += *interval;
+                Some(deadline)
+            }
+            Deadlines::Cron { cron, last } => {
+                let now = SystemTime::now();
+                // Timers can expire slightly before the time of the system
+                // clock, use the last time to ensure we don't use the same time
+                // twice.
+                let time = cron.next_after(cmp::max(now, *last))?;
+                *last = time;
+                let wait = time.duration_since(now).unwrap_or(Duration::ZERO);
+                Some(Instant::now() + wait)
+            }
+        }
+    }
+}
+
+/// Cron-like expression.
+///
+/// The expression consists of five fields separated by whitespace:
+///
+/// | Field        | Allowed values          |
+/// |--------------|-------------------------|
+/// | Minute       | 0-59                    |
+/// | Hour         | 0-23                    |
+/// | Day of month | 1-31                    |
+/// | Month        | 1-12                    |
+/// | Day of week  | 0-7 (0 and 7 is Sunday) |
+///
+/// Each field is either `*` (all values), a single value (`5`), a range
+/// (`1-5`) or a list of these (`1,3,10-12`). A step can be added to `*` or a
+/// range to only match every nth value, e.g. `*/15` in the minute field matches
+/// minute 0, 15, 30 and 45.
+///
+/// Like cron, if both the day of month and day of week fields are restricted
+/// (i.e. not `*`) the expression matches if either field matches.
+///
+/// Furthermore the following shorthands are supported: `@yearly` (or
+/// `@annually`), `@monthly`, `@weekly`, `@daily` (or `@midnight`) and
+/// `@hourly`.
+///
+/// # Notes
+///
+/// All times are in UTC.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, SystemTime};
+///
+/// use heph_rt::schedule::Cron;
+///
+/// // Every 15 minutes during office hours on weekdays.
+/// let cron: Cron = "*/15 9-17 * * 1-5".parse().unwrap();
+///
+/// // Thursday 1 January 1970 00:00.
+/// let time = SystemTime::UNIX_EPOCH;
+/// let next = cron.next_after(time).unwrap();
+/// assert_eq!(next, time + Duration::from_secs(9 * 60 * 60));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Cron {
+    /// Bit set for each minute (0-59).
+    minutes: u64,
+    /// Bit set for each hour (0-23).
+    hours: u32,
+    /// Bit set for each day of the month (1-31).
+    days: u32,
+    /// Bit set for each month (1-12).
+    months: u16,
+    /// Bit set for each day of the week (0-6, starting at Sunday).
+    weekdays: u8,
+    /// Whether or not the day of month and day of week fields are restricted,
+    /// i.e. not `*`.
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+/// Number of days we'll search for the next time, a bit over 8 years as that
+/// is the longest time between two leap days.
+const MAX_DAYS: u64 = 9 * 366;
+/// Number of minutes in a day.
+const DAY_MINUTES: u64 = 24 * 60;
+
+impl Cron {
+    /// Parse a cron expression.
+    pub fn parse(expr: &str) -> Result<Cron, CronError> {
+        let expr = match expr.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            expr => expr,
+        };
+        let mut fields = expr.split_whitespace();
+        let mut next_field = |min, max| match fields.next() {
+            Some(field) => parse_field(field, min, max),
+            None => Err(CronError::FieldCount),
+        };
+        let (minutes, _) = next_field(0, 59)?;
+        let (hours, _) = next_field(0, 23)?;
+        let (days, days_restricted) = next_field(1, 31)?;
+        let (months, _) = next_field(1, 12)?;
+        let (mut weekdays, weekdays_restricted) = next_field(0, 7)?;
+        if fields.next().is_some() {
+            return Err(CronError::FieldCount);
+        }
+        // Map 7 to 0, both are Sunday.
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        #[allow(clippy::cast_possible_truncation)] // Checked by `parse_field`.
+        Ok(Cron {
+            minutes,
+            hours: hours as u32,
+            days: days as u32,
+            months: months as u16,
+            weekdays: weekdays as u8 & 0b0111_1111,
+            days_restricted,
+            weekdays_restricted,
+        })
+    }
+
+    /// Returns the first time matching the expression strictly after `time`.
+    ///
+    /// Returns `None` if the expression never matches, e.g. `0 0 30 2 *` (30th
+    /// of February), or if `time` is before the Unix epoch.
+    pub fn next_after(&self, time: SystemTime) -> Option<SystemTime> {
+        let secs = time.duration_since(SystemTime::UNIX_EPOCH).ok()?.as_secs();
+        // Start at the next whole minute.
+        let minutes = secs / 60 + 1;
+        let start = minutes / DAY_MINUTES;
+        let mut minute_of_day = minutes % DAY_MINUTES;
+        for days in start..start + MAX_DAYS {
+            if self.matches_day(days) {
+                if let Some(minute_of_day) = self.next_minute_of_day(minute_of_day) {
+                    let minutes = (days * DAY_MINUTES) + minute_of_day;
+                    return Some(SystemTime::UNIX_EPOCH + Duration::from_secs(minutes * 60));
+                }
+            }
+            minute_of_day = 0;
+        }
+        None
+    }
+
+    /// Returns `true` if `days` (since the Unix epoch) matches.
+    fn matches_day(&self, days: u64) -> bool {
+        let (month, day) = month_day(days);
+        // 1 January 1970 was a Thursday.
+        let weekday = (days + 4) % 7;
+        if self.months & (1 << month) == 0 {
+            return false;
+        }
+        let day_matches = self.days & (1 << day) != 0;
+        let weekday_matches = self.weekdays & (1 << weekday) != 0;
+        if self.days_restricted && self.weekdays_restricted {
+            day_matches || weekday_matches
+        } else {
+            day_matches && weekday_matches
+        }
+    }
+
+    /// Returns the first minute of the day that matches, starting at `from`.
+    fn next_minute_of_day(&self, from: u64) -> Option<u64> {
+        let (mut hour, mut minute) = (from / 60, from % 60);
+        while hour < 24 {
+            if self.hours & (1 << hour) != 0 {
+                while minute < 60 {
+                    if self.minutes & (1 << minute) != 0 {
+                        return Some((hour * 60) + minute);
+                    }
+                    minute += 1;
+                }
+            }
+            hour += 1;
+            minute = 0;
+        }
+        None
+    }
+}
+
+impl FromStr for Cron {
+    type Err = CronError;
+
+    fn from_str(expr: &str) -> Result<Cron, CronError> {
+        Cron::parse(expr)
+    }
+}
+
+/// Parse a single field of a cron expression, returning the bit set of
+/// matching values and whether or not the field was restricted (not `*`).
+fn parse_field(field: &str, min: u64, max: u64) -> Result<(u64, bool), CronError> {
+    let mut set = 0;
+    let mut restricted = true;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = parse_value(step, 1, max)?;
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+        let (start, end) = if range == "*" {
+            restricted = step.is_some();
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            let start = parse_value(start, min, max)?;
+            let end = parse_value(end, min, max)?;
+            if start > end {
+                return Err(CronError::InvalidRange);
+            }
+            (start, end)
+        } else if step.is_some() {
+            // Steps are only supported on `*` or a range.
+            return Err(CronError::InvalidValue);
+        } else {
+            let value = parse_value(range, min, max)?;
+            (value, value)
+        };
+        let step = step.unwrap_or(1);
+        let mut value = start;
+        while value <= end {
+            set |= 1 << value;
+            value += step;
+        }
+    }
+    Ok((set, restricted))
+}
+
+/// Parse a single value, which must be in the range `min..=max`.
+fn parse_value(value: &str, min: u64, max: u64) -> Result<u64, CronError> {
+    match value.parse() {
+        Ok(value) if value >= min && value <= max => Ok(value),
+        Ok(_) => Err(CronError::OutOfRange),
+        Err(_) => Err(CronError::InvalidValue),
+    }
+}
+
+/// Returns the month (1-12) and day of the month (1-31) for `days` since the
+/// Unix epoch.
+///
+/// Based on the `civil_from_days` algorithm by Howard Hinnant.
+const fn month_day(days: u64) -> (u64, u64) {
+    // Shift the epoch to 1 March 0000.
+    let days = days + 719_468;
+    // Day of the era (400 year period).
+    let doe = days % 146_097;
+    // Year of the era.
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    // Day of the year, starting at 1 March.
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    // Month, starting at March (0).
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (month, day)
+}
+
+/// Error returned by parsing a [`Cron`] expression.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CronError {
+    /// Expression doesn't have exactly five fields.
+    FieldCount,
+    /// Invalid value, e.g. `a`.
+    InvalidValue,
+    /// Value is out of range for the field, e.g. `60` for minutes.
+    OutOfRange,
+    /// Range's start is after its end, e.g. `5-1`.
+    InvalidRange,
+}
+
+impl CronError {
+    /// Returns a description of the error.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            CronError::FieldCount => "expected five fields",
+            CronError::InvalidValue => "invalid value",
+            CronError::OutOfRange => "value out of range",
+            CronError::InvalidRange => "invalid range",
+        }
+    }
+}
+
+impl fmt::Display for CronError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+impl std::error::Error for CronError {}
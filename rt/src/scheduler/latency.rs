@@ -0,0 +1,119 @@
+//! Scheduling latency metrics.
+//!
+//! The scheduling latency is the time between a process being marked as ready
+//! to run and the process actually running. It's recorded per [`Priority`]
+//! class, which can be used to validate the priorities given to actors.
+
+use std::time::Duration;
+
+use crate::spawn::options::Priority;
+
+/// Number of buckets in the [`Histogram`].
+///
+/// Bucket `n` holds latencies smaller than `2^n` microseconds, with the last
+/// bucket holding all latencies that don't fit in the others (>= ~8.4 seconds).
+const BUCKETS: usize = 24;
+
+/// Priority classes tracked in [`SchedulingLatency`].
+const PRIORITIES: [Priority; 4] = [
+    Priority::SYSTEM,
+    Priority::HIGH,
+    Priority::NORMAL,
+    Priority::LOW,
+];
+
+/// Scheduling latency histograms per priority class.
+#[derive(Debug)]
+pub(crate) struct SchedulingLatency {
+    /// Histogram per priority, indexed by the position of the priority in
+    /// [`PRIORITIES`].
+    histograms: [Histogram; PRIORITIES.len()],
+}
+
+impl SchedulingLatency {
+    /// Create a new, empty, `SchedulingLatency`.
+    pub(crate) const fn new() -> SchedulingLatency {
+        SchedulingLatency {
+            histograms: [const { Histogram::new() }; PRIORITIES.len()],
+        }
+    }
+
+    /// Record a scheduling `latency` for a process with `priority`.
+    ///
+    /// Latencies of unknown priorities are ignored.
+    pub(crate) fn record(&mut self, priority: Priority, latency: Duration) {
+        if let Some(idx) = PRIORITIES.iter().position(|p| *p == priority) {
+            self.histograms[idx].record(latency);
+        }
+    }
+
+    /// Returns an iterator over all priority classes and their histograms.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (Priority, &Histogram)> {
+        PRIORITIES.into_iter().zip(self.histograms.iter())
+    }
+}
+
+/// Histogram of scheduling latencies, using exponential buckets.
+#[derive(Debug)]
+pub(crate) struct Histogram {
+    /// Number of latencies recorded per bucket, see [`BUCKETS`].
+    buckets: [u64; BUCKETS],
+    /// Total number of latencies recorded.
+    count: u64,
+    /// Largest latency recorded.
+    max: Duration,
+}
+
+impl Histogram {
+    /// Create a new, empty, `Histogram`.
+    const fn new() -> Histogram {
+        Histogram {
+            buckets: [0; BUCKETS],
+            count: 0,
+            max: Duration::ZERO,
+        }
+    }
+
+    /// Record a single `latency`.
+    pub(crate) fn record(&mut self, latency: Duration) {
+        let micros = u64::try_from(latency.as_micros()).unwrap_or(u64::MAX);
+        // Number of bits required to represent `micros`, e.g. 0 for 0µs, 1 for
+        // 1µs, 2 for 2µs and 3µs, etc.
+        let bits = (u64::BITS - micros.leading_zeros()) as usize;
+        self.buckets[bits.min(BUCKETS - 1)] += 1;
+        self.count += 1;
+        self.max = self.max.max(latency);
+    }
+
+    /// Returns the total number of latencies recorded.
+    pub(crate) const fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the largest latency recorded.
+    pub(crate) const fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// Returns the (approximate) latency at `percentile` (0-100).
+    ///
+    /// This returns the upper bound of the bucket the percentile falls into,
+    /// limited by the largest latency recorded. Returns zero if no latencies
+    /// were recorded.
+    pub(crate) fn percentile(&self, percentile: u8) -> Duration {
+        debug_assert!(percentile <= 100);
+        // Rank of the latency we're looking for, rounded up.
+        let rank = (self.count * u64::from(percentile)).div_ceil(100).max(1);
+        let mut seen = 0;
+        for (n, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                if n == BUCKETS - 1 {
+                    break;
+                }
+                return Duration::from_micros(1 << n).min(self.max);
+            }
+        }
+        self.max
+    }
+}
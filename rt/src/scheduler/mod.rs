@@ -10,6 +10,7 @@ use crate::process::{self, Process, ProcessId};
 use crate::spawn::options::Priority;
 
 mod inactive;
+pub(crate) mod latency;
 pub(crate) mod shared;
 #[cfg(test)]
 mod tests;
@@ -18,12 +19,20 @@ use inactive::Inactive;
 
 type ProcessData = process::ProcessData<dyn Process>;
 
+/// Number of completed processes after which the inactive processes are
+/// compacted, returning the memory used by completed processes.
+const COMPACT_INTERVAL: usize = 1024;
+
 #[derive(Debug)]
 pub(crate) struct Scheduler {
     /// Processes that are ready to run.
     ready: BinaryHeap<Pin<Box<ProcessData>>>,
     /// Processes that are not ready to run.
     inactive: Inactive,
+    /// Number of completed processes since `inactive` was last compacted.
+    completed: usize,
+    /// Total number of entries reclaimed by compacting `inactive`.
+    reclaimed: usize,
 }
 
 impl Scheduler {
@@ -32,6 +41,8 @@ impl Scheduler {
         Scheduler {
             ready: BinaryHeap::new(),
             inactive: Inactive::empty(),
+            completed: 0,
+            reclaimed: 0,
         }
     }
 
@@ -41,10 +52,25 @@ impl Scheduler {
     }
 
     /// Returns the number of inactive processes.
-    pub(crate) const fn inactive(&self) -> usize {
+    pub(crate) fn inactive(&self) -> usize {
         self.inactive.len()
     }
 
+    /// Returns the total number of entries reclaimed by compacting the
+    /// inactive processes.
+    pub(crate) const fn reclaimed(&self) -> usize {
+        self.reclaimed
+    }
+
+    /// Returns an iterator over all ready and inactive processes.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn processes(&self) -> impl Iterator<Item = &ProcessData> {
+        self.ready
+            .iter()
+            .map(|process| &**process)
+            .chain(self.inactive.iter())
+    }
+
     /// Returns `true` if the scheduler has any user processes (in any state),
     /// `false` otherwise. This ignore system processes.
     pub(crate) fn has_user_process(&self) -> bool {
@@ -62,8 +88,9 @@ impl Scheduler {
     where
         P: Process + 'static,
     {
-        let process = Box::pin(ProcessData::new(priority, Box::pin(process)));
+        let mut process = Box::pin(ProcessData::new(priority, Box::pin(process)));
         let pid = process.as_ref().id();
+        process.set_ready();
         self.ready.push(process);
         pid
     }
@@ -75,7 +102,8 @@ impl Scheduler {
     /// Calling this with an invalid or outdated `pid` will be silently ignored.
     pub(crate) fn mark_ready(&mut self, pid: ProcessId) {
         trace!(pid = pid.0; "marking process as ready");
-        if let Some(process) = self.inactive.remove(pid) {
+        if let Some(mut process) = self.inactive.remove(pid) {
+            process.set_ready();
             self.ready.push(process);
         }
     }
@@ -94,11 +122,26 @@ impl Scheduler {
     }
 
     /// Mark `process` as complete, removing it from the scheduler.
-    #[allow(clippy::unused_self)]
-    pub(crate) fn complete(&self, process: Pin<Box<ProcessData>>) {
+    ///
+    /// Every [`COMPACT_INTERVAL`] completed processes the inactive processes
+    /// are compacted.
+    pub(crate) fn complete(&mut self, process: Pin<Box<ProcessData>>) {
         let pid = process.as_ref().id();
         trace!(pid = pid.0; "removing process");
         // Don't want to panic when dropping the process.
         drop(catch_unwind(AssertUnwindSafe(move || drop(process))));
+
+        self.completed += 1;
+        if self.completed >= COMPACT_INTERVAL {
+            self.completed = 0;
+            self.compact();
+        }
+    }
+
+    /// Compact the inactive processes, returning unused memory.
+    pub(crate) fn compact(&mut self) {
+        let reclaimed = self.inactive.compact();
+        trace!(reclaimed = reclaimed; "compacted inactive processes");
+        self.reclaimed += reclaimed;
     }
 }
@@ -41,6 +41,18 @@ const fn ok_pid(pid: ProcessId) -> bool {
 /// in place when removing processes. This also means that we don't have to
 /// solve the difficult problem of concurrent garbage collection.
 ///
+/// As a consequence, unlike the thread-local scheduler, the tree is never
+/// compacted: branches are only freed once the tree is dropped. Compacting
+/// requires exclusive access to the tree, which would require a lock on the
+/// wake-up path (adding and marking processes as ready), which is exactly what
+/// the tree avoids. Process ids are based on the address of the process, so
+/// new processes mostly reuse the branches of completed processes.
+///
+/// Unlike the thread-local scheduler, which indexes its inactive processes
+/// using a hash map, this doesn't use a hash index. Processes are marked as
+/// ready from any thread, a hash map would require a lock on that path while
+/// the tree only uses atomic operations.
+///
 /// The implementation is effectively a hash set based on Hash Array Mapped Trie
 /// (HAMT). Some resources:
 /// * <https://en.wikipedia.org/wiki/Hash_array_mapped_trie>,
@@ -51,7 +51,8 @@ impl RunQueue {
     }
 
     /// Add `process` to the queue of running processes.
-    pub(crate) fn add(&self, process: Pin<Box<ProcessData>>) {
+    pub(crate) fn add(&self, mut process: Pin<Box<ProcessData>>) {
+        process.set_ready();
         let mut next_node = &mut *self.root.lock().unwrap();
         loop {
             match next_node {
@@ -15,7 +15,11 @@ use crate::ThreadSafe;
 
 #[test]
 fn size_assertions() {
-    assert_size::<ProcessData>(40);
+    // `MemoryUsage` is only included with the `alloc` feature and the time the
+    // process was marked as ready with the `metrics` feature.
+    let memory = if cfg!(feature = "alloc") { 16 } else { 0 };
+    let ready_since = if cfg!(feature = "metrics") { 16 } else { 0 };
+    assert_size::<ProcessData>(40 + memory + ready_since);
 }
 
 #[test]
@@ -6,13 +6,15 @@ use std::future::Future;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{self, Poll};
+use std::time::Duration;
 
 use heph::actor::{self, actor_fn};
 use heph::supervisor::NoSupervisor;
 use heph::ActorFutureBuilder;
 
 use crate::process::{FutureProcess, Process, ProcessId, RunStats};
-use crate::scheduler::{ProcessData, Scheduler};
+use crate::scheduler::latency::SchedulingLatency;
+use crate::scheduler::{ProcessData, Scheduler, COMPACT_INTERVAL};
 use crate::spawn::options::Priority;
 use crate::test::{self, assert_size, AssertUnmoved, TestAssertUnmovedNewActor};
 use crate::worker::SYSTEM_ACTORS;
@@ -20,7 +22,11 @@ use crate::ThreadLocal;
 
 #[test]
 fn size_assertions() {
-    assert_size::<ProcessData>(40);
+    // `MemoryUsage` is only included with the `alloc` feature and the time the
+    // process was marked as ready with the `metrics` feature.
+    let memory = if cfg!(feature = "alloc") { 16 } else { 0 };
+    let ready_since = if cfg!(feature = "metrics") { 16 } else { 0 };
+    assert_size::<ProcessData>(40 + memory + ready_since);
 }
 
 #[derive(Debug)]
@@ -290,6 +296,77 @@ fn assert_future_process_unmoved() {
     assert_eq!(process.as_mut().run(&mut ctx), Poll::Pending);
 }
 
+#[test]
+fn compact_after_completing_processes() {
+    let mut scheduler = test_scheduler();
+    let pids: Vec<ProcessId> = (0..COMPACT_INTERVAL)
+        .map(|_| add_test_actor(&mut scheduler, Priority::NORMAL))
+        .collect();
+    // Move all processes to the inactive processes.
+    while let Some(process) = scheduler.next_process() {
+        scheduler.add_back_process(process);
+    }
+    assert_eq!(scheduler.inactive(), COMPACT_INTERVAL + SYSTEM_ACTORS);
+
+    for pid in pids {
+        scheduler.mark_ready(pid);
+        let process = scheduler.next_process().unwrap();
+        scheduler.complete(process);
+    }
+    assert_eq!(scheduler.inactive(), SYSTEM_ACTORS);
+    assert!(scheduler.reclaimed() > 0);
+}
+
+#[test]
+#[cfg(feature = "metrics")]
+fn scheduling_latency() {
+    let mut scheduler = test_scheduler();
+    let mut ctx = task::Context::from_waker(task::Waker::noop());
+
+    let process = FutureProcess(pending());
+    let _ = scheduler.add_new_process(Priority::NORMAL, process);
+
+    let mut process = scheduler.next_process().unwrap();
+    let pid = process.as_ref().id();
+    let result = process.as_mut().run(&mut ctx);
+    assert!(result.latency.is_some());
+    scheduler.add_back_process(process);
+
+    scheduler.mark_ready(pid);
+    let mut process = scheduler.next_process().unwrap();
+    let result = process.as_mut().run(&mut ctx);
+    assert!(result.latency.is_some());
+
+    // Running the process again without marking it as ready has no latency.
+    let result = process.as_mut().run(&mut ctx);
+    assert!(result.latency.is_none());
+}
+
+#[test]
+fn scheduling_latency_histogram() {
+    let mut latency = SchedulingLatency::new();
+    for micros in 1..=100 {
+        latency.record(Priority::HIGH, Duration::from_micros(micros));
+    }
+    latency.record(Priority::LOW, Duration::from_secs(60));
+
+    for (priority, histogram) in latency.iter() {
+        if priority == Priority::HIGH {
+            assert_eq!(histogram.count(), 100);
+            assert_eq!(histogram.max(), Duration::from_micros(100));
+            assert_eq!(histogram.percentile(0), Duration::from_micros(2));
+            assert_eq!(histogram.percentile(50), Duration::from_micros(64));
+            assert_eq!(histogram.percentile(99), Duration::from_micros(100));
+        } else if priority == Priority::LOW {
+            assert_eq!(histogram.count(), 1);
+            assert_eq!(histogram.percentile(50), Duration::from_secs(60));
+        } else {
+            assert_eq!(histogram.count(), 0);
+            assert_eq!(histogram.percentile(50), Duration::ZERO);
+        }
+    }
+}
+
 fn add_test_actor(scheduler: &mut Scheduler, priority: Priority) -> ProcessId {
     let new_actor = actor_fn(simple_actor);
     let rt = ThreadLocal::new(test::runtime());
@@ -6,14 +6,18 @@ use std::mem::MaybeUninit;
 use std::num::NonZeroUsize;
 use std::path::{self, Path};
 use std::sync::Arc;
+use std::time::Duration;
 use std::{env, fmt, io, thread};
 
 use heph::actor_ref::ActorGroup;
-use log::{debug, warn};
+use log::{debug, warn, LevelFilter};
 
+use crate::config::{Config, Workers};
+#[cfg(feature = "console")]
+use crate::console;
 use crate::trace;
 use crate::wakers::shared::Wakers;
-use crate::{coordinator, shared, worker, Error, Runtime};
+use crate::{coordinator, shared, worker, Error, LocalData, Runtime, RuntimeRef};
 
 /// Setup a [`Runtime`].
 ///
@@ -32,6 +36,23 @@ pub struct Setup {
     auto_cpu_affinity: bool,
     /// Optional trace log.
     trace_log: Option<trace::CoordinatorLog>,
+    /// Granularity of the timer deadlines.
+    timer_granularity: Duration,
+    /// Number of entries in the worker threads' io_uring submission queue.
+    ring_entries: u32,
+    /// Maximum log level, if set.
+    log_level: Option<LevelFilter>,
+    /// Function to initialise the worker-local data.
+    local_data: Option<LocalDataInit>,
+    /// Maximum number of times a panicked worker thread is restarted.
+    worker_restarts: usize,
+    /// Runtime-wide shared resources.
+    resources: shared::Resources,
+    /// Maximum time to run the shutdown hooks.
+    shutdown_timeout: Duration,
+    /// Optional console listener.
+    #[cfg(feature = "console")]
+    console: Option<console::Listener>,
 }
 
 impl Setup {
@@ -42,9 +63,100 @@ impl Setup {
             threads: 1,
             auto_cpu_affinity: false,
             trace_log: None,
+            timer_granularity: Duration::ZERO,
+            ring_entries: worker::DEFAULT_RING_ENTRIES,
+            log_level: None,
+            local_data: None,
+            worker_restarts: 0,
+            resources: shared::Resources::new(),
+            shutdown_timeout: Duration::from_secs(10),
+            #[cfg(feature = "console")]
+            console: None,
         }
     }
 
+    /// Create a new `Setup` using the configuration from the environment
+    /// variables.
+    ///
+    /// The following environment variables are supported, all are optional:
+    ///  * `HEPH_NAME`: name of the application, see [`Setup::with_name`].
+    ///  * `HEPH_WORKERS`: number of worker threads, or `all` to use all CPU
+    ///    cores, see [`Setup::num_threads`] and [`Setup::use_all_cores`].
+    ///  * `HEPH_CPU_AFFINITY`: `true` or `false`, see
+    ///    [`Setup::auto_cpu_affinity`].
+    ///  * `HEPH_RING_ENTRIES`: number of entries in the worker threads'
+    ///    io_uring submission queue, see [`Setup::with_ring_entries`].
+    ///  * `HEPH_TIMER_GRANULARITY_MS`: timer granularity in milliseconds, see
+    ///    [`Setup::with_timer_granularity`].
+    ///  * `HEPH_TRACE`: path to the trace file, see [`Setup::enable_tracing`].
+    ///  * `HEPH_LOG_LEVEL`: maximum log level, e.g. `info` or `debug`, see
+    ///    [`Setup::with_log_level`].
+    ///
+    /// Unknown environment variables starting with `HEPH_` are ignored (with
+    /// a warning). Returns an error if a variable has an invalid value.
+    ///
+    /// The returned `Setup` can be modified further before the runtime is
+    /// build, e.g. to set a default name.
+    pub fn from_env() -> Result<Setup, Error> {
+        let config = Config::from_env().map_err(Error::setup_config)?;
+        Setup::new().with_config(config)
+    }
+
+    /// Create a new `Setup` using the configuration file at `path`.
+    ///
+    /// The configuration file uses a subset of [TOML]: `key = value` pairs,
+    /// where the value is a string, integer or boolean, and comments. The keys
+    /// are the same as the environment variables used in [`Setup::from_env`],
+    /// without the `HEPH_` prefix and in lowercase. For example:
+    ///
+    /// ```toml
+    /// name = "my_app"
+    /// # Use a worker thread per CPU core, pinned to the core.
+    /// workers = "all"
+    /// cpu_affinity = true
+    /// ring_entries = 256
+    /// timer_granularity_ms = 10
+    /// trace = "/var/log/my_app.trace"
+    /// log_level = "info"
+    /// ```
+    ///
+    /// Returns an error if the file can't be read, contains unknown settings
+    /// or invalid values.
+    ///
+    /// [TOML]: https://toml.io
+    pub fn from_config_file<P: AsRef<Path>>(path: P) -> Result<Setup, Error> {
+        let config = Config::from_file(path.as_ref()).map_err(Error::setup_config)?;
+        Setup::new().with_config(config)
+    }
+
+    /// Apply all settings in `config`.
+    fn with_config(mut self, config: Config) -> Result<Setup, Error> {
+        if let Some(name) = config.name {
+            self = self.with_name(name);
+        }
+        match config.workers {
+            Some(Workers::Count(n)) => self = self.num_threads(n),
+            Some(Workers::AllCores) => self = self.use_all_cores(),
+            None => {}
+        }
+        if let Some(cpu_affinity) = config.cpu_affinity {
+            self.auto_cpu_affinity = cpu_affinity;
+        }
+        if let Some(entries) = config.ring_entries {
+            self = self.with_ring_entries(entries);
+        }
+        if let Some(granularity) = config.timer_granularity {
+            self = self.with_timer_granularity(granularity);
+        }
+        if let Some(path) = config.trace {
+            self.enable_tracing(path)?;
+        }
+        if let Some(level) = config.log_level {
+            self = self.with_log_level(level);
+        }
+        Ok(self)
+    }
+
     /// Set the name of the application.
     ///
     /// If the name is not set when the runtime is build the name of the binary
@@ -119,6 +231,155 @@ impl Setup {
         self
     }
 
+    /// Round the deadlines of all timers up to a multiple of `granularity`,
+    /// defaults to zero (no rounding).
+    ///
+    /// This applies to all timers, deadlines and timeouts (see the [`timer`]
+    /// module) in both thread-local and thread-safe actors. Rounding the
+    /// deadlines causes timers with similar deadlines to expire at the same
+    /// time, reducing the number of times the worker threads are woken up. For
+    /// example using a granularity of 10 milliseconds, timers with a deadline
+    /// 101 and 105 milliseconds from now will both expire 110 milliseconds from
+    /// now.
+    ///
+    /// This is useful for applications with a large number of timers where
+    /// precision is not important, e.g. idle timeouts for connections. Note
+    /// that timers will expire up to `granularity` later than requested, but
+    /// never earlier.
+    ///
+    /// [`timer`]: crate::timer
+    pub const fn with_timer_granularity(mut self, granularity: Duration) -> Self {
+        self.timer_granularity = granularity;
+        self
+    }
+
+    /// Set the number of entries in the io_uring submission queue of each
+    /// worker thread, defaults to 128.
+    ///
+    /// Applications with a lot of concurrent I/O operations per worker thread
+    /// can increase this to reduce the number of times the queue is full.
+    pub const fn with_ring_entries(mut self, entries: u32) -> Self {
+        assert!(entries != 0, "Can't use zero io_uring entries");
+        self.ring_entries = entries;
+        self
+    }
+
+    /// Restart worker threads that panicked, at most `max_restarts` times in
+    /// total, defaults to zero.
+    ///
+    /// By default a panic in a worker thread is returned as an error from
+    /// [`Runtime::start`], stopping the runtime. With a restart budget the
+    /// coordinator instead starts a new worker thread (with the same id) to
+    /// replace the one that panicked, which picks up the work of the
+    /// thread-safe actors and futures. Only once the budget is exhausted is the
+    /// panic returned as error.
+    ///
+    /// # Notes
+    ///
+    /// The thread-local actors and futures of the panicked worker thread are
+    /// lost. The same is true for the thread-safe actor or future the worker
+    /// thread was running when it panicked, its state can't be trusted after
+    /// the panic so it's dropped rather than scheduled again. Functions send
+    /// using [`Runtime::run_on_workers`] are not run again on the new worker
+    /// thread, but the worker-local data is initialised again (see
+    /// [`Setup::with_local_data`]).
+    ///
+    /// [`Runtime::start`]: crate::Runtime::start
+    /// [`Runtime::run_on_workers`]: crate::Runtime::run_on_workers
+    pub const fn with_worker_restarts(mut self, max_restarts: usize) -> Self {
+        self.worker_restarts = max_restarts;
+        self
+    }
+
+    /// Set the maximum time to run the shutdown hooks, defaults to 10 seconds.
+    ///
+    /// Hooks that didn't complete within `timeout` are dropped, see
+    /// [`RuntimeRef::on_shutdown`].
+    ///
+    /// [`RuntimeRef::on_shutdown`]: crate::RuntimeRef::on_shutdown
+    pub const fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Set the maximum log level, see [`log::set_max_level`].
+    ///
+    /// The level is set when the runtime is build. Note that this doesn't setup
+    /// a logging implementation, see the [`mod@log`] module.
+    ///
+    /// [`mod@log`]: crate::log
+    pub const fn with_log_level(mut self, level: LevelFilter) -> Self {
+        self.log_level = Some(level);
+        self
+    }
+
+    /// Initialise the worker-local data on all worker threads using `init`.
+    ///
+    /// `init` is called once on each worker thread, before any thread-local
+    /// actors are spawned using [`Runtime::run_on_workers`]. See [`LocalData`]
+    /// for more information.
+    ///
+    /// [`Runtime::run_on_workers`]: crate::Runtime::run_on_workers
+    pub fn with_local_data<F>(mut self, init: F) -> Self
+    where
+        F: Fn(&mut LocalData) + Send + Sync + 'static,
+    {
+        self.local_data = Some(LocalDataInit(Arc::new(init)));
+        self
+    }
+
+    /// Add a runtime-wide shared resource, e.g. a connection pool or cache.
+    ///
+    /// The resource can be retrieved on any thread using the `resource`
+    /// method on [`Runtime`], [`RuntimeRef`] (and thus [`ThreadLocal`]),
+    /// [`ThreadSafe`] and [`Sync`], which returns a shared reference to it. At
+    /// most one resource per type is stored, adding a resource of the same type
+    /// again replaces the previous resource.
+    ///
+    /// Once all actors have finished, i.e. when the runtime shuts down, the
+    /// resources are dropped in reverse order of registration. This means a
+    /// resource can depend on resources added before it, e.g. a cache using a
+    /// connection pool. Note that resources still referenced elsewhere are
+    /// only dropped once the last reference is dropped.
+    ///
+    /// [`ThreadLocal`]: crate::ThreadLocal
+    /// [`ThreadSafe`]: crate::ThreadSafe
+    /// [`Sync`]: crate::Sync
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use heph::actor;
+    /// use heph_rt::{Runtime, ThreadSafe};
+    ///
+    /// /// Configuration shared by all actors.
+    /// struct AppConfig {
+    ///     greeting: String,
+    /// }
+    ///
+    /// let runtime = Runtime::setup()
+    ///     .with_resource(AppConfig { greeting: "Hello".to_owned() })
+    ///     .build()?;
+    /// let config = runtime.resource::<AppConfig>().unwrap();
+    /// assert_eq!(config.greeting, "Hello");
+    ///
+    /// async fn actor(mut ctx: actor::Context<String, ThreadSafe>) {
+    ///     let config = ctx.runtime().resource::<AppConfig>().unwrap();
+    ///     while let Ok(name) = ctx.receive_next().await {
+    ///         println!("{} {name}", config.greeting);
+    ///     }
+    /// }
+    /// # _ = actor; // Silence dead code warnings.
+    /// # Ok::<(), heph_rt::Error>(())
+    /// ```
+    pub fn with_resource<T>(mut self, resource: T) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        self.resources.insert(resource);
+        self
+    }
+
     /// Generate a trace of the runtime, writing it to the file specified by
     /// `path`.
     ///
@@ -136,19 +397,47 @@ impl Setup {
         }
     }
 
+    /// Enable the console, accepting connections on a Unix socket at `path`.
+    ///
+    /// See the [`mod@console`] module for more information.
+    ///
+    /// Returns an error if a file at `path` already exists or the socket can't
+    /// be bound. The socket is removed once the runtime is dropped.
+    ///
+    /// [`mod@console`]: crate::console
+    #[cfg(feature = "console")]
+    pub fn enable_console<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        match console::Listener::bind(path.as_ref()) {
+            Ok(listener) => {
+                self.console = Some(listener);
+                Ok(())
+            }
+            Err(err) => Err(Error::setup_console(err)),
+        }
+    }
+
     /// Build the runtime.
     ///
     /// This will spawn a number of worker threads (see [`Setup::num_threads`])
     /// to run all the actors.
     pub fn build(self) -> Result<Runtime, Error> {
         #[rustfmt::skip]
-        let Setup { name, threads, auto_cpu_affinity, mut trace_log } = self;
+        let Setup { name, threads, auto_cpu_affinity, mut trace_log, timer_granularity, ring_entries, log_level, local_data, worker_restarts, resources, shutdown_timeout, #[cfg(feature = "console")] console } = self;
+        if let Some(level) = log_level {
+            log::set_max_level(level);
+        }
         let timing = trace::start(&trace_log);
 
         let name = name.unwrap_or_else(default_app_name).into_boxed_str();
         debug!(name = name, workers = threads; "building Heph runtime");
 
-        let coordinator_setup = coordinator::setup(name, threads)?;
+        let restarts = coordinator::WorkerRestarts {
+            left: worker_restarts,
+            auto_cpu_affinity,
+            ring_entries,
+            local_data: local_data.clone(),
+        };
+        let coordinator_setup = coordinator::setup(name, threads, restarts, shutdown_timeout)?;
         let coordinator_sq = coordinator_setup.submission_queue();
 
         // Setup the worker threads, but don't spawn them yet.
@@ -157,8 +446,9 @@ impl Setup {
         for id in 1..=threads {
             // Coordinator has id 0.
             let id = NonZeroUsize::new(id).unwrap();
-            let (worker_setup, worker_sq) = worker::setup(id, auto_cpu_affinity, coordinator_sq)
-                .map_err(Error::start_worker)?;
+            let (worker_setup, worker_sq) =
+                worker::setup(id, auto_cpu_affinity, ring_entries, coordinator_sq)
+                    .map_err(Error::start_worker)?;
             worker_setups.push(worker_setup);
             worker_sqs.push(worker_sq);
         }
@@ -167,7 +457,9 @@ impl Setup {
         #[allow(clippy::cast_possible_truncation)]
         let entries = max((threads * 64) as u32, 8);
         let setup = shared::RuntimeInternals::setup(coordinator_sq.clone(), entries)
-            .map_err(Error::init_coordinator)?;
+            .map_err(Error::init_coordinator)?
+            .with_timer_granularity(timer_granularity)
+            .with_resources(resources);
         let worker_sqs = worker_sqs.into_boxed_slice();
         let shared_trace_log = trace_log.as_ref().map(trace::CoordinatorLog::clone_shared);
         let internals = Arc::new_cyclic(|shared_internals| {
@@ -175,6 +467,13 @@ impl Setup {
             setup.complete(wakers, worker_sqs, shared_trace_log)
         });
 
+        #[cfg(feature = "console")]
+        if let Some(console) = console {
+            console
+                .start(Arc::downgrade(&internals))
+                .map_err(Error::setup_console)?;
+        }
+
         trace::finish_rt(
             trace_log.as_mut(),
             timing,
@@ -195,6 +494,15 @@ impl Setup {
             })
             .collect::<io::Result<Vec<worker::Handle>>>()
             .map_err(Error::start_worker)?;
+        if let Some(local_data) = local_data {
+            // NOTE: the function is send before any other function send using
+            // `Runtime::run_on_workers`, so it's run first.
+            for worker in &workers {
+                local_data
+                    .send_to(worker)
+                    .map_err(|err| Error::coordinator(coordinator::Error::SendingFunc(err)))?;
+            }
+        }
         trace::finish_rt(
             trace_log.as_mut(),
             timing,
@@ -213,6 +521,27 @@ impl Setup {
     }
 }
 
+/// Function to initialise the worker-local data, see [`Setup::with_local_data`].
+#[derive(Clone)]
+pub(crate) struct LocalDataInit(Arc<dyn Fn(&mut LocalData) + Send + Sync>);
+
+impl LocalDataInit {
+    /// Send the function to the `worker` thread to initialise its data.
+    pub(crate) fn send_to(&self, worker: &worker::Handle) -> io::Result<()> {
+        let init = self.0.clone();
+        worker.send_function(Box::new(move |runtime_ref: RuntimeRef| {
+            runtime_ref.local_data(|data| init(data));
+            Ok(())
+        }))
+    }
+}
+
+impl fmt::Debug for LocalDataInit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LocalDataInit")
+    }
+}
+
 /// Returns the name of the binary called (i.e. `arg[0]`) as name.
 fn default_app_name() -> String {
     match env::args().next() {
@@ -2,18 +2,22 @@
 
 use std::cmp::min;
 use std::future::Future;
+use std::num::NonZeroUsize;
 use std::pin::Pin;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, TryLockError};
 use std::time::{Duration, Instant};
-use std::{io, task};
+use std::{fmt, io, task};
 
 use heph::actor_ref::ActorRef;
 use heph::supervisor::Supervisor;
 use heph::{ActorFutureBuilder, NewActor};
 use log::{debug, trace};
 
+#[cfg(feature = "console")]
+use crate::console::Console;
 use crate::process::{FutureProcess, Process, ProcessId};
+use crate::ring::RingMetrics;
 use crate::scheduler::shared::{ProcessData, Scheduler};
 #[cfg(test)]
 use crate::spawn::options::Priority;
@@ -23,6 +27,12 @@ use crate::timers::TimerToken;
 use crate::wakers::shared::Wakers;
 use crate::{trace, ThreadSafe};
 
+mod resources;
+mod shutdown;
+
+pub(crate) use resources::Resources;
+pub(crate) use shutdown::{ShutdownHook, ShutdownHooks};
+
 /// Setup of [`RuntimeInternals`].
 ///
 /// # Notes
@@ -36,9 +46,23 @@ use crate::{trace, ThreadSafe};
 pub(crate) struct RuntimeSetup {
     ring: a10::Ring,
     coordinator_sq: a10::SubmissionQueue,
+    timer_granularity: Duration,
+    resources: Resources,
 }
 
 impl RuntimeSetup {
+    /// Set the granularity of the timers, see [`Timers::with_granularity`].
+    pub(crate) const fn with_timer_granularity(mut self, granularity: Duration) -> RuntimeSetup {
+        self.timer_granularity = granularity;
+        self
+    }
+
+    /// Set the runtime-wide shared resources.
+    pub(crate) fn with_resources(mut self, resources: Resources) -> RuntimeSetup {
+        self.resources = resources;
+        self
+    }
+
     /// Complete the runtime setup.
     pub(crate) fn complete(
         self,
@@ -48,15 +72,20 @@ impl RuntimeSetup {
     ) -> RuntimeInternals {
         // Needed by `RuntimeInternals::wake_workers`.
         debug_assert!(worker_sqs.len() >= 1);
+        let worker_sqs = worker_sqs.into_vec().into_iter().map(WorkerSq::new);
         let sq = self.ring.submission_queue().clone();
         RuntimeInternals {
-            worker_sqs,
+            worker_sqs: worker_sqs.collect(),
             wake_worker_idx: AtomicUsize::new(0),
             ring: Mutex::new(self.ring),
             sq,
             wakers,
             scheduler: Scheduler::new(),
-            timers: Timers::new(),
+            timers: Timers::new().with_granularity(self.timer_granularity),
+            resources: self.resources,
+            shutdown_hooks: ShutdownHooks::new(),
+            #[cfg(feature = "console")]
+            console: Console::new(),
             trace_log,
             coordinator_sq: self.coordinator_sq,
         }
@@ -67,7 +96,10 @@ impl RuntimeSetup {
 #[derive(Debug)]
 pub(crate) struct RuntimeInternals {
     /// Submission queues for the workers, used to wake them.
-    worker_sqs: Box<[a10::SubmissionQueue]>,
+    ///
+    /// Only replaced when a worker thread is restarted, see
+    /// [`RuntimeInternals::replace_worker_sq`].
+    worker_sqs: Box<[WorkerSq]>,
     /// Index into `worker_sqs` to wake next, see
     /// [`RuntimeInternals::wake_workers`].
     wake_worker_idx: AtomicUsize,
@@ -81,6 +113,13 @@ pub(crate) struct RuntimeInternals {
     scheduler: Scheduler,
     /// Timers for thread-safe actors.
     timers: Timers,
+    /// Runtime-wide shared resources.
+    resources: Resources,
+    /// Futures to run once all actors have stopped.
+    shutdown_hooks: ShutdownHooks,
+    /// Statistics of the worker threads for the console.
+    #[cfg(feature = "console")]
+    console: Console,
     /// Shared trace log.
     ///
     /// # Notes
@@ -99,6 +138,8 @@ pub(crate) struct Metrics {
     pub(crate) scheduler_inactive: usize,
     pub(crate) timers_total: usize,
     pub(crate) timers_next: Option<Duration>,
+    pub(crate) timers_wakeups_avoided: u64,
+    pub(crate) ring: RingMetrics,
 }
 
 impl RuntimeInternals {
@@ -115,6 +156,8 @@ impl RuntimeInternals {
         Ok(RuntimeSetup {
             ring,
             coordinator_sq,
+            timer_granularity: Duration::ZERO,
+            resources: Resources::new(),
         })
     }
 
@@ -130,6 +173,8 @@ impl RuntimeInternals {
         Ok(RuntimeSetup {
             ring,
             coordinator_sq,
+            timer_granularity: Duration::ZERO,
+            resources: Resources::new(),
         })
     }
 
@@ -140,6 +185,8 @@ impl RuntimeInternals {
             scheduler_inactive: self.scheduler.inactive(),
             timers_total: self.timers.len(),
             timers_next: self.timers.next_timer(),
+            timers_wakeups_avoided: self.timers.wakeups_avoided(),
+            ring: RingMetrics::read(&self.ring.lock().unwrap()).unwrap_or_default(),
         }
     }
 
@@ -149,10 +196,12 @@ impl RuntimeInternals {
     }
 
     /// Polls the io_uring completion ring if it's currently not being polled.
-    pub(crate) fn try_poll_ring(&self) -> io::Result<()> {
+    ///
+    /// Returns `true` if the ring was polled.
+    pub(crate) fn try_poll_ring(&self) -> io::Result<bool> {
         match self.ring.try_lock() {
-            Ok(mut ring) => ring.poll(Some(Duration::ZERO)),
-            Err(TryLockError::WouldBlock) => Ok(()),
+            Ok(mut ring) => ring.poll(Some(Duration::ZERO)).map(|()| true),
+            Err(TryLockError::WouldBlock) => Ok(false),
             Err(TryLockError::Poisoned(err)) => panic!("failed to lock shared io_uring: {err}"),
         }
     }
@@ -178,6 +227,13 @@ impl RuntimeInternals {
         self.timers.remove(deadline, token);
     }
 
+    /// Returns the granularity of the timers.
+    ///
+    /// See [`Timers::granularity`].
+    pub(crate) const fn timer_granularity(&self) -> Duration {
+        self.timers.granularity()
+    }
+
     /// Wake all futures who's timers has expired.
     ///
     /// See [`Timers::expire_timers`].
@@ -226,6 +282,8 @@ impl RuntimeInternals {
         let (process, actor_ref) = ActorFutureBuilder::new()
             .with_rt(rt)
             .with_inbox_size(options.inbox_size())
+            .with_keep_on_restart(options.keep_on_restart())
+            .with_budget(options.budget())
             .build(supervisor, new_actor, arg)?;
         let pid = self.scheduler.add_new_process(options.priority(), process);
         let name = NA::name();
@@ -276,7 +334,7 @@ impl RuntimeInternals {
         let (wake_second, wake_first) = self.worker_sqs.split_at(wake_worker_idx);
         let workers_to_wake = wake_first.iter().chain(wake_second.iter());
         for worker in workers_to_wake {
-            worker.wake();
+            worker.get().wake();
         }
     }
 
@@ -284,10 +342,17 @@ impl RuntimeInternals {
     pub(crate) fn wake_all_workers(&self) {
         trace!("waking all worker thread(s)");
         for worker in &*self.worker_sqs {
-            worker.wake();
+            worker.get().wake();
         }
     }
 
+    /// Replace the submission queue of the worker with `id`, used when the
+    /// worker thread is restarted.
+    pub(crate) fn replace_worker_sq(&self, id: NonZeroUsize, sq: a10::SubmissionQueue) {
+        // NOTE: worker ids start at 1, the coordinator has id 0.
+        self.worker_sqs[id.get() - 1].replace(sq);
+    }
+
     /// See [`Scheduler::has_process`].
     pub(crate) fn has_process(&self) -> bool {
         self.scheduler.has_process()
@@ -298,6 +363,11 @@ impl RuntimeInternals {
         self.scheduler.has_ready_process()
     }
 
+    /// See [`Scheduler::ready`].
+    pub(crate) fn ready_processes(&self) -> usize {
+        self.scheduler.ready()
+    }
+
     /// See [`Scheduler::remove`].
     pub(crate) fn remove_process(&self) -> Option<Pin<Box<ProcessData>>> {
         self.scheduler.remove()
@@ -333,8 +403,98 @@ impl RuntimeInternals {
         );
     }
 
+    /// See [`Resources::get`].
+    pub(crate) fn resource<T>(&self) -> Option<Arc<T>>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.resources.get()
+    }
+
+    /// See [`Resources::clear`].
+    pub(crate) fn clear_resources(&self) {
+        self.resources.clear();
+    }
+
+    /// See [`ShutdownHooks::add`].
+    pub(crate) fn add_shutdown_hook<Fut>(&self, hook: Fut)
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.shutdown_hooks.add(hook);
+    }
+
+    /// See [`ShutdownHooks::take`].
+    pub(crate) fn take_shutdown_hooks(&self) -> Vec<ShutdownHook> {
+        self.shutdown_hooks.take()
+    }
+
+    /// Returns the statistics of the worker threads for the console.
+    #[cfg(feature = "console")]
+    pub(crate) const fn console(&self) -> &Console {
+        &self.console
+    }
+
     /// Wake the coordinator.
     pub(crate) fn wake_coordinator(&self) {
         self.coordinator_sq.wake();
     }
 }
+
+/// Submission queue of a worker thread, which can be replaced without
+/// blocking the threads using it to wake the worker.
+struct WorkerSq {
+    /// Current submission queue, never null.
+    current: AtomicPtr<a10::SubmissionQueue>,
+    /// Submission queues that were replaced.
+    ///
+    /// Other threads might still be using a replaced submission queue to wake
+    /// the (old) worker, so we can't drop them until `WorkerSq` is dropped.
+    /// Replacements only happen when a worker thread is restarted, which is
+    /// limited by [`Setup::with_worker_restarts`], so this doesn't grow
+    /// unbounded.
+    ///
+    /// [`Setup::with_worker_restarts`]: crate::Setup::with_worker_restarts
+    replaced: Mutex<Vec<Box<a10::SubmissionQueue>>>,
+}
+
+impl WorkerSq {
+    fn new(sq: a10::SubmissionQueue) -> WorkerSq {
+        WorkerSq {
+            current: AtomicPtr::new(Box::into_raw(Box::new(sq))),
+            replaced: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the current submission queue.
+    fn get(&self) -> &a10::SubmissionQueue {
+        // SAFETY: the pointer is created in `new` or `replace` and is valid
+        // until `self` is dropped, see `replaced`.
+        unsafe { &*self.current.load(Ordering::Acquire) }
+    }
+
+    /// Replace the current submission queue with `sq`.
+    fn replace(&self, sq: a10::SubmissionQueue) {
+        let old = self
+            .current
+            .swap(Box::into_raw(Box::new(sq)), Ordering::AcqRel);
+        // SAFETY: the pointer is created in `new` or `replace` using
+        // `Box::into_raw` and after the swap above we're the only owner.
+        let old = unsafe { Box::from_raw(old) };
+        self.replaced.lock().unwrap().push(old);
+    }
+}
+
+impl fmt::Debug for WorkerSq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+impl Drop for WorkerSq {
+    fn drop(&mut self) {
+        // SAFETY: the pointer is created in `new` or `replace` using
+        // `Box::into_raw` and we have unique access.
+        drop(unsafe { Box::from_raw(*self.current.get_mut()) });
+    }
+}
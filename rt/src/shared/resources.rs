@@ -0,0 +1,91 @@
+//! Module containing the [`Resources`] type.
+
+use std::any::{Any, TypeId};
+use std::fmt;
+use std::mem::take;
+use std::sync::{Arc, RwLock};
+
+/// Runtime-wide shared resources.
+///
+/// Type map, storing at most one value per type, shared by all threads of the
+/// runtime. See [`Setup::with_resource`].
+///
+/// [`Setup::with_resource`]: crate::Setup::with_resource
+pub(crate) struct Resources {
+    /// Values in order of registration.
+    values: RwLock<Vec<(TypeId, Arc<dyn Any + Send + Sync>)>>,
+}
+
+impl Resources {
+    /// Create an empty set of resources.
+    pub(crate) const fn new() -> Resources {
+        Resources {
+            values: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Insert `value`, replacing the previous value of the same type (if any).
+    pub(crate) fn insert<T>(&mut self, value: T)
+    where
+        T: Send + Sync + 'static,
+    {
+        let id = TypeId::of::<T>();
+        let value = Arc::new(value);
+        let values = self.values.get_mut().unwrap();
+        if let Some((_, old_value)) = values.iter_mut().find(|(type_id, _)| *type_id == id) {
+            *old_value = value;
+        } else {
+            values.push((id, value));
+        }
+    }
+
+    /// Returns the value of type `T`, if any.
+    pub(crate) fn get<T>(&self) -> Option<Arc<T>>
+    where
+        T: Send + Sync + 'static,
+    {
+        let id = TypeId::of::<T>();
+        let values = self.values.read().unwrap();
+        let (_, value) = values.iter().find(|(type_id, _)| *type_id == id)?;
+        // Can't fail as the value is stored under `TypeId::of::<T>()`.
+        value.clone().downcast().ok()
+    }
+
+    /// Drop all values in reverse order of registration.
+    ///
+    /// # Notes
+    ///
+    /// Values still referenced elsewhere (e.g. by an actor) are only dropped
+    /// once the last reference is dropped.
+    pub(crate) fn clear(&self) {
+        drop_in_reverse(take(&mut *self.values.write().unwrap()));
+    }
+
+    /// Returns the number of values.
+    fn len(&self) -> usize {
+        self.values.read().unwrap().len()
+    }
+}
+
+/// Drop `values` in reverse order, i.e. the last value first.
+fn drop_in_reverse<T>(mut values: Vec<T>) {
+    while let Some(value) = values.pop() {
+        drop(value);
+    }
+}
+
+impl Drop for Resources {
+    fn drop(&mut self) {
+        if let Ok(values) = self.values.get_mut() {
+            drop_in_reverse(take(values));
+        }
+    }
+}
+
+impl fmt::Debug for Resources {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Resources")
+            .field("len", &self.len())
+            .finish()
+    }
+}
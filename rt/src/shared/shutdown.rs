@@ -0,0 +1,55 @@
+//! Module containing the [`ShutdownHooks`] type.
+
+use std::fmt;
+use std::future::Future;
+use std::mem::take;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+/// Future run during shutdown, see [`ShutdownHooks`].
+pub(crate) type ShutdownHook = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Futures run by the coordinator once all actors have stopped.
+///
+/// See [`RuntimeRef::on_shutdown`].
+///
+/// [`RuntimeRef::on_shutdown`]: crate::RuntimeRef::on_shutdown
+pub(crate) struct ShutdownHooks {
+    /// Hooks in order of registration.
+    hooks: Mutex<Vec<ShutdownHook>>,
+}
+
+impl ShutdownHooks {
+    /// Create an empty set of hooks.
+    pub(crate) const fn new() -> ShutdownHooks {
+        ShutdownHooks {
+            hooks: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Add a new `hook`, it's run after all previously added hooks.
+    pub(crate) fn add<Fut>(&self, hook: Fut)
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.hooks.lock().unwrap().push(Box::pin(hook));
+    }
+
+    /// Remove all hooks, in order of registration.
+    pub(crate) fn take(&self) -> Vec<ShutdownHook> {
+        take(&mut *self.hooks.lock().unwrap())
+    }
+
+    /// Returns the number of hooks.
+    fn len(&self) -> usize {
+        self.hooks.lock().unwrap().len()
+    }
+}
+
+impl fmt::Debug for ShutdownHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShutdownHooks")
+            .field("len", &self.len())
+            .finish()
+    }
+}
@@ -1,6 +1,11 @@
 use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{self, Poll};
 
+use heph::actor_ref::ActorRef;
 use heph::messages::Terminate;
+use heph_inbox::{self as inbox, RecvValue};
 
 /// Process signal.
 ///
@@ -62,8 +67,11 @@ pub enum Signal {
     ///
     /// # Notes
     ///
-    /// The runtime will output various metrics about itself when it receives
-    /// this signal.
+    /// The runtime will output various metrics about itself, and the events in
+    /// the flight recorders of the worker threads (see
+    /// [`RuntimeRef::dump_flight_recorder`]), when it receives this signal.
+    ///
+    /// [`RuntimeRef::dump_flight_recorder`]: crate::RuntimeRef::dump_flight_recorder
     User2,
     /// Child signal.
     ///
@@ -397,6 +405,90 @@ impl fmt::Display for Signal {
     }
 }
 
+/// Receiver of process signals.
+///
+/// Created by [`Runtime::signal_receiver`] or [`RuntimeRef::signal_receiver`].
+/// This is an alternative to receiving process signals as messages in the
+/// actor's inbox, useful for actors with a message type that can't represent
+/// signals. [`SignalReceiver::recv`] can be combined with receiving the next
+/// message using [`ReceiveMessage::or_signal`].
+///
+/// [`Runtime::signal_receiver`]: crate::Runtime::signal_receiver
+/// [`RuntimeRef::signal_receiver`]: crate::RuntimeRef::signal_receiver
+/// [`ReceiveMessage::or_signal`]: heph::actor::ReceiveMessage::or_signal
+///
+/// # Examples
+///
+/// ```
+/// use heph::actor::{self, MessageOrSignal};
+/// use heph_rt::ThreadLocal;
+///
+/// async fn actor(mut ctx: actor::Context<String, ThreadLocal>) {
+///     let mut signals = ctx.runtime().signal_receiver();
+///     loop {
+///         match ctx.receive_next().or_signal(signals.recv()).await {
+///             Ok(MessageOrSignal::Message(msg)) => println!("Got a message: {msg}"),
+///             Ok(MessageOrSignal::Signal(signal)) if signal.should_stop() => break,
+///             Ok(MessageOrSignal::Signal(_)) => {}
+///             Err(_) => break,
+///         }
+///     }
+/// }
+/// # _ = actor; // Silence dead code warnings.
+/// ```
+#[derive(Debug)]
+pub struct SignalReceiver {
+    inbox: inbox::Receiver<Signal>,
+}
+
+impl SignalReceiver {
+    /// Create a new `SignalReceiver`, returning the actor reference to add to
+    /// the runtime's signal receivers.
+    pub(crate) fn new() -> (SignalReceiver, ActorRef<Signal>) {
+        let (sender, inbox) = inbox::new_small();
+        (SignalReceiver { inbox }, ActorRef::local(sender))
+    }
+
+    /// Attempt to receive a process signal.
+    ///
+    /// Returns `None` if no signal was received.
+    pub fn try_recv(&mut self) -> Option<Signal> {
+        self.inbox.try_recv().ok()
+    }
+
+    /// Receive the next process signal.
+    pub fn recv(&mut self) -> RecvSignal<'_> {
+        RecvSignal {
+            recv: self.inbox.recv(),
+        }
+    }
+}
+
+/// [`Future`] behind [`SignalReceiver::recv`].
+///
+/// # Notes
+///
+/// If the runtime is shutting down, and no longer sends process signals, this
+/// future never completes.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvSignal<'r> {
+    recv: RecvValue<'r, Signal>,
+}
+
+impl<'r> Future for RecvSignal<'r> {
+    type Output = Signal;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        match Pin::new(&mut self.recv).poll(ctx) {
+            Poll::Ready(Some(signal)) => Poll::Ready(signal),
+            // The runtime dropped its actor reference, so we'll never receive
+            // another signal.
+            Poll::Ready(None) | Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
 impl TryFrom<Signal> for Terminate {
     type Error = ();
 
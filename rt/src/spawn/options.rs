@@ -6,10 +6,12 @@
 //! [`Future`]: std::future::Future
 
 use std::cmp::Ordering;
+use std::num::NonZeroUsize;
 use std::ops::Mul;
 use std::time::Duration;
 
 pub use heph::future::InboxSize;
+use heph::future::DEFAULT_BUDGET;
 
 /// Options for [spawning] an [`Actor`].
 ///
@@ -35,11 +37,13 @@ pub use heph::future::InboxSize;
 /// let opts = ActorOptions::default().with_priority(Priority::HIGH);
 /// # _ = opts; // Silence unused variable warning.
 /// ```
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug)]
 #[must_use]
 pub struct ActorOptions {
     priority: Priority,
     inbox_size: InboxSize,
+    keep_on_restart: bool,
+    budget: Option<NonZeroUsize>,
 }
 
 impl ActorOptions {
@@ -47,6 +51,8 @@ impl ActorOptions {
     pub(crate) const SYSTEM: ActorOptions = ActorOptions {
         priority: Priority::SYSTEM,
         inbox_size: InboxSize::ONE,
+        keep_on_restart: false,
+        budget: Some(DEFAULT_BUDGET),
     };
 
     /// Returns the priority set in the options.
@@ -70,6 +76,48 @@ impl ActorOptions {
         self.inbox_size = inbox_size;
         self
     }
+
+    /// Returns `true` if the actor's local storage is kept across restarts.
+    pub const fn keep_on_restart(&self) -> bool {
+        self.keep_on_restart
+    }
+
+    /// Keep the actor's local storage across restarts.
+    ///
+    /// See [`ActorFutureBuilder::with_keep_on_restart`].
+    ///
+    /// [`ActorFutureBuilder::with_keep_on_restart`]: heph::future::ActorFutureBuilder::with_keep_on_restart
+    pub const fn with_keep_on_restart(mut self, keep: bool) -> Self {
+        self.keep_on_restart = keep;
+        self
+    }
+
+    /// Returns the cooperative scheduling budget set in the options.
+    pub const fn budget(&self) -> Option<NonZeroUsize> {
+        self.budget
+    }
+
+    /// Set the cooperative scheduling budget, `None` means unlimited.
+    ///
+    /// Only messages received from the actor's inbox count towards the budget,
+    /// I/O operations don't. See [`ActorFutureBuilder::with_budget`].
+    ///
+    /// [`ActorFutureBuilder::with_budget`]: heph::future::ActorFutureBuilder::with_budget
+    pub const fn with_budget(mut self, budget: Option<NonZeroUsize>) -> Self {
+        self.budget = budget;
+        self
+    }
+}
+
+impl Default for ActorOptions {
+    fn default() -> ActorOptions {
+        ActorOptions {
+            priority: Priority::default(),
+            inbox_size: InboxSize::default(),
+            keep_on_restart: false,
+            budget: Some(DEFAULT_BUDGET),
+        }
+    }
 }
 
 /// Priority for an actor or future in the scheduler.
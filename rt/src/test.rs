@@ -32,6 +32,8 @@
 //!  * Miscellaneous:
 //!    * [`size_of_actor`], [`size_of_actor_val`]: returns the size of an actor.
 //!    * [`set_message_loss`]: set the percentage of messages lost on purpose.
+//!    * [`inject_io_faults`]: inject I/O faults, such as errors or short reads,
+//!      into an actor.
 //!    * [`PanicSupervisor`]: supervisor that panics when it receives an actor's
 //!      error.
 //!
@@ -54,14 +56,18 @@
 
 use std::any::Any;
 use std::async_iter::AsyncIterator;
+use std::cell::Cell;
+use std::cmp::min;
+use std::collections::hash_map::RandomState;
 use std::future::{poll_fn, Future};
+use std::hash::{BuildHasher, Hasher};
 use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
 use std::pin::{pin, Pin};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, OnceLock};
 use std::task::{self, Poll};
 use std::time::{Duration, Instant};
-use std::{fmt, io, slice, thread};
+use std::{fmt, io, ptr, slice, thread};
 
 use heph::actor::{self, Actor, NewActor};
 use heph::actor_ref::{ActorGroup, ActorRef};
@@ -664,6 +670,270 @@ where
     Actor::try_poll(actor, &mut ctx)
 }
 
+/// Wrap `new_actor` to inject I/O `faults` into the actor it creates.
+///
+/// See [`IoFaults`] for the faults that can be injected.
+///
+/// # Examples
+///
+/// Testing the error handling of an actor.
+///
+/// ```
+/// # #![feature(never_type)]
+/// use std::io;
+/// use std::net::SocketAddr;
+///
+/// use heph::actor::{self, actor_fn};
+/// use heph_rt::net::TcpStream;
+/// use heph_rt::test::{block_on_local_actor, inject_io_faults, IoFaults};
+/// use heph_rt::ThreadLocal;
+///
+/// async fn actor(ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) {
+///     // Connecting is not affected by the injected faults.
+///     let stream = TcpStream::connect(ctx.runtime_ref(), address).await.unwrap();
+///     let err = stream.send_all("Hello world").await.unwrap_err();
+///     assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+/// }
+/// # let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+/// # let address = listener.local_addr().unwrap();
+///
+/// // Fail all I/O operations.
+/// let faults = IoFaults::new().with_errors(100, io::ErrorKind::ConnectionReset);
+/// let actor = inject_io_faults(actor_fn(actor), faults);
+/// block_on_local_actor(actor, address);
+/// ```
+pub const fn inject_io_faults<NA>(new_actor: NA, faults: IoFaults) -> InjectIoFaults<NA> {
+    InjectIoFaults {
+        inner: new_actor,
+        faults,
+    }
+}
+
+/// I/O faults to inject into an actor's I/O operations.
+///
+/// All percentages are in the range `0..=100`, where `0` (the default) means
+/// the fault is never injected and `100` means the fault is injected every time
+/// the I/O operation is polled. Note that using `100` for
+/// [`IoFaults::with_would_block`] or [`IoFaults::with_latency`] means the
+/// operation never completes.
+///
+/// Use [`inject_io_faults`] to inject the faults into an actor.
+///
+/// # Notes
+///
+/// Faults are only injected into reading and writing, or receiving and sending,
+/// operations, e.g. [`TcpStream::recv`] or [`File::write_at`]. Other operations,
+/// such as connecting a stream or accepting a connection, are not affected.
+///
+/// Short writes can't be injected as writing operations that write the entire
+/// buffer, e.g. [`TcpStream::send_all`], use the buffer to track their
+/// progress.
+///
+/// [`TcpStream::recv`]: crate::net::TcpStream::recv
+/// [`TcpStream::send_all`]: crate::net::TcpStream::send_all
+/// [`File::write_at`]: crate::fs::File::write_at
+#[derive(Clone, Debug, Default)]
+pub struct IoFaults {
+    /// Percentage and kind of errors.
+    errors: Option<(u8, io::ErrorKind)>,
+    /// Percentage of `WouldBlock`-like spurious wake-ups.
+    would_block: u8,
+    /// Percentage and maximum length of short reads.
+    short_reads: Option<(u8, usize)>,
+    /// Percentage and amount of latency.
+    latency: Option<(u8, Duration)>,
+}
+
+impl IoFaults {
+    /// Create a new `IoFaults` that doesn't inject any faults.
+    pub const fn new() -> IoFaults {
+        IoFaults {
+            errors: None,
+            would_block: 0,
+            short_reads: None,
+            latency: None,
+        }
+    }
+
+    /// Fail `percentage` of the I/O operations with an error of `kind`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentage` is larger than 100.
+    pub const fn with_errors(mut self, percentage: u8, kind: io::ErrorKind) -> IoFaults {
+        assert!(percentage <= 100, "percentage must be in the range 0..=100");
+        self.errors = Some((percentage, kind));
+        self
+    }
+
+    /// Return [`Poll::Pending`] for `percentage` of the polls, waking the
+    /// actor immediately, as if the operation would block.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentage` is larger than 100.
+    pub const fn with_would_block(mut self, percentage: u8) -> IoFaults {
+        assert!(percentage <= 100, "percentage must be in the range 0..=100");
+        self.would_block = percentage;
+        self
+    }
+
+    /// Limit `percentage` of the reads to read at most `max` bytes.
+    ///
+    /// Doesn't apply to vectored reads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentage` is larger than 100 or if `max` is zero.
+    pub const fn with_short_reads(mut self, percentage: u8, max: usize) -> IoFaults {
+        assert!(percentage <= 100, "percentage must be in the range 0..=100");
+        assert!(max != 0, "maximum short read length must not be zero");
+        self.short_reads = Some((percentage, max));
+        self
+    }
+
+    /// Delay `percentage` of the polls by `latency`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentage` is larger than 100.
+    pub const fn with_latency(mut self, percentage: u8, latency: Duration) -> IoFaults {
+        assert!(percentage <= 100, "percentage must be in the range 0..=100");
+        self.latency = Some((percentage, latency));
+        self
+    }
+
+    /// Call `f` with `self` set as faults for the I/O operations.
+    fn set<F: FnOnce() -> T, T>(&self, f: F) -> T {
+        /// Resets the current faults, also on panics.
+        struct Reset(*const IoFaults);
+
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                IO_FAULTS.with(|current| current.set(self.0));
+            }
+        }
+
+        let previous = IO_FAULTS.with(|current| current.replace(ptr::addr_of!(*self)));
+        let _reset = Reset(previous);
+        f()
+    }
+}
+
+thread_local! {
+    /// I/O faults of the currently running actor, or null if no faults should
+    /// be injected.
+    static IO_FAULTS: Cell<*const IoFaults> = const { Cell::new(ptr::null()) };
+}
+
+/// Call `f` with the I/O faults of the currently running actor, if any.
+fn with_io_faults<F: FnOnce(&IoFaults) -> Option<T>, T>(f: F) -> Option<T> {
+    let faults = IO_FAULTS.with(Cell::get);
+    // SAFETY: `IoFaults::set` ensures the pointer is valid while set.
+    unsafe { faults.as_ref() }.and_then(f)
+}
+
+/// Returns `true` with a chance of `percentage`%.
+fn chance(percentage: u8) -> bool {
+    percentage != 0 && random() % 100 < u64::from(percentage)
+}
+
+/// Returns a random number.
+///
+/// Not cryptographically secure, but good enough to inject faults.
+fn random() -> u64 {
+    // NOTE: every `RandomState` uses different keys.
+    RandomState::new().build_hasher().finish()
+}
+
+/// Inject an I/O fault, if any, into the current I/O operation.
+///
+/// Returns `None` if no fault should be injected.
+pub(crate) fn inject_io_fault(ctx: &mut task::Context<'_>) -> Option<Poll<io::Error>> {
+    with_io_faults(|faults| {
+        if let Some((percentage, kind)) = faults.errors {
+            if chance(percentage) {
+                return Some(Poll::Ready(io::Error::new(kind, "injected I/O fault")));
+            }
+        }
+        if chance(faults.would_block) {
+            ctx.waker().wake_by_ref();
+            return Some(Poll::Pending);
+        }
+        if let Some((percentage, latency)) = faults.latency {
+            if chance(percentage) {
+                let waker = ctx.waker().clone();
+                _ = thread::spawn(move || {
+                    thread::sleep(latency);
+                    waker.wake();
+                });
+                return Some(Poll::Pending);
+            }
+        }
+        None
+    })
+}
+
+/// Returns the length to read into a buffer of `len` bytes, which is smaller
+/// than `len` if a short read is injected.
+pub(crate) fn short_read_limit(len: usize) -> usize {
+    with_io_faults(|faults| match faults.short_reads {
+        #[allow(clippy::cast_possible_truncation)]
+        Some((percentage, max)) if len > 1 && chance(percentage) => {
+            Some(min(len, 1 + (random() % max as u64) as usize))
+        }
+        _ => None,
+    })
+    .unwrap_or(len)
+}
+
+/// [`NewActor`] and [`Actor`] wrapper to inject I/O faults.
+///
+/// See [`inject_io_faults`].
+#[derive(Clone, Debug)]
+pub struct InjectIoFaults<T> {
+    inner: T,
+    faults: IoFaults,
+}
+
+impl<NA: NewActor> NewActor for InjectIoFaults<NA> {
+    type Message = NA::Message;
+    type Argument = NA::Argument;
+    type Actor = InjectIoFaults<NA::Actor>;
+    type Error = NA::Error;
+    type RuntimeAccess = NA::RuntimeAccess;
+
+    fn new(
+        &mut self,
+        ctx: actor::Context<Self::Message, Self::RuntimeAccess>,
+        arg: Self::Argument,
+    ) -> Result<Self::Actor, Self::Error> {
+        let actor = self.inner.new(ctx, arg)?;
+        Ok(InjectIoFaults {
+            inner: actor,
+            faults: self.faults.clone(),
+        })
+    }
+
+    fn name() -> &'static str {
+        NA::name()
+    }
+}
+
+impl<A: Actor> Actor for InjectIoFaults<A> {
+    type Error = A::Error;
+
+    fn try_poll(
+        self: Pin<&mut Self>,
+        ctx: &mut task::Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        // SAFETY: not moving `inner`.
+        let this = unsafe { Pin::get_unchecked_mut(self) };
+        let actor = unsafe { Pin::new_unchecked(&mut this.inner) };
+        this.faults.set(|| actor.try_poll(ctx))
+    }
+}
+
 /// Assert that a `Future` is not moved between calls.
 #[cfg(test)]
 pub(crate) struct AssertUnmoved<Fut> {
@@ -678,7 +948,7 @@ impl<Fut> AssertUnmoved<Fut> {
     pub(crate) const fn new(future: Fut) -> AssertUnmoved<Fut> {
         AssertUnmoved {
             future,
-            last_place: std::ptr::null(),
+            last_place: ptr::null(),
         }
     }
 }
@@ -692,7 +962,7 @@ where
 
     #[track_caller]
     fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
-        let place = std::ptr::from_ref(&*self);
+        let place = ptr::from_ref(&*self);
         if self.last_place.is_null() {
             unsafe { Pin::map_unchecked_mut(self.as_mut(), |s| &mut s.last_place).set(place) }
         } else {
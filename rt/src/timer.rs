@@ -1,9 +1,11 @@
 //! Time related utilities.
 //!
-//! This module provides three types.
+//! This module provides four types.
 //!
 //! - [`Timer`] is a stand-alone [`Future`] that returns [`DeadlinePassed`] once
 //!   the deadline has passed.
+//! - [`Sleep`] is like `Timer`, but its deadline can be reset, e.g. for idle
+//!   timers.
 //! - [`Deadline`] wraps another `Future` and checks the deadline each time it's
 //!   polled.
 //! - [`Interval`] implements [`AsyncIterator`] which yields an item after the
@@ -13,7 +15,7 @@ use std::async_iter::AsyncIterator;
 use std::future::Future;
 use std::io;
 use std::pin::Pin;
-use std::task::{self, Poll};
+use std::task::{self, Poll, Waker};
 use std::time::{Duration, Instant};
 
 use crate::access::Access;
@@ -397,3 +399,151 @@ impl<RT: Access> AsyncIterator for Interval<RT> {
 }
 
 impl<RT: Access> Unpin for Interval<RT> {}
+
+/// A [`Future`] that represents a timer of which the deadline can be reset.
+///
+/// This is similar to [`Timer`], but [`Sleep::reset`] can be used to change
+/// the deadline, updating the registered timer in place. This is useful for
+/// timers that are pushed back often, such as idle timers of a connection that
+/// are reset on each activity.
+///
+/// # Examples
+///
+/// ```
+/// # #![feature(never_type)]
+/// #
+/// use std::time::Duration;
+///
+/// use heph::actor;
+/// # use heph::actor::actor_fn;
+/// # use heph::supervisor::NoSupervisor;
+/// # use heph_rt::spawn::ActorOptions;
+/// # use heph_rt::{self as rt, Runtime, RuntimeRef};
+/// use heph_rt::ThreadLocal;
+/// use heph_rt::timer::Sleep;
+/// use heph_rt::util::either;
+///
+/// # fn main() -> Result<(), rt::Error> {
+/// #     let mut runtime = Runtime::new()?;
+/// #     runtime.run_on_workers(setup)?;
+/// #     runtime.start()
+/// # }
+/// #
+/// # fn setup(mut runtime_ref: RuntimeRef) -> Result<(), !> {
+/// #   runtime_ref.spawn_local(NoSupervisor, actor_fn(actor), (), ActorOptions::default());
+/// #   Ok(())
+/// # }
+/// #
+/// const IDLE_TIMEOUT: Duration = Duration::from_millis(200);
+///
+/// async fn actor(mut ctx: actor::Context<String, ThreadLocal>) {
+///     let mut idle = Sleep::after(ctx.runtime_ref().clone(), IDLE_TIMEOUT);
+///     loop {
+///         match either(ctx.receive_next(), &mut idle).await {
+///             Ok(Ok(msg)) => {
+///                 println!("Got a message: {msg}");
+///                 // Push back the idle timer.
+///                 idle.reset_after(IDLE_TIMEOUT);
+///             }
+///             // No more messages or we've been idle for too long.
+///             Ok(Err(_)) | Err(_) => break,
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Sleep<RT: Access> {
+    timer: Timer<RT>,
+    /// Waker of the task that registered the timer, set if
+    /// `timer.timer_pending` is `Some`. Used to determine if the timer needs to
+    /// be registered again when polled by a different task.
+    waker: Option<Waker>,
+}
+
+impl<RT: Access> Sleep<RT> {
+    /// Create a new `Sleep`.
+    pub const fn at(rt: RT, deadline: Instant) -> Sleep<RT> {
+        Sleep {
+            timer: Timer::at(rt, deadline),
+            waker: None,
+        }
+    }
+
+    /// Create a new `Sleep`, based on a timeout.
+    ///
+    /// Same as calling `Sleep::at(rt, Instant::now() + timeout)`.
+    pub fn after(rt: RT, timeout: Duration) -> Sleep<RT> {
+        Sleep::at(rt, Instant::now() + timeout)
+    }
+
+    /// Returns the deadline set for this `Sleep`.
+    pub const fn deadline(&self) -> Instant {
+        self.timer.deadline
+    }
+
+    /// Returns `true` if the deadline has passed.
+    pub fn has_passed(&self) -> bool {
+        self.timer.has_passed()
+    }
+
+    /// Reset the deadline to `deadline`.
+    ///
+    /// If a timer was registered with the runtime it's moved to the new
+    /// `deadline`, waking the same task. This can also be used after the
+    /// future has completed, after which it can be polled again.
+    pub fn reset(&mut self, deadline: Instant) {
+        let timer = &mut self.timer;
+        if let Some(token) = timer.timer_pending.take() {
+            timer.rt.remove_timer(timer.deadline, token);
+            if let Some(waker) = &self.waker {
+                timer.timer_pending = Some(timer.rt.add_timer(deadline, timer_waker(waker)));
+            }
+        }
+        timer.deadline = deadline;
+    }
+
+    /// Reset the deadline to `timeout` from now.
+    ///
+    /// Same as calling `sleep.reset(Instant::now() + timeout)`.
+    pub fn reset_after(&mut self, timeout: Duration) {
+        self.reset(Instant::now() + timeout);
+    }
+}
+
+impl<RT: Access> Future for Sleep<RT> {
+    type Output = DeadlinePassed;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let timer = &mut this.timer;
+        if timer.has_passed() {
+            timer.timer_pending = None;
+            this.waker = None;
+            return Poll::Ready(DeadlinePassed);
+        }
+
+        let registered = this
+            .waker
+            .as_ref()
+            .is_some_and(|w| w.will_wake(ctx.waker()));
+        if !registered {
+            // Not registered yet, or polled by a different task.
+            if let Some(token) = timer.timer_pending.take() {
+                timer.rt.remove_timer(timer.deadline, token);
+            }
+            let waker = ctx.waker().clone();
+            timer.timer_pending = Some(timer.rt.add_timer(timer.deadline, timer_waker(&waker)));
+            this.waker = Some(waker);
+        }
+        Poll::Pending
+    }
+}
+
+/// Returns the waker to register a timer with for `waker`, see
+/// [`create_no_ring_waker`].
+fn timer_waker(waker: &Waker) -> Waker {
+    create_no_ring_waker(&mut task::Context::from_waker(waker)).unwrap_or_else(|| waker.clone())
+}
+
+impl<RT: Access> Unpin for Sleep<RT> {}
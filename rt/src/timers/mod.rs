@@ -35,6 +35,10 @@
 //! next slot (now `slots[index+1]`) holds timers that expire `0..NS_PER_SLOT`
 //! nanoseconds after `epoch`.
 //!
+//! Optionally the deadlines of the timers can be rounded up to a multiple of a
+//! granularity, see [`Coalesce`]. This reduces the number of distinct deadlines
+//! and thus the number of wake-ups.
+//!
 //! Note that for the `shared` version, which uses the same implementation as
 //! described above, it's possible for a thread to read the epoch (index and
 //! time), than gets descheduled, another thread updates the epoch and finally
@@ -104,6 +108,8 @@ pub(crate) struct Timers {
     /// If `Timers` is empty this prevents us from checking all `slots` and the
     /// `overflow` list.
     cached_next_deadline: CachedInstant,
+    /// Rounding of the deadlines.
+    coalesce: Coalesce,
 }
 
 /// A timer in [`Timers`].
@@ -117,15 +123,24 @@ impl Timers {
     /// Create a new collection of timers.
     pub(crate) fn new() -> Timers {
         const EMPTY: Vec<Timer<TimeOffset>> = Vec::new();
+        let epoch = Instant::now();
         Timers {
-            epoch: Instant::now(),
+            epoch,
             index: 0,
             slots: [EMPTY; SLOTS],
             overflow: Vec::new(),
             cached_next_deadline: CachedInstant::Empty,
+            coalesce: Coalesce::new(epoch),
         }
     }
 
+    /// Round all deadlines up to a multiple of `granularity`, see
+    /// [`Coalesce`].
+    pub(crate) fn with_granularity(mut self, granularity: Duration) -> Timers {
+        self.coalesce = self.coalesce.with_granularity(granularity);
+        self
+    }
+
     /// Returns the total number of timers.
     pub(crate) fn len(&self) -> usize {
         let mut timers = 0;
@@ -180,7 +195,7 @@ impl Timers {
     pub(crate) fn add(&mut self, deadline: Instant, waker: task::Waker) -> TimerToken {
         // Can't have deadline before the epoch, so we'll add a deadline with
         // same time as the epoch instead.
-        let deadline = max(deadline, self.epoch);
+        let deadline = max(self.coalesce.deadline(deadline), self.epoch);
         self.cached_next_deadline.update(deadline);
         self.get_timers(deadline, |timers| match timers {
             TimerLocation::InSlot((timers, deadline)) => add_timer(timers, deadline, waker),
@@ -190,7 +205,7 @@ impl Timers {
 
     /// Remove a previously added deadline.
     pub(crate) fn remove(&mut self, deadline: Instant, token: TimerToken) {
-        let deadline = max(deadline, self.epoch);
+        let deadline = max(self.coalesce.deadline(deadline), self.epoch);
         self.cached_next_deadline.invalidate(deadline);
         self.get_timers(deadline, |timers| match timers {
             TimerLocation::InSlot((timers, deadline)) => remove_timer(timers, deadline, token),
@@ -301,7 +316,7 @@ impl Timers {
         // needs to be the last. So we reverse the order, which ensures the list
         // is sorted again.
         timers.reverse();
-        debug_assert!(timers.is_sorted_by(|t1, t2| t1.deadline <= t2.deadline));
+        debug_assert!(timers.is_sorted_by(|t1, t2| t1.deadline >= t2.deadline));
 
         true
     }
@@ -312,6 +327,61 @@ impl Timers {
     }
 }
 
+/// Rounds the deadlines of timers up to a multiple of a granularity.
+///
+/// Timers with deadlines close to each other, e.g. idle timeouts for a large
+/// number of connections, will share the same deadline. This reduces the number
+/// of wake-ups and allows the timers to be expired in a single go. The
+/// granularity is relative to a fixed `anchor` (rather than the moving epoch)
+/// to ensure the same deadline is always rounded to the same value, which is
+/// required to remove timers.
+///
+/// A granularity of zero (the default) disables the rounding.
+#[derive(Copy, Clone, Debug)]
+struct Coalesce {
+    anchor: Instant,
+    /// In nanoseconds.
+    granularity: u64,
+}
+
+impl Coalesce {
+    /// Create a new `Coalesce` that doesn't round the deadlines.
+    const fn new(anchor: Instant) -> Coalesce {
+        Coalesce {
+            anchor,
+            granularity: 0,
+        }
+    }
+
+    /// Set the granularity.
+    fn with_granularity(mut self, granularity: Duration) -> Coalesce {
+        #[allow(clippy::cast_possible_truncation)] // ~584 years is plenty.
+        let granularity = min(granularity.as_nanos(), u128::from(u64::MAX)) as u64;
+        self.granularity = granularity;
+        self
+    }
+
+    /// Returns the granularity.
+    const fn granularity(&self) -> Duration {
+        Duration::from_nanos(self.granularity)
+    }
+
+    /// Returns `deadline` rounded up to the granularity.
+    fn deadline(&self, deadline: Instant) -> Instant {
+        if self.granularity == 0 {
+            return deadline;
+        }
+        let nanos = deadline.saturating_duration_since(self.anchor).as_nanos();
+        #[allow(clippy::cast_possible_truncation)] // Always < `granularity`.
+        let remainder = (nanos % u128::from(self.granularity)) as u64;
+        if remainder == 0 {
+            deadline
+        } else {
+            deadline + Duration::from_nanos(self.granularity - remainder)
+        }
+    }
+}
+
 /// Location of a timer.
 enum TimerLocation<'a> {
     /// In of the wheel's slots.
@@ -321,8 +391,11 @@ enum TimerLocation<'a> {
 }
 
 /// Add a new timer to `timers`, ensuring it remains sorted.
+///
+/// The timers are sorted in reverse order, i.e. the timer first to expire is
+/// the last timer, see [`remove_if_before`].
 fn add_timer<T: Ord>(timers: &mut Vec<Timer<T>>, deadline: T, waker: task::Waker) -> TimerToken {
-    let idx = match timers.binary_search_by(|timer| timer.deadline.cmp(&deadline)) {
+    let idx = match timers.binary_search_by(|timer| deadline.cmp(&timer.deadline)) {
         Ok(idx) | Err(idx) => idx,
     };
     let token = TimerToken(waker.as_raw().data() as usize);
@@ -333,10 +406,21 @@ fn add_timer<T: Ord>(timers: &mut Vec<Timer<T>>, deadline: T, waker: task::Waker
 /// Remove a previously added `deadline` from `timers`, ensuring it remains sorted.
 #[allow(clippy::needless_pass_by_value)]
 fn remove_timer<T: Ord>(timers: &mut Vec<Timer<T>>, deadline: T, token: TimerToken) {
-    if let Ok(idx) = timers.binary_search_by(|timer| timer.deadline.cmp(&deadline)) {
-        if timers[idx].waker.as_raw().data() as usize == token.0 {
-            _ = timers.remove(idx);
-        }
+    let Ok(idx) = timers.binary_search_by(|timer| deadline.cmp(&timer.deadline)) else {
+        return;
+    };
+    // Multiple timers can have the same deadline (especially when coalescing
+    // deadlines), so we need to check all of them.
+    let start = timers[..idx]
+        .iter()
+        .rposition(|timer| timer.deadline != deadline)
+        .map_or(0, |idx| idx + 1);
+    let idx = timers[start..]
+        .iter()
+        .take_while(|timer| timer.deadline == deadline)
+        .position(|timer| timer.waker.as_raw().data() as usize == token.0);
+    if let Some(idx) = idx {
+        _ = timers.remove(start + idx);
     }
 }
 
@@ -1,15 +1,17 @@
 //! Thread-safe version of `Timers`.
 
 use std::cmp::min;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::RwLock;
 use std::task::Waker;
 use std::time::{Duration, Instant};
 
 use crate::timers::{
-    add_timer, remove_if_before, remove_timer, TimeOffset, Timer, TimerLocation, TimerToken,
-    DURATION_PER_SLOT, NS_OVERFLOW, NS_PER_SLOT, NS_PER_SLOT_BITS, NS_SLOT_MASK, OVERFLOW_DURATION,
-    SLOTS, SLOT_BITS,
+    add_timer, remove_if_before, remove_timer, Coalesce, TimeOffset, Timer, TimerLocation,
+    TimerToken, DURATION_PER_SLOT, NS_OVERFLOW, NS_PER_SLOT, NS_PER_SLOT_BITS, NS_SLOT_MASK,
+    OVERFLOW_DURATION, SLOTS, SLOT_BITS,
 };
+use crate::wakers::wake_no_ring;
 
 /// Shared timers.
 #[derive(Debug)]
@@ -19,6 +21,11 @@ pub(crate) struct Timers {
     slots: [RwLock<Vec<Timer<TimeOffset>>>; SLOTS],
     /// The vector is sorted.
     overflow: RwLock<Vec<Timer<Instant>>>,
+    /// Rounding of the deadlines.
+    coalesce: Coalesce,
+    /// Number of worker thread wake-ups avoided by batching the wake-ups of
+    /// expired timers, see [`Timers::expire_timers`].
+    wakeups_avoided: AtomicU64,
 }
 
 /// Separate struct because both fields need to be updated atomically.
@@ -33,16 +40,31 @@ impl Timers {
     pub(crate) fn new() -> Timers {
         #[allow(clippy::declare_interior_mutable_const)]
         const EMPTY: RwLock<Vec<Timer<TimeOffset>>> = RwLock::new(Vec::new());
+        let epoch = Instant::now();
         Timers {
             epoch: RwLock::new(Epoch {
-                time: Instant::now(),
+                time: epoch,
                 index: 0,
             }),
             slots: [EMPTY; SLOTS],
             overflow: RwLock::new(Vec::new()),
+            coalesce: Coalesce::new(epoch),
+            wakeups_avoided: AtomicU64::new(0),
         }
     }
 
+    /// Round all deadlines up to a multiple of `granularity`, see
+    /// [`Coalesce`].
+    pub(crate) fn with_granularity(mut self, granularity: Duration) -> Timers {
+        self.coalesce = self.coalesce.with_granularity(granularity);
+        self
+    }
+
+    /// Returns the granularity of the deadlines.
+    pub(crate) const fn granularity(&self) -> Duration {
+        self.coalesce.granularity()
+    }
+
     /// Returns the total number of timers.
     pub(crate) fn len(&self) -> usize {
         let mut timers = 0;
@@ -93,6 +115,7 @@ impl Timers {
 
     /// Add a new deadline.
     pub(crate) fn add(&self, deadline: Instant, waker: Waker) -> TimerToken {
+        let deadline = self.coalesce.deadline(deadline);
         // NOTE: it's possible that we call `add_timer` based on an outdated
         // epoch.
         self.get_timers(deadline, |timers| match timers {
@@ -103,6 +126,7 @@ impl Timers {
 
     /// Remove a previously added deadline.
     pub(crate) fn remove(&self, deadline: Instant, token: TimerToken) {
+        let deadline = self.coalesce.deadline(deadline);
         self.get_timers(deadline, |timers| match timers {
             TimerLocation::InSlot((timers, deadline)) => remove_timer(timers, deadline, token),
             TimerLocation::Overflow((timers, deadline)) => remove_timer(timers, deadline, token),
@@ -135,13 +159,37 @@ impl Timers {
         }
     }
 
+    /// Returns the number of worker thread wake-ups avoided by batching the
+    /// wake-ups of expired timers.
+    pub(crate) fn wakeups_avoided(&self) -> u64 {
+        self.wakeups_avoided.load(Ordering::Relaxed)
+    }
+
     /// Expire all timers that have elapsed based on `now`. Returns the amount
     /// of expired timers.
     ///
+    /// Thread-safe processes are woken without waking a worker thread for
+    /// each timer, the caller is expected to wake enough worker threads to run
+    /// `amount` processes.
+    ///
     /// # Safety
     ///
     /// `now` may never go backwards between calls.
     pub(crate) fn expire_timers(&self, now: Instant) -> usize {
+        let mut batched = 0;
+        let amount = self.expire(now, &mut batched);
+        // Without batching we would have woken a worker thread for each
+        // process, now the caller wakes them once for all processes.
+        if batched != 0 {
+            _ = self.wakeups_avoided.fetch_add(batched, Ordering::Relaxed);
+        }
+        amount
+    }
+
+    /// Expire all timers that have elapsed based on `now`, see
+    /// [`Timers::expire_timers`]. Sets `batched` to the number of thread-safe
+    /// processes that were woken without waking a worker thread.
+    fn expire(&self, now: Instant, batched: &mut u64) -> usize {
         let mut amount = 0;
         loop {
             // NOTE: Each loop iteration needs to calculate the `epoch_offset`
@@ -166,7 +214,9 @@ impl Timers {
                 match result {
                     // Wake up the future.
                     Ok(timer) => {
-                        timer.waker.wake();
+                        if wake_no_ring(timer.waker) {
+                            *batched += 1;
+                        }
                         amount += 1;
                         // Try another timer in this slot.
                         continue;
@@ -180,6 +180,27 @@ fn adding_earlier_deadline_updates_cache() {
     assert_eq!(timers.expire_timers(deadline1), 0);
 }
 
+#[test]
+fn adding_earlier_deadline_in_same_slot() {
+    let mut timers = Timers::new();
+    let mut wakers = WakerBuilder::<2>::new();
+
+    let deadline1 = timers.epoch + Duration::from_millis(500);
+    let (n1, waker) = wakers.task_waker();
+    _ = timers.add(deadline1, waker);
+    let deadline2 = timers.epoch + Duration::from_millis(10);
+    let (n2, waker) = wakers.task_waker();
+    _ = timers.add(deadline2, waker);
+
+    assert_eq!(timers.expire_timers(deadline2), 1);
+    assert!(!wakers.is_awoken(n1));
+    assert!(wakers.is_awoken(n2));
+    assert_eq!(timers.next(), Some(deadline1));
+
+    assert_eq!(timers.expire_timers(deadline1), 1);
+    assert!(wakers.is_awoken(n1));
+}
+
 #[test]
 fn remove_deadline() {
     let mut timers = Timers::new();
@@ -333,6 +354,50 @@ fn remove_deadline_in_the_past() {
     assert!(!wakers.is_awoken(n));
 }
 
+#[test]
+fn coalesce_deadlines() {
+    let mut timers = Timers::new().with_granularity(Duration::from_millis(10));
+    let mut wakers = WakerBuilder::<3>::new();
+
+    let deadline1 = timers.epoch + Duration::from_millis(101);
+    let deadline2 = timers.epoch + Duration::from_millis(105);
+    let deadline3 = timers.epoch + Duration::from_millis(110);
+    let expected = timers.epoch + Duration::from_millis(110);
+    let (n1, waker) = wakers.task_waker();
+    _ = timers.add(deadline1, waker);
+    let (n2, waker) = wakers.task_waker();
+    _ = timers.add(deadline2, waker);
+    let (n3, waker) = wakers.task_waker();
+    _ = timers.add(deadline3, waker);
+    assert_eq!(timers.next(), Some(expected));
+
+    // Should never expire early.
+    assert_eq!(timers.expire_timers(deadline2), 0);
+    assert_eq!(timers.expire_timers(expected), 3);
+    assert!(wakers.is_awoken(n1));
+    assert!(wakers.is_awoken(n2));
+    assert!(wakers.is_awoken(n3));
+}
+
+#[test]
+fn remove_coalesced_deadline() {
+    let mut timers = Timers::new().with_granularity(Duration::from_millis(10));
+    let mut wakers = WakerBuilder::<1>::new();
+
+    // Go through a couple of epochs to ensure the rounding doesn't change.
+    let now = timers.epoch + DURATION_PER_SLOT * 3;
+    assert_eq!(timers.expire_timers(now), 0);
+
+    let deadline = now + Duration::from_millis(3);
+    let (n, waker) = wakers.task_waker();
+    let token = timers.add(deadline, waker);
+    assert!(timers.next().unwrap() > deadline);
+    timers.remove(deadline, token);
+    assert_eq!(timers.next(), None);
+    assert_eq!(timers.expire_timers(now + Duration::from_secs(1)), 0);
+    assert!(!wakers.is_awoken(n));
+}
+
 mod shared {
     use std::time::Duration;
 
@@ -480,6 +545,28 @@ mod shared {
         assert_eq!(timers.expire_timers(deadline1), 0);
     }
 
+    #[test]
+    fn adding_earlier_deadline_in_same_slot() {
+        let timers = Timers::new();
+        let mut wakers = WakerBuilder::<2>::new();
+        let epoch = timers.epoch().0;
+
+        let (n1, waker) = wakers.task_waker();
+        let deadline1 = epoch + Duration::from_millis(500);
+        _ = timers.add(deadline1, waker);
+        let (n2, waker) = wakers.task_waker();
+        let deadline2 = epoch + Duration::from_millis(10);
+        _ = timers.add(deadline2, waker);
+
+        assert_eq!(timers.expire_timers(deadline2), 1);
+        assert!(!wakers.is_awoken(n1));
+        assert!(wakers.is_awoken(n2));
+        assert_eq!(timers.next(), Some(deadline1));
+
+        assert_eq!(timers.expire_timers(deadline1), 1);
+        assert!(wakers.is_awoken(n1));
+    }
+
     #[test]
     fn remove_deadline() {
         let timers = Timers::new();
@@ -637,4 +724,30 @@ mod shared {
         assert_eq!(timers.next(), None);
         assert_eq!(timers.expire_timers(epoch), 0);
     }
+
+    #[test]
+    fn coalesce_deadlines() {
+        let timers = Timers::new().with_granularity(Duration::from_millis(10));
+        let mut wakers = WakerBuilder::<2>::new();
+        let epoch = timers.epoch().0;
+        assert_eq!(timers.granularity(), Duration::from_millis(10));
+
+        let deadline1 = epoch + Duration::from_millis(101);
+        let deadline2 = epoch + Duration::from_millis(105);
+        let expected = epoch + Duration::from_millis(110);
+        let (n1, waker) = wakers.task_waker();
+        _ = timers.add(deadline1, waker);
+        let (n2, waker) = wakers.task_waker();
+        let token = timers.add(deadline2, waker);
+        assert_eq!(timers.next(), Some(expected));
+
+        timers.remove(deadline2, token);
+        assert_eq!(timers.len(), 1);
+
+        // Should never expire early.
+        assert_eq!(timers.expire_timers(deadline1), 0);
+        assert_eq!(timers.expire_timers(expected), 1);
+        assert!(wakers.is_awoken(n1));
+        assert!(!wakers.is_awoken(n2));
+    }
 }
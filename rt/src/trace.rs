@@ -76,7 +76,7 @@ use std::sync::atomic::{self, AtomicU32};
 use std::sync::Arc;
 use std::time::{Instant, SystemTime};
 
-use log::warn;
+use log::{info, warn};
 
 /// Default buffer size, only needs to hold a single trace event.
 const BUF_SIZE: usize = 128;
@@ -342,6 +342,110 @@ impl Clone for Log {
     }
 }
 
+/// Number of events kept by a [`FlightRecorder`].
+pub(crate) const FLIGHT_RECORDER_SIZE: usize = 256;
+
+/// Flight recorder of a worker thread.
+///
+/// Ring buffer holding the last [`FLIGHT_RECORDER_SIZE`] events of a worker
+/// thread, e.g. running a process or polling for OS events. Unlike the trace
+/// [`Log`] it's always enabled, so it's kept cheap: a record is only a
+/// timestamp, a static description and a single value (e.g. a process id).
+///
+/// The recorded events are logged using [`FlightRecorder::dump`], which is done
+/// when the worker thread panics, when the process receives `SIGUSR2` and when
+/// [`RuntimeRef::dump_flight_recorder`] is called.
+///
+/// [`RuntimeRef::dump_flight_recorder`]: crate::RuntimeRef::dump_flight_recorder
+#[derive(Debug)]
+pub(crate) struct FlightRecorder {
+    /// Recorded events, once full it's overwritten starting at the oldest
+    /// record.
+    records: Vec<Record>,
+    /// Index of the oldest record in `records`, once full.
+    next: usize,
+}
+
+/// Event recorded by the [`FlightRecorder`].
+#[derive(Copy, Clone, Debug)]
+struct Record {
+    time: Instant,
+    description: &'static str,
+    value: u64,
+}
+
+impl FlightRecorder {
+    /// Create a new empty flight recorder.
+    pub(crate) fn new() -> FlightRecorder {
+        FlightRecorder {
+            records: Vec::with_capacity(FLIGHT_RECORDER_SIZE),
+            next: 0,
+        }
+    }
+
+    /// Record an event with `description` and `value`, overwriting the oldest
+    /// event if the recorder is full.
+    pub(crate) fn record(&mut self, description: &'static str, value: u64) {
+        let record = Record {
+            time: Instant::now(),
+            description,
+            value,
+        };
+        if self.records.len() < FLIGHT_RECORDER_SIZE {
+            self.records.push(record);
+        } else {
+            self.records[self.next] = record;
+            self.next = (self.next + 1) % FLIGHT_RECORDER_SIZE;
+        }
+    }
+
+    /// Returns the number of recorded events.
+    pub(crate) fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Iterate over the recorded events, oldest first.
+    fn iter(&self) -> impl Iterator<Item = &Record> {
+        let (newest, oldest) = self.records.split_at(self.next);
+        oldest.iter().chain(newest)
+    }
+
+    /// Returns the last `n` recorded events formatted as strings, oldest
+    /// first.
+    pub(crate) fn recent(&self, n: usize) -> Vec<String> {
+        let now = Instant::now();
+        let skip = self.len().saturating_sub(n);
+        self.iter()
+            .skip(skip)
+            .map(|record| {
+                let ago = now.saturating_duration_since(record.time);
+                format!("{}={} ({ago:?} ago)", record.description, record.value)
+            })
+            .collect()
+    }
+
+    /// Log all recorded events, oldest first, using the `flight_recorder`
+    /// target.
+    pub(crate) fn dump(&self, worker_id: usize) {
+        let now = Instant::now();
+        info!(
+            target: "flight_recorder",
+            worker_id = worker_id,
+            events = self.len();
+            "dumping flight recorder",
+        );
+        for record in self.iter() {
+            info!(
+                target: "flight_recorder",
+                worker_id = worker_id,
+                ago:? = now.saturating_duration_since(record.time),
+                value = record.value;
+                "{}", record.description,
+            );
+        }
+    }
+}
+
 /// Start timing an event (using [`EventTiming`]) if we're tracing, i.e. if
 /// `log` is `Some`.
 pub(crate) fn start<L>(log: &Option<L>) -> Option<EventTiming>
@@ -728,3 +832,45 @@ mod private {
 
     impl<T, const N: usize> super::AttributeValue for [T; N] where T: super::AttributeValue + Default {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{FlightRecorder, FLIGHT_RECORDER_SIZE};
+
+    #[test]
+    fn flight_recorder_overwrites_oldest() {
+        let mut recorder = FlightRecorder::new();
+        assert_eq!(recorder.len(), 0);
+        assert_eq!(recorder.iter().count(), 0);
+
+        for value in 0..10 {
+            recorder.record("event", value);
+        }
+        assert_eq!(recorder.len(), 10);
+        let values: Vec<u64> = recorder.iter().map(|r| r.value).collect();
+        assert_eq!(values, (0..10).collect::<Vec<u64>>());
+
+        let total = FLIGHT_RECORDER_SIZE as u64 + 10;
+        for value in 10..total {
+            recorder.record("event", value);
+        }
+        assert_eq!(recorder.len(), FLIGHT_RECORDER_SIZE);
+        let values: Vec<u64> = recorder.iter().map(|r| r.value).collect();
+        assert_eq!(values, (10..total).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn flight_recorder_recent() {
+        let mut recorder = FlightRecorder::new();
+        assert!(recorder.recent(2).is_empty());
+
+        for value in 0..3 {
+            recorder.record("event", value);
+        }
+        let recent = recorder.recent(2);
+        assert_eq!(recent.len(), 2);
+        assert!(recent[0].starts_with("event=1 ("), "{recent:?}");
+        assert!(recent[1].starts_with("event=2 ("), "{recent:?}");
+        assert_eq!(recorder.recent(10).len(), 3);
+    }
+}
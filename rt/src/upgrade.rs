@@ -0,0 +1,510 @@
+//! Zero-downtime binary upgrades.
+//!
+//! This module implements the classic upgrade flow of network daemons: the
+//! running (old) process passes its listening sockets to a new process, e.g.
+//! running an upgraded binary, after which the old process stops accepting new
+//! connections and drains its existing connections. As the listening sockets
+//! are never closed no connections are dropped.
+//!
+//! The old process collects the file descriptors to pass in a [`Handover`],
+//! along with some optional (serialised) state. [`Handover::start`] spawns the
+//! new process and sends it the file descriptors over a Unix socket. This
+//! returns an [`Upgrade`], which can be used to wait until the new process is
+//! ready to take over using [`Upgrade::ready`]. After that the old process
+//! should stop accepting new connections, finish the work in progress and
+//! stop.
+//!
+//! The new process calls [`inherit`] when starting, which returns the
+//! [`Inherited`] file descriptors and state, or `None` if the process was not
+//! started by an upgrade (e.g. the first start). Once the process is ready to
+//! take over, e.g. once it's accepting connections on the inherited listeners,
+//! it calls [`Inherited::ready`] to notify the old process.
+//!
+//! # Notes
+//!
+//! The new process is a child process of the old process. When the old process
+//! stops the new process is re-parented, e.g. to init or a [subreaper]. When
+//! running under systemd the service manager must be informed of the new main
+//! process, see `MAINPID=` in [`sd_notify(3)`].
+//!
+//! [subreaper]: https://man7.org/linux/man-pages/man2/prctl.2.html
+//! [`sd_notify(3)`]: https://www.freedesktop.org/software/systemd/man/sd_notify.html
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # #![feature(never_type)]
+//! use std::io;
+//! use std::net::TcpListener;
+//!
+//! use heph::actor;
+//! use heph_rt::process::Command;
+//! use heph_rt::upgrade::{self, Handover};
+//! use heph_rt::{self as rt};
+//!
+//! // At start-up, inherit the listener from the old process or create a new
+//! // one.
+//! let (listener, inherited) = match upgrade::inherit()? {
+//!     Some(mut inherited) => {
+//!         let fd = inherited.take("http").expect("missing HTTP listener");
+//!         (TcpListener::from(fd), Some(inherited))
+//!     }
+//!     None => (TcpListener::bind("127.0.0.1:8080")?, None),
+//! };
+//! // Start accepting connections on `listener`, e.g. using
+//! // `heph_rt::net::TcpListener::from_std`...
+//! # drop(listener);
+//!
+//! // Notify the old process that we're ready to take over.
+//! if let Some(inherited) = inherited {
+//!     inherited.ready()?;
+//! }
+//!
+//! // Later on, e.g. when receiving a signal, an actor can start the upgrade.
+//! async fn upgrade_actor<RT>(ctx: actor::Context<!, RT>, listener: TcpListener) -> io::Result<()>
+//! where
+//!     RT: rt::Access,
+//! {
+//!     let mut handover = Handover::new();
+//!     handover.add("http", &listener)?;
+//!     let mut command = Command::new(std::env::current_exe()?);
+//!     let mut upgrade = handover.start(ctx.runtime_ref(), &mut command)?;
+//!     upgrade.ready().await?;
+//!     // The new process is accepting connections, stop accepting new
+//!     // connections and drain the existing ones.
+//!     Ok(())
+//! }
+//! # _ = upgrade_actor::<rt::ThreadLocal>;
+//! # Ok::<(), io::Error>(())
+//! ```
+
+use std::mem::{self, size_of};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::process::CommandExt;
+use std::{env, io, ptr};
+
+use socket2::{Domain, Type};
+
+use crate::access::Access;
+use crate::net::UnixStream;
+use crate::process::{Child, Command};
+
+/// Environment variable used to pass the socket to the new process.
+const FD_ENV_VAR: &str = "HEPH_UPGRADE_FD";
+/// Maximum number of file descriptors that can be passed, `SCM_MAX_FD`.
+const MAX_FDS: usize = 253;
+/// Maximum size of the names and state passed to the new process.
+const MAX_MESSAGE_SIZE: usize = 1 << 16; // 64kb.
+/// Byte send by the new process once it's ready.
+const READY: u8 = 1;
+
+/// File descriptor with its name.
+type NamedFd = (String, OwnedFd);
+
+/// File descriptors and state to pass to the new process.
+///
+/// See the [module documentation] for more information.
+///
+/// [module documentation]: crate::upgrade
+#[derive(Debug, Default)]
+pub struct Handover {
+    fds: Vec<NamedFd>,
+    state: Vec<u8>,
+}
+
+impl Handover {
+    /// Create a new, empty, `Handover`.
+    pub const fn new() -> Handover {
+        Handover {
+            fds: Vec::new(),
+            state: Vec::new(),
+        }
+    }
+
+    /// Add the file descriptor `fd`, e.g. a listening socket, to pass to the
+    /// new process under `name`.
+    ///
+    /// This duplicates the file descriptor, `fd` can still be used until the
+    /// new process is [ready].
+    ///
+    /// [ready]: Upgrade::ready
+    pub fn add<N, F>(&mut self, name: N, fd: F) -> io::Result<&mut Handover>
+    where
+        N: Into<String>,
+        F: AsFd,
+    {
+        let fd = fd.as_fd().try_clone_to_owned()?;
+        self.fds.push((name.into(), fd));
+        Ok(self)
+    }
+
+    /// Set the `state` to pass to the new process, e.g. some serialised
+    /// configuration.
+    ///
+    /// The new process can retrieve it using [`Inherited::state`].
+    pub fn set_state(&mut self, state: Vec<u8>) -> &mut Handover {
+        self.state = state;
+        self
+    }
+
+    /// Spawn the new process using `command` and pass it the file descriptors
+    /// and state.
+    ///
+    /// This adds an environment variable to `command` so that the new process
+    /// can find the file descriptors using [`inherit`].
+    pub fn start<RT>(self, rt: &RT, command: &mut Command) -> io::Result<Upgrade>
+    where
+        RT: Access,
+    {
+        let (socket, peer) = socket2::Socket::pair(Domain::UNIX, Type::SEQPACKET.cloexec(), None)?;
+        let peer_fd = peer.as_raw_fd();
+        let command = command.env(FD_ENV_VAR, peer_fd.to_string());
+        // Allow the new process to inherit the socket.
+        let inherit_socket = move || syscall!(fcntl(peer_fd, libc::F_SETFD, 0)).map(|_| ());
+        // SAFETY: `fcntl(2)` is async-signal-safe.
+        _ = unsafe { command.inner_mut().pre_exec(inherit_socket) };
+        let child = command.spawn(rt)?;
+        drop(peer);
+
+        send_handover(socket.as_fd(), &self.fds, &self.state)?;
+        let stream = std::os::unix::net::UnixStream::from(OwnedFd::from(socket));
+        Ok(Upgrade {
+            child,
+            stream: UnixStream::from_std(rt, stream),
+        })
+    }
+}
+
+/// Upgrade in progress, created by [`Handover::start`].
+#[derive(Debug)]
+pub struct Upgrade {
+    child: Child,
+    stream: UnixStream,
+}
+
+impl Upgrade {
+    /// Wait until the new process is ready to take over, i.e. when it calls
+    /// [`Inherited::ready`].
+    ///
+    /// Returns an error if the new process stopped before it was ready.
+    pub async fn ready(&mut self) -> io::Result<()> {
+        let buf = self.stream.recv(Vec::with_capacity(1)).await?;
+        match buf.first() {
+            Some(&READY) => Ok(()),
+            Some(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid message from new process",
+            )),
+            None => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "new process stopped before it was ready",
+            )),
+        }
+    }
+
+    /// Returns the new (child) process.
+    pub fn child(&mut self) -> &mut Child {
+        &mut self.child
+    }
+
+    /// Returns the new (child) process.
+    pub fn into_child(self) -> Child {
+        self.child
+    }
+}
+
+/// Inherit the file descriptors and state from the old process.
+///
+/// Returns `None` if the process was not started by an [`Upgrade`].
+///
+/// # Notes
+///
+/// This removes the environment variable set by [`Handover::start`], which
+/// means this should be called before starting any threads (i.e. before
+/// setting up the runtime). Calling it again will return `None`.
+pub fn inherit() -> io::Result<Option<Inherited>> {
+    let Some(fd) = env::var_os(FD_ENV_VAR) else {
+        return Ok(None);
+    };
+    env::remove_var(FD_ENV_VAR);
+    let fd: RawFd = match fd.to_str().and_then(|fd| fd.parse().ok()) {
+        Some(fd) if fd >= 0 => fd,
+        _ => {
+            let msg = format!("invalid {FD_ENV_VAR} environment variable");
+            return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+        }
+    };
+    // SAFETY: the old process passed us the socket, which we take ownership
+    // of.
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+    // Don't leak the socket to our own child processes.
+    _ = syscall!(fcntl(socket.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC))?;
+
+    let (fds, state) = recv_handover(socket.as_fd())?;
+    Ok(Some(Inherited { socket, fds, state }))
+}
+
+/// File descriptors and state inherited from the old process.
+///
+/// Created by [`inherit`].
+#[derive(Debug)]
+pub struct Inherited {
+    socket: OwnedFd,
+    fds: Vec<NamedFd>,
+    state: Vec<u8>,
+}
+
+impl Inherited {
+    /// Take the file descriptor added under `name`, see [`Handover::add`].
+    ///
+    /// Returns `None` if no file descriptor was added under `name`, or if it
+    /// was already taken.
+    pub fn take(&mut self, name: &str) -> Option<OwnedFd> {
+        let idx = self.fds.iter().position(|(n, _)| n == name)?;
+        Some(self.fds.swap_remove(idx).1)
+    }
+
+    /// Returns the names of the file descriptors not yet taken.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.fds.iter().map(|(name, _)| &**name)
+    }
+
+    /// Returns the state set by the old process, see [`Handover::set_state`].
+    pub fn state(&self) -> &[u8] {
+        &self.state
+    }
+
+    /// Notify the old process that this process is ready to take over.
+    ///
+    /// File descriptors that are not taken are closed.
+    pub fn ready(self) -> io::Result<()> {
+        let buf = [READY];
+        let n = syscall!(send(
+            self.socket.as_raw_fd(),
+            buf.as_ptr().cast(),
+            buf.len(),
+            0
+        ))?;
+        if n == 1 {
+            Ok(())
+        } else {
+            Err(io::ErrorKind::WriteZero.into())
+        }
+    }
+}
+
+/// Send the file descriptors `fds` and `state` over `socket`.
+///
+/// The message has the following layout (all integers are little endian):
+///  * number of file descriptors (u32),
+///  * for each file descriptor: the length of its name (u16) and the name,
+///  * the state.
+///
+/// The file descriptors themselves are passed using `SCM_RIGHTS`.
+fn send_handover(socket: BorrowedFd<'_>, fds: &[NamedFd], state: &[u8]) -> io::Result<()> {
+    if fds.len() > MAX_FDS {
+        let msg = format!("can't pass more than {MAX_FDS} file descriptors");
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+    }
+
+    let mut buf = Vec::with_capacity(4 + state.len());
+    buf.extend_from_slice(&(fds.len() as u32).to_le_bytes());
+    for (name, _) in fds {
+        let Ok(len) = u16::try_from(name.len()) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "name too long"));
+        };
+        buf.extend_from_slice(&len.to_le_bytes());
+        buf.extend_from_slice(name.as_bytes());
+    }
+    buf.extend_from_slice(state);
+    if buf.len() > MAX_MESSAGE_SIZE {
+        let msg = format!("names and state too large, max is {MAX_MESSAGE_SIZE} bytes");
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+    }
+
+    let mut iovec = libc::iovec {
+        iov_base: buf.as_mut_ptr().cast(),
+        iov_len: buf.len(),
+    };
+    // NOTE: using `u64` to ensure the control message is properly aligned.
+    let mut control = [0u64; control_size() / size_of::<u64>()];
+    // SAFETY: all zero is valid for `msghdr`.
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iovec;
+    msg.msg_iovlen = 1;
+    if !fds.is_empty() {
+        let fds_size = (fds.len() * size_of::<RawFd>()) as u32;
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen = unsafe { libc::CMSG_SPACE(fds_size) } as usize;
+        // SAFETY: `control` is large enough for `MAX_FDS` file descriptors.
+        unsafe {
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(fds_size) as usize;
+            let data = libc::CMSG_DATA(cmsg).cast::<RawFd>();
+            for (i, (_, fd)) in fds.iter().enumerate() {
+                ptr::write_unaligned(data.add(i), fd.as_raw_fd());
+            }
+        }
+    }
+
+    let n = syscall!(sendmsg(socket.as_raw_fd(), &msg, 0))?;
+    if n as usize == buf.len() {
+        Ok(())
+    } else {
+        Err(io::ErrorKind::WriteZero.into())
+    }
+}
+
+/// Receive the file descriptors and state send by [`send_handover`].
+fn recv_handover(socket: BorrowedFd<'_>) -> io::Result<(Vec<NamedFd>, Vec<u8>)> {
+    let mut buf = vec![0; MAX_MESSAGE_SIZE];
+    let mut iovec = libc::iovec {
+        iov_base: buf.as_mut_ptr().cast(),
+        iov_len: buf.len(),
+    };
+    let mut control = [0u64; control_size() / size_of::<u64>()];
+    // SAFETY: all zero is valid for `msghdr`.
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iovec;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = control_size();
+
+    let n = syscall!(recvmsg(
+        socket.as_raw_fd(),
+        &mut msg,
+        libc::MSG_CMSG_CLOEXEC
+    ))? as usize;
+
+    // Take ownership of the file descriptors first, to ensure they're closed
+    // in case of an error below.
+    let mut raw_fds = Vec::new();
+    // SAFETY: the kernel filled the control messages for us.
+    let mut cmsg = unsafe { libc::CMSG_FIRSTHDR(&msg) };
+    while !cmsg.is_null() {
+        // SAFETY: checked that the pointer is not null above.
+        let (level, kind, len) =
+            unsafe { ((*cmsg).cmsg_level, (*cmsg).cmsg_type, (*cmsg).cmsg_len) };
+        if level == libc::SOL_SOCKET && kind == libc::SCM_RIGHTS {
+            let n = (len - unsafe { libc::CMSG_LEN(0) } as usize) / size_of::<RawFd>();
+            // SAFETY: per `unix(7)` the data is an array of file descriptors,
+            // which we take ownership of.
+            let data = unsafe { libc::CMSG_DATA(cmsg).cast::<RawFd>() };
+            for i in 0..n {
+                raw_fds.push(unsafe { OwnedFd::from_raw_fd(data.add(i).read_unaligned()) });
+            }
+        }
+        // SAFETY: `cmsg` is a valid control message of `msg`.
+        cmsg = unsafe { libc::CMSG_NXTHDR(&msg, cmsg) };
+    }
+
+    if msg.msg_flags & (libc::MSG_TRUNC | libc::MSG_CTRUNC) != 0 {
+        return Err(invalid_handover());
+    } else if n == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "old process closed the connection",
+        ));
+    }
+    buf.truncate(n);
+
+    let mut buf = &*buf;
+    let count = take_bytes::<4>(&mut buf).map(u32::from_le_bytes)?;
+    if count as usize != raw_fds.len() {
+        return Err(invalid_handover());
+    }
+    let mut fds = Vec::with_capacity(raw_fds.len());
+    for fd in raw_fds {
+        let len = take_bytes::<2>(&mut buf).map(u16::from_le_bytes)?;
+        let Some((name, rest)) = buf.split_at_checked(usize::from(len)) else {
+            return Err(invalid_handover());
+        };
+        let Ok(name) = String::from_utf8(name.to_vec()) else {
+            return Err(invalid_handover());
+        };
+        fds.push((name, fd));
+        buf = rest;
+    }
+    Ok((fds, buf.to_vec()))
+}
+
+/// Take the first `N` bytes from `buf`.
+fn take_bytes<const N: usize>(buf: &mut &[u8]) -> io::Result<[u8; N]> {
+    let Some((bytes, rest)) = buf.split_first_chunk::<N>() else {
+        return Err(invalid_handover());
+    };
+    *buf = rest;
+    Ok(*bytes)
+}
+
+fn invalid_handover() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid handover message")
+}
+
+/// Size of the control message buffer, large enough for `MAX_FDS` file
+/// descriptors.
+const fn control_size() -> usize {
+    // NOTE: `CMSG_SPACE` is not a `const fn`, so we calculate it ourselves:
+    // the (aligned) header followed by the (aligned) data.
+    let header = size_of::<libc::cmsghdr>().next_multiple_of(size_of::<usize>());
+    let data = (MAX_FDS * size_of::<RawFd>()).next_multiple_of(size_of::<usize>());
+    header + data
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::os::fd::AsFd;
+
+    use socket2::{Domain, Type};
+
+    use super::{recv_handover, send_handover};
+
+    #[test]
+    fn handover() {
+        let (socket, peer) =
+            socket2::Socket::pair(Domain::UNIX, Type::SEQPACKET.cloexec(), None).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let fds = vec![
+            (
+                "http".to_owned(),
+                listener.as_fd().try_clone_to_owned().unwrap(),
+            ),
+            (
+                "other".to_owned(),
+                listener.as_fd().try_clone_to_owned().unwrap(),
+            ),
+        ];
+
+        send_handover(socket.as_fd(), &fds, b"state").unwrap();
+        let (mut fds, state) = recv_handover(peer.as_fd()).unwrap();
+        assert_eq!(state, b"state");
+        assert_eq!(fds.len(), 2);
+        assert_eq!(fds[0].0, "http");
+        assert_eq!(fds[1].0, "other");
+        let listener = TcpListener::from(fds.remove(0).1);
+        assert_eq!(listener.local_addr().unwrap(), address);
+    }
+
+    #[test]
+    fn handover_no_fds() {
+        let (socket, peer) =
+            socket2::Socket::pair(Domain::UNIX, Type::SEQPACKET.cloexec(), None).unwrap();
+        send_handover(socket.as_fd(), &[], &[]).unwrap();
+        let (fds, state) = recv_handover(peer.as_fd()).unwrap();
+        assert!(fds.is_empty());
+        assert!(state.is_empty());
+    }
+
+    #[test]
+    fn handover_closed() {
+        let (socket, peer) =
+            socket2::Socket::pair(Domain::UNIX, Type::SEQPACKET.cloexec(), None).unwrap();
+        drop(socket);
+        let err = recv_handover(peer.as_fd()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -249,6 +249,27 @@ pub(crate) fn create_no_ring_waker(ctx: &mut task::Context<'_>) -> Option<task::
     }
 }
 
+/// Wake the thread-safe process behind `waker` without waking a worker thread.
+///
+/// Returns `true` if `waker` is a [`task::Waker`] for a thread-safe process, in
+/// which case the caller is responsible for waking a worker thread to run the
+/// process. Otherwise `waker` is woken as normal and this returns `false`.
+pub(crate) fn wake_no_ring(waker: task::Waker) -> bool {
+    if *waker.vtable() == shared::WAKER_VTABLE {
+        // SAFETY: checked that the `data` is created for the shared waker
+        // implementation above. The shared waker data is `Copy`, so we don't
+        // have to drop `waker`.
+        unsafe {
+            task::Waker::from_raw(shared::waker_vtable_no_ring::clone_wake_data(waker.data()))
+                .wake();
+        }
+        true
+    } else {
+        waker.wake();
+        false
+    }
+}
+
 // The two waker implementations below share the same `data`, see `WakerData`.
 // The `WAKER_VTABLE` implementation schedules the process and wakes the worker
 // thread, while `WAKER_VTABLE_NO_RING` only schedules the process and does
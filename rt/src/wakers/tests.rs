@@ -2,7 +2,7 @@ use std::task;
 
 use crate::process::{FutureProcess, ProcessId};
 use crate::spawn::options::Priority;
-use crate::wakers::{self, create_no_ring_waker};
+use crate::wakers::{self, create_no_ring_waker, wake_no_ring};
 
 #[test]
 fn create_no_ring_waker_local() {
@@ -49,6 +49,11 @@ fn create_no_ring_waker_other() {
     assert!(create_no_ring_waker(&mut ctx).is_none());
 }
 
+#[test]
+fn wake_no_ring_other() {
+    assert!(!wake_no_ring(task::Waker::noop().clone()));
+}
+
 mod local {
     use std::thread;
 
@@ -113,7 +118,7 @@ mod shared {
     use std::sync::{Arc, Weak};
     use std::task::{self, Poll};
     use std::thread::{self, sleep};
-    use std::time::Duration;
+    use std::time::{Duration, Instant};
 
     use crate::process::{FutureProcess, Process, ProcessId};
     use crate::shared::RuntimeInternals;
@@ -170,6 +175,30 @@ mod shared {
         assert!(!shared_internals.has_ready_process());
     }
 
+    #[test]
+    fn expire_timers_batches_wake_ups() {
+        let shared_internals = new_internals();
+
+        let pid = shared_internals.add_new_process(Priority::NORMAL, FutureProcess(TestProcess));
+        let process = shared_internals.remove_process().unwrap();
+        shared_internals.add_back_process(process);
+        assert!(!shared_internals.has_ready_process());
+
+        let waker = shared_internals.new_task_waker(pid);
+        let deadline = Instant::now();
+        _ = shared_internals.add_timer(deadline, waker);
+        assert_eq!(shared_internals.metrics().timers_wakeups_avoided, 0);
+
+        // Expiring the timer should schedule the process, without waking a
+        // worker thread.
+        assert_eq!(
+            shared_internals.expire_timers(deadline + Duration::from_millis(1)),
+            1
+        );
+        assert!(shared_internals.has_ready_process());
+        assert_eq!(shared_internals.metrics().timers_wakeups_avoided, 1);
+    }
+
     #[test]
     fn cloned_waker() {
         let shared_internals = new_internals();
@@ -17,6 +17,8 @@
 
 use std::cell::RefMut;
 use std::num::NonZeroUsize;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
 use std::rc::Rc;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -25,15 +27,21 @@ use std::{fmt, io, task, thread};
 use crossbeam_channel::Receiver;
 use heph::actor::{self, actor_fn};
 use heph::supervisor::NoSupervisor;
-use log::{debug, trace, warn};
+use log::{debug, error, trace, warn};
 
+#[cfg(feature = "console")]
+use crate::console::{WakeReason, WorkerState};
 use crate::error::StringError;
 use crate::local::RuntimeInternals;
-use crate::process::ProcessId;
+use crate::process::{Process, ProcessData, ProcessId, RunStats};
+use crate::ring::RingMetrics;
 use crate::setup::set_cpu_affinity;
-use crate::spawn::options::ActorOptions;
+use crate::spawn::options::{ActorOptions, Priority};
 use crate::wakers::Wakers;
-use crate::{self as rt, shared, trace, RuntimeRef, Signal, ThreadLocal};
+use crate::{self as rt, panic_message, shared, trace, RuntimeRef, Signal, ThreadLocal};
+
+/// Number of flight recorder events included in a [`PanicReport`].
+const PANIC_REPORT_EVENTS: usize = 16;
 
 /// Number of system actors (spawned in the local scheduler).
 pub(crate) const SYSTEM_ACTORS: usize = 1;
@@ -52,15 +60,23 @@ const RUN_POLL_RATIO: usize = 32;
 // TODO: make this configurable.
 const MAX_EVENT_LOOP_DURATION: Duration = Duration::from_millis(5);
 
+/// Minimum time between tracing the io_uring metrics, see
+/// [`Worker::trace_ring_metrics`].
+const RING_METRICS_TRACE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default number of entries in a worker's io_uring submission queue.
+pub(crate) const DEFAULT_RING_ENTRIES: u32 = 128;
+
 /// Setup a new worker thread.
 ///
 /// Use [`WorkerSetup::start`] to spawn the worker thread.
 pub(crate) fn setup(
     id: NonZeroUsize,
     auto_cpu_affinity: bool,
+    ring_entries: u32,
     coordinator_sq: &a10::SubmissionQueue,
 ) -> io::Result<(WorkerSetup, a10::SubmissionQueue)> {
-    let config = a10::Ring::config(128)
+    let config = a10::Ring::config(ring_entries)
         .disable() // Enabled on the worker thread.
         .single_issuer()
         .with_kernel_thread(true)
@@ -78,7 +94,7 @@ pub(crate) fn setup(
 /// Test version of [`setup`].
 #[cfg(any(test, feature = "test"))]
 pub(crate) fn setup_test() -> io::Result<(WorkerSetup, a10::SubmissionQueue)> {
-    let ring = a10::Ring::config(128)
+    let ring = a10::Ring::config(DEFAULT_RING_ENTRIES)
         .disable() // Enabled on the worker thread.
         .single_issuer()
         .with_kernel_thread(true)
@@ -223,6 +239,8 @@ pub(crate) struct Worker {
     /// Receiving side of the channel for waker events, see the
     /// [`rt::local::waker`] module for the implementation.
     waker_events: Receiver<ProcessId>,
+    /// Last time the io_uring metrics were traced.
+    ring_metrics_traced: Instant,
 }
 
 impl Worker {
@@ -265,6 +283,7 @@ impl Worker {
         let mut worker = Worker {
             internals,
             waker_events: setup.waker_events,
+            ring_metrics_traced: Instant::now(),
         };
 
         trace::finish_rt(
@@ -280,6 +299,9 @@ impl Worker {
     pub(crate) fn run(mut self) -> Result<(), Error> {
         debug!(worker_id = self.internals.id.get(); "starting worker");
         loop {
+            let timing = trace::start(&*self.internals.trace_log.borrow());
+            #[cfg(feature = "console")]
+            self.internals.console.set_state(WorkerState::Running);
             // We first run the processes and only poll after to ensure that we
             // return if there are no processes to run.
             let mut n = 0;
@@ -293,6 +315,7 @@ impl Worker {
                     None => break,
                 }
             }
+            let local_run = n;
             while n < RUN_POLL_RATIO && elapsed < MAX_EVENT_LOOP_DURATION {
                 match self.run_shared_process() {
                     Some(process_elapsed) => {
@@ -302,6 +325,20 @@ impl Worker {
                     None => break,
                 }
             }
+            let budget_exhausted = n >= RUN_POLL_RATIO || elapsed >= MAX_EVENT_LOOP_DURATION;
+            if budget_exhausted {
+                // Any remaining ready processes have to wait for the next
+                // iteration, after we've polled for events.
+                self.internals
+                    .record_event("Exhausted event loop budget", n as u64);
+                let elapsed_ns = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+                trace::finish_rt(
+                    self.internals.trace_log.borrow_mut().as_mut(),
+                    timing.clone(),
+                    "Exhausted event loop budget",
+                    &[("processes run", &n), ("elapsed_ns", &elapsed_ns)],
+                );
+            }
 
             if let Some(err) = self.internals.take_err() {
                 return Err(err);
@@ -312,7 +349,27 @@ impl Worker {
                 return Ok(());
             }
 
-            self.schedule_processes()?;
+            let (polls, events) = self.schedule_processes()?;
+
+            let local_queue = self.internals.scheduler.borrow().ready();
+            let shared_queue = self.internals.shared.ready_processes();
+            #[cfg(feature = "console")]
+            self.internals
+                .console
+                .record_iteration(budget_exhausted, local_queue, shared_queue);
+            trace::finish_rt(
+                self.internals.trace_log.borrow_mut().as_mut(),
+                timing,
+                "Running event loop iteration",
+                &[
+                    ("local queue length", &local_queue),
+                    ("shared queue length", &shared_queue),
+                    ("local processes run", &local_run),
+                    ("shared processes run", &(n - local_run)),
+                    ("polls", &polls),
+                    ("events", &events),
+                ],
+            );
         }
     }
 
@@ -328,10 +385,20 @@ impl Worker {
                 let pid = process.as_ref().id();
                 let name = process.as_ref().name();
                 debug!(worker_id = self.internals.id.get(), pid = pid.0, name = name; "running local process");
+                self.internals
+                    .record_event("Running thread-local process", pid.0 as u64);
                 // TODO: reuse wakers, maybe by storing them in the processes?
                 let waker = self.internals.wakers.borrow_mut().new_task_waker(pid);
                 let mut ctx = task::Context::from_waker(&waker);
-                let result = process.as_mut().run(&mut ctx);
+                let result = self.run_process(process.as_mut(), &mut ctx);
+                let latency = self.record_latency(process.priority(), result.latency);
+                #[cfg(feature = "console")]
+                self.internals.console.record_run(
+                    pid,
+                    name,
+                    result.elapsed,
+                    result.result.is_ready(),
+                );
                 match result.result {
                     task::Poll::Ready(()) => {
                         self.internals.scheduler.borrow_mut().complete(process);
@@ -347,7 +414,7 @@ impl Worker {
                     self.internals.trace_log.borrow_mut().as_mut(),
                     timing,
                     "Running thread-local process",
-                    &[("id", &pid.0), ("name", &name)],
+                    &[("id", &pid.0), ("name", &name), ("latency_ns", &latency)],
                 );
                 Some(result.elapsed)
             }
@@ -367,9 +434,19 @@ impl Worker {
                 let pid = process.as_ref().id();
                 let name = process.as_ref().name();
                 debug!(worker_id = self.internals.id.get(), pid = pid.0, name = name; "running shared process");
+                self.internals
+                    .record_event("Running thread-safe process", pid.0 as u64);
                 let waker = self.internals.shared.new_task_waker(pid);
                 let mut ctx = task::Context::from_waker(&waker);
-                let result = process.as_mut().run(&mut ctx);
+                let result = self.run_process(process.as_mut(), &mut ctx);
+                let latency = self.record_latency(process.priority(), result.latency);
+                #[cfg(feature = "console")]
+                self.internals.console.record_run(
+                    pid,
+                    name,
+                    result.elapsed,
+                    result.result.is_ready(),
+                );
                 match result.result {
                     task::Poll::Ready(()) => {
                         self.internals.shared.complete(process);
@@ -382,7 +459,7 @@ impl Worker {
                     self.internals.trace_log.borrow_mut().as_mut(),
                     timing,
                     "Running thread-safe process",
-                    &[("id", &pid.0), ("name", &name)],
+                    &[("id", &pid.0), ("name", &name), ("latency_ns", &latency)],
                 );
                 Some(result.elapsed)
             }
@@ -390,6 +467,56 @@ impl Worker {
         }
     }
 
+    /// Run `process`.
+    ///
+    /// If the process panics this logs and resumes the panic with a
+    /// [`PanicReport`] as payload, which includes the context of the process.
+    /// The report is passed to the coordinator when it joins the thread.
+    fn run_process<P>(
+        &self,
+        mut process: Pin<&mut ProcessData<P>>,
+        ctx: &mut task::Context<'_>,
+    ) -> RunStats
+    where
+        P: Process + ?Sized,
+    {
+        let pid = process.as_ref().id();
+        let name = process.name();
+        match panic::catch_unwind(AssertUnwindSafe(|| process.as_mut().run(ctx))) {
+            Ok(result) => result,
+            Err(panic) => {
+                let report = PanicReport {
+                    message: panic_message(&*panic).to_owned(),
+                    worker_id: self.internals.id.get(),
+                    process: Some((pid, name)),
+                    inbox_len: process.inbox_len(),
+                    events: self.internals.recent_events(PANIC_REPORT_EVENTS),
+                };
+                error!(
+                    worker_id = report.worker_id, pid = pid.0, actor = name,
+                    inbox_len:? = report.inbox_len;
+                    "worker thread panicked while running process: {}", report.message,
+                );
+                panic::resume_unwind(Box::new(report))
+            }
+        }
+    }
+
+    /// Record the scheduling `latency` of a process with `priority`.
+    ///
+    /// Returns the latency in nanoseconds, for use in the trace event, or zero
+    /// if the latency is unknown.
+    fn record_latency(&self, priority: Priority, latency: Option<Duration>) -> u64 {
+        let Some(latency) = latency else {
+            return 0;
+        };
+        self.internals
+            .scheduling_latency
+            .borrow_mut()
+            .record(priority, latency);
+        u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX)
+    }
+
     /// Returns `true` if there are processes in either the local or shared
     /// schedulers.
     fn has_user_process(&self) -> bool {
@@ -399,16 +526,22 @@ impl Worker {
     /// Schedule processes.
     ///
     /// This polls all event subsystems and schedules processes based on them.
-    fn schedule_processes(&mut self) -> Result<(), Error> {
+    /// Returns the number of polls performed (see [`Worker::poll_os`]) and the
+    /// number of events received, i.e. the number of processes scheduled.
+    fn schedule_processes(&mut self) -> Result<(usize, usize), Error> {
         trace!(worker_id = self.internals.id.get(); "polling event sources to schedule processes");
         let timing = trace::start(&*self.internals.trace_log.borrow());
+        #[cfg(feature = "console")]
+        self.internals.console.set_state(WorkerState::Scheduling);
 
         // Schedule local and shared processes based on various event sources.
-        self.poll_os().map_err(Error::Polling)?;
+        let polls = self.poll_os().map_err(Error::Polling)?;
         let mut local_amount = self.schedule_from_waker();
         let now = Instant::now();
         local_amount += self.schedule_from_local_timers(now);
         let shared_amount = self.schedule_from_shared_timers(now);
+        self.internals
+            .record_event("Scheduled processes", (local_amount + shared_amount) as u64);
 
         trace::finish_rt(
             self.internals.trace_log.borrow_mut().as_mut(),
@@ -425,7 +558,7 @@ impl Worker {
         // processes (that we can't directly run).
         self.wake_workers(local_amount, shared_amount);
 
-        Ok(())
+        Ok((polls, local_amount + shared_amount))
     }
 
     /// Schedule processes based on user space waker events, e.g. used by the
@@ -441,6 +574,10 @@ impl Worker {
             scheduler.mark_ready(pid);
             amount += 1;
         }
+        #[cfg(feature = "console")]
+        self.internals
+            .console
+            .record_wakes(WakeReason::Waker, amount);
 
         trace::finish_rt(
             self.internals.trace_log.borrow_mut().as_mut(),
@@ -456,6 +593,10 @@ impl Worker {
         trace!(worker_id = self.internals.id.get(); "polling local timers");
         let timing = trace::start(&*self.internals.trace_log.borrow());
         let amount = self.internals.timers.borrow_mut().expire_timers(now);
+        #[cfg(feature = "console")]
+        self.internals
+            .console
+            .record_wakes(WakeReason::LocalTimer, amount);
         trace::finish_rt(
             self.internals.trace_log.borrow_mut().as_mut(),
             timing,
@@ -470,6 +611,10 @@ impl Worker {
         trace!(worker_id = self.internals.id.get(); "polling shared timers");
         let timing = trace::start(&*self.internals.trace_log.borrow());
         let amount = self.internals.shared.expire_timers(now);
+        #[cfg(feature = "console")]
+        self.internals
+            .console
+            .record_wakes(WakeReason::SharedTimer, amount);
         trace::finish_rt(
             self.internals.trace_log.borrow_mut().as_mut(),
             timing,
@@ -506,32 +651,76 @@ impl Worker {
 
     /// Poll for OS events, filling `self.events`.
     ///
-    /// Returns a boolean indicating if the shared timers should be checked.
-    fn poll_os(&mut self) -> io::Result<()> {
+    /// Returns the number of io_uring rings polled, including the shared ring
+    /// (which is skipped if another worker is polling it).
+    fn poll_os(&mut self) -> io::Result<usize> {
         let timing = trace::start(&*self.internals.trace_log.borrow());
 
         // First process any shared completions, this influences
         // `determine_timeout` below as we might schedule shared processes etc.
         // Note that the call never blocks.
         trace!(worker_id = self.internals.id.get(); "polling shared ring");
-        self.internals.shared.try_poll_ring()?;
+        let mut polls = usize::from(self.internals.shared.try_poll_ring()?);
 
         let timeout = self.determine_timeout();
         trace!(worker_id = self.internals.id.get(), timeout:? = timeout; "polling for OS events");
+        self.internals.record_event(
+            "Polling for OS events",
+            timeout.map_or(u64::MAX, |t| t.as_millis() as u64),
+        );
+        #[cfg(feature = "console")]
+        self.internals.console.set_state(WorkerState::Polling);
         self.internals.ring.borrow_mut().poll(timeout)?;
+        #[cfg(feature = "console")]
+        self.internals.console.set_state(WorkerState::Scheduling);
+        polls += 1;
 
         // Since we could have been polling our own ring for a long time we poll
         // the shared ring again.
         trace!(worker_id = self.internals.id.get(); "polling shared ring");
-        self.internals.shared.try_poll_ring()?;
+        polls += usize::from(self.internals.shared.try_poll_ring()?);
 
         trace::finish_rt(
             self.internals.trace_log.borrow_mut().as_mut(),
             timing,
             "Polling for OS events",
-            &[],
+            &[("polls", &polls)],
+        );
+        self.trace_ring_metrics();
+        Ok(polls)
+    }
+
+    /// Trace the metrics of the io_uring ring, if tracing is enabled and
+    /// [`RING_METRICS_TRACE_INTERVAL`] passed since the last time.
+    fn trace_ring_metrics(&mut self) {
+        if self.internals.trace_log.borrow().is_none()
+            || self.ring_metrics_traced.elapsed() < RING_METRICS_TRACE_INTERVAL
+        {
+            return;
+        }
+        self.ring_metrics_traced = Instant::now();
+
+        let timing = trace::start(&*self.internals.trace_log.borrow());
+
+        let ring = match RingMetrics::read(&self.internals.ring.borrow()) {
+            Ok(ring) => ring,
+            Err(err) => {
+                debug!(worker_id = self.internals.id.get(); "failed to read io_uring metrics: {err}");
+                return;
+            }
+        };
+        trace::finish_rt(
+            self.internals.trace_log.borrow_mut().as_mut(),
+            timing,
+            "io_uring metrics",
+            &[
+                ("sq_pending", &ring.sq_pending),
+                ("cq_pending", &ring.cq_pending),
+                ("submissions", &ring.submissions),
+                ("completions", &ring.completions),
+                ("cq_overflows", &ring.cq_overflows),
+            ],
         );
-        Ok(())
     }
 
     /// Determine the timeout to be used in polling.
@@ -574,11 +763,49 @@ impl Worker {
 
 impl Drop for Worker {
     fn drop(&mut self) {
+        if thread::panicking() {
+            self.internals.dump_flight_recorder();
+        }
         // Wake the coordinator forcing it check if the workers are still alive.
         self.internals.shared.wake_coordinator();
     }
 }
 
+/// Report of a panic in a worker thread.
+///
+/// This is used as panic payload, so that the coordinator can include it in
+/// the [`rt::Error`] it returns.
+#[derive(Debug)]
+pub(crate) struct PanicReport {
+    /// The panic message.
+    pub(crate) message: String,
+    /// Id of the worker thread.
+    worker_id: usize,
+    /// Pid and name of the process that was running, if any.
+    process: Option<(ProcessId, &'static str)>,
+    /// Number of messages in the inbox of the process, if any.
+    inbox_len: Option<usize>,
+    /// Most recent events of the flight recorder, oldest first.
+    events: Vec<String>,
+}
+
+impl fmt::Display for PanicReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (worker_id={}", self.message, self.worker_id)?;
+        if let Some((pid, name)) = self.process {
+            write!(f, ", pid={}, actor={name}", pid.0)?;
+        }
+        if let Some(inbox_len) = self.inbox_len {
+            write!(f, ", inbox_len={inbox_len}")?;
+        }
+        f.write_str(")")?;
+        if !self.events.is_empty() {
+            write!(f, ", recent events: {}", self.events.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
 /// Error running a [`Worker`].
 #[derive(Debug)]
 pub(crate) enum Error {
@@ -660,6 +887,7 @@ async fn comm_actor(
         let internals = &ctx.runtime_ref().internals;
         trace!(worker_id = internals.id.get(), message:? = msg; "processing coordinator message");
         let timing = trace::start(&*internals.trace_log.borrow());
+        internals.record_event("Processing coordinator message", 0);
         match msg {
             Control::Started => internals.start(),
             Control::Signal(signal) => internals.relay_signal(signal),
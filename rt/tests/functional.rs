@@ -19,8 +19,10 @@ mod functional {
     mod future;
     mod io;
     mod pipe;
+    mod process;
     mod restart_supervisor;
     mod runtime;
+    mod schedule;
     mod signal;
     mod spawn;
     mod sync_actor;
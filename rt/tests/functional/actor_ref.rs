@@ -6,14 +6,17 @@ use std::future::ready;
 use std::num::NonZeroUsize;
 use std::pin::{pin, Pin};
 use std::task::Poll;
+use std::time::{Duration, Instant};
 
-use heph::actor::{self, actor_fn};
+use heph::actor::{self, actor_fn, NoMessages, RecvError};
+use heph::actor_ref::rpc::RetryPolicy;
 use heph::actor_ref::{ActorRef, Join, RpcError, RpcMessage, SendError, SendValue};
 use heph::messages::from_message;
 use heph::supervisor::NoSupervisor;
 use heph_rt::spawn::options::Priority;
 use heph_rt::spawn::ActorOptions;
-use heph_rt::test::{init_local_actor, poll_actor, poll_future};
+use heph_rt::test::{block_on_local_actor, init_local_actor, poll_actor, poll_future, spawn_local};
+use heph_rt::timer::Timer;
 use heph_rt::{Runtime, ThreadLocal};
 
 use crate::util::{assert_send, assert_size, assert_sync, pending_once};
@@ -354,6 +357,71 @@ fn try_mapped_send_conversion_error() {
     assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
 }
 
+/// Converts from `usize`, returning the original value as error for zero.
+#[derive(Debug, Eq, PartialEq)]
+struct NonZero(NonZeroUsize);
+
+impl TryFrom<usize> for NonZero {
+    type Error = usize;
+
+    fn try_from(n: usize) -> Result<NonZero, usize> {
+        NonZeroUsize::new(n).map(NonZero).ok_or(n)
+    }
+}
+
+#[test]
+fn try_mapped_or() {
+    let expect_non_zero = actor_fn(expect_msgs);
+    let expected = vec![
+        NonZero(NonZeroUsize::new(1).unwrap()),
+        NonZero(NonZeroUsize::new(2).unwrap()),
+    ];
+    let (actor, actor_ref) = init_local_actor(expect_non_zero, expected).unwrap();
+    let mut actor = Box::pin(actor);
+
+    let expect_msgs = actor_fn(expect_msgs);
+    let (fallback_actor, fallback_ref) = init_local_actor(expect_msgs, vec![0usize, 0]).unwrap();
+    let mut fallback_actor = Box::pin(fallback_actor);
+
+    let actor_ref: ActorRef<usize> = actor_ref.try_map_or(fallback_ref);
+    for msg in [0usize, 1, 0, 2] {
+        actor_ref.try_send(msg).unwrap();
+    }
+
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+    assert_eq!(
+        poll_actor(Pin::as_mut(&mut fallback_actor)),
+        Poll::Ready(Ok(()))
+    );
+}
+
+#[test]
+fn try_mapped_or_send() {
+    let expect_non_zero = actor_fn(expect_msgs);
+    let expected = vec![NonZero(NonZeroUsize::new(1).unwrap())];
+    let (actor, actor_ref) = init_local_actor(expect_non_zero, expected).unwrap();
+    let mut actor = Box::pin(actor);
+
+    let expect_msgs = actor_fn(expect_msgs);
+    let (fallback_actor, fallback_ref) = init_local_actor(expect_msgs, vec![0usize]).unwrap();
+    let mut fallback_actor = Box::pin(fallback_actor);
+
+    let actor_ref: ActorRef<usize> = actor_ref.try_map_or(fallback_ref);
+    let relay_msgs = actor_fn(relay_msgs);
+    let (relay_actor, _) = init_local_actor(relay_msgs, (actor_ref, vec![0usize, 1])).unwrap();
+    let mut relay_actor = Box::pin(relay_actor);
+
+    assert_eq!(
+        poll_actor(Pin::as_mut(&mut relay_actor)),
+        Poll::Ready(Ok(()))
+    );
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+    assert_eq!(
+        poll_actor(Pin::as_mut(&mut fallback_actor)),
+        Poll::Ready(Ok(()))
+    );
+}
+
 #[test]
 fn try_mapped_cloned() {
     let expected: Vec<usize> = (0..INBOX_SIZE - 1).collect();
@@ -848,6 +916,101 @@ fn rpc_error_format() {
     assert_eq!(format!("{}", RpcError::SendError), "unable to send message");
     assert_eq!(format!("{}", RpcError::SendError), format!("{}", SendError));
     assert_eq!(format!("{}", RpcError::NoResponse), "no RPC response");
+    assert_eq!(format!("{}", RpcError::TimedOut), "RPC timed out");
+}
+
+/// Doesn't respond to the first request.
+async fn flaky_pong(mut ctx: actor::Context<RpcTestMessage, ThreadLocal>) {
+    let mut first = true;
+    while let Ok(msg) = ctx.receive_next().await {
+        match msg {
+            RpcTestMessage::Ping(msg) if first => {
+                first = false;
+                drop(msg);
+            }
+            RpcTestMessage::Ping(msg) => msg.handle(|_| ready(Pong)).await.unwrap(),
+            RpcTestMessage::Check => {}
+        }
+    }
+}
+
+/// Never responds, but keeps the requests around.
+async fn silent_pong(mut ctx: actor::Context<RpcTestMessage, ThreadLocal>) {
+    let mut requests = Vec::new();
+    while let Ok(msg) = ctx.receive_next().await {
+        match msg {
+            RpcTestMessage::Ping(msg) => requests.push(msg),
+            RpcTestMessage::Check => {}
+        }
+    }
+}
+
+const RETRY_BACKOFF: Duration = Duration::from_millis(10);
+const RETRY_TIMEOUT: Duration = Duration::from_millis(20);
+
+#[test]
+fn rpc_retry() {
+    async fn actor(ctx: actor::Context<!, ThreadLocal>, relay_ref: ActorRef<RpcTestMessage>) {
+        let start = Instant::now();
+        let policy = RetryPolicy::new(2).with_backoff(RETRY_BACKOFF, RETRY_BACKOFF);
+        let rt = ctx.runtime_ref().clone();
+        let timer = |timeout| Timer::after(rt.clone(), timeout);
+        let res = relay_ref.rpc_retry(Ping, policy, timer).await;
+        assert_eq!(res, Ok(Pong));
+        assert!(start.elapsed() >= RETRY_BACKOFF);
+    }
+
+    let pong = actor_fn(flaky_pong);
+    let relay_ref = spawn_local(NoSupervisor, pong, (), ActorOptions::default());
+    block_on_local_actor(actor_fn(actor), relay_ref);
+}
+
+#[test]
+fn rpc_retry_max_attempts() {
+    async fn actor(ctx: actor::Context<!, ThreadLocal>, relay_ref: ActorRef<RpcTestMessage>) {
+        let policy = RetryPolicy::new(1);
+        let rt = ctx.runtime_ref().clone();
+        let timer = |timeout| Timer::after(rt.clone(), timeout);
+        let res = relay_ref.rpc_retry(Ping, policy, timer).await;
+        assert_eq!(res, Err(RpcError::NoResponse));
+    }
+
+    let pong = actor_fn(flaky_pong);
+    let relay_ref = spawn_local(NoSupervisor, pong, (), ActorOptions::default());
+    block_on_local_actor(actor_fn(actor), relay_ref);
+}
+
+#[test]
+fn rpc_retry_timeout() {
+    async fn actor(ctx: actor::Context<!, ThreadLocal>, relay_ref: ActorRef<RpcTestMessage>) {
+        let start = Instant::now();
+        let policy = RetryPolicy::new(2).with_timeout(RETRY_TIMEOUT);
+        let rt = ctx.runtime_ref().clone();
+        let timer = |timeout| Timer::after(rt.clone(), timeout);
+        let res = relay_ref.rpc_retry(Ping, policy, timer).await;
+        assert_eq!(res, Err(RpcError::TimedOut));
+        assert!(start.elapsed() >= 2 * RETRY_TIMEOUT);
+    }
+
+    let pong = actor_fn(silent_pong);
+    let relay_ref = spawn_local(NoSupervisor, pong, (), ActorOptions::default());
+    block_on_local_actor(actor_fn(actor), relay_ref);
+}
+
+#[test]
+fn rpc_retry_on() {
+    async fn actor(ctx: actor::Context<!, ThreadLocal>, relay_ref: ActorRef<RpcTestMessage>) {
+        // Don't retry if the actor didn't respond.
+        let policy = RetryPolicy::new(2).with_retry_on(|err| *err == RpcError::TimedOut);
+        let rt = ctx.runtime_ref().clone();
+        let timer = |timeout| Timer::after(rt.clone(), timeout);
+        let res = relay_ref.rpc_retry(Ping, policy, timer).await;
+        assert_eq!(res, Err(RpcError::NoResponse));
+    }
+
+    let pong = actor_fn(flaky_pong);
+    let relay_ref = spawn_local(NoSupervisor, pong, (), ActorOptions::default());
+    block_on_local_actor(actor_fn(actor), relay_ref);
 }
 
 async fn wake_on_response(_: actor::Context<!, ThreadLocal>, relay_ref: ActorRef<RpcTestMessage>) {
@@ -947,6 +1110,97 @@ fn join_before_actor_finished() {
     assert_eq!(poll_future(Pin::new(&mut future)), Poll::Ready(()));
 }
 
+async fn receive_until_stopped(mut ctx: actor::Context<usize, ThreadLocal>, expected: Vec<usize>) {
+    let mut got = Vec::new();
+    while let Ok(msg) = ctx.receive_next().await {
+        got.push(msg);
+    }
+    assert!(ctx.is_stopped());
+    assert_eq!(got, expected);
+    // Once stopped no more messages are returned.
+    assert_eq!(ctx.try_receive_next(), Err(RecvError::Stopped));
+    assert_eq!(ctx.receive_next().await, Err(NoMessages));
+}
+
+#[test]
+fn stop() {
+    let receive_until_stopped = actor_fn(receive_until_stopped);
+    let (actor, actor_ref) = init_local_actor(receive_until_stopped, vec![1, 2]).unwrap();
+    let mut actor = Box::pin(actor);
+
+    actor_ref.try_send(1_usize).unwrap();
+    actor_ref.try_send(2_usize).unwrap();
+    actor_ref.stop().unwrap();
+    // Messages can't be send after the actor is stopped.
+    assert_eq!(actor_ref.try_send(3_usize), Err(SendError));
+
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+    assert!(!actor_ref.is_connected());
+}
+
+#[test]
+fn stop_disconnected() {
+    let receive_until_stopped = actor_fn(receive_until_stopped);
+    let (actor, actor_ref) = init_local_actor(receive_until_stopped, Vec::new()).unwrap();
+    drop(actor);
+    assert_eq!(actor_ref.stop(), Err(SendError));
+}
+
+async fn send_until_stopped(_: actor::Context<!, ThreadLocal>, actor_ref: ActorRef<usize>) {
+    let mut msg = 0usize;
+    while actor_ref.send(msg).await.is_ok() {
+        msg += 1;
+    }
+}
+
+#[test]
+fn stop_wakes_sender() {
+    let expected: Vec<usize> = (0..INBOX_SIZE).collect();
+    let receive_until_stopped = actor_fn(receive_until_stopped);
+    let (actor, actor_ref) = init_local_actor(receive_until_stopped, expected).unwrap();
+    let mut actor = Box::pin(actor);
+
+    let send_until_stopped = actor_fn(send_until_stopped);
+    let (sender_actor, _) = init_local_actor(send_until_stopped, actor_ref.clone()).unwrap();
+    let mut sender_actor = Box::pin(sender_actor);
+
+    // Fill the inbox, the sender has to wait.
+    assert_eq!(poll_actor(Pin::as_mut(&mut sender_actor)), Poll::Pending);
+    // Stopping the actor makes the sender's send fail.
+    actor_ref.stop().unwrap();
+    assert_eq!(
+        poll_actor(Pin::as_mut(&mut sender_actor)),
+        Poll::Ready(Ok(()))
+    );
+    // The messages send before stopping are still received.
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
+#[test]
+fn mapped_stop() {
+    let receive_until_stopped = actor_fn(receive_until_stopped);
+    let (actor, actor_ref) = init_local_actor(receive_until_stopped, vec![1]).unwrap();
+    let mut actor = Box::pin(actor);
+
+    let actor_ref: ActorRef<u8> = actor_ref.map();
+    actor_ref.try_send(1_u8).unwrap();
+    actor_ref.stop().unwrap();
+
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
+#[test]
+fn mapped_fn_stop() {
+    let receive_until_stopped = actor_fn(receive_until_stopped);
+    let (actor, actor_ref) = init_local_actor(receive_until_stopped, Vec::new()).unwrap();
+    let mut actor = Box::pin(actor);
+
+    let actor_ref: ActorRef<String> = actor_ref.try_map_fn(|msg: String| msg.parse::<usize>());
+    actor_ref.stop().unwrap();
+
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
 #[derive(Debug)]
 enum CalcMessage {
     Get(RpcMessage<(), usize>),
@@ -1,7 +1,10 @@
 //! Tests for the filesystem operations.
 
+use std::async_iter::AsyncIterator;
+use std::future::poll_fn;
 use std::io;
 use std::path::PathBuf;
+use std::pin::Pin;
 
 use heph::actor::{self, actor_fn};
 use heph_rt::access::ThreadLocal;
@@ -263,3 +266,102 @@ fn remove_dir() {
 
     block_on_local_actor(actor_fn(actor), ());
 }
+
+#[test]
+fn create_dir_all() {
+    async fn actor(ctx: actor::Context<!, ThreadLocal>) {
+        let root = temp_file("create_dir_all");
+        let dir = root.join("a").join("b").join("c");
+        fs::create_dir_all(ctx.runtime_ref(), dir.clone())
+            .await
+            .unwrap();
+        assert!(dir.is_dir());
+        // Already existing directories are fine.
+        fs::create_dir_all(ctx.runtime_ref(), dir.clone())
+            .await
+            .unwrap();
+        fs::create_dir_all(ctx.runtime_ref(), root.join("a"))
+            .await
+            .unwrap();
+
+        // But files are not.
+        let path = dir.join("file");
+        drop(File::create(ctx.runtime_ref(), path.clone()).await.unwrap());
+        let err = fs::create_dir_all(ctx.runtime_ref(), path.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        let err = fs::create_dir_all(ctx.runtime_ref(), path.join("dir"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotADirectory);
+    }
+
+    block_on_local_actor(actor_fn(actor), ());
+}
+
+#[test]
+fn metadata() {
+    async fn actor(ctx: actor::Context<!, ThreadLocal>) {
+        let path = temp_file("metadata");
+        let file = File::create(ctx.runtime_ref(), path.clone()).await.unwrap();
+        (&file).write_all(DATA1).await.unwrap();
+        drop(file);
+
+        let metadata = fs::metadata(ctx.runtime_ref(), path).await.unwrap();
+        assert!(metadata.is_file());
+        assert_eq!(metadata.len(), DATA1.len() as u64);
+
+        let metadata = fs::metadata(ctx.runtime_ref(), temp_dir_root())
+            .await
+            .unwrap();
+        assert!(metadata.is_dir());
+
+        let err = fs::metadata(ctx.runtime_ref(), temp_file("metadata.missing"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    block_on_local_actor(actor_fn(actor), ());
+}
+
+#[test]
+fn read_dir() {
+    async fn actor(ctx: actor::Context<!, ThreadLocal>) {
+        let dir = temp_file("read_dir");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::create_dir(dir.join("dir")).unwrap();
+        // Enough files to require multiple reads.
+        let mut expected = vec![dir.join("dir")];
+        for n in 0..200 {
+            let path = dir.join(format!("file_{n:03}"));
+            std::fs::write(&path, DATA1).unwrap();
+            expected.push(path);
+        }
+
+        let mut read_dir = fs::read_dir(ctx.runtime_ref(), dir.clone()).await.unwrap();
+        let mut got = Vec::new();
+        while let Some(entry) = poll_fn(|ctx| Pin::new(&mut read_dir).poll_next(ctx)).await {
+            let entry = entry.unwrap();
+            assert_eq!(entry.path().parent(), Some(&*dir));
+            if entry.file_name() == "dir" {
+                assert!(entry.is_dir());
+            } else {
+                assert!(entry.is_file());
+                let metadata = entry.metadata(ctx.runtime_ref()).await.unwrap();
+                assert_eq!(metadata.len(), DATA1.len() as u64);
+            }
+            got.push(entry.into_path());
+        }
+        got.sort();
+        assert_eq!(got, expected);
+
+        let err = fs::read_dir(ctx.runtime_ref(), temp_file("read_dir.missing"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    block_on_local_actor(actor_fn(actor), ());
+}
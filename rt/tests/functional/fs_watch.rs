@@ -3,8 +3,10 @@
 use std::path::{Path, PathBuf};
 
 use heph::actor::{self, actor_fn};
+use heph::supervisor::StopSupervisor;
 use heph_rt::access::ThreadLocal;
-use heph_rt::fs::watch::{Event, Interest, Recursive, Watch};
+use heph_rt::fs::watch::{self, Event, EventBuf, Interest, Recursive, Watch};
+use heph_rt::spawn::ActorOptions;
 use heph_rt::test::block_on_local_actor;
 
 use crate::util::temp_dir;
@@ -996,6 +998,37 @@ fn watched_file_all() {
     );
 }
 
+#[test]
+fn watcher_actor() {
+    async fn actor(mut ctx: actor::Context<EventBuf, ThreadLocal>) {
+        let tmp_dir = temp_dir("fs_watch.watcher_actor");
+        std::fs::create_dir(&tmp_dir).expect("failed to create test directory");
+
+        let mut watch = Watch::new(ctx.runtime_ref()).expect("failed to create watch");
+        watch
+            .watch_directory(tmp_dir.clone(), Interest::CREATE, Recursive::No)
+            .expect("failed to add watch");
+        let actor_ref = ctx.actor_ref();
+        let watcher = actor_fn(watch::watcher::<ThreadLocal>);
+        _ = ctx.runtime().spawn_local(
+            StopSupervisor,
+            watcher,
+            (watch, actor_ref),
+            ActorOptions::default(),
+        );
+
+        let path = tmp_dir.join(FILE_NAME);
+        std::fs::write(&path, b"Hello, World!").expect("failed to create file");
+
+        let event = ctx.receive_next().await.expect("missing event");
+        assert_eq!(event.path(), path);
+        assert!(event.file_created());
+        assert!(!event.is_dir());
+    }
+
+    block_on_local_actor(actor_fn(actor), ());
+}
+
 /// Test actors.
 ///
 /// Arguments:
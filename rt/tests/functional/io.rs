@@ -5,7 +5,9 @@ use std::cmp::min;
 use std::ptr;
 use std::sync::Arc;
 
-use heph_rt::io::{Buf, BufMut, BufMutSlice, BufSlice, ReadBuf, ReadBufPool};
+use heph_rt::io::{
+    Buf, BufMut, BufMutSlice, BufSlice, Checksum, Crc32c, ReadBuf, ReadBufPool, XxHash64,
+};
 
 use crate::util::assert_size;
 
@@ -60,6 +62,22 @@ fn buf_mut_for_limited() {
     test_buf_mut(BufMut::limit(buf, TEST_BUF_MUT_CAPACITY));
 }
 
+#[test]
+fn buf_mut_for_checksummed() {
+    let buf = Vec::with_capacity(TEST_BUF_MUT_CAPACITY);
+    test_buf_mut(BufMut::checksum(buf, Crc32c::new()));
+
+    let mut buf = Vec::with_capacity(TEST_BUF_MUT_CAPACITY + 1);
+    buf.push(b'!'); // Not part of the checksum.
+    let mut buf = BufMut::checksum(buf, Crc32c::new());
+    assert_eq!(write_bytes(DATA, &mut buf), DATA.len());
+    assert_eq!(write_bytes(DATA2, &mut buf), DATA2.len());
+    assert_eq!(buf.finish(), crc32c(&[DATA, DATA2].concat()));
+    let (buf, crc) = buf.into_parts();
+    assert_eq!(&buf[1..], [DATA, DATA2].concat());
+    assert_eq!(crc.finish(), crc32c(&[DATA, DATA2].concat()));
+}
+
 const TEST_BUF_MUT_CAPACITY: usize = DATA.len() + DATA2.len();
 
 fn test_buf_mut<B: BufMut>(mut buf: B) {
@@ -149,6 +167,20 @@ fn buf_for_limited() {
     test_buf(DATA.limit(DATA.len())); // Smaller.
 }
 
+#[test]
+fn buf_for_checksummed() {
+    test_buf(DATA.checksum(Crc32c::new()));
+
+    let buf = DATA.checksum(Crc32c::new());
+    assert_eq!(buf.finish(), crc32c(DATA));
+    // Continue the checksum using the next buffer.
+    let (buf, crc) = buf.into_parts();
+    assert_eq!(buf, DATA);
+    let buf = DATA2.checksum(crc);
+    assert_eq!(buf.finish(), crc32c(&[DATA, DATA2].concat()));
+    assert_eq!(buf.into_inner(), DATA2);
+}
+
 fn test_buf<B: Buf>(buf: B) {
     let (ptr, len) = unsafe { buf.parts() };
     let got = unsafe { std::slice::from_raw_parts(ptr, len) };
@@ -285,6 +317,53 @@ fn test_buf_mut_slice<B: BufMutSlice<2>>(mut bufs: B, expected_limit: usize) {
     assert!(bufs.total_spare_capacity() == 0);
 }
 
+fn crc32c(bytes: &[u8]) -> u32 {
+    let mut crc = Crc32c::new();
+    crc.update(bytes);
+    crc.finish()
+}
+
+fn xxhash64(bytes: &[u8], seed: u64) -> u64 {
+    let mut hash = XxHash64::with_seed(seed);
+    hash.update(bytes);
+    hash.finish()
+}
+
+#[test]
+fn crc32c_test_vectors() {
+    assert_eq!(crc32c(b""), 0);
+    assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+    // From RFC 3720 (iSCSI), section B.4.
+    assert_eq!(crc32c(&[0; 32]), 0x8A91_36AA);
+    assert_eq!(crc32c(&[0xFF; 32]), 0x62A8_AB43);
+}
+
+#[test]
+fn xxhash64_test_vectors() {
+    assert_eq!(xxhash64(b"", 0), 0xEF46_DB37_51D8_E999);
+    assert_eq!(xxhash64(b"a", 0), 0xD24E_C4F1_A98C_6E5B);
+    assert_eq!(xxhash64(b"abc", 0), 0x44BC_2CF5_AD77_0999);
+    assert_eq!(
+        xxhash64(b"Nobody inspects the spammish repetition", 0),
+        0xFBCE_A83C_8A37_8BF1
+    );
+}
+
+#[test]
+fn checksum_streaming() {
+    let data: Vec<u8> = (0..1000_u32).map(|i| (i * 31 % 251) as u8).collect();
+    for size in [1, 3, 8, 31, 32, 33, 100] {
+        let mut crc = Crc32c::new();
+        let mut hash = XxHash64::with_seed(123);
+        for chunk in data.chunks(size) {
+            crc.update(chunk);
+            hash.update(chunk);
+        }
+        assert_eq!(crc.finish(), crc32c(&data));
+        assert_eq!(hash.finish(), xxhash64(&data, 123));
+    }
+}
+
 #[test]
 fn read_buf_pool_size() {
     assert_size::<ReadBufPool>(8);
@@ -0,0 +1,94 @@
+//! Tests for the child process management.
+
+use std::io;
+use std::os::unix::process::ExitStatusExt;
+use std::process::Stdio;
+use std::time::Duration;
+
+use heph::actor::{self, actor_fn};
+use heph_rt::io::{Read, Write};
+use heph_rt::process::Command;
+use heph_rt::spawn::ActorOptions;
+use heph_rt::test::{join, try_spawn_local, PanicSupervisor};
+use heph_rt::{self as rt};
+
+const DATA: &[u8] = b"Hello world";
+
+#[test]
+fn smoke() {
+    async fn actor<RT>(ctx: actor::Context<!, RT>) -> io::Result<()>
+    where
+        RT: rt::Access,
+    {
+        let mut child = Command::new("cat")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn(ctx.runtime_ref())?;
+        assert!(child.stderr.is_none());
+
+        let stdin = child.stdin.take().unwrap();
+        (&stdin).write_all(DATA).await?;
+        drop(stdin);
+
+        let stdout = child.stdout.take().unwrap();
+        let buf = (&stdout)
+            .read_n(Vec::with_capacity(DATA.len() + 1), DATA.len())
+            .await?;
+        assert_eq!(buf, DATA);
+
+        let status = child.wait().await?;
+        assert!(status.success());
+        // Waiting again should return the same status.
+        assert_eq!(child.wait().await?, status);
+        assert_eq!(child.try_wait()?, Some(status));
+        Ok(())
+    }
+
+    let actor = actor_fn(actor);
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn exit_code() {
+    async fn actor<RT>(ctx: actor::Context<!, RT>) -> io::Result<()>
+    where
+        RT: rt::Access,
+    {
+        let mut child = Command::new("sh")
+            .args(["-c", "exit $CODE"])
+            .env("CODE", "12")
+            .spawn(ctx.runtime_ref())?;
+        let status = child.wait().await?;
+        assert!(!status.success());
+        assert_eq!(status.code(), Some(12));
+        Ok(())
+    }
+
+    let actor = actor_fn(actor);
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn kill() {
+    async fn actor<RT>(ctx: actor::Context<!, RT>) -> io::Result<()>
+    where
+        RT: rt::Access,
+    {
+        let mut child = Command::new("sleep").arg("10").spawn(ctx.runtime_ref())?;
+        assert_eq!(child.try_wait()?, None);
+        child.kill()?;
+        let status = child.wait().await?;
+        assert_eq!(status.code(), None);
+        assert_eq!(status.signal(), Some(libc::SIGKILL));
+        // Killing an already awaited process should be a no-op.
+        child.kill()?;
+        Ok(())
+    }
+
+    let actor = actor_fn(actor);
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
@@ -1,8 +1,10 @@
+use std::cell::Cell;
 use std::future::Future;
 use std::io::{self, Write};
 use std::marker::PhantomData;
 use std::pin::Pin;
 use std::process::Command;
+use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Condvar, Mutex};
 use std::task::{self, Poll};
@@ -396,6 +398,31 @@ fn tracing() {
     }
 }
 
+#[test]
+#[cfg(feature = "console")]
+fn console() {
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::UnixStream;
+
+    let console_path = temp_file("runtime_console.sock");
+
+    let mut setup = Runtime::setup().num_threads(2);
+    setup.enable_console(&console_path).unwrap();
+    let runtime = setup.build().unwrap();
+
+    // The console is started when the runtime is build.
+    let stream = UnixStream::connect(&console_path).unwrap();
+    let mut snapshot = String::new();
+    let n = BufReader::new(stream).read_line(&mut snapshot).unwrap();
+    assert!(n > 0);
+    assert!(snapshot.starts_with("{\"timestamp_ms\":"), "{snapshot}");
+    assert!(snapshot.contains("\"workers\":[{\"id\":1,"), "{snapshot}");
+    assert!(snapshot.contains("{\"id\":2,"), "{snapshot}");
+    assert!(snapshot.ends_with("]}\n"), "{snapshot}");
+
+    runtime.start().unwrap();
+}
+
 #[derive(Clone)] // Needed in setup function.
 struct WaitFuture {
     #[allow(clippy::type_complexity)]
@@ -746,3 +773,289 @@ fn catches_local_future_panics_on_drop() {
     assert!(PANIC_RAN.load(Ordering::Acquire));
     assert!(OK_RAN.load(Ordering::Acquire));
 }
+
+#[test]
+fn local_data() {
+    static RAN: AtomicUsize = AtomicUsize::new(0);
+
+    /// Value that isn't `Send`, stored in the worker-local data.
+    struct Counter(Rc<Cell<usize>>);
+
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) {
+        let count = ctx.runtime().local_data(|data| {
+            let counter = data.get::<Counter>().unwrap();
+            counter.0.set(counter.0.get() + 1);
+            counter.0.get()
+        });
+        assert!(count == 1 || count == 2);
+        _ = RAN.fetch_add(1, Ordering::AcqRel);
+    }
+
+    let mut runtime = Runtime::setup()
+        .num_threads(2)
+        .with_local_data(|data| {
+            assert!(data.insert(Counter(Rc::new(Cell::new(0)))).is_none());
+        })
+        .build()
+        .unwrap();
+    runtime
+        .run_on_workers(|mut runtime_ref| -> Result<(), !> {
+            assert!(runtime_ref.local_data(|data| data.contains::<Counter>()));
+            for _ in 0..2 {
+                runtime_ref.spawn_local(NoSupervisor, actor_fn(actor), (), ActorOptions::default());
+            }
+            Ok(())
+        })
+        .unwrap();
+    runtime.start().unwrap();
+
+    assert_eq!(RAN.load(Ordering::Acquire), 4);
+}
+
+#[test]
+fn resources() {
+    static RAN: AtomicUsize = AtomicUsize::new(0);
+    static DROPPED: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+    /// Resource that records when it's dropped.
+    struct Resource(&'static str);
+
+    impl Drop for Resource {
+        fn drop(&mut self) {
+            DROPPED.lock().unwrap().push(self.0);
+        }
+    }
+
+    /// Resource registered after `Resource`, so it's dropped first.
+    struct Second(Resource);
+
+    /// Resource of which the first value is replaced.
+    struct Replaced(usize);
+
+    async fn local_actor(mut ctx: actor::Context<!, ThreadLocal>) {
+        assert_eq!(ctx.runtime().resource::<Resource>().unwrap().0, "first");
+        _ = RAN.fetch_add(1, Ordering::AcqRel);
+    }
+
+    async fn thread_safe_actor(mut ctx: actor::Context<!, ThreadSafe>) {
+        assert_eq!(ctx.runtime().resource::<Replaced>().unwrap().0, 2);
+        _ = RAN.fetch_add(1, Ordering::AcqRel);
+    }
+
+    fn sync_actor(mut ctx: sync::Context<!, heph_rt::Sync>) {
+        assert_eq!(ctx.runtime().resource::<Resource>().unwrap().0, "first");
+        assert!(ctx.runtime().resource::<usize>().is_none());
+        _ = RAN.fetch_add(1, Ordering::AcqRel);
+    }
+
+    let mut runtime = Runtime::setup()
+        .with_resource(Resource("first"))
+        .with_resource(Replaced(1))
+        .with_resource(Replaced(2))
+        .with_resource(Second(Resource("second")))
+        .build()
+        .unwrap();
+    assert_eq!(runtime.resource::<Second>().unwrap().0 .0, "second");
+    assert!(runtime.resource::<usize>().is_none());
+    runtime
+        .run_on_workers(|mut runtime_ref| -> Result<(), !> {
+            runtime_ref.spawn_local(
+                NoSupervisor,
+                actor_fn(local_actor),
+                (),
+                ActorOptions::default(),
+            );
+            Ok(())
+        })
+        .unwrap();
+    _ = runtime.spawn(
+        NoSupervisor,
+        actor_fn(thread_safe_actor),
+        (),
+        ActorOptions::default(),
+    );
+    _ = runtime
+        .spawn_sync_actor(
+            NoSupervisor,
+            actor_fn(sync_actor),
+            (),
+            SyncActorOptions::default(),
+        )
+        .unwrap();
+    runtime.start().unwrap();
+
+    assert_eq!(RAN.load(Ordering::Acquire), 3);
+    assert_eq!(*DROPPED.lock().unwrap(), ["second", "first"]);
+}
+
+#[test]
+fn dump_flight_recorder() {
+    static RAN: AtomicUsize = AtomicUsize::new(0);
+
+    async fn actor(ctx: actor::Context<!, ThreadLocal>) {
+        ctx.runtime_ref().dump_flight_recorder();
+        _ = RAN.fetch_add(1, Ordering::AcqRel);
+    }
+
+    let mut runtime = Runtime::setup().num_threads(2).build().unwrap();
+    runtime
+        .run_on_workers(|mut runtime_ref| -> Result<(), !> {
+            // Before any process ran.
+            runtime_ref.dump_flight_recorder();
+            runtime_ref.spawn_local(NoSupervisor, actor_fn(actor), (), ActorOptions::default());
+            Ok(())
+        })
+        .unwrap();
+    runtime.start().unwrap();
+
+    assert_eq!(RAN.load(Ordering::Acquire), 2);
+}
+
+#[test]
+fn worker_restarts() {
+    static SPAWNED: AtomicBool = AtomicBool::new(false);
+    static LOCAL_DATA_INIT: AtomicUsize = AtomicUsize::new(0);
+    static RAN: AtomicUsize = AtomicUsize::new(0);
+
+    async fn actor(_: actor::Context<!, ThreadLocal>) -> Result<(), &'static str> {
+        _ = RAN.fetch_add(1, Ordering::AcqRel);
+        Err("oops")
+    }
+
+    // Panicking in the supervisor isn't caught and takes the worker thread down
+    // with it.
+    fn supervisor(err: &'static str) -> SupervisorStrategy<()> {
+        panic!("supervisor panic: {err}")
+    }
+
+    let mut runtime = Runtime::setup()
+        .num_threads(2)
+        .with_worker_restarts(1)
+        .with_local_data(|_| {
+            _ = LOCAL_DATA_INIT.fetch_add(1, Ordering::AcqRel);
+        })
+        .build()
+        .unwrap();
+    runtime
+        .run_on_workers(|mut runtime_ref| -> Result<(), !> {
+            if !SPAWNED.swap(true, Ordering::AcqRel) {
+                runtime_ref.spawn_local(supervisor, actor_fn(actor), (), ActorOptions::default());
+            }
+            Ok(())
+        })
+        .unwrap();
+    runtime.start().unwrap();
+
+    assert_eq!(RAN.load(Ordering::Acquire), 1);
+    // Two workers started, plus one restarted worker.
+    assert_eq!(LOCAL_DATA_INIT.load(Ordering::Acquire), 3);
+}
+
+#[test]
+fn no_worker_restarts() {
+    static SPAWNED: AtomicBool = AtomicBool::new(false);
+
+    async fn actor(_: actor::Context<!, ThreadLocal>) -> Result<(), &'static str> {
+        Err("oops")
+    }
+
+    fn supervisor(err: &'static str) -> SupervisorStrategy<()> {
+        panic!("supervisor panic: {err}")
+    }
+
+    let mut runtime = Runtime::setup().num_threads(2).build().unwrap();
+    runtime
+        .run_on_workers(|mut runtime_ref| -> Result<(), !> {
+            if !SPAWNED.swap(true, Ordering::AcqRel) {
+                runtime_ref.spawn_local(supervisor, actor_fn(actor), (), ActorOptions::default());
+            }
+            Ok(())
+        })
+        .unwrap();
+    let err = runtime.start().unwrap_err();
+    assert!(err.to_string().contains("supervisor panic: oops"), "{err}");
+}
+
+#[test]
+fn shutdown_hooks() {
+    static ACTOR_STOPPED: AtomicBool = AtomicBool::new(false);
+    static RESOURCE_DROPPED: AtomicBool = AtomicBool::new(false);
+    static RAN: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
+    /// Resource that is only dropped after the hooks ran.
+    struct Resource;
+
+    impl Drop for Resource {
+        fn drop(&mut self) {
+            RESOURCE_DROPPED.store(true, Ordering::Release);
+        }
+    }
+
+    async fn actor(_: actor::Context<!, ThreadLocal>) {
+        ACTOR_STOPPED.store(true, Ordering::Release);
+    }
+
+    let mut runtime = Runtime::setup().with_resource(Resource).build().unwrap();
+    runtime
+        .run_on_workers(|mut runtime_ref| -> Result<(), !> {
+            runtime_ref.spawn_local(NoSupervisor, actor_fn(actor), (), ActorOptions::default());
+            for n in 0..3 {
+                runtime_ref.on_shutdown(async move {
+                    assert!(ACTOR_STOPPED.load(Ordering::Acquire));
+                    assert!(!RESOURCE_DROPPED.load(Ordering::Acquire));
+                    // Hooks are awaited, not just polled once.
+                    YieldOnce(false).await;
+                    RAN.lock().unwrap().push(n);
+                });
+            }
+            Ok(())
+        })
+        .unwrap();
+    runtime.start().unwrap();
+
+    assert_eq!(*RAN.lock().unwrap(), [0, 1, 2]);
+    assert!(RESOURCE_DROPPED.load(Ordering::Acquire));
+}
+
+#[test]
+fn shutdown_hooks_timeout() {
+    static RAN: AtomicUsize = AtomicUsize::new(0);
+
+    let mut runtime = Runtime::setup()
+        .with_shutdown_timeout(Duration::from_millis(50))
+        .build()
+        .unwrap();
+    runtime
+        .run_on_workers(|mut runtime_ref| -> Result<(), !> {
+            runtime_ref.on_shutdown(async {
+                _ = RAN.fetch_add(1, Ordering::AcqRel);
+            });
+            runtime_ref.on_shutdown(std::future::pending());
+            // Never run as the previous hook never completes.
+            runtime_ref.on_shutdown(async {
+                _ = RAN.fetch_add(1, Ordering::AcqRel);
+            });
+            Ok(())
+        })
+        .unwrap();
+    runtime.start().unwrap();
+
+    assert_eq!(RAN.load(Ordering::Acquire), 1);
+}
+
+/// Future that returns `Poll::Pending` once, waking itself.
+struct YieldOnce(bool);
+
+impl Future for YieldOnce {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            ctx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Tests for the schedule module.
+
+use std::time::{Duration, Instant, SystemTime};
+
+use heph::actor::{self, actor_fn};
+use heph::supervisor::NoSupervisor;
+use heph_rt::schedule::{self, Cron, CronError, Schedule};
+use heph_rt::spawn::ActorOptions;
+use heph_rt::test::block_on_local_actor;
+use heph_rt::ThreadLocal;
+
+const INTERVAL: Duration = Duration::from_millis(20);
+
+/// Wednesday 15 March 2023 12:34:56 UTC.
+const TIME: u64 = 1_678_883_696;
+
+fn time(secs: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+fn next_after(expr: &str, secs: u64) -> Option<SystemTime> {
+    expr.parse::<Cron>().unwrap().next_after(time(secs))
+}
+
+#[test]
+fn cron_every_minute() {
+    assert_eq!(next_after("* * * * *", TIME), Some(time(TIME + 4)));
+    // Must be strictly after the time.
+    assert_eq!(next_after("* * * * *", TIME + 4), Some(time(TIME + 64)));
+}
+
+#[test]
+fn cron_steps_and_ranges() {
+    // 12:45.
+    assert_eq!(next_after("*/15 * * * *", TIME), Some(time(TIME + 604)));
+    // 13:00.
+    assert_eq!(next_after("0 13-15 * * *", TIME), Some(time(TIME + 1504)));
+    // 14:00, 13:00 is skipped by the step.
+    assert_eq!(next_after("0 12-18/2 * * *", TIME), Some(time(TIME + 5104)));
+    // 12:40.
+    assert_eq!(next_after("10,40 * * * *", TIME), Some(time(TIME + 304)));
+}
+
+#[test]
+fn cron_days() {
+    // Thursday 16 March 2023 00:00.
+    let midnight = TIME + 41104;
+    assert_eq!(next_after("@daily", TIME), Some(time(midnight)));
+    assert_eq!(next_after("0 0 * * 4", TIME), Some(time(midnight)));
+    // Sunday 19 March 2023, 7 is also Sunday.
+    assert_eq!(
+        next_after("@weekly", TIME),
+        Some(time(midnight + 3 * 86400))
+    );
+    assert_eq!(
+        next_after("0 0 * * 7", TIME),
+        Some(time(midnight + 3 * 86400))
+    );
+    // Saturday 1 April 2023.
+    assert_eq!(
+        next_after("@monthly", TIME),
+        Some(time(midnight + 16 * 86400))
+    );
+    // If both day of month and day of week are restricted either one matches.
+    assert_eq!(
+        next_after("0 0 1 * 0", TIME),
+        Some(time(midnight + 3 * 86400))
+    );
+    // Monday 1 January 2024.
+    assert_eq!(next_after("@yearly", TIME), Some(time(1_704_067_200)));
+}
+
+#[test]
+fn cron_leap_day() {
+    // Thursday 29 February 2024.
+    assert_eq!(next_after("0 0 29 2 *", TIME), Some(time(1_709_164_800)));
+    // Never matches.
+    assert_eq!(next_after("0 0 30 2 *", TIME), None);
+}
+
+#[test]
+fn cron_parse_errors() {
+    let tests = [
+        ("", CronError::FieldCount),
+        ("* * * *", CronError::FieldCount),
+        ("* * * * * *", CronError::FieldCount),
+        ("a * * * *", CronError::InvalidValue),
+        ("5/2 * * * *", CronError::InvalidValue),
+        ("1,,2 * * * *", CronError::InvalidValue),
+        ("60 * * * *", CronError::OutOfRange),
+        ("* 24 * * *", CronError::OutOfRange),
+        ("* * 0 * *", CronError::OutOfRange),
+        ("* * * 13 *", CronError::OutOfRange),
+        ("* * * * 8", CronError::OutOfRange),
+        ("*/0 * * * *", CronError::OutOfRange),
+        ("5-1 * * * *", CronError::InvalidRange),
+    ];
+    for (expr, expected) in tests {
+        let got = expr.parse::<Cron>().unwrap_err();
+        assert_eq!(got, expected, "expression: '{expr}'");
+    }
+}
+
+#[test]
+fn scheduler_every() {
+    async fn actor(mut ctx: actor::Context<usize, ThreadLocal>) {
+        let start = Instant::now();
+        let mut count = 0;
+        let actor_ref = ctx.actor_ref();
+        let scheduler = actor_fn(schedule::scheduler);
+        let create_msg = move || {
+            count += 1;
+            count
+        };
+        _ = ctx.runtime().spawn_local(
+            NoSupervisor,
+            scheduler,
+            (Schedule::Every(INTERVAL), actor_ref, create_msg),
+            ActorOptions::default(),
+        );
+
+        for expected in 1..=3 {
+            let msg = ctx.receive_next().await.expect("missing message");
+            assert_eq!(msg, expected);
+            assert!(start.elapsed() >= INTERVAL * expected as u32);
+        }
+    }
+
+    block_on_local_actor(actor_fn(actor), ());
+}
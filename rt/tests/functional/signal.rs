@@ -1,5 +1,7 @@
+use heph::actor::{self, actor_fn, MessageOrSignal};
 use heph::messages::Terminate;
-use heph_rt::Signal;
+use heph_rt::test::block_on_local_actor;
+use heph_rt::{Signal, ThreadLocal};
 
 #[test]
 fn terminate_try_from_signal() {
@@ -16,3 +18,17 @@ fn terminate_try_from_signal() {
         assert_eq!(expected, got);
     }
 }
+
+#[test]
+fn signal_receiver() {
+    async fn actor(mut ctx: actor::Context<usize, ThreadLocal>) {
+        let mut signals = ctx.runtime().signal_receiver();
+        assert_eq!(signals.try_recv(), None);
+
+        ctx.actor_ref().try_send(1_usize).unwrap();
+        let got = ctx.receive_next().or_signal(signals.recv()).await;
+        assert_eq!(got, Ok(MessageOrSignal::Message(1)));
+    }
+
+    block_on_local_actor(actor_fn(actor), ());
+}
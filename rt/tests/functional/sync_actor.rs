@@ -3,13 +3,14 @@ use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 use std::task::{self, Poll};
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use heph::actor::{actor_fn, RecvError};
 use heph::supervisor::{NoSupervisor, SupervisorStrategy};
 use heph::sync;
 use heph_rt::spawn::SyncActorOptions;
 use heph_rt::test::spawn_sync_actor;
+use heph_rt::timer::Timer;
 
 #[derive(Clone, Debug)]
 struct BlockFuture {
@@ -116,6 +117,7 @@ fn try_receive_next_actor<RT>(mut ctx: sync::Context<String, RT>) {
             }
             Err(RecvError::Empty) => continue,
             Err(RecvError::Disconnected) => panic!("unexpected disconnected error"),
+            Err(RecvError::Stopped) => panic!("unexpected stopped error"),
         }
     }
 }
@@ -134,6 +136,27 @@ fn context_try_receive_next() {
     handle.join().unwrap();
 }
 
+fn timer_actor(mut ctx: sync::Context<String, heph_rt::Sync>) {
+    let timeout = Duration::from_millis(20);
+    let start = Instant::now();
+    let timer = Timer::after(ctx.runtime_ref().clone(), timeout);
+    ctx.block_on(timer);
+    assert!(start.elapsed() >= timeout);
+}
+
+#[test]
+fn timer() {
+    let (handle, _) = spawn_sync_actor(
+        NoSupervisor,
+        actor_fn(timer_actor),
+        (),
+        SyncActorOptions::default(),
+    )
+    .unwrap();
+
+    handle.join().unwrap();
+}
+
 #[test]
 fn supervision() {
     let (handle, _) = spawn_sync_actor(
@@ -5,11 +5,12 @@ use std::time::Duration;
 use heph::actor::{self, actor_fn};
 use heph::supervisor::NoSupervisor;
 use heph::ActorRef;
-use heph_rt::net::{TcpListener, TcpStream};
+use heph_rt::net::{SocketConfig, TcpListener, TcpStream};
 use heph_rt::spawn::ActorOptions;
 use heph_rt::test::{block_on_local_actor, join, join_many, try_spawn_local};
 use heph_rt::util::next;
 use heph_rt::{self as rt, ThreadLocal};
+use socket2::SockRef;
 
 use crate::util::{any_local_address, any_local_ipv6_address};
 
@@ -71,6 +72,67 @@ fn ttl() {
     join(&actor_ref, Duration::from_secs(1)).unwrap();
 }
 
+#[test]
+fn bind_with_config() {
+    async fn actor(ctx: actor::Context<!, ThreadLocal>) {
+        let config = SocketConfig::new()
+            .with_ttl(42)
+            .with_nodelay(true)
+            .with_recv_buffer_size(32 * 1024);
+        let listener = TcpListener::bind_with(ctx.runtime_ref(), any_local_address(), &config)
+            .await
+            .unwrap();
+        assert_eq!(listener.ttl().unwrap(), 42);
+    }
+
+    let actor = actor_fn(actor);
+    let actor_ref = try_spawn_local(NoSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn bind_with_freebind() {
+    async fn actor(ctx: actor::Context<!, ThreadLocal>) {
+        // Address from TEST-NET-1 (RFC 5737), not assigned to any interface.
+        let address: SocketAddr = "192.0.2.1:0".parse().unwrap();
+        let config = SocketConfig::new().with_freebind(true);
+        let listener = TcpListener::bind_with(ctx.runtime_ref(), address, &config)
+            .await
+            .unwrap();
+        assert_eq!(listener.local_addr().unwrap().ip(), address.ip());
+    }
+
+    let actor = actor_fn(actor);
+    let actor_ref = try_spawn_local(NoSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn bind_with_only_v6() {
+    async fn actor(ctx: actor::Context<!, ThreadLocal>) {
+        let address: SocketAddr = "[::]:0".parse().unwrap();
+        for only_v6 in [true, false] {
+            let config = SocketConfig::new().with_only_v6(only_v6);
+            let listener = TcpListener::bind_with(ctx.runtime_ref(), address, &config)
+                .await
+                .unwrap();
+            assert_eq!(SockRef::from(&listener).only_v6().unwrap(), only_v6);
+        }
+
+        // Ignored for IPv4 sockets.
+        let config = SocketConfig::new().with_only_v6(true);
+        let listener = TcpListener::bind_with(ctx.runtime_ref(), any_local_address(), &config)
+            .await
+            .unwrap();
+        drop(listener);
+    }
+
+    let actor = actor_fn(actor);
+    let actor_ref = try_spawn_local(NoSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
 #[test]
 fn listener_from_std() {
     async fn actor(ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
@@ -262,3 +262,95 @@ fn new_actor_error() {
 
     join_many(&[server_ref, stream_ref], Duration::from_secs(1)).unwrap();
 }
+
+/// Actor that keeps the connection open until the peer closes it.
+async fn hold_actor<RT>(_: actor::Context<!, RT>, stream: TcpStream)
+where
+    RT: rt::Access,
+{
+    let buf = stream.recv(Vec::with_capacity(8)).await.unwrap();
+    assert!(buf.is_empty());
+}
+
+/// Connects twice to `address`, expecting the second connection to be
+/// rejected, i.e. closed with `response` written to it.
+async fn rejected_actor<M, RT>(
+    mut ctx: actor::Context<M, RT>,
+    address: SocketAddr,
+    response: &'static [u8],
+    server_ref: ActorRef<tcp::server::Message>,
+) where
+    RT: rt::Access + Clone,
+{
+    let stream1 = tcp_connect(&mut ctx, address).await.unwrap();
+    let stream2 = tcp_connect(&mut ctx, address).await.unwrap();
+
+    if !response.is_empty() {
+        let buf = stream2
+            .recv_n(Vec::with_capacity(response.len() + 1), response.len())
+            .await
+            .unwrap();
+        assert_eq!(buf, response);
+    }
+    // Connection should be closed after the response.
+    let buf = stream2.recv(Vec::with_capacity(8)).await.unwrap();
+    assert!(buf.is_empty());
+
+    drop(stream1);
+    server_ref.send(Terminate).await.unwrap();
+}
+
+#[test]
+fn max_connections() {
+    let server = tcp::server::setup(
+        any_local_address(),
+        |err| panic!("unexpect error: {err}"),
+        actor_fn(hold_actor),
+        ActorOptions::default(),
+    )
+    .unwrap()
+    .with_max_connections(1)
+    .with_rejection_response(b"busy");
+    let address = server.local_addr();
+    let server_ref =
+        try_spawn_local(PanicSupervisor, server.clone(), (), ActorOptions::default()).unwrap();
+
+    let client = actor_fn(rejected_actor);
+    let client_ref = try_spawn_local(
+        NoSupervisor,
+        client,
+        (address, &b"busy"[..], server_ref.clone()),
+        ActorOptions::default(),
+    )
+    .unwrap();
+
+    join_many(&[server_ref, client_ref], Duration::from_secs(1)).unwrap();
+    assert_eq!(server.rejected_connections(), 1);
+}
+
+#[test]
+fn max_accept_rate() {
+    let server = tcp::server::setup(
+        any_local_address(),
+        |err| panic!("unexpect error: {err}"),
+        actor_fn(hold_actor),
+        ActorOptions::default(),
+    )
+    .unwrap()
+    .with_max_accept_rate(1);
+    let address = server.local_addr();
+    let server_ref =
+        try_spawn_local(PanicSupervisor, server.clone(), (), ActorOptions::default()).unwrap();
+
+    let client = actor_fn(rejected_actor);
+    let client_ref = try_spawn_local(
+        NoSupervisor,
+        client,
+        (address, &b""[..], server_ref.clone()),
+        ActorOptions::default(),
+    )
+    .unwrap();
+
+    join_many(&[server_ref, client_ref], Duration::from_secs(1)).unwrap();
+    assert_eq!(server.rejected_connections(), 1);
+}
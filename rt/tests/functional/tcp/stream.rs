@@ -3,14 +3,18 @@
 use std::cmp::min;
 use std::io::{self, IoSlice, Read, Write};
 use std::net::{self, Shutdown, SocketAddr};
+use std::thread::sleep;
 use std::time::Duration;
 
 use heph::actor::{self, actor_fn};
 use heph::actor_ref::ActorRef;
 use heph::supervisor::NoSupervisor;
-use heph_rt::net::{TcpListener, TcpStream};
+use heph_rt::io::ReadBufPool;
+use heph_rt::net::tcp::stream::KeepAlive;
+use heph_rt::net::{SocketConfig, TcpListener, TcpStream};
 use heph_rt::spawn::ActorOptions;
 use heph_rt::test::{block_on_local_actor, join, join_many, try_spawn_local, PanicSupervisor};
+use heph_rt::util::next;
 use heph_rt::ThreadLocal;
 
 use crate::util::{any_local_address, refused_address};
@@ -53,9 +57,25 @@ fn smoke() {
         stream.set_nodelay(!nodelay).unwrap();
         assert_eq!(stream.nodelay().unwrap(), !nodelay);
 
-        let keepalive = stream.keepalive().unwrap();
-        stream.set_keepalive(!keepalive).unwrap();
-        assert_eq!(stream.keepalive().unwrap(), !keepalive);
+        let keepalive = KeepAlive::new()
+            .with_time(Duration::from_secs(120))
+            .with_interval(Duration::from_secs(5))
+            .with_retries(4);
+        stream.set_keepalive(Some(keepalive)).unwrap();
+        assert_eq!(stream.keepalive().unwrap(), Some(keepalive));
+        // Parameters not set should be left unchanged.
+        let update = KeepAlive::new().with_retries(2);
+        stream.set_keepalive(Some(update)).unwrap();
+        let got = stream.keepalive().unwrap().unwrap();
+        assert_eq!(got, keepalive.with_retries(2));
+        stream.set_keepalive(None).unwrap();
+        assert_eq!(stream.keepalive().unwrap(), None);
+
+        let timeout = Duration::from_secs(30);
+        stream.set_user_timeout(Some(timeout)).unwrap();
+        assert_eq!(stream.user_timeout().unwrap(), Some(timeout));
+        stream.set_user_timeout(None).unwrap();
+        assert_eq!(stream.user_timeout().unwrap(), None);
 
         assert!(stream.take_error().unwrap().is_none());
 
@@ -107,6 +127,35 @@ fn connect() {
     join(&actor_ref, Duration::from_secs(1)).unwrap();
 }
 
+#[test]
+fn connect_with_config() {
+    async fn actor(ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
+        let config = SocketConfig::new()
+            .with_ttl(42)
+            .with_nodelay(true)
+            .with_keepalive(KeepAlive::new().with_retries(3))
+            .with_user_timeout(Duration::from_secs(10))
+            .with_send_buffer_size(32 * 1024);
+        let stream = TcpStream::connect_with(ctx.runtime_ref(), address, &config).await?;
+        assert_eq!(stream.ttl()?, 42);
+        assert!(stream.nodelay()?);
+        assert_eq!(stream.keepalive()?.unwrap().retries(), Some(3));
+        assert_eq!(stream.user_timeout()?, Some(Duration::from_secs(10)));
+        Ok(())
+    }
+
+    let listener = net::TcpListener::bind(any_local_address()).unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let actor = actor_fn(actor);
+    let actor_ref =
+        try_spawn_local(PanicSupervisor, actor, address, ActorOptions::default()).unwrap();
+
+    let (stream, _) = listener.accept().unwrap();
+    drop(stream);
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
 #[test]
 fn stream_from_std() {
     async fn actor(ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
@@ -182,6 +231,43 @@ fn recv() {
     join(&actor_ref, Duration::from_secs(1)).unwrap();
 }
 
+#[test]
+fn recv_stream() {
+    async fn actor(ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
+        let stream = TcpStream::connect(ctx.runtime_ref(), address).await?;
+
+        let pool = ReadBufPool::new(ctx.runtime_ref(), 4, 64)?;
+        let mut bufs = stream.recv_stream(pool);
+        let mut received = Vec::new();
+        loop {
+            let buf = next(&mut bufs).await.expect("missing buffer")?;
+            if buf.is_empty() {
+                // The stream is dropped.
+                break;
+            }
+            received.extend_from_slice(&buf);
+        }
+        assert_eq!(received, [DATA, DATA].concat());
+        assert!(next(&mut bufs).await.is_none());
+
+        Ok(())
+    }
+
+    let listener = net::TcpListener::bind(any_local_address()).unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let actor = actor_fn(actor);
+    let actor_ref =
+        try_spawn_local(PanicSupervisor, actor, address, ActorOptions::default()).unwrap();
+
+    let (mut stream, _) = listener.accept().unwrap();
+    stream.write_all(DATA).unwrap();
+    stream.write_all(DATA).unwrap();
+    drop(stream);
+
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
 #[test]
 fn recv_n_read_exact_amount() {
     async fn actor(ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
@@ -674,6 +760,66 @@ fn peek() {
     join(&actor_ref, Duration::from_secs(1)).unwrap();
 }
 
+#[test]
+fn recv_exact_into() {
+    fn frame_len(header: [u8; 2]) -> io::Result<usize> {
+        Ok(2 + usize::from(u16::from_be_bytes(header)))
+    }
+
+    async fn actor(ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
+        let stream = TcpStream::connect(ctx.runtime_ref(), address).await?;
+
+        // Buffer too small for the frame, shouldn't remove it from the stream.
+        let buf = Vec::with_capacity(4);
+        let err = stream.recv_exact_into(buf, frame_len).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut buf = Vec::with_capacity(128);
+        buf = stream.recv_exact_into(buf, frame_len).await?;
+        assert_eq!(&buf[..2], &[0, DATA.len() as u8]);
+        assert_eq!(&buf[2..], DATA);
+
+        // Empty frame.
+        buf.clear();
+        buf = stream.recv_exact_into(buf, frame_len).await?;
+        assert_eq!(buf, [0, 0]);
+
+        // Header send in multiple parts.
+        buf.clear();
+        buf = stream.recv_exact_into(buf, frame_len).await?;
+        assert_eq!(&buf[2..], DATA);
+
+        // The stream is dropped so we should receive nothing.
+        buf.clear();
+        let buf = stream.recv_exact_into(buf, frame_len).await?;
+        assert!(buf.is_empty());
+
+        Ok(())
+    }
+
+    let listener = net::TcpListener::bind(any_local_address()).unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let actor = actor_fn(actor);
+    let actor_ref =
+        try_spawn_local(PanicSupervisor, actor, address, ActorOptions::default()).unwrap();
+
+    let (mut stream, _) = listener.accept().unwrap();
+    stream.write_all(&[0, DATA.len() as u8]).unwrap();
+    stream.write_all(DATA).unwrap();
+    stream.write_all(&[0, 0]).unwrap();
+    stream.flush().unwrap();
+    sleep(Duration::from_millis(100));
+    stream.write_all(&[0]).unwrap();
+    stream.flush().unwrap();
+    sleep(Duration::from_millis(100));
+    stream.write_all(&[DATA.len() as u8]).unwrap();
+    stream.write_all(DATA).unwrap();
+    drop(stream);
+
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
 /* TODO: add back `sendfile(2)` support.
 #[test]
 fn send_file() {
@@ -2,18 +2,22 @@
 
 use std::future::pending;
 use std::future::poll_fn;
+use std::io::{self, Read, Write};
 use std::mem::size_of;
+use std::net::{self, SocketAddr};
 use std::pin::Pin;
 use std::task::{self, Poll};
+use std::thread;
 use std::time::{Duration, Instant};
 
 use heph::actor::{self, actor_fn, Actor, NewActor};
 use heph::actor_ref::ActorGroup;
 use heph::supervisor::NoSupervisor;
+use heph_rt::net::TcpStream;
 use heph_rt::spawn::{ActorOptions, FutureOptions};
 use heph_rt::test::{
-    self, join, join_all, join_many, size_of_actor, size_of_actor_val, spawn_future, try_spawn,
-    try_spawn_local, JoinResult,
+    self, block_on_local_actor, inject_io_faults, join, join_all, join_many, size_of_actor,
+    size_of_actor_val, spawn_future, try_spawn, try_spawn_local, IoFaults, JoinResult,
 };
 use heph_rt::timer::Timer;
 use heph_rt::{self as rt, ThreadLocal};
@@ -36,7 +40,7 @@ fn test_size_of_actor() {
         /* Nothing. */
     }
 
-    assert_eq!(size_of_actor_val(&actor_fn(actor1)), 32);
+    assert_eq!(size_of_actor_val(&actor_fn(actor1)), 72);
 
     struct Na;
 
@@ -243,6 +247,93 @@ fn join_all_local_and_thread_safe_timeout() {
     assert_within_margin(start, TIMEOUT);
 }
 
+const DATA: &[u8] = b"Hello world";
+
+/// Actor that sends [`DATA`] to `address` and receives it back.
+async fn echo_actor(ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) {
+    let stream = TcpStream::connect(ctx.runtime_ref(), address)
+        .await
+        .unwrap();
+    stream.send_all(DATA).await.unwrap();
+    let buf = stream
+        .recv_n(Vec::with_capacity(DATA.len()), DATA.len())
+        .await
+        .unwrap();
+    assert_eq!(buf, DATA);
+}
+
+/// Accepts a single connection on `listener` and echos [`DATA`].
+fn echo_server(listener: &net::TcpListener) {
+    let (mut stream, _) = listener.accept().unwrap();
+    let mut buf = [0; DATA.len()];
+    stream.read_exact(&mut buf).unwrap();
+    stream.write_all(&buf).unwrap();
+}
+
+#[test]
+fn inject_io_faults_no_faults() {
+    let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let handle = thread::spawn(move || echo_server(&listener));
+    let actor = inject_io_faults(actor_fn(echo_actor), IoFaults::new());
+    block_on_local_actor(actor, address);
+    handle.join().unwrap();
+}
+
+#[test]
+fn inject_io_faults_errors() {
+    async fn actor(ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) {
+        let stream = TcpStream::connect(ctx.runtime_ref(), address)
+            .await
+            .unwrap();
+        let err = stream.send_all(DATA).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+        let err = stream.recv(Vec::with_capacity(1)).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+    }
+
+    let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let faults = IoFaults::new().with_errors(100, io::ErrorKind::ConnectionReset);
+    block_on_local_actor(inject_io_faults(actor_fn(actor), faults), address);
+}
+
+#[test]
+fn inject_io_faults_short_reads() {
+    async fn actor(ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) {
+        let stream = TcpStream::connect(ctx.runtime_ref(), address)
+            .await
+            .unwrap();
+        stream.send_all(DATA).await.unwrap();
+        let buf = stream.recv(Vec::with_capacity(DATA.len())).await.unwrap();
+        assert!(!buf.is_empty() && buf.len() <= 2, "read: {}", buf.len());
+        assert_eq!(buf, &DATA[..buf.len()]);
+        // Reading `n` bytes should still work.
+        let n = DATA.len() - buf.len();
+        let buf = stream.recv_n(buf, n).await.unwrap();
+        assert_eq!(buf, DATA);
+    }
+
+    let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let handle = thread::spawn(move || echo_server(&listener));
+    let faults = IoFaults::new().with_short_reads(100, 2);
+    block_on_local_actor(inject_io_faults(actor_fn(actor), faults), address);
+    handle.join().unwrap();
+}
+
+#[test]
+fn inject_io_faults_would_block_and_latency() {
+    let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let handle = thread::spawn(move || echo_server(&listener));
+    let faults = IoFaults::new()
+        .with_would_block(50)
+        .with_latency(50, Duration::from_millis(10));
+    block_on_local_actor(inject_io_faults(actor_fn(echo_actor), faults), address);
+    handle.join().unwrap();
+}
+
 /// Assert that less then `expected` time has elapsed since `start`.
 #[track_caller]
 fn assert_within_margin(start: Instant, expected: Duration) {
@@ -8,7 +8,7 @@ use heph::actor::{self, actor_fn};
 use heph::supervisor::NoSupervisor;
 use heph_rt::spawn::ActorOptions;
 use heph_rt::test::{block_on_local_actor, poll_future, poll_next};
-use heph_rt::timer::{Deadline, DeadlinePassed, Interval, Timer};
+use heph_rt::timer::{Deadline, DeadlinePassed, Interval, Sleep, Timer};
 use heph_rt::util::next;
 use heph_rt::{self as rt, Runtime, RuntimeRef, ThreadLocal, ThreadSafe};
 
@@ -25,6 +25,8 @@ fn size() {
     assert_size::<Deadline<(), ThreadSafe>>(40);
     assert_size::<Interval<ThreadLocal>>(56);
     assert_size::<Interval<ThreadSafe>>(56);
+    assert_size::<Sleep<ThreadLocal>>(56);
+    assert_size::<Sleep<ThreadSafe>>(56);
     assert_size::<DeadlinePassed>(0);
 }
 
@@ -70,6 +72,32 @@ fn timer_ref_rt() {
     block_on_local_actor(actor_fn(actor), ());
 }
 
+#[test]
+fn sleep_reset() {
+    async fn actor(ctx: actor::Context<!, ThreadLocal>) {
+        let start = Instant::now();
+        let mut sleep = Sleep::after(ctx.runtime_ref().clone(), SMALL_TIMEOUT);
+        assert!(!sleep.has_passed());
+        expect_pending(poll_future(Pin::new(&mut sleep)));
+
+        // Push back the deadline.
+        sleep.reset(start + TIMEOUT);
+        assert_eq!(sleep.deadline(), start + TIMEOUT);
+        let _ = (&mut sleep).await;
+        assert!(sleep.has_passed());
+        assert!(start.elapsed() >= TIMEOUT);
+
+        // Can be reused after the deadline passed.
+        let start = Instant::now();
+        sleep.reset_after(SMALL_TIMEOUT);
+        assert!(!sleep.has_passed());
+        let _ = (&mut sleep).await;
+        assert!(start.elapsed() >= SMALL_TIMEOUT);
+    }
+
+    block_on_local_actor(actor_fn(actor), ());
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct AlwaysPending;
 
@@ -5,9 +5,13 @@ use std::net::SocketAddr;
 use std::time::Duration;
 
 use heph::actor::{self, actor_fn, Actor, NewActor};
-use heph_rt::net::udp::{UdpSocket, Unconnected};
+use heph_rt::io::ReadBufPool;
+use heph_rt::net::tcp::stream::KeepAlive;
+use heph_rt::net::udp::{ErrorOrigin, UdpSocket, Unconnected};
+use heph_rt::net::SocketConfig;
 use heph_rt::spawn::ActorOptions;
 use heph_rt::test::{block_on_local_actor, join, try_spawn_local, PanicSupervisor};
+use heph_rt::util::next;
 use heph_rt::ThreadLocal;
 
 use crate::util::{any_local_address, any_local_ipv6_address};
@@ -61,6 +65,46 @@ where
     join(&actor_ref, Duration::from_secs(1)).unwrap();
 }
 
+#[test]
+fn recv_stream() {
+    async fn actor(
+        ctx: actor::Context<!, ThreadLocal>,
+        peer_address: SocketAddr,
+    ) -> io::Result<()> {
+        let local_address = SocketAddr::new(peer_address.ip(), 0);
+        let socket = UdpSocket::bind(ctx.runtime_ref(), local_address).await?;
+        let socket = socket.connect(peer_address).await?;
+        // Let the peer know our address.
+        let (_, bytes_written) = socket.send(DATA).await?;
+        assert_eq!(bytes_written, DATA.len());
+
+        let pool = ReadBufPool::new(ctx.runtime_ref(), 4, 64)?;
+        let mut bufs = socket.recv_stream(pool);
+        for _ in 0..3 {
+            let buf = next(&mut bufs).await.expect("missing datagram")?;
+            assert_eq!(buf.as_slice(), DATA);
+        }
+
+        Ok(())
+    }
+
+    let echo_socket = std::net::UdpSocket::bind(any_local_address()).unwrap();
+    let address = echo_socket.local_addr().unwrap();
+
+    let actor = actor_fn(actor);
+    let actor_ref =
+        try_spawn_local(PanicSupervisor, actor, address, ActorOptions::default()).unwrap();
+
+    let mut buf = [0; DATA.len() + 1];
+    let (bytes_read, peer_address) = echo_socket.recv_from(&mut buf).unwrap();
+    assert_eq!(&buf[..bytes_read], DATA);
+    for _ in 0..3 {
+        _ = echo_socket.send_to(DATA, peer_address).unwrap();
+    }
+
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
 async fn unconnected_udp_actor(
     ctx: actor::Context<!, ThreadLocal>,
     peer_address: SocketAddr,
@@ -110,6 +154,138 @@ async fn connected_udp_actor(
     Ok(())
 }
 
+#[test]
+fn bind_with_config() {
+    async fn actor(ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+        // TCP only options should be ignored.
+        let config = SocketConfig::new()
+            .with_ttl(42)
+            .with_nodelay(true)
+            .with_keepalive(KeepAlive::new().with_time(Duration::from_secs(60)))
+            .with_send_buffer_size(32 * 1024);
+        let socket = UdpSocket::bind_with(ctx.runtime_ref(), any_local_address(), &config).await?;
+        let ipv6_socket =
+            UdpSocket::bind_with(ctx.runtime_ref(), any_local_ipv6_address(), &config).await?;
+        drop((socket, ipv6_socket));
+        Ok(())
+    }
+
+    block_on_local_actor(actor_fn(actor), ());
+}
+
+#[test]
+fn recv_error_ipv4() {
+    test_recv_error(any_local_address(), ErrorOrigin::Icmp, (3, 3));
+}
+
+#[test]
+fn recv_error_ipv6() {
+    test_recv_error(any_local_ipv6_address(), ErrorOrigin::Icmp6, (1, 4));
+}
+
+fn test_recv_error(local_address: SocketAddr, origin: ErrorOrigin, icmp: (u8, u8)) {
+    async fn actor(
+        ctx: actor::Context<!, ThreadLocal>,
+        local_address: SocketAddr,
+        origin: ErrorOrigin,
+        icmp: (u8, u8),
+    ) -> io::Result<()> {
+        // Get an address on which no one is listening.
+        let peer_address = std::net::UdpSocket::bind(local_address)?.local_addr()?;
+
+        let socket = UdpSocket::bind(ctx.runtime_ref(), local_address).await?;
+        socket.set_recv_error(true)?;
+        assert!(socket.try_recv_error()?.is_none());
+        let socket = socket.connect(peer_address).await?;
+        let (_, bytes_written) = socket.send(DATA).await?;
+        assert_eq!(bytes_written, DATA.len());
+
+        let err = socket.recv_error().await?;
+        assert_eq!(err.error().kind(), io::ErrorKind::ConnectionRefused);
+        assert_eq!(err.origin(), origin);
+        assert_eq!((err.icmp_type(), err.icmp_code()), icmp);
+        assert_eq!(err.mtu(), None);
+        assert_eq!(err.destination(), Some(peer_address));
+        assert_eq!(err.offender().map(|a| a.ip()), Some(peer_address.ip()));
+        assert!(socket.try_recv_error()?.is_none());
+        Ok(())
+    }
+
+    block_on_local_actor(actor_fn(actor), (local_address, origin, icmp));
+}
+
+#[test]
+fn segmented_ipv4() {
+    test_segmented(any_local_address());
+}
+
+#[test]
+fn segmented_ipv6() {
+    test_segmented(any_local_ipv6_address());
+}
+
+fn test_segmented(local_address: SocketAddr) {
+    async fn actor(
+        ctx: actor::Context<!, ThreadLocal>,
+        local_address: SocketAddr,
+    ) -> io::Result<()> {
+        const SEGMENT_SIZE: usize = 4;
+
+        let peer = std::net::UdpSocket::bind(local_address)?;
+        let peer_address = peer.local_addr()?;
+
+        let socket = UdpSocket::bind(ctx.runtime_ref(), local_address).await?;
+        socket.set_gro(true)?;
+        let local_address = socket.local_addr()?;
+
+        // Kernel splits the data into multiple datagrams.
+        let (_, bytes_written) = socket
+            .send_to_segmented(DATA, SEGMENT_SIZE as u16, peer_address)
+            .await?;
+        assert_eq!(bytes_written, DATA.len());
+        let mut buf = vec![0; DATA.len() + 2];
+        for expected in DATA.chunks(SEGMENT_SIZE) {
+            let (n, address) = peer.recv_from(&mut buf)?;
+            assert_eq!(&buf[..n], expected);
+            assert_eq!(address, local_address);
+        }
+
+        // Kernel may or may not coalesce the datagrams.
+        for chunk in DATA.chunks(SEGMENT_SIZE) {
+            _ = peer.send_to(chunk, local_address)?;
+        }
+        let mut received = Vec::new();
+        while received.len() < DATA.len() {
+            let (buf, segment_size, address) = socket
+                .recv_from_segmented(Vec::with_capacity(DATA.len() + 2))
+                .await?;
+            assert_eq!(address, peer_address);
+            assert!(segment_size <= SEGMENT_SIZE);
+            received.extend_from_slice(&buf);
+        }
+        assert_eq!(received, DATA);
+
+        let socket = socket.connect(peer_address).await?;
+        let (_, bytes_written) = socket.send_segmented(DATA, SEGMENT_SIZE as u16).await?;
+        assert_eq!(bytes_written, DATA.len());
+        for expected in DATA.chunks(SEGMENT_SIZE) {
+            let n = peer.recv(&mut buf)?;
+            assert_eq!(&buf[..n], expected);
+        }
+
+        peer.connect(local_address)?;
+        _ = peer.send(DATA)?;
+        let (buf, segment_size) = socket
+            .recv_segmented(Vec::with_capacity(DATA.len() + 2))
+            .await?;
+        assert_eq!(buf, DATA);
+        assert_eq!(segment_size, DATA.len());
+        Ok(())
+    }
+
+    block_on_local_actor(actor_fn(actor), local_address);
+}
+
 #[test]
 fn reconnecting_ipv4() {
     test_reconnecting(any_local_address())
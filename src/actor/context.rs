@@ -1,13 +1,17 @@
 //! Module containing the `Context` and related types.
 
-use std::fmt;
 use std::future::Future;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
 use std::task::{self, Poll};
+use std::time::Instant;
+use std::{fmt, mem};
 
 use heph_inbox::{self as inbox, Receiver, RecvValue};
 
-use crate::actor_ref::ActorRef;
+use crate::actor::LocalStorage;
+use crate::actor_ref::{ActorRef, Deadline};
 
 /// The context in which an actor is executed.
 ///
@@ -20,13 +24,47 @@ pub struct Context<M, RT = ()> {
     inbox: Receiver<M>,
     /// Runtime access.
     rt: RT,
+    /// Actor-local storage.
+    storage: LocalStorage,
+    /// If set the `storage` is moved into it when the context is dropped, so
+    /// it can be reused by the restarted actor.
+    keep_storage: Option<Arc<Mutex<LocalStorage>>>,
+    /// Cooperative scheduling budget.
+    budget: Budget,
+    /// Deadline of the last message received using
+    /// [`Context::receive_next_unexpired`].
+    deadline: Option<Instant>,
 }
 
 impl<M, RT> Context<M, RT> {
     /// Create a new `actor::Context`.
     #[doc(hidden)] // Not part of the stable API.
     pub const fn new(inbox: Receiver<M>, rt: RT) -> Context<M, RT> {
-        Context { inbox, rt }
+        Context {
+            inbox,
+            rt,
+            storage: LocalStorage::new(),
+            keep_storage: None,
+            budget: Budget::new(None),
+            deadline: None,
+        }
+    }
+
+    /// Set the cooperative scheduling budget, see
+    /// [`ActorFutureBuilder::with_budget`].
+    ///
+    /// [`ActorFutureBuilder::with_budget`]: crate::future::ActorFutureBuilder::with_budget
+    pub(crate) const fn with_budget(mut self, budget: Option<NonZeroUsize>) -> Context<M, RT> {
+        self.budget = Budget::new(budget);
+        self
+    }
+
+    /// Use the local storage in `slot`, and move it back into `slot` once the
+    /// context is dropped.
+    pub(crate) fn keep_storage_in(mut self, slot: Arc<Mutex<LocalStorage>>) -> Context<M, RT> {
+        self.storage = mem::take(&mut *slot.lock().unwrap_or_else(PoisonError::into_inner));
+        self.keep_storage = Some(slot);
+        self
     }
 
     /// Attempt to receive the next message.
@@ -35,6 +73,10 @@ impl<M, RT> Context<M, RT> {
     /// actor wants to wait until a message is received [`receive_next`] can be
     /// used, which returns a `Future<Output = M>`.
     ///
+    /// If the actor was stopped, see [`ActorRef::stop`], this returns
+    /// [`RecvError::Stopped`] once all messages send before stopping are
+    /// received.
+    ///
     /// [`receive_next`]: Context::receive_next
     ///
     /// # Examples
@@ -54,12 +96,17 @@ impl<M, RT> Context<M, RT> {
     /// # _ = greeter_actor; // Silence dead code warnings.
     /// ```
     pub fn try_receive_next(&mut self) -> Result<M, RecvError> {
-        self.inbox.try_recv().map_err(RecvError::from)
+        self.inbox
+            .try_recv()
+            .map_err(|err| RecvError::from(err, &self.inbox))
     }
 
     /// Receive the next message.
     ///
     /// This returns a [`Future`] that will complete once a message is ready.
+    /// The future returns [`NoMessages`] if all actor references are dropped,
+    /// or once the actor was stopped and all messages send before are
+    /// received, see [`ActorRef::stop`] and [`Context::is_stopped`].
     ///
     /// # Examples
     ///
@@ -78,6 +125,50 @@ impl<M, RT> Context<M, RT> {
     pub fn receive_next<'ctx>(&'ctx mut self) -> ReceiveMessage<'ctx, M> {
         ReceiveMessage {
             recv: self.inbox.recv(),
+            budget: &mut self.budget,
+        }
+    }
+
+    /// Returns `true` if the actor was stopped, see [`ActorRef::stop`].
+    ///
+    /// Once stopped [`Context::receive_next`] and [`Context::try_receive_next`]
+    /// only return the messages send before the actor was stopped.
+    pub fn is_stopped(&self) -> bool {
+        self.inbox.is_closed()
+    }
+
+    /// Yield control back to the scheduler.
+    ///
+    /// The returned [`Future`] returns [`Poll::Pending`] once, marking the
+    /// actor as ready to run again, allowing other actors and futures to run
+    /// in the meantime. This resets the cooperative scheduling budget, see
+    /// [`ActorFutureBuilder::with_budget`].
+    ///
+    /// [`ActorFutureBuilder::with_budget`]: crate::future::ActorFutureBuilder::with_budget
+    ///
+    /// # Examples
+    ///
+    /// An actor that yields after each chunk of a long running computation.
+    ///
+    /// ```
+    /// use heph::actor;
+    ///
+    /// async fn sum_actor(mut ctx: actor::Context<Vec<u64>>) {
+    ///     while let Ok(values) = ctx.receive_next().await {
+    ///         let mut sum = 0;
+    ///         for chunk in values.chunks(1024) {
+    ///             sum += chunk.iter().sum::<u64>();
+    ///             ctx.yield_now().await;
+    ///         }
+    ///         println!("sum: {sum}");
+    ///     }
+    /// }
+    /// # _ = sum_actor; // Silence dead code warnings.
+    /// ```
+    pub fn yield_now<'ctx>(&'ctx mut self) -> YieldNow<'ctx> {
+        YieldNow {
+            budget: &mut self.budget,
+            yielded: false,
         }
     }
 
@@ -96,12 +187,104 @@ impl<M, RT> Context<M, RT> {
         &self.rt
     }
 
+    /// Get mutable access to the actor-local storage.
+    ///
+    /// See [`LocalStorage`] for more information.
+    pub fn local_storage(&mut self) -> &mut LocalStorage {
+        &mut self.storage
+    }
+
+    /// Get access to the actor-local storage.
+    pub const fn local_storage_ref(&self) -> &LocalStorage {
+        &self.storage
+    }
+
     #[doc(hidden)] // Not part of the stable API.
     pub fn pid(&self) -> usize {
         self.inbox.id().as_usize()
     }
 }
 
+impl<M, RT> Context<Deadline<M>, RT> {
+    /// Attempt to receive the next message of which the deadline didn't pass.
+    ///
+    /// Same as [`Context::try_receive_next`], but drops all messages of which
+    /// the deadline passed. See [Deadlines] for more details.
+    ///
+    /// [Deadlines]: crate::actor_ref#deadlines
+    pub fn try_receive_next_unexpired(&mut self) -> Result<Deadline<M>, RecvError> {
+        loop {
+            if let Some(msg) = self.try_receive_next()?.unexpired() {
+                self.deadline = Some(msg.deadline());
+                return Ok(msg);
+            }
+        }
+    }
+
+    /// Receive the next message of which the deadline didn't pass.
+    ///
+    /// Same as [`Context::receive_next`], but drops all messages of which the
+    /// deadline passed. See [Deadlines] for more details.
+    ///
+    /// [Deadlines]: crate::actor_ref#deadlines
+    ///
+    /// # Examples
+    ///
+    /// An actor that makes a request to another actor, using the deadline of
+    /// the message it received.
+    ///
+    /// ```
+    /// use heph::actor;
+    /// use heph::actor_ref::{ActorRef, Deadline, RpcMessage};
+    ///
+    /// async fn lookup_actor(
+    ///     mut ctx: actor::Context<Deadline<String>>,
+    ///     db: ActorRef<Deadline<RpcMessage<String, usize>>>,
+    /// ) {
+    ///     while let Ok(msg) = ctx.receive_next_unexpired().await {
+    ///         let deadline = msg.deadline();
+    ///         match db.rpc_with_deadline(msg.into_inner(), deadline).await {
+    ///             Ok(value) => println!("Got value: {value}"),
+    ///             Err(err) => eprintln!("Lookup failed: {err}"),
+    ///         }
+    ///     }
+    /// }
+    /// # _ = lookup_actor; // Silence dead code warnings.
+    /// ```
+    pub fn receive_next_unexpired<'ctx>(&'ctx mut self) -> ReceiveUnexpired<'ctx, M> {
+        ReceiveUnexpired {
+            recv: ReceiveMessage {
+                recv: self.inbox.recv(),
+                budget: &mut self.budget,
+            },
+            deadline: &mut self.deadline,
+        }
+    }
+
+    /// Returns the deadline of the last message received using
+    /// [`Context::receive_next_unexpired`] or
+    /// [`Context::try_receive_next_unexpired`], or `None` if no message was
+    /// received yet.
+    ///
+    /// This can be used to pass the remaining time budget of the message that
+    /// is being handled on to requests made to other actors. See [Deadlines]
+    /// for more details.
+    ///
+    /// [Deadlines]: crate::actor_ref#deadlines
+    pub const fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+}
+
+impl<M, RT> Drop for Context<M, RT> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.keep_storage.take() {
+            let storage = mem::take(&mut self.storage);
+            *slot.lock().unwrap_or_else(PoisonError::into_inner) = storage;
+        }
+    }
+}
+
 /// Error returned in case receiving a value from an actor's inbox fails.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum RecvError {
@@ -109,12 +292,17 @@ pub enum RecvError {
     Empty,
     /// All [`ActorRef`]s  are disconnected and the inbox is empty.
     Disconnected,
+    /// The actor was stopped, see [`ActorRef::stop`], and the inbox is empty.
+    Stopped,
 }
 
 impl RecvError {
-    pub(crate) const fn from(err: inbox::RecvError) -> RecvError {
+    /// Convert `err`, returned by `inbox`.
+    pub(crate) fn from<T>(err: inbox::RecvError, inbox: &Receiver<T>) -> RecvError {
         match err {
             inbox::RecvError::Empty => RecvError::Empty,
+            // The inbox is closed when the actor is stopped.
+            inbox::RecvError::Disconnected if inbox.is_closed() => RecvError::Stopped,
             inbox::RecvError::Disconnected => RecvError::Disconnected,
         }
     }
@@ -129,20 +317,220 @@ impl RecvError {
 #[must_use = "futures do nothing unless you `.await` or poll them"]
 pub struct ReceiveMessage<'ctx, M> {
     recv: RecvValue<'ctx, M>,
+    budget: &'ctx mut Budget,
 }
 
 impl<'ctx, M> Future for ReceiveMessage<'ctx, M> {
     type Output = Result<M, NoMessages>;
 
     fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
-        Pin::new(&mut self.recv)
-            .poll(ctx)
-            .map(|r| r.ok_or(NoMessages))
+        let this = &mut *self;
+        if this.budget.is_exhausted() {
+            // Give other actors a chance to run.
+            this.budget.reset();
+            ctx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        match Pin::new(&mut this.recv).poll(ctx) {
+            Poll::Ready(Some(msg)) => {
+                this.budget.consume();
+                Poll::Ready(Ok(msg))
+            }
+            Poll::Ready(None) => Poll::Ready(Err(NoMessages)),
+            Poll::Pending => {
+                // The actor will be suspended until a new message arrives.
+                this.budget.reset();
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<'ctx, M> ReceiveMessage<'ctx, M> {
+    /// Receive the next message or a signal, whichever is ready first.
+    ///
+    /// This waits on both the next message and the `signal` future, e.g. a
+    /// process signal received using heph-rt's `SignalReceiver`. If both are
+    /// ready the signal is returned, leaving the message in the inbox.
+    ///
+    /// # Examples
+    ///
+    /// An actor that prints messages until it receives a signal.
+    ///
+    /// ```
+    /// use std::future::Future;
+    ///
+    /// use heph::actor::{self, MessageOrSignal};
+    ///
+    /// async fn print_actor<S>(mut ctx: actor::Context<String>, mut signal: S)
+    /// where
+    ///     S: Future<Output = ()> + Unpin,
+    /// {
+    ///     loop {
+    ///         match ctx.receive_next().or_signal(&mut signal).await {
+    ///             Ok(MessageOrSignal::Message(msg)) => println!("Got a message: {msg}"),
+    ///             Ok(MessageOrSignal::Signal(())) | Err(_) => break,
+    ///         }
+    ///     }
+    /// }
+    /// # _ = print_actor::<std::future::Pending<()>>; // Silence dead code warnings.
+    /// ```
+    pub fn or_signal<Fut>(self, signal: Fut) -> ReceiveMessageOrSignal<'ctx, M, Fut>
+    where
+        Fut: Future,
+    {
+        ReceiveMessageOrSignal { recv: self, signal }
+    }
+}
+
+/// Future to receive a single message of which the deadline didn't pass.
+///
+/// The implementation behind [`actor::Context::receive_next_unexpired`].
+///
+/// [`actor::Context::receive_next_unexpired`]: crate::actor::Context::receive_next_unexpired
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ReceiveUnexpired<'ctx, M> {
+    recv: ReceiveMessage<'ctx, Deadline<M>>,
+    deadline: &'ctx mut Option<Instant>,
+}
+
+impl<'ctx, M> Future for ReceiveUnexpired<'ctx, M> {
+    type Output = Result<Deadline<M>, NoMessages>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        loop {
+            match Pin::new(&mut self.recv).poll(ctx) {
+                Poll::Ready(Ok(msg)) => {
+                    if let Some(msg) = msg.unexpired() {
+                        *self.deadline = Some(msg.deadline());
+                        return Poll::Ready(Ok(msg));
+                    }
+                }
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Either a message or a signal, returned by [`ReceiveMessageOrSignal`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MessageOrSignal<M, S> {
+    /// Message received from the actor's inbox.
+    Message(M),
+    /// Signal received.
+    Signal(S),
+}
+
+/// Future to receive a single message or a signal.
+///
+/// The implementation behind [`ReceiveMessage::or_signal`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ReceiveMessageOrSignal<'ctx, M, Fut> {
+    recv: ReceiveMessage<'ctx, M>,
+    signal: Fut,
+}
+
+impl<'ctx, M, Fut> Future for ReceiveMessageOrSignal<'ctx, M, Fut>
+where
+    Fut: Future,
+{
+    type Output = Result<MessageOrSignal<M, Fut::Output>, NoMessages>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: not moving `signal`, `recv` is `Unpin`.
+        let this = unsafe { self.get_unchecked_mut() };
+        // SAFETY: not moving `signal`.
+        let signal = unsafe { Pin::new_unchecked(&mut this.signal) };
+        if let Poll::Ready(signal) = signal.poll(ctx) {
+            return Poll::Ready(Ok(MessageOrSignal::Signal(signal)));
+        }
+        match Pin::new(&mut this.recv).poll(ctx) {
+            Poll::Ready(Ok(msg)) => Poll::Ready(Ok(MessageOrSignal::Message(msg))),
+            Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Future to yield control back to the scheduler.
+///
+/// The implementation behind and [`actor::Context::yield_now`].
+///
+/// [`actor::Context::yield_now`]: crate::actor::Context::yield_now
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct YieldNow<'ctx> {
+    budget: &'ctx mut Budget,
+    yielded: bool,
+}
+
+impl<'ctx> Future for YieldNow<'ctx> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        if self.yielded {
+            Poll::Ready(())
+        } else {
+            self.yielded = true;
+            self.budget.reset();
+            ctx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+/// Cooperative scheduling budget.
+///
+/// Limits the number of messages an actor can receive before it's forced to
+/// yield back to the scheduler.
+#[derive(Debug)]
+///
+/// Uses 32 bit integers to keep the size of the [`Context`] down, budgets
+/// larger than `u32::MAX` are effectively unlimited anyway.
+struct Budget {
+    /// Maximum number of messages received before yielding, `None` means
+    /// unlimited.
+    max: Option<NonZeroU32>,
+    /// Number of messages left to receive before yielding.
+    left: u32,
+}
+
+impl Budget {
+    const fn new(max: Option<NonZeroUsize>) -> Budget {
+        let max = match max {
+            Some(max) if max.get() > u32::MAX as usize => Some(NonZeroU32::MAX),
+            #[allow(clippy::cast_possible_truncation)] // Checked above.
+            Some(max) => NonZeroU32::new(max.get() as u32),
+            None => None,
+        };
+        let left = match max {
+            Some(max) => max.get(),
+            None => 0,
+        };
+        Budget { max, left }
+    }
+
+    const fn is_exhausted(&self) -> bool {
+        self.max.is_some() && self.left == 0
+    }
+
+    fn consume(&mut self) {
+        self.left = self.left.saturating_sub(1);
+    }
+
+    fn reset(&mut self) {
+        if let Some(max) = self.max {
+            self.left = max.get();
+        }
     }
 }
 
 /// Returned when an actor's inbox has no messages and no references to the
-/// actor exists.
+/// actor exists, or when the actor was stopped (see [`ActorRef::stop`]).
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct NoMessages;
 
@@ -0,0 +1,144 @@
+//! Module containing the [`LocalStorage`] type.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Actor-local storage.
+///
+/// Type map, storing at most one value per type, that is owned by the actor's
+/// [`actor::Context`]. This can be used by helpers to stash state, e.g. a
+/// request counter or a cache, without it having to be passed to every
+/// (asynchronous) function.
+///
+/// By default the storage is dropped along with the context, i.e. when the
+/// actor is restarted the new actor starts with an empty storage. Use
+/// [`ActorFutureBuilder::with_keep_on_restart`] to keep the storage across
+/// restarts.
+///
+/// [`actor::Context`]: crate::actor::Context
+/// [`ActorFutureBuilder::with_keep_on_restart`]: crate::future::ActorFutureBuilder::with_keep_on_restart
+///
+/// # Examples
+///
+/// ```
+/// use heph::actor;
+///
+/// /// Number of messages the actor received.
+/// struct Received(usize);
+///
+/// async fn counting_actor(mut ctx: actor::Context<String>) {
+///     while let Ok(msg) = ctx.receive_next().await {
+///         count_message(&mut ctx);
+///         println!("Got a message: {msg}");
+///     }
+/// }
+///
+/// fn count_message<M, RT>(ctx: &mut actor::Context<M, RT>) {
+///     ctx.local_storage().get_or_insert_with(|| Received(0)).0 += 1;
+/// }
+/// # _ = counting_actor; // Silence dead code warnings.
+/// ```
+#[derive(Default)]
+pub struct LocalStorage {
+    /// Lazily allocated as most actors don't use the storage, boxed to keep
+    /// the size of the context small.
+    #[allow(clippy::box_collection)]
+    map: Option<Box<HashMap<TypeId, Box<dyn Any + Send + Sync>>>>,
+}
+
+impl LocalStorage {
+    /// Create an empty storage.
+    pub(crate) const fn new() -> LocalStorage {
+        LocalStorage { map: None }
+    }
+
+    /// Insert `value`, returning the previous value of the same type (if any).
+    pub fn insert<T>(&mut self, value: T) -> Option<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.map
+            .get_or_insert_with(Box::default)
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .map(downcast)
+    }
+
+    /// Returns a reference to the value of type `T`, if any.
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.map
+            .as_ref()?
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
+
+    /// Returns a mutable reference to the value of type `T`, if any.
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.map
+            .as_mut()?
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_mut())
+    }
+
+    /// Returns a mutable reference to the value of type `T`, inserting the
+    /// value returned by `f` if no value is present.
+    pub fn get_or_insert_with<T, F>(&mut self, f: F) -> &mut T
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce() -> T,
+    {
+        self.map
+            .get_or_insert_with(Box::default)
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(f()))
+            .downcast_mut()
+            .unwrap()
+    }
+
+    /// Returns `true` if a value of type `T` is present.
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.map
+            .as_ref()
+            .is_some_and(|map| map.contains_key(&TypeId::of::<T>()))
+    }
+
+    /// Remove the value of type `T`, returning it (if any).
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        self.map.as_mut()?.remove(&TypeId::of::<T>()).map(downcast)
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.map.as_ref().map_or(0, |map| map.len())
+    }
+
+    /// Returns `true` if there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all values.
+    pub fn clear(&mut self) {
+        if let Some(map) = self.map.as_mut() {
+            map.clear();
+        }
+    }
+}
+
+impl fmt::Debug for LocalStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalStorage")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Downcast `value` to `T`.
+///
+/// # Panics
+///
+/// Panics if `value` is not of type `T`, which can't happen as the values are
+/// keyed by their type id.
+fn downcast<T: 'static>(value: Box<dyn Any + Send + Sync>) -> T {
+    *value.downcast().unwrap()
+}
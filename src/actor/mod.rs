@@ -112,11 +112,17 @@ use std::pin::Pin;
 use std::task::{self, Poll};
 
 mod context;
+mod local_storage;
 #[cfg(test)]
 mod tests;
 
 #[doc(inline)]
-pub use context::{Context, NoMessages, ReceiveMessage, RecvError};
+pub use context::{
+    Context, MessageOrSignal, NoMessages, ReceiveMessage, ReceiveMessageOrSignal, ReceiveUnexpired,
+    RecvError, YieldNow,
+};
+#[doc(inline)]
+pub use local_storage::LocalStorage;
 
 /// Creating asynchronous actors.
 ///
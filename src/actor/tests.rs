@@ -1,12 +1,17 @@
 use std::any::Any;
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::future::Future;
+use std::num::NonZeroUsize;
 use std::pin::pin;
+use std::rc::Rc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::task::{self, Poll};
+use std::time::{Duration, Instant};
 
-use crate::actor::{self, actor_fn, Actor, NewActor};
+use crate::actor::{self, actor_fn, Actor, MessageOrSignal, NewActor};
+use crate::actor_ref::{ActorGroup, ActorRef, Deadline};
+use crate::future::{ActorFutureBuilder, InboxSize};
 use crate::supervisor::{NoSupervisor, Supervisor, SupervisorStrategy};
 use crate::ActorFuture;
 
@@ -125,6 +130,68 @@ fn actor_future() {
     assert_eq!(res, Poll::Ready(()));
 }
 
+async fn deadline_actor(mut ctx: actor::Context<Deadline<usize>>) {
+    assert_eq!(ctx.deadline(), None);
+    // Message 1 is expired and should be dropped.
+    let msg = ctx.receive_next_unexpired().await.unwrap();
+    assert!(!msg.is_expired());
+    assert_eq!(ctx.deadline(), Some(msg.deadline()));
+    assert_eq!(msg.into_inner(), 2);
+    let msg = ctx.try_receive_next_unexpired().unwrap();
+    assert_eq!(ctx.deadline(), Some(msg.deadline()));
+    assert_eq!(msg.into_inner(), 3);
+    let deadline = ctx.deadline();
+    // Message 4 is expired and should be dropped.
+    assert_eq!(
+        ctx.try_receive_next_unexpired(),
+        Err(actor::RecvError::Empty)
+    );
+    // Expired messages can still be received using `receive_next`.
+    let now = Instant::now();
+    ctx.actor_ref().try_send(Deadline::new(5, now)).unwrap();
+    let msg = ctx.receive_next().await.unwrap();
+    assert!(msg.is_expired());
+    assert_eq!(msg.deadline(), now);
+    // Only the `unexpired` methods update the deadline.
+    assert_eq!(ctx.deadline(), deadline);
+}
+
+#[test]
+fn actor_message_deadline() {
+    let (actor, actor_ref) = ActorFuture::new(NoSupervisor, actor_fn(deadline_actor), ()).unwrap();
+    let mut actor = pin!(actor);
+
+    let (waker, _) = task_wake_counter();
+    let mut ctx = task::Context::from_waker(&waker);
+
+    let now = Instant::now();
+    let future = now + Duration::from_secs(60);
+    actor_ref.try_send_with_deadline(1_usize, now).unwrap();
+    actor_ref.try_send_with_deadline(2_usize, future).unwrap();
+    actor_ref.try_send(Deadline::new(3_usize, future)).unwrap();
+    actor_ref.try_send_with_deadline(4_usize, now).unwrap();
+    assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Ready(()));
+}
+
+#[test]
+fn mapped_actor_ref_message_deadline() {
+    let (actor, actor_ref) = ActorFuture::new(NoSupervisor, actor_fn(deadline_actor), ()).unwrap();
+    let mut actor = pin!(actor);
+
+    let (waker, _) = task_wake_counter();
+    let mut ctx = task::Context::from_waker(&waker);
+
+    let actor_ref: ActorRef<Deadline<u8>> =
+        actor_ref.map_fn(|msg: Deadline<u8>| msg.map(usize::from));
+    let now = Instant::now();
+    let future = now + Duration::from_secs(60);
+    actor_ref.try_send_with_deadline(1_u8, now).unwrap();
+    actor_ref.try_send_with_deadline(2_u8, future).unwrap();
+    actor_ref.try_send_with_deadline(3_u8, future).unwrap();
+    actor_ref.try_send_with_deadline(4_u8, now).unwrap();
+    assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Ready(()));
+}
+
 async fn error_actor(mut ctx: actor::Context<()>, fail: bool) -> Result<(), ()> {
     if fail {
         Err(())
@@ -185,6 +252,41 @@ fn restarting_erroneous_actor_process() {
     assert_eq!(supervisor_called_count.get(), 1);
 }
 
+#[test]
+fn restarted_actor_keeps_inbox() {
+    let supervisor = |()| SupervisorStrategy::Restart(false);
+    let (actor, actor_ref) = ActorFuture::new(supervisor, actor_fn(error_actor), true).unwrap();
+    let mut actor = pin!(actor);
+
+    // Message send before the actor returns an error should be received by the
+    // restarted actor.
+    actor_ref.try_send(()).unwrap();
+
+    let (waker, _) = task_wake_counter();
+    let mut ctx = task::Context::from_waker(&waker);
+    // Actor returns an error and gets restarted.
+    assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Pending);
+    // Restarted actor receives the message and completes.
+    assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Ready(()));
+}
+
+#[test]
+fn restarted_actor_stays_in_group() {
+    let supervisor = |()| SupervisorStrategy::Restart(false);
+    let (actor, actor_ref) = ActorFuture::new(supervisor, actor_fn(error_actor), true).unwrap();
+    let mut actor = pin!(actor);
+    let group = ActorGroup::from(actor_ref);
+
+    let (waker, _) = task_wake_counter();
+    let mut ctx = task::Context::from_waker(&waker);
+    // Actor returns an error and gets restarted.
+    assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Pending);
+    // The actor reference in the group should still point to the restarted
+    // actor.
+    group.try_send_to_one(()).unwrap();
+    assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Ready(()));
+}
+
 async fn panic_actor(mut ctx: actor::Context<()>, fail: bool) -> Result<(), ()> {
     if fail {
         panic!("oops!")
@@ -299,6 +401,203 @@ fn restarting_panicking_actor_process() {
     assert_eq!(supervisor_called_count.get(), 1);
 }
 
+#[test]
+fn local_storage() {
+    let mut storage = actor::LocalStorage::default();
+    assert!(storage.is_empty());
+    assert_eq!(storage.insert(1_usize), None);
+    assert_eq!(storage.insert("hello"), None);
+    assert_eq!(storage.len(), 2);
+    assert_eq!(storage.get::<usize>(), Some(&1));
+    assert_eq!(storage.get::<&str>(), Some(&"hello"));
+    assert_eq!(storage.get::<u8>(), None);
+
+    *storage.get_mut::<usize>().unwrap() += 1;
+    assert_eq!(storage.insert(10_usize), Some(2));
+    *storage.get_or_insert_with(|| 0_u8) += 1;
+    *storage.get_or_insert_with(|| 0_u8) += 1;
+    assert_eq!(storage.get::<u8>(), Some(&2));
+
+    assert!(storage.contains::<&str>());
+    assert_eq!(storage.remove::<&str>(), Some("hello"));
+    assert!(!storage.contains::<&str>());
+    storage.clear();
+    assert!(storage.is_empty());
+}
+
+/// Number of times [`counting_actor`] is started.
+struct Started(usize);
+
+async fn counting_actor(mut ctx: actor::Context<()>, fail: bool) -> Result<(), usize> {
+    let started = ctx.local_storage().get_or_insert_with(|| Started(0));
+    started.0 += 1;
+    let started = started.0;
+    if fail {
+        return Err(started);
+    }
+
+    assert_eq!(ctx.receive_next().await, Ok(()));
+    // Storage should be the same across polls.
+    let got = ctx.local_storage_ref().get::<Started>().map(|s| s.0);
+    assert_eq!(got, Some(started));
+    Ok(())
+}
+
+/// Runs [`counting_actor`], failing three times, returning the starts it
+/// returned as errors.
+fn run_counting_actor(keep_on_restart: bool) -> Vec<usize> {
+    let starts = RefCell::new(Vec::new());
+    let supervisor = |started| {
+        let mut starts = starts.borrow_mut();
+        starts.push(started);
+        SupervisorStrategy::Restart(starts.len() < 3)
+    };
+    {
+        let (actor, actor_ref) = ActorFutureBuilder::new()
+            .with_keep_on_restart(keep_on_restart)
+            .build(supervisor, actor_fn(counting_actor), true)
+            .unwrap();
+        let mut actor = pin!(actor);
+
+        let (waker, _) = task_wake_counter();
+        let mut ctx = task::Context::from_waker(&waker);
+        for _ in 0..4 {
+            assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Pending);
+        }
+        actor_ref.try_send(()).unwrap();
+        assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Ready(()));
+    }
+    starts.into_inner()
+}
+
+#[test]
+fn local_storage_not_kept_on_restart() {
+    assert_eq!(run_counting_actor(false), [1, 1, 1]);
+}
+
+#[test]
+fn local_storage_kept_on_restart() {
+    assert_eq!(run_counting_actor(true), [1, 2, 3]);
+}
+
+async fn budget_actor(mut ctx: actor::Context<usize>, received: Rc<Cell<usize>>) {
+    while ctx.receive_next().await.is_ok() {
+        received.set(received.get() + 1);
+    }
+}
+
+#[test]
+fn actor_budget() {
+    let received = Rc::new(Cell::new(0));
+    let (actor, actor_ref) = ActorFutureBuilder::new()
+        .with_inbox_size(InboxSize::MAX)
+        .with_budget(NonZeroUsize::new(2))
+        .build(NoSupervisor, actor_fn(budget_actor), received.clone())
+        .unwrap();
+    let mut actor = pin!(actor);
+
+    let (waker, count) = task_wake_counter();
+    let mut ctx = task::Context::from_waker(&waker);
+
+    for n in 0..5_usize {
+        actor_ref.try_send(n).unwrap();
+    }
+    let wakes = count.load(Ordering::Acquire);
+
+    // Budget of two messages, after which the actor should yield and wake
+    // itself.
+    for expected in [2, 4] {
+        assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Pending);
+        assert_eq!(received.get(), expected);
+        assert_eq!(count.load(Ordering::Acquire), wakes + (expected / 2));
+    }
+    // Inbox is empty after the last message, so it shouldn't wake itself.
+    assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Pending);
+    assert_eq!(received.get(), 5);
+    assert_eq!(count.load(Ordering::Acquire), wakes + 2);
+
+    drop(actor_ref);
+    assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Ready(()));
+}
+
+#[test]
+fn actor_unlimited_budget() {
+    let received = Rc::new(Cell::new(0));
+    let (actor, actor_ref) = ActorFutureBuilder::new()
+        .with_inbox_size(InboxSize::MAX)
+        .with_budget(None)
+        .build(NoSupervisor, actor_fn(budget_actor), received.clone())
+        .unwrap();
+    let mut actor = pin!(actor);
+
+    let (waker, _) = task_wake_counter();
+    let mut ctx = task::Context::from_waker(&waker);
+
+    for n in 0..InboxSize::MAX.get() {
+        actor_ref.try_send(n).unwrap();
+    }
+    assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Pending);
+    assert_eq!(received.get(), InboxSize::MAX.get());
+}
+
+async fn yielding_actor(mut ctx: actor::Context<()>, yields: Rc<Cell<usize>>) {
+    for _ in 0..3 {
+        ctx.yield_now().await;
+        yields.set(yields.get() + 1);
+    }
+}
+
+#[test]
+fn actor_yield_now() {
+    let yields = Rc::new(Cell::new(0));
+    let (actor, _) =
+        ActorFuture::new(NoSupervisor, actor_fn(yielding_actor), yields.clone()).unwrap();
+    let mut actor = pin!(actor);
+
+    let (waker, count) = task_wake_counter();
+    let mut ctx = task::Context::from_waker(&waker);
+
+    for n in 0..3 {
+        assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Pending);
+        assert_eq!(yields.get(), n);
+        assert_eq!(count.load(Ordering::Acquire), n + 1);
+    }
+    assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Ready(()));
+    assert_eq!(yields.get(), 3);
+}
+
+async fn signal_actor(
+    mut ctx: actor::Context<usize>,
+    mut signals: heph_inbox::Receiver<&'static str>,
+) {
+    let got = ctx.receive_next().or_signal(signals.recv()).await;
+    assert_eq!(got, Ok(MessageOrSignal::Message(1)));
+    // Signals take precedence over messages.
+    let got = ctx.receive_next().or_signal(signals.recv()).await;
+    assert_eq!(got, Ok(MessageOrSignal::Signal(Some("stop"))));
+    assert_eq!(ctx.try_receive_next(), Ok(2));
+}
+
+#[test]
+fn actor_receive_message_or_signal() {
+    let (signal_sender, signals) = heph_inbox::new_small();
+    let (actor, actor_ref) =
+        ActorFuture::new(NoSupervisor, actor_fn(signal_actor), signals).unwrap();
+    let mut actor = pin!(actor);
+
+    let (waker, count) = task_wake_counter();
+    let mut ctx = task::Context::from_waker(&waker);
+
+    assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Pending);
+    actor_ref.try_send(1_usize).unwrap();
+    assert_eq!(count.load(Ordering::Acquire), 1);
+    assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Pending);
+
+    actor_ref.try_send(2_usize).unwrap();
+    signal_sender.try_send("stop").unwrap();
+    assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Ready(()));
+}
+
 /// Returns a [`task::Waker`] that counts the times it's called in `call_count`.
 pub(crate) fn task_wake_counter() -> (task::Waker, Arc<AtomicUsize>) {
     #[repr(transparent)]
@@ -319,3 +618,68 @@ pub(crate) fn task_wake_counter() -> (task::Waker, Arc<AtomicUsize>) {
         call_count,
     )
 }
+
+async fn priority_actor(mut ctx: actor::Context<usize>) {
+    assert_eq!(ctx.receive_next().await, Ok(10));
+    assert_eq!(ctx.try_receive_next(), Ok(11));
+    assert_eq!(ctx.receive_next().await, Ok(1));
+    ctx.yield_now().await;
+    // Priority message jumps the queue, even after the actor started
+    // receiving normal messages.
+    assert_eq!(ctx.receive_next().await, Ok(12));
+    assert_eq!(ctx.try_receive_next(), Ok(2));
+    assert_eq!(ctx.receive_next().await, Ok(3));
+    assert_eq!(ctx.try_receive_next(), Err(actor::RecvError::Empty));
+}
+
+#[test]
+fn actor_priority_messages() {
+    let (actor, actor_ref) = ActorFuture::new(NoSupervisor, actor_fn(priority_actor), ()).unwrap();
+    let mut actor = pin!(actor);
+
+    let (waker, _) = task_wake_counter();
+    let mut ctx = task::Context::from_waker(&waker);
+
+    actor_ref.try_send(1_usize).unwrap();
+    actor_ref.try_send(2_usize).unwrap();
+    actor_ref.try_send_priority(10_usize).unwrap();
+    actor_ref.try_send_priority(11_usize).unwrap();
+    actor_ref.try_send(3_usize).unwrap();
+    // Receives 10, 11 and 1.
+    assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Pending);
+
+    actor_ref.try_send_priority(12_usize).unwrap();
+    assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Ready(()));
+}
+
+async fn priority_error_actor(mut ctx: actor::Context<usize>, fail: bool) -> Result<(), ()> {
+    if fail {
+        // Leaves the normal messages in the inbox.
+        assert_eq!(ctx.receive_next().await, Ok(10));
+        return Err(());
+    }
+    assert_eq!(ctx.receive_next().await, Ok(1));
+    assert_eq!(ctx.try_receive_next(), Ok(2));
+    assert_eq!(ctx.try_receive_next(), Err(actor::RecvError::Empty));
+    Ok(())
+}
+
+#[test]
+fn restarted_actor_keeps_messages_after_priority_message() {
+    let supervisor = |()| SupervisorStrategy::Restart(false);
+    let (actor, actor_ref) =
+        ActorFuture::new(supervisor, actor_fn(priority_error_actor), true).unwrap();
+    let mut actor = pin!(actor);
+
+    actor_ref.try_send(1_usize).unwrap();
+    actor_ref.try_send(2_usize).unwrap();
+    actor_ref.try_send_priority(10_usize).unwrap();
+
+    let (waker, _) = task_wake_counter();
+    let mut ctx = task::Context::from_waker(&waker);
+    // Actor receives the priority message, returns an error and gets
+    // restarted.
+    assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Pending);
+    // Restarted actor receives the normal messages and completes.
+    assert_eq!(actor.as_mut().poll(&mut ctx), Poll::Ready(()));
+}
@@ -12,6 +12,7 @@
 //!  * Remote Produce Calls (RPC), see the [`rpc`] module.
 //!  * Wait until the actor is done ("join" the actor, similar to join a
 //!    thread), see the relevant section below.
+//!  * Stopping the actor, see the relevant section below.
 //!
 //! Note that the following sections talk about `ActorRef`s, but similar methods
 //! can be found on `ActorGroup`.
@@ -128,6 +129,132 @@
 //! # _ = (actor_future, watchdog_future);
 //! ```
 //!
+//! # Deadlines
+//!
+//! Actors can opt-in to receiving messages with a deadline by using
+//! [`Deadline`]`<M>` as message type. Such messages can be send using
+//! [`send_with_deadline`] (or [`try_send_with_deadline`]). The receiving actor
+//! can use [`actor::Context::receive_next_unexpired`] to drop all messages of
+//! which the deadline passed before they were received. The deadline of a
+//! received message, also available using [`actor::Context::deadline`], can be
+//! passed on to requests made to other actors, giving them the remaining time
+//! budget, e.g. using [`rpc_with_deadline`].
+//!
+//! ```
+//! use std::time::{Duration, Instant};
+//!
+//! use heph::actor::{self, actor_fn};
+//! use heph::actor_ref::{ActorRef, Deadline};
+//! use heph::future::ActorFuture;
+//! use heph::supervisor::NoSupervisor;
+//!
+//! /// Actor that forwards requests to another actor, using the deadline of the
+//! /// original request.
+//! async fn forward_actor(
+//!     mut ctx: actor::Context<Deadline<String>>,
+//!     next: ActorRef<Deadline<String>>,
+//! ) {
+//!     while let Ok(msg) = ctx.receive_next_unexpired().await {
+//!         if next.send(msg).await.is_err() {
+//!             break;
+//!         }
+//!     }
+//! }
+//!
+//! async fn print_actor(mut ctx: actor::Context<Deadline<String>>) {
+//!     while let Ok(msg) = ctx.receive_next_unexpired().await {
+//!         println!("Got a message: {}", msg.into_inner());
+//!     }
+//! }
+//!
+//! let (print_future, print_ref) = ActorFuture::new(NoSupervisor, actor_fn(print_actor), ()).unwrap();
+//! let (forward_future, forward_ref) = ActorFuture::new(NoSupervisor, actor_fn(forward_actor), print_ref).unwrap();
+//!
+//! let deadline = Instant::now() + Duration::from_secs(1);
+//! forward_ref.try_send_with_deadline("Hello world".to_owned(), deadline).unwrap();
+//! # _ = (print_future, forward_future);
+//! ```
+//!
+//! [`send_with_deadline`]: ActorRef::send_with_deadline
+//! [`try_send_with_deadline`]: ActorRef::try_send_with_deadline
+//! [`actor::Context::receive_next_unexpired`]: crate::actor::Context::receive_next_unexpired
+//! [`actor::Context::deadline`]: crate::actor::Context::deadline
+//! [`rpc_with_deadline`]: ActorRef::rpc_with_deadline
+//!
+//! # Priority messages
+//!
+//! Messages are normally received in the order in which they're added to the
+//! actor's inbox. Using [`send_priority`] (or [`try_send_priority`]) a message
+//! can jump the queue, it will be received before all normal messages waiting
+//! in the inbox. This is useful for messages that should be handled promptly,
+//! even if the actor is busy processing a lot of other work, e.g. health checks.
+//!
+//! Priority messages are received in the order in which they're send, relative
+//! to other priority messages. They share the capacity of the inbox with normal
+//! messages, so sending a priority message to a full inbox still has to wait.
+//!
+//! ```
+//! use heph::actor::{self, actor_fn};
+//! use heph::future::ActorFuture;
+//! use heph::supervisor::NoSupervisor;
+//!
+//! async fn actor(mut ctx: actor::Context<&'static str>) {
+//!     // Receives "health check" first, followed by "bulk work".
+//!     while let Ok(msg) = ctx.receive_next().await {
+//!         println!("Got a message: {msg}");
+//!     }
+//! }
+//!
+//! let (actor_future, actor_ref) = ActorFuture::new(NoSupervisor, actor_fn(actor), ()).unwrap();
+//!
+//! actor_ref.try_send("bulk work").unwrap();
+//! actor_ref.try_send_priority("health check").unwrap();
+//! # _ = actor_future;
+//! ```
+//!
+//! [`send_priority`]: ActorRef::send_priority
+//! [`try_send_priority`]: ActorRef::try_send_priority
+//!
+//! # Stopping actors
+//!
+//! An actor can be asked to stop using [`stop`]. This closes the actor's
+//! inbox: no more messages can be send to it, but the messages already in the
+//! inbox are still received. Unlike a message type such as [`Terminate`] the
+//! actor doesn't need to handle stopping itself: once all messages are received
+//! [`actor::Context::receive_next`] will return [`NoMessages`], the same as
+//! when all actor references are dropped. This means that the common `while
+//! let Ok(msg) = ctx.receive_next().await` loop stops without any additional
+//! code. The actor can use [`actor::Context::is_stopped`] to determine if it
+//! was stopped.
+//!
+//! ```
+//! use heph::actor::{self, actor_fn};
+//! use heph::future::ActorFuture;
+//! use heph::supervisor::NoSupervisor;
+//!
+//! async fn actor(mut ctx: actor::Context<String>) {
+//!     while let Ok(msg) = ctx.receive_next().await {
+//!         println!("Got a message: {msg}");
+//!     }
+//!     assert!(ctx.is_stopped());
+//! }
+//!
+//! let (actor_future, actor_ref) = ActorFuture::new(NoSupervisor, actor_fn(actor), ()).unwrap();
+//!
+//! actor_ref.try_send("Hello world".to_owned()).unwrap();
+//! // Stop the actor after it processed the message above.
+//! actor_ref.stop().unwrap();
+//! // Messages can't be send after the actor is stopped.
+//! assert!(actor_ref.try_send("Bye".to_owned()).is_err());
+//! # _ = actor_future;
+//! ```
+//!
+//! [`stop`]: ActorRef::stop
+//! [`Terminate`]: crate::messages::Terminate
+//! [`actor::Context::receive_next`]: crate::actor::Context::receive_next
+//! [`NoMessages`]: crate::actor::NoMessages
+//! [`actor::Context::is_stopped`]: crate::actor::Context::is_stopped
+//!
 //! [`supervisor`]: crate::supervisor
 
 use std::any::TypeId;
@@ -139,6 +266,7 @@ use std::pin::Pin;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::task::{self, Poll};
+use std::time::{Duration, Instant};
 
 use heph_inbox::{self as inbox, Sender};
 
@@ -196,12 +324,30 @@ impl<M> ActorRef<M> {
     where
         Msg: Into<M>,
     {
+        self.send_msg(msg.into(), false)
+    }
+
+    /// Send a priority message to the actor.
+    ///
+    /// The message will be received before all normal messages waiting in the
+    /// actor's inbox. See [Priority messages] and [`ActorRef::send`] for more
+    /// details.
+    ///
+    /// [Priority messages]: index.html#priority-messages
+    pub fn send_priority<'r, Msg>(&'r self, msg: Msg) -> SendValue<'r, M>
+    where
+        Msg: Into<M>,
+    {
+        self.send_msg(msg.into(), true)
+    }
+
+    fn send_msg<'r>(&'r self, msg: M, priority: bool) -> SendValue<'r, M> {
         use ActorRefKind::*;
-        let msg = msg.into();
         SendValue {
             kind: match &self.kind {
+                Local(sender) if priority => SendValueKind::Local(sender.send_priority(msg)),
                 Local(sender) => SendValueKind::Local(sender.send(msg)),
-                Mapped(actor_ref) => SendValueKind::Mapped(actor_ref.mapped_send(msg)),
+                Mapped(actor_ref) => SendValueKind::Mapped(actor_ref.mapped_send(msg, priority)),
             },
         }
     }
@@ -220,6 +366,24 @@ impl<M> ActorRef<M> {
     where
         Msg: Into<M>,
     {
+        self.try_send_msg(msg.into(), false)
+    }
+
+    /// Attempt to send a priority message to the actor.
+    ///
+    /// The message will be received before all normal messages waiting in the
+    /// actor's inbox. See [Priority messages] and [`ActorRef::try_send`] for
+    /// more details.
+    ///
+    /// [Priority messages]: index.html#priority-messages
+    pub fn try_send_priority<Msg>(&self, msg: Msg) -> Result<(), SendError>
+    where
+        Msg: Into<M>,
+    {
+        self.try_send_msg(msg.into(), true)
+    }
+
+    fn try_send_msg(&self, msg: M, priority: bool) -> Result<(), SendError> {
         use ActorRefKind::*;
         #[cfg(any(test, feature = "test"))]
         if crate::test::should_lose_msg() {
@@ -227,10 +391,37 @@ impl<M> ActorRef<M> {
             return Ok(());
         }
 
-        let msg = msg.into();
         match &self.kind {
-            Local(sender) => sender.try_send(msg).map_err(|_| SendError),
-            Mapped(actor_ref) => actor_ref.try_mapped_send(msg),
+            Local(sender) => try_send_local(sender, msg, priority).map_err(|_| SendError),
+            Mapped(actor_ref) => actor_ref.try_mapped_send(msg, priority),
+        }
+    }
+
+    /// Stop the actor.
+    ///
+    /// This closes the actor's inbox, after which sending messages to the
+    /// actor fails. Once the actor received all messages already in its inbox
+    /// [`actor::Context::receive_next`] returns [`NoMessages`] and
+    /// [`actor::Context::try_receive_next`] returns [`RecvError::Stopped`].
+    /// Stopping an actor is permanent, it also applies to the actor if it's
+    /// restarted. See [Stopping actors] for more details.
+    ///
+    /// This only returns an error if the actor is no longer running.
+    ///
+    /// [`actor::Context::receive_next`]: crate::actor::Context::receive_next
+    /// [`NoMessages`]: crate::actor::NoMessages
+    /// [`actor::Context::try_receive_next`]: crate::actor::Context::try_receive_next
+    /// [`RecvError::Stopped`]: crate::actor::RecvError::Stopped
+    /// [Stopping actors]: index.html#stopping-actors
+    pub fn stop(&self) -> Result<(), SendError> {
+        use ActorRefKind::*;
+        match &self.kind {
+            Local(sender) if sender.is_connected() => {
+                _ = sender.close();
+                Ok(())
+            }
+            Local(_) => Err(SendError),
+            Mapped(actor_ref) => actor_ref.stop(),
         }
     }
 
@@ -250,6 +441,34 @@ impl<M> ActorRef<M> {
         Rpc::new(self, request)
     }
 
+    /// Make a Remote Procedure Call (RPC), retrying it according to `policy`.
+    ///
+    /// Each attempt sends a clone of `request` using [`ActorRef::rpc`]. If an
+    /// attempt fails, or doesn't complete within the timeout of the `policy`,
+    /// it's retried (after the back-off) if the `policy` allows it, otherwise
+    /// the last error is returned.
+    ///
+    /// Heph doesn't have timers of its own, so the timeouts and back-off use
+    /// the futures returned by `timer`. It's called with the duration to wait
+    /// and must return a future that completes after that duration, e.g. using
+    /// `heph_rt::timer::Timer::after`.
+    ///
+    /// See the [`rpc`] module for more details and an example.
+    pub async fn rpc_retry<Req, Res, T, Fut>(
+        &self,
+        request: Req,
+        policy: rpc::RetryPolicy,
+        timer: T,
+    ) -> Result<Res, RpcError>
+    where
+        M: From<RpcMessage<Req, Res>>,
+        Req: Clone,
+        T: FnMut(Duration) -> Fut,
+        Fut: Future,
+    {
+        rpc::retry(self, request, policy, timer).await
+    }
+
     /// Change the message type of the actor reference.
     ///
     /// Before sending the message this will first change the message type from
@@ -308,6 +527,39 @@ impl<M> ActorRef<M> {
         }
     }
 
+    /// Much like [`try_map`], but messages that fail to convert are send to
+    /// the `fallback` actor instead.
+    ///
+    /// This requires the [`TryFrom`] implementation to return the original
+    /// message as error. This is useful for evolving protocols, where unknown
+    /// or legacy messages should be handled by a catch-all actor instead of
+    /// being dropped, see [`RawMessage`] for an example.
+    ///
+    /// [`try_map`]: ActorRef::try_map
+    /// [`RawMessage`]: crate::messages::RawMessage
+    ///
+    /// # Notes
+    ///
+    /// This conversion is **not** cheap, it requires an allocation so use with
+    /// caution when it comes to performance sensitive code.
+    ///
+    /// Prefer to clone an existing mapped `ActorRef` over creating a new one as
+    /// that can reuse the allocation mentioned above.
+    #[allow(clippy::arc_with_non_send_sync)]
+    pub fn try_map_or<Msg>(self, fallback: ActorRef<Msg>) -> ActorRef<Msg>
+    where
+        M: TryFrom<Msg, Error = Msg> + 'static,
+        Msg: 'static,
+    {
+        let mapped_ref = MappedActorRefFallback {
+            actor_ref: self,
+            fallback,
+        };
+        ActorRef {
+            kind: ActorRefKind::Mapped(Arc::new(mapped_ref)),
+        }
+    }
+
     /// Change the message type of the actor reference.
     ///
     /// Before sending the message this will first change the message into a
@@ -413,6 +665,55 @@ impl<M> ActorRef<M> {
     }
 }
 
+impl<M> ActorRef<Deadline<M>> {
+    /// Send a message to the actor with a `deadline`.
+    ///
+    /// Same as sending [`Deadline::new`]`(msg, deadline)` using
+    /// [`ActorRef::send`], see [Deadlines] for more details.
+    ///
+    /// [Deadlines]: index.html#deadlines
+    pub fn send_with_deadline<'r, Msg>(
+        &'r self,
+        msg: Msg,
+        deadline: Instant,
+    ) -> SendValue<'r, Deadline<M>>
+    where
+        Msg: Into<M>,
+    {
+        self.send(Deadline::new(msg.into(), deadline))
+    }
+
+    /// Attempt to send a message to the actor with a `deadline`.
+    ///
+    /// Same as sending [`Deadline::new`]`(msg, deadline)` using
+    /// [`ActorRef::try_send`], see [Deadlines] for more details.
+    ///
+    /// [Deadlines]: index.html#deadlines
+    pub fn try_send_with_deadline<Msg>(&self, msg: Msg, deadline: Instant) -> Result<(), SendError>
+    where
+        Msg: Into<M>,
+    {
+        self.try_send(Deadline::new(msg.into(), deadline))
+    }
+
+    /// Make a Remote Procedure Call (RPC) with a `deadline`.
+    ///
+    /// Same as [`ActorRef::rpc`], but sends the request with a `deadline`.
+    /// Note that the returned [`Rpc`] doesn't time out once the `deadline`
+    /// passes, but if the request isn't received before the deadline the
+    /// [`Rpc`] returns [`RpcError::NoResponse`].
+    pub fn rpc_with_deadline<'r, Req, Res>(
+        &'r self,
+        request: Req,
+        deadline: Instant,
+    ) -> Rpc<'r, Deadline<M>, Res>
+    where
+        M: From<RpcMessage<Req, Res>>,
+    {
+        Rpc::wrapped(self, request, |msg| Deadline::new(M::from(msg), deadline))
+    }
+}
+
 impl<M> Clone for ActorRef<M> {
     fn clone(&self) -> ActorRef<M> {
         use ActorRefKind::*;
@@ -431,6 +732,20 @@ impl<M> fmt::Debug for ActorRef<M> {
     }
 }
 
+/// Attempt to send `msg` using `sender`, as priority message if `priority` is
+/// `true`.
+fn try_send_local<M>(
+    sender: &Sender<M>,
+    msg: M,
+    priority: bool,
+) -> Result<(), heph_inbox::SendError<M>> {
+    if priority {
+        sender.try_send_priority(msg)
+    } else {
+        sender.try_send(msg)
+    }
+}
+
 /// Trait to erase the original message type of the actor reference.
 ///
 /// # Notes
@@ -438,12 +753,14 @@ impl<M> fmt::Debug for ActorRef<M> {
 /// For correctness this may only be implemented on [`ActorRef`].
 trait MappedActorRef<M> {
     /// Same as [`ActorRef::try_send`] but converts the message first.
-    fn try_mapped_send(&self, msg: M) -> Result<(), SendError>;
+    fn try_mapped_send(&self, msg: M, priority: bool) -> Result<(), SendError>;
 
-    fn mapped_send<'r>(&'r self, msg: M) -> MappedSendValue<'r>;
+    fn mapped_send<'r>(&'r self, msg: M, priority: bool) -> MappedSendValue<'r>;
 
     fn mapped_join<'r>(&'r self) -> MappedJoin<'r>;
 
+    fn stop(&self) -> Result<(), SendError>;
+
     fn is_connected(&self) -> bool;
 
     fn id(&self) -> inbox::Id;
@@ -453,23 +770,23 @@ impl<M, Msg> MappedActorRef<Msg> for ActorRef<M>
 where
     M: TryFrom<Msg>,
 {
-    fn try_mapped_send(&self, msg: Msg) -> Result<(), SendError> {
+    fn try_mapped_send(&self, msg: Msg, priority: bool) -> Result<(), SendError> {
         M::try_from(msg)
             .map_err(|_| SendError)
-            .and_then(|msg| self.try_send(msg))
+            .and_then(|msg| self.try_send_msg(msg, priority))
     }
 
-    fn mapped_send<'r>(&'r self, msg: Msg) -> MappedSendValue<'r> {
+    fn mapped_send<'r>(&'r self, msg: Msg, priority: bool) -> MappedSendValue<'r> {
         match M::try_from(msg) {
             Ok(msg) => match &self.kind {
-                ActorRefKind::Local(sender) => match sender.try_send(msg) {
+                ActorRefKind::Local(sender) => match try_send_local(sender, msg, priority) {
                     Ok(()) => MappedSendValue::Send,
                     Err(heph_inbox::SendError::Full(msg)) => {
-                        MappedSendValue::Sending(Box::pin(self.send(msg)))
+                        MappedSendValue::Sending(Box::pin(self.send_msg(msg, priority)))
                     }
                     Err(heph_inbox::SendError::Disconnected(_)) => MappedSendValue::SendErr,
                 },
-                ActorRefKind::Mapped(sender) => sender.mapped_send(msg),
+                ActorRefKind::Mapped(sender) => sender.mapped_send(msg, priority),
             },
             Err(..) => MappedSendValue::SendErr,
         }
@@ -485,6 +802,10 @@ where
         }
     }
 
+    fn stop(&self) -> Result<(), SendError> {
+        self.stop()
+    }
+
     fn is_connected(&self) -> bool {
         self.is_connected()
     }
@@ -504,24 +825,24 @@ impl<M, Msg, F, E> MappedActorRef<Msg> for MappedActorRefFn<M, F>
 where
     F: Fn(Msg) -> Result<M, E>,
 {
-    fn try_mapped_send(&self, msg: Msg) -> Result<(), SendError> {
+    fn try_mapped_send(&self, msg: Msg, priority: bool) -> Result<(), SendError> {
         match (self.map)(msg) {
-            Ok(msg) => self.actor_ref.try_send(msg),
+            Ok(msg) => self.actor_ref.try_send_msg(msg, priority),
             Err(..) => Err(SendError),
         }
     }
 
-    fn mapped_send<'r>(&'r self, msg: Msg) -> MappedSendValue<'r> {
+    fn mapped_send<'r>(&'r self, msg: Msg, priority: bool) -> MappedSendValue<'r> {
         match (self.map)(msg) {
             Ok(msg) => match &self.actor_ref.kind {
-                ActorRefKind::Local(sender) => match sender.try_send(msg) {
+                ActorRefKind::Local(sender) => match try_send_local(sender, msg, priority) {
                     Ok(()) => MappedSendValue::Send,
                     Err(heph_inbox::SendError::Full(msg)) => {
-                        MappedSendValue::Sending(Box::pin(self.actor_ref.send(msg)))
+                        MappedSendValue::Sending(Box::pin(self.actor_ref.send_msg(msg, priority)))
                     }
                     Err(heph_inbox::SendError::Disconnected(_)) => MappedSendValue::SendErr,
                 },
-                ActorRefKind::Mapped(sender) => sender.mapped_send(msg),
+                ActorRefKind::Mapped(sender) => sender.mapped_send(msg, priority),
             },
             Err(..) => MappedSendValue::SendErr,
         }
@@ -537,6 +858,67 @@ where
         }
     }
 
+    fn stop(&self) -> Result<(), SendError> {
+        self.actor_ref.stop()
+    }
+
+    fn is_connected(&self) -> bool {
+        self.actor_ref.is_connected()
+    }
+
+    fn id(&self) -> inbox::Id {
+        self.actor_ref.id()
+    }
+}
+
+/// Wrapper around an [`ActorRef`] to change the message type, sending messages
+/// that fail to convert to a `fallback` actor.
+struct MappedActorRefFallback<M, Msg> {
+    actor_ref: ActorRef<M>,
+    fallback: ActorRef<Msg>,
+}
+
+impl<M, Msg> MappedActorRef<Msg> for MappedActorRefFallback<M, Msg>
+where
+    M: TryFrom<Msg, Error = Msg>,
+{
+    fn try_mapped_send(&self, msg: Msg, priority: bool) -> Result<(), SendError> {
+        match M::try_from(msg) {
+            Ok(msg) => self.actor_ref.try_send_msg(msg, priority),
+            Err(msg) => self.fallback.try_send_msg(msg, priority),
+        }
+    }
+
+    fn mapped_send<'r>(&'r self, msg: Msg, priority: bool) -> MappedSendValue<'r> {
+        match M::try_from(msg) {
+            Ok(msg) => match &self.actor_ref.kind {
+                ActorRefKind::Local(sender) => match try_send_local(sender, msg, priority) {
+                    Ok(()) => MappedSendValue::Send,
+                    Err(heph_inbox::SendError::Full(msg)) => {
+                        MappedSendValue::Sending(Box::pin(self.actor_ref.send_msg(msg, priority)))
+                    }
+                    Err(heph_inbox::SendError::Disconnected(_)) => MappedSendValue::SendErr,
+                },
+                ActorRefKind::Mapped(sender) => sender.mapped_send(msg, priority),
+            },
+            Err(msg) => MappedSendValue::Sending(Box::pin(self.fallback.send_msg(msg, priority))),
+        }
+    }
+
+    fn mapped_join<'r>(&'r self) -> MappedJoin<'r> {
+        match &self.actor_ref.kind {
+            ActorRefKind::Local(sender) => match sender.is_connected() {
+                false => MappedJoin::Disconnected,
+                true => MappedJoin::Join(Box::pin(self.actor_ref.join())),
+            },
+            ActorRefKind::Mapped(sender) => sender.mapped_join(),
+        }
+    }
+
+    fn stop(&self) -> Result<(), SendError> {
+        self.actor_ref.stop()
+    }
+
     fn is_connected(&self) -> bool {
         self.actor_ref.is_connected()
     }
@@ -698,12 +1080,75 @@ impl<'r, M> fmt::Debug for Join<'r, M> {
     }
 }
 
+/// Message with a deadline.
+///
+/// Actors that want to support deadlines use this as message type, i.e.
+/// `actor::Context<Deadline<M>>`. See [Deadlines] for more details.
+///
+/// [Deadlines]: index.html#deadlines
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Deadline<M> {
+    msg: M,
+    deadline: Instant,
+}
+
+impl<M> Deadline<M> {
+    /// Create a new message `msg` with `deadline`.
+    pub const fn new(msg: M, deadline: Instant) -> Deadline<M> {
+        Deadline { msg, deadline }
+    }
+
+    /// Returns the deadline of the message.
+    pub const fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Returns `true` if the deadline of the message has passed.
+    pub fn is_expired(&self) -> bool {
+        self.deadline <= Instant::now()
+    }
+
+    /// Returns the message, dropping the deadline.
+    pub fn into_inner(self) -> M {
+        self.msg
+    }
+
+    /// Map the message, keeping the deadline.
+    pub fn map<Msg, F>(self, map: F) -> Deadline<Msg>
+    where
+        F: FnOnce(M) -> Msg,
+    {
+        Deadline {
+            msg: map(self.msg),
+            deadline: self.deadline,
+        }
+    }
+
+    /// Returns the message if the deadline didn't pass yet, `None` otherwise.
+    pub(crate) fn unexpired(self) -> Option<Deadline<M>> {
+        if self.is_expired() {
+            log::debug!("dropping message as its deadline passed");
+            None
+        } else {
+            Some(self)
+        }
+    }
+}
+
 /// A group of [`ActorRef`]s used to send a message to multiple actors.
 ///
 /// # Notes
 ///
 /// Unlike [`ActorRef`] this is **not** cheap to clone as it's requires a clone
 /// of an internal vector.
+///
+/// Restarting an actor (see the [`supervisor`] module) doesn't change its actor
+/// reference, the restarted actor keeps using the same inbox. This means actor
+/// references in a group remain valid when an actor is restarted. Only actors
+/// that stopped leave disconnected actor references in the group, these can be
+/// removed using [`ActorGroup::remove_disconnected`].
+///
+/// [`supervisor`]: crate::supervisor
 pub struct ActorGroup<M> {
     actor_refs: Vec<ActorRef<M>>,
     /// Index of the actor reference to send the next single delivery message
@@ -847,6 +1292,23 @@ impl<M> ActorGroup<M> {
         Ok(())
     }
 
+    /// Stop all of the actors in the group.
+    ///
+    /// See [`ActorRef::stop`].
+    ///
+    /// This only returns an error if the group is empty, otherwise this will
+    /// always return `Ok(())`.
+    pub fn stop_all(&self) -> Result<(), SendError> {
+        if self.actor_refs.is_empty() {
+            return Err(SendError);
+        }
+
+        for actor_ref in &self.actor_refs {
+            _ = actor_ref.stop();
+        }
+        Ok(())
+    }
+
     /// Wait for all actors in this group to finish running.
     ///
     /// This works the same way as [`ActorRef::join`], but waits on a group of
@@ -16,6 +16,13 @@
 //!
 //! [`from_message`]: crate::from_message
 //!
+//! # Retrying
+//!
+//! [`ActorRef::rpc_retry`] can be used to retry RPCs that fail or don't
+//! complete in time, according to a [`RetryPolicy`]. As Heph doesn't have
+//! timers of its own this requires a function to create timers, e.g. using
+//! the timers of Heph-rt.
+//!
 //! # Examples
 //!
 //! Using RPC to communicate with another actor.
@@ -117,12 +124,51 @@
 //! }
 //! # _ = (counter, requester);
 //! ```
+//!
+//! Retrying an RPC, using the timers of Heph-rt for the timeouts and back-off.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use heph::actor;
+//! use heph::actor_ref::rpc::RetryPolicy;
+//! use heph::actor_ref::{ActorRef, RpcMessage};
+//! use heph_rt::timer::Timer;
+//! use heph_rt::ThreadLocal;
+//!
+//! /// Message type for the receiving actor.
+//! # #[allow(dead_code)]
+//! struct Lookup(RpcMessage<String, Option<String>>);
+//! # impl From<RpcMessage<String, Option<String>>> for Lookup {
+//! #     fn from(msg: RpcMessage<String, Option<String>>) -> Lookup {
+//! #         Lookup(msg)
+//! #     }
+//! # }
+//!
+//! /// Sending actor of the RPC.
+//! async fn requester(ctx: actor::Context<(), ThreadLocal>, actor_ref: ActorRef<Lookup>) {
+//!     // Try at most three times, with a timeout of 100 milliseconds per
+//!     // attempt and waiting 10 milliseconds before the second attempt and 20
+//!     // before the third.
+//!     let policy = RetryPolicy::new(3)
+//!         .with_timeout(Duration::from_millis(100))
+//!         .with_backoff(Duration::from_millis(10), Duration::from_secs(1));
+//!     let rt = ctx.runtime_ref().clone();
+//!     let timer = |timeout| Timer::after(rt.clone(), timeout);
+//!     match actor_ref.rpc_retry("key".to_owned(), policy, timer).await {
+//!         Ok(value) => println!("Got value: {value:?}"),
+//!         Err(err) => eprintln!("Lookup failed: {err}"),
+//!     }
+//! }
+//! # _ = requester;
+//! ```
 
 use std::error::Error;
 use std::fmt;
-use std::future::Future;
-use std::pin::Pin;
+use std::future::{poll_fn, Future};
+use std::pin::{pin, Pin};
 use std::task::{self, Poll};
+use std::time::Duration;
 
 use heph_inbox::oneshot::{new_oneshot, RecvOnce, Sender};
 
@@ -143,13 +189,24 @@ impl<'r, M, Res> Rpc<'r, M, Res> {
     pub(super) fn new<Req>(actor_ref: &'r ActorRef<M>, request: Req) -> Rpc<'r, M, Res>
     where
         M: From<RpcMessage<Req, Res>>,
+    {
+        Rpc::wrapped(actor_ref, request, M::from)
+    }
+
+    /// Create a new RPC, using `wrap` to create the message send to the actor.
+    pub(super) fn wrapped<Req, F>(
+        actor_ref: &'r ActorRef<M>,
+        request: Req,
+        wrap: F,
+    ) -> Rpc<'r, M, Res>
+    where
+        F: FnOnce(RpcMessage<Req, Res>) -> M,
     {
         let (sender, receiver) = new_oneshot();
         let response = RpcResponse { sender };
         let msg = RpcMessage { request, response };
-        let send = actor_ref.send(msg);
         Rpc {
-            send: Some(send),
+            send: Some(actor_ref.send(wrap(msg))),
             recv: receiver.recv_once(),
         }
     }
@@ -194,6 +251,11 @@ pub enum RpcError {
     /// Disconnects can not always be detected, consider this error informative
     /// rather than depending on it.
     NoResponse,
+    /// No response was received within the timeout.
+    ///
+    /// Only returned by [`ActorRef::rpc_retry`], see
+    /// [`RetryPolicy::with_timeout`].
+    TimedOut,
 }
 
 impl From<SendError> for RpcError {
@@ -207,12 +269,168 @@ impl fmt::Display for RpcError {
         match self {
             RpcError::SendError => SendError.fmt(f),
             RpcError::NoResponse => f.write_str("no RPC response"),
+            RpcError::TimedOut => f.write_str("RPC timed out"),
         }
     }
 }
 
 impl Error for RpcError {}
 
+/// Policy used by [`ActorRef::rpc_retry`] to determine if and when to retry a
+/// Remote Procedure Call (RPC).
+///
+/// By default an RPC is attempted once, without a timeout, and only retried
+/// on [`RpcError::NoResponse`] and [`RpcError::TimedOut`]. Note that a
+/// [`RpcError::SendError`] means the actor is no longer running, retrying
+/// wouldn't change that.
+#[derive(Copy, Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: usize,
+    timeout: Option<Duration>,
+    backoff: Duration,
+    max_backoff: Duration,
+    retry_on: fn(&RpcError) -> bool,
+}
+
+impl RetryPolicy {
+    /// Create a new policy that attempts the RPC at most `max_attempts` times,
+    /// i.e. the RPC is retried at most `max_attempts - 1` times.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_attempts` is zero.
+    pub const fn new(max_attempts: usize) -> RetryPolicy {
+        assert!(max_attempts != 0, "need at least a single RPC attempt");
+        RetryPolicy {
+            max_attempts,
+            timeout: None,
+            backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+            retry_on: default_retry_on,
+        }
+    }
+
+    /// Set the `timeout` of a single attempt.
+    ///
+    /// If no response is received within `timeout` the attempt fails with
+    /// [`RpcError::TimedOut`]. Note that the request might still be (or have
+    /// been) processed by the receiving actor.
+    pub const fn with_timeout(mut self, timeout: Duration) -> RetryPolicy {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the exponential back-off.
+    ///
+    /// After the first failed attempt this waits for `initial` before retrying,
+    /// doubling the time to wait after each subsequent failed attempt, up to
+    /// `max`. Defaults to not waiting.
+    pub const fn with_backoff(mut self, initial: Duration, max: Duration) -> RetryPolicy {
+        self.backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Set the predicate that determines if an attempt that failed with an
+    /// error is retried.
+    pub const fn with_retry_on(mut self, retry_on: fn(&RpcError) -> bool) -> RetryPolicy {
+        self.retry_on = retry_on;
+        self
+    }
+
+    /// Returns the maximum number of attempts.
+    pub const fn max_attempts(&self) -> usize {
+        self.max_attempts
+    }
+
+    /// Returns the timeout of a single attempt, if any.
+    pub const fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Returns `true` if an `attempt` (starting at one) that failed with `err`
+    /// should be retried.
+    fn should_retry(&self, attempt: usize, err: &RpcError) -> bool {
+        attempt < self.max_attempts && (self.retry_on)(err)
+    }
+
+    /// Returns the time to wait before retrying after a failed `attempt`
+    /// (starting at one).
+    fn backoff(&self, attempt: usize) -> Duration {
+        let factor = u32::try_from(attempt - 1)
+            .ok()
+            .and_then(|n| 2_u32.checked_pow(n))
+            .unwrap_or(u32::MAX);
+        self.backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy::new(1)
+    }
+}
+
+/// Default predicate of [`RetryPolicy::with_retry_on`].
+fn default_retry_on(err: &RpcError) -> bool {
+    matches!(err, RpcError::NoResponse | RpcError::TimedOut)
+}
+
+/// See [`ActorRef::rpc_retry`].
+pub(super) async fn retry<M, Req, Res, T, Fut>(
+    actor_ref: &ActorRef<M>,
+    request: Req,
+    policy: RetryPolicy,
+    mut timer: T,
+) -> Result<Res, RpcError>
+where
+    M: From<RpcMessage<Req, Res>>,
+    Req: Clone,
+    T: FnMut(Duration) -> Fut,
+    Fut: Future,
+{
+    let mut attempt = 1;
+    loop {
+        let rpc = Rpc::new(actor_ref, request.clone());
+        let result = match policy.timeout {
+            Some(timeout) => with_timeout(rpc, timer(timeout)).await,
+            None => rpc.await,
+        };
+        match result {
+            Ok(response) => return Ok(response),
+            Err(err) if policy.should_retry(attempt, &err) => {
+                let backoff = policy.backoff(attempt);
+                if !backoff.is_zero() {
+                    _ = timer(backoff).await;
+                }
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Await `rpc`, returning [`RpcError::TimedOut`] if `timer` completes first.
+async fn with_timeout<M, Res, Fut>(rpc: Rpc<'_, M, Res>, timer: Fut) -> Result<Res, RpcError>
+where
+    Fut: Future,
+{
+    let mut rpc = pin!(rpc);
+    let mut timer = pin!(timer);
+    poll_fn(|ctx| {
+        if let Poll::Ready(result) = rpc.as_mut().poll(ctx) {
+            return Poll::Ready(result);
+        }
+        match timer.as_mut().poll(ctx) {
+            Poll::Ready(_) => Poll::Ready(Err(RpcError::TimedOut)),
+            Poll::Pending => Poll::Pending,
+        }
+    })
+    .await
+}
+
 /// Message type that holds an RPC request.
 ///
 /// It holds both the request (`Req`) and the way to respond [`RpcResponse`].
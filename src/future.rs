@@ -4,15 +4,16 @@ use std::any::Any;
 use std::error::Error;
 use std::fmt;
 use std::future::Future;
-use std::num::NonZeroU8;
+use std::num::{NonZeroU8, NonZeroUsize};
 use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::pin::Pin;
+use std::sync::{Arc, Mutex};
 use std::task::{self, Poll};
 
 use heph_inbox::{self as inbox, ReceiverConnected};
 use log::error;
 
-use crate::actor::{self, Actor, NewActor};
+use crate::actor::{self, Actor, LocalStorage, NewActor};
 use crate::actor_ref::ActorRef;
 use crate::panic_message;
 use crate::supervisor::{Supervisor, SupervisorStrategy};
@@ -37,6 +38,10 @@ pub struct ActorFuture<S, NA: NewActor> {
     actor: NA::Actor,
     /// Runtime access.
     rt: NA::RuntimeAccess,
+    /// Actor-local storage kept across restarts, if enabled.
+    local_storage: Option<Arc<Mutex<LocalStorage>>>,
+    /// Cooperative scheduling budget passed to the [`actor::Context`].
+    budget: Option<NonZeroUsize>,
 }
 
 impl<S, NA> ActorFuture<S, NA>
@@ -79,6 +84,11 @@ where
         self.inbox.id().as_usize()
     }
 
+    #[doc(hidden)] // Not part of the stable API.
+    pub fn inbox_len(&self) -> usize {
+        self.inbox.len()
+    }
+
     /// Returns `Poll::Pending` if the actor was successfully restarted,
     /// `Poll::Ready` if the actor wasn't restarted (or failed to restart).
     fn handle_actor_error(
@@ -143,7 +153,10 @@ where
     /// Creates a new actor and, if successful, replaces the old actor with it.
     fn create_new_actor(&mut self, arg: NA::Argument) -> Result<(), NA::Error> {
         let receiver = self.inbox.new_receiver().unwrap_or_else(inbox_failure);
-        let ctx = actor::Context::new(receiver, self.rt.clone());
+        let mut ctx = actor::Context::new(receiver, self.rt.clone()).with_budget(self.budget);
+        if let Some(slot) = &self.local_storage {
+            ctx = ctx.keep_storage_in(slot.clone());
+        }
         self.new_actor.new(ctx, arg).map(|actor| {
             // We pin the actor here to ensure its dropped in place when
             // replacing it with out new actor.
@@ -194,6 +207,7 @@ where
             .field("actor", &NA::name())
             .field("inbox", &self.inbox)
             .field("rt", &self.rt)
+            .field("local_storage", &self.local_storage)
             .finish()
     }
 }
@@ -212,6 +226,8 @@ fn inbox_failure<T>(_: ReceiverConnected) -> T {
 pub struct ActorFutureBuilder<RT = ()> {
     rt: RT,
     inbox_size: InboxSize,
+    keep_on_restart: bool,
+    budget: Option<NonZeroUsize>,
 }
 
 impl ActorFutureBuilder {
@@ -221,6 +237,8 @@ impl ActorFutureBuilder {
         ActorFutureBuilder {
             rt: (),
             inbox_size: InboxSize::DEFAULT,
+            keep_on_restart: false,
+            budget: Some(DEFAULT_BUDGET),
         }
     }
 }
@@ -243,6 +261,8 @@ impl<RT> ActorFutureBuilder<RT> {
         ActorFutureBuilder {
             rt,
             inbox_size: self.inbox_size,
+            keep_on_restart: self.keep_on_restart,
+            budget: self.budget,
         }
     }
 
@@ -257,6 +277,56 @@ impl<RT> ActorFutureBuilder<RT> {
         self
     }
 
+    /// Returns `true` if the actor's local storage is kept across restarts.
+    pub fn keep_on_restart(&self) -> bool {
+        self.keep_on_restart
+    }
+
+    /// Keep the actor's [local storage] across restarts.
+    ///
+    /// By default a restarted actor starts with an empty local storage. If
+    /// this is enabled the storage is moved back into the `ActorFuture` when
+    /// the [`actor::Context`] is dropped, and passed to the context of the
+    /// restarted actor. For asynchronous functions the context is dropped
+    /// before the error is returned, or while unwinding in case of a panic.
+    ///
+    /// [local storage]: actor::Context::local_storage
+    pub fn with_keep_on_restart(mut self, keep: bool) -> Self {
+        self.keep_on_restart = keep;
+        self
+    }
+
+    /// Returns the cooperative scheduling budget of the actor.
+    pub fn budget(&self) -> Option<NonZeroUsize> {
+        self.budget
+    }
+
+    /// Set the cooperative scheduling budget of the actor.
+    ///
+    /// The budget is the maximum number of messages the actor can receive,
+    /// using [`actor::Context::receive_next`], before it yields control back
+    /// to the scheduler (returning [`Poll::Pending`] and waking itself). This
+    /// prevents a busy actor, one that always has messages in its inbox, from
+    /// starving other actors and futures.
+    ///
+    /// The budget is reset when the actor is waiting for a new message, or
+    /// when it calls [`actor::Context::yield_now`].
+    ///
+    /// `None` means an unlimited budget, i.e. the actor never yields on its
+    /// own. Defaults to [`DEFAULT_BUDGET`].
+    ///
+    /// # Notes
+    ///
+    /// Only messages received from the actor's inbox count towards the budget,
+    /// completed I/O operations (e.g. reading from a TCP stream) do **not**.
+    /// An actor that mostly does I/O that completes immediately can still
+    /// starve other actors, it should use [`actor::Context::yield_now`] to
+    /// yield manually.
+    pub fn with_budget(mut self, budget: Option<NonZeroUsize>) -> Self {
+        self.budget = budget;
+        self
+    }
+
     /// Create a new `ActorFuture`.
     ///
     /// Arguments:
@@ -279,7 +349,13 @@ impl<RT> ActorFutureBuilder<RT> {
         let rt = self.rt;
         let (inbox, sender, receiver) = inbox::Manager::new_channel(self.inbox_size.get());
         let actor_ref = ActorRef::local(sender);
-        let ctx = actor::Context::new(receiver, rt.clone());
+        let local_storage = self
+            .keep_on_restart
+            .then(|| Arc::new(Mutex::new(LocalStorage::default())));
+        let mut ctx = actor::Context::new(receiver, rt.clone()).with_budget(self.budget);
+        if let Some(slot) = &local_storage {
+            ctx = ctx.keep_storage_in(slot.clone());
+        }
         let actor = match new_actor.new(ctx, argument) {
             Ok(actor) => actor,
             Err(err) => return Err(err),
@@ -290,11 +366,17 @@ impl<RT> ActorFutureBuilder<RT> {
             inbox,
             actor,
             rt,
+            local_storage,
+            budget: self.budget,
         };
         Ok((future, actor_ref))
     }
 }
 
+/// Default cooperative scheduling budget, currently 128 messages, see
+/// [`ActorFutureBuilder::with_budget`].
+pub const DEFAULT_BUDGET: NonZeroUsize = NonZeroUsize::new(128).unwrap();
+
 /// Maximum size of the actor's inbox before sending to it will (asynchronously)
 /// block the sender.
 ///
@@ -42,8 +42,10 @@
 //!
 //! ## Features
 //!
-//! This crate has one optional feature: `test`. The `test` feature will enable
-//! the `test` module which contains testing facilities.
+//! This crate has two optional features: `macros` and `test`. The `macros`
+//! feature will enable the `macros` module which contains procedural macros to
+//! reduce boilerplate. The `test` feature will enable the `test` module which
+//! contains testing facilities.
 
 #![feature(const_option, doc_auto_cfg, doc_cfg_hide, never_type)]
 #![warn(
@@ -73,8 +75,11 @@
 pub mod actor;
 pub mod actor_ref;
 pub mod future;
+#[cfg(feature = "macros")]
+pub mod macros;
 pub mod messages;
 pub mod quick_start;
+pub mod standalone;
 pub mod supervisor;
 pub mod sync;
 #[cfg(any(test, feature = "test"))]
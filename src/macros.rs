@@ -0,0 +1,179 @@
+//! Procedural macros.
+//!
+//! Requires the `macros` feature.
+//!
+//! This module contains [`Message`], a derive macro to implement [`From`] for
+//! message enums, and [`restart_supervisor`], an attribute to create a
+//! supervisor that restarts the actor. They're alternatives to the
+//! [`from_message!`] and [`restart_supervisor!`] macros. Finally [`actor`] is
+//! an attribute to define an actor using a handler method per message.
+//!
+//! [`from_message!`]: crate::from_message
+//! [`restart_supervisor!`]: crate::restart_supervisor!
+
+/// Derive macro to implement [`From`] for an enum message type.
+///
+/// For each variant with a single unnamed field this implements `From<T>`,
+/// where `T` is the type of the field. This includes [`RpcMessage`], allowing
+/// the message to be used in [`ActorRef::rpc`].
+///
+/// Variants that don't have a single unnamed field, or that would create a
+/// conflicting implementation (e.g. two variants with the same type), must be
+/// skipped using the `#[message(skip)]` attribute.
+///
+/// [`RpcMessage`]: crate::actor_ref::RpcMessage
+/// [`ActorRef::rpc`]: crate::ActorRef::rpc
+///
+/// # Examples
+///
+/// ```
+/// # #![allow(dead_code)]
+/// use heph::actor_ref::RpcMessage;
+/// use heph::macros::Message;
+///
+/// #[derive(Debug, Message)]
+/// enum Message {
+///     // Implements `From<String>`.
+///     Msg(String),
+///     // Implements `From<RpcMessage<String, usize>>`.
+///     Rpc(RpcMessage<String, usize>),
+///     Rpc2(RpcMessage<(String, usize), (usize, usize)>),
+///     // Doesn't implement anything.
+///     #[message(skip)]
+///     Stop,
+/// }
+///
+/// let msg = Message::from("Hello world".to_owned());
+/// # assert!(matches!(msg, Message::Msg(_)));
+/// ```
+pub use heph_macros::Message;
+
+/// Attribute to create a supervisor that logs the error and restarts the actor.
+///
+/// The attribute is used on a unit or tuple struct, the fields of which are the
+/// types of the argument(s) used to restart the actor (the
+/// [`NewActor::Argument`] type). A unit struct means the unit type (`()`),
+/// a struct with a single field means that type and a struct with multiple
+/// fields means a tuple of those types.
+///
+/// The struct is replaced by a type that implements the [`Supervisor`] and
+/// [`SyncSupervisor`] traits, similar to the type created by the
+/// [`restart_supervisor!`] macro. It also derives [`Debug`], so that shouldn't
+/// be done by the caller. The arguments are cloned each time the actor is
+/// restarted, thus they must implement [`Clone`].
+///
+/// The attribute accepts the following (*optional*) options:
+///
+/// * `max_restarts`: maximum number of restarts, defaults to 5.
+/// * `max_duration`: maximum duration before the restart counter get reset,
+///   defaults to 5 seconds.
+/// * `log_extra`: additional logging message, defaults to nothing extra. This
+///   uses normal [rust formatting rules] and is added at the end of the default
+///   message, after the error.
+/// * `log_args`: the arguments to the `log_extra` message. The `args` variable
+///   gives access to (a reference to) the arguments. See the example below.
+///
+/// The supervisor can be created using the `new` function, e.g.
+/// `MySupervisor::new(args)`, see the example below.
+///
+/// The logged messages are the same as the messages logged by the supervisor
+/// created by [`restart_supervisor!`].
+///
+/// [`NewActor::Argument`]: crate::NewActor::Argument
+/// [`Supervisor`]: crate::Supervisor
+/// [`SyncSupervisor`]: crate::SyncSupervisor
+/// [`restart_supervisor!`]: crate::restart_supervisor!
+/// [rust formatting rules]: std::fmt
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use heph::macros::restart_supervisor;
+///
+/// /// Supervisor for my actor.
+/// #[restart_supervisor(
+///     max_restarts = 2,
+///     max_duration = Duration::from_secs(30),
+///     log_extra = ": actor arguments {:?}: {}, {}",
+///     log_args(args, args.0, args.1),
+/// )]
+/// pub struct MySupervisor(bool, u32);
+///
+/// // Create a new supervisor.
+/// let supervisor = MySupervisor::new(true, 23);
+/// # assert_eq!(MySupervisor::MAX_RESTARTS, 2);
+/// # drop(supervisor);
+/// ```
+pub use heph_macros::restart_supervisor;
+
+/// Attribute to define an actor as a type with a handler method per message.
+///
+/// The attribute is used on an `impl` block. Each `async` method with a name
+/// starting with `handle_` that takes `&mut self` and a single argument is a
+/// handler. For each handler a variant is added to the generated message enum,
+/// named after the method without the `handle_` prefix in `CamelCase`, e.g.
+/// `handle_add_item` becomes `AddItem`. Documentation of the handlers is copied
+/// to the variants. Other methods in the `impl` block are left untouched.
+///
+/// If the handler doesn't return anything the variant holds the argument. If
+/// it does return a value the variant holds a [`RpcMessage`] and the returned
+/// value is send as response, allowing the handler to be called using
+/// [`ActorRef::rpc`]. [`From`] is implemented for each variant, similar to the
+/// [`Message`] derive, so handlers must have unique argument types.
+///
+/// Finally a `run` function is added to the type, which runs the actor: it
+/// receives messages and calls the matching handler until all actor
+/// references are dropped. It can be used as actor by passing it to
+/// [`actor_fn`], the type itself is the actor's argument.
+///
+/// The attribute accepts the following (*optional*) option:
+///
+/// * `message`: the name of the message enum, optionally prefixed with a
+///   visibility, e.g. `message = pub CounterMessage`. Defaults to the name of
+///   the type with `Message` appended, with private visibility.
+///
+/// [`RpcMessage`]: crate::actor_ref::RpcMessage
+/// [`ActorRef::rpc`]: crate::ActorRef::rpc
+/// [`actor_fn`]: crate::actor::actor_fn
+///
+/// # Examples
+///
+/// ```
+/// use heph::actor::actor_fn;
+/// use heph::future::ActorFuture;
+/// use heph::macros::actor;
+/// use heph::supervisor::NoSupervisor;
+///
+/// struct Counter {
+///     count: usize,
+/// }
+///
+/// #[actor(message = pub CounterMessage)]
+/// impl Counter {
+///     /// Add a value to the counter.
+///     async fn handle_add(&mut self, n: usize) {
+///         self.count += n;
+///     }
+///
+///     /// Get the current count.
+///     async fn handle_get(&mut self, _: ()) -> usize {
+///         self.count
+///     }
+/// }
+///
+/// // Generates the following message type:
+/// // pub enum CounterMessage {
+/// //     Add(usize),
+/// //     Get(RpcMessage<(), usize>),
+/// // }
+/// let msg = CounterMessage::from(1_usize);
+/// # assert!(matches!(msg, CounterMessage::Add(1)));
+///
+/// // And a `run` function that can be used as actor.
+/// let counter = Counter { count: 0 };
+/// let (future, actor_ref) = ActorFuture::new(NoSupervisor, actor_fn(Counter::run), counter).unwrap();
+/// # drop((future, actor_ref));
+/// ```
+pub use heph_macros::actor;
@@ -93,10 +93,107 @@ pub struct Cancel<Id = ()>(pub Id);
 /// # Notes
 ///
 /// This message is not special in anyway, this means the receiving actor can
-/// simply ignore this message and continue running.
+/// simply ignore this message and continue running. To stop an actor without
+/// it having to handle a message see [`ActorRef::stop`].
+///
+/// [`ActorRef::stop`]: crate::ActorRef::stop
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub struct Terminate;
 
+/// A message in its raw, serialised, form.
+///
+/// This can be used to evolve the message types of an actor over time, for
+/// example when receiving messages from remote nodes running a different
+/// version of the code. A raw message consists of a `kind`, identifying the
+/// message type or variant, the `version` of the message format and the
+/// serialised `data`.
+///
+/// The message type of the actor implements [`TryFrom`]`<RawMessage>`,
+/// returning the original `RawMessage` as error for unknown or legacy messages.
+/// Using [`ActorRef::try_map_or`] those messages can be routed to a catch-all
+/// actor, instead of being dropped.
+///
+/// [`ActorRef::try_map_or`]: crate::ActorRef::try_map_or
+///
+/// # Examples
+///
+/// ```
+/// # #![allow(dead_code)]
+/// use heph::messages::RawMessage;
+/// use heph::ActorRef;
+///
+/// enum Message {
+///     Greet(String),
+///     Count(u64),
+/// }
+///
+/// impl TryFrom<RawMessage> for Message {
+///     type Error = RawMessage;
+///
+///     fn try_from(raw: RawMessage) -> Result<Message, RawMessage> {
+///         match (raw.kind(), raw.version()) {
+///             // Version 1 and 2 of the greeting use the same format.
+///             ("greet", 1 | 2) => match String::from_utf8(raw.data().to_vec()) {
+///                 Ok(name) => Ok(Message::Greet(name)),
+///                 Err(_) => Err(raw),
+///             },
+///             ("count", 1) => match raw.data().try_into() {
+///                 Ok(bytes) => Ok(Message::Count(u64::from_be_bytes(bytes))),
+///                 Err(_) => Err(raw),
+///             },
+///             // Unknown message, let the caller handle it.
+///             _ => Err(raw),
+///         }
+///     }
+/// }
+///
+/// /// Create an actor reference that sends all known messages to `actor_ref`
+/// /// and all others to `catch_all_ref`.
+/// fn raw_ref(
+///     actor_ref: ActorRef<Message>,
+///     catch_all_ref: ActorRef<RawMessage>,
+/// ) -> ActorRef<RawMessage> {
+///     actor_ref.try_map_or(catch_all_ref)
+/// }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RawMessage {
+    kind: String,
+    version: u32,
+    data: Vec<u8>,
+}
+
+impl RawMessage {
+    /// Create a new raw message.
+    pub const fn new(kind: String, version: u32, data: Vec<u8>) -> RawMessage {
+        RawMessage {
+            kind,
+            version,
+            data,
+        }
+    }
+
+    /// Returns the kind of message.
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// Returns the version of the message format.
+    pub const fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns the serialised message.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the serialised message, consuming the raw message.
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+}
+
 /// Macro to implement [`From`] for an enum message type.
 ///
 /// # Examples
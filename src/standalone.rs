@@ -0,0 +1,90 @@
+//! Running actors without Heph-rt.
+//!
+//! An [`ActorFuture`] combines an actor, its supervisor and its inbox into a
+//! single [`Future`]. It only uses the [`task::Waker`] provided by the executor
+//! polling it, which means it can be run on any executor, e.g. by passing it to
+//! `tokio::spawn`, `async_std::task::spawn` or `pollster::block_on`. This
+//! allows libraries to use Heph's actor model without committing to the
+//! Heph-rt crate.
+//!
+//! When not using Heph-rt actors should use `()` as [runtime access] (the
+//! default for [`actor::Context`]), which is what [`ActorFuture::new`] expects.
+//! Another type can be used by passing it to [`ActorFutureBuilder::with_rt`],
+//! for example to give actors access to a handle of the executor to spawn
+//! other actors.
+//!
+//! For simple programs and tests that don't need a full executor this module
+//! provides [`block_on`], which runs a single `Future` on the current thread.
+//!
+//! [`ActorFuture`]: crate::ActorFuture
+//! [`ActorFuture::new`]: crate::ActorFuture::new
+//! [runtime access]: crate::actor::NewActor::RuntimeAccess
+//! [`actor::Context`]: crate::actor::Context
+//! [`ActorFutureBuilder::with_rt`]: crate::ActorFutureBuilder::with_rt
+//!
+//! # Examples
+//!
+//! ```
+//! use heph::actor::{self, actor_fn};
+//! use heph::future::ActorFuture;
+//! use heph::standalone::block_on;
+//! use heph::supervisor::NoSupervisor;
+//!
+//! async fn greeter_actor(mut ctx: actor::Context<&'static str>) {
+//!     while let Ok(name) = ctx.receive_next().await {
+//!         println!("Hello {name}");
+//!     }
+//! }
+//!
+//! let (future, actor_ref) = ActorFuture::new(NoSupervisor, actor_fn(greeter_actor), ()).unwrap();
+//!
+//! // Send the actor a message from another thread.
+//! let handle = std::thread::spawn(move || actor_ref.try_send("World").unwrap());
+//!
+//! // Run the actor until all actor references are dropped.
+//! block_on(future);
+//! # handle.join().unwrap();
+//! ```
+
+use std::future::{Future, IntoFuture};
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{self, Poll, Wake};
+use std::thread::{self, Thread};
+
+/// Run `future` on the current thread, blocking until it completes.
+///
+/// The thread is parked while the future can't make progress and unparked
+/// when it's woken, so it doesn't consume any CPU time while waiting.
+///
+/// See the [module documentation] for an example.
+///
+/// [module documentation]: crate::standalone
+pub fn block_on<Fut>(future: Fut) -> Fut::Output
+where
+    Fut: IntoFuture,
+{
+    let mut future = pin!(future.into_future());
+    let waker = Arc::new(ThreadWaker(thread::current())).into();
+    let mut ctx = task::Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut ctx) {
+            Poll::Ready(output) => return output,
+            // NOTE: spurious wake-ups are fine, we'll poll the future again.
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// [`task::Waker`] implementation that unparks a thread.
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
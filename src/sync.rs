@@ -47,19 +47,19 @@
 
 use std::future::Future;
 use std::panic::{self, AssertUnwindSafe};
-use std::pin::pin;
+use std::pin::{pin, Pin};
 use std::task::{self, Poll, RawWaker, RawWakerVTable};
 use std::thread::{self, Thread};
 use std::time::{Duration, Instant};
 use std::{io, ptr};
 
-use heph_inbox::Receiver;
 use heph_inbox::{self as inbox, ReceiverConnected};
+use heph_inbox::{Receiver, RecvValue};
 use log::trace;
 
 use crate::actor::private::ActorResult;
 use crate::actor::{ActorFn, NoMessages, RecvError};
-use crate::actor_ref::ActorRef;
+use crate::actor_ref::{ActorRef, Deadline};
 use crate::supervisor::{SupervisorStrategy, SyncSupervisor};
 
 pub use crate::future::InboxSize;
@@ -260,6 +260,9 @@ pub struct Context<M, RT = ()> {
     future_waker: Option<SyncWaker>,
     /// Runtime access.
     rt: RT,
+    /// Deadline of the last message received using
+    /// [`Context::receive_next_unexpired`].
+    deadline: Option<Instant>,
 }
 
 impl<M, RT> Context<M, RT> {
@@ -269,6 +272,7 @@ impl<M, RT> Context<M, RT> {
             inbox,
             future_waker: None,
             rt,
+            deadline: None,
         }
     }
 
@@ -278,6 +282,10 @@ impl<M, RT> Context<M, RT> {
     /// the actor wants to wait until a message is received [`receive_next`] can
     /// be used, which blocks until a message is ready.
     ///
+    /// If the actor was stopped, see [`ActorRef::stop`], this returns
+    /// [`RecvError::Stopped`] once all messages send before stopping are
+    /// received.
+    ///
     /// [`receive_next`]: Context::receive_next
     ///
     /// # Examples
@@ -300,14 +308,18 @@ impl<M, RT> Context<M, RT> {
     /// # assert_sync_actor(heph::actor::actor_fn(greeter_actor));
     /// ```
     pub fn try_receive_next(&mut self) -> Result<M, RecvError> {
-        self.inbox.try_recv().map_err(RecvError::from)
+        self.inbox
+            .try_recv()
+            .map_err(|err| RecvError::from(err, &self.inbox))
     }
 
     /// Receive the next message.
     ///
     /// Returns the next message available. If no messages are currently
     /// available it will block until a message becomes available or until all
-    /// actor references (that reference this actor) are dropped.
+    /// actor references (that reference this actor) are dropped. Once the
+    /// actor is stopped, see [`ActorRef::stop`], and all messages send before
+    /// are received this returns [`NoMessages`].
     ///
     /// # Examples
     ///
@@ -328,8 +340,67 @@ impl<M, RT> Context<M, RT> {
     /// # assert_sync_actor(heph::actor::actor_fn(print_actor));
     /// ```
     pub fn receive_next(&mut self) -> Result<M, NoMessages> {
+        self.block_on_with(Context::receive)
+    }
+
+    /// Receive the next message, waiting up to `timeout` time.
+    ///
+    /// This is the same as [`receive_next`], but returns
+    /// [`RecvError::Empty`] if no message was received within `timeout`,
+    /// [`RecvError::Disconnected`] if all actor references are dropped and
+    /// [`RecvError::Stopped`] if the actor was stopped.
+    ///
+    /// [`receive_next`]: Context::receive_next
+    ///
+    /// # Examples
+    ///
+    /// An actor that prints a message if it receives one within a second.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use heph::sync;
+    ///
+    /// fn print_actor(mut ctx: sync::Context<String>) {
+    ///     match ctx.receive_next_timeout(Duration::from_secs(1)) {
+    ///         Ok(msg) => println!("Got a message: {msg}"),
+    ///         Err(err) => eprintln!("No message received: {err:?}"),
+    ///     }
+    /// }
+    ///
+    /// # fn assert_sync_actor<A: heph::SyncActor<RuntimeAccess = ()>>(_: A) { }
+    /// # assert_sync_actor(heph::actor::actor_fn(print_actor));
+    /// ```
+    pub fn receive_next_timeout(&mut self, timeout: Duration) -> Result<M, RecvError> {
         let waker = self.future_waker();
-        waker.block_on(self.inbox.recv()).ok_or(NoMessages)
+        match waker.block_for(self.receive(), timeout) {
+            Some(Ok(msg)) => Ok(msg),
+            Some(Err(NoMessages)) if self.is_stopped() => Err(RecvError::Stopped),
+            Some(Err(NoMessages)) => Err(RecvError::Disconnected),
+            None => Err(RecvError::Empty),
+        }
+    }
+
+    /// Returns a [`Future`] to receive the next message.
+    ///
+    /// This is the asynchronous version of [`receive_next`], following the
+    /// same semantics. It can be used in combination with other futures, e.g.
+    /// timers of the runtime, using [`Context::block_on_with`].
+    ///
+    /// [`receive_next`]: Context::receive_next
+    pub fn receive(&mut self) -> ReceiveMessage<'_, M> {
+        ReceiveMessage {
+            recv: self.inbox.recv(),
+        }
+    }
+
+    /// Returns `true` if the actor was stopped.
+    ///
+    /// See [`actor::Context::is_stopped`] for more details.
+    ///
+    /// [`actor::Context::is_stopped`]: crate::actor::Context::is_stopped
+    pub fn is_stopped(&self) -> bool {
+        self.inbox.is_closed()
     }
 
     /// Block on a [`Future`] waiting for it's completion.
@@ -341,6 +412,49 @@ impl<M, RT> Context<M, RT> {
         waker.block_on(fut)
     }
 
+    /// Block on the [`Future`] created by `f` waiting for it's completion.
+    ///
+    /// This is useful for futures that borrow the context, such as the future
+    /// returned by [`Context::receive`].
+    ///
+    /// # Examples
+    ///
+    /// Waiting for the next message.
+    ///
+    /// ```
+    /// use heph::actor::NoMessages;
+    /// use heph::sync;
+    ///
+    /// fn actor(mut ctx: sync::Context<String>) -> Result<(), NoMessages> {
+    ///     let msg = ctx.block_on_with(|ctx| ctx.receive())?;
+    ///     println!("Got a message: {msg}");
+    ///     Ok(())
+    /// }
+    ///
+    /// # fn assert_sync_actor<A: heph::SyncActor<RuntimeAccess = ()>>(_: A) { }
+    /// # assert_sync_actor(heph::actor::actor_fn(actor));
+    /// ```
+    pub fn block_on_with<'ctx, F, Fut>(&'ctx mut self, f: F) -> Fut::Output
+    where
+        F: FnOnce(&'ctx mut Self) -> Fut,
+        Fut: Future,
+    {
+        let waker = self.future_waker();
+        waker.block_on(f(self))
+    }
+
+    /// Block on a [`Future`] waiting for it's completion, waiting up to
+    /// `timeout` time.
+    ///
+    /// Returns `None` if the future didn't complete within `timeout`.
+    pub fn block_for<Fut>(&mut self, fut: Fut, timeout: Duration) -> Option<Fut::Output>
+    where
+        Fut: Future,
+    {
+        let waker = self.future_waker();
+        waker.block_for(fut, timeout)
+    }
+
     /// Get mutable access to the runtime this actor is running in.
     pub fn runtime(&mut self) -> &mut RT {
         &mut self.rt
@@ -363,6 +477,69 @@ impl<M, RT> Context<M, RT> {
     }
 }
 
+impl<M, RT> Context<Deadline<M>, RT> {
+    /// Attempt to receive the next message of which the deadline didn't pass.
+    ///
+    /// See [`actor::Context::try_receive_next_unexpired`] for more details.
+    ///
+    /// [`actor::Context::try_receive_next_unexpired`]: crate::actor::Context::try_receive_next_unexpired
+    pub fn try_receive_next_unexpired(&mut self) -> Result<Deadline<M>, RecvError> {
+        loop {
+            if let Some(msg) = self.try_receive_next()?.unexpired() {
+                self.deadline = Some(msg.deadline());
+                return Ok(msg);
+            }
+        }
+    }
+
+    /// Receive the next message of which the deadline didn't pass.
+    ///
+    /// See [`actor::Context::receive_next_unexpired`] for more details.
+    ///
+    /// [`actor::Context::receive_next_unexpired`]: crate::actor::Context::receive_next_unexpired
+    pub fn receive_next_unexpired(&mut self) -> Result<Deadline<M>, NoMessages> {
+        loop {
+            if let Some(msg) = self.receive_next()?.unexpired() {
+                self.deadline = Some(msg.deadline());
+                return Ok(msg);
+            }
+        }
+    }
+
+    /// Returns the deadline of the last message received using
+    /// [`Context::receive_next_unexpired`] or
+    /// [`Context::try_receive_next_unexpired`], or `None` if no message was
+    /// received yet.
+    ///
+    /// See [`actor::Context::deadline`] for more details.
+    ///
+    /// [`actor::Context::deadline`]: crate::actor::Context::deadline
+    pub const fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+}
+
+/// Future to receive a single message.
+///
+/// The implementation behind [`sync::Context::receive`].
+///
+/// [`sync::Context::receive`]: crate::sync::Context::receive
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ReceiveMessage<'ctx, M> {
+    recv: RecvValue<'ctx, M>,
+}
+
+impl<'ctx, M> Future for ReceiveMessage<'ctx, M> {
+    type Output = Result<M, NoMessages>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.recv)
+            .poll(ctx)
+            .map(|r| r.ok_or(NoMessages))
+    }
+}
+
 /// [`task::Waker`] implementation for blocking on [`Future`]s.
 // TODO: a `Thread` is already wrapped in an `Arc`, which mean we're double
 // `Arc`ing for the `Waker` implementation, try to remove that.
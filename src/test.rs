@@ -91,7 +91,7 @@ where
 ///     }
 /// }
 ///
-/// assert_eq!(size_of_actor_val(&actor_fn(actor)), 56);
+/// assert_eq!(size_of_actor_val(&actor_fn(actor)), 144);
 /// ```
 pub const fn size_of_actor_val<NA>(_: &NA) -> usize
 where
@@ -42,7 +42,10 @@ mod functional {
     mod actor;
     mod actor_group;
     mod actor_ref;
+    #[cfg(feature = "macros")]
+    mod macros;
     mod restart_supervisor;
+    mod standalone;
     mod sync_actor;
     mod test;
 }
@@ -38,4 +38,5 @@ fn rpc_error_format() {
     assert_eq!(format!("{}", RpcError::SendError), "unable to send message");
     assert_eq!(format!("{}", RpcError::SendError), format!("{}", SendError));
     assert_eq!(format!("{}", RpcError::NoResponse), "no RPC response");
+    assert_eq!(format!("{}", RpcError::TimedOut), "RPC timed out");
 }
@@ -0,0 +1,207 @@
+//! Tests for the procedural macros in the `macros` module.
+
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::task::{self, Poll};
+use std::time::Duration;
+
+use heph::actor::actor_fn;
+use heph::actor_ref::RpcMessage;
+use heph::future::ActorFuture;
+use heph::macros::{actor, restart_supervisor, Message};
+use heph::supervisor::NoSupervisor;
+use heph::{actor, Actor, NewActor, SupervisorStrategy};
+
+fn assert_from<T, M: From<T>>() {}
+
+#[derive(Debug, Message)]
+enum Msg {
+    String(String),
+    Number(usize),
+    #[allow(dead_code)]
+    Rpc(RpcMessage<String, usize>),
+    #[message(skip)]
+    Other(usize),
+    #[message(skip)]
+    Stop,
+}
+
+#[derive(Debug, Message)]
+enum GenericMsg<T> {
+    Value(T),
+    #[message(skip)]
+    #[allow(dead_code)]
+    Stop,
+}
+
+#[test]
+fn derive_message() {
+    assert!(matches!(Msg::from("a".to_owned()), Msg::String(s) if s == "a"));
+    assert!(matches!(Msg::from(123), Msg::Number(123)));
+    assert_from::<RpcMessage<String, usize>, Msg>();
+    // Skipped variants.
+    assert!(matches!(Msg::Other(1), Msg::Other(1)));
+    assert!(matches!(Msg::Stop, Msg::Stop));
+
+    assert!(matches!(GenericMsg::from(1u8), GenericMsg::Value(1)));
+}
+
+// NOTE: keep in sync with the documentation.
+const DEFAULT_MAX_RESTARTS: usize = 5;
+const DEFAULT_MAX_DURATION: Duration = Duration::from_secs(5);
+
+#[test]
+fn restart_supervisor_unit_argument() {
+    #[restart_supervisor]
+    struct Supervisor;
+
+    let _supervisor = Supervisor::new();
+    assert_eq!(Supervisor::MAX_RESTARTS, DEFAULT_MAX_RESTARTS);
+    assert_eq!(Supervisor::MAX_DURATION, DEFAULT_MAX_DURATION);
+}
+
+#[test]
+fn restart_supervisor_single_argument() {
+    #[restart_supervisor(max_restarts = 2)]
+    struct Supervisor(String);
+
+    let _supervisor = Supervisor::new("Hello World".to_owned());
+    assert_eq!(Supervisor::MAX_RESTARTS, 2);
+    assert_eq!(Supervisor::MAX_DURATION, DEFAULT_MAX_DURATION);
+}
+
+#[test]
+fn restart_supervisor_tuple_argument() {
+    #[restart_supervisor(
+        max_restarts = 2,
+        max_duration = Duration::from_secs(10),
+        log_extra = ": log extra: {}, {:?}",
+        log_args(args.0, args),
+    )]
+    struct Supervisor(String, usize, u8, u8, u8, u8, u8);
+
+    // Unlike `restart_supervisor!` there is no limit on the number of
+    // arguments.
+    let _supervisor = Supervisor::new("Hello World".to_owned(), 123, 1, 2, 3, 4, 5);
+    assert_eq!(Supervisor::MAX_RESTARTS, 2);
+    assert_eq!(Supervisor::MAX_DURATION, Duration::from_secs(10));
+}
+
+const ERROR1: &str = "error 1";
+const ERROR2: &str = "error 2";
+
+struct NewActorImpl;
+
+impl NewActor for NewActorImpl {
+    type Message = !;
+    type Argument = bool;
+    type Actor = ActorImpl;
+    type Error = &'static str;
+    type RuntimeAccess = ();
+
+    fn new(
+        &mut self,
+        _: actor::Context<Self::Message, Self::RuntimeAccess>,
+        _: Self::Argument,
+    ) -> Result<Self::Actor, Self::Error> {
+        unimplemented!()
+    }
+}
+
+struct ActorImpl;
+
+impl Actor for ActorImpl {
+    type Error = &'static str;
+
+    fn try_poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+        unimplemented!()
+    }
+}
+
+#[test]
+fn restart_supervisor_decide() {
+    #[restart_supervisor(max_restarts = 1, max_duration = Duration::from_secs(60))]
+    struct Supervisor(bool);
+
+    let arg = true;
+    let mut supervisor = Supervisor::new(arg);
+
+    let decide = |supervisor: &mut Supervisor| {
+        <Supervisor as heph::Supervisor<NewActorImpl>>::decide(supervisor, ERROR1)
+    };
+    assert_eq!(decide(&mut supervisor), SupervisorStrategy::Restart(arg));
+    assert_eq!(decide(&mut supervisor), SupervisorStrategy::Stop);
+
+    let mut supervisor = Supervisor::new(arg);
+    let decide_on_restart_error = |supervisor: &mut Supervisor| {
+        <Supervisor as heph::Supervisor<NewActorImpl>>::decide_on_restart_error(supervisor, ERROR2)
+    };
+    assert_eq!(
+        decide_on_restart_error(&mut supervisor),
+        SupervisorStrategy::Restart(arg)
+    );
+    assert_eq!(
+        decide_on_restart_error(&mut supervisor),
+        SupervisorStrategy::Stop
+    );
+    <Supervisor as heph::Supervisor<NewActorImpl>>::second_restart_error(&mut supervisor, ERROR2);
+
+    let mut supervisor = Supervisor::new(arg);
+    let decide_on_panic = |supervisor: &mut Supervisor| {
+        let panic = Box::new("boom");
+        <Supervisor as heph::Supervisor<NewActorImpl>>::decide_on_panic(supervisor, panic)
+    };
+    assert_eq!(
+        decide_on_panic(&mut supervisor),
+        SupervisorStrategy::Restart(arg)
+    );
+    assert_eq!(decide_on_panic(&mut supervisor), SupervisorStrategy::Stop);
+}
+
+struct Counter {
+    count: usize,
+}
+
+#[actor]
+impl Counter {
+    async fn handle_add(&mut self, n: usize) {
+        self.count += n;
+    }
+
+    async fn handle_get_count(&mut self, _: ()) -> usize {
+        self.count
+    }
+
+    /// Not a handler.
+    fn reset(&mut self) {
+        self.count = 0;
+    }
+}
+
+#[test]
+fn actor_attribute() {
+    assert!(matches!(CounterMessage::from(1), CounterMessage::Add(1)));
+    assert_from::<RpcMessage<(), usize>, CounterMessage>();
+
+    let mut counter = Counter { count: 1 };
+    counter.reset();
+
+    let (future, actor_ref) =
+        ActorFuture::new(NoSupervisor, actor_fn(Counter::run), counter).unwrap();
+    let mut future = pin!(future);
+    let mut ctx = task::Context::from_waker(task::Waker::noop());
+
+    actor_ref.try_send(2_usize).unwrap();
+    actor_ref.try_send(3_usize).unwrap();
+    {
+        let rpc = actor_ref.rpc(());
+        let mut rpc = pin!(rpc);
+        assert!(rpc.as_mut().poll(&mut ctx).is_pending());
+
+        assert_eq!(future.as_mut().poll(&mut ctx), Poll::Pending);
+        assert_eq!(rpc.as_mut().poll(&mut ctx), Poll::Ready(Ok(5)));
+    }
+
+    drop(actor_ref);
+    assert_eq!(future.as_mut().poll(&mut ctx), Poll::Ready(()));
+}
@@ -0,0 +1,69 @@
+//! Tests for the `standalone` module.
+
+use std::thread;
+use std::time::Duration;
+
+use heph::actor::{self, actor_fn};
+use heph::future::ActorFuture;
+use heph::standalone::block_on;
+use heph::supervisor::{NoSupervisor, SupervisorStrategy};
+
+#[test]
+fn block_on_future() {
+    assert_eq!(block_on(async { 123 }), 123);
+}
+
+async fn count_actor(mut ctx: actor::Context<usize>, expected: usize) -> Result<(), String> {
+    let mut total = 0;
+    while let Ok(n) = ctx.receive_next().await {
+        total += n;
+    }
+    if total == expected {
+        Ok(())
+    } else {
+        Err(format!("unexpected total: {total}, expected: {expected}"))
+    }
+}
+
+#[test]
+fn block_on_actor_woken_by_other_thread() {
+    let supervisor = |err| panic!("{err}");
+    let (future, actor_ref) = ActorFuture::new(supervisor, actor_fn(count_actor), 6).unwrap();
+
+    let handle = thread::spawn(move || {
+        for n in 1..=3usize {
+            thread::sleep(Duration::from_millis(10));
+            actor_ref.try_send(n).unwrap();
+        }
+    });
+
+    block_on(future);
+    handle.join().unwrap();
+}
+
+#[test]
+fn block_on_restarted_actor() {
+    async fn actor(mut ctx: actor::Context<()>, fail: bool) -> Result<(), ()> {
+        if fail {
+            return Err(());
+        }
+        ctx.receive_next().await.map_err(|_| ())
+    }
+
+    let supervisor = |()| SupervisorStrategy::Restart(false);
+    let (future, actor_ref) = ActorFuture::new(supervisor, actor_fn(actor), true).unwrap();
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        actor_ref.try_send(()).unwrap();
+    });
+    block_on(future);
+    handle.join().unwrap();
+}
+
+#[test]
+fn block_on_no_supervisor() {
+    async fn actor(_: actor::Context<!>) {}
+
+    let (future, _) = ActorFuture::new(NoSupervisor, actor_fn(actor), ()).unwrap();
+    block_on(future);
+}
@@ -4,9 +4,10 @@ use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 use std::task::{self, Poll};
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use heph::actor::{actor_fn, RecvError};
+use heph::actor::{actor_fn, NoMessages, RecvError};
+use heph::actor_ref::{Deadline, SendError};
 use heph::supervisor::{NoSupervisor, SupervisorStrategy, SyncSupervisor};
 use heph::sync::{self, SyncActor, SyncActorRunnerBuilder};
 
@@ -107,6 +108,7 @@ fn try_receive_next_actor<RT>(mut ctx: sync::Context<String, RT>) {
             }
             Err(RecvError::Empty) => continue,
             Err(RecvError::Disconnected) => panic!("unexpected disconnected error"),
+            Err(RecvError::Stopped) => panic!("unexpected stopped error"),
         }
     }
 }
@@ -121,6 +123,58 @@ fn context_try_receive_next() {
     handle.join().unwrap();
 }
 
+fn deadline_actor<RT>(mut ctx: sync::Context<Deadline<usize>, RT>) {
+    assert_eq!(ctx.deadline(), None);
+    // Message 1 is expired and should be dropped.
+    let msg = ctx.receive_next_unexpired().unwrap();
+    assert_eq!(ctx.deadline(), Some(msg.deadline()));
+    assert_eq!(msg.into_inner(), 2);
+    assert_eq!(ctx.receive_next_unexpired().unwrap().into_inner(), 3);
+    // Message 4 is expired and should be dropped.
+    assert_eq!(ctx.receive_next_unexpired(), Err(NoMessages));
+    assert_eq!(
+        ctx.try_receive_next_unexpired(),
+        Err(RecvError::Disconnected)
+    );
+}
+
+#[test]
+fn context_message_deadline() {
+    let (handle, actor_ref) = SyncActorRunnerBuilder::new()
+        .spawn(NoSupervisor, actor_fn(deadline_actor), ())
+        .unwrap();
+
+    let now = Instant::now();
+    actor_ref.try_send_with_deadline(1_usize, now).unwrap();
+    let future = now + Duration::from_secs(60);
+    actor_ref.try_send_with_deadline(2_usize, future).unwrap();
+    actor_ref.try_send(Deadline::new(3_usize, future)).unwrap();
+    actor_ref.try_send_with_deadline(4_usize, now).unwrap();
+    drop(actor_ref);
+    handle.join().unwrap();
+}
+
+fn stop_actor<RT>(mut ctx: sync::Context<usize, RT>) {
+    // Message send before stopping is still received.
+    assert_eq!(ctx.receive_next(), Ok(1));
+    assert_eq!(ctx.receive_next(), Err(NoMessages));
+    assert!(ctx.is_stopped());
+    assert_eq!(ctx.try_receive_next(), Err(RecvError::Stopped));
+}
+
+#[test]
+fn context_stopped() {
+    let (handle, actor_ref) = SyncActorRunnerBuilder::new()
+        .spawn(NoSupervisor, actor_fn(stop_actor), ())
+        .unwrap();
+
+    actor_ref.try_send(1_usize).unwrap();
+    actor_ref.stop().unwrap();
+    assert_eq!(actor_ref.try_send(2_usize), Err(SendError));
+    // NOTE: not dropping `actor_ref` as the actor should stop regardless.
+    handle.join().unwrap();
+}
+
 #[test]
 fn supervision() {
     let (handle, _) = SyncActorRunnerBuilder::new()
@@ -194,3 +248,78 @@ fn sync_actor_name() {
         "block_on_actor",
     );
 }
+
+fn block_for_actor<RT>(mut ctx: sync::Context<String, RT>, fut: BlockFuture) {
+    assert_eq!(ctx.block_for(fut, Duration::from_millis(10)), None);
+}
+
+#[test]
+fn block_for() {
+    let future = BlockFuture::new();
+
+    let (handle, _) = SyncActorRunnerBuilder::new()
+        .spawn(NoSupervisor, actor_fn(block_for_actor), future)
+        .unwrap();
+    handle.join().unwrap();
+}
+
+fn receive_actor<RT>(mut ctx: sync::Context<usize, RT>) {
+    assert_eq!(ctx.block_on_with(|ctx| ctx.receive()), Ok(1));
+    assert_eq!(ctx.receive_next(), Ok(2));
+    assert_eq!(ctx.block_on_with(|ctx| ctx.receive()), Err(NoMessages));
+}
+
+#[test]
+fn context_receive() {
+    let (handle, actor_ref) = SyncActorRunnerBuilder::new()
+        .spawn(NoSupervisor, actor_fn(receive_actor), ())
+        .unwrap();
+
+    actor_ref.try_send(1_usize).unwrap();
+    actor_ref.try_send(2_usize).unwrap();
+    drop(actor_ref);
+    handle.join().unwrap();
+}
+
+fn receive_next_timeout_actor<RT>(mut ctx: sync::Context<usize, RT>) {
+    let timeout = Duration::from_millis(10);
+    let start = Instant::now();
+    assert_eq!(ctx.receive_next_timeout(timeout), Err(RecvError::Empty));
+    assert!(start.elapsed() >= timeout);
+    assert_eq!(ctx.receive_next_timeout(Duration::from_secs(10)), Ok(1));
+    assert_eq!(ctx.receive_next_timeout(timeout), Err(RecvError::Stopped));
+}
+
+#[test]
+fn context_receive_next_timeout() {
+    let (handle, actor_ref) = SyncActorRunnerBuilder::new()
+        .spawn(NoSupervisor, actor_fn(receive_next_timeout_actor), ())
+        .unwrap();
+
+    sleep(Duration::from_millis(20));
+    actor_ref.try_send(1_usize).unwrap();
+    actor_ref.stop().unwrap();
+    handle.join().unwrap();
+}
+
+fn receive_next_timeout_disconnected_actor<RT>(mut ctx: sync::Context<usize, RT>) {
+    let timeout = Duration::from_secs(10);
+    assert_eq!(
+        ctx.receive_next_timeout(timeout),
+        Err(RecvError::Disconnected)
+    );
+}
+
+#[test]
+fn context_receive_next_timeout_disconnected() {
+    let (handle, actor_ref) = SyncActorRunnerBuilder::new()
+        .spawn(
+            NoSupervisor,
+            actor_fn(receive_next_timeout_disconnected_actor),
+            (),
+        )
+        .unwrap();
+
+    drop(actor_ref);
+    handle.join().unwrap();
+}
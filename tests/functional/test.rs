@@ -13,7 +13,7 @@ fn test_size_of_actor() {
         /* Nothing. */
     }
 
-    assert_eq!(size_of_actor_val(&actor_fn(actor1)), 24);
+    assert_eq!(size_of_actor_val(&actor_fn(actor1)), 64);
 
     struct Na;
 